@@ -346,6 +346,33 @@ fn bench_unicode_strings(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_string_validation(c: &mut Criterion) {
+    use serde_bonjson::{DecoderConfig, StringValidation};
+
+    for (name, unit) in [("ascii", "abcdefgh"), ("unicode", "Привет")] {
+        let mut group = c.benchmark_group(format!("string_validation_{}", name));
+        for len in [16, 64, 256, 1024, 4096, 16384] {
+            let text: String = unit.chars().cycle().take(len).collect();
+            let data = vec![text; 16];
+            let bonjson_bytes = serde_bonjson::to_vec(&data).unwrap();
+            group.throughput(Throughput::Bytes(bonjson_bytes.len() as u64));
+
+            for mode in [StringValidation::Separate, StringValidation::Combined] {
+                let config = DecoderConfig { string_validation: mode, ..Default::default() };
+                group.bench_function(format!("{:?}/{}", mode, len), |b| {
+                    b.iter(|| {
+                        black_box(serde_bonjson::from_slice_with_config::<Vec<&str>>(
+                            black_box(&bonjson_bytes),
+                            config.clone(),
+                        ).unwrap())
+                    })
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
 criterion_group!(
     benches,
     bench_simple_struct,
@@ -355,6 +382,7 @@ criterion_group!(
     bench_many_small_strings,
     bench_large_strings,
     bench_unicode_strings,
+    bench_string_validation,
 );
 
 criterion_main!(benches);
@@ -0,0 +1,133 @@
+// ABOUTME: Benchmarks BONJSON against serde_json on the standard JSON corpora and config options.
+// ABOUTME: Set BONJSON_BENCH_CORPUS_DIR to use the real twitter/canada/citm_catalog files.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use serde::{Deserialize, Serialize};
+use serde_bonjson::bench_corpus::{Corpus, CORPUS_DIR_ENV};
+use serde_bonjson::{SerializerConfig, Value};
+
+fn load(corpus: Corpus) -> Value {
+    match corpus.read_json() {
+        Some(Ok(text)) => serde_json::from_str(&text).unwrap(),
+        Some(Err(e)) => panic!("{CORPUS_DIR_ENV}: can't read {}: {e}", corpus.file_name()),
+        None => corpus.synthesize(),
+    }
+}
+
+fn bench_corpora(c: &mut Criterion) {
+    for corpus in Corpus::ALL {
+        let value = load(corpus);
+        let bonjson_bytes = serde_bonjson::encode_value(&value).unwrap();
+        let json_bytes = serde_json::to_vec(&value).unwrap();
+
+        let mut group = c.benchmark_group(format!("corpus/{}", corpus.name()));
+        group.throughput(Throughput::Bytes(json_bytes.len() as u64));
+
+        group.bench_function("bonjson_encode", |b| {
+            b.iter(|| black_box(serde_bonjson::encode_value(black_box(&value)).unwrap()))
+        });
+
+        group.bench_function("json_encode", |b| {
+            b.iter(|| black_box(serde_json::to_vec(black_box(&value)).unwrap()))
+        });
+
+        group.bench_function("bonjson_decode", |b| {
+            b.iter(|| black_box(serde_bonjson::decode_value(black_box(&bonjson_bytes)).unwrap()))
+        });
+
+        group.bench_function("json_decode", |b| {
+            b.iter(|| {
+                black_box(serde_json::from_slice::<serde_json::Value>(black_box(&json_bytes)).unwrap())
+            })
+        });
+
+        println!("{}: BONJSON={} bytes, JSON={} bytes",
+                 corpus.name(), bonjson_bytes.len(), json_bytes.len());
+
+        group.finish();
+    }
+}
+
+fn bench_typed_arrays(c: &mut Criterion) {
+    let data: Vec<f64> = (0..10_000).map(|i| f64::from(i) * 0.37).collect();
+
+    let mut group = c.benchmark_group("typed_arrays_f64_10000");
+    group.throughput(Throughput::Elements(data.len() as u64));
+
+    for typed_arrays in [true, false] {
+        let config = SerializerConfig { typed_arrays, ..Default::default() };
+        let bytes = serde_bonjson::to_vec_with_config(&data, &config).unwrap();
+        let label = if typed_arrays { "typed" } else { "plain" };
+
+        group.bench_function(format!("bonjson_encode_{label}"), |b| {
+            b.iter(|| black_box(serde_bonjson::to_vec_with_config(black_box(&data), &config).unwrap()))
+        });
+
+        group.bench_function(format!("bonjson_decode_{label}"), |b| {
+            b.iter(|| black_box(serde_bonjson::from_slice::<Vec<f64>>(black_box(&bytes)).unwrap()))
+        });
+
+        println!("Typed arrays ({label}): BONJSON={} bytes", bytes.len());
+    }
+
+    let json_bytes = serde_json::to_vec(&data).unwrap();
+    group.bench_function("json_decode", |b| {
+        b.iter(|| black_box(serde_json::from_slice::<Vec<f64>>(black_box(&json_bytes)).unwrap()))
+    });
+
+    group.finish();
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct Reading {
+    sensor: String,
+    timestamp: u64,
+    value: f64,
+    ok: bool,
+}
+
+fn bench_records(c: &mut Criterion) {
+    let data: Vec<Reading> = (0..1000)
+        .map(|i| Reading {
+            sensor: format!("sensor-{}", i % 16),
+            timestamp: 1_700_000_000 + i,
+            value: i as f64 * 0.25,
+            ok: i % 7 != 0,
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("records_1000");
+    group.throughput(Throughput::Elements(data.len() as u64));
+
+    for records in [true, false] {
+        let config = SerializerConfig { records, ..Default::default() };
+        let bytes = serde_bonjson::to_vec_with_config(&data, &config).unwrap();
+        let label = if records { "records" } else { "objects" };
+
+        group.bench_function(format!("bonjson_encode_{label}"), |b| {
+            b.iter(|| black_box(serde_bonjson::to_vec_with_config(black_box(&data), &config).unwrap()))
+        });
+
+        group.bench_function(format!("bonjson_decode_{label}"), |b| {
+            b.iter(|| black_box(serde_bonjson::from_slice::<Vec<Reading>>(black_box(&bytes)).unwrap()))
+        });
+
+        println!("Records ({label}): BONJSON={} bytes", bytes.len());
+    }
+
+    let json_bytes = serde_json::to_vec(&data).unwrap();
+    group.bench_function("json_decode", |b| {
+        b.iter(|| black_box(serde_json::from_slice::<Vec<Reading>>(black_box(&json_bytes)).unwrap()))
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_corpora,
+    bench_typed_arrays,
+    bench_records,
+);
+
+criterion_main!(benches);
@@ -0,0 +1,27 @@
+// ABOUTME: Fuzz target for BigNumber decoding and decimal string parsing.
+// ABOUTME: Input is tried both as a BigNumber payload and as text; survivors must round-trip.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde_bonjson::{decode_value, encode_value, type_code, BigNumber, Value};
+
+/// Display writes every zero as `0e0`, dropping its exponent.
+fn assert_reparses(bn: BigNumber) {
+    let reparsed: BigNumber = bn.to_string().parse().unwrap();
+    assert!(reparsed == bn || bn.significand == 0, "{bn:?} reparsed as {reparsed:?}");
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut document = vec![type_code::BIG_NUMBER];
+    document.extend_from_slice(data);
+    if let Ok(Value::BigNumber(bn)) = decode_value(&document) {
+        let reencoded = encode_value(&Value::BigNumber(bn)).unwrap();
+        assert_eq!(decode_value(&reencoded).unwrap(), Value::BigNumber(bn));
+        assert_reparses(bn);
+    }
+
+    if let Ok(Ok(bn)) = std::str::from_utf8(data).map(str::parse::<BigNumber>) {
+        assert_reparses(bn);
+    }
+});
@@ -0,0 +1,12 @@
+// ABOUTME: Fuzz target for decode_value under the default and permissive configs.
+// ABOUTME: Any input must decode or fail with an error, never panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde_bonjson::{decode_value, decode_value_with_config, DecoderConfig};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_value(data);
+    let _ = decode_value_with_config(data, DecoderConfig::permissive());
+});
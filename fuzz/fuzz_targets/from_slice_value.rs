@@ -0,0 +1,16 @@
+// ABOUTME: Fuzz target for serde deserialization into Value.
+// ABOUTME: from_slice::<Value> must never panic, and must agree with decode_value.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde_bonjson::{decode_value, from_slice, Value};
+use std::cmp::Ordering;
+
+fuzz_target!(|data: &[u8]| {
+    let via_serde = from_slice::<Value>(data);
+    // decode_value keeps unsigned wire integers as UInt, so compare numerically
+    if let (Ok(a), Ok(b)) = (&via_serde, &decode_value(data)) {
+        assert_eq!(a.total_cmp(b), Ordering::Equal);
+    }
+});
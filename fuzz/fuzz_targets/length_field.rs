@@ -0,0 +1,19 @@
+// ABOUTME: Fuzz target for the LEB128 length field decoder.
+// ABOUTME: A decoded length must re-encode, in no more bytes, to the same value.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde_bonjson::{decode_length_field, encode_length_field, length_field_size};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok((value, consumed)) = decode_length_field(data) else {
+        return;
+    };
+    assert!(consumed <= data.len());
+    let mut encoded = Vec::new();
+    let written = encode_length_field(value, &mut encoded);
+    assert_eq!(written, length_field_size(value));
+    assert!(written <= consumed);
+    assert_eq!(decode_length_field(&encoded).unwrap(), (value, written));
+});
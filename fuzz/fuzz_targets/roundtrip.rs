@@ -0,0 +1,25 @@
+// ABOUTME: Fuzz target for the decode -> encode -> decode invariant.
+// ABOUTME: Re-encoding a decoded document must give back an equal value.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde_bonjson::{decode_value, decode_value_with_config, encode_value, DecoderConfig};
+use std::cmp::Ordering;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(value) = decode_value(data) else {
+        return;
+    };
+    // encode_value may write a float or UInt as a smaller integer, so compare numerically
+    let reencoded = encode_value(&value).unwrap();
+    assert_eq!(decode_value(&reencoded).unwrap().total_cmp(&value), Ordering::Equal);
+
+    // With float widths kept, a second pass must write the same bytes as the first
+    let config = DecoderConfig { preserve_float_width: true, ..DecoderConfig::default() };
+    let sized = decode_value_with_config(data, config.clone()).unwrap();
+    let reencoded = encode_value(&sized).unwrap();
+    let again = decode_value_with_config(&reencoded, config).unwrap();
+    assert_eq!(again.total_cmp(&sized), Ordering::Equal);
+    assert_eq!(encode_value(&again).unwrap(), reencoded);
+});
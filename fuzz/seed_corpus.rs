@@ -0,0 +1,38 @@
+// ABOUTME: Writes the seed corpora for the fuzz targets from the crate's pinned test vectors.
+// ABOUTME: Run from the fuzz directory with `cargo run --bin seed_corpus` after the vectors change.
+
+use serde_bonjson::compat::TEST_VECTORS;
+use serde_bonjson::{encode_length_field, type_code};
+use std::fs;
+use std::path::Path;
+
+/// Targets that take a whole document.
+const DOCUMENT_TARGETS: &[&str] = &["decode_value", "from_slice_value", "roundtrip"];
+
+fn write_seed(target: &str, name: &str, bytes: &[u8]) -> std::io::Result<()> {
+    let dir = Path::new("corpus").join(target);
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(name), bytes)
+}
+
+fn main() -> std::io::Result<()> {
+    for vector in TEST_VECTORS {
+        for target in DOCUMENT_TARGETS {
+            write_seed(target, vector.name, vector.bytes)?;
+        }
+        if vector.bytes[0] == type_code::BIG_NUMBER {
+            write_seed("big_number", vector.name, &vector.bytes[1..])?;
+        }
+    }
+
+    for text in ["0", "-1.5e-3", "18446744073709551616", "1E+400", "-0.000"] {
+        write_seed("big_number", &format!("text_{}", text.replace('.', "_")), text.as_bytes())?;
+    }
+
+    for value in [0, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+        let mut bytes = Vec::new();
+        encode_length_field(value, &mut bytes);
+        write_seed("length_field", &format!("length_{value}"), &bytes)?;
+    }
+    Ok(())
+}
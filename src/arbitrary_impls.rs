@@ -0,0 +1,179 @@
+// ABOUTME: `arbitrary::Arbitrary` impls for Value, BigNumber, DecoderConfig and SerializerConfig (arbitrary feature).
+// ABOUTME: Generated values decode under the default config, and exhausted input yields nulls and default configs.
+
+use crate::compat::CapabilitySet;
+use crate::decoder::{
+    DecoderConfig, DuplicateKeyCheck, DuplicateKeyMode, FieldMatching, InvalidUtf8Mode, NanInfinityMode, OutOfRangeMode, RecordMismatchMode,
+    StringValidation, UnicodeNormalization, UnknownTypeCodeMode,
+};
+use crate::encoder::DuplicateKeys;
+use crate::ser::{SerializerConfig, TypedArrayStrategy};
+use crate::types::{limits, BigNumber, FloatWidth};
+use crate::value::Value;
+use arbitrary::{Arbitrary, Result, Unstructured};
+use std::collections::BTreeMap;
+
+/// Arrays and objects this deep hold only scalars.
+const MAX_DEPTH: usize = 4;
+
+/// Any u64 significand times 10^288 stays below `f64::MAX` (about 1.8e308).
+const MAX_POSITIVE_EXPONENT: i64 = 288;
+
+/// Most elements (or members) in a generated array (or object).
+const MAX_CONTAINER_LEN: usize = 8;
+
+impl<'a> Arbitrary<'a> for Value {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_value(u, 0)
+    }
+}
+
+fn arbitrary_value(u: &mut Unstructured<'_>, depth: usize) -> Result<Value> {
+    // Containers come last, so they're only reachable while depth allows
+    let kinds = if depth < MAX_DEPTH { 10 } else { 8 };
+    Ok(match u.choose_index(kinds)? {
+        0 => Value::Null,
+        1 => Value::Bool(u.arbitrary()?),
+        2 => Value::Int(u.arbitrary()?),
+        3 => Value::UInt(u.arbitrary()?),
+        4 => Value::Float(finite(u.arbitrary()?)),
+        5 => {
+            if u.arbitrary()? {
+                Value::SizedFloat(finite(f64::from(u.arbitrary::<f32>()?)), FloatWidth::F32)
+            } else {
+                Value::SizedFloat(finite(u.arbitrary()?), FloatWidth::F64)
+            }
+        }
+        6 => Value::BigNumber(u.arbitrary()?),
+        7 => Value::String(arbitrary_string(u)?),
+        8 => {
+            let len = u.int_in_range(0..=MAX_CONTAINER_LEN)?;
+            Value::Array((0..len).map(|_| arbitrary_value(u, depth + 1)).collect::<Result<_>>()?)
+        }
+        _ => {
+            let len = u.int_in_range(0..=MAX_CONTAINER_LEN)?;
+            let mut members = BTreeMap::new();
+            for _ in 0..len {
+                members.insert(arbitrary_string(u)?, arbitrary_value(u, depth + 1)?);
+            }
+            Value::Object(members)
+        }
+    })
+}
+
+/// `value`, or 0 if it's NaN or infinite.
+fn finite(value: f64) -> f64 {
+    if value.is_finite() { value } else { 0.0 }
+}
+
+fn arbitrary_string(u: &mut Unstructured<'_>) -> Result<String> {
+    Ok(u.arbitrary::<&str>()?.replace('\0', ""))
+}
+
+impl<'a> Arbitrary<'a> for BigNumber {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let significand: u64 = u.arbitrary()?;
+        // Larger values would exceed the f64 range, which decoding rejects by default
+        #[allow(clippy::cast_possible_wrap)]
+        let exponent = if u.arbitrary()? {
+            -u.int_in_range(0..=limits::MAX_BIGNUMBER_EXPONENT as i64)?
+        } else {
+            u.int_in_range(0..=MAX_POSITIVE_EXPONENT)?
+        };
+        let sign = if significand != 0 && u.arbitrary()? { -1 } else { 1 };
+        Ok(BigNumber::new(sign, significand, exponent))
+    }
+}
+
+/// Overwrite each field with its generator's value when the next input
+/// byte says so, leaving the default otherwise.
+macro_rules! vary_fields {
+    ($u:ident, $config:ident { $($field:ident => $value:expr,)* }) => {
+        $(
+            if $u.arbitrary::<bool>()? {
+                $config.$field = $value;
+            }
+        )*
+    };
+}
+
+/// The NFC mode only with the feature that implements it.
+fn arbitrary_normalization(u: &mut Unstructured<'_>) -> Result<UnicodeNormalization> {
+    if cfg!(feature = "unicode-normalization") && u.arbitrary()? {
+        Ok(UnicodeNormalization::Nfc)
+    } else {
+        Ok(UnicodeNormalization::None)
+    }
+}
+
+impl<'a> Arbitrary<'a> for DecoderConfig {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut config = DecoderConfig::default();
+        let defaults = DecoderConfig::default();
+        vary_fields!(u, config {
+            allow_nul => u.arbitrary()?,
+            nan_infinity_mode => *u.choose(&[NanInfinityMode::Reject, NanInfinityMode::Allow, NanInfinityMode::Stringify])?,
+            allow_trailing_bytes => u.arbitrary()?,
+            duplicate_key_mode => *u.choose(&[DuplicateKeyMode::Error, DuplicateKeyMode::KeepFirst, DuplicateKeyMode::KeepLast])?,
+            duplicate_key_check => match u.int_in_range(0..=2)? {
+                0 => DuplicateKeyCheck::Off,
+                1 => DuplicateKeyCheck::Exact,
+                _ => DuplicateKeyCheck::Bloom { bits: u.int_in_range(0..=4096)? },
+            },
+            max_depth => u.int_in_range(0..=defaults.max_depth)?,
+            max_container_size => u.int_in_range(0..=defaults.max_container_size)?,
+            max_string_length => u.int_in_range(0..=defaults.max_string_length)?,
+            max_document_size => u.int_in_range(0..=defaults.max_document_size)?,
+            allow_huge_documents => u.arbitrary()?,
+            max_total_allocated_bytes => u.int_in_range(0..=defaults.max_total_allocated_bytes)?,
+            max_typed_array_bytes => u.int_in_range(0..=defaults.max_typed_array_bytes)?,
+            max_record_definitions => u.int_in_range(0..=defaults.max_record_definitions)?,
+            max_record_keys => u.int_in_range(0..=defaults.max_record_keys)?,
+            max_record_key_length => u.int_in_range(0..=defaults.max_record_key_length)?,
+            max_bignumber_exponent => u.int_in_range(0..=defaults.max_bignumber_exponent)?,
+            max_bignumber_magnitude => u.int_in_range(0..=defaults.max_bignumber_magnitude)?,
+            out_of_range_mode => *u.choose(&[OutOfRangeMode::Error, OutOfRangeMode::Stringify])?,
+            invalid_utf8_mode => *u.choose(&[InvalidUtf8Mode::Reject, InvalidUtf8Mode::Replace, InvalidUtf8Mode::Delete])?,
+            string_validation => *u.choose(&[StringValidation::Auto, StringValidation::Combined, StringValidation::Separate])?,
+            unicode_normalization => arbitrary_normalization(u)?,
+            unknown_type_code => *u.choose(&[UnknownTypeCodeMode::Error, UnknownTypeCodeMode::SkipValue, UnknownTypeCodeMode::Null])?,
+            record_mismatch => *u.choose(&[RecordMismatchMode::Error, RecordMismatchMode::NullFill, RecordMismatchMode::Truncate])?,
+            preserve_float_width => u.arbitrary()?,
+            require_canonical_ints => u.arbitrary()?,
+            require_canonical_numbers => u.arbitrary()?,
+            deny_unknown_fields_globally => u.arbitrary()?,
+            field_matching => *u.choose(&[FieldMatching::Exact, FieldMatching::CaseInsensitive])?,
+            field_aliases => u.arbitrary()?,
+            untagged_numeric_coercion => u.arbitrary()?,
+        });
+        Ok(config)
+    }
+}
+
+impl<'a> Arbitrary<'a> for SerializerConfig {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut config = SerializerConfig::default();
+        vary_fields!(u, config {
+            typed_arrays => u.arbitrary()?,
+            records => u.arbitrary()?,
+            skip_null_struct_fields => u.arbitrary()?,
+            typed_array_strategy => *u.choose(&[TypedArrayStrategy::Never, TypedArrayStrategy::SizeOptimal, TypedArrayStrategy::Always])?,
+            typed_array_min_len => u.int_in_range(0..=MAX_CONTAINER_LEN)?,
+            typed_array_max_buffer_bytes => u.arbitrary()?,
+            columnar_structs => u.arbitrary()?,
+            capabilities => CapabilitySet {
+                records: u.arbitrary()?,
+                typed_arrays: u.arbitrary()?,
+                big_numbers: u.arbitrary()?,
+                long_strings: u.arbitrary()?,
+            },
+            sort_map_keys => u.arbitrary()?,
+            duplicate_keys => *u.choose(&[DuplicateKeys::Allow, DuplicateKeys::Error])?,
+            key_normalization => arbitrary_normalization(u)?,
+            checksum_trailer => u.arbitrary()?,
+            nan_infinity_mode => *u.choose(&[NanInfinityMode::Reject, NanInfinityMode::Allow, NanInfinityMode::Stringify])?,
+            stringify_map_keys => u.arbitrary()?,
+        });
+        Ok(config)
+    }
+}
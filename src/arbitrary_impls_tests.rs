@@ -0,0 +1,69 @@
+// ABOUTME: Unit tests for the Arbitrary impls (arbitrary feature).
+// ABOUTME: Tests determinism, defaults on exhausted input, and that generated values round-trip.
+
+use crate::types::BigNumber;
+use crate::{decode_value, decode_value_with_config, encode_value, to_vec_with_config, DecoderConfig, SerializerConfig, Value};
+use arbitrary::{Arbitrary, Unstructured};
+
+/// `len` bytes of deterministic noise for `seed`.
+fn noise(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state.to_le_bytes()[0]
+        })
+        .collect()
+}
+
+#[test]
+fn test_arbitrary_exhausted_input() {
+    let mut u = Unstructured::new(&[]);
+    assert_eq!(Value::arbitrary(&mut u).unwrap(), Value::Null);
+    assert_eq!(BigNumber::arbitrary(&mut u).unwrap(), BigNumber::zero());
+    let decoder = DecoderConfig::arbitrary(&mut u).unwrap();
+    assert_eq!(format!("{decoder:?}"), format!("{:?}", DecoderConfig::default()));
+    let serializer = SerializerConfig::arbitrary(&mut u).unwrap();
+    assert_eq!(format!("{serializer:?}"), format!("{:?}", SerializerConfig::default()));
+}
+
+#[test]
+fn test_arbitrary_values_round_trip() {
+    let mut containers = 0;
+    for seed in 0..500 {
+        let data = noise(seed, 256);
+        let value = Value::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert!(value == Value::arbitrary(&mut Unstructured::new(&data)).unwrap(), "not deterministic: {value:?}");
+        if matches!(value, Value::Array(_) | Value::Object(_)) {
+            containers += 1;
+        }
+
+        let bytes = encode_value(&value).unwrap_or_else(|e| panic!("{e} encoding {value:?}"));
+        let decoded = decode_value(&bytes).unwrap_or_else(|e| panic!("{e} decoding {value:?}"));
+        assert_eq!(encode_value(&decoded).unwrap(), bytes, "{value:?}");
+    }
+    assert!(containers > 50, "only {containers} containers");
+}
+
+#[test]
+fn test_arbitrary_configs() {
+    let mut varied = 0;
+    for seed in 0..200 {
+        let data = noise(seed, 512);
+        let mut u = Unstructured::new(&data);
+        let value = Value::arbitrary(&mut u).unwrap();
+        let decoder = DecoderConfig::arbitrary(&mut u).unwrap();
+        let serializer = SerializerConfig::arbitrary(&mut u).unwrap();
+        if decoder.max_depth != DecoderConfig::default().max_depth {
+            varied += 1;
+        }
+
+        // Any combination may fail, but none may panic
+        if let Ok(bytes) = to_vec_with_config(&value, &serializer) {
+            let _ = decode_value_with_config(&bytes, decoder);
+        }
+    }
+    assert!(varied > 50, "max_depth varied only {varied} times");
+}
@@ -0,0 +1,298 @@
+// ABOUTME: Multi-document archives sharing one record dictionary, with an index for random access.
+// ABOUTME: ArchiveWriter streams documents out; ArchiveReader decodes any one of them on demand.
+
+use crate::de::Deserializer;
+use crate::decoder::{Decoder, DecoderConfig};
+use crate::encoder::Encoder;
+use crate::error::{Error, Result};
+use crate::lazy::LazyValue;
+use crate::value::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Marks the start and end of an archive.
+const MAGIC: &[u8; 4] = b"BJAR";
+
+/// The archive layout version written after the leading magic.
+const VERSION: u8 = 1;
+
+/// Leading magic plus version.
+const HEADER_SIZE: usize = 5;
+
+/// Dictionary offset, index offset and document count (u64 LE each), then the magic.
+const FOOTER_SIZE: usize = 28;
+
+/// Writes many documents into one archive whose object key sets are stored
+/// once, in a dictionary shared by every document.
+///
+/// Every non-empty object is written as a record instance of its key set, so
+/// near-identical documents cost little more than their values. String
+/// values are written inline, since BONJSON has no way to refer to them.
+///
+/// The layout is:
+///
+/// | Part | Contents |
+/// |------|----------|
+/// | Header | `BJAR`, version byte |
+/// | Documents | Each document's root value, back to back, with no record definitions |
+/// | Dictionary | The shared record definitions, as they'd appear at the start of a document |
+/// | Index | Each document's offset, as a u64 LE |
+/// | Footer | Dictionary offset, index offset and document count (u64 LE each), `BJAR` |
+///
+/// Documents are written out as they're appended; the dictionary and index
+/// follow in [`finish`](Self::finish).
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::archive::{ArchiveReader, ArchiveWriter};
+/// use serde_bonjson::bonjson;
+///
+/// let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+/// for id in 0..3 {
+///     writer.append_value(&bonjson!({"id": id, "status": "ok"})).unwrap();
+/// }
+/// let bytes = writer.finish().unwrap();
+///
+/// let archive = ArchiveReader::new(&bytes).unwrap();
+/// assert_eq!(archive.len(), 3);
+/// assert_eq!(archive.value(2).unwrap(), bonjson!({"id": 2, "status": "ok"}));
+/// ```
+pub struct ArchiveWriter<W: Write> {
+    writer: W,
+    /// Bytes written so far.
+    position: u64,
+    definitions: Vec<Vec<String>>,
+    definition_indices: HashMap<Vec<String>, usize>,
+    offsets: Vec<u64>,
+    /// Reused buffer for encoding each document.
+    buf: Vec<u8>,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Start an archive, writing its header.
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(Self {
+            writer,
+            position: HEADER_SIZE as u64,
+            definitions: Vec::new(),
+            definition_indices: HashMap::new(),
+            offsets: Vec::new(),
+            buf: Vec::new(),
+        })
+    }
+
+    /// Append a document, returning its index.
+    pub fn append_value(&mut self, value: &Value) -> Result<usize> {
+        self.add_key_sets(value);
+        self.buf.clear();
+        let mut encoder = Encoder::new(&mut self.buf);
+        crate::encode_value_recursive_inner(&mut encoder, value, &self.definitions, &self.definition_indices)?;
+        encoder.finish()?;
+        self.writer.write_all(&self.buf)?;
+        self.offsets.push(self.position);
+        self.position += self.buf.len() as u64;
+        Ok(self.offsets.len() - 1)
+    }
+
+    /// Serialize a value and append it as a document, returning its index.
+    pub fn append<T: Serialize>(&mut self, value: &T) -> Result<usize> {
+        self.append_value(&crate::to_value(value)?)
+    }
+
+    /// The number of documents appended so far.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns true if no documents have been appended.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Write the dictionary, index and footer, returning the writer.
+    pub fn finish(mut self) -> Result<W> {
+        let dictionary_offset = self.position;
+        self.buf.clear();
+        let mut encoder = Encoder::new(&mut self.buf);
+        for keys in &self.definitions {
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            encoder.write_record_definition(&keys)?;
+        }
+        self.writer.write_all(&self.buf)?;
+        let index_offset = dictionary_offset + self.buf.len() as u64;
+        for offset in &self.offsets {
+            self.writer.write_all(&offset.to_le_bytes())?;
+        }
+        self.writer.write_all(&dictionary_offset.to_le_bytes())?;
+        self.writer.write_all(&index_offset.to_le_bytes())?;
+        self.writer.write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+        self.writer.write_all(MAGIC)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Add the key sets of a document's objects that the dictionary doesn't have yet.
+    fn add_key_sets(&mut self, value: &Value) {
+        match value {
+            Value::Object(map) => {
+                if !map.is_empty() {
+                    let keys: Vec<String> = map.keys().cloned().collect();
+                    if !self.definition_indices.contains_key(&keys) {
+                        self.definition_indices.insert(keys.clone(), self.definitions.len());
+                        self.definitions.push(keys);
+                    }
+                }
+                for v in map.values() {
+                    self.add_key_sets(v);
+                }
+            }
+            Value::Array(arr) => {
+                for item in arr {
+                    self.add_key_sets(item);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reads documents out of an archive written by [`ArchiveWriter`].
+///
+/// Opening an archive reads its footer, index and dictionary; each document
+/// is only decoded when it's asked for, and `max_document_size` applies to
+/// each document on its own.
+#[derive(Debug, Clone)]
+pub struct ArchiveReader<'a> {
+    data: &'a [u8],
+    config: DecoderConfig,
+    record_definitions: Arc<Vec<Vec<String>>>,
+    /// Each document's offset, followed by the dictionary offset (where the last one ends).
+    offsets: Vec<usize>,
+}
+
+impl<'a> ArchiveReader<'a> {
+    /// Open an archive.
+    pub fn new(data: &'a [u8]) -> Result<Self> {
+        Self::with_config(data, DecoderConfig::default())
+    }
+
+    /// Open an archive, decoding its documents with custom configuration.
+    pub fn with_config(data: &'a [u8], config: DecoderConfig) -> Result<Self> {
+        if data.len() < HEADER_SIZE + FOOTER_SIZE || &data[..4] != MAGIC || !data.ends_with(MAGIC) {
+            return Err(Error::InvalidData("not a BONJSON archive".into()));
+        }
+        if data[4] != VERSION {
+            return Err(Error::InvalidData(format!("unsupported archive version {}", data[4])));
+        }
+        let footer = data.len() - FOOTER_SIZE;
+        let read_u64 = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        let invalid = || Error::InvalidData("corrupt archive index".into());
+        let to_offset = |n: u64| usize::try_from(n).map_err(|_| invalid());
+        let dictionary_offset = to_offset(read_u64(footer))?;
+        let index_offset = to_offset(read_u64(footer + 8))?;
+        let count = to_offset(read_u64(footer + 16))?;
+        if dictionary_offset < HEADER_SIZE
+            || index_offset < dictionary_offset
+            || footer < index_offset
+            || count.checked_mul(8) != Some(footer - index_offset)
+        {
+            return Err(invalid());
+        }
+
+        let mut offsets = Vec::with_capacity(count + 1);
+        for i in 0..count {
+            let offset = to_offset(read_u64(index_offset + i * 8))?;
+            // Offsets must ascend from the header, and a document can't be empty
+            let previous = offsets.last().map_or(HEADER_SIZE, |&prev| prev + 1);
+            if offset < previous || offset >= dictionary_offset {
+                return Err(invalid());
+            }
+            offsets.push(offset);
+        }
+        offsets.push(dictionary_offset);
+
+        let mut decoder = Decoder::with_config(&data[dictionary_offset..index_offset], config.clone());
+        decoder.read_record_definitions()?;
+        if !decoder.remaining().is_empty() {
+            return Err(Error::InvalidData("archive dictionary holds more than record definitions".into()));
+        }
+        let record_definitions = decoder.shared_record_definitions();
+        Ok(Self { data, config, record_definitions, offsets })
+    }
+
+    /// The number of documents in the archive.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Returns true if the archive holds no documents.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of record definitions in the shared dictionary.
+    #[must_use]
+    pub fn dictionary_len(&self) -> usize {
+        self.record_definitions.len()
+    }
+
+    /// The encoded bytes of a document. They refer to the archive's
+    /// dictionary, so they aren't a standalone document.
+    #[must_use]
+    pub fn document_bytes(&self, index: usize) -> Option<&'a [u8]> {
+        let end = *self.offsets.get(index + 1)?;
+        Some(&self.data[self.offsets[index]..end])
+    }
+
+    /// Decode a document into a `Value`.
+    pub fn value(&self, index: usize) -> Result<Value> {
+        let mut decoder = self.decoder(index)?;
+        decoder.check_document_size()?;
+        let value = crate::decode_value_recursive(&mut decoder)?;
+        decoder.finish()?;
+        Ok(value)
+    }
+
+    /// Deserialize a document into any type implementing `Deserialize`.
+    pub fn deserialize<T: Deserialize<'a>>(&self, index: usize) -> Result<T> {
+        let decoder = self.decoder(index)?;
+        decoder.check_document_size()?;
+        let mut de = Deserializer::from_decoder(decoder);
+        let value = T::deserialize(&mut de)?;
+        de.into_decoder().finish()?;
+        Ok(value)
+    }
+
+    /// A lazy view of a document, decoding only the parts that are read.
+    pub fn lazy(&self, index: usize) -> Result<LazyValue<'a>> {
+        let data = self.document_bytes(index).ok_or_else(|| Self::out_of_range(index))?;
+        Ok(LazyValue::with_record_definitions(
+            data,
+            self.config.clone(),
+            Arc::clone(&self.record_definitions),
+        ))
+    }
+
+    /// Decode every document into a `Value`, in order.
+    pub fn values(&self) -> impl Iterator<Item = Result<Value>> + '_ {
+        (0..self.len()).map(|i| self.value(i))
+    }
+
+    fn decoder(&self, index: usize) -> Result<Decoder<'a>> {
+        let data = self.document_bytes(index).ok_or_else(|| Self::out_of_range(index))?;
+        Ok(Decoder::resume(data, self.config.clone(), Arc::clone(&self.record_definitions), 0))
+    }
+
+    fn out_of_range(index: usize) -> Error {
+        Error::InvalidData(format!("archive has no document {index}"))
+    }
+}
@@ -0,0 +1,99 @@
+// ABOUTME: Unit tests for the archive module.
+// ABOUTME: Tests round trips through the shared dictionary, random access, and corrupt archives.
+
+use crate::archive::{ArchiveReader, ArchiveWriter};
+use crate::{bonjson, DecoderConfig, Error, Value};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Event<'a> {
+    id: u32,
+    kind: &'a str,
+    tags: Vec<String>,
+}
+
+fn sample(id: i64) -> Value {
+    bonjson!({"id": id, "user": {"name": "ada", "roles": ["admin"]}, "items": [{"sku": "a", "qty": 1}, {"sku": "b"}]})
+}
+
+#[test]
+fn test_round_trip() {
+    let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+    assert!(writer.is_empty());
+    for id in 0..100 {
+        assert_eq!(writer.append_value(&sample(id)).unwrap(), id as usize);
+    }
+    writer.append(&Event { id: 7, kind: "click", tags: vec!["x".into()] }).unwrap();
+    writer.append_value(&bonjson!([1, "two", null])).unwrap();
+    assert_eq!(writer.len(), 102);
+    let bytes = writer.finish().unwrap();
+
+    let archive = ArchiveReader::new(&bytes).unwrap();
+    assert_eq!(archive.len(), 102);
+    // The root, user and two item key sets, then the event's
+    assert_eq!(archive.dictionary_len(), 5);
+    assert_eq!(archive.value(57).unwrap(), sample(57));
+    assert_eq!(archive.value(101).unwrap(), bonjson!([1, "two", null]));
+    let event: Event<'_> = archive.deserialize(100).unwrap();
+    assert_eq!(event, Event { id: 7, kind: "click", tags: vec!["x".into()] });
+    assert_eq!(archive.values().filter(Result::is_ok).count(), 102);
+
+    let lazy = archive.lazy(3).unwrap();
+    let name = lazy.get_key("user").unwrap().unwrap().get_key("name").unwrap().unwrap();
+    assert_eq!(name.decode().unwrap(), bonjson!("ada"));
+
+    // Keys are stored once, so a document is much smaller than standalone
+    let standalone = crate::encode_value(&sample(5)).unwrap();
+    assert!(archive.document_bytes(5).unwrap().len() * 2 < standalone.len());
+
+    assert!(archive.value(102).is_err());
+    assert!(archive.document_bytes(102).is_none());
+}
+
+#[test]
+fn test_empty_and_limits() {
+    let bytes = ArchiveWriter::new(Vec::new()).unwrap().finish().unwrap();
+    let archive = ArchiveReader::new(&bytes).unwrap();
+    assert!(archive.is_empty());
+    assert_eq!(archive.values().count(), 0);
+
+    let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+    writer.append_value(&bonjson!("short")).unwrap();
+    writer.append_value(&bonjson!("a much longer string than the limit")).unwrap();
+    let bytes = writer.finish().unwrap();
+    // The document size limit applies per document
+    let config = DecoderConfig { max_document_size: 10, ..Default::default() };
+    let archive = ArchiveReader::with_config(&bytes, config).unwrap();
+    assert_eq!(archive.value(0).unwrap(), bonjson!("short"));
+    assert_eq!(archive.value(1).unwrap_err(), Error::MaxDocumentSizeExceeded);
+}
+
+#[test]
+fn test_corrupt_archives() {
+    let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+    writer.append_value(&sample(1)).unwrap();
+    writer.append_value(&sample(2)).unwrap();
+    let bytes = writer.finish().unwrap();
+
+    assert!(ArchiveReader::new(&bytes[..bytes.len() - 1]).is_err());
+    assert!(ArchiveReader::new(&crate::encode_value(&sample(1)).unwrap()).is_err());
+
+    let mut wrong_version = bytes.clone();
+    wrong_version[4] = 9;
+    assert_eq!(
+        ArchiveReader::new(&wrong_version).unwrap_err(),
+        Error::InvalidData("unsupported archive version 9".into())
+    );
+
+    // Swap the two index entries so the offsets descend
+    let index = bytes.len() - 28 - 16;
+    let mut swapped = bytes.clone();
+    swapped[index..index + 16].rotate_left(8);
+    assert_eq!(ArchiveReader::new(&swapped).unwrap_err(), Error::InvalidData("corrupt archive index".into()));
+
+    // A document count that doesn't match the index size
+    let mut miscounted = bytes;
+    let count = miscounted.len() - 12;
+    miscounted[count] = 3;
+    assert!(ArchiveReader::new(&miscounted).is_err());
+}
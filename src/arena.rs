@@ -0,0 +1,444 @@
+// ABOUTME: Arena-allocated decoding for short-lived documents (`arena` feature).
+// ABOUTME: ArenaValue trees live in a bumpalo::Bump (strings borrow from the input where they can) and are freed all at once.
+
+use crate::decoder::{
+    DecodedValue, Decoder, DecoderConfig, DuplicateKeyMode, RecordMismatchMode, TypedElement, UnicodeNormalization,
+};
+use crate::error::{Error, Result};
+use crate::types::BigNumber;
+use crate::value::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+pub use bumpalo::Bump;
+
+/// Objects with more entries than this index their keys for duplicate checks
+/// instead of scanning.
+const KEY_INDEX_THRESHOLD: usize = 16;
+
+/// A decoded value allocated in a [`Bump`] arena.
+///
+/// Arrays and objects are slices in the arena, and strings are either
+/// borrowed from the input or copied into the arena when decoding had to
+/// change them. Nothing is freed per value: dropping or resetting the arena
+/// releases the whole tree at once, which suits decoding a request,
+/// inspecting it and throwing it away.
+///
+/// Decoding follows the same rules as [`decode_value`](crate::decode_value):
+/// record instances become objects and typed arrays become arrays. Unlike
+/// [`Value`], objects keep their entries in document order, and
+/// [`get_key`](Self::get_key) searches them linearly.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ArenaValue<'a> {
+    /// JSON null
+    #[default]
+    Null,
+    /// JSON boolean
+    Bool(bool),
+    /// A signed 64-bit integer
+    Int(i64),
+    /// An unsigned 64-bit integer
+    UInt(u64),
+    /// A 64-bit floating point number
+    Float(f64),
+    /// An arbitrary-precision decimal number
+    BigNumber(BigNumber),
+    /// A UTF-8 string
+    String(&'a str),
+    /// A JSON array
+    Array(&'a [ArenaValue<'a>]),
+    /// A JSON object, as key-value pairs in document order
+    Object(&'a [(&'a str, ArenaValue<'a>)]),
+}
+
+impl<'a> ArenaValue<'a> {
+    /// Returns true if this value is null.
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, ArenaValue::Null)
+    }
+
+    /// If this is a boolean, returns the value.
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ArenaValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// If this is an integer, returns the value as i64.
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ArenaValue::Int(n) => Some(*n),
+            ArenaValue::UInt(n) => i64::try_from(*n).ok(),
+            ArenaValue::BigNumber(bn) => bn.to_i64(),
+            _ => None,
+        }
+    }
+
+    /// If this is an integer, returns the value as u64.
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            ArenaValue::UInt(n) => Some(*n),
+            ArenaValue::Int(n) => u64::try_from(*n).ok(),
+            ArenaValue::BigNumber(bn) => bn.to_u64(),
+            _ => None,
+        }
+    }
+
+    /// If this is a number, returns the value as f64.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)] // Intentional: int-to-float conversion may lose precision
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ArenaValue::Float(f) => Some(*f),
+            ArenaValue::Int(n) => Some(*n as f64),
+            ArenaValue::UInt(n) => Some(*n as f64),
+            ArenaValue::BigNumber(bn) => Some(bn.to_f64()),
+            _ => None,
+        }
+    }
+
+    /// If this is a string, returns it.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            ArenaValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// If this is an array, returns its elements.
+    #[must_use]
+    pub fn as_array(&self) -> Option<&'a [ArenaValue<'a>]> {
+        match self {
+            ArenaValue::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// If this is an object, returns its entries in document order.
+    #[must_use]
+    pub fn as_object(&self) -> Option<&'a [(&'a str, ArenaValue<'a>)]> {
+        match self {
+            ArenaValue::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Index into an array. Returns None if not an array or out of bounds.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&'a ArenaValue<'a>> {
+        self.as_array().and_then(|a| a.get(index))
+    }
+
+    /// Look up an object member by key. Returns None if not an object or key not found.
+    #[must_use]
+    pub fn get_key(&self, key: &str) -> Option<&'a ArenaValue<'a>> {
+        self.as_object()?.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// Copy this value out of the arena into an owned [`Value`].
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        match *self {
+            ArenaValue::Null => Value::Null,
+            ArenaValue::Bool(b) => Value::Bool(b),
+            ArenaValue::Int(n) => Value::Int(n),
+            ArenaValue::UInt(n) => Value::UInt(n),
+            ArenaValue::Float(f) => Value::Float(f),
+            ArenaValue::BigNumber(bn) => Value::BigNumber(bn),
+            ArenaValue::String(s) => Value::String(s.to_owned()),
+            ArenaValue::Array(a) => Value::Array(a.iter().map(ArenaValue::to_value).collect()),
+            ArenaValue::Object(o) => Value::Object(o.iter().map(|(k, v)| ((*k).to_owned(), v.to_value())).collect()),
+        }
+    }
+}
+
+/// Decode a BONJSON document into an [`ArenaValue`] allocated in `arena`.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::arena::{decode_value_in, Bump};
+/// use serde_bonjson::{bonjson, encode_value};
+///
+/// let bytes = encode_value(&bonjson!({"user": "ada", "roles": ["admin", "dev"]})).unwrap();
+/// let mut arena = Bump::new();
+/// for _ in 0..3 {
+///     let value = decode_value_in(&bytes, &arena).unwrap();
+///     assert_eq!(value.get_key("roles").and_then(|r| r.get(1)).and_then(|r| r.as_str()), Some("dev"));
+///     // Free the whole tree and reuse the memory for the next request
+///     arena.reset();
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`decode_value`](crate::decode_value).
+pub fn decode_value_in<'a>(data: &'a [u8], arena: &'a Bump) -> Result<ArenaValue<'a>> {
+    decode_value_in_with_config(data, arena, DecoderConfig::default())
+}
+
+/// Decode a BONJSON document into an [`ArenaValue`] allocated in `arena`, with custom configuration.
+///
+/// # Errors
+///
+/// Returns the same errors as [`decode_value_with_config`](crate::decode_value_with_config).
+pub fn decode_value_in_with_config<'a>(data: &'a [u8], arena: &'a Bump, config: DecoderConfig) -> Result<ArenaValue<'a>> {
+    codec_span!("bonjson.decode_value_in", input_bytes = data.len());
+    let mut decoder = Decoder::with_config(data, config);
+    let result = decode_document(&mut decoder, arena);
+    decoder.report_metrics();
+    if result.is_ok() {
+        decoder.finish_progress();
+    }
+    result
+}
+
+fn decode_document<'a>(decoder: &mut Decoder<'a>, arena: &'a Bump) -> Result<ArenaValue<'a>> {
+    decoder.check_document_size()?;
+    decoder.read_record_definitions()?;
+    let mut builder = Builder::new(decoder, arena);
+    let value = builder.value(decoder)?;
+    decoder.finish()?;
+    Ok(value)
+}
+
+// ============================================================================
+// Builder
+// ============================================================================
+
+/// Builds an [`ArenaValue`] tree. Children are collected on shared scratch
+/// stacks and copied into the arena once their container ends, so each
+/// container costs one exact-size arena allocation.
+struct Builder<'a> {
+    arena: &'a Bump,
+    /// Record definition keys, copied into the arena (and normalized) once.
+    record_keys: Vec<&'a [&'a str]>,
+    elements: Vec<ArenaValue<'a>>,
+    entries: Vec<(&'a str, ArenaValue<'a>)>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(decoder: &Decoder<'a>, arena: &'a Bump) -> Self {
+        let normalization = decoder.config().unicode_normalization;
+        let record_keys = decoder
+            .record_definitions()
+            .iter()
+            .map(|keys| {
+                let keys: Vec<&'a str> =
+                    keys.iter().map(|k| alloc_string(arena, normalization, Cow::Owned(k.clone()))).collect();
+                &*arena.alloc_slice_copy(&keys)
+            })
+            .collect();
+        Self { arena, record_keys, elements: Vec::new(), entries: Vec::new() }
+    }
+
+    fn string(&self, decoder: &Decoder<'a>, s: Cow<'a, str>) -> &'a str {
+        alloc_string(self.arena, decoder.config().unicode_normalization, s)
+    }
+
+    fn value(&mut self, decoder: &mut Decoder<'a>) -> Result<ArenaValue<'a>> {
+        match decoder.decode_value()? {
+            DecodedValue::Null => Ok(ArenaValue::Null),
+            DecodedValue::Bool(b) => Ok(ArenaValue::Bool(b)),
+            DecodedValue::Int(n) => Ok(ArenaValue::Int(n)),
+            DecodedValue::UInt(n) => Ok(ArenaValue::UInt(n)),
+            DecodedValue::Float(f) => Ok(float(decoder.config(), f)),
+            DecodedValue::BigNumber(bn) => match crate::stringify_big_number(decoder.config(), &bn)? {
+                Some(s) => Ok(ArenaValue::String(self.arena.alloc_str(&s))),
+                None => Ok(ArenaValue::BigNumber(bn)),
+            },
+            DecodedValue::String(s) => {
+                decoder.charge_allocation(s.len())?;
+                Ok(ArenaValue::String(self.string(decoder, s)))
+            }
+            DecodedValue::ArrayStart => self.array(decoder),
+            DecodedValue::ObjectStart => self.object(decoder),
+            DecodedValue::RecordInstanceStart(def_index) => self.record(decoder, def_index),
+            DecodedValue::TypedArrayStart { element_type_code, count } => {
+                decoder.charge_allocation(count.saturating_mul(crate::decoder::ELEMENT_ALLOCATION))?;
+                let start = self.elements.len();
+                for _ in 0..count {
+                    decoder.poll_cancellation()?;
+                    let value = match decoder.read_typed_element(element_type_code)? {
+                        TypedElement::Int(n) => ArenaValue::Int(n),
+                        TypedElement::UInt(n) => ArenaValue::UInt(n),
+                        TypedElement::F32(f) => float(decoder.config(), f64::from(f)),
+                        TypedElement::F64(f) => float(decoder.config(), f),
+                    };
+                    self.elements.push(value);
+                }
+                decoder.end_typed_array()?;
+                Ok(ArenaValue::Array(self.take_elements(start)))
+            }
+            DecodedValue::ContainerEnd => Err(Error::UnbalancedContainers),
+        }
+    }
+
+    fn array(&mut self, decoder: &mut Decoder<'a>) -> Result<ArenaValue<'a>> {
+        let max_size = decoder.config().max_container_size;
+        let start = self.elements.len();
+        while !decoder.is_at_container_end()? {
+            if self.elements.len() - start >= max_size {
+                return Err(Error::MaxContainerSizeExceeded);
+            }
+            decoder.begin_element(0)?;
+            let value = self.value(decoder)?;
+            self.elements.push(value);
+        }
+        decoder.end_container()?;
+        Ok(ArenaValue::Array(self.take_elements(start)))
+    }
+
+    fn object(&mut self, decoder: &mut Decoder<'a>) -> Result<ArenaValue<'a>> {
+        let max_size = decoder.config().max_container_size;
+        let mut object = ObjectEntries::new(self.entries.len(), decoder.config().duplicate_key_mode);
+        let mut pair_count: usize = 0;
+        while !decoder.is_at_container_end()? {
+            if pair_count >= max_size {
+                return Err(Error::MaxContainerSizeExceeded);
+            }
+            let key = match decoder.decode_value()? {
+                DecodedValue::String(s) => {
+                    decoder.begin_element(s.len())?;
+                    self.string(decoder, s)
+                }
+                _ => return Err(Error::ExpectedObjectKey),
+            };
+            let value = self.value(decoder)?;
+            if object.insert(&mut self.entries, key, value)? {
+                pair_count += 1;
+            }
+        }
+        decoder.end_container()?;
+        Ok(ArenaValue::Object(self.take_entries(object.start)))
+    }
+
+    fn record(&mut self, decoder: &mut Decoder<'a>, def_index: usize) -> Result<ArenaValue<'a>> {
+        let keys = self.record_keys[def_index];
+        let max_size = decoder.config().max_container_size;
+        let mismatch_mode = decoder.config().record_mismatch;
+        let mut object = ObjectEntries::new(self.entries.len(), decoder.config().duplicate_key_mode);
+        let mut value_count: usize = 0;
+        let mut extra_count: usize = 0;
+
+        while !decoder.is_at_container_end()? {
+            if value_count + extra_count >= max_size {
+                return Err(Error::MaxContainerSizeExceeded);
+            }
+            if value_count >= keys.len() {
+                // Decode extras so the error can report the real count
+                decoder.begin_element(0)?;
+                self.value(decoder)?;
+                extra_count += 1;
+                continue;
+            }
+            let key = keys[value_count];
+            decoder.begin_element(key.len())?;
+            let value = self.value(decoder)?;
+            object.insert(&mut self.entries, key, value)?;
+            value_count += 1;
+        }
+        decoder.end_container()?;
+
+        let too_many = extra_count > 0 && mismatch_mode != RecordMismatchMode::Truncate;
+        let too_few = value_count < keys.len() && mismatch_mode == RecordMismatchMode::Error;
+        if too_many || too_few {
+            return Err(Error::RecordMismatch { definition: def_index, keys: keys.len(), values: value_count + extra_count });
+        }
+
+        if mismatch_mode == RecordMismatchMode::NullFill {
+            for key in &keys[value_count..] {
+                decoder.begin_element(key.len())?;
+                if object.find(&self.entries, key).is_none() {
+                    object.push(&mut self.entries, key, ArenaValue::Null);
+                }
+            }
+        }
+        Ok(ArenaValue::Object(self.take_entries(object.start)))
+    }
+
+    fn take_elements(&mut self, start: usize) -> &'a [ArenaValue<'a>] {
+        let slice = self.arena.alloc_slice_copy(&self.elements[start..]);
+        self.elements.truncate(start);
+        slice
+    }
+
+    fn take_entries(&mut self, start: usize) -> &'a [(&'a str, ArenaValue<'a>)] {
+        let slice = self.arena.alloc_slice_copy(&self.entries[start..]);
+        self.entries.truncate(start);
+        slice
+    }
+}
+
+/// The entries of one object under construction, at `entries[start..]` of
+/// the builder's scratch stack.
+struct ObjectEntries<'a> {
+    start: usize,
+    duplicate_key_mode: DuplicateKeyMode,
+    /// Key positions, built once the object outgrows [`KEY_INDEX_THRESHOLD`].
+    index: Option<HashMap<&'a str, usize>>,
+}
+
+impl<'a> ObjectEntries<'a> {
+    fn new(start: usize, duplicate_key_mode: DuplicateKeyMode) -> Self {
+        Self { start, duplicate_key_mode, index: None }
+    }
+
+    /// Add an entry, applying the duplicate key mode. Returns false if the
+    /// value was discarded as a duplicate.
+    fn insert(&mut self, entries: &mut Vec<(&'a str, ArenaValue<'a>)>, key: &'a str, value: ArenaValue<'a>) -> Result<bool> {
+        let Some(position) = self.find(entries, key) else {
+            self.push(entries, key, value);
+            return Ok(true);
+        };
+        match self.duplicate_key_mode {
+            DuplicateKeyMode::Error => Err(Error::DuplicateKey),
+            DuplicateKeyMode::KeepFirst => Ok(false),
+            DuplicateKeyMode::KeepLast => {
+                entries[position].1 = value;
+                Ok(true)
+            }
+        }
+    }
+
+    fn find(&self, entries: &[(&'a str, ArenaValue<'a>)], key: &str) -> Option<usize> {
+        match &self.index {
+            Some(index) => index.get(key).copied(),
+            None => entries[self.start..].iter().position(|(k, _)| *k == key).map(|i| self.start + i),
+        }
+    }
+
+    fn push(&mut self, entries: &mut Vec<(&'a str, ArenaValue<'a>)>, key: &'a str, value: ArenaValue<'a>) {
+        entries.push((key, value));
+        let position = entries.len() - 1;
+        if let Some(index) = &mut self.index {
+            index.insert(key, position);
+        } else if entries.len() - self.start > KEY_INDEX_THRESHOLD {
+            let own = entries[self.start..].iter().enumerate().map(|(i, (k, _))| (*k, self.start + i));
+            self.index = Some(own.collect());
+        }
+    }
+}
+
+/// A float, or its name under `NanInfinityMode::Stringify`.
+fn float(config: &DecoderConfig, value: f64) -> ArenaValue<'static> {
+    match config.nan_infinity_mode.stringify(value) {
+        Some(name) => ArenaValue::String(name),
+        None => ArenaValue::Float(value),
+    }
+}
+
+/// A string borrowed from the input where possible, otherwise copied into
+/// the arena (after NFC normalization, if configured).
+fn alloc_string<'a>(arena: &'a Bump, normalization: UnicodeNormalization, s: Cow<'a, str>) -> &'a str {
+    match s {
+        Cow::Borrowed(b) if normalization == UnicodeNormalization::None => b,
+        s => arena.alloc_str(&crate::maybe_nfc_normalize(normalization, s.into_owned())),
+    }
+}
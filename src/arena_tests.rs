@@ -0,0 +1,91 @@
+// ABOUTME: Unit tests for the arena module (arena feature).
+// ABOUTME: Tests that arena decoding matches decode_value, borrows strings, and applies the decoder configuration.
+
+use crate::arena::{decode_value_in, decode_value_in_with_config, ArenaValue, Bump};
+use crate::{
+    bonjson, decode_value, decode_value_with_config, encode_value, to_vec_with_config, DecoderConfig, DuplicateKeyMode,
+    Error, NanInfinityMode, RecordMismatchMode, SerializerConfig, Value,
+};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Point {
+    x: i32,
+    y: i32,
+    label: String,
+}
+
+#[test]
+fn test_arena_matches_decode_value() {
+    let arena = Bump::new();
+    let value = bonjson!({
+        "name": "widget",
+        "long": ("x".repeat(100)),
+        "nested": {"list": [1, (-2), 3.5, null, true, [0.1, 0.2, 0.3]], "empty": []},
+        "big": (u64::MAX)
+    });
+    let bytes = encode_value(&value).unwrap();
+    let decoded = decode_value_in(&bytes, &arena).unwrap();
+    assert_eq!(decoded.to_value(), value);
+    assert_eq!(decoded.get_key("name").and_then(|v| v.as_str()), Some("widget"));
+    assert_eq!(decoded.get_key("big").and_then(|v| v.as_u64()), Some(u64::MAX));
+
+    // Strings are borrowed from the input rather than copied
+    let name = decoded.get_key("name").and_then(ArenaValue::as_str).unwrap();
+    assert!(bytes.as_ptr_range().contains(&name.as_ptr()));
+
+    // Records and typed arrays come out as objects and arrays
+    let config = SerializerConfig { records: true, ..Default::default() };
+    let points: Vec<Point> = (0..4).map(|i| Point { x: i, y: -i, label: format!("p{i}") }).collect();
+    let bytes = to_vec_with_config(&(points, vec![0.5f32, 1.5]), &config).unwrap();
+    let decoded = decode_value_in(&bytes, &arena).unwrap();
+    assert_eq!(decoded.to_value(), decode_value(&bytes).unwrap());
+    let first = decoded.get(0).and_then(|p| p.get(0)).unwrap();
+    assert_eq!(first.as_object().unwrap().iter().map(|(k, _)| *k).collect::<Vec<_>>(), ["x", "y", "label"]);
+}
+
+#[test]
+fn test_arena_follows_config() {
+    let arena = Bump::new();
+
+    // Duplicate keys, in small objects and in ones large enough to be indexed
+    for extra in [0, 40] {
+        let mut bytes = vec![crate::type_code::OBJECT];
+        for i in 0..extra {
+            bytes.extend(encode_value(&Value::String(format!("k{i}"))).unwrap());
+            bytes.push(0x00);
+        }
+        bytes.extend([0x66, b'a', 0x01, 0x66, b'a', 0x02, crate::type_code::CONTAINER_END]);
+        assert_eq!(decode_value_in(&bytes, &arena).unwrap_err(), Error::DuplicateKey);
+        for mode in [DuplicateKeyMode::KeepFirst, DuplicateKeyMode::KeepLast] {
+            let config = DecoderConfig { duplicate_key_mode: mode, ..Default::default() };
+            let decoded = decode_value_in_with_config(&bytes, &arena, config.clone()).unwrap();
+            assert_eq!(decoded.to_value(), decode_value_with_config(&bytes, config).unwrap());
+            assert_eq!(decoded.as_object().unwrap().len(), extra + 1);
+        }
+    }
+
+    // Record instances with missing values
+    let config = SerializerConfig { records: true, ..Default::default() };
+    let points = vec![Point { x: 1, y: 2, label: "a".into() }, Point { x: 3, y: 4, label: "b".into() }];
+    let mut bytes = to_vec_with_config(&points, &config).unwrap();
+    let end = bytes.len() - 2;
+    let label = bytes.iter().rposition(|&b| b == 0x66).unwrap();
+    bytes.drain(label..end);
+    let decoded = decode_value_in(&bytes, &arena).unwrap();
+    assert_eq!(decoded.get(1).unwrap().get_key("label"), Some(&ArenaValue::Null));
+    let strict = DecoderConfig { record_mismatch: RecordMismatchMode::Error, ..Default::default() };
+    assert!(matches!(decode_value_in_with_config(&bytes, &arena, strict), Err(Error::RecordMismatch { .. })));
+
+    // NaN policy and limits
+    let nan = [&[crate::type_code::FLOAT64][..], &f64::NAN.to_le_bytes()].concat();
+    assert_eq!(decode_value_in(&nan, &arena).unwrap_err(), Error::NanNotAllowed);
+    let config = DecoderConfig { nan_infinity_mode: NanInfinityMode::Stringify, ..Default::default() };
+    assert_eq!(decode_value_in_with_config(&nan, &arena, config).unwrap(), ArenaValue::String("NaN"));
+    let bytes = encode_value(&bonjson!([1, 2, 3])).unwrap();
+    let config = DecoderConfig { max_container_size: 2, ..Default::default() };
+    assert_eq!(decode_value_in_with_config(&bytes, &arena, config).unwrap_err(), Error::MaxContainerSizeExceeded);
+    let mut trailing = bytes.clone();
+    trailing.push(0x00);
+    assert_eq!(decode_value_in(&trailing, &arena).unwrap_err(), Error::TrailingBytes(bytes.len()));
+}
@@ -0,0 +1,98 @@
+// ABOUTME: BonjsonWrite and BonjsonRead, the byte sinks and sources the Encoder and ReaderDeserializer run on.
+// ABOUTME: Every io::Write and io::Read is one; other backends implement the traits directly.
+
+use std::io::{self, ErrorKind, IoSlice, Read, Write};
+
+/// Where an [`Encoder`](crate::Encoder) (and so a [`Serializer`](crate::Serializer))
+/// writes its bytes.
+///
+/// Every [`io::Write`] is a `BonjsonWrite`, so writers, `Vec<u8>` and
+/// `BufWriter` need nothing extra. Implement it directly for a backend that
+/// isn't an `io::Write`, or can't be one (a memory-mapped region, a ring
+/// buffer, an adapter that hands chunks to an async task). Method names don't
+/// clash with `io::Write`'s, so both traits can be in scope.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{BonjsonWrite, Encoder};
+///
+/// /// Counts the bytes that would be written, without keeping them.
+/// #[derive(Default)]
+/// struct SizeOnly(usize);
+///
+/// impl BonjsonWrite for SizeOnly {
+///     fn write_bytes(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+///         self.0 += bytes.len();
+///         Ok(())
+///     }
+/// }
+///
+/// let mut encoder = Encoder::new(SizeOnly::default());
+/// encoder.write_str("hello").unwrap();
+/// assert_eq!(encoder.finish().unwrap().0, 6);
+/// ```
+pub trait BonjsonWrite {
+    /// Write all of `bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the backend's error; the encoder passes it on as `Error::Io`.
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()>;
+
+    /// Write all of `slices`, in order. The encoder uses this for a length
+    /// prefix followed by a large payload, so a backend can take the payload
+    /// without copying it through a buffer first. Defaults to one
+    /// `write_bytes` per slice.
+    ///
+    /// # Errors
+    ///
+    /// Returns the backend's error.
+    fn write_slices(&mut self, slices: &mut [IoSlice<'_>]) -> io::Result<()> {
+        slices.iter().try_for_each(|slice| self.write_bytes(slice))
+    }
+}
+
+impl<W: Write + ?Sized> BonjsonWrite for W {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.write_all(bytes)
+    }
+
+    /// Uses `write_vectored`, which lets `BufWriter` pass an oversized
+    /// payload straight to the inner writer.
+    fn write_slices(&mut self, mut slices: &mut [IoSlice<'_>]) -> io::Result<()> {
+        while !slices.is_empty() {
+            match self.write_vectored(slices) {
+                Ok(0) => return Err(io::Error::from(ErrorKind::WriteZero)),
+                Ok(n) => IoSlice::advance_slices(&mut slices, n),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a [`ReaderDeserializer`](crate::ReaderDeserializer) pulls its bytes from.
+///
+/// Every [`io::Read`] is a `BonjsonRead`. Implement it directly for a source
+/// that isn't one, such as a ring buffer a producer thread fills.
+///
+/// Whole documents already in memory (including memory-mapped files) don't
+/// need either: decode the slice with `from_slice` or `decode_value`, which
+/// borrow strings straight from it.
+pub trait BonjsonRead {
+    /// Append up to `max` bytes to `buf`, returning how many were appended.
+    /// Returning 0 means the input has ended; `max` is never 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns the source's error; the deserializer passes it on as `Error::Io`.
+    fn read_into(&mut self, buf: &mut Vec<u8>, max: usize) -> io::Result<usize>;
+}
+
+impl<R: Read> BonjsonRead for R {
+    fn read_into(&mut self, buf: &mut Vec<u8>, max: usize) -> io::Result<usize> {
+        self.by_ref().take(max as u64).read_to_end(buf)
+    }
+}
@@ -0,0 +1,98 @@
+// ABOUTME: Unit tests for the backend module.
+// ABOUTME: Tests the encoder, serializer and reader deserializer over backends that aren't io::Write or io::Read.
+
+use crate::{to_vec, BonjsonRead, BonjsonWrite, Encoder, ReaderDeserializer, Serializer};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{self, IoSlice};
+
+/// Keeps each write as a separate chunk, and each `write_slices` call as one chunk.
+#[derive(Default)]
+struct Chunks(Vec<Vec<u8>>);
+
+impl BonjsonWrite for Chunks {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.0.push(bytes.to_vec());
+        Ok(())
+    }
+
+    fn write_slices(&mut self, slices: &mut [IoSlice<'_>]) -> io::Result<()> {
+        self.0.push(slices.iter().flat_map(|slice| slice.iter().copied()).collect());
+        Ok(())
+    }
+}
+
+/// A fixed-capacity region that fails once full.
+struct Region {
+    data: [u8; 16],
+    len: usize,
+}
+
+impl BonjsonWrite for Region {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let end = self.len + bytes.len();
+        if end > self.data.len() {
+            return Err(io::Error::new(io::ErrorKind::OutOfMemory, "region full"));
+        }
+        self.data[self.len..end].copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// A ring buffer that hands out at most `step` bytes per read.
+struct Ring {
+    queue: VecDeque<u8>,
+    step: usize,
+}
+
+impl BonjsonRead for Ring {
+    fn read_into(&mut self, buf: &mut Vec<u8>, max: usize) -> io::Result<usize> {
+        let n = max.min(self.step).min(self.queue.len());
+        buf.extend(self.queue.drain(..n));
+        Ok(n)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Reading {
+    sensor: String,
+    values: Vec<i32>,
+}
+
+#[test]
+fn test_custom_write_backend() {
+    let reading = Reading { sensor: "s".repeat(40), values: vec![1, -2, 300] };
+    let expected = to_vec(&reading).unwrap();
+
+    let mut encoder = Encoder::new(Chunks::default());
+    reading.serialize(&mut Serializer::new(&mut encoder)).unwrap();
+    let chunks = encoder.finish().unwrap();
+    assert_eq!(chunks.0.concat(), expected);
+    // The 40-byte string went out with its length prefix in one write_slices call
+    assert!(chunks.0.iter().any(|chunk| chunk.len() > 40));
+
+    let mut encoder = Encoder::new(Region { data: [0; 16], len: 0 });
+    encoder.write_str("hello").unwrap();
+    let region = encoder.finish().unwrap();
+    assert_eq!(&region.data[..region.len], to_vec(&"hello").unwrap().as_slice());
+
+    let mut encoder = Encoder::new(Region { data: [0; 16], len: 0 });
+    assert!(matches!(encoder.write_str(&"x".repeat(20)), Err(crate::EncodeError::Io(_))));
+}
+
+#[test]
+fn test_custom_read_backend() {
+    let readings: Vec<Reading> = (0..50).map(|i| Reading { sensor: format!("sensor-{i}"), values: vec![i; 10] }).collect();
+    let bytes = to_vec(&readings).unwrap();
+
+    let ring = Ring { queue: bytes.into_iter().collect(), step: 7 };
+    let mut de = ReaderDeserializer::new(ring);
+    let decoded: Vec<Reading> = de.array_elements().collect::<crate::Result<_>>().unwrap();
+    assert_eq!(decoded, readings);
+
+    // io::Read sources still work through the blanket impl
+    let bytes = to_vec(&readings).unwrap();
+    let mut de = ReaderDeserializer::new(bytes.as_slice());
+    assert_eq!(de.array_elements::<Reading>().count(), 50);
+}
@@ -0,0 +1,236 @@
+// ABOUTME: Benchmark corpora modeled on the standard JSON benchmark files.
+// ABOUTME: Loads twitter/canada/citm_catalog from disk when available, otherwise synthesizes look-alikes.
+
+use crate::value::Value;
+use crate::Map;
+use std::path::PathBuf;
+
+/// Environment variable naming a directory that holds the original corpus
+/// files (`twitter.json`, `canada.json`, `citm_catalog.json`).
+pub const CORPUS_DIR_ENV: &str = "BONJSON_BENCH_CORPUS_DIR";
+
+/// One of the standard JSON benchmark corpora.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Corpus {
+    /// Twitter API search results: string-heavy, deeply nested, lots of unicode.
+    Twitter,
+    /// Canada's border as GeoJSON: dominated by arrays of float pairs.
+    Canada,
+    /// A concert-hall event catalog: many same-shaped objects and integer ids.
+    CitmCatalog,
+}
+
+impl Corpus {
+    /// All corpora, in a stable order.
+    pub const ALL: [Corpus; 3] = [Corpus::Twitter, Corpus::Canada, Corpus::CitmCatalog];
+
+    /// A short name suitable for benchmark group ids.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Corpus::Twitter => "twitter",
+            Corpus::Canada => "canada",
+            Corpus::CitmCatalog => "citm_catalog",
+        }
+    }
+
+    /// The file name of the original corpus.
+    #[must_use]
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Corpus::Twitter => "twitter.json",
+            Corpus::Canada => "canada.json",
+            Corpus::CitmCatalog => "citm_catalog.json",
+        }
+    }
+
+    /// Read the original corpus as JSON text from the directory named by
+    /// [`CORPUS_DIR_ENV`].
+    ///
+    /// Returns `None` if the variable isn't set. The text is returned
+    /// unparsed, since parsing JSON is up to the caller.
+    #[must_use]
+    pub fn read_json(self) -> Option<std::io::Result<String>> {
+        let dir = std::env::var_os(CORPUS_DIR_ENV)?;
+        Some(std::fs::read_to_string(PathBuf::from(dir).join(self.file_name())))
+    }
+
+    /// Generate a deterministic document with the same shape and value mix
+    /// as the original corpus, at a comparable (somewhat smaller) size.
+    #[must_use]
+    pub fn synthesize(self) -> Value {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        match self {
+            Corpus::Twitter => twitter(&mut rng),
+            Corpus::Canada => canada(&mut rng),
+            Corpus::CitmCatalog => citm_catalog(&mut rng),
+        }
+    }
+}
+
+/// xorshift64*: deterministic and dependency-free.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: i64) -> i64 {
+        (self.next() % n as u64) as i64
+    }
+
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as i64) as usize]
+    }
+
+    fn words(&mut self, count: usize) -> String {
+        const WORDS: &[&str] = &[
+            "the", "bonjson", "binary", "fast", "data", "東京", "ラーメン", "café", "naïve",
+            "stream", "record", "value", "😀", "encode", "decode", "@user", "#tag", "http://t.co/x",
+        ];
+        (0..count).map(|_| self.pick(WORDS)).collect::<Vec<_>>().join(" ")
+    }
+}
+
+fn object(entries: Vec<(&str, Value)>) -> Value {
+    let map: Map<String, Value> = entries.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    Value::Object(map)
+}
+
+fn twitter(rng: &mut Rng) -> Value {
+    let statuses = (0..100)
+        .map(|i| {
+            let user_id = 1_000_000 + rng.below(1_000_000);
+            let words = 4 + rng.below(16) as usize;
+            let hashtags = (0..rng.below(3))
+                .map(|_| {
+                    let start = rng.below(100);
+                    object(vec![
+                        ("text", Value::from(rng.words(1))),
+                        ("indices", Value::from(vec![start, start + 8])),
+                    ])
+                })
+                .collect::<Vec<_>>();
+            object(vec![
+                ("created_at", Value::from("Sun Aug 31 00:29:15 +0000 2014")),
+                ("id", Value::Int(505_874_924_095_815_681 + i)),
+                ("id_str", Value::from((505_874_924_095_815_681 + i).to_string())),
+                ("text", Value::from(rng.words(words))),
+                ("truncated", Value::Bool(false)),
+                ("in_reply_to_status_id", Value::Null),
+                ("retweet_count", Value::from(rng.below(500))),
+                ("favorite_count", Value::from(rng.below(500))),
+                ("favorited", Value::Bool(rng.below(2) == 0)),
+                ("lang", Value::from(rng.pick(&["ja", "en", "es", "fr"]))),
+                ("entities", object(vec![
+                    ("hashtags", Value::Array(hashtags)),
+                    ("urls", Value::Array(Vec::new())),
+                ])),
+                ("user", object(vec![
+                    ("id", Value::from(user_id)),
+                    ("name", Value::from(rng.words(2))),
+                    ("screen_name", Value::from(format!("user_{user_id}"))),
+                    ("description", Value::from(rng.words(12))),
+                    ("followers_count", Value::from(rng.below(100_000))),
+                    ("friends_count", Value::from(rng.below(5_000))),
+                    ("verified", Value::Bool(false)),
+                    ("profile_background_color", Value::from("C0DEED")),
+                    ("profile_image_url", Value::from(format!("http://pbs.twimg.com/profile_images/{user_id}/a.jpeg"))),
+                ])),
+            ])
+        })
+        .collect::<Vec<_>>();
+    object(vec![
+        ("statuses", Value::Array(statuses)),
+        ("search_metadata", object(vec![
+            ("completed_in", Value::Float(0.087)),
+            ("max_id", Value::Int(505_874_924_095_815_681)),
+            ("query", Value::from("%E4%B8%80")),
+            ("count", Value::from(100)),
+        ])),
+    ])
+}
+
+fn canada(rng: &mut Rng) -> Value {
+    let polygons = (0..40)
+        .map(|_| {
+            let (mut lon, mut lat) = (-65.0 - rng.unit() * 70.0, 43.0 + rng.unit() * 30.0);
+            let ring = (0..500)
+                .map(|_| {
+                    lon += (rng.unit() - 0.5) * 0.01;
+                    lat += (rng.unit() - 0.5) * 0.01;
+                    Value::from(vec![lon, lat])
+                })
+                .collect::<Vec<_>>();
+            Value::Array(vec![Value::Array(ring)])
+        })
+        .collect::<Vec<_>>();
+    object(vec![
+        ("type", Value::from("FeatureCollection")),
+        ("features", Value::Array(vec![object(vec![
+            ("type", Value::from("Feature")),
+            ("properties", object(vec![("name", Value::from("Canada"))])),
+            ("geometry", object(vec![
+                ("type", Value::from("Polygon")),
+                ("coordinates", Value::Array(polygons)),
+            ])),
+        ])])),
+    ])
+}
+
+fn citm_catalog(rng: &mut Rng) -> Value {
+    let events: Map<String, Value> = (0..200i64)
+        .map(|i| {
+            let id = 138_586_341 + i;
+            let event = object(vec![
+                ("id", Value::from(id)),
+                ("name", Value::from(rng.words(3))),
+                ("description", Value::Null),
+                ("logo", Value::from(format!("/images/UE0AAAAACEKo6QAAAA{i}"))),
+                ("subjectCode", Value::Null),
+                ("subtitle", Value::Null),
+                ("subTopicIds", Value::from(vec![337_184_269i64, 337_184_283])),
+                ("topicIds", Value::from(vec![324_846_099i64, 107_888_604])),
+            ]);
+            (id.to_string(), event)
+        })
+        .collect();
+    let performances = (0..400i64)
+        .map(|i| {
+            let prices = (0..3)
+                .map(|_| {
+                    object(vec![
+                        ("amount", Value::from(10_000 + rng.below(90_000))),
+                        ("audienceSubCategoryId", Value::from(337_100_890i64)),
+                        ("seatCategoryId", Value::from(338_937_295 + rng.below(10))),
+                    ])
+                })
+                .collect::<Vec<_>>();
+            object(vec![
+                ("id", Value::from(339_887_544 + i)),
+                ("eventId", Value::from(138_586_341 + i % 200)),
+                ("logo", Value::Null),
+                ("name", Value::Null),
+                ("prices", Value::Array(prices)),
+                ("seatCategories", Value::Array(Vec::new())),
+                ("seatMapImage", Value::Null),
+                ("start", Value::from(1_372_701_600_000 + i * 86_400_000)),
+                ("venueCode", Value::from("PLEYEL_PLEYEL")),
+            ])
+        })
+        .collect::<Vec<_>>();
+    object(vec![
+        ("areaNames", object(vec![("205705993", Value::from("Arrière-scène central"))])),
+        ("events", Value::Object(events)),
+        ("performances", Value::Array(performances)),
+        ("venueNames", object(vec![("PLEYEL_PLEYEL", Value::from("Salle Pleyel"))])),
+    ])
+}
@@ -0,0 +1,34 @@
+// ABOUTME: Unit tests for the benchmark corpus module.
+// ABOUTME: Tests that synthesized corpora are deterministic and round-trip.
+
+use crate::bench_corpus::Corpus;
+use std::cmp::Ordering;
+use crate::{decode_value, decode_with_metadata, encode_value, type_code, Value};
+
+#[test]
+fn test_synthesized_corpora_round_trip() {
+    for corpus in Corpus::ALL {
+        let value = corpus.synthesize();
+        assert_eq!(value, corpus.synthesize(), "{} is not deterministic", corpus.name());
+        let bytes = encode_value(&value).unwrap();
+        // Compare numerically: small positive ints may decode as UInt
+        let decoded = decode_value(&bytes).unwrap();
+        assert_eq!(decoded.total_cmp(&value), Ordering::Equal, "{} did not round-trip", corpus.name());
+    }
+}
+
+#[test]
+fn test_synthesized_corpora_exercise_their_features() {
+    // canada is coordinate pairs, which become float typed arrays
+    let bytes = encode_value(&Corpus::Canada.synthesize()).unwrap();
+    let (_, meta) = decode_with_metadata(&bytes).unwrap();
+    assert!(meta.typed_array_stats(type_code::TYPED_ARRAY_FLOAT64).unwrap().array_count > 1000);
+
+    // citm_catalog repeats object shapes, which become records
+    let bytes = encode_value(&Corpus::CitmCatalog.synthesize()).unwrap();
+    let (_, meta) = decode_with_metadata(&bytes).unwrap();
+    assert!(meta.record_instance_count() > 500);
+
+    let Value::Object(twitter) = Corpus::Twitter.synthesize() else { panic!("expected object") };
+    assert_eq!(twitter["statuses"].as_array().unwrap().len(), 100);
+}
@@ -0,0 +1,35 @@
+// ABOUTME: Command-line runner for the universal BONJSON conformance suite.
+// ABOUTME: Runs a test or config file against serde_bonjson and prints the results as JSON.
+
+//! Usage: `bonjson-conformance <test-or-config.json>`
+//!
+//! Prints a [`Report`](serde_bonjson::conformance::Report) as JSON on
+//! stdout. Exits with 0 if every case passed or was skipped, 1 if any failed,
+//! and 2 if the file couldn't be run (bad arguments or a structural error).
+
+use serde_bonjson::conformance;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args_os().skip(1);
+    let (Some(path), None) = (args.next(), args.next()) else {
+        eprintln!("usage: bonjson-conformance <test-or-config.json>");
+        return ExitCode::from(2);
+    };
+
+    let report = conformance::run(&PathBuf::from(path));
+    println!("{}", report.to_json());
+
+    if let Some(err) = &report.structural_error {
+        eprintln!("error: {err}");
+        ExitCode::from(2)
+    } else if report.failed > 0 {
+        for err in &report.errors {
+            eprintln!("FAIL: {err}");
+        }
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
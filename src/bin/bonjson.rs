@@ -0,0 +1,211 @@
+// ABOUTME: The `bonjson` command-line tool: converts between BONJSON and JSON, and inspects,
+// ABOUTME: validates and summarizes BONJSON documents, all through the library APIs.
+
+//! Usage: `bonjson <command> [options] [FILE]`
+//!
+//! Reads FILE, or stdin if it's omitted or `-`, and writes to stdout:
+//!
+//! - `to-json [--pretty]` - decode a document and print it as JSON
+//! - `from-json` - encode a JSON document as BONJSON
+//! - `inspect` - print the document's values one per line with their offsets
+//! - `validate [--config default|strict|permissive|embedded]` - check a document
+//! - `stats` - print the document's size breakdown and the features it uses
+//!
+//! Exits with 0 on success, 1 if the input couldn't be converted or isn't
+//! valid, and 2 on bad arguments or I/O errors.
+
+use serde_bonjson::{check_compatibility, decode_value, decode_value_with_config, decode_with_metadata, encode_value, tools, type_code, DecoderConfig, Value};
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: bonjson <command> [options] [FILE]
+
+commands:
+  to-json [--pretty]     decode BONJSON and print it as JSON
+  from-json              encode JSON as BONJSON
+  inspect                print each value with its offset and type
+  validate [--config P]  check a document; P is default, strict, permissive or embedded
+  stats                  print the size breakdown and optional features used
+
+FILE defaults to stdin; output goes to stdout.";
+
+/// Why a command failed, and so which exit code it gets.
+enum Failure {
+    /// Bad arguments or an I/O error (exit code 2)
+    Usage(String),
+    /// The input couldn't be decoded, encoded or validated (exit code 1)
+    Invalid(String),
+}
+
+impl From<io::Error> for Failure {
+    fn from(err: io::Error) -> Self {
+        Failure::Usage(err.to_string())
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Invalid(msg)) => {
+            eprintln!("error: {msg}");
+            ExitCode::from(1)
+        }
+        Err(Failure::Usage(msg)) => {
+            eprintln!("error: {msg}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Failure> {
+    let Some((command, rest)) = args.split_first() else {
+        return Err(Failure::Usage("no command given".into()));
+    };
+    let options = Options::parse(rest)?;
+    let allowed: &[&str] = match command.as_str() {
+        "to-json" => &["--pretty"],
+        "validate" => &["--config"],
+        "from-json" | "inspect" | "stats" => &[],
+        other => return Err(Failure::Usage(format!("unknown command {other:?}"))),
+    };
+    options.only(allowed)?;
+    let input = options.read_input()?;
+    let mut out = io::stdout().lock();
+
+    match command.as_str() {
+        "to-json" => {
+            let value = decode_value(&input).map_err(|e| Failure::Invalid(e.to_string()))?;
+            let json = if options.pretty { serde_json::to_string_pretty(&value) } else { serde_json::to_string(&value) };
+            writeln!(out, "{}", json.map_err(|e| Failure::Invalid(e.to_string()))?)?;
+        }
+        "from-json" => {
+            let value: Value = serde_json::from_slice(&input).map_err(|e| Failure::Invalid(format!("invalid JSON: {e}")))?;
+            out.write_all(&encode_value(&value).map_err(|e| Failure::Invalid(e.to_string()))?)?;
+        }
+        "inspect" => {
+            let node = tools::dissect(&input);
+            out.write_all(node.to_text().as_bytes())?;
+            if let Some(error) = node.error() {
+                return Err(Failure::Invalid(describe_error(error)));
+            }
+        }
+        "validate" => match decode_value_with_config(&input, options.config.clone()) {
+            Ok(_) => writeln!(out, "valid ({} bytes)", input.len())?,
+            Err(e) => {
+                // Checks made on the decoded tree (such as duplicate keys) have no offset
+                let node = tools::dissect_with_config(&input, options.config.clone());
+                let at = node.error().map(|error| format!(" at offset {}", error.offset)).unwrap_or_default();
+                return Err(Failure::Invalid(format!("{e}{at}")));
+            }
+        },
+        "stats" => {
+            let (_, meta) = decode_with_metadata(&input).map_err(|e| Failure::Invalid(e.to_string()))?;
+            out.write_all(stats_text(&meta, &check_compatibility(&input).features()).as_bytes())?;
+        }
+        _ => unreachable!("checked above"),
+    }
+    Ok(())
+}
+
+/// The options given after the command.
+struct Options {
+    pretty: bool,
+    config: DecoderConfig,
+    path: Option<String>,
+    /// The flags seen, so commands can reject the ones they don't take.
+    flags: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, Failure> {
+        let mut options = Options { pretty: false, config: DecoderConfig::default(), path: None, flags: Vec::new() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--pretty" => options.pretty = true,
+                "--config" => {
+                    options.config = match args.next().map(String::as_str) {
+                        Some("default") => DecoderConfig::default(),
+                        Some("strict") => DecoderConfig::strict(),
+                        Some("permissive") => DecoderConfig::permissive(),
+                        Some("embedded") => DecoderConfig::embedded(),
+                        Some(other) => return Err(Failure::Usage(format!("unknown config preset {other:?}"))),
+                        None => return Err(Failure::Usage("--config needs a preset name".into())),
+                    };
+                }
+                flag if flag.starts_with("--") => return Err(Failure::Usage(format!("unknown option {flag}"))),
+                path if options.path.is_none() => options.path = Some(path.to_owned()),
+                extra => return Err(Failure::Usage(format!("unexpected argument {extra:?}"))),
+            }
+            if arg.starts_with("--") {
+                options.flags.push(arg.clone());
+            }
+        }
+        Ok(options)
+    }
+
+    /// Fail if a flag outside `allowed` was given.
+    fn only(&self, allowed: &[&str]) -> Result<(), Failure> {
+        match self.flags.iter().find(|flag| !allowed.contains(&flag.as_str())) {
+            Some(flag) => Err(Failure::Usage(format!("{flag} doesn't apply to this command"))),
+            None => Ok(()),
+        }
+    }
+
+    fn read_input(&self) -> Result<Vec<u8>, Failure> {
+        match self.path.as_deref() {
+            None | Some("-") => {
+                let mut input = Vec::new();
+                io::stdin().lock().read_to_end(&mut input)?;
+                Ok(input)
+            }
+            Some(path) => std::fs::read(path).map_err(|e| Failure::Usage(format!("{path}: {e}"))),
+        }
+    }
+}
+
+fn describe_error(error: &tools::DissectNode) -> String {
+    let message = match &error.value {
+        Some(Value::String(msg)) => msg.as_str(),
+        _ => "invalid document",
+    };
+    format!("{message} at offset {}", error.offset)
+}
+
+fn stats_text(meta: &serde_bonjson::DocumentMetadata, features: &[&str]) -> String {
+    let sizes = &meta.sizes;
+    let mut text = String::new();
+    let _ = writeln!(text, "total bytes:        {}", sizes.total);
+    for (name, bytes) in [
+        ("record definitions", sizes.record_definitions),
+        ("containers", sizes.containers),
+        ("strings", sizes.strings),
+        ("numbers", sizes.numbers),
+        ("typed arrays", sizes.typed_arrays),
+        ("literals", sizes.literals),
+        ("trailing", sizes.trailing),
+    ] {
+        let _ = writeln!(text, "  {name:<18}{bytes}");
+    }
+    let _ = writeln!(
+        text,
+        "record definitions: {} ({} instances)",
+        meta.record_definitions.len(),
+        meta.record_instance_count()
+    );
+    for (code, stats) in &meta.typed_arrays {
+        let _ = writeln!(
+            text,
+            "typed arrays {}: {} arrays, {} elements, {} bytes",
+            type_code::name(*code),
+            stats.array_count,
+            stats.element_count,
+            stats.bytes
+        );
+    }
+    let features = if features.is_empty() { "none".to_owned() } else { features.join(", ") };
+    let _ = writeln!(text, "optional features:  {features}");
+    text
+}
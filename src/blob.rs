@@ -0,0 +1,88 @@
+// ABOUTME: Serde `with` module for binary data, written as a uint8 typed array (this crate's blob convention).
+// ABOUTME: Reads blobs back from typed arrays, plain arrays of bytes, or other formats' byte strings.
+
+//! Binary data ("blobs") in BONJSON.
+//!
+//! JSON has no binary type, so BONJSON doesn't either. This crate's
+//! convention is a uint8 typed array: the bytes follow a type code and count
+//! as-is, so encoding is a copy and decoding can borrow them from the input.
+//! In JSON the same value reads as an array of numbers 0-255. Use it through:
+//!
+//! - `#[serde(with = "serde_bonjson::blob")]` on a `Vec<u8>` (or any type
+//!   that converts from one and derefs to bytes)
+//! - [`Value::from_blob`](crate::Value::from_blob) and
+//!   [`Value::as_blob`](crate::Value::as_blob)
+//! - [`Encoder::write_blob`](crate::Encoder::write_blob) and
+//!   [`Encoder::write_blob_from_reader`](crate::Encoder::write_blob_from_reader),
+//!   which streams a blob of known length
+//! - [`Decoder::read_blob`](crate::Decoder::read_blob), which borrows the bytes
+//!
+//! A `&'a [u8]` field marked `#[serde(borrow)]` borrows a blob from the input
+//! without this module.
+//!
+//! # Example
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Attachment {
+//!     name: String,
+//!     #[serde(with = "serde_bonjson::blob")]
+//!     data: Vec<u8>,
+//! }
+//!
+//! let attachment = Attachment { name: "logo.png".into(), data: vec![0x89, b'P', b'N', b'G'] };
+//! let bytes = serde_bonjson::to_vec(&attachment).unwrap();
+//! let value = serde_bonjson::decode_value(&bytes).unwrap();
+//! assert_eq!(value.get_key("data").unwrap().as_blob().unwrap(), attachment.data);
+//! assert_eq!(serde_bonjson::from_slice::<Attachment>(&bytes).unwrap(), attachment);
+//! ```
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserializer, Serializer};
+use std::fmt;
+
+/// Serialize `bytes` as a blob.
+///
+/// # Errors
+///
+/// Returns the serializer's error.
+pub fn serialize<T: AsRef<[u8]> + ?Sized, S: Serializer>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(bytes.as_ref())
+}
+
+/// Deserialize a blob, or an array of numbers 0-255.
+///
+/// # Errors
+///
+/// Fails if the value is neither.
+pub fn deserialize<'de, T: From<Vec<u8>>, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    deserializer.deserialize_bytes(BlobVisitor).map(T::from)
+}
+
+struct BlobVisitor;
+
+impl<'de> Visitor<'de> for BlobVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a blob (uint8 typed array) or an array of bytes")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
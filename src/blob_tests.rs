@@ -0,0 +1,88 @@
+// ABOUTME: Unit tests for the blob module.
+// ABOUTME: Tests the blob convention through serde, Value, the Encoder and the Decoder.
+
+use crate::types::type_code;
+use crate::{decode_value, encode_value, from_slice, to_vec, to_vec_with, CapabilitySet, DecodeError, Decoder, EncodeError, Encoder, EncoderConfig, Value};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Attachment {
+    #[serde(with = "crate::blob")]
+    data: Vec<u8>,
+    #[serde(with = "crate::blob")]
+    thumbnail: Box<[u8]>,
+}
+
+#[test]
+fn test_blob_serde() {
+    let attachment = Attachment { data: (0..=255).collect(), thumbnail: Box::new([1, 2, 3]) };
+    let bytes = to_vec(&attachment).unwrap();
+    let value = decode_value(&bytes).unwrap();
+    assert_eq!(value.get_key("data"), Some(&Value::from_blob(&attachment.data)));
+    assert_eq!(from_slice::<Attachment>(&bytes).unwrap(), attachment);
+    let header = [type_code::TYPED_ARRAY_UINT8, 0x80, 0x02];
+    assert!(bytes.windows(3).any(|w| w == header), "data wasn't a uint8 typed array");
+
+    // Without typed arrays the blob is a plain array, which still reads back
+    let bytes = to_vec_with(&attachment, |cfg| cfg.capabilities(CapabilitySet::CORE)).unwrap();
+    assert!(!bytes.windows(3).any(|w| w == header));
+    assert_eq!(from_slice::<Attachment>(&bytes).unwrap(), attachment);
+
+    #[derive(Deserialize)]
+    struct Borrowed<'a> {
+        #[serde(borrow)]
+        data: &'a [u8],
+    }
+    let bytes = to_vec(&attachment).unwrap();
+    let borrowed: Borrowed<'_> = from_slice(&bytes).unwrap();
+    assert_eq!(borrowed.data, attachment.data.as_slice());
+    assert!(bytes.as_ptr_range().contains(&borrowed.data.as_ptr()));
+}
+
+#[test]
+fn test_blob_value() {
+    let blob = Value::from_blob(b"\x00\x01\xff");
+    let bytes = encode_value(&blob).unwrap();
+    assert_eq!(bytes, [type_code::TYPED_ARRAY_UINT8, 3, 0x00, 0x01, 0xff]);
+    assert_eq!(decode_value(&bytes).unwrap(), blob);
+    assert_eq!(blob.as_blob(), Some(vec![0, 1, 255]));
+
+    assert_eq!(crate::bonjson!([1, 2]).as_blob(), Some(vec![1, 2]));
+    assert_eq!(crate::bonjson!([1, 256]).as_blob(), None);
+    assert_eq!(crate::bonjson!([1, (-1)]).as_blob(), None);
+    assert_eq!(crate::bonjson!("AAEC").as_blob(), None);
+}
+
+#[test]
+fn test_blob_encoder_and_decoder() {
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let mut encoder = Encoder::new(Vec::new());
+    encoder.write_blob(&data).unwrap();
+    let expected = encoder.finish().unwrap();
+
+    let mut encoder = Encoder::new(Vec::new());
+    encoder.write_blob_from_reader(data.as_slice(), data.len() as u64).unwrap();
+    let streamed = encoder.finish().unwrap();
+    assert_eq!(streamed, expected);
+
+    let mut decoder = Decoder::new(&streamed);
+    let blob = decoder.read_blob().unwrap();
+    assert_eq!(blob, data.as_slice());
+    assert!(streamed.as_ptr_range().contains(&blob.as_ptr()));
+
+    let mut encoder = Encoder::new(Vec::new());
+    assert!(matches!(encoder.write_blob_from_reader(&data[..10], 11), Err(EncodeError::InvalidData(_))));
+    let mut encoder = Encoder::new(Vec::new());
+    encoder.begin_array().unwrap();
+    encoder.write_blob_from_reader(&data[..], 2).unwrap();
+    encoder.write_blob_from_reader(&[][..], 0).unwrap();
+    encoder.end_container().unwrap();
+    assert_eq!(decode_value(&encoder.finish().unwrap()).unwrap(), Value::Array(vec![Value::from_blob(&[0, 1]), Value::from_blob(&[])]));
+
+    let config = EncoderConfig { capabilities: CapabilitySet::CORE, ..Default::default() };
+    let mut encoder = Encoder::with_config(Vec::new(), config);
+    assert_eq!(encoder.write_blob_from_reader(&data[..], 1), Err(EncodeError::UnsupportedCapability("typed_arrays")));
+
+    let ints = to_vec(&[1u16, 2]).unwrap();
+    assert!(matches!(Decoder::new(&ints).read_blob(), Err(DecodeError::UnexpectedType { .. })));
+}
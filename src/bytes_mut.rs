@@ -0,0 +1,87 @@
+// ABOUTME: Encoding into a bytes::BytesMut (`bytes` feature).
+// ABOUTME: BytesMutWriter appends to a BytesMut, reserving once for each vectored write.
+
+use crate::encoder::EncoderConfig;
+use crate::error::Result;
+use crate::ser::SerializerConfig;
+use crate::value::Value;
+use serde::Serialize;
+use std::io::{IoSlice, Write};
+
+pub use bytes::BytesMut;
+
+/// A [`Write`] adapter that appends to a [`BytesMut`].
+///
+/// Unlike `bytes::BufMut::writer()`, it takes all the slices of a vectored
+/// write at once, so the encoder's type code and large payload (long strings,
+/// typed arrays) are appended after a single reservation.
+pub struct BytesMutWriter<'a> {
+    buf: &'a mut BytesMut,
+}
+
+impl<'a> BytesMutWriter<'a> {
+    /// Append to `buf`.
+    pub fn new(buf: &'a mut BytesMut) -> Self {
+        Self { buf }
+    }
+}
+
+impl Write for BytesMutWriter<'_> {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> std::io::Result<usize> {
+        let len = bufs.iter().map(|buf| buf.len()).sum();
+        self.buf.reserve(len);
+        for buf in bufs {
+            self.buf.extend_from_slice(buf);
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serialize a value into a new `BytesMut`.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::bytes_mut::to_bytes;
+///
+/// let bytes = to_bytes(&vec!["a", "b"]).unwrap();
+/// assert_eq!(serde_bonjson::from_slice::<Vec<String>>(&bytes).unwrap(), ["a", "b"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if serialization fails (e.g., NaN/infinity floats).
+pub fn to_bytes<T: Serialize>(value: &T) -> Result<BytesMut> {
+    to_bytes_with_config(value, &SerializerConfig::default())
+}
+
+/// Serialize a value into a new `BytesMut` with custom configuration.
+///
+/// # Errors
+///
+/// Returns an error if serialization fails (e.g., NaN/infinity floats).
+pub fn to_bytes_with_config<T: Serialize>(value: &T, config: &SerializerConfig) -> Result<BytesMut> {
+    let mut buf = BytesMut::with_capacity(128);
+    crate::to_writer_with_config(BytesMutWriter::new(&mut buf), value, config)?;
+    Ok(buf)
+}
+
+/// Encode a `Value` into a new `BytesMut`.
+///
+/// # Errors
+///
+/// Returns an error if encoding fails (e.g., NaN/infinity floats in the value).
+pub fn encode_value_to_bytes(value: &Value, config: EncoderConfig) -> Result<BytesMut> {
+    let mut buf = BytesMut::new();
+    crate::encode_value_to_writer_with_config(BytesMutWriter::new(&mut buf), value, config)?;
+    Ok(buf)
+}
@@ -0,0 +1,33 @@
+// ABOUTME: Unit tests for the bytes_mut module (bytes feature).
+// ABOUTME: Tests that BytesMut output matches Vec output, including vectored long strings and typed arrays.
+
+use crate::bytes_mut::{encode_value_to_bytes, to_bytes, to_bytes_with_config, BytesMut, BytesMutWriter};
+use crate::{bonjson, encode_value, to_vec, to_vec_with_config, EncoderConfig, SerializerConfig, TypedArrayStrategy};
+use std::io::Write;
+
+#[test]
+fn test_bytes_output_matches_vec() {
+    let data = (
+        "s".repeat(100_000),
+        vec![0.5f64; 1000],
+        vec!["short"; 3],
+    );
+    assert_eq!(to_bytes(&data).unwrap(), to_vec(&data).unwrap());
+
+    let config = SerializerConfig { typed_array_strategy: TypedArrayStrategy::Always, ..Default::default() };
+    assert_eq!(to_bytes_with_config(&data, &config).unwrap(), to_vec_with_config(&data, &config).unwrap());
+
+    let text = "t".repeat(500);
+    let value = bonjson!({"text": text, "list": [1, 2.5, null]});
+    assert_eq!(encode_value_to_bytes(&value, EncoderConfig::default()).unwrap(), encode_value(&value).unwrap());
+}
+
+#[test]
+fn test_writer_appends() {
+    let mut buf = BytesMut::from(&b"head"[..]);
+    let mut writer = BytesMutWriter::new(&mut buf);
+    writer.write_all(b"-").unwrap();
+    crate::to_writer(writer, &"x".repeat(70)).unwrap();
+    assert_eq!(&buf[..5], b"head-");
+    assert_eq!(buf.len(), 5 + 72);
+}
@@ -0,0 +1,124 @@
+// ABOUTME: Checksum trailers that let decoders detect corrupted documents up front.
+// ABOUTME: A CRC-32 of the document follows it, behind a four-byte tag.
+
+use crate::error::{Error, Result};
+use std::io::{self, Write};
+
+/// Tags the trailer, so that a document without one is reported as such
+/// rather than as a checksum mismatch.
+pub const TRAILER_TAG: [u8; 4] = *b"BJCK";
+
+/// The tag plus the CRC-32 (little-endian) of the document in front of it.
+pub const TRAILER_SIZE: usize = 8;
+
+/// Lookup table for the reflected IEEE polynomial (as used by zlib, PNG and Ethernet).
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// The CRC-32 (IEEE) of `data`.
+#[must_use]
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
+
+/// A streaming CRC-32 (IEEE).
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Crc32(u32);
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Crc32(!0)
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        let mut crc = self.0;
+        for &byte in data {
+            crc = CRC_TABLE[((crc ^ u32::from(byte)) & 0xFF) as usize] ^ (crc >> 8);
+        }
+        self.0 = crc;
+    }
+
+    pub(crate) fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+/// The trailer for a document whose CRC-32 is `crc`.
+fn trailer(crc: u32) -> [u8; TRAILER_SIZE] {
+    let mut trailer = [0u8; TRAILER_SIZE];
+    trailer[..4].copy_from_slice(&TRAILER_TAG);
+    trailer[4..].copy_from_slice(&crc.to_le_bytes());
+    trailer
+}
+
+/// Append a checksum trailer covering everything in `buf`.
+pub fn append_trailer(buf: &mut Vec<u8>) {
+    let trailer = trailer(crc32(buf));
+    buf.extend_from_slice(&trailer);
+}
+
+/// Check the checksum trailer at the end of `data`, returning the document in front of it.
+///
+/// # Errors
+///
+/// Returns `Error::MissingChecksum` if `data` doesn't end with a trailer, or
+/// `Error::ChecksumMismatch` if the document doesn't match it.
+pub fn verify_trailer(data: &[u8]) -> Result<&[u8]> {
+    let split = data.len().checked_sub(TRAILER_SIZE).ok_or(Error::MissingChecksum)?;
+    let (document, trailer) = data.split_at(split);
+    if trailer[..4] != TRAILER_TAG {
+        return Err(Error::MissingChecksum);
+    }
+    let expected = u32::from_le_bytes(trailer[4..].try_into().unwrap());
+    let actual = crc32(document);
+    if actual != expected {
+        return Err(Error::ChecksumMismatch { expected, actual });
+    }
+    Ok(document)
+}
+
+/// Passes writes through while computing their CRC-32, for appending a
+/// trailer to output that isn't kept in memory.
+pub(crate) struct ChecksumWriter<W> {
+    inner: W,
+    crc: Crc32,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        ChecksumWriter { inner, crc: Crc32::new() }
+    }
+
+    /// Write the trailer covering everything written so far.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(&trailer(self.crc.finish()))?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.crc.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
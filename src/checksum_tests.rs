@@ -0,0 +1,65 @@
+// ABOUTME: Unit tests for the checksum module.
+// ABOUTME: Tests CRC-32 vectors, trailers from every encode path, and verified decoding.
+
+use crate::checksum::{append_trailer, crc32, verify_trailer, TRAILER_SIZE};
+use crate::{
+    bonjson, decode_value_verified, decode_value_verified_with_config, encode_value, encode_value_to_writer_with_config,
+    encode_value_with_config, to_vec_with_config, to_writer_with_config, DecoderConfig, EncoderConfig, Error,
+    SerializerConfig,
+};
+
+#[test]
+fn test_crc32_vectors() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(b"The quick brown fox jumps over the lazy dog"), 0x414F_A339);
+}
+
+#[test]
+fn test_trailers_match_across_encoders() {
+    let value = bonjson!({"id": 7, "samples": [1.5, 2.5], "name": "sensor"});
+    let plain = encode_value(&value).unwrap();
+    let mut expected = plain.clone();
+    append_trailer(&mut expected);
+    assert_eq!(expected.len(), plain.len() + TRAILER_SIZE);
+    assert_eq!(verify_trailer(&expected).unwrap(), plain.as_slice());
+
+    let config = EncoderConfig { checksum_trailer: true, ..Default::default() };
+    assert_eq!(encode_value_with_config(&value, config.clone()).unwrap(), expected);
+    let mut written = Vec::new();
+    encode_value_to_writer_with_config(&mut written, &value, config).unwrap();
+    assert_eq!(written, expected);
+
+    let config = SerializerConfig { checksum_trailer: true, ..Default::default() };
+    let serialized = to_vec_with_config(&vec![1u32, 2, 3], &config).unwrap();
+    let mut written = Vec::new();
+    to_writer_with_config(&mut written, &vec![1u32, 2, 3], &config).unwrap();
+    assert_eq!(written, serialized);
+    let document = verify_trailer(&serialized).unwrap();
+    assert_eq!(crate::from_slice::<Vec<u32>>(document).unwrap(), [1, 2, 3]);
+}
+
+#[test]
+fn test_verified_decode() {
+    let value = bonjson!({"reading": [20, 21, 22], "unit": "C"});
+    let config = EncoderConfig { checksum_trailer: true, ..Default::default() };
+    let bytes = encode_value_with_config(&value, config).unwrap();
+    assert_eq!(decode_value_verified(&bytes).unwrap(), value);
+
+    // Every single-bit flip is caught before decoding
+    for i in 0..bytes.len() * 8 {
+        let mut damaged = bytes.clone();
+        damaged[i / 8] ^= 1 << (i % 8);
+        let err = decode_value_verified(&damaged).unwrap_err();
+        assert!(matches!(err, Error::ChecksumMismatch { .. } | Error::MissingChecksum), "bit {i}: {err:?}");
+    }
+
+    let plain = encode_value(&value).unwrap();
+    assert_eq!(decode_value_verified(&plain).unwrap_err(), Error::MissingChecksum);
+    assert_eq!(decode_value_verified(&[0x01]).unwrap_err(), Error::MissingChecksum);
+    // Without verification, the trailer is just trailing bytes
+    assert!(matches!(crate::decode_value(&bytes), Err(Error::TrailingBytes(_))));
+
+    let limited = DecoderConfig { max_document_size: 4, ..Default::default() };
+    assert_eq!(decode_value_verified_with_config(&bytes, limited).unwrap_err(), Error::MaxDocumentSizeExceeded);
+}
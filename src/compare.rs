@@ -0,0 +1,250 @@
+// ABOUTME: Configurable Value comparison for tests: float epsilon, NaN and signed zero handling,
+// ABOUTME: and equality across number types, with the path of the first difference and assert_bonjson_eq!.
+
+use crate::jsonpath::JsonPath;
+use crate::types::BigNumber;
+use crate::value::Value;
+use std::fmt;
+
+/// How [`values_equal`] and [`assert_bonjson_eq!`](crate::assert_bonjson_eq)
+/// compare values.
+///
+/// The default is exact, except that a number's representation doesn't
+/// matter (`Int(5)`, `UInt(5)`, `Float(5.0)` and a BigNumber of 5 are all
+/// equal) and NaN equals NaN, as a round-trip test wants.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueCompareOptions {
+    /// Floats whose difference is at most this are equal (default: 0.0, exact).
+    /// Also applies between a float and an integer or BigNumber.
+    pub float_epsilon: f64,
+    /// `Int` and `UInt` holding the same number are equal (default: true)
+    pub int_uint_equal: bool,
+    /// Integers, floats and BigNumbers holding the same number are equal
+    /// (default: true). Integers compare with floats exactly, without rounding
+    /// the integer to `f64` first.
+    pub mixed_numbers_equal: bool,
+    /// NaN equals NaN (default: true)
+    pub nan_equal: bool,
+    /// 0.0 equals -0.0 (default: false)
+    pub signed_zeros_equal: bool,
+}
+
+impl Default for ValueCompareOptions {
+    fn default() -> Self {
+        Self {
+            float_epsilon: 0.0,
+            int_uint_equal: true,
+            mixed_numbers_equal: true,
+            nan_equal: true,
+            signed_zeros_equal: false,
+        }
+    }
+}
+
+impl ValueCompareOptions {
+    config_setters! {
+        float_epsilon: f64,
+        int_uint_equal: bool,
+        mixed_numbers_equal: bool,
+        nan_equal: bool,
+        signed_zeros_equal: bool,
+    }
+}
+
+/// Where two values first differ, from [`find_difference`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference<'a> {
+    /// The path of the differing values within both documents.
+    pub path: JsonPath,
+    /// The left value there, or None if only the right has a member or element there.
+    pub left: Option<&'a Value>,
+    /// The right value there, or None if only the left has a member or element there.
+    pub right: Option<&'a Value>,
+}
+
+impl fmt::Display for Difference<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |value: Option<&Value>| value.map_or_else(|| "(missing)".to_owned(), |v| format!("{v:?}"));
+        write!(f, "values differ at {}\n  left: {}\n right: {}", self.path, side(self.left), side(self.right))
+    }
+}
+
+/// Compare two values under `options`.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::compare::{values_equal, ValueCompareOptions};
+/// use serde_bonjson::bonjson;
+///
+/// let options = ValueCompareOptions::default().float_epsilon(1e-9);
+/// assert!(values_equal(&bonjson!({"x": 0.1, "n": 5}), &bonjson!({"x": (0.1 + 1e-12), "n": 5.0}), &options));
+/// ```
+#[must_use]
+pub fn values_equal(a: &Value, b: &Value, options: &ValueCompareOptions) -> bool {
+    find_difference(a, b, options).is_none()
+}
+
+/// The first place, depth first, where two values differ under `options`.
+#[must_use]
+pub fn find_difference<'a>(a: &'a Value, b: &'a Value, options: &ValueCompareOptions) -> Option<Difference<'a>> {
+    let mut path = JsonPath::root();
+    difference_at(a, b, options, &mut path)
+}
+
+fn difference_at<'a>(a: &'a Value, b: &'a Value, options: &ValueCompareOptions, path: &mut JsonPath) -> Option<Difference<'a>> {
+    let here = |path: &JsonPath, left, right| Some(Difference { path: path.clone(), left, right });
+    match (a, b) {
+        (Value::Array(left), Value::Array(right)) => {
+            for i in 0..left.len().max(right.len()) {
+                path.push_index(i);
+                let found = match (left.get(i), right.get(i)) {
+                    (Some(l), Some(r)) => difference_at(l, r, options, path),
+                    (l, r) => here(path, l, r),
+                };
+                path.pop();
+                if found.is_some() {
+                    return found;
+                }
+            }
+            None
+        }
+        (Value::Object(left), Value::Object(right)) => {
+            // Every key either side has, in order
+            let mut keys: Vec<&String> = left.keys().chain(right.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                path.push_key(key.as_str());
+                let found = match (left.get(key), right.get(key)) {
+                    (Some(l), Some(r)) => difference_at(l, r, options, path),
+                    (l, r) => here(path, l, r),
+                };
+                path.pop();
+                if found.is_some() {
+                    return found;
+                }
+            }
+            None
+        }
+        _ if scalars_equal(a, b, options) => None,
+        _ => here(path, Some(a), Some(b)),
+    }
+}
+
+/// A number in one of the forms the comparison rules distinguish.
+enum Number {
+    Int(i128),
+    Float(f64),
+    Big(BigNumber),
+}
+
+fn number(value: &Value) -> Option<Number> {
+    match *value {
+        Value::Int(n) => Some(Number::Int(i128::from(n))),
+        Value::UInt(n) => Some(Number::Int(i128::from(n))),
+        Value::Float(f) | Value::SizedFloat(f, _) => Some(Number::Float(f)),
+        Value::BigNumber(bn) => Some(Number::Big(bn)),
+        _ => None,
+    }
+}
+
+fn scalars_equal(a: &Value, b: &Value, options: &ValueCompareOptions) -> bool {
+    match (a, b) {
+        (Value::Null, Value::Null) => true,
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        (Value::String(a), Value::String(b)) => a == b,
+        (Value::Int(_), Value::UInt(_)) | (Value::UInt(_), Value::Int(_)) if !options.int_uint_equal => false,
+        _ => match (number(a), number(b)) {
+            (Some(x), Some(y)) => numbers_equal(&x, &y, options),
+            _ => false,
+        },
+    }
+}
+
+fn numbers_equal(a: &Number, b: &Number, options: &ValueCompareOptions) -> bool {
+    match (a, b) {
+        (Number::Int(x), Number::Int(y)) => x == y,
+        (Number::Float(x), Number::Float(y)) => floats_equal(*x, *y, options),
+        (Number::Big(x), Number::Big(y)) => normalize_bignumber(x) == normalize_bignumber(y),
+        _ if !options.mixed_numbers_equal => false,
+        (Number::Int(i), Number::Float(f)) | (Number::Float(f), Number::Int(i)) => {
+            if options.float_epsilon > 0.0 {
+                #[allow(clippy::cast_precision_loss)]
+                let rounded = *i as f64;
+                floats_equal(*f, rounded, options)
+            } else {
+                float_equals_int(*f, *i)
+            }
+        }
+        (Number::Big(bn), Number::Int(i)) | (Number::Int(i), Number::Big(bn)) => {
+            let (sign, significand, exponent) = normalize_bignumber(bn);
+            BigNumber::new(sign, significand, exponent).to_i128() == Some(*i)
+        }
+        (Number::Big(bn), Number::Float(f)) | (Number::Float(f), Number::Big(bn)) => floats_equal(bn.to_f64(), *f, options),
+    }
+}
+
+fn floats_equal(a: f64, b: f64, options: &ValueCompareOptions) -> bool {
+    if a.is_nan() || b.is_nan() {
+        return options.nan_equal && a.is_nan() && b.is_nan();
+    }
+    if a == 0.0 && b == 0.0 {
+        return options.signed_zeros_equal || a.is_sign_negative() == b.is_sign_negative();
+    }
+    a == b || (a - b).abs() <= options.float_epsilon
+}
+
+/// Check whether a float holds exactly the given integer value.
+fn float_equals_int(f: f64, i: i128) -> bool {
+    // Every i64/u64 lies within (-2^64, 2^64), where f64 -> i128 is exact
+    // for whole numbers.
+    #[allow(clippy::cast_possible_truncation)]
+    let whole = f as i128;
+    f.fract() == 0.0 && f.abs() < 18_446_744_073_709_551_616.0 && whole == i
+}
+
+/// The sign, significand and exponent with trailing decimal zeros moved into
+/// the exponent, so that equal BigNumbers compare equal; every zero is `(1, 0, 0)`.
+fn normalize_bignumber(bn: &BigNumber) -> (i8, u64, i64) {
+    if bn.significand == 0 {
+        return (1, 0, 0);
+    }
+    let (mut significand, mut exponent) = (bn.significand, bn.exponent);
+    while significand % 10 == 0 {
+        significand /= 10;
+        exponent += 1;
+    }
+    (bn.sign, significand, exponent)
+}
+
+/// Assert that two [`Value`]s are equal under [`ValueCompareOptions`] (the
+/// defaults, or the options given as a third argument), panicking with the
+/// path and values of the first difference otherwise.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::compare::ValueCompareOptions;
+/// use serde_bonjson::{assert_bonjson_eq, bonjson, decode_value, encode_value};
+///
+/// let value = bonjson!({"id": 7, "ratio": 0.25, "tags": ["a", "b"]});
+/// let decoded = decode_value(&encode_value(&value).unwrap()).unwrap();
+/// assert_bonjson_eq!(decoded, value);
+/// assert_bonjson_eq!(bonjson!([1.0]), bonjson!([1.001]), &ValueCompareOptions::default().float_epsilon(0.01));
+/// ```
+#[macro_export]
+macro_rules! assert_bonjson_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_bonjson_eq!($left, $right, &$crate::compare::ValueCompareOptions::default())
+    };
+    ($left:expr, $right:expr, $options:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if let Some(difference) = $crate::compare::find_difference(left, right, $options) {
+                    panic!("assertion `left == right` failed: {difference}");
+                }
+            }
+        }
+    };
+}
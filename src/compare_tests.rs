@@ -0,0 +1,65 @@
+// ABOUTME: Unit tests for the compare module.
+// ABOUTME: Tests each ValueCompareOptions rule, difference paths, and assert_bonjson_eq!.
+
+use crate::compare::{find_difference, values_equal, ValueCompareOptions};
+use crate::jsonpath::JsonPath;
+use crate::types::BigNumber;
+use crate::{assert_bonjson_eq, bonjson, Value};
+
+#[test]
+fn test_compare_numbers() {
+    let exact = ValueCompareOptions::default();
+    assert!(values_equal(&Value::Int(5), &Value::UInt(5), &exact));
+    assert!(values_equal(&Value::Int(5), &Value::Float(5.0), &exact));
+    assert!(values_equal(&Value::BigNumber(BigNumber::new(1, 50, -1)), &Value::Int(5), &exact));
+    assert!(values_equal(&Value::BigNumber(BigNumber::new(1, 50, -1)), &Value::BigNumber(BigNumber::new(1, 5, 0)), &exact));
+    assert!(values_equal(&Value::Float(f64::NAN), &Value::Float(f64::NAN), &exact));
+    assert!(!values_equal(&Value::Float(0.0), &Value::Float(-0.0), &exact));
+    assert!(!values_equal(&Value::Float(0.1), &Value::Float(0.1 + 1e-12), &exact));
+    // Exact, where rounding 2^53 + 1 to f64 would call these equal
+    assert!(!values_equal(&Value::Int((1 << 53) + 1), &Value::Float(9_007_199_254_740_992.0), &exact));
+    assert!(!values_equal(&Value::Int(-1), &Value::UInt(u64::MAX), &exact));
+    assert!(!values_equal(&Value::Int(1), &Value::Bool(true), &exact));
+
+    let strict = ValueCompareOptions::default().int_uint_equal(false).mixed_numbers_equal(false).nan_equal(false);
+    assert!(!values_equal(&Value::Int(5), &Value::UInt(5), &strict));
+    assert!(!values_equal(&Value::Int(5), &Value::Float(5.0), &strict));
+    assert!(!values_equal(&Value::Float(f64::NAN), &Value::Float(f64::NAN), &strict));
+    assert!(values_equal(&Value::UInt(5), &Value::UInt(5), &strict));
+
+    let loose = ValueCompareOptions::default().float_epsilon(1e-9).signed_zeros_equal(true);
+    assert!(values_equal(&Value::Float(0.0), &Value::Float(-0.0), &loose));
+    assert!(values_equal(&Value::Float(0.1), &Value::Float(0.1 + 1e-12), &loose));
+    assert!(values_equal(&Value::Int(3), &Value::Float(3.000_000_000_1), &loose));
+    assert!(!values_equal(&Value::Float(0.1), &Value::Float(0.2), &loose));
+}
+
+#[test]
+fn test_find_difference() {
+    let options = ValueCompareOptions::default();
+    let left = bonjson!({"a": [1, 2, {"b": "x"}], "c": true});
+    assert_eq!(find_difference(&left, &left.clone(), &options), None);
+
+    let right = bonjson!({"a": [1, 2, {"b": "y"}], "c": true});
+    let difference = find_difference(&left, &right, &options).unwrap();
+    assert_eq!(difference.path, "$.a[2].b".parse::<JsonPath>().unwrap());
+    assert_eq!(difference.left, Some(&bonjson!("x")));
+    assert_eq!(difference.to_string(), "values differ at $.a[2].b\n  left: String(\"x\")\n right: String(\"y\")");
+
+    let shorter = bonjson!({"a": [1, 2], "c": true});
+    let difference = find_difference(&left, &shorter, &options).unwrap();
+    assert_eq!((difference.path.to_string(), difference.right), ("$.a[2]".to_owned(), None));
+    let extra = bonjson!({"a": [1, 2, {"b": "x"}], "b": null, "c": true});
+    let difference = find_difference(&left, &extra, &options).unwrap();
+    assert_eq!((difference.path.to_string(), difference.left), ("$.b".to_owned(), None));
+}
+
+#[test]
+fn test_assert_bonjson_eq() {
+    assert_bonjson_eq!(bonjson!([1, 2.5]), bonjson!([1.0, 2.5]));
+    assert_bonjson_eq!(&bonjson!([0.3]), &bonjson!([(0.1 + 0.2)]), &ValueCompareOptions::default().float_epsilon(1e-12));
+
+    let panic = std::panic::catch_unwind(|| assert_bonjson_eq!(bonjson!({"k": [1]}), bonjson!({"k": [2]}))).unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(message.contains("values differ at $.k[0]\n  left: Int(1)\n right: Int(2)"), "{message}");
+}
@@ -0,0 +1,340 @@
+// ABOUTME: Wire-compatibility helpers: the implemented spec version and build info, pinned test vectors,
+// ABOUTME: and a scan that reports which optional format features a document uses.
+
+use crate::decoder::{DecodedValue, Decoder, DecoderConfig, NanInfinityMode};
+use crate::error::Error;
+use crate::types::{type_code, BigNumber};
+use crate::value::Value;
+use crate::bonjson;
+
+/// The revision of the BONJSON specification this crate implements.
+pub const SPEC_VERSION: &str = "1.0.0";
+
+/// Returns the revision of the BONJSON specification this crate implements.
+#[must_use]
+pub fn spec_version() -> &'static str {
+    SPEC_VERSION
+}
+
+/// The Cargo features this build was compiled with.
+const FEATURES: &[&str] = &[
+    #[cfg(feature = "simd-utf8")]
+    "simd-utf8",
+    #[cfg(feature = "unicode-normalization")]
+    "unicode-normalization",
+    #[cfg(feature = "half")]
+    "half",
+    #[cfg(feature = "tracing")]
+    "tracing",
+    #[cfg(feature = "arena")]
+    "arena",
+    #[cfg(feature = "bytes")]
+    "bytes",
+    #[cfg(feature = "conformance")]
+    "conformance",
+    #[cfg(feature = "arbitrary")]
+    "arbitrary",
+    #[cfg(feature = "rc")]
+    "rc",
+    #[cfg(feature = "rayon")]
+    "rayon",
+];
+
+/// What this build of the crate is, for logging at startup: see [`build_info`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BuildInfo {
+    /// The crate version.
+    pub crate_version: &'static str,
+    /// The BONJSON specification revision implemented, as [`spec_version`].
+    pub spec_version: &'static str,
+    /// The optional Cargo features compiled in, e.g. `"simd-utf8"`.
+    pub features: &'static [&'static str],
+}
+
+impl BuildInfo {
+    /// Returns true if the named Cargo feature is compiled in.
+    #[must_use]
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.features.contains(&name)
+    }
+}
+
+/// One line, e.g. `serde_bonjson 0.1.0 (BONJSON 1.0.0; features: half, simd-utf8)`.
+impl std::fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "serde_bonjson {} (BONJSON {}; features: ", self.crate_version, self.spec_version)?;
+        if self.features.is_empty() {
+            write!(f, "none)")
+        } else {
+            write!(f, "{})", self.features.join(", "))
+        }
+    }
+}
+
+/// Describe this build: crate version, spec revision, and which optional
+/// features are compiled in, since those change behavior (e.g. NFC
+/// normalization is a no-op without `unicode-normalization`).
+#[must_use]
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        crate_version: env!("CARGO_PKG_VERSION"),
+        spec_version: SPEC_VERSION,
+        features: FEATURES,
+    }
+}
+
+/// The optional format features a document uses.
+///
+/// A decoder that predates a feature can still read documents that don't use it,
+/// so this answers "can every decoder in the fleet read this payload?".
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormatReport {
+    /// Record definitions and record instances (`0xB9` / `0xBA`).
+    pub records: bool,
+    /// Typed arrays (`0xF5`-`0xFE`).
+    pub typed_arrays: bool,
+    /// BigNumbers (`0xB2`).
+    pub big_numbers: bool,
+    /// Long strings (`0xFF`-terminated), which replaced the older chunked strings.
+    pub long_strings: bool,
+    /// Set if the document is malformed. The flags then cover only the bytes
+    /// before the error.
+    pub error: Option<Error>,
+}
+
+impl FormatReport {
+    /// Returns true if the document uses none of the optional features.
+    #[must_use]
+    pub fn is_core_only(&self) -> bool {
+        self.features().is_empty()
+    }
+
+    /// Returns the names of the optional features the document uses.
+    #[must_use]
+    pub fn features(&self) -> Vec<&'static str> {
+        [
+            (self.records, "records"),
+            (self.typed_arrays, "typed_arrays"),
+            (self.big_numbers, "big_numbers"),
+            (self.long_strings, "long_strings"),
+        ]
+        .into_iter()
+        .filter_map(|(used, name)| used.then_some(name))
+        .collect()
+    }
+}
+
+/// A set of optional format features that a decoder supports or an encoder may use.
+///
+/// Encoders restricted to a set (via [`EncoderConfig::target_capabilities`](crate::EncoderConfig::target_capabilities)
+/// or [`SerializerConfig::capabilities`](crate::SerializerConfig::capabilities)) never emit
+/// features outside it, so their output is readable by the oldest decoder in the fleet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapabilitySet {
+    /// Record definitions and record instances.
+    pub records: bool,
+    /// Typed arrays.
+    pub typed_arrays: bool,
+    /// BigNumbers.
+    pub big_numbers: bool,
+    /// Long strings (more than 66 bytes).
+    pub long_strings: bool,
+}
+
+impl CapabilitySet {
+    /// Every feature this crate implements.
+    pub const ALL: CapabilitySet =
+        CapabilitySet { records: true, typed_arrays: true, big_numbers: true, long_strings: true };
+
+    /// No optional features: readable by any BONJSON decoder.
+    pub const CORE: CapabilitySet =
+        CapabilitySet { records: false, typed_arrays: false, big_numbers: false, long_strings: false };
+
+    /// The features a decoder needs in order to read `data`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the decode error if the document is malformed.
+    pub fn detect(data: &[u8]) -> crate::Result<CapabilitySet> {
+        let report = check_compatibility(data);
+        match report.error {
+            Some(e) => Err(e),
+            None => Ok(CapabilitySet::from(&report)),
+        }
+    }
+
+    /// The features in both sets.
+    #[must_use]
+    pub fn intersection(self, other: CapabilitySet) -> CapabilitySet {
+        CapabilitySet {
+            records: self.records && other.records,
+            typed_arrays: self.typed_arrays && other.typed_arrays,
+            big_numbers: self.big_numbers && other.big_numbers,
+            long_strings: self.long_strings && other.long_strings,
+        }
+    }
+
+    /// Returns true if every feature in `other` is also in this set.
+    #[must_use]
+    pub fn contains(self, other: CapabilitySet) -> bool {
+        self.intersection(other) == other
+    }
+}
+
+impl Default for CapabilitySet {
+    fn default() -> Self {
+        CapabilitySet::ALL
+    }
+}
+
+impl From<&FormatReport> for CapabilitySet {
+    fn from(report: &FormatReport) -> Self {
+        CapabilitySet {
+            records: report.records,
+            typed_arrays: report.typed_arrays,
+            big_numbers: report.big_numbers,
+            long_strings: report.long_strings,
+        }
+    }
+}
+
+/// Scan a document and report which optional format features it uses.
+///
+/// Content policies (NUL characters, NaN/Infinity) are relaxed for the scan,
+/// since they don't affect which decoders can parse the document.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{bonjson, compat, encode_value};
+///
+/// let bytes = encode_value(&bonjson!({"samples": [0.1, 0.2, 0.3]})).unwrap();
+/// let report = compat::check_compatibility(&bytes);
+/// assert_eq!(report.features(), vec!["typed_arrays"]);
+/// ```
+#[must_use]
+pub fn check_compatibility(data: &[u8]) -> FormatReport {
+    let config = DecoderConfig {
+        allow_nul: true,
+        nan_infinity_mode: NanInfinityMode::Allow,
+        allow_trailing_bytes: true,
+        ..Default::default()
+    };
+    let mut report = FormatReport::default();
+    if let Err(e) = scan(data, config, &mut report) {
+        report.error = Some(e);
+    }
+    report
+}
+
+fn scan(data: &[u8], config: DecoderConfig, report: &mut FormatReport) -> crate::Result<()> {
+    let mut decoder = Decoder::with_config(data, config);
+    decoder.read_record_definitions()?;
+    report.records = !decoder.record_definitions().is_empty();
+
+    let mut depth: usize = 0;
+    loop {
+        let tc = decoder.peek_type_code()?;
+        match decoder.decode_value()? {
+            DecodedValue::ArrayStart | DecodedValue::ObjectStart => depth += 1,
+            DecodedValue::RecordInstanceStart(_) => {
+                report.records = true;
+                depth += 1;
+            }
+            DecodedValue::ContainerEnd => depth -= 1,
+            DecodedValue::TypedArrayStart { element_type_code, count } => {
+                report.typed_arrays = true;
+                for _ in 0..count {
+                    decoder.read_typed_array_element(element_type_code)?;
+                }
+                decoder.end_typed_array()?;
+            }
+            DecodedValue::BigNumber(_) => report.big_numbers = true,
+            DecodedValue::String(_) => report.long_strings |= tc == type_code::STRING_LONG,
+            _ => {}
+        }
+        if depth == 0 {
+            return Ok(decoder.finish()?);
+        }
+    }
+}
+
+// ============================================================================
+// Test vectors
+// ============================================================================
+
+/// A pinned encoding: `bytes` is exactly what this crate's encoder produces
+/// for `value()`, and what every conforming decoder must read back as it.
+#[derive(Debug, Clone, Copy)]
+pub struct TestVector {
+    /// A short, stable identifier.
+    pub name: &'static str,
+    /// The encoded document.
+    pub bytes: &'static [u8],
+    /// Builds the value the document decodes to.
+    pub value: fn() -> Value,
+}
+
+/// Wire-compatibility vectors covering every value type and optional feature.
+///
+/// Other implementations can check these in their own test suites; this
+/// crate's tests ensure the encoder's output never drifts from them.
+pub const TEST_VECTORS: &[TestVector] = &[
+    TestVector { name: "null", bytes: &[0xb3], value: || Value::Null },
+    TestVector { name: "true", bytes: &[0xb5], value: || Value::Bool(true) },
+    TestVector { name: "false", bytes: &[0xb4], value: || Value::Bool(false) },
+    TestVector { name: "small_int", bytes: &[0x2a], value: || Value::Int(42) },
+    TestVector { name: "sint16", bytes: &[0xad, 0x18, 0xfc], value: || Value::Int(-1000) },
+    TestVector {
+        name: "uint64",
+        bytes: &[0xab, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        value: || Value::UInt(u64::MAX),
+    },
+    TestVector { name: "float32", bytes: &[0xb0, 0x00, 0x00, 0xc0, 0x3f], value: || Value::Float(1.5) },
+    TestVector {
+        name: "float64",
+        bytes: &[0xb1, 0x9a, 0x99, 0x99, 0x99, 0x99, 0x99, 0xb9, 0x3f],
+        value: || Value::Float(0.1),
+    },
+    TestVector {
+        name: "big_number",
+        bytes: &[0xb2, 0x01, 0x01, 0x0f],
+        value: || Value::BigNumber(BigNumber::new(-1, 15, -1)),
+    },
+    TestVector {
+        name: "short_string",
+        bytes: &[0x6b, 0x68, 0xc3, 0xa9, 0x6c, 0x6c, 0x6f],
+        value: || Value::from("héllo"),
+    },
+    TestVector {
+        name: "long_string",
+        bytes: b"\xffThe quick brown fox jumps over the lazy dog, then naps in the warm sun.\xff",
+        value: || Value::from("The quick brown fox jumps over the lazy dog, then naps in the warm sun."),
+    },
+    TestVector { name: "empty_array", bytes: &[0xb7, 0xb6], value: || Value::Array(Vec::new()) },
+    TestVector { name: "empty_object", bytes: &[0xb8, 0xb6], value: || Value::Object(crate::Map::new()) },
+    TestVector {
+        name: "object",
+        bytes: &[0xb8, 0x66, 0x61, 0x01, 0x66, 0x62, 0xb7, 0xb5, 0xb3, 0xb6, 0xb6],
+        value: || bonjson!({"a": 1, "b": [true, null]}),
+    },
+    TestVector {
+        name: "typed_array",
+        bytes: &[
+            0xf5, 0x03,
+            0x9a, 0x99, 0x99, 0x99, 0x99, 0x99, 0xb9, 0x3f,
+            0x9a, 0x99, 0x99, 0x99, 0x99, 0x99, 0xc9, 0x3f,
+            0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0xd3, 0x3f,
+        ],
+        value: || bonjson!([0.1, 0.2, 0.3]),
+    },
+    TestVector {
+        name: "records",
+        bytes: &[
+            0xb9, 0x66, 0x78, 0x66, 0x79, 0xb6,
+            0xb7, 0xba, 0x00, 0x01, 0x02, 0xb6, 0xba, 0x00, 0x03, 0x04, 0xb6, 0xb6,
+        ],
+        value: || bonjson!([{"x": 1, "y": 2}, {"x": 3, "y": 4}]),
+    },
+];
@@ -0,0 +1,159 @@
+// ABOUTME: Unit tests for the compat module.
+// ABOUTME: Pins the test vectors against the encoder and decoder, and tests feature detection.
+
+use crate::compat::{build_info, check_compatibility, spec_version, TEST_VECTORS};
+use crate::{bonjson, decode_value, encode_value, Error};
+
+#[test]
+fn test_spec_version() {
+    assert_eq!(spec_version(), crate::compat::SPEC_VERSION);
+    assert_eq!(spec_version().split('.').count(), 3);
+}
+
+#[test]
+fn test_build_info() {
+    let info = build_info();
+    assert_eq!(info.crate_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(info.spec_version, spec_version());
+    assert_eq!(info.has_feature("half"), cfg!(feature = "half"));
+    assert_eq!(info.has_feature("simd-utf8"), cfg!(feature = "simd-utf8"));
+    assert!(!info.has_feature("preserve_order"));
+
+    let line = info.to_string();
+    assert!(line.starts_with(&format!("serde_bonjson {} (BONJSON {}; features: ", info.crate_version, info.spec_version)));
+    if info.features.is_empty() {
+        assert!(line.ends_with("features: none)"));
+    } else {
+        assert!(line.ends_with(&format!("{})", info.features.join(", "))));
+    }
+}
+
+#[test]
+fn test_vectors_match_encoder_and_decoder() {
+    for vector in TEST_VECTORS {
+        let value = (vector.value)();
+        assert_eq!(encode_value(&value).unwrap(), vector.bytes, "encoding {}", vector.name);
+        let decoded = decode_value(vector.bytes).unwrap();
+        assert_eq!(decoded.total_cmp(&value), std::cmp::Ordering::Equal, "decoding {}", vector.name);
+    }
+}
+
+#[test]
+fn test_vectors_report_their_features() {
+    let features = |name: &str| {
+        let vector = TEST_VECTORS.iter().find(|v| v.name == name).unwrap();
+        check_compatibility(vector.bytes).features()
+    };
+    assert_eq!(features("object"), Vec::<&str>::new());
+    assert_eq!(features("records"), vec!["records"]);
+    assert_eq!(features("typed_array"), vec!["typed_arrays"]);
+    assert_eq!(features("big_number"), vec!["big_numbers"]);
+    assert_eq!(features("long_string"), vec!["long_strings"]);
+}
+
+#[test]
+fn test_check_compatibility() {
+    let bytes = encode_value(&bonjson!({"name": "x", "list": [1, "two", null]})).unwrap();
+    let report = check_compatibility(&bytes);
+    assert!(report.is_core_only());
+    assert!(report.error.is_none());
+
+    // Features nested anywhere are found
+    let doc = bonjson!({"outer": [{"samples": [0.1, 0.2, 0.3]}]});
+    assert!(check_compatibility(&encode_value(&doc).unwrap()).typed_arrays);
+
+    // Content policies don't get in the way of the scan
+    let nul = [0x68, b'a', 0x00, b'b'];
+    assert!(check_compatibility(&nul).error.is_none());
+
+    // Malformed input reports the error, with the features seen so far
+    let mut bytes = encode_value(&bonjson!([[0.1, 0.2, 0.3], [1, "two"]])).unwrap();
+    bytes.truncate(bytes.len() - 2);
+    let report = check_compatibility(&bytes);
+    assert!(report.typed_arrays);
+    assert_eq!(report.error, Some(Error::Truncated));
+}
+
+#[test]
+fn test_capability_set_detect() {
+    use crate::CapabilitySet;
+
+    let records = TEST_VECTORS.iter().find(|v| v.name == "records").unwrap();
+    let needed = CapabilitySet::detect(records.bytes).unwrap();
+    assert_eq!(needed, CapabilitySet { records: true, ..CapabilitySet::CORE });
+    assert!(CapabilitySet::ALL.contains(needed));
+    assert!(!CapabilitySet::CORE.contains(needed));
+    assert_eq!(CapabilitySet::detect(&[0xb7]).unwrap_err(), Error::Truncated);
+}
+
+#[test]
+fn test_encode_value_target_capabilities() {
+    use crate::{encode_value_with_config, BigNumber, CapabilitySet, EncoderConfig, Value};
+
+    let doc = bonjson!({
+        "points": [{"x": 1, "y": 2}, {"x": 3, "y": 4}],
+        "samples": [0.1, 0.2, 0.3],
+        "count": (Value::BigNumber(BigNumber::new(1, 12, 3)))
+    });
+    let full = encode_value(&doc).unwrap();
+    assert_eq!(CapabilitySet::detect(&full).unwrap(), CapabilitySet {
+        long_strings: false,
+        ..CapabilitySet::ALL
+    });
+
+    // Core output falls back to objects, regular arrays and plain integers
+    let config = EncoderConfig::default().target_capabilities(CapabilitySet::CORE);
+    let core = encode_value_with_config(&doc, config.clone()).unwrap();
+    assert_eq!(CapabilitySet::detect(&core).unwrap(), CapabilitySet::CORE);
+    assert_eq!(decode_value(&core).unwrap().total_cmp(&doc), std::cmp::Ordering::Equal);
+
+    // Values with no fallback encoding are rejected
+    let long = Value::from("x".repeat(100));
+    assert_eq!(
+        encode_value_with_config(&long, config.clone()).unwrap_err(),
+        Error::UnsupportedCapability("long_strings")
+    );
+    let fraction = Value::BigNumber(BigNumber::new(1, 15, -1));
+    assert_eq!(
+        encode_value_with_config(&fraction, config).unwrap_err(),
+        Error::UnsupportedCapability("big_numbers")
+    );
+}
+
+#[test]
+fn test_serializer_capabilities() {
+    use crate::{to_vec_with_config, CapabilitySet, SerializerConfig};
+
+    #[derive(serde::Serialize)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    let data = (vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }], vec![0.1, 0.2, 0.3]);
+    let config = SerializerConfig { records: true, ..Default::default() };
+    let full = to_vec_with_config(&data, &config).unwrap();
+    assert!(CapabilitySet::detect(&full).unwrap().records);
+
+    let config = SerializerConfig { capabilities: CapabilitySet::CORE, ..config };
+    let core = to_vec_with_config(&data, &config).unwrap();
+    assert_eq!(CapabilitySet::detect(&core).unwrap(), CapabilitySet::CORE);
+    assert_eq!(
+        to_vec_with_config(&serde_bytes_like(), &config).unwrap(),
+        crate::to_vec(&vec![1u8, 2, 3]).unwrap()
+    );
+    assert_eq!(
+        to_vec_with_config(&10i128.pow(20), &config).unwrap_err(),
+        Error::UnsupportedCapability("big_numbers")
+    );
+}
+
+/// A value that serializes through `serialize_bytes`.
+fn serde_bytes_like() -> impl serde::Serialize {
+    struct Bytes;
+    impl serde::Serialize for Bytes {
+        fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+            s.serialize_bytes(&[1, 2, 3])
+        }
+    }
+    Bytes
+}
@@ -1,14 +1,53 @@
 // ABOUTME: Serde Deserializer implementation for BONJSON decoding.
 // ABOUTME: Allows BONJSON bytes to be decoded into any serde-deserializable Rust type.
 
-use crate::decoder::{DecodedValue, Decoder, DecoderConfig};
+use crate::decoder::{DecodedValue, Decoder, DecoderConfig, DuplicateKeyCheck, FieldMatching, NanInfinityMode, OutOfRangeMode, RecordMismatchMode, TypedElement, UnknownTypeCodeMode};
+use crate::types::{type_code, BigNumber, BIG_NUMBER_TOKEN, VALUE_TOKEN};
 use crate::error::{Error, Result};
+use crate::jsonpath::{JsonPath, PathSegment};
+use crate::traced::Track;
 use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
 use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::Arc;
 
 /// A serde Deserializer that reads BONJSON.
 pub struct Deserializer<'de> {
     decoder: Decoder<'de>,
+    /// Field tracking state; only present when a report was requested or
+    /// `deny_unknown_fields_globally` is set.
+    tracker: Option<FieldTracker>,
+}
+
+/// A report of how a document's fields lined up with the target types.
+///
+/// Paths are rendered as [`JsonPath`]s, e.g. `$.items[2].extra`, with keys
+/// that aren't identifiers quoted (`$['odd key']`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeReport {
+    /// Keys present in the document that no target struct declares.
+    pub unknown_fields: Vec<String>,
+    /// Struct fields absent from the document, which were filled from
+    /// `Default` or `Option::None`.
+    ///
+    /// Serde lists `#[serde(alias)]` names alongside field names, so a field
+    /// supplied under one of its aliases still reports its other names here.
+    pub defaulted_fields: Vec<String>,
+}
+
+#[derive(Default)]
+struct FieldTracker {
+    path: JsonPath,
+    report: DecodeReport,
+}
+
+impl FieldTracker {
+    fn render(&self, leaf: &str) -> String {
+        self.path.child_key(leaf).to_string()
+    }
 }
 
 impl<'de> Deserializer<'de> {
@@ -16,43 +55,638 @@ impl<'de> Deserializer<'de> {
     #[must_use] pub fn from_slice(data: &'de [u8]) -> Self {
         Self {
             decoder: Decoder::new(data),
+            tracker: None,
         }
     }
 
     /// Create a new Deserializer with custom configuration.
     #[must_use] pub fn from_slice_with_config(data: &'de [u8], config: DecoderConfig) -> Self {
+        let tracker = config.deny_unknown_fields_globally.then(FieldTracker::default);
         Self {
             decoder: Decoder::with_config(data, config),
+            tracker,
         }
     }
 
+    /// Wrap a decoder that is already positioned at a value.
+    pub(crate) fn from_decoder(decoder: Decoder<'de>) -> Self {
+        Self { decoder, tracker: None }
+    }
+
+    /// The number of input bytes consumed so far.
+    #[must_use]
+    pub fn byte_offset(&self) -> usize {
+        self.decoder.position()
+    }
+
     /// Get the underlying decoder (consumes self).
     #[must_use] pub fn into_decoder(self) -> Decoder<'de> {
         self.decoder
     }
+
+    /// Iterate over back-to-back documents in the input, deserializing each as a `T`.
+    ///
+    /// Each document has its own record definitions and is checked against
+    /// `max_document_size` on its own. Use
+    /// [`ReaderDeserializer::into_iter`](crate::ReaderDeserializer::into_iter)
+    /// for documents arriving over an `io::Read`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_bonjson::{to_vec, Deserializer};
+    ///
+    /// let mut log = to_vec(&"started").unwrap();
+    /// log.extend(to_vec(&"stopped").unwrap());
+    ///
+    /// let mut stream = Deserializer::from_slice(&log).into_iter::<String>();
+    /// assert_eq!(stream.next().unwrap().unwrap(), "started");
+    /// assert_eq!(stream.byte_offset(), 8);
+    /// assert_eq!(stream.next().unwrap().unwrap(), "stopped");
+    /// assert!(stream.next().is_none());
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter<T: Deserialize<'de>>(self) -> StreamDeserializer<'de, T> {
+        StreamDeserializer {
+            data: self.decoder.remaining(),
+            offset: 0,
+            config: self.decoder.config().clone(),
+            failed: false,
+            skip_padding: false,
+            resync_on_error: false,
+            resyncing: false,
+            skipped: 0,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Decode a string, charging its bytes against `max_total_allocated_bytes`.
+    #[inline]
+    fn decode_str(&mut self) -> Result<&'de str> {
+        let s = self.decoder.decode_str_direct()?;
+        self.decoder.charge_allocation(s.len())?;
+        Ok(s)
+    }
+
+    /// Visit a string value, after normalization and the configured `string_transform`.
+    fn visit_string_value<V: Visitor<'de>>(&self, s: Cow<'de, str>, visitor: V) -> Result<V::Value> {
+        let normalization = self.decoder.config().unicode_normalization;
+        let s = match s {
+            Cow::Borrowed(b) => crate::nfc_normalize_borrowed(normalization, b),
+            Cow::Owned(o) => Cow::Owned(crate::maybe_nfc_normalize(normalization, o)),
+        };
+        let s = match (&self.decoder.config().string_transform, s) {
+            (Some(transform), Cow::Borrowed(b)) => transform.apply(b),
+            (Some(transform), Cow::Owned(o)) => Cow::Owned(transform.apply_owned(o)),
+            (None, s) => s,
+        };
+        match s {
+            Cow::Borrowed(b) => visitor.visit_borrowed_str(b),
+            Cow::Owned(o) => visitor.visit_string(o),
+        }
+    }
+
+    /// Where a string is expected, read a number that `decode_value` would
+    /// turn into one: NaN or Infinity under `NanInfinityMode::Stringify`, or
+    /// an out-of-range BigNumber under `OutOfRangeMode::Stringify`.
+    fn decode_stringified_number(&mut self) -> Result<Option<String>> {
+        let tc = self.decoder.peek_type_code()?;
+        let config = self.decoder.config();
+        let stringified = match tc {
+            type_code::FLOAT32 | type_code::FLOAT64 if config.nan_infinity_mode == NanInfinityMode::Stringify => {
+                let f = self.decoder.decode_f64_direct()?;
+                NanInfinityMode::Stringify.stringify(f).map(String::from)
+            }
+            type_code::BIG_NUMBER if config.out_of_range_mode == OutOfRangeMode::Stringify => {
+                match self.decoder.decode_value_unchecked()? {
+                    DecodedValue::BigNumber(bn) => crate::stringify_big_number(self.decoder.config(), &bn)?,
+                    _ => unreachable!("peeked a BigNumber"),
+                }
+            }
+            _ => return Ok(None),
+        };
+        stringified.map(Some).ok_or_else(|| Error::Custom(format!("expected string, got 0x{tc:02x}")))
+    }
+
+    /// Where a sequence is expected, read an object of equal-length typed
+    /// arrays, as written under `SerializerConfig::columnar_structs`.
+    fn read_columns(&mut self) -> Result<ColumnarSeqDeserializer<'de>> {
+        self.decoder.expect_object_start()?;
+        let mut keys = Vec::new();
+        let mut columns: Vec<Vec<TypedElement>> = Vec::new();
+        while !self.decoder.try_consume_container_end()? {
+            self.decoder.begin_element(0)?;
+            let key = self.decode_str()?;
+            let tc = self.decoder.peek_type_code()?;
+            if !type_code::is_typed_array(tc) {
+                return Err(Error::Custom(format!("expected typed array for column `{key}`, got 0x{tc:02x}")));
+            }
+            let (_, count) = self.decoder.read_typed_array_header()?;
+            if let Some(first) = columns.first() {
+                if count != first.len() {
+                    return Err(Error::InvalidData(format!(
+                        "column `{key}` has {count} rows, expected {}",
+                        first.len()
+                    )));
+                }
+            }
+            let mut column = Vec::new();
+            for _ in 0..count {
+                self.decoder.begin_element(0)?;
+                column.push(self.decoder.read_typed_element(tc)?);
+            }
+            keys.push(key);
+            columns.push(column);
+        }
+        if keys.is_empty() {
+            return Err(Error::Custom("expected array, got an object with no columns".into()));
+        }
+        Ok(ColumnarSeqDeserializer {
+            keys,
+            remaining: columns[0].len(),
+            columns: columns.into_iter().map(Vec::into_iter).collect(),
+            modes: ElementModes::of(self.decoder.config()),
+        })
+    }
 }
 
 /// Deserialize a value from a BONJSON byte slice.
 pub fn from_slice<'de, T: Deserialize<'de>>(data: &'de [u8]) -> Result<T> {
-    let mut de = Deserializer::from_slice(data);
-    de.decoder.check_document_size()?;
-    de.decoder.read_record_definitions()?;
-    let value = T::deserialize(&mut de)?;
-    de.decoder.finish()?;
-    Ok(value)
+    let de = Deserializer::from_slice(data);
+    deserialize_document(de, PhantomData).map(|(value, _)| value)
 }
 
 /// Deserialize a value from a BONJSON byte slice with custom configuration.
 pub fn from_slice_with_config<'de, T: Deserialize<'de>>(
     data: &'de [u8],
     config: DecoderConfig,
+) -> Result<T> {
+    let de = Deserializer::from_slice_with_config(data, config);
+    deserialize_document(de, PhantomData).map(|(value, _)| value)
+}
+
+/// Deserialize a value from a BONJSON byte slice, adjusting the default
+/// configuration with the config's setters.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{from_slice_with, to_vec};
+///
+/// let mut bytes = to_vec(&"hello").unwrap();
+/// bytes.push(0);
+/// let s: String = from_slice_with(&bytes, |cfg| cfg.allow_trailing_bytes(true)).unwrap();
+/// assert_eq!(s, "hello");
+/// ```
+pub fn from_slice_with<'de, T, F>(data: &'de [u8], configure: F) -> Result<T>
+where
+    T: Deserialize<'de>,
+    F: FnOnce(DecoderConfig) -> DecoderConfig,
+{
+    from_slice_with_config(data, configure(DecoderConfig::default()))
+}
+
+/// Deserialize a document from the start of `data`, returning it and the
+/// number of bytes it occupies.
+///
+/// Bytes after the document are left alone rather than rejected as trailing
+/// bytes, so this parses a document embedded at the start of a larger buffer
+/// without turning on `allow_trailing_bytes`. `max_document_size` applies
+/// to the document itself.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{from_slice_partial, to_vec};
+///
+/// let mut buffer = to_vec(&vec![1, 2, 3]).unwrap();
+/// let len = buffer.len();
+/// buffer.extend_from_slice(b"trailer");
+///
+/// let (value, consumed): (Vec<u8>, usize) = from_slice_partial(&buffer).unwrap();
+/// assert_eq!((value, consumed), (vec![1, 2, 3], len));
+/// assert_eq!(&buffer[consumed..], b"trailer");
+/// ```
+pub fn from_slice_partial<'de, T: Deserialize<'de>>(data: &'de [u8]) -> Result<(T, usize)> {
+    from_slice_partial_with_config(data, DecoderConfig::default())
+}
+
+/// Deserialize a document from the start of `data` with custom configuration.
+///
+/// See [`from_slice_partial`].
+pub fn from_slice_partial_with_config<'de, T: Deserialize<'de>>(
+    data: &'de [u8],
+    mut config: DecoderConfig,
+) -> Result<(T, usize)> {
+    let max_document_size = config.document_size_limit();
+    config.allow_trailing_bytes = true;
+    let mut de = Deserializer::from_slice_with_config(data, config);
+    let (value, _) = deserialize_root(&mut de, PhantomData)?;
+    let consumed = de.byte_offset();
+    if consumed > max_document_size {
+        return Err(Error::MaxDocumentSizeExceeded);
+    }
+    Ok((value, consumed))
+}
+
+/// Deserialize a value and report unknown and defaulted struct fields.
+///
+/// Unknown keys are reported regardless of `#[serde(deny_unknown_fields)]`
+/// on the target types. If `config.deny_unknown_fields_globally` is set and
+/// any were found, this returns [`Error::UnknownFields`] listing all of them.
+pub fn from_slice_with_report<'de, T: Deserialize<'de>>(
+    data: &'de [u8],
+    config: DecoderConfig,
+) -> Result<(T, DecodeReport)> {
+    let mut de = Deserializer::from_slice_with_config(data, config);
+    de.tracker.get_or_insert_with(FieldTracker::default);
+    deserialize_document(de, PhantomData).map(|(value, report)| (value, report.unwrap_or_default()))
+}
+
+/// Deserialize a value from a BONJSON byte slice, reporting where any error
+/// happened.
+///
+/// Every error comes back as [`Error::AtPath`], holding the path of the
+/// innermost value that failed (`$.payload.items[17].price`) and the offset
+/// decoding had reached, which is in or just after the value at fault: past
+/// the type code of a value of the wrong type, say. Tracking costs a little on each value, so use this where
+/// errors need diagnosing, rather than for every decode.
+///
+/// # Example
+///
+/// ```rust
+/// use serde::Deserialize;
+/// use serde_bonjson::{from_slice_traced, to_vec, Error};
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Item {
+///     price: u32,
+/// }
+///
+/// let bytes = to_vec(&serde_bonjson::bonjson!({"items": [{"price": 5}, {"price": "free"}]})).unwrap();
+/// let err = from_slice_traced::<std::collections::HashMap<String, Vec<Item>>>(&bytes).unwrap_err();
+/// let Error::AtPath { path, offset, .. } = &err else { panic!() };
+/// assert_eq!(path.to_string(), "$.items[1].price");
+/// assert!(offset.is_some());
+/// ```
+///
+/// # Errors
+///
+/// Returns [`Error::AtPath`] wrapping the error [`from_slice`] would return.
+pub fn from_slice_traced<'de, T: Deserialize<'de>>(data: &'de [u8]) -> Result<T> {
+    from_slice_traced_with_config(data, DecoderConfig::default())
+}
+
+/// Deserialize a value from a BONJSON byte slice with custom configuration,
+/// reporting where any error happened.
+///
+/// See [`from_slice_traced`].
+pub fn from_slice_traced_with_config<'de, T: Deserialize<'de>>(
+    data: &'de [u8],
+    config: DecoderConfig,
 ) -> Result<T> {
     let mut de = Deserializer::from_slice_with_config(data, config);
+    let track = Track::default();
+    let result = de.decoder.check_document_size().map_err(Error::from).and_then(|()| deserialize_root(&mut de, track.seed::<T>()));
+    result.map(|(value, _)| value).map_err(|error| track.annotate(error, Some(de.byte_offset())))
+}
+
+/// Like [`from_slice`], but an error inside the root value comes back as
+/// [`Error::AtPath`], with where it happened.
+pub(crate) fn from_slice_at_path<'de, T: Deserialize<'de>>(data: &'de [u8]) -> Result<T> {
+    let track = Track::default();
+    let result = deserialize_document(Deserializer::from_slice(data), track.seed::<T>());
+    result.map(|(value, _)| value).map_err(|error| track.annotate(error, None))
+}
+
+fn deserialize_document<'de, S: DeserializeSeed<'de>>(
+    mut de: Deserializer<'de>,
+    seed: S,
+) -> Result<(S::Value, Option<DecodeReport>)> {
     de.decoder.check_document_size()?;
+    deserialize_root(&mut de, seed)
+}
+
+/// Deserialize a document's record definitions and root value, then check it
+/// ends there. This is where serde decoding reports its metrics.
+fn deserialize_root<'de, S: DeserializeSeed<'de>>(
+    de: &mut Deserializer<'de>,
+    seed: S,
+) -> Result<(S::Value, Option<DecodeReport>)> {
+    codec_span!("bonjson.deserialize", input_bytes = de.decoder.remaining().len());
+    let result = deserialize_root_value(de, seed);
+    de.decoder.report_metrics();
+    if result.is_ok() {
+        de.decoder.finish_progress();
+    }
+    result
+}
+
+fn deserialize_root_value<'de, S: DeserializeSeed<'de>>(
+    de: &mut Deserializer<'de>,
+    seed: S,
+) -> Result<(S::Value, Option<DecodeReport>)> {
     de.decoder.read_record_definitions()?;
-    let value = T::deserialize(&mut de)?;
+    let value = seed.deserialize(&mut *de)?;
     de.decoder.finish()?;
-    Ok(value)
+    let report = de.tracker.take().map(|t| t.report);
+    if let Some(report) = &report {
+        if de.decoder.config().deny_unknown_fields_globally && !report.unknown_fields.is_empty() {
+            return Err(Error::UnknownFields(report.unknown_fields.clone()));
+        }
+    }
+    Ok((value, report))
+}
+
+/// Iterator over back-to-back documents; see [`Deserializer::into_iter`].
+pub struct StreamDeserializer<'de, T> {
+    data: &'de [u8],
+    offset: usize,
+    config: DecoderConfig,
+    failed: bool,
+    skip_padding: bool,
+    resync_on_error: bool,
+    /// Set after an error under `resync_on_error`, until a document decodes.
+    resyncing: bool,
+    skipped: usize,
+    _marker: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T> StreamDeserializer<'_, T> {
+    /// The stream position after the documents yielded so far and any bytes
+    /// skipped. After an error, this is the offset of the document that failed.
+    #[must_use]
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Skip `0x00` bytes between documents, for transports that pad frames
+    /// with zeros.
+    ///
+    /// A lone `0x00` is also a complete document (the integer 0), so only
+    /// enable this for streams whose documents are never a bare zero.
+    #[must_use]
+    pub fn skip_padding(mut self, skip: bool) -> Self {
+        self.skip_padding = skip;
+        self
+    }
+
+    /// Keep going after a document fails to decode, instead of stopping.
+    ///
+    /// The error is still yielded. The next call scans forward from the byte
+    /// after the failed document's start to the first offset where a `T`
+    /// decodes, counting the bytes passed over in
+    /// [`skipped_bytes`](Self::skipped_bytes). Every offset is tried, so the
+    /// more specific `T` is, the less likely garbage is to be taken for a
+    /// document; scanning a long damaged stretch costs a decode attempt per byte.
+    #[must_use]
+    pub fn resync_on_error(mut self, resync: bool) -> Self {
+        self.resync_on_error = resync;
+        self
+    }
+
+    /// The number of padding and resynchronization bytes skipped so far.
+    #[must_use]
+    pub fn skipped_bytes(&self) -> usize {
+        self.skipped
+    }
+
+    fn skip(&mut self, count: usize) {
+        self.offset += count;
+        self.skipped += count;
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Iterator for StreamDeserializer<'de, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        loop {
+            if self.skip_padding {
+                let padding = self.data[self.offset..].iter().take_while(|&&b| b == 0).count();
+                self.skip(padding);
+            }
+            if self.failed || self.offset == self.data.len() {
+                return None;
+            }
+            match from_slice_partial_with_config(&self.data[self.offset..], self.config.clone()) {
+                Ok((value, consumed)) => {
+                    self.offset += consumed;
+                    self.resyncing = false;
+                    return Some(Ok(value));
+                }
+                // Includes the document that failed, which fails again
+                Err(_) if self.resyncing => self.skip(1),
+                Err(e) => {
+                    self.resyncing = self.resync_on_error;
+                    self.failed = !self.resync_on_error;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Deserialize each element of the array at `path`, one at a time.
+///
+/// Only one element is held in memory at once, so this runs in constant
+/// memory however long the array is. `path` is a concrete JSONPath such as
+/// `$.metrics.samples`. Returns the number of elements visited.
+///
+/// Decoding stops once the array ends; the rest of the document is not
+/// validated. If an object along the path has duplicate keys, the first
+/// occurrence is used.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{bonjson, encode_value, for_each_element};
+///
+/// let bytes = encode_value(&bonjson!({"metrics": {"samples": [3, 1, 4]}})).unwrap();
+/// let mut sum = 0;
+/// let count = for_each_element(&bytes, "$.metrics.samples", |n: i64| sum += n).unwrap();
+/// assert_eq!((count, sum), (3, 8));
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the path is malformed, doesn't lead to an array, or
+/// an element fails to decode or deserialize.
+pub fn for_each_element<'de, T, F>(data: &'de [u8], path: &str, f: F) -> Result<usize>
+where
+    T: Deserialize<'de>,
+    F: FnMut(T),
+{
+    for_each_element_with_config(data, path, DecoderConfig::default(), f)
+}
+
+/// Deserialize each element of the array at `path` with custom configuration.
+///
+/// See [`for_each_element`].
+///
+/// # Errors
+///
+/// Returns an error if the path is malformed, doesn't lead to an array, or
+/// an element fails to decode or deserialize.
+pub fn for_each_element_with_config<'de, T, F>(
+    data: &'de [u8],
+    path: &str,
+    config: DecoderConfig,
+    mut f: F,
+) -> Result<usize>
+where
+    T: Deserialize<'de>,
+    F: FnMut(T),
+{
+    let path: JsonPath = path.parse()?;
+    let mut de = Deserializer::from_slice_with_config(data, config);
+    de.decoder.check_document_size()?;
+    de.decoder.read_record_definitions()?;
+    if !seek_path(&mut de.decoder, &path)? {
+        return Err(Error::Custom(format!("no value at {path}")));
+    }
+
+    let mut count = 0;
+    match de.decoder.decode_value()? {
+        DecodedValue::ArrayStart => {
+            while de.decoder.skip_to_array_element()? {
+                de.decoder.begin_element(0)?;
+                f(T::deserialize(&mut de)?);
+                count += 1;
+            }
+        }
+        DecodedValue::TypedArrayStart { element_type_code, count: len } => {
+            for _ in 0..len {
+                let element = de.decoder.read_typed_element(element_type_code)?;
+                let modes = ElementModes::of(de.decoder.config());
+                f(deserialize_typed_element(std::marker::PhantomData, element, modes)?);
+            }
+            de.decoder.end_typed_array()?;
+            count = len;
+        }
+        _ => return Err(Error::Custom(format!("value at {path} is not an array"))),
+    }
+    Ok(count)
+}
+
+/// Deserialize the first `max_elements` elements of a document's root array,
+/// returning them and whether the array has more.
+///
+/// The serde counterpart of [`decode_value_prefix`](crate::decode_value_prefix):
+/// nothing past the elements returned is read, and when the whole array was,
+/// trailing bytes are rejected as by [`from_slice`].
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{from_slice_prefix, to_vec};
+///
+/// let bytes = to_vec(&vec!["a", "b", "c"]).unwrap();
+/// let (head, more): (Vec<String>, bool) = from_slice_prefix(&bytes, 2).unwrap();
+/// assert_eq!((head, more), (vec!["a".to_owned(), "b".to_owned()], true));
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the root value isn't an array, or an element fails to
+/// decode or deserialize.
+pub fn from_slice_prefix<'de, T: Deserialize<'de>>(data: &'de [u8], max_elements: usize) -> Result<(Vec<T>, bool)> {
+    from_slice_prefix_with_config(data, max_elements, DecoderConfig::default())
+}
+
+/// Deserialize the first `max_elements` elements of a document's root array
+/// with custom configuration.
+///
+/// See [`from_slice_prefix`]. `max_document_size` applies to the bytes read,
+/// unless the whole array was.
+///
+/// # Errors
+///
+/// Returns the errors `from_slice_prefix` would, with the configured limits.
+pub fn from_slice_prefix_with_config<'de, T: Deserialize<'de>>(
+    data: &'de [u8],
+    max_elements: usize,
+    config: DecoderConfig,
+) -> Result<(Vec<T>, bool)> {
+    let max_document_size = config.document_size_limit();
+    let mut de = Deserializer::from_slice_with_config(data, config);
+    de.decoder.read_record_definitions()?;
+    let mut elements = Vec::new();
+    let more = match de.decoder.decode_value()? {
+        DecodedValue::ArrayStart => loop {
+            if !de.decoder.skip_to_array_element()? {
+                break false;
+            }
+            if elements.len() == max_elements {
+                break true;
+            }
+            de.decoder.begin_element(0)?;
+            elements.push(T::deserialize(&mut de)?);
+        },
+        DecodedValue::TypedArrayStart { element_type_code, count } => {
+            let len = count.min(max_elements);
+            let modes = ElementModes::of(de.decoder.config());
+            for _ in 0..len {
+                let element = de.decoder.read_typed_element(element_type_code)?;
+                elements.push(deserialize_typed_element(PhantomData, element, modes)?);
+            }
+            if len == count {
+                de.decoder.end_typed_array()?;
+            }
+            len < count
+        }
+        _ => return Err(Error::Custom("root value is not an array".into())),
+    };
+    if more {
+        if de.byte_offset() > max_document_size {
+            return Err(Error::MaxDocumentSizeExceeded);
+        }
+    } else {
+        de.decoder.check_document_size()?;
+        de.decoder.finish()?;
+    }
+    Ok((elements, more))
+}
+
+/// Advance the decoder to the value at `path`. Returns false if there's no
+/// value there.
+pub(crate) fn seek_path(decoder: &mut Decoder<'_>, path: &JsonPath) -> Result<bool> {
+    for segment in path.segments() {
+        let found = match (segment, decoder.decode_value()?) {
+            (PathSegment::Key(key), DecodedValue::ObjectStart) => loop {
+                if decoder.is_at_container_end()? {
+                    break false;
+                }
+                if decoder.decode_str_direct()? == key {
+                    break true;
+                }
+                decoder.skip_value()?;
+            },
+            (PathSegment::Key(key), DecodedValue::RecordInstanceStart(def_index)) => {
+                match decoder.record_definitions()[def_index].iter().position(|k| k == key) {
+                    Some(position) => skip_elements(decoder, position)?,
+                    None => false,
+                }
+            }
+            (PathSegment::Index(index), DecodedValue::ArrayStart) => skip_elements(decoder, *index)?,
+            _ => false,
+        };
+        if !found {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Skip `n` values in the current container. Returns false if it ends first.
+fn skip_elements(decoder: &mut Decoder<'_>, n: usize) -> Result<bool> {
+    for _ in 0..n {
+        if decoder.is_at_container_end()? {
+            return Ok(false);
+        }
+        decoder.skip_value()?;
+    }
+    Ok(!decoder.is_at_container_end()?)
 }
 
 impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
@@ -62,11 +696,22 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
         match self.decoder.decode_value_unchecked()? {
             DecodedValue::Null => visitor.visit_unit(),
             DecodedValue::Bool(b) => visitor.visit_bool(b),
-            DecodedValue::Int(n) => visitor.visit_i64(n),
+            DecodedValue::Int(n) => match u64::try_from(n) {
+                Ok(u) if self.decoder.config().untagged_numeric_coercion => visitor.visit_u64(u),
+                _ => visitor.visit_i64(n),
+            },
             DecodedValue::UInt(n) => visitor.visit_u64(n),
-            DecodedValue::Float(f) => visitor.visit_f64(f),
+            DecodedValue::Float(f) => match self.decoder.config().nan_infinity_mode.stringify(f) {
+                Some(name) => visitor.visit_borrowed_str(name),
+                None => visitor.visit_f64(f),
+            },
             DecodedValue::BigNumber(bn) => {
-                if let Some(i) = bn.to_i64() {
+                let coerce = self.decoder.config().untagged_numeric_coercion;
+                if let Some(s) = crate::stringify_big_number(self.decoder.config(), &bn)? {
+                    visitor.visit_string(s)
+                } else if let Some(u) = bn.to_u64().filter(|_| coerce) {
+                    visitor.visit_u64(u)
+                } else if let Some(i) = bn.to_i64() {
                     visitor.visit_i64(i)
                 } else if let Some(u) = bn.to_u64() {
                     visitor.visit_u64(u)
@@ -74,10 +719,10 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
                     visitor.visit_f64(bn.to_f64())
                 }
             }
-            DecodedValue::String(s) => match s {
-                std::borrow::Cow::Borrowed(b) => visitor.visit_borrowed_str(b),
-                std::borrow::Cow::Owned(o) => visitor.visit_string(o),
-            },
+            DecodedValue::String(s) => {
+                self.decoder.charge_allocation(s.len())?;
+                self.visit_string_value(s, visitor)
+            }
             DecodedValue::ArrayStart => {
                 let seq = SeqDeserializer::new(self);
                 visitor.visit_seq(seq)
@@ -91,8 +736,7 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
                 visitor.visit_seq(seq)
             }
             DecodedValue::RecordInstanceStart(def_index) => {
-                let keys = self.decoder.record_definitions()[def_index].clone();
-                let map = RecordMapDeserializer::new(self, keys);
+                let map = RecordMapDeserializer::new(self, def_index, None);
                 visitor.visit_map(map)
             }
             DecodedValue::ContainerEnd => Err(Error::UnbalancedContainers),
@@ -135,8 +779,16 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
         visitor.visit_u64(self.decoder.decode_u64_direct()?)
     }
 
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i128(self.decoder.decode_i128_direct()?)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u128(self.decoder.decode_u128_direct()?)
+    }
+
     fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_f64(self.decoder.decode_f64_direct()?)
+        visitor.visit_f32(narrow_to_f32(self.decoder.decode_f64_direct()?)?)
     }
 
     fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
@@ -144,7 +796,7 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     }
 
     fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        let s = self.decoder.decode_str_direct()?;
+        let s = self.decode_str()?;
         let mut chars = s.chars();
         match (chars.next(), chars.next()) {
             (Some(c), None) => visitor.visit_char(c),
@@ -153,11 +805,15 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     }
 
     fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_borrowed_str(self.decoder.decode_str_direct()?)
+        if let Some(s) = self.decode_stringified_number()? {
+            return visitor.visit_string(s);
+        }
+        let s = self.decode_str()?;
+        self.visit_string_value(Cow::Borrowed(s), visitor)
     }
 
     fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_borrowed_str(self.decoder.decode_str_direct()?)
+        self.deserialize_str(visitor)
     }
 
     fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
@@ -167,17 +823,9 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
 
         // Support typed uint8 arrays for byte buffers
         if type_code::is_typed_array(tc) && tc == type_code::TYPED_ARRAY_UINT8 {
-            self.decoder.skip_byte();
-            let remaining = self.decoder.remaining();
-            let (count_raw, consumed) = crate::types::leb128_decode(remaining)
-                .ok_or(Error::Truncated)?;
-            for _ in 0..consumed { self.decoder.skip_byte(); }
-            let count = count_raw as usize;
-            let mut bytes = Vec::with_capacity(count);
-            for _ in 0..count {
-                bytes.push(self.decoder.read_byte_unchecked());
-            }
-            return visitor.visit_bytes(&bytes);
+            let (_, count) = self.decoder.read_typed_array_header()?;
+            let bytes = self.decoder.read_bytes(count)?;
+            return visitor.visit_borrowed_bytes(bytes);
         }
 
         self.decoder.expect_array_start()?;
@@ -190,11 +838,17 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
             if type_code::is_small_int(tc) {
                 let val = type_code::small_int_value(tc);
                 self.decoder.skip_byte();
+                self.decoder.count_value();
                 bytes.push(val);
             } else if tc == type_code::UINT8 {
                 self.decoder.skip_byte();
+                self.decoder.count_value();
                 let b = self.decoder.read_byte_unchecked();
                 bytes.push(b);
+            } else if type_code::is_any_int(tc) {
+                // Other encodings of 0-255, such as the sint8 the encoder picks for 101-127
+                let val = self.decoder.decode_u64_direct()?;
+                bytes.push(u8::try_from(val).map_err(|_| Error::ValueOutOfRange)?);
             } else {
                 return Err(Error::Custom("expected byte array".into()));
             }
@@ -207,8 +861,14 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     }
 
     fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        if self.decoder.peek_type_code()? == crate::types::type_code::NULL {
+        let tc = self.decoder.peek_type_code()?;
+        if tc == crate::types::type_code::NULL {
             self.decoder.skip_byte();
+            self.decoder.count_value();
+            visitor.visit_none()
+        } else if type_code::is_reserved(tc) && self.decoder.config().unknown_type_code != UnknownTypeCodeMode::Error {
+            // Reads as null
+            self.decoder.decode_value_unchecked()?;
             visitor.visit_none()
         } else {
             visitor.visit_some(self)
@@ -218,6 +878,7 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         if self.decoder.peek_type_code()? == crate::types::type_code::NULL {
             self.decoder.skip_byte();
+            self.decoder.count_value();
             visitor.visit_unit()
         } else {
             Err(Error::Custom("expected null".into()))
@@ -234,25 +895,43 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
 
     fn deserialize_newtype_struct<V: Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value> {
+        // half::f16 and half::bf16 are encoded as plain numbers
+        #[cfg(feature = "half")]
+        if matches!(name, "f16" | "bf16") {
+            return visitor.visit_f64(self.decoder.decode_f64_direct()?);
+        }
+        // BigNumber and Value ask for an exact BigNumber; anything else is read as usual
+        if name == BIG_NUMBER_TOKEN || name == VALUE_TOKEN {
+            if self.decoder.peek_type_code()? != type_code::BIG_NUMBER {
+                return self.deserialize_any(visitor);
+            }
+            let DecodedValue::BigNumber(bn) = self.decoder.decode_value_unchecked()? else {
+                unreachable!("peeked a BigNumber")
+            };
+            if name == VALUE_TOKEN {
+                if let Some(s) = crate::stringify_big_number(self.decoder.config(), &bn)? {
+                    return visitor.visit_string(s);
+                }
+            }
+            return visitor.visit_map(BigNumberAccess(Some(bn)));
+        }
         visitor.visit_newtype_struct(self)
     }
 
     fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         let tc = self.decoder.peek_type_code()?;
         if crate::types::type_code::is_typed_array(tc) {
-            // Consume the type code and read count
-            self.decoder.skip_byte();
-            let remaining = self.decoder.remaining();
-            let (count_raw, consumed) = crate::types::leb128_decode(remaining)
-                .ok_or(Error::Truncated)?;
-            for _ in 0..consumed { self.decoder.skip_byte(); }
-            let count = count_raw as usize;
+            let (_, count) = self.decoder.read_typed_array_header()?;
             let seq = TypedArraySeqDeserializer::new_without_container(self, tc, count);
             return visitor.visit_seq(seq);
         }
+        if tc == type_code::OBJECT {
+            let seq = self.read_columns()?;
+            return visitor.visit_seq(seq);
+        }
         self.decoder.expect_array_start()?;
         let seq = SeqDeserializer::new(self);
         visitor.visit_seq(seq)
@@ -276,6 +955,13 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     }
 
     fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        // encode_value turns repeated objects into record instances, so maps accept them too
+        if self.decoder.peek_type_code()? == crate::types::type_code::RECORD_INSTANCE {
+            if let DecodedValue::RecordInstanceStart(def_index) = self.decoder.decode_value_unchecked()? {
+                let map = RecordMapDeserializer::new(self, def_index, None);
+                return visitor.visit_map(map);
+            }
+        }
         self.decoder.expect_object_start()?;
         let map = MapDeserializer::new(self);
         visitor.visit_map(map)
@@ -284,7 +970,7 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     fn deserialize_struct<V: Visitor<'de>>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
         let tc = self.decoder.peek_type_code()?;
@@ -292,15 +978,14 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
             // Consume the type code and read the definition index
             match self.decoder.decode_value_unchecked()? {
                 DecodedValue::RecordInstanceStart(def_index) => {
-                    let keys = self.decoder.record_definitions()[def_index].clone();
-                    let map = RecordMapDeserializer::new(self, keys);
+                    let map = RecordMapDeserializer::new(self, def_index, Some(fields));
                     visitor.visit_map(map)
                 }
                 _ => unreachable!(),
             }
         } else {
             self.decoder.expect_object_start()?;
-            let map = MapDeserializer::new(self);
+            let map = MapDeserializer::for_struct(self, fields);
             visitor.visit_map(map)
         }
     }
@@ -312,6 +997,10 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
         visitor: V,
     ) -> Result<V::Value> {
         use crate::types::type_code;
+        // A stringified NaN, Infinity or BigNumber names a unit variant
+        if let Some(s) = self.decode_stringified_number()? {
+            return visitor.visit_enum(de::value::StringDeserializer::new(s));
+        }
         let tc = self.decoder.peek_type_code()?;
         if type_code::is_any_string(tc) {
             visitor.visit_enum(UnitVariantDeserializer::new(self))
@@ -324,7 +1013,7 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     }
 
     fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_borrowed_str(self.decoder.decode_str_direct()?)
+        visitor.visit_borrowed_str(self.decode_str()?)
     }
 
     fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
@@ -334,11 +1023,12 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
 
 struct SeqDeserializer<'a, 'de> {
     de: &'a mut Deserializer<'de>,
+    index: usize,
 }
 
 impl<'a, 'de> SeqDeserializer<'a, 'de> {
     fn new(de: &'a mut Deserializer<'de>) -> Self {
-        SeqDeserializer { de }
+        SeqDeserializer { de, index: 0 }
     }
 }
 
@@ -349,20 +1039,193 @@ impl<'de> SeqAccess<'de> for SeqDeserializer<'_, 'de> {
         &mut self,
         seed: T,
     ) -> Result<Option<T::Value>> {
-        if self.de.decoder.try_consume_container_end()? {
+        if !self.de.decoder.skip_to_array_element()? {
             return Ok(None);
         }
-        seed.deserialize(&mut *self.de).map(Some)
+        self.de.decoder.begin_element(0)?;
+        let Some(tracker) = &mut self.de.tracker else {
+            return seed.deserialize(&mut *self.de).map(Some);
+        };
+        tracker.path.push_index(self.index);
+        self.index += 1;
+        let value = seed.deserialize(&mut *self.de)?;
+        if let Some(tracker) = &mut self.de.tracker {
+            tracker.path.pop();
+        }
+        Ok(Some(value))
+    }
+}
+
+/// Tracks which of a struct's fields have been seen, for the decode report.
+struct StructFields {
+    fields: &'static [&'static str],
+    seen: Vec<bool>,
+}
+
+impl StructFields {
+    fn new(fields: &'static [&'static str]) -> Self {
+        StructFields { fields, seen: vec![false; fields.len()] }
+    }
+
+    /// Mark a key as seen, returning false if the struct doesn't declare it.
+    fn see(&mut self, key: &str) -> bool {
+        match self.fields.iter().position(|f| *f == key) {
+            Some(i) => {
+                self.seen[i] = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn unseen(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.fields.iter().zip(&self.seen).filter(|(_, seen)| !**seen).map(|(f, _)| *f)
+    }
+}
+
+/// Record a key against the current struct (if any) and push it onto the path.
+fn track_key(tracker: &mut FieldTracker, fields: Option<&mut StructFields>, key: &str) {
+    if let Some(fields) = fields {
+        if !fields.see(key) {
+            let path = tracker.render(key);
+            tracker.report.unknown_fields.push(path);
+        }
+    }
+    tracker.path.push_key(key);
+}
+
+/// Report the struct's fields that never appeared in the document.
+fn track_struct_end(tracker: &mut FieldTracker, fields: Option<&StructFields>) {
+    if let Some(fields) = fields {
+        for field in fields.unseen() {
+            let path = tracker.render(field);
+            tracker.report.defaulted_fields.push(path);
+        }
+    }
+}
+
+/// A struct's fields, when `field_matching` or `field_aliases` may map keys onto them.
+fn loosely_matched(config: &DecoderConfig, fields: &'static [&'static str]) -> Option<&'static [&'static str]> {
+    let loose = config.field_matching != FieldMatching::Exact || !config.field_aliases.is_empty();
+    loose.then_some(fields)
+}
+
+/// The struct field that `key` names under `field_matching` and
+/// `field_aliases`, or None if it's a field as written or matches none.
+fn match_field(config: &DecoderConfig, fields: &'static [&'static str], key: &str) -> Option<&'static str> {
+    if fields.contains(&key) {
+        return None;
+    }
+    let mode = config.field_matching;
+    fields.iter().copied().find(|field| {
+        mode.matches(key, field)
+            || config
+                .field_aliases
+                .iter()
+                .any(|(a, b)| (b == field && mode.matches(key, a)) || (a == field && mode.matches(key, b)))
+    })
+}
+
+/// Probes per key in a [`DuplicateKeyCheck::Bloom`] filter.
+const BLOOM_HASHES: u64 = 3;
+
+/// Filter hits confirmed by rescanning, per object; later hits are reported
+/// as duplicates unconfirmed, so a saturated filter can't make decoding quadratic.
+const BLOOM_MAX_RESCANS: usize = 64;
+
+/// The keys of one object seen so far, under `DecoderConfig::duplicate_key_check`.
+enum SeenKeys {
+    Off,
+    Exact(HashSet<String>),
+    /// The filter (allocated at the first key), its size in bits, where the
+    /// object's members start, for confirming hits, and the hits confirmed so far.
+    Bloom { filter: Vec<u64>, bits: usize, members_start: usize, rescans: usize },
+}
+
+impl SeenKeys {
+    /// Start tracking the object whose members begin at the decoder's position.
+    fn new(decoder: &Decoder<'_>) -> Self {
+        match decoder.config().duplicate_key_check {
+            DuplicateKeyCheck::Off => SeenKeys::Off,
+            DuplicateKeyCheck::Exact => SeenKeys::Exact(HashSet::new()),
+            DuplicateKeyCheck::Bloom { bits } => {
+                SeenKeys::Bloom { filter: Vec::new(), bits: bits.max(1).div_ceil(64) * 64, members_start: decoder.position(), rescans: 0 }
+            }
+        }
+    }
+
+    /// Add `key`, read from `key_start`, failing if the object already has it.
+    fn insert(&mut self, decoder: &mut Decoder<'_>, key: &str, key_start: usize) -> Result<()> {
+        match self {
+            SeenKeys::Off => Ok(()),
+            SeenKeys::Exact(keys) => {
+                if keys.contains(key) {
+                    return Err(Error::DuplicateKey);
+                }
+                decoder.charge_allocation(key.len())?;
+                keys.insert(key.to_owned());
+                Ok(())
+            }
+            SeenKeys::Bloom { filter, bits, members_start, rescans } => {
+                if filter.is_empty() {
+                    decoder.charge_allocation(*bits / 8)?;
+                    filter.resize(*bits / 64, 0);
+                }
+                if bloom_insert(filter, key) {
+                    if *rescans == BLOOM_MAX_RESCANS {
+                        return Err(Error::DuplicateKey);
+                    }
+                    *rescans += 1;
+                    let config = decoder.config();
+                    if decoder.object_has_key(*members_start, key_start, |k| finish_key(config, k) == key)? {
+                        return Err(Error::DuplicateKey);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Set `key`'s bits in `filter`, returning whether they were all set already.
+fn bloom_insert(filter: &mut [u64], key: &str) -> bool {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    let hash = hasher.finish();
+    // Double hashing: probe i is h1 + i*h2
+    let (h1, h2) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+    let bits = filter.len() as u64 * 64;
+    let mut present = true;
+    for i in 0..BLOOM_HASHES {
+        let bit = h1.wrapping_add(i.wrapping_mul(h2)) % bits;
+        #[allow(clippy::cast_possible_truncation)]
+        let (word, mask) = ((bit / 64) as usize, 1u64 << (bit % 64));
+        present &= filter[word] & mask != 0;
+        filter[word] |= mask;
     }
+    present
 }
 
 struct MapDeserializer<'a, 'de> {
     de: &'a mut Deserializer<'de>,
+    /// Present when deserializing a struct and field tracking is enabled.
+    fields: Option<StructFields>,
+    /// The struct's fields, when keys are matched to them loosely.
+    names: Option<&'static [&'static str]>,
+    seen: SeenKeys,
 }
 
 impl<'a, 'de> MapDeserializer<'a, 'de> {
     fn new(de: &'a mut Deserializer<'de>) -> Self {
-        MapDeserializer { de }
+        let seen = SeenKeys::new(&de.decoder);
+        MapDeserializer { de, fields: None, names: None, seen }
+    }
+
+    fn for_struct(de: &'a mut Deserializer<'de>, fields: &'static [&'static str]) -> Self {
+        let names = loosely_matched(de.decoder.config(), fields);
+        let fields = de.tracker.as_ref().map(|_| StructFields::new(fields));
+        let seen = SeenKeys::new(&de.decoder);
+        MapDeserializer { de, fields, names, seen }
     }
 }
 
@@ -370,14 +1233,43 @@ impl<'de> MapAccess<'de> for MapDeserializer<'_, 'de> {
     type Error = Error;
 
     fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
-        if self.de.decoder.try_consume_container_end()? {
-            return Ok(None);
+        let (key, key_start) = loop {
+            if self.de.decoder.try_consume_container_end()? {
+                if let Some(tracker) = &mut self.de.tracker {
+                    track_struct_end(tracker, self.fields.as_ref());
+                }
+                return Ok(None);
+            }
+            let key_start = self.de.decoder.position();
+            self.de.decoder.begin_element(0)?;
+            let key = self.de.decode_str()?;
+            // Members whose value has a reserved type code may be dropped
+            if !self.de.decoder.skip_unknown_value()? {
+                break (key, key_start);
+            }
+        };
+        let key = finish_key(self.de.decoder.config(), key);
+        self.seen.insert(&mut self.de.decoder, &key, key_start)?;
+        let config = self.de.decoder.config();
+        let key = match self.names.and_then(|names| match_field(config, names, &key)) {
+            Some(field) => Cow::Borrowed(field),
+            None => key,
+        };
+        if let Some(tracker) = &mut self.de.tracker {
+            track_key(tracker, self.fields.as_mut(), &key);
+        }
+        match key {
+            Cow::Borrowed(key) => seed.deserialize(MapKeyDeserializer::borrowed(key)).map(Some),
+            Cow::Owned(key) => seed.deserialize(MapKeyDeserializer::transient(&key)).map(Some),
         }
-        seed.deserialize(&mut *self.de).map(Some)
     }
 
     fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
-        seed.deserialize(&mut *self.de)
+        let value = seed.deserialize(&mut *self.de)?;
+        if let Some(tracker) = &mut self.de.tracker {
+            tracker.path.pop();
+        }
+        Ok(value)
     }
 }
 
@@ -412,28 +1304,347 @@ impl<'de> SeqAccess<'de> for TypedArraySeqDeserializer<'_, 'de> {
             return Ok(None);
         }
         self.remaining -= 1;
+        self.de.decoder.begin_element(0)?;
         // Read the element and deserialize it inline
-        let elem = self.de.decoder.read_typed_array_element(self.element_type_code)?;
-        let value = match elem {
-            DecodedValue::Int(n) => seed.deserialize(serde::de::value::I64Deserializer::new(n)),
-            DecodedValue::UInt(n) => seed.deserialize(serde::de::value::U64Deserializer::new(n)),
-            DecodedValue::Float(f) => seed.deserialize(serde::de::value::F64Deserializer::new(f)),
-            _ => unreachable!(),
-        }.map_err(|_: serde::de::value::Error| Error::Custom("typed array element deserialization failed".into()))?;
-        Ok(Some(value))
+        let element = self.de.decoder.read_typed_element(self.element_type_code)?;
+        deserialize_typed_element(seed, element, ElementModes::of(self.de.decoder.config())).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+/// Reassembles rows from columns read by [`Deserializer::read_columns`].
+struct ColumnarSeqDeserializer<'de> {
+    keys: Vec<&'de str>,
+    columns: Vec<std::vec::IntoIter<TypedElement>>,
+    remaining: usize,
+    modes: ElementModes,
+}
+
+impl<'de> SeqAccess<'de> for ColumnarSeqDeserializer<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        let row = ColumnarRowDeserializer {
+            keys: &self.keys,
+            elements: self.columns.iter_mut().filter_map(Iterator::next).collect(),
+            modes: self.modes,
+        };
+        seed.deserialize(row).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+/// One row of a columnar sequence, presented as a map of its fields.
+struct ColumnarRowDeserializer<'a, 'de> {
+    keys: &'a [&'de str],
+    elements: Vec<TypedElement>,
+    modes: ElementModes,
+}
+
+impl<'de> de::Deserializer<'de> for ColumnarRowDeserializer<'_, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(ColumnarRowAccess {
+            keys: self.keys.iter(),
+            elements: self.elements.into_iter(),
+            modes: self.modes,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct ColumnarRowAccess<'a, 'de> {
+    keys: std::slice::Iter<'a, &'de str>,
+    elements: std::vec::IntoIter<TypedElement>,
+    modes: ElementModes,
+}
+
+impl<'de> MapAccess<'de> for ColumnarRowAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.keys.next() {
+            Some(key) => seed.deserialize(MapKeyDeserializer::borrowed(key)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let element = self.elements.next().ok_or(Error::ExpectedObjectValue)?;
+        deserialize_typed_element(seed, element, self.modes)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+/// The `DecoderConfig` settings that shape typed array elements, copied out
+/// so that columnar rows don't borrow the decoder.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ElementModes {
+    nan_infinity_mode: NanInfinityMode,
+    untagged_numeric_coercion: bool,
+}
+
+impl ElementModes {
+    pub(crate) fn of(config: &DecoderConfig) -> Self {
+        Self { nan_infinity_mode: config.nan_infinity_mode, untagged_numeric_coercion: config.untagged_numeric_coercion }
+    }
+}
+
+/// Deserialize a typed array element into whatever numeric type the target asks
+/// for. NaN and Infinity have already been checked against `nan_infinity_mode`;
+/// under `Stringify`, they become strings for string and self-describing targets.
+pub(crate) fn deserialize_typed_element<'de, T: DeserializeSeed<'de>>(
+    seed: T,
+    element: TypedElement,
+    modes: ElementModes,
+) -> Result<T::Value> {
+    seed.deserialize(TypedElementDeserializer { element, modes })
+}
+
+/// Narrow a float to `f32`, failing if a finite value overflows it.
+#[allow(clippy::cast_possible_truncation)]
+fn narrow_to_f32(value: f64) -> Result<f32> {
+    let narrowed = value as f32;
+    if narrowed.is_infinite() && value.is_finite() {
+        return Err(Error::ValueOutOfRange);
+    }
+    Ok(narrowed)
+}
+
+/// Hands an exact BigNumber to a `BigNumber` or `Value` visitor, as a
+/// one-entry map from [`BIG_NUMBER_TOKEN`] to its decimal string.
+struct BigNumberAccess(Option<BigNumber>);
+
+impl<'de> MapAccess<'de> for BigNumberAccess {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.0.is_none() {
+            return Ok(None);
+        }
+        seed.deserialize(MapKeyDeserializer::borrowed(BIG_NUMBER_TOKEN)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let bn = self.0.take().ok_or_else(|| Error::Custom("BigNumber already read".into()))?;
+        seed.deserialize(de::value::StringDeserializer::new(bn.to_string()))
+    }
+}
+
+/// Converts one typed array element straight to the requested type: integers
+/// widen freely and narrow with a range check, integers convert to floats,
+/// float32 elements reach `f32` without an f64 round trip, and float64 narrows
+/// to `f32` unless the value overflows it. Floats don't convert to integers.
+struct TypedElementDeserializer {
+    element: TypedElement,
+    modes: ElementModes,
+}
+
+impl TypedElementDeserializer {
+    fn int<T: TryFrom<i64> + TryFrom<u64>>(self) -> Result<T> {
+        match self.element {
+            TypedElement::Int(n) => T::try_from(n).map_err(|_| Error::ValueOutOfRange),
+            TypedElement::UInt(n) => T::try_from(n).map_err(|_| Error::ValueOutOfRange),
+            TypedElement::F32(_) | TypedElement::F64(_) => {
+                Err(Error::Custom("expected integer, got float typed array element".into()))
+            }
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn f32(self) -> Result<f32> {
+        match self.element {
+            TypedElement::Int(n) => Ok(n as f32),
+            TypedElement::UInt(n) => Ok(n as f32),
+            TypedElement::F32(f) => Ok(f),
+            TypedElement::F64(f) => narrow_to_f32(f),
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn f64(self) -> f64 {
+        match self.element {
+            TypedElement::Int(n) => n as f64,
+            TypedElement::UInt(n) => n as f64,
+            TypedElement::F32(f) => f64::from(f),
+            TypedElement::F64(f) => f,
+        }
+    }
+
+    /// The string a NaN or Infinity element becomes under `Stringify`.
+    fn stringified(&self) -> Option<&'static str> {
+        match self.element {
+            TypedElement::F32(f) => self.modes.nan_infinity_mode.stringify(f64::from(f)),
+            TypedElement::F64(f) => self.modes.nan_infinity_mode.stringify(f),
+            TypedElement::Int(_) | TypedElement::UInt(_) => None,
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for TypedElementDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if let Some(name) = self.stringified() {
+            return visitor.visit_borrowed_str(name);
+        }
+        let coerce = self.modes.untagged_numeric_coercion;
+        match self.element {
+            TypedElement::Int(n) => match u64::try_from(n) {
+                Ok(u) if coerce => visitor.visit_u64(u),
+                _ => visitor.visit_i64(n),
+            },
+            TypedElement::UInt(n) => visitor.visit_u64(n),
+            TypedElement::F32(f) if coerce => visitor.visit_f64(f64::from(f)),
+            TypedElement::F32(f) => visitor.visit_f32(f),
+            TypedElement::F64(f) => visitor.visit_f64(f),
+        }
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i8(self.int()?)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i16(self.int()?)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i32(self.int()?)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.int()?)
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i128(self.int()?)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u8(self.int()?)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u16(self.int()?)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u32(self.int()?)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u64(self.int()?)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u128(self.int()?)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f32(self.f32()?)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f64(self.f64())
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        // half::f16 and half::bf16 are encoded as plain numbers
+        #[cfg(feature = "half")]
+        if matches!(_name, "f16" | "bf16") {
+            return visitor.visit_f64(self.f64());
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
     }
 }
 
 struct RecordMapDeserializer<'a, 'de> {
     de: &'a mut Deserializer<'de>,
-    keys: Vec<String>,
+    def_index: usize,
+    /// All record definitions, shared with the decoder rather than copying this one's keys per instance.
+    definitions: Arc<Vec<Vec<String>>>,
     index: usize,
-    serving_key: bool,
+    /// Container end was reached early; remaining keys are served with null values.
+    null_filling: bool,
+    /// Present when field tracking is enabled.
+    fields: Option<StructFields>,
+    /// The struct's fields, when keys are matched to them loosely.
+    names: Option<&'static [&'static str]>,
 }
 
 impl<'a, 'de> RecordMapDeserializer<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>, keys: Vec<String>) -> Self {
-        RecordMapDeserializer { de, keys, index: 0, serving_key: true }
+    fn new(
+        de: &'a mut Deserializer<'de>,
+        def_index: usize,
+        fields: Option<&'static [&'static str]>,
+    ) -> Self {
+        let definitions = de.decoder.shared_record_definitions();
+        let names = fields.and_then(|fields| loosely_matched(de.decoder.config(), fields));
+        let fields = fields.filter(|_| de.tracker.is_some()).map(StructFields::new);
+        RecordMapDeserializer { de, def_index, definitions, index: 0, null_filling: false, fields, names }
+    }
+
+    fn key_count(&self) -> usize {
+        self.definitions[self.def_index].len()
+    }
+
+    fn end(&mut self) {
+        if let Some(tracker) = &mut self.de.tracker {
+            track_struct_end(tracker, self.fields.as_ref());
+        }
+    }
+
+    fn mismatch(&self, values: usize) -> Error {
+        Error::RecordMismatch { definition: self.def_index, keys: self.key_count(), values }
+    }
+
+    /// Consume any values beyond the definition's keys, plus the end marker.
+    fn finish_extra_values(&mut self) -> Result<()> {
+        let mut extra_count = 0;
+        while !self.de.decoder.try_consume_container_end()? {
+            if self.key_count() + extra_count >= self.de.decoder.config().max_container_size {
+                return Err(Error::MaxContainerSizeExceeded);
+            }
+            self.de.decoder.begin_element(0)?;
+            de::IgnoredAny::deserialize(&mut *self.de)?;
+            extra_count += 1;
+        }
+        if extra_count > 0 && self.de.decoder.config().record_mismatch != RecordMismatchMode::Truncate {
+            return Err(self.mismatch(self.key_count() + extra_count));
+        }
+        Ok(())
     }
 }
 
@@ -441,26 +1652,160 @@ impl<'de> MapAccess<'de> for RecordMapDeserializer<'_, 'de> {
     type Error = Error;
 
     fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
-        // Check if we hit container end (fewer values than keys)
-        if self.de.decoder.try_consume_container_end()? {
-            return Ok(None);
+        loop {
+            if self.index >= self.key_count() {
+                if !self.null_filling {
+                    self.finish_extra_values()?;
+                }
+                self.end();
+                return Ok(None);
+            }
+            // Check if we hit container end (fewer values than keys)
+            if !self.null_filling && self.de.decoder.try_consume_container_end()? {
+                match self.de.decoder.config().record_mismatch {
+                    RecordMismatchMode::Error => return Err(self.mismatch(self.index)),
+                    RecordMismatchMode::Truncate => {
+                        self.end();
+                        return Ok(None);
+                    }
+                    RecordMismatchMode::NullFill => self.null_filling = true,
+                }
+            }
+            // Values with reserved type codes may be dropped, omitting their key
+            if self.null_filling || !self.de.decoder.skip_unknown_value()? {
+                break;
+            }
+            self.index += 1;
         }
-        if self.index >= self.keys.len() {
-            // Consume remaining values + end marker
-            self.de.decoder.try_consume_container_end()?;
-            return Ok(None);
+        let key = &self.definitions[self.def_index][self.index];
+        self.de.decoder.begin_element(key.len())?;
+        let config = self.de.decoder.config();
+        let key = finish_key(config, key);
+        let key = match self.names.and_then(|names| match_field(config, names, &key)) {
+            Some(field) => Cow::Borrowed(field),
+            None => key,
+        };
+        if let Some(tracker) = &mut self.de.tracker {
+            track_key(tracker, self.fields.as_mut(), &key);
         }
-        let key = &self.keys[self.index];
-        self.serving_key = false;
-        seed.deserialize(serde::de::value::StrDeserializer::new(key))
-            .map(Some)
-            .map_err(|_: serde::de::value::Error| Error::Custom("record key deserialization failed".into()))
+        seed.deserialize(MapKeyDeserializer::transient(&key)).map(Some)
     }
 
     fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
         self.index += 1;
-        self.serving_key = true;
-        seed.deserialize(&mut *self.de)
+        let value = if self.null_filling {
+            seed.deserialize(serde::de::value::UnitDeserializer::<Error>::new())?
+        } else {
+            seed.deserialize(&mut *self.de)?
+        };
+        if let Some(tracker) = &mut self.de.tracker {
+            tracker.path.pop();
+        }
+        Ok(value)
+    }
+}
+
+/// An object key after normalization and the configured `key_transform`, as
+/// `decode_value` gives it.
+fn finish_key<'k>(config: &DecoderConfig, key: &'k str) -> Cow<'k, str> {
+    match (crate::nfc_normalize_borrowed(config.unicode_normalization, key), &config.key_transform) {
+        (Cow::Borrowed(key), Some(transform)) => transform.apply(key),
+        (Cow::Owned(key), Some(transform)) => Cow::Owned(transform.apply_owned(key)),
+        (key, None) => key,
+    }
+}
+
+/// Deserializes an object key. Keys are always strings on the wire; integer,
+/// float, bool and char key types (as in `HashMap<u32, T>`) are parsed from
+/// the string forms that the serializer writes for them.
+struct MapKeyDeserializer<'a, 'de> {
+    key: &'a str,
+    /// The same key with the input's lifetime, when it's borrowed from the input.
+    borrowed: Option<&'de str>,
+}
+
+impl<'de> MapKeyDeserializer<'de, 'de> {
+    fn borrowed(key: &'de str) -> Self {
+        MapKeyDeserializer { key, borrowed: Some(key) }
+    }
+}
+
+impl<'a> MapKeyDeserializer<'a, '_> {
+    fn transient(key: &'a str) -> Self {
+        MapKeyDeserializer { key, borrowed: None }
+    }
+
+    fn parse<T: std::str::FromStr>(&self, expected: &str) -> Result<T> {
+        self.key
+            .parse()
+            .map_err(|_| Error::Custom(format!("map key `{}` is not a valid {expected}", self.key)))
+    }
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $ty:ty, $visit:ident, $expected:literal;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                visitor.$visit(self.parse::<$ty>($expected)?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for MapKeyDeserializer<'_, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.borrowed {
+            Some(key) => visitor.visit_borrowed_str(key),
+            None => visitor.visit_str(self.key),
+        }
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => bool, visit_bool, "bool";
+        deserialize_i8 => i8, visit_i8, "i8";
+        deserialize_i16 => i16, visit_i16, "i16";
+        deserialize_i32 => i32, visit_i32, "i32";
+        deserialize_i64 => i64, visit_i64, "i64";
+        deserialize_i128 => i128, visit_i128, "i128";
+        deserialize_u8 => u8, visit_u8, "u8";
+        deserialize_u16 => u16, visit_u16, "u16";
+        deserialize_u32 => u32, visit_u32, "u32";
+        deserialize_u64 => u64, visit_u64, "u64";
+        deserialize_u128 => u128, visit_u128, "u128";
+        deserialize_f32 => f32, visit_f32, "f32";
+        deserialize_f64 => f64, visit_f64, "f64";
+        deserialize_char => char, visit_char, "char";
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.borrowed {
+            Some(key) => visitor.visit_enum(de::value::BorrowedStrDeserializer::new(key)),
+            None => visitor.visit_enum(de::value::StrDeserializer::new(self.key)),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
     }
 }
 
@@ -553,11 +1898,11 @@ impl<'de> de::VariantAccess<'de> for EnumDeserializer<'_, 'de> {
 
     fn struct_variant<V: Visitor<'de>>(
         self,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
         self.de.decoder.expect_object_start()?;
-        let map = MapDeserializer::new(self.de);
+        let map = MapDeserializer::for_struct(self.de, fields);
         let value = visitor.visit_map(map)?;
         // Consume the outer object's end marker
         self.de.decoder.try_consume_container_end()?;
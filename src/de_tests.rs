@@ -330,3 +330,1232 @@ fn test_from_slice_with_config_allow_nul() {
     let result: String = crate::from_slice_with_config(&bytes, config).unwrap();
     assert_eq!(result, "a\0b");
 }
+
+#[test]
+fn test_string_validation_modes_agree() {
+    use crate::decoder::{DecoderConfig, InvalidUtf8Mode, StringValidation};
+    use crate::Error;
+
+    // Patch the last bytes of an encoded string's contents, which long
+    // strings follow with a 0xFF terminator
+    fn encoded(text: &str, tail: &[u8]) -> Vec<u8> {
+        let mut bytes = crate::to_vec(&text).unwrap();
+        let end = bytes.len() - usize::from(bytes[0] == 0xff);
+        let start = end - tail.len();
+        bytes[start..end].copy_from_slice(tail);
+        bytes
+    }
+
+    let modes = [StringValidation::Auto, StringValidation::Combined, StringValidation::Separate];
+    for len in [1, 7, 8, 9, 15, 16, 17, 64, 65, 300] {
+        let ascii = "a".repeat(len);
+        let unicode = format!("{}é", "a".repeat(len));
+        let cases: [(Vec<u8>, std::result::Result<&str, Error>); 6] = [
+            (encoded(&ascii, &[]), Ok(&ascii)),
+            (encoded(&unicode, &[]), Ok(&unicode)),
+            (encoded(&ascii, &[0]), Err(Error::NulCharacter)),
+            (encoded(&unicode, &[0, 0xa9]), Err(Error::InvalidUtf8)),
+            (encoded(&unicode, &[0xc3, 0]), Err(Error::InvalidUtf8)),
+            (encoded(&format!("{unicode}a"), &[0]), Err(Error::NulCharacter)),
+        ];
+        for (bytes, expected) in &cases {
+            for mode in modes {
+                let config = DecoderConfig { string_validation: mode, ..Default::default() };
+                let result = crate::from_slice_with_config::<&str>(bytes, config);
+                assert_eq!(result, *expected, "{mode:?} at length {len}");
+            }
+        }
+
+        // Strings decoded with replacement are still checked for NUL
+        let bytes = encoded(&unicode, &[0, 0xa9]);
+        for mode in modes {
+            let config = DecoderConfig {
+                string_validation: mode,
+                invalid_utf8_mode: InvalidUtf8Mode::Replace,
+                ..Default::default()
+            };
+            let result = crate::decode_value_with_config(&bytes, config);
+            assert_eq!(result, Err(Error::NulCharacter), "{mode:?} at length {len}");
+        }
+    }
+}
+
+// =========================================================================
+// 128-bit integer tests
+// =========================================================================
+
+#[test]
+fn test_deserialize_i128_out_of_range() {
+    // BigNumber 1e39 exceeds both i128 and u128
+    let bytes = crate::encode_value(&crate::Value::BigNumber(crate::BigNumber::new(1, 1, 39))).unwrap();
+    assert!(matches!(from_slice::<i128>(&bytes), Err(crate::Error::ValueOutOfRange)));
+    assert!(matches!(from_slice::<u128>(&bytes), Err(crate::Error::ValueOutOfRange)));
+
+    // Negative values don't fit u128
+    let bytes = crate::to_vec(&-1i128).unwrap();
+    assert!(matches!(from_slice::<u128>(&bytes), Err(crate::Error::ValueOutOfRange)));
+
+    // Fractional BigNumbers are not integers
+    let bytes = crate::encode_value(&crate::Value::BigNumber(crate::BigNumber::new(1, 15, -1))).unwrap();
+    assert!(from_slice::<i128>(&bytes).is_err());
+
+    // Magnitudes wider than 16 bytes can't be a 128-bit integer
+    let mut bytes = vec![crate::types::type_code::BIG_NUMBER, 0, 34];
+    bytes.extend_from_slice(&[0xff; 17]);
+    assert!(from_slice::<u128>(&bytes).is_err());
+
+    // Wide magnitudes only decode into the 128-bit targets, not into a Value
+    let bytes = crate::to_vec(&u128::MAX).unwrap();
+    assert!(crate::decode_value(&bytes).is_err());
+}
+
+#[test]
+fn test_deserialize_i128_into_narrower_type() {
+    let bytes = crate::to_vec(&(i128::from(u64::MAX) * 10)).unwrap();
+    assert!(from_slice::<u64>(&bytes).is_err());
+    assert!(from_slice::<i64>(&bytes).is_err());
+}
+
+// =========================================================================
+// Record mismatch tests
+// =========================================================================
+
+// Record definition ["a", "b"] followed by one instance with the given values
+fn record_with_values(values: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0xb9, 0x66, b'a', 0x66, b'b', 0xb6, 0xba, 0x00];
+    bytes.extend_from_slice(values);
+    bytes.push(0xb6);
+    bytes
+}
+
+fn record_mismatch_config(mode: crate::RecordMismatchMode) -> crate::DecoderConfig {
+    crate::DecoderConfig {
+        record_mismatch: mode,
+        ..Default::default()
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct RecordAB {
+    a: i32,
+    #[serde(default)]
+    b: Option<i32>,
+}
+
+#[test]
+fn test_record_fewer_values_null_fill() {
+    let bytes = record_with_values(&[0x01]);
+    let decoded: RecordAB = from_slice(&bytes).unwrap();
+    assert_eq!(decoded, RecordAB { a: 1, b: None });
+
+    // Null-filled fields are real nulls, not missing fields
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Strict {
+        a: i32,
+        #[serde(default)]
+        b: i32,
+    }
+    assert!(from_slice::<Strict>(&bytes).is_err());
+}
+
+#[test]
+fn test_record_fewer_values_truncate() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct WithDefault {
+        a: i32,
+        #[serde(default)]
+        b: i32,
+    }
+
+    let bytes = record_with_values(&[0x01]);
+    let config = record_mismatch_config(crate::RecordMismatchMode::Truncate);
+    let decoded: WithDefault = crate::from_slice_with_config(&bytes, config).unwrap();
+    assert_eq!(decoded, WithDefault { a: 1, b: 0 });
+}
+
+#[test]
+fn test_record_fewer_values_error() {
+    let bytes = record_with_values(&[0x01]);
+    let config = record_mismatch_config(crate::RecordMismatchMode::Error);
+    let err = crate::from_slice_with_config::<RecordAB>(&bytes, config).unwrap_err();
+    assert_eq!(err, crate::Error::RecordMismatch { definition: 0, keys: 2, values: 1 });
+}
+
+#[test]
+fn test_record_extra_values() {
+    let bytes = record_with_values(&[0x01, 0x02, 0x03, 0x04]);
+
+    // Extra values are an error unless truncating
+    for mode in [crate::RecordMismatchMode::NullFill, crate::RecordMismatchMode::Error] {
+        let err = crate::from_slice_with_config::<RecordAB>(&bytes, record_mismatch_config(mode))
+            .unwrap_err();
+        assert_eq!(err, crate::Error::RecordMismatch { definition: 0, keys: 2, values: 4 });
+    }
+
+    let config = record_mismatch_config(crate::RecordMismatchMode::Truncate);
+    let decoded: RecordAB = crate::from_slice_with_config(&bytes, config).unwrap();
+    assert_eq!(decoded, RecordAB { a: 1, b: Some(2) });
+}
+
+// ============================================================================
+// Unknown field tracking tests
+// ============================================================================
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Item {
+    id: i32,
+    #[serde(default)]
+    label: String,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Order {
+    name: String,
+    items: Vec<Item>,
+}
+
+fn order_bytes() -> Vec<u8> {
+    crate::to_vec(&serde_json::json!({
+        "name": "x",
+        "items": [{"id": 1, "label": "a"}, {"id": 2, "color": "red"}],
+        "extra": {"nested": true}
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_report_unknown_and_defaulted_fields() {
+    let (order, report): (Order, _) =
+        crate::from_slice_with_report(&order_bytes(), crate::DecoderConfig::default()).unwrap();
+    assert_eq!(order.items[1], Item { id: 2, label: String::new() });
+    assert_eq!(report.unknown_fields, vec!["$.extra", "$.items[1].color"]);
+    assert_eq!(report.defaulted_fields, vec!["$.items[1].label"]);
+}
+
+#[test]
+fn test_report_clean_document() {
+    let bytes = crate::to_vec(&serde_json::json!({"id": 1, "label": "a"})).unwrap();
+    let (_, report): (Item, _) =
+        crate::from_slice_with_report(&bytes, crate::DecoderConfig::default()).unwrap();
+    assert_eq!(report, crate::DecodeReport::default());
+}
+
+#[test]
+fn test_report_map_values_contribute_to_path() {
+    let bytes = crate::to_vec(&serde_json::json!({"k": {"id": 1, "zz": 0}})).unwrap();
+    let (_, report): (std::collections::HashMap<String, Item>, _) =
+        crate::from_slice_with_report(&bytes, crate::DecoderConfig::default()).unwrap();
+    assert_eq!(report.unknown_fields, vec!["$.k.zz"]);
+
+    // Keys that aren't identifiers are quoted, so paths stay unambiguous
+    let bytes = crate::to_vec(&serde_json::json!({"a.b": {"id": 1, "x y": 0}, "it's": {"id": 2, "[0]": 0}})).unwrap();
+    let (_, report): (std::collections::BTreeMap<String, Item>, _) =
+        crate::from_slice_with_report(&bytes, crate::DecoderConfig::default()).unwrap();
+    assert_eq!(report.unknown_fields, vec!["$['a.b']['x y']", "$['it\\'s']['[0]']"]);
+}
+
+#[test]
+fn test_deny_unknown_fields_globally() {
+    let config = crate::DecoderConfig { deny_unknown_fields_globally: true, ..Default::default() };
+    let err = crate::from_slice_with_config::<Order>(&order_bytes(), config.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        crate::Error::UnknownFields(vec!["$.extra".into(), "$.items[1].color".into()])
+    );
+    assert!(crate::from_slice_with_report::<Order>(&order_bytes(), config).is_err());
+
+    // Without the option, unknown fields are ignored as usual
+    assert!(crate::from_slice_with_config::<Order>(&order_bytes(), crate::DecoderConfig::default()).is_ok());
+}
+
+#[test]
+fn test_report_record_instances() {
+    let config = crate::SerializerConfig { records: true, ..Default::default() };
+    #[derive(serde::Serialize)]
+    struct Wide {
+        id: i32,
+        size: i32,
+    }
+    let bytes = crate::to_vec_with_config(&vec![Wide { id: 1, size: 2 }, Wide { id: 3, size: 4 }], &config).unwrap();
+    assert_eq!(bytes[0], crate::type_code::RECORD_DEF);
+
+    let (items, report): (Vec<Item>, _) =
+        crate::from_slice_with_report(&bytes, crate::DecoderConfig::default()).unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(report.unknown_fields, vec!["$[0].size", "$[1].size"]);
+    assert_eq!(report.defaulted_fields, vec!["$[0].label", "$[1].label"]);
+}
+
+#[test]
+fn test_report_accepts_aliases() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Aliased {
+        #[serde(alias = "nm")]
+        name: String,
+    }
+    let bytes = crate::to_vec(&serde_json::json!({"nm": "Alice"})).unwrap();
+    let (_, report): (Aliased, _) =
+        crate::from_slice_with_report(&bytes, crate::DecoderConfig::default()).unwrap();
+    // The alias is a known field; the primary name counts as absent
+    assert!(report.unknown_fields.is_empty());
+    assert_eq!(report.defaulted_fields, vec!["$.name"]);
+}
+
+// ============================================================================
+// Streaming element tests
+// ============================================================================
+
+#[test]
+fn test_for_each_element() {
+    #[derive(Deserialize)]
+    struct Sample {
+        v: f64,
+    }
+    let bytes = crate::to_vec(&serde_json::json!({
+        "a": {"skip": [1, 2, {"deep": true}]},
+        "metrics": {"name": "cpu", "samples": [{"v": 1.5}, {"v": 2.5}, {"v": 4.0}]},
+        "z": "after"
+    }))
+    .unwrap();
+
+    let mut total = 0.0;
+    let count = crate::for_each_element(&bytes, "$.metrics.samples", |s: Sample| total += s.v).unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(total, 8.0);
+
+    // Heterogeneous elements can be visited as Values
+    let mut seen = Vec::new();
+    crate::for_each_element(&bytes, "$['a'].skip", |v: crate::Value| seen.push(v)).unwrap();
+    assert_eq!(seen[2], crate::bonjson!({"deep": true}));
+
+    // A path that ends at a non-array is an error
+    assert!(crate::for_each_element(&bytes, "$.a.skip[2]", |_: serde::de::IgnoredAny| {}).is_err());
+}
+
+#[test]
+fn test_for_each_element_typed_array_and_root() {
+    let bytes = crate::to_vec(&vec![0.1f64, 0.2, 0.3]).unwrap();
+    assert_eq!(bytes[0], crate::type_code::TYPED_ARRAY_FLOAT64);
+    let mut values = Vec::new();
+    let count = crate::for_each_element(&bytes, "$", |f: f64| values.push(f)).unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(values, vec![0.1, 0.2, 0.3]);
+}
+
+#[test]
+fn test_for_each_element_through_records() {
+    #[derive(serde::Serialize)]
+    struct Series {
+        name: &'static str,
+        points: Vec<i32>,
+    }
+    let config = crate::SerializerConfig { records: true, ..Default::default() };
+    let series = vec![
+        Series { name: "a", points: vec![1, 2] },
+        Series { name: "b", points: vec![300, -400, 500] },
+    ];
+    let bytes = crate::to_vec_with_config(&series, &config).unwrap();
+    assert_eq!(bytes[0], crate::type_code::RECORD_DEF);
+
+    let mut sum = 0;
+    let count = crate::for_each_element(&bytes, "$[1].points", |n: i32| sum += n).unwrap();
+    assert_eq!((count, sum), (3, 400));
+}
+
+#[test]
+fn test_for_each_element_errors() {
+    let bytes = crate::to_vec(&serde_json::json!({"a": [1, 2], "b": 5})).unwrap();
+    let ignore = |_: i32| {};
+    assert!(crate::for_each_element(&bytes, "$.missing", ignore).is_err());
+    assert!(crate::for_each_element(&bytes, "$.b", ignore).is_err());
+    assert!(crate::for_each_element(&bytes, "$.a[5]", ignore).is_err());
+    let err = crate::for_each_element(&bytes, "$..a", ignore).unwrap_err();
+    assert_eq!(err.error_type(), "invalid_query");
+
+    // Element type mismatch surfaces as a deserialization error
+    let bytes = crate::to_vec(&serde_json::json!({"a": [1, "two"]})).unwrap();
+    assert!(crate::for_each_element(&bytes, "$.a", ignore).is_err());
+}
+
+#[test]
+fn test_for_each_element_skips_unknown_type_codes() {
+    use crate::{DecoderConfig, UnknownTypeCodeMode};
+
+    // [1, <0xd0, empty>, 2, <0xd0 with a 1-byte payload>]
+    let bytes = [0xb7, 0x01, 0xd0, 0x00, 0x02, 0xd0, 0x01, 0xff, 0xb6];
+    let config = DecoderConfig { unknown_type_code: UnknownTypeCodeMode::SkipValue, ..Default::default() };
+    let mut seen = Vec::new();
+    let count = crate::for_each_element_with_config(&bytes, "$", config, |n: u8| seen.push(n)).unwrap();
+    assert_eq!((count, seen), (2, vec![1, 2]));
+    assert!(crate::for_each_element(&bytes, "$", |_: crate::Value| {}).is_err());
+}
+
+#[test]
+fn test_from_slice_max_total_allocated_bytes() {
+    use crate::{from_slice_with_config, DecoderConfig, Error};
+
+    let strings: Vec<String> = (0..10).map(|i| format!("{i:0>100}")).collect();
+    let bytes = crate::to_vec(&strings).unwrap();
+    let config = |limit| DecoderConfig { max_total_allocated_bytes: limit, ..Default::default() };
+
+    let err = from_slice_with_config::<Vec<String>>(&bytes, config(500)).unwrap_err();
+    assert_eq!(err, Error::MaxTotalAllocatedBytesExceeded);
+    assert_eq!(from_slice_with_config::<Vec<String>>(&bytes, config(2000)).unwrap(), strings);
+
+    // Record keys are charged for every instance, since each may be copied
+    #[derive(serde::Serialize, Deserialize)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    let points: Vec<Point> = (0..100).map(|i| Point { x: i, y: i }).collect();
+    let records = crate::SerializerConfig { records: true, ..Default::default() };
+    let bytes = crate::to_vec_with_config(&points, &records).unwrap();
+    assert!(from_slice_with_config::<Vec<Point>>(&bytes, config(1000)).is_err());
+    assert!(from_slice_with_config::<Vec<Point>>(&bytes, DecoderConfig::default()).is_ok());
+}
+
+#[test]
+fn test_non_string_map_keys() {
+    use std::collections::{BTreeMap, HashMap};
+
+    let ids: HashMap<u32, String> = HashMap::from([(7, "seven".into()), (4_000_000_000, "big".into())]);
+    let bytes = crate::to_vec(&ids).unwrap();
+    assert_eq!(from_slice::<HashMap<u32, String>>(&bytes).unwrap(), ids);
+
+    let signed: BTreeMap<i64, bool> = BTreeMap::from([(-3, true), (i64::MAX, false)]);
+    assert_eq!(from_slice::<BTreeMap<i64, bool>>(&crate::to_vec(&signed).unwrap()).unwrap(), signed);
+
+    let chars: BTreeMap<char, u8> = BTreeMap::from([('x', 1), ('é', 2)]);
+    assert_eq!(from_slice::<BTreeMap<char, u8>>(&crate::to_vec(&chars).unwrap()).unwrap(), chars);
+
+    // Keys written as strings by other encoders parse the same way
+    let bytes = crate::to_vec(&serde_json::json!({"true": 1, "false": 0})).unwrap();
+    let flags: BTreeMap<bool, u8> = from_slice(&bytes).unwrap();
+    assert_eq!(flags, BTreeMap::from([(false, 0), (true, 1)]));
+
+    #[derive(Deserialize, Debug, PartialEq, Eq, Hash)]
+    struct UserId(u16);
+    let bytes = crate::to_vec(&serde_json::json!({"12": "a"})).unwrap();
+    let users: HashMap<UserId, String> = from_slice(&bytes).unwrap();
+    assert_eq!(users[&UserId(12)], "a");
+
+    let bytes = crate::to_vec(&serde_json::json!({"-1": 0})).unwrap();
+    let err = from_slice::<HashMap<u32, u8>>(&bytes).unwrap_err();
+    assert!(err.to_string().contains("`-1`"), "{err}");
+}
+
+#[test]
+fn test_non_string_record_keys() {
+    use std::collections::BTreeMap;
+
+    // Record definitions can hold numeric-looking keys too
+    let value = crate::bonjson!([{"1": "a", "2": "b"}, {"1": "c", "2": "d"}]);
+    let bytes = crate::encode_value(&value).unwrap();
+    assert_eq!(bytes[0], crate::type_code::RECORD_DEF);
+    let maps: Vec<BTreeMap<u8, String>> = from_slice(&bytes).unwrap();
+    assert_eq!(maps[1][&2], "d");
+}
+
+#[test]
+fn test_stream_deserializer() {
+    use crate::{Deserializer, DecoderConfig, Error, SerializerConfig};
+
+    #[derive(serde::Serialize, Deserialize, Debug, PartialEq)]
+    struct Event {
+        kind: String,
+        at: u64,
+    }
+
+    // Each document carries its own record definitions
+    let records = SerializerConfig { records: true, ..Default::default() };
+    let first = vec![Event { kind: "open".into(), at: 1 }, Event { kind: "read".into(), at: 2 }];
+    let mut log = crate::to_vec_with_config(&first, &records).unwrap();
+    let first_len = log.len();
+    log.extend(crate::to_vec(&vec![Event { kind: "close".into(), at: 3 }]).unwrap());
+    log.extend(crate::to_vec_with_config(&first, &records).unwrap());
+
+    let mut stream = Deserializer::from_slice(&log).into_iter::<Vec<Event>>();
+    assert_eq!(stream.byte_offset(), 0);
+    assert_eq!(stream.next().unwrap().unwrap(), first);
+    assert_eq!(stream.byte_offset(), first_len);
+    assert_eq!(stream.next().unwrap().unwrap()[0].at, 3);
+    assert_eq!(stream.next().unwrap().unwrap(), first);
+    assert_eq!(stream.byte_offset(), log.len());
+    assert!(stream.next().is_none());
+
+    // Borrowed strings point into the input
+    let mut log = crate::to_vec(&"a").unwrap();
+    log.extend(crate::to_vec(&"b").unwrap());
+    let words: Vec<&str> = Deserializer::from_slice(&log).into_iter().collect::<crate::Result<_>>().unwrap();
+    assert_eq!(words, ["a", "b"]);
+
+    // A truncated document is reported once, at its own offset
+    log.extend(&[0xb7, 0x01]);
+    let mut stream = Deserializer::from_slice(&log).into_iter::<crate::Value>();
+    assert!(stream.next().unwrap().is_ok());
+    assert!(stream.next().unwrap().is_ok());
+    assert_eq!(stream.next().unwrap(), Err(Error::Truncated));
+    assert_eq!(stream.byte_offset(), 4);
+    assert!(stream.next().is_none());
+
+    // max_document_size applies to each document, not the whole stream
+    let log: Vec<u8> = (0..10).flat_map(|i| crate::to_vec(&vec![i; 20]).unwrap()).collect();
+    let config = DecoderConfig { max_document_size: 30, ..Default::default() };
+    let stream = Deserializer::from_slice_with_config(&log, config).into_iter::<Vec<u8>>();
+    assert_eq!(stream.count(), 10);
+    let config = DecoderConfig { max_document_size: 10, ..Default::default() };
+    let mut stream = Deserializer::from_slice_with_config(&log, config).into_iter::<Vec<u8>>();
+    assert_eq!(stream.next().unwrap(), Err(Error::MaxDocumentSizeExceeded));
+}
+
+#[test]
+fn test_stream_padding_and_resync() {
+    use crate::{Deserializer, Error};
+
+    #[derive(serde::Serialize, Deserialize, Debug, PartialEq)]
+    struct Event {
+        kind: String,
+        at: u64,
+    }
+
+    let event = |at| crate::to_vec(&Event { kind: "tick".into(), at }).unwrap();
+    let mut log = event(1);
+    log.extend([0, 0, 0]);
+    log.extend(event(2));
+    let garbage_at = log.len();
+    // A reserved type code, then the tail of a cut-off object
+    log.extend([0xc0, 0x66, b'a', 0x01]);
+    log.extend(event(3));
+    log.extend([0, 0]);
+
+    // Padding is a document (the integer 0), which isn't an Event
+    let results: Vec<_> = Deserializer::from_slice(&log).into_iter::<Event>().collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[1].is_err());
+
+    let mut stream = Deserializer::from_slice(&log).into_iter::<Event>().skip_padding(true);
+    assert_eq!(stream.next().unwrap().unwrap().at, 1);
+    assert_eq!(stream.next().unwrap().unwrap().at, 2);
+    assert!(stream.next().unwrap().is_err());
+    assert!(stream.next().is_none());
+    assert_eq!(stream.skipped_bytes(), 3);
+
+    let mut stream = Deserializer::from_slice(&log).into_iter::<Event>().skip_padding(true).resync_on_error(true);
+    assert_eq!(stream.next().unwrap().unwrap().at, 1);
+    assert_eq!(stream.next().unwrap().unwrap().at, 2);
+    assert!(stream.next().unwrap().is_err());
+    assert_eq!(stream.byte_offset(), garbage_at);
+    assert_eq!(stream.next().unwrap().unwrap().at, 3);
+    assert!(stream.next().is_none());
+    assert_eq!(stream.skipped_bytes(), 3 + 4 + 2);
+    assert_eq!(stream.byte_offset(), log.len());
+
+    // A damaged tail is skipped to the end
+    let mut log = event(1);
+    log.extend([0xb8, 0x66]);
+    let mut stream = Deserializer::from_slice(&log).into_iter::<Event>().resync_on_error(true);
+    assert!(stream.next().unwrap().is_ok());
+    assert_eq!(stream.next().unwrap(), Err(Error::Truncated));
+    assert!(stream.next().is_none());
+    assert_eq!(stream.skipped_bytes(), 2);
+}
+
+#[test]
+fn test_from_slice_partial() {
+    use crate::{from_slice_partial, from_slice_partial_with_config, DecoderConfig, Error};
+
+    #[derive(serde::Serialize, Deserialize, Debug, PartialEq)]
+    struct Header {
+        version: u8,
+        name: String,
+    }
+
+    let header = Header { version: 2, name: "frame".into() };
+    let mut buffer = crate::to_vec(&header).unwrap();
+    let len = buffer.len();
+    buffer.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+
+    // from_slice rejects the payload and says where it starts
+    assert_eq!(from_slice::<Header>(&buffer).unwrap_err(), Error::TrailingBytes(len));
+
+    let (decoded, consumed): (Header, usize) = from_slice_partial(&buffer).unwrap();
+    assert_eq!((decoded, consumed), (header, len));
+
+    // Borrowing from the buffer still works
+    let bytes = crate::to_vec(&"hi").unwrap();
+    let (name, consumed): (&str, usize) = from_slice_partial(&bytes).unwrap();
+    assert_eq!((name, consumed), ("hi", 3));
+
+    // The size limit covers the document, not the whole buffer
+    let config = DecoderConfig { max_document_size: len, ..Default::default() };
+    assert!(from_slice_partial_with_config::<Header>(&buffer, config).is_ok());
+    let config = DecoderConfig { max_document_size: len - 1, ..Default::default() };
+    let err = from_slice_partial_with_config::<Header>(&buffer, config).unwrap_err();
+    assert_eq!(err, Error::MaxDocumentSizeExceeded);
+
+    assert_eq!(from_slice_partial::<Header>(&buffer[..len - 1]).unwrap_err(), Error::Truncated);
+
+    // The deserializer reports its position as it goes
+    let mut de = crate::Deserializer::from_slice(&buffer);
+    assert_eq!(de.byte_offset(), 0);
+    let _: Header = Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!(de.byte_offset(), len);
+}
+
+#[test]
+fn test_typed_array_element_conversions() {
+    use crate::{to_vec_with_config, type_code, Error, SerializerConfig, TypedArrayStrategy};
+
+    fn encode<T: serde::Serialize>(value: &T) -> Vec<u8> {
+        let config = SerializerConfig { typed_array_strategy: TypedArrayStrategy::Always, ..Default::default() };
+        to_vec_with_config(value, &config).unwrap()
+    }
+
+    // Integers widen to any integer or float type
+    let bytes = encode(&vec![-300i16, 0, 300]);
+    assert_eq!(bytes[0], type_code::TYPED_ARRAY_SINT16);
+    assert_eq!(from_slice::<Vec<i64>>(&bytes).unwrap(), [-300, 0, 300]);
+    assert_eq!(from_slice::<Vec<i128>>(&bytes).unwrap(), [-300, 0, 300]);
+    assert_eq!(from_slice::<Vec<f64>>(&bytes).unwrap(), [-300.0, 0.0, 300.0]);
+    assert_eq!(from_slice::<Vec<f32>>(&bytes).unwrap(), [-300.0, 0.0, 300.0]);
+    assert_eq!(from_slice::<Vec<Option<i32>>>(&bytes).unwrap(), [Some(-300), Some(0), Some(300)]);
+
+    // and narrow when every element fits
+    assert_eq!(from_slice::<Vec<u8>>(&encode(&vec![1u64, 255])).unwrap(), [1, 255]);
+    assert_eq!(from_slice::<Vec<i8>>(&bytes).unwrap_err(), Error::ValueOutOfRange);
+    assert_eq!(from_slice::<Vec<u16>>(&bytes).unwrap_err(), Error::ValueOutOfRange);
+    let bytes = encode(&vec![u64::MAX]);
+    assert_eq!(from_slice::<Vec<i64>>(&bytes).unwrap_err(), Error::ValueOutOfRange);
+    assert_eq!(from_slice::<Vec<u128>>(&bytes).unwrap(), [u128::from(u64::MAX)]);
+
+    // float32 elements reach f32 unchanged
+    let floats = vec![0.1f32, -2.5, f32::MAX];
+    let bytes = encode(&floats);
+    assert_eq!(bytes[0], type_code::TYPED_ARRAY_FLOAT32);
+    assert_eq!(from_slice::<Vec<f32>>(&bytes).unwrap(), floats);
+    assert_eq!(from_slice::<Vec<f64>>(&bytes).unwrap(), floats.iter().map(|&f| f64::from(f)).collect::<Vec<_>>());
+
+    // float64 narrows to f32 unless it overflows
+    let bytes = encode(&vec![0.1f64, -2.5]);
+    assert_eq!(bytes[0], type_code::TYPED_ARRAY_FLOAT64);
+    assert_eq!(from_slice::<Vec<f32>>(&bytes).unwrap(), [0.1f32, -2.5]);
+    assert_eq!(from_slice::<Vec<f32>>(&encode(&vec![0.1f64, 1e300])).unwrap_err(), Error::ValueOutOfRange);
+
+    // Floats don't become integers
+    assert!(matches!(from_slice::<Vec<i64>>(&bytes), Err(Error::Custom(_))));
+
+    // Newtypes see the element itself
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Meters(f64);
+    assert_eq!(from_slice::<Vec<Meters>>(&bytes).unwrap(), [Meters(0.1), Meters(-2.5)]);
+}
+
+#[test]
+fn test_nan_infinity_policy_matches_decode_value() {
+    use crate::{
+        decode_value_with_config, from_slice_with_config, type_code, BigNumber, DecoderConfig, Error, NanInfinityMode,
+        OutOfRangeMode, Value,
+    };
+
+    let float64 = |f: f64| [&[type_code::FLOAT64][..], &f.to_le_bytes()].concat();
+    let float32 = |f: f32| [&[type_code::FLOAT32][..], &f.to_le_bytes()].concat();
+    let typed32 = [&[type_code::TYPED_ARRAY_FLOAT32, 2][..], &1.5f32.to_le_bytes(), &f32::INFINITY.to_le_bytes()].concat();
+    let typed64 = [&[type_code::TYPED_ARRAY_FLOAT64, 2][..], &f64::NAN.to_le_bytes(), &2.0f64.to_le_bytes()].concat();
+    let huge = crate::encode_value(&Value::BigNumber(BigNumber::new(1, 1, 400))).unwrap();
+    let documents = [float64(f64::NAN), float32(f32::NEG_INFINITY), typed32.clone(), typed64.clone(), huge.clone()];
+
+    let config = |nan_infinity_mode, out_of_range_mode| DecoderConfig {
+        nan_infinity_mode,
+        out_of_range_mode,
+        ..Default::default()
+    };
+    let reject = config(NanInfinityMode::Reject, OutOfRangeMode::Error);
+    let allow = config(NanInfinityMode::Allow, OutOfRangeMode::Error);
+    let stringify = config(NanInfinityMode::Stringify, OutOfRangeMode::Stringify);
+
+    // Deserializing a Value gives what decode_value does, errors included
+    for config in [&reject, &allow, &stringify] {
+        for document in &documents {
+            let expected = decode_value_with_config(document, config.clone());
+            let actual = from_slice_with_config::<Value>(document, config.clone());
+            assert_eq!(format!("{actual:?}"), format!("{expected:?}"), "{:?} {document:02x?}", config.nan_infinity_mode);
+        }
+    }
+
+    // Reject
+    assert_eq!(from_slice_with_config::<f64>(&float64(f64::NAN), reject.clone()).unwrap_err(), Error::NanNotAllowed);
+    assert_eq!(from_slice_with_config::<f32>(&float32(f32::NEG_INFINITY), reject.clone()).unwrap_err(), Error::InfinityNotAllowed);
+    assert_eq!(from_slice_with_config::<Vec<f32>>(&typed32, reject.clone()).unwrap_err(), Error::InfinityNotAllowed);
+    assert_eq!(from_slice_with_config::<f64>(&huge, reject.clone()).unwrap_err(), Error::ValueOutOfRange);
+
+    // Allow: numeric targets get the float; string targets don't
+    assert!(from_slice_with_config::<f64>(&float64(f64::NAN), allow.clone()).unwrap().is_nan());
+    assert_eq!(from_slice_with_config::<f32>(&float32(f32::NEG_INFINITY), allow.clone()).unwrap(), f32::NEG_INFINITY);
+    assert_eq!(from_slice_with_config::<Vec<f32>>(&typed32, allow.clone()).unwrap(), [1.5, f32::INFINITY]);
+    assert!(from_slice_with_config::<String>(&float64(f64::NAN), allow.clone()).is_err());
+
+    // Stringify: string targets get the name, numeric targets still get the float
+    assert_eq!(from_slice_with_config::<String>(&float64(f64::NAN), stringify.clone()).unwrap(), "NaN");
+    assert_eq!(from_slice_with_config::<String>(&float32(f32::NEG_INFINITY), stringify.clone()).unwrap(), "-Infinity");
+    assert!(from_slice_with_config::<f64>(&float64(f64::NAN), stringify.clone()).unwrap().is_nan());
+    assert!(from_slice_with_config::<String>(&float64(1.5), stringify.clone()).is_err());
+    assert!(from_slice_with_config::<Vec<String>>(&typed32, stringify.clone()).is_err());
+    let infinite = [&[type_code::TYPED_ARRAY_FLOAT32, 1][..], &f32::INFINITY.to_le_bytes()].concat();
+    let names: Vec<Option<String>> = from_slice_with_config(&infinite, stringify.clone()).unwrap();
+    assert_eq!(names, [Some("Infinity".to_string())]);
+    assert_eq!(from_slice_with_config::<String>(&huge, stringify.clone()).unwrap(), BigNumber::new(1, 1, 400).to_string_notation());
+
+    // f32 targets reject finite values they can't hold; BigNumbers within range are numbers
+    assert_eq!(from_slice_with_config::<f32>(&float64(1e300), allow).unwrap_err(), Error::ValueOutOfRange);
+    let bytes = crate::encode_value(&Value::BigNumber(BigNumber::new(1, 15, -1))).unwrap();
+    assert_eq!(from_slice::<f64>(&bytes).unwrap(), 1.5);
+}
+
+#[test]
+fn test_stringified_nan_infinity_enums() {
+    use crate::{from_slice_with_config, type_code, DecoderConfig, NanInfinityMode};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Special {
+        NaN,
+        Infinity,
+        #[serde(rename = "-Infinity")]
+        NegInfinity,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Reading {
+        Value(f64),
+        Special(Special),
+    }
+
+    let float64 = |f: f64| [&[type_code::FLOAT64][..], &f.to_le_bytes()].concat();
+    let stringify = DecoderConfig { nan_infinity_mode: NanInfinityMode::Stringify, ..Default::default() };
+
+    assert_eq!(from_slice_with_config::<Special>(&float64(f64::NAN), stringify.clone()).unwrap(), Special::NaN);
+    assert_eq!(
+        from_slice_with_config::<Special>(&float64(f64::NEG_INFINITY), stringify.clone()).unwrap(),
+        Special::NegInfinity
+    );
+    assert!(from_slice_with_config::<Special>(&float64(1.5), stringify.clone()).is_err());
+    assert!(from_slice::<Special>(&float64(f64::INFINITY)).is_err());
+
+    // Untagged enums see the name through deserialize_any
+    assert_eq!(
+        from_slice_with_config::<Reading>(&float64(f64::INFINITY), stringify.clone()).unwrap(),
+        Reading::Special(Special::Infinity)
+    );
+    assert_eq!(from_slice_with_config::<Reading>(&float64(2.5), stringify).unwrap(), Reading::Value(2.5));
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn test_nfc_normalization_matches_decode_value() {
+    use crate::{bonjson, decode_value_with_config, from_slice_with_config, to_vec, DecoderConfig, UnicodeNormalization, Value};
+    use std::collections::BTreeMap;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Place {
+        #[serde(rename = "caf\u{e9}")]
+        cafe: String,
+    }
+
+    // Decomposed "é" in a key and a value
+    let bytes = to_vec(&BTreeMap::from([("cafe\u{301}", "cre\u{300}me")])).unwrap();
+    let nfc = DecoderConfig { unicode_normalization: UnicodeNormalization::Nfc, ..Default::default() };
+
+    assert!(from_slice::<Place>(&bytes).is_err());
+    let place: Place = from_slice_with_config(&bytes, nfc.clone()).unwrap();
+    assert_eq!(place.cafe, "cr\u{e8}me");
+    let value: Value = from_slice_with_config(&bytes, nfc.clone()).unwrap();
+    assert_eq!(value, decode_value_with_config(&bytes, nfc.clone()).unwrap());
+    assert_eq!(value, bonjson!({"caf\u{e9}": "cr\u{e8}me"}));
+
+    // Strings already in NFC come through unchanged (and still borrowed)
+    let bytes = to_vec(&"caf\u{e9}").unwrap();
+    let s: &str = from_slice_with_config(&bytes, nfc).unwrap();
+    assert_eq!(s, "caf\u{e9}");
+}
+
+#[test]
+fn test_columnar_sequence_errors() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Point {
+        x: i8,
+        y: i8,
+    }
+
+    let columns = |y_rows: u8| {
+        let mut encoder = crate::Encoder::new(Vec::new());
+        encoder.begin_object().unwrap();
+        encoder.write_str("x").unwrap();
+        encoder.write_typed_array_raw(crate::types::type_code::TYPED_ARRAY_SINT8, 2, &[1, 2]).unwrap();
+        encoder.write_str("y").unwrap();
+        let y = vec![0xFF; usize::from(y_rows)];
+        encoder.write_typed_array_raw(crate::types::type_code::TYPED_ARRAY_SINT8, y.len(), &y).unwrap();
+        encoder.end_container().unwrap();
+        encoder.finish().unwrap()
+    };
+    let points: Vec<Point> = from_slice(&columns(2)).unwrap();
+    assert_eq!((points[1].x, points[1].y), (2, -1));
+    assert_eq!(
+        from_slice::<Vec<Point>>(&columns(3)).unwrap_err(),
+        crate::Error::InvalidData("column `y` has 3 rows, expected 2".into())
+    );
+
+    // Objects of anything other than typed arrays aren't sequences
+    let bytes = crate::to_vec(&crate::bonjson!({"x": [1, 2]})).unwrap();
+    assert!(from_slice::<Vec<Point>>(&bytes).is_err());
+    let bytes = crate::to_vec(&std::collections::BTreeMap::<String, u8>::new()).unwrap();
+    assert!(from_slice::<Vec<Point>>(&bytes).is_err());
+}
+
+#[test]
+fn test_string_transforms() {
+    use crate::{DecoderConfig, StringTransform};
+    use std::borrow::Cow;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct User {
+        userid: u32,
+        name: String,
+    }
+    let config = DecoderConfig {
+        key_transform: Some(StringTransform::ascii_lowercase()),
+        string_transform: Some(StringTransform::new(|s| Cow::Owned(s.to_uppercase()))),
+        ..Default::default()
+    };
+    let user = User { userid: 7, name: "ADA".into() };
+    for bytes in [
+        crate::to_vec(&crate::bonjson!({"UserId": 7, "NAME": "ada"})).unwrap(),
+        crate::to_vec(&crate::bonjson!({"userId": 7, "name": "Ada"})).unwrap(),
+    ] {
+        assert_eq!(crate::from_slice_with_config::<User>(&bytes, config.clone()).unwrap(), user);
+    }
+
+    // Record keys are transformed too
+    #[derive(Clone, serde::Serialize)]
+    #[allow(non_snake_case)]
+    struct Wire {
+        UserId: u32,
+        Name: &'static str,
+    }
+    let records = crate::SerializerConfig { records: true, ..Default::default() };
+    let bytes = crate::to_vec_with_config(&vec![Wire { UserId: 7, Name: "ada" }; 2], &records).unwrap();
+    assert_eq!(bytes[0], crate::type_code::RECORD_DEF);
+    let users: Vec<User> = crate::from_slice_with_config(&bytes, config).unwrap();
+    assert_eq!(users, [User { userid: 7, name: "ADA".into() }, User { userid: 7, name: "ADA".into() }]);
+
+    // Borrowed strings stay borrowed when the transform borrows
+    let config = DecoderConfig {
+        string_transform: Some(StringTransform::new(|s| Cow::Borrowed(s.trim()))),
+        ..Default::default()
+    };
+    let bytes = crate::to_vec(&"  padded ").unwrap();
+    let s: &str = crate::from_slice_with_config(&bytes, config).unwrap();
+    assert_eq!(s, "padded");
+}
+
+#[test]
+fn test_field_matching() {
+    use crate::{DecoderConfig, FieldMatching};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Account {
+        user_id: u32,
+        name: String,
+        #[serde(default, rename = "Name")]
+        display_name: String,
+    }
+    let bytes = crate::to_vec(&crate::bonjson!({"userId": 7, "NAME": "a", "Name": "b"})).unwrap();
+
+    // Exact matching ignores both `userId` and `NAME`
+    assert!(crate::from_slice::<Account>(&bytes).is_err());
+
+    let config = DecoderConfig {
+        field_matching: FieldMatching::CaseInsensitive,
+        field_aliases: vec![("userId".into(), "user_id".into())],
+        ..Default::default()
+    };
+    let account: Account = crate::from_slice_with_config(&bytes, config.clone()).unwrap();
+    // An exact match wins over a case-insensitive one
+    assert_eq!(account, Account { user_id: 7, name: "a".into(), display_name: "b".into() });
+
+    // Aliases apply in either direction and under the matching mode
+    let config = DecoderConfig { field_aliases: vec![("user_id".into(), "USERID".into())], ..config };
+    let bytes = crate::to_vec(&crate::bonjson!({"UserId": 1, "name": "x"})).unwrap();
+    let (account, report): (Account, _) = crate::from_slice_with_report(&bytes, config.clone()).unwrap();
+    assert_eq!((account.user_id, account.name.as_str()), (1, "x"));
+    assert!(report.unknown_fields.is_empty());
+    assert_eq!(report.defaulted_fields, vec!["$.Name"]);
+
+    // Record keys are matched too
+    #[derive(Clone, serde::Serialize)]
+    #[allow(non_snake_case)]
+    struct Wire {
+        UserID: u32,
+        NAME: &'static str,
+    }
+    let records = crate::SerializerConfig { records: true, ..Default::default() };
+    let bytes = crate::to_vec_with_config(&vec![Wire { UserID: 3, NAME: "r" }; 2], &records).unwrap();
+    let accounts: Vec<Account> = crate::from_slice_with_config(&bytes, config).unwrap();
+    assert_eq!(accounts[1], Account { user_id: 3, name: "r".into(), display_name: String::new() });
+}
+
+#[test]
+fn test_unknown_type_codes() {
+    use crate::{DecoderConfig, UnknownTypeCodeMode};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Reading {
+        id: u8,
+        #[serde(default)]
+        unit: Option<String>,
+        samples: Vec<u8>,
+    }
+    // {"id": 1, "unit": <0xd0 with a 1-byte payload>, "samples": [<0xd0, empty>, 5]}
+    let mut bytes = vec![0xb8, 0x67, b'i', b'd', 0x01, 0x69];
+    bytes.extend_from_slice(b"unit");
+    bytes.extend_from_slice(&[0xd0, 0x01, 0xff, 0x6c]);
+    bytes.extend_from_slice(b"samples");
+    bytes.extend_from_slice(&[0xb7, 0xd0, 0x00, 0x05, 0xb6, 0xb6]);
+
+    assert!(from_slice::<Reading>(&bytes).is_err());
+    assert_eq!(from_slice::<crate::Value>(&bytes).unwrap_err(), crate::Error::InvalidTypeCode(0xd0));
+    let config = |mode| DecoderConfig { unknown_type_code: mode, ..Default::default() };
+    let skipped: Reading = crate::from_slice_with_config(&bytes, config(UnknownTypeCodeMode::SkipValue)).unwrap();
+    assert_eq!(skipped, Reading { id: 1, unit: None, samples: vec![5] });
+
+    // As null, the array element can't become a u8
+    let err = crate::from_slice_with_config::<Reading>(&bytes, config(UnknownTypeCodeMode::Null)).unwrap_err();
+    assert!(matches!(err, crate::Error::Custom(_) | crate::Error::InvalidData(_)), "{err:?}");
+    let values: crate::Value = crate::from_slice_with_config(&bytes, config(UnknownTypeCodeMode::Null)).unwrap();
+    assert_eq!(values, crate::bonjson!({"id": 1, "unit": null, "samples": [null, 5]}));
+}
+
+#[test]
+fn test_deserialize_bytes_from_typed_array() {
+    use crate::types::type_code;
+
+    #[derive(Debug, PartialEq)]
+    struct Bytes(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for Bytes {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct BytesVisitor;
+            impl serde::de::Visitor<'_> for BytesVisitor {
+                type Value = Bytes;
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("bytes")
+                }
+                fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Bytes, E> {
+                    Ok(Bytes(v.to_vec()))
+                }
+            }
+            deserializer.deserialize_bytes(BytesVisitor)
+        }
+    }
+
+    let bytes = [type_code::TYPED_ARRAY_UINT8, 0x03, 1, 2, 3];
+    assert_eq!(from_slice::<Bytes>(&bytes).unwrap(), Bytes(vec![1, 2, 3]));
+    // The count promises more bytes than there are
+    assert_eq!(from_slice::<Bytes>(&bytes[..4]).unwrap_err(), crate::Error::Truncated);
+}
+
+#[test]
+fn test_big_number_fields() {
+    use crate::{bonjson, to_vec, to_vec_with_config, BigNumber, SerializerConfig, Value};
+    use serde::Serialize;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Payment {
+        amount: BigNumber,
+        fee: Option<BigNumber>,
+        extra: Value,
+    }
+    let payments = vec![
+        Payment {
+            amount: BigNumber::new(1, 12_345, -2),
+            fee: None,
+            extra: Value::BigNumber(BigNumber::new(-1, 3, -40)),
+        },
+        Payment { amount: BigNumber::new(-1, u64::MAX, -30), fee: Some(BigNumber::zero()), extra: bonjson!([1]) },
+    ];
+    let bytes = to_vec(&payments).unwrap();
+    assert_eq!(from_slice::<Vec<Payment>>(&bytes).unwrap(), payments);
+    // Records take a counting pass first, which sizes BigNumbers the same way
+    let config = SerializerConfig { records: true, ..Default::default() };
+    assert_eq!(from_slice::<Vec<Payment>>(&to_vec_with_config(&payments, &config).unwrap()).unwrap(), payments);
+    let value = crate::decode_value(&bytes).unwrap();
+    let first = value.as_array().and_then(|arr| arr[0].get_key("amount"));
+    assert_eq!(first, Some(&Value::BigNumber(BigNumber::new(1, 12_345, -2))));
+
+    // Other numbers and decimal strings convert
+    let other = crate::encode_value(&bonjson!([7, (-7), 0.5, "1.25e3"])).unwrap();
+    assert_eq!(
+        from_slice::<Vec<BigNumber>>(&other).unwrap(),
+        [BigNumber::new(1, 7, 0), BigNumber::new(-1, 7, 0), BigNumber::new(1, 5, -1), BigNumber::new(1, 125, 1)]
+    );
+    let nan = crate::encode_value(&bonjson!(null)).unwrap();
+    assert!(from_slice::<BigNumber>(&nan).is_err());
+
+    // Other formats see the decimal string
+    assert_eq!(serde_json::to_string(&BigNumber::new(1, 15, -1)).unwrap(), r#""15e-1""#);
+    assert_eq!(serde_json::from_str::<BigNumber>("1.5").unwrap(), BigNumber::new(1, 15, -1));
+    assert_eq!(serde_json::from_str::<BigNumber>(r#""-2e9""#).unwrap(), BigNumber::new(-1, 2, 9));
+    assert_eq!(serde_json::from_str::<Value>("[1.5]").unwrap(), bonjson!([1.5]));
+}
+
+#[test]
+fn test_from_slice_traced() {
+    use crate::{from_slice_traced, to_vec, to_vec_with, Error};
+    use serde::Serialize;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Item {
+        name: String,
+        price: u32,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Payload {
+        items: Vec<Item>,
+        levels: Vec<i8>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Envelope {
+        payload: Payload,
+    }
+
+    let location = |bytes: &[u8]| match from_slice_traced::<Envelope>(bytes).unwrap_err() {
+        Error::AtPath { path, offset, error } => (path.to_string(), offset.unwrap(), *error),
+        other => panic!("no path: {other}"),
+    };
+
+    let mut items: Vec<crate::Value> = (0..20).map(|i| crate::bonjson!({"name": "n", "price": i})).collect();
+    items[17] = crate::bonjson!({"name": "n", "price": "free"});
+    let bytes = to_vec(&crate::bonjson!({"payload": {"items": items, "levels": []}})).unwrap();
+    let (path, offset, error) = location(&bytes);
+    assert_eq!(path, "$.payload.items[17].price");
+    assert_eq!(error, from_slice::<u32>(&to_vec(&"free").unwrap()).unwrap_err());
+    // Decoding stopped at the string "free", having read its type code
+    let free = to_vec(&"free").unwrap();
+    let start = bytes.windows(free.len()).position(|w| w == free).unwrap();
+    assert_eq!(offset, start + 1);
+
+    // Records and typed arrays are traced too; the levels below are a typed
+    // array of i16, one of which doesn't fit an i8
+    #[derive(Serialize)]
+    struct Named {
+        name: &'static str,
+    }
+    #[derive(Serialize)]
+    struct NamedPayload {
+        items: Vec<Named>,
+        levels: Vec<i8>,
+    }
+    let named = NamedPayload { items: vec![Named { name: "a" }, Named { name: "b" }], levels: vec![] };
+    let bytes = to_vec_with(&std::collections::BTreeMap::from([("payload", named)]), |cfg| cfg.records(true)).unwrap();
+    assert!(crate::check_compatibility(&bytes).features().contains(&"records"));
+    assert_eq!(location(&bytes).0, "$.payload.items[0]");
+    let payload = Envelope { payload: Payload { items: vec![], levels: vec![] } };
+    #[derive(Serialize)]
+    struct WidePayload {
+        items: Vec<Item>,
+        levels: Vec<i16>,
+    }
+    let wide = WidePayload { items: vec![], levels: (0..20).map(|i| if i == 17 { 300 } else { i }).collect() };
+    let always = |cfg: crate::SerializerConfig| cfg.typed_array_strategy(crate::TypedArrayStrategy::Always);
+    let mut levels = to_vec_with(&std::collections::BTreeMap::from([("payload", wide)]), always).unwrap();
+    assert!(crate::check_compatibility(&levels).features().contains(&"typed_arrays"));
+    assert_eq!(location(&levels).0, "$.payload.levels[17]");
+
+    // Errors outside the value are at the root, with their offset
+    let good = to_vec(&payload).unwrap();
+    levels.clone_from(&good);
+    levels.push(0);
+    let (path, offset, error) = location(&levels);
+    assert_eq!((path.as_str(), offset, error), ("$", good.len(), Error::TrailingBytes(good.len())));
+    assert!(from_slice_traced::<Envelope>(&good).is_ok());
+}
+
+#[test]
+fn test_untagged_numeric_coercion() {
+    use crate::{from_slice_with_config, to_vec, to_vec_with, DecoderConfig, TypedArrayStrategy};
+    use serde::de::Visitor;
+
+    /// An id type that, like many hand-written visitors, only accepts `u64`.
+    #[derive(Debug, PartialEq)]
+    struct Id(u64);
+    impl<'de> Deserialize<'de> for Id {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct IdVisitor;
+            impl Visitor<'_> for IdVisitor {
+                type Value = Id;
+                fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("an unsigned id")
+                }
+                fn visit_u64<E>(self, v: u64) -> Result<Id, E> {
+                    Ok(Id(v))
+                }
+            }
+            deserializer.deserialize_any(IdVisitor)
+        }
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Key {
+        Id(Id),
+        Offset(i64),
+    }
+
+    /// Which visitor method a number reached.
+    #[derive(Debug, PartialEq)]
+    struct Visited(&'static str);
+    impl<'de> Deserialize<'de> for Visited {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct KindVisitor;
+            impl Visitor<'_> for KindVisitor {
+                type Value = Visited;
+                fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str("a number")
+                }
+                fn visit_i64<E>(self, _: i64) -> Result<Visited, E> {
+                    Ok(Visited("i64"))
+                }
+                fn visit_u64<E>(self, _: u64) -> Result<Visited, E> {
+                    Ok(Visited("u64"))
+                }
+                fn visit_f32<E>(self, _: f32) -> Result<Visited, E> {
+                    Ok(Visited("f32"))
+                }
+                fn visit_f64<E>(self, _: f64) -> Result<Visited, E> {
+                    Ok(Visited("f64"))
+                }
+            }
+            deserializer.deserialize_any(KindVisitor)
+        }
+    }
+
+    let coerce = DecoderConfig::default().untagged_numeric_coercion(true);
+    let small = to_vec(&5u8).unwrap();
+    assert_eq!(from_slice::<Key>(&small).unwrap(), Key::Offset(5));
+    assert_eq!(from_slice_with_config::<Key>(&small, coerce.clone()).unwrap(), Key::Id(Id(5)));
+    assert_eq!(serde_json::from_str::<Key>("5").unwrap(), Key::Id(Id(5)));
+    let negative = to_vec(&-5i32).unwrap();
+    assert_eq!(from_slice_with_config::<Key>(&negative, coerce.clone()).unwrap(), Key::Offset(-5));
+
+    // BigNumbers holding integers, and typed array elements
+    let big = to_vec(&crate::BigNumber::new(1, 5, 2)).unwrap();
+    assert_eq!(from_slice::<Visited>(&big).unwrap(), Visited("i64"));
+    assert_eq!(from_slice_with_config::<Visited>(&big, coerce.clone()).unwrap(), Visited("u64"));
+    let always = |cfg: crate::SerializerConfig| cfg.typed_array_strategy(TypedArrayStrategy::Always);
+    let ints = to_vec_with(&vec![-1i32, 2, 3], always).unwrap();
+    let visited = |config: DecoderConfig| from_slice_with_config::<Vec<Visited>>(&ints, config).unwrap();
+    assert_eq!(visited(DecoderConfig::default()), [Visited("i64"), Visited("i64"), Visited("i64")]);
+    assert_eq!(visited(coerce.clone()), [Visited("i64"), Visited("u64"), Visited("u64")]);
+    let floats = to_vec_with(&vec![0.5f32, 1.5], always).unwrap();
+    assert_eq!(from_slice::<Vec<Visited>>(&floats).unwrap(), [Visited("f32"), Visited("f32")]);
+    assert_eq!(from_slice_with_config::<Vec<Visited>>(&floats, coerce).unwrap(), [Visited("f64"), Visited("f64")]);
+    assert_eq!(serde_json::from_str::<Vec<Visited>>("[-1, 2, 0.5]").unwrap(), [Visited("i64"), Visited("u64"), Visited("f64")]);
+}
+
+#[test]
+fn test_duplicate_key_check() {
+    use crate::{decode_value_with_config, from_slice_with_config, DecoderConfig, DuplicateKeyCheck, DuplicateKeyMode, Encoder, Error};
+    use std::collections::HashMap;
+
+    // An object with `count` distinct keys, then `dup` written again if given
+    let object = |count: usize, dup: Option<usize>| {
+        let mut enc = Encoder::new(Vec::new());
+        enc.begin_object().unwrap();
+        for i in (0..count).chain(dup) {
+            enc.write_str(&format!("key{i}")).unwrap();
+            enc.write_u64(i as u64).unwrap();
+        }
+        enc.end_container().unwrap();
+        enc.finish().unwrap()
+    };
+    let decode = |bytes: &[u8], check| {
+        from_slice_with_config::<HashMap<String, u64>>(bytes, DecoderConfig::default().duplicate_key_check(check))
+    };
+    let (distinct, repeated) = (object(300, None), object(300, Some(17)));
+
+    assert_eq!(decode(&repeated, DuplicateKeyCheck::Off).unwrap().len(), 300);
+    for check in [DuplicateKeyCheck::Exact, DuplicateKeyCheck::Bloom { bits: 1 << 14 }] {
+        assert_eq!(decode(&distinct, check).unwrap().len(), 300, "{check:?}");
+        assert_eq!(decode(&repeated, check), Err(Error::DuplicateKey), "{check:?}");
+    }
+
+    // A saturated filter confirms a bounded number of hits, then reports
+    // further hits as probable duplicates
+    let tiny = DuplicateKeyCheck::Bloom { bits: 64 };
+    assert_eq!(decode(&object(40, None), tiny).unwrap().len(), 40);
+    assert_eq!(decode(&object(40, Some(17)), tiny), Err(Error::DuplicateKey));
+    assert_eq!(decode(&distinct, tiny), Err(Error::DuplicateKey));
+
+    // Keys only collide within one object, and structs are checked too
+    #[derive(Deserialize, Debug)]
+    struct Pair {
+        #[allow(dead_code)]
+        a: HashMap<String, u64>,
+        #[allow(dead_code)]
+        b: HashMap<String, u64>,
+    }
+    let mut enc = Encoder::new(Vec::new());
+    enc.begin_object().unwrap();
+    for key in ["a", "b"] {
+        enc.write_str(key).unwrap();
+        enc.begin_object().unwrap();
+        enc.write_str("x").unwrap();
+        enc.write_u64(1).unwrap();
+        enc.end_container().unwrap();
+    }
+    enc.write_str("a").unwrap();
+    enc.write_null().unwrap();
+    enc.end_container().unwrap();
+    let bytes = enc.finish().unwrap();
+    let bloom = DecoderConfig::default().duplicate_key_check(DuplicateKeyCheck::Bloom { bits: 0 });
+    assert_eq!(from_slice_with_config::<Pair>(&bytes, bloom).unwrap_err(), Error::DuplicateKey);
+
+    // decode_value goes by duplicate_key_mode alone
+    let config = DecoderConfig::default().duplicate_key_check(DuplicateKeyCheck::Exact).duplicate_key_mode(DuplicateKeyMode::KeepFirst);
+    assert_eq!(decode_value_with_config(&repeated, config).unwrap().as_object().unwrap().len(), 300);
+}
+
+#[test]
+fn test_from_slice_prefix() {
+    use crate::{from_slice_prefix, to_vec, Error};
+
+    #[derive(Debug, PartialEq, Deserialize, serde::Serialize)]
+    struct Row {
+        id: u32,
+        name: String,
+    }
+    let rows: Vec<Row> = (0..1000).map(|id| Row { id, name: format!("row {id}") }).collect();
+    let bytes = to_vec(&rows).unwrap();
+    let (head, more): (Vec<Row>, bool) = from_slice_prefix(&bytes[..bytes.len() / 10], 2).unwrap();
+    assert_eq!((&head[..], more), (&rows[..2], true));
+    let (all, more): (Vec<Row>, bool) = from_slice_prefix(&bytes, 1000).unwrap();
+    assert_eq!((all, more), (rows, false));
+
+    let bytes = to_vec(&vec![1.25f64; 100]).unwrap();
+    assert_eq!(from_slice_prefix::<f64>(&bytes, 3).unwrap(), (vec![1.25; 3], true));
+    assert_eq!(from_slice_prefix::<f32>(&bytes, 200).unwrap(), (vec![1.25; 100], false));
+    assert!(matches!(from_slice_prefix::<u8>(&[0x01], 1), Err(Error::Custom(_))));
+
+    // Skipped values don't surface as elements or count toward max_elements
+    // [1, <0xd0, empty>, 2, <0xd0 with a 1-byte payload>]
+    let bytes = [0xb7, 0x01, 0xd0, 0x00, 0x02, 0xd0, 0x01, 0xff, 0xb6];
+    let config = crate::DecoderConfig { unknown_type_code: crate::UnknownTypeCodeMode::SkipValue, ..Default::default() };
+    let prefix = |n| crate::from_slice_prefix_with_config::<Option<u8>>(&bytes, n, config.clone()).unwrap();
+    assert_eq!(prefix(2), (vec![Some(1), Some(2)], false));
+    assert_eq!(prefix(1), (vec![Some(1)], true));
+}
@@ -5,22 +5,125 @@
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::cast_sign_loss)]
 
-use crate::error::{Error, Result};
-use crate::types::{limits, type_code, BigNumber, zigzag_decode, leb128_decode};
+use crate::error::DecodeError;
+use crate::metadata::Collector;
+#[cfg(feature = "tracing")]
+use crate::metrics::{CodecMetrics, CodecOperation, MetricsSink};
+use crate::types::{limits, type_code, BigNumber, FloatWidth, zigzag_decode, leb128_decode};
 use std::borrow::Cow;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// The result of a [`Decoder`] operation.
+pub type Result<T> = std::result::Result<T, DecodeError>;
 
 /// Validate and convert bytes to a UTF-8 string.
 /// Uses simdutf8 for SIMD-accelerated validation when the feature is enabled.
 #[cfg(feature = "simd-utf8")]
 #[inline]
 fn validate_utf8(bytes: &[u8]) -> Result<&str> {
-    simdutf8::basic::from_utf8(bytes).map_err(|_| Error::InvalidUtf8)
+    simdutf8::basic::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)
 }
 
 #[cfg(not(feature = "simd-utf8"))]
 #[inline]
 fn validate_utf8(bytes: &[u8]) -> Result<&str> {
-    std::str::from_utf8(bytes).map_err(|_| Error::InvalidUtf8)
+    std::str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)
+}
+
+/// Validate UTF-8, then scan for NUL: two passes, each SIMD-accelerated.
+#[inline]
+fn validate_utf8_then_nul(bytes: &[u8]) -> Result<&str> {
+    let s = validate_utf8(bytes)?;
+    if memchr::memchr(0, bytes).is_some() {
+        return Err(DecodeError::NulCharacter);
+    }
+    Ok(s)
+}
+
+/// Validate UTF-8 and reject NUL in a single pass over the leading ASCII run,
+/// which for most strings is the whole string. Only the bytes from the first
+/// non-ASCII (or NUL) byte on get the two-pass treatment. Reports the same
+/// error as [`validate_utf8_then_nul`] when a string has both problems.
+#[inline]
+fn validate_utf8_no_nul(bytes: &[u8]) -> Result<&str> {
+    let ascii_len = ascii_no_nul_prefix_len(bytes);
+    let tail = &bytes[ascii_len..];
+    if !tail.is_empty() {
+        validate_utf8_then_nul(tail)?;
+    }
+    // Safety: the prefix is ASCII, so it ends on a character boundary, and the tail is valid UTF-8
+    #[cfg(not(feature = "forbid-unsafe"))]
+    return Ok(unsafe { std::str::from_utf8_unchecked(bytes) });
+    #[cfg(feature = "forbid-unsafe")]
+    validate_utf8(bytes)
+}
+
+/// Length of the leading run of bytes that are ASCII and not NUL, checked a
+/// word at a time.
+#[inline]
+fn ascii_no_nul_prefix_len(bytes: &[u8]) -> usize {
+    const ONES: u64 = 0x0101_0101_0101_0101;
+    const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+    let mut words = bytes.chunks_exact(8);
+    let mut len = 0;
+    for word in &mut words {
+        let word = u64::from_le_bytes(word.try_into().unwrap());
+        // A byte's high bit ends up set if it was already set (non-ASCII) or
+        // if the byte was zero and the subtraction borrowed through it
+        if (word | word.wrapping_sub(ONES)) & HIGH_BITS != 0 {
+            break;
+        }
+        len += 8;
+    }
+    len + bytes[len..].iter().take_while(|&&b| b != 0 && b < 0x80).count()
+}
+
+/// Scale a BigNumber magnitude by its decimal exponent, if the result is a
+/// whole number that fits in a u128.
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+fn scale_u128(magnitude: u128, exponent: i64) -> Option<u128> {
+    if !(0..=38).contains(&exponent) {
+        return None;
+    }
+    magnitude.checked_mul(10u128.checked_pow(exponent as u32)?)
+}
+
+/// Strings up to this many bytes use [`StringValidation::Combined`] under
+/// [`StringValidation::Auto`]; longer ones are faster with the SIMD passes.
+/// The crossover comes earlier with `simd-utf8` (see the `string_validation`
+/// benchmarks).
+#[cfg(feature = "simd-utf8")]
+pub const COMBINED_VALIDATION_MAX_LEN: usize = 32;
+
+/// Strings up to this many bytes use [`StringValidation::Combined`] under
+/// [`StringValidation::Auto`]; longer ones are faster with the SIMD passes.
+#[cfg(not(feature = "simd-utf8"))]
+pub const COMBINED_VALIDATION_MAX_LEN: usize = 64;
+
+/// The error for an integer written wider than its minimal encoding.
+#[cold]
+fn non_canonical_int(value: impl fmt::Display) -> DecodeError {
+    DecodeError::InvalidData(format!("non-minimal encoding of integer {value}"))
+}
+
+/// The error for a float written wider than its minimal encoding.
+#[cold]
+fn non_canonical_float(value: f64) -> DecodeError {
+    DecodeError::InvalidData(format!("non-minimal encoding of float {value}"))
+}
+
+/// Whether the encoder would write `value` as a float of `width`: it writes
+/// whole numbers (other than -0.0) as integers, and a float64 only when a
+/// float32 can't hold the value bit for bit.
+fn is_canonical_float(value: f64, width: FloatWidth) -> bool {
+    let negative_zero = value == 0.0 && value.is_sign_negative();
+    if !negative_zero && crate::encoder::float_as_exact_i64(value).is_some() {
+        return false;
+    }
+    width == FloatWidth::F32 || !FloatWidth::F32.holds(value)
 }
 
 /// Delete invalid UTF-8 bytes, keeping only valid UTF-8 sequences.
@@ -36,8 +139,12 @@ fn delete_invalid_utf8(bytes: &[u8]) -> String {
             Err(e) => {
                 let valid_up_to = e.valid_up_to();
                 if valid_up_to > 0 {
+                    let valid = &bytes[i..i + valid_up_to];
                     // Safety: from_utf8 confirmed these bytes are valid
-                    result.push_str(unsafe { std::str::from_utf8_unchecked(&bytes[i..i + valid_up_to]) });
+                    #[cfg(not(feature = "forbid-unsafe"))]
+                    result.push_str(unsafe { std::str::from_utf8_unchecked(valid) });
+                    #[cfg(feature = "forbid-unsafe")]
+                    result.push_str(std::str::from_utf8(valid).unwrap_or_default());
                 }
                 // Skip the invalid byte(s)
                 i += valid_up_to + e.error_len().unwrap_or(1);
@@ -48,9 +155,10 @@ fn delete_invalid_utf8(bytes: &[u8]) -> String {
 }
 
 /// How to handle duplicate keys in objects.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum DuplicateKeyMode {
     /// Raise an error on duplicate keys (default per spec)
+    #[default]
     Error,
     /// Keep the first value, ignore subsequent duplicates
     KeepFirst,
@@ -58,16 +166,40 @@ pub enum DuplicateKeyMode {
     KeepLast,
 }
 
-impl Default for DuplicateKeyMode {
-    fn default() -> Self {
-        Self::Error
-    }
+/// How serde deserialization looks for duplicate object keys, which it
+/// otherwise doesn't (a `HashMap` keeps the last value).
+///
+/// Separate from [`DuplicateKeyMode`], which applies where `decode_value`
+/// builds a [`Value`](crate::Value) and holds every key in its map anyway.
+/// Either check fails with `Error::DuplicateKey`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeyCheck {
+    /// Don't check (default)
+    #[default]
+    Off,
+    /// Keep each open object's keys in a set: memory grows with the keys.
+    Exact,
+    /// Keep a Bloom filter of `bits` bits (rounded up to a multiple of 64) per
+    /// open object, so memory stays bounded however many keys an object has.
+    /// When the filter says a key may already be present, the object's earlier
+    /// keys are re-read from the input to confirm it: a false positive costs
+    /// only that rescan. After `n` keys, a new key is a false positive with
+    /// probability about `(1 - e^(-3n/bits))^3`; 16 bits per expected key
+    /// keeps that under 1%.
+    ///
+    /// Worst case: an object with many more keys than the filter was sized
+    /// for saturates it, and every key becomes a hit. To keep decoding linear,
+    /// only the first 64 hits per object are confirmed (at most 64 rescans of
+    /// its members); any hit after that fails with `Error::DuplicateKey` as a
+    /// probable duplicate, so such an object may be rejected without one.
+    Bloom { bits: usize },
 }
 
 /// How to handle NaN and Infinity float values.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum NanInfinityMode {
     /// Reject NaN and Infinity values (default)
+    #[default]
     Reject,
     /// Allow NaN and Infinity as float values
     Allow,
@@ -75,31 +207,39 @@ pub enum NanInfinityMode {
     Stringify,
 }
 
-impl Default for NanInfinityMode {
-    fn default() -> Self {
-        Self::Reject
+impl NanInfinityMode {
+    /// The string a NaN or Infinity float becomes under `Stringify`, or None
+    /// if it stays a float.
+    #[must_use]
+    pub(crate) fn stringify(self, value: f64) -> Option<&'static str> {
+        if self != NanInfinityMode::Stringify || value.is_finite() {
+            return None;
+        }
+        Some(if value.is_nan() {
+            "NaN"
+        } else if value > 0.0 {
+            "Infinity"
+        } else {
+            "-Infinity"
+        })
     }
 }
 
 /// How to handle BigNumber values that exceed configured limits.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum OutOfRangeMode {
     /// Return an error (default)
+    #[default]
     Error,
     /// Convert to string representation
     Stringify,
 }
 
-impl Default for OutOfRangeMode {
-    fn default() -> Self {
-        Self::Error
-    }
-}
-
 /// How to handle invalid UTF-8 in strings.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum InvalidUtf8Mode {
     /// Reject invalid UTF-8 (default)
+    #[default]
     Reject,
     /// Replace invalid bytes with U+FFFD replacement character
     Replace,
@@ -107,24 +247,197 @@ pub enum InvalidUtf8Mode {
     Delete,
 }
 
-impl Default for InvalidUtf8Mode {
-    fn default() -> Self {
-        Self::Reject
-    }
+/// How string contents are checked for valid UTF-8 and, unless `allow_nul`
+/// is set, for NUL bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StringValidation {
+    /// `Combined` for strings up to [`COMBINED_VALIDATION_MAX_LEN`] bytes,
+    /// `Separate` for longer ones (default)
+    #[default]
+    Auto,
+    /// One word-at-a-time pass checks for NUL and non-ASCII bytes together;
+    /// UTF-8 validation and the NUL scan only run from the first non-ASCII byte
+    Combined,
+    /// A UTF-8 validation pass (SIMD with `simd-utf8`), then a SIMD NUL scan
+    Separate,
 }
 
 /// Unicode normalization mode for string comparison.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum UnicodeNormalization {
     /// No normalization (default)
+    #[default]
     None,
     /// NFC normalization
     Nfc,
 }
 
-impl Default for UnicodeNormalization {
-    fn default() -> Self {
-        Self::None
+/// How to handle record instances whose value count doesn't match their definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordMismatchMode {
+    /// Raise an error on any mismatch
+    Error,
+    /// Fill missing trailing values with null; extra values are an error (default).
+    /// The filled-in nulls are real nulls: an [`OptionalField`](crate::OptionalField) reads them as `Null`
+    #[default]
+    NullFill,
+    /// Omit missing trailing keys and discard extra values. An
+    /// [`OptionalField`](crate::OptionalField) reads the omitted keys as `Missing`
+    Truncate,
+}
+
+/// How to handle values whose type code the spec reserves (0xbb-0xf4).
+///
+/// So that newer producers can degrade gracefully, such a value is taken to
+/// be framed as its type code, a LEB128 payload length, and the payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownTypeCodeMode {
+    /// Raise `DecodeError::InvalidTypeCode` (default)
+    #[default]
+    Error,
+    /// Drop the value: array elements and object or record members holding it
+    /// are omitted. Where it can't be dropped (e.g. the root), it reads as null
+    SkipValue,
+    /// Read the value as null
+    Null,
+}
+
+/// How serde deserialization matches object keys to struct fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldMatching {
+    /// Keys must equal the field name (default)
+    #[default]
+    Exact,
+    /// Keys match fields regardless of case; an exact match wins
+    CaseInsensitive,
+}
+
+impl FieldMatching {
+    /// Whether a key matches a field name (or alias) under this mode.
+    pub(crate) fn matches(self, key: &str, name: &str) -> bool {
+        match self {
+            FieldMatching::Exact => key == name,
+            FieldMatching::CaseInsensitive => {
+                key.eq_ignore_ascii_case(name)
+                    || key.chars().flat_map(char::to_lowercase).eq(name.chars().flat_map(char::to_lowercase))
+            }
+        }
+    }
+}
+
+/// A function applied to decoded strings, such as lowercasing object keys so
+/// that they match regardless of how the producer cased them.
+///
+/// Returning `Cow::Borrowed` keeps a string borrowed from the input.
+///
+/// ```rust
+/// use serde_bonjson::{decode_value_with_config, encode_value, bonjson, DecoderConfig, StringTransform};
+///
+/// let bytes = encode_value(&bonjson!({"UserId": 7})).unwrap();
+/// let config = DecoderConfig {
+///     key_transform: Some(StringTransform::ascii_lowercase()),
+///     ..DecoderConfig::default()
+/// };
+/// assert_eq!(decode_value_with_config(&bytes, config).unwrap(), bonjson!({"userid": 7}));
+/// ```
+#[derive(Clone)]
+pub struct StringTransform(Arc<TransformFn>);
+
+type TransformFn = dyn Fn(&str) -> Cow<'_, str> + Send + Sync;
+
+impl StringTransform {
+    /// Wrap a transform function.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&str) -> Cow<'_, str> + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// Lowercase ASCII letters, borrowing strings that are already lowercase.
+    #[must_use]
+    pub fn ascii_lowercase() -> Self {
+        Self::new(|s| {
+            if s.bytes().any(|b| b.is_ascii_uppercase()) {
+                Cow::Owned(s.to_ascii_lowercase())
+            } else {
+                Cow::Borrowed(s)
+            }
+        })
+    }
+
+    /// Apply the transform.
+    pub fn apply<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        (self.0)(s)
+    }
+
+    /// Apply the transform to an owned string, reusing it if it's unchanged.
+    pub(crate) fn apply_owned(&self, s: String) -> String {
+        let transformed = match self.apply(&s) {
+            Cow::Owned(o) => Some(o),
+            Cow::Borrowed(b) if b.len() == s.len() => None,
+            Cow::Borrowed(b) => Some(b.to_owned()),
+        };
+        transformed.unwrap_or(s)
+    }
+}
+
+impl fmt::Debug for StringTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StringTransform")
+    }
+}
+
+/// A function told how far decoding has got, as `(bytes_done, total_bytes)`,
+/// for drawing a progress bar.
+///
+/// Set it as [`DecoderConfig::progress`]; it's called each time decoding
+/// passes another [`DecoderConfig::progress_interval`] bytes, and once more
+/// when the document has decoded, with `bytes_done == total_bytes` unless
+/// the input had bytes after it.
+///
+/// ```rust
+/// use serde_bonjson::{decode_value_with_config, to_vec, DecoderConfig, ProgressHook};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// let bytes = to_vec(&vec!["some log line"; 100_000]).unwrap();
+/// let calls = Arc::new(AtomicUsize::new(0));
+/// let counter = Arc::clone(&calls);
+/// let config = DecoderConfig {
+///     progress: Some(ProgressHook::new(move |done, total| {
+///         assert!(done <= total);
+///         counter.fetch_add(1, Ordering::Relaxed);
+///     })),
+///     progress_interval: 256 << 10,
+///     ..DecoderConfig::default()
+/// };
+/// decode_value_with_config(&bytes, config).unwrap();
+/// assert!(calls.load(Ordering::Relaxed) > 1);
+/// ```
+#[derive(Clone)]
+pub struct ProgressHook(Arc<ProgressFn>);
+
+type ProgressFn = dyn Fn(usize, usize) + Send + Sync;
+
+impl ProgressHook {
+    /// Wrap a progress function.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(usize, usize) + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// Report `done` of `total` bytes.
+    pub fn report(&self, done: usize, total: usize) {
+        (self.0)(done, total);
+    }
+}
+
+impl fmt::Debug for ProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressHook")
     }
 }
 
@@ -139,14 +452,37 @@ pub struct DecoderConfig {
     pub allow_trailing_bytes: bool,
     /// How to handle duplicate keys (default: Error)
     pub duplicate_key_mode: DuplicateKeyMode,
+    /// How serde deserialization detects duplicate keys (default: Off).
+    /// Doesn't affect `decode_value`, which uses `duplicate_key_mode`.
+    pub duplicate_key_check: DuplicateKeyCheck,
     /// Maximum container nesting depth
     pub max_depth: usize,
     /// Maximum elements in a container
     pub max_container_size: usize,
     /// Maximum string length in bytes
     pub max_string_length: usize,
-    /// Maximum document size in bytes
+    /// Maximum document size in bytes. Above the spec's default of 2 GB
+    /// ([`limits::MAX_DOCUMENT_SIZE`]), this only applies with
+    /// `allow_huge_documents`.
     pub max_document_size: usize,
+    /// Let `max_document_size` and `max_typed_array_bytes` go past 2 GB, which
+    /// the spec permits as a non-default limit (default: false, treating
+    /// larger limits as 2 GB). On 64-bit targets, offsets and lengths within
+    /// such documents need no other configuration.
+    pub allow_huge_documents: bool,
+    /// Maximum memory, in bytes, that decoding the whole document may allocate
+    /// (default: unlimited). Counts string bytes plus a fixed cost per container
+    /// element, so it also bounds documents that stay within the per-item limits.
+    pub max_total_allocated_bytes: usize,
+    /// Maximum typed array payload in bytes (element count times element size),
+    /// capped at 2 GB unless `allow_huge_documents` is set
+    pub max_typed_array_bytes: usize,
+    /// Maximum number of record definitions a document may declare
+    pub max_record_definitions: usize,
+    /// Maximum keys in a single record definition
+    pub max_record_keys: usize,
+    /// Maximum length of a record definition key in bytes
+    pub max_record_key_length: usize,
     /// Maximum BigNumber exponent (absolute value)
     pub max_bignumber_exponent: usize,
     /// Maximum BigNumber magnitude in bytes
@@ -155,9 +491,77 @@ pub struct DecoderConfig {
     pub out_of_range_mode: OutOfRangeMode,
     /// How to handle invalid UTF-8 in strings (default: Reject)
     pub invalid_utf8_mode: InvalidUtf8Mode,
+    /// How strings are checked for valid UTF-8 and NUL (default: Auto)
+    pub string_validation: StringValidation,
     /// Unicode normalization mode (default: None).
     /// Requires the `unicode-normalization` feature for Nfc mode.
     pub unicode_normalization: UnicodeNormalization,
+    /// Applied to every object key, after normalization (default: None).
+    /// Used by `decode_value` and serde deserialization; struct fields match
+    /// the transformed key.
+    pub key_transform: Option<StringTransform>,
+    /// Applied to every string value, after normalization (default: None).
+    /// Used by `decode_value` and serde deserialization.
+    pub string_transform: Option<StringTransform>,
+    /// How to handle values with reserved type codes (default: Error)
+    pub unknown_type_code: UnknownTypeCodeMode,
+    /// How to handle record instances with more or fewer values than keys (default: NullFill)
+    pub record_mismatch: RecordMismatchMode,
+    /// Decode floats as [`Value::SizedFloat`](crate::Value::SizedFloat), keeping
+    /// the width they were written with (default: false), so that `encode_value`
+    /// writes them back byte for byte. For relays that must not alter payloads.
+    /// Only affects `decode_value`; serde deserialization sees plain floats.
+    pub preserve_float_width: bool,
+    /// Reject sized integers that a shorter encoding could hold (default:
+    /// false), such as 5 written as a sint16 rather than a small int, with
+    /// `DecodeError::InvalidData`. Signedness isn't checked: a uint8 and a sint8
+    /// are equally short.
+    pub require_canonical_ints: bool,
+    /// Reject every number that has a shorter encoding (default: false):
+    /// integers as under [`require_canonical_ints`](Self::require_canonical_ints),
+    /// floats holding whole numbers, and float64s that a float32 holds
+    /// exactly, with `DecodeError::InvalidData`. A value then has exactly one
+    /// accepted encoding, the one the encoder writes, as consensus and
+    /// signature checks over re-encoded data need.
+    pub require_canonical_numbers: bool,
+    /// Reject documents containing keys that the target struct doesn't declare,
+    /// as if every struct had `#[serde(deny_unknown_fields)]` (default: false).
+    /// Only affects serde deserialization; see [`from_slice_with_report`](crate::from_slice_with_report).
+    pub deny_unknown_fields_globally: bool,
+    /// How object keys are matched to struct fields (default: Exact).
+    /// Only affects serde deserialization.
+    pub field_matching: FieldMatching,
+    /// Pairs of names that match each other's struct fields, in either
+    /// direction, as if the field had `#[serde(alias)]` (default: empty).
+    /// Compared under `field_matching`; only affects serde deserialization.
+    pub field_aliases: Vec<(String, String)>,
+    /// Present numbers to self-describing targets (`deserialize_any`, as used
+    /// by untagged enums, `serde_json::Value` and other buffered content) the
+    /// way serde_json does (default: false): non-negative integers as `u64`,
+    /// negative ones as `i64`, and every float as `f64`. Without it, small
+    /// ints arrive as `i64` and float32 typed array elements as `f32`, so an
+    /// untagged enum ported from JSON can pick a different variant. Floats
+    /// the encoder narrowed to integers still arrive as integers. Only
+    /// affects serde deserialization.
+    pub untagged_numeric_coercion: bool,
+    /// Abort decoding with `DecodeError::DeadlineExceeded` once this instant passes (default: None).
+    /// Checked every [`CANCELLATION_POLL_INTERVAL`] container elements.
+    pub deadline: Option<Instant>,
+    /// Abort decoding with `DecodeError::Cancelled` once this flag is set (default: None).
+    /// Checked every [`CANCELLATION_POLL_INTERVAL`] container elements.
+    pub cancel_token: Option<Arc<AtomicBool>>,
+    /// Told how many bytes of the document have been decoded (default: None).
+    /// Called by whole-document decodes (`decode_value`, `from_slice`,
+    /// `from_reader` and their variants) when they pass each
+    /// `progress_interval`, checked every [`CANCELLATION_POLL_INTERVAL`]
+    /// container elements. `ReaderDeserializer` and lazy navigation don't
+    /// report, since they never see the whole document.
+    pub progress: Option<ProgressHook>,
+    /// Bytes decoded between calls to `progress` (default: 1 MiB).
+    pub progress_interval: usize,
+    /// Receives [`CodecMetrics`] when a top-level decode call finishes (default: None).
+    #[cfg(feature = "tracing")]
+    pub metrics_sink: Option<Arc<dyn MetricsSink>>,
 }
 
 impl Default for DecoderConfig {
@@ -167,15 +571,170 @@ impl Default for DecoderConfig {
             nan_infinity_mode: NanInfinityMode::default(),
             allow_trailing_bytes: false,
             duplicate_key_mode: DuplicateKeyMode::default(),
+            duplicate_key_check: DuplicateKeyCheck::default(),
             max_depth: limits::MAX_DEPTH,
             max_container_size: limits::MAX_CONTAINER_SIZE,
             max_string_length: limits::MAX_STRING_LENGTH,
             max_document_size: limits::MAX_DOCUMENT_SIZE,
+            allow_huge_documents: false,
+            max_total_allocated_bytes: usize::MAX,
+            max_typed_array_bytes: limits::MAX_TYPED_ARRAY_BYTES,
+            max_record_definitions: limits::MAX_RECORD_DEFINITIONS,
+            max_record_keys: limits::MAX_RECORD_KEYS,
+            max_record_key_length: limits::MAX_RECORD_KEY_LENGTH,
             max_bignumber_exponent: limits::MAX_BIGNUMBER_EXPONENT,
             max_bignumber_magnitude: limits::MAX_BIGNUMBER_MAGNITUDE,
             out_of_range_mode: OutOfRangeMode::default(),
             invalid_utf8_mode: InvalidUtf8Mode::default(),
+            string_validation: StringValidation::default(),
             unicode_normalization: UnicodeNormalization::default(),
+            key_transform: None,
+            string_transform: None,
+            unknown_type_code: UnknownTypeCodeMode::default(),
+            record_mismatch: RecordMismatchMode::default(),
+            preserve_float_width: false,
+            require_canonical_ints: false,
+            require_canonical_numbers: false,
+            deny_unknown_fields_globally: false,
+            field_matching: FieldMatching::default(),
+            field_aliases: Vec::new(),
+            untagged_numeric_coercion: false,
+            deadline: None,
+            cancel_token: None,
+            progress: None,
+            progress_interval: 1 << 20,
+            #[cfg(feature = "tracing")]
+            metrics_sink: None,
+        }
+    }
+}
+
+impl DecoderConfig {
+    config_setters! {
+        allow_nul: bool,
+        nan_infinity_mode: NanInfinityMode,
+        allow_trailing_bytes: bool,
+        duplicate_key_mode: DuplicateKeyMode,
+        duplicate_key_check: DuplicateKeyCheck,
+        max_depth: usize,
+        max_container_size: usize,
+        max_string_length: usize,
+        max_document_size: usize,
+        allow_huge_documents: bool,
+        max_total_allocated_bytes: usize,
+        max_typed_array_bytes: usize,
+        max_record_definitions: usize,
+        max_record_keys: usize,
+        max_record_key_length: usize,
+        max_bignumber_exponent: usize,
+        max_bignumber_magnitude: usize,
+        out_of_range_mode: OutOfRangeMode,
+        invalid_utf8_mode: InvalidUtf8Mode,
+        string_validation: StringValidation,
+        unicode_normalization: UnicodeNormalization,
+        unknown_type_code: UnknownTypeCodeMode,
+        record_mismatch: RecordMismatchMode,
+        preserve_float_width: bool,
+        require_canonical_ints: bool,
+        require_canonical_numbers: bool,
+        deny_unknown_fields_globally: bool,
+        field_matching: FieldMatching,
+        field_aliases: Vec<(String, String)>,
+        untagged_numeric_coercion: bool,
+        progress_interval: usize,
+    }
+    config_setters! { Some:
+        key_transform: StringTransform,
+        string_transform: StringTransform,
+        deadline: Instant,
+        cancel_token: Arc<AtomicBool>,
+        progress: ProgressHook,
+        #[cfg(feature = "tracing")]
+        metrics_sink: Arc<dyn MetricsSink>,
+    }
+
+    /// `max_document_size`, capped at 2 GB unless `allow_huge_documents` is set.
+    pub(crate) fn document_size_limit(&self) -> usize {
+        self.huge_limit(self.max_document_size)
+    }
+
+    /// `max_typed_array_bytes`, capped at 2 GB unless `allow_huge_documents` is set.
+    pub(crate) fn typed_array_bytes_limit(&self) -> usize {
+        self.huge_limit(self.max_typed_array_bytes)
+    }
+
+    fn huge_limit(&self, limit: usize) -> usize {
+        if self.allow_huge_documents {
+            limit
+        } else {
+            limit.min(limits::MAX_DOCUMENT_SIZE)
+        }
+    }
+
+    /// Every validation on, and limits sized for untrusted input to a service:
+    /// duplicate keys (also under serde, through [`DuplicateKeyCheck::Exact`]),
+    /// mismatched records, unknown struct fields and non-canonical numbers are
+    /// errors, and documents are capped at 16 MiB, 64 levels deep, with at
+    /// most 64 MiB allocated.
+    #[must_use]
+    pub fn strict() -> Self {
+        Self {
+            duplicate_key_mode: DuplicateKeyMode::Error,
+            duplicate_key_check: DuplicateKeyCheck::Exact,
+            require_canonical_ints: true,
+            require_canonical_numbers: true,
+            record_mismatch: RecordMismatchMode::Error,
+            deny_unknown_fields_globally: true,
+            max_depth: 64,
+            max_container_size: 100_000,
+            max_string_length: 1 << 20,
+            max_document_size: 16 << 20,
+            max_total_allocated_bytes: 64 << 20,
+            max_typed_array_bytes: 8 << 20,
+            max_record_definitions: 1024,
+            max_record_keys: 1024,
+            max_record_key_length: 1024,
+            max_bignumber_exponent: 1000,
+            ..Self::default()
+        }
+    }
+
+    /// Accept what other encoders may produce: NUL in strings, NaN and
+    /// Infinity, trailing bytes after the document, and duplicate keys (the
+    /// last one wins). Limits stay at their defaults.
+    #[must_use]
+    pub fn permissive() -> Self {
+        Self {
+            allow_nul: true,
+            nan_infinity_mode: NanInfinityMode::Allow,
+            allow_trailing_bytes: true,
+            duplicate_key_mode: DuplicateKeyMode::KeepLast,
+            ..Self::default()
+        }
+    }
+
+    /// Tiny limits for memory-constrained devices: 64 KiB documents, 16
+    /// levels deep, at most 256 KiB allocated, and no modes that allocate
+    /// replacement strings (invalid UTF-8, NaN and out-of-range numbers are
+    /// errors).
+    #[must_use]
+    pub fn embedded() -> Self {
+        Self {
+            max_depth: 16,
+            max_container_size: 1024,
+            max_string_length: 4096,
+            max_document_size: 64 << 10,
+            max_total_allocated_bytes: 256 << 10,
+            max_typed_array_bytes: 8 << 10,
+            max_record_definitions: 64,
+            max_record_keys: 64,
+            max_record_key_length: 256,
+            max_bignumber_exponent: 64,
+            nan_infinity_mode: NanInfinityMode::Reject,
+            out_of_range_mode: OutOfRangeMode::Error,
+            invalid_utf8_mode: InvalidUtf8Mode::Reject,
+            unicode_normalization: UnicodeNormalization::None,
+            ..Self::default()
         }
     }
 }
@@ -187,10 +746,29 @@ pub struct Decoder<'a> {
     config: DecoderConfig,
     /// Stack tracking container depth (true = object)
     containers: Vec<bool>,
-    /// Stored record definitions (each is a list of key strings)
-    record_definitions: Vec<Vec<String>>,
+    /// Stored record definitions (each is a list of key strings).
+    /// Shared so that decoders resumed mid-document don't re-copy them.
+    record_definitions: Arc<Vec<Vec<String>>>,
+    /// Bytes charged against `max_total_allocated_bytes` so far
+    allocated_bytes: usize,
+    /// Elements decoded since `deadline` and `cancel_token` were last checked
+    elements_since_poll: usize,
+    /// Position past which `progress` is next called; `usize::MAX` when it isn't set
+    next_progress: usize,
+    /// Metadata tallied as values are decoded, for `decode_with_metadata`
+    metadata: Option<Box<Collector>>,
+    #[cfg(feature = "tracing")]
+    metrics: CodecMetrics,
 }
 
+/// How many container elements are decoded between checks of the `deadline`
+/// and `cancel_token`, keeping the cost of polling off the per-value path.
+pub const CANCELLATION_POLL_INTERVAL: usize = 1024;
+
+/// The allocation charged for each container element: the size of the `Value`
+/// slot it occupies once decoded.
+pub(crate) const ELEMENT_ALLOCATION: usize = std::mem::size_of::<crate::value::Value>();
+
 /// The type of value that was decoded.
 #[derive(Debug, Clone, PartialEq)]
 pub enum DecodedValue<'a> {
@@ -210,6 +788,163 @@ pub enum DecodedValue<'a> {
     TypedArrayStart { element_type_code: u8, count: usize },
 }
 
+/// A fully-owned counterpart to [`DecodedValue`].
+///
+/// Strings are copied into `String`, so the value carries no borrow of the
+/// input buffer and can be queued, sent across threads, or held after the
+/// buffer is dropped.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedValueOwned {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    BigNumber(BigNumber),
+    String(String),
+    ArrayStart,
+    ObjectStart,
+    ContainerEnd,
+    /// Start of a record instance, with the definition index.
+    RecordInstanceStart(usize),
+    /// Start of a typed array, with element type code and count.
+    TypedArrayStart { element_type_code: u8, count: usize },
+}
+
+impl DecodedValue<'_> {
+    /// Convert into a [`DecodedValueOwned`] that does not borrow the input.
+    #[must_use]
+    pub fn into_owned(self) -> DecodedValueOwned {
+        match self {
+            DecodedValue::Null => DecodedValueOwned::Null,
+            DecodedValue::Bool(b) => DecodedValueOwned::Bool(b),
+            DecodedValue::Int(n) => DecodedValueOwned::Int(n),
+            DecodedValue::UInt(n) => DecodedValueOwned::UInt(n),
+            DecodedValue::Float(f) => DecodedValueOwned::Float(f),
+            DecodedValue::BigNumber(bn) => DecodedValueOwned::BigNumber(bn),
+            DecodedValue::String(s) => DecodedValueOwned::String(s.into_owned()),
+            DecodedValue::ArrayStart => DecodedValueOwned::ArrayStart,
+            DecodedValue::ObjectStart => DecodedValueOwned::ObjectStart,
+            DecodedValue::ContainerEnd => DecodedValueOwned::ContainerEnd,
+            DecodedValue::RecordInstanceStart(index) => DecodedValueOwned::RecordInstanceStart(index),
+            DecodedValue::TypedArrayStart { element_type_code, count } => {
+                DecodedValueOwned::TypedArrayStart { element_type_code, count }
+            }
+        }
+    }
+}
+
+/// A whole typed array borrowed from the input, returned by
+/// [`Decoder::read_typed_array_slice`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypedArraySlice<'a> {
+    /// The array's type code, one of the `type_code::TYPED_ARRAY_*` constants.
+    pub element_type_code: u8,
+    /// Number of elements.
+    pub count: usize,
+    /// The elements' little-endian bytes, `count * element_size()` long.
+    /// Not necessarily aligned for the element type.
+    pub bytes: &'a [u8],
+}
+
+impl TypedArraySlice<'_> {
+    /// Size of each element in bytes.
+    #[must_use]
+    pub fn element_size(&self) -> usize {
+        type_code::typed_array_element_size(self.element_type_code)
+    }
+}
+
+/// A Rust type that a typed array decodes into directly, with
+/// [`Decoder::read_typed_array_into`]. Implemented for the ten element types
+/// of BONJSON typed arrays.
+pub trait TypedArrayElement: Copy {
+    /// The type code of a typed array of this element type.
+    const TYPE_CODE: u8;
+
+    /// Convert one little-endian element. `bytes` is exactly the element's size.
+    fn from_le_slice(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_typed_array_element {
+    ($($ty:ty => $code:ident;)*) => {
+        $(
+            impl TypedArrayElement for $ty {
+                const TYPE_CODE: u8 = type_code::$code;
+
+                #[inline]
+                fn from_le_slice(bytes: &[u8]) -> Self {
+                    <$ty>::from_le_bytes(bytes.try_into().unwrap())
+                }
+            }
+        )*
+    };
+}
+
+impl_typed_array_element! {
+    u8 => TYPED_ARRAY_UINT8;
+    u16 => TYPED_ARRAY_UINT16;
+    u32 => TYPED_ARRAY_UINT32;
+    u64 => TYPED_ARRAY_UINT64;
+    i8 => TYPED_ARRAY_SINT8;
+    i16 => TYPED_ARRAY_SINT16;
+    i32 => TYPED_ARRAY_SINT32;
+    i64 => TYPED_ARRAY_SINT64;
+    f32 => TYPED_ARRAY_FLOAT32;
+    f64 => TYPED_ARRAY_FLOAT64;
+}
+
+/// A typed array element at its encoded width, so that float32 elements
+/// can reach an `f32` target without widening to f64 first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TypedElement {
+    Int(i64),
+    UInt(u64),
+    F32(f32),
+    F64(f64),
+}
+
+impl From<TypedElement> for DecodedValue<'_> {
+    fn from(element: TypedElement) -> Self {
+        match element {
+            TypedElement::Int(n) => DecodedValue::Int(n),
+            TypedElement::UInt(n) => DecodedValue::UInt(n),
+            TypedElement::F32(f) => DecodedValue::Float(f64::from(f)),
+            TypedElement::F64(f) => DecodedValue::Float(f),
+        }
+    }
+}
+
+impl From<DecodedValue<'_>> for DecodedValueOwned {
+    fn from(value: DecodedValue<'_>) -> Self {
+        value.into_owned()
+    }
+}
+
+/// One-line event text for logs and test assertions, e.g. `int 42`,
+/// `string "x"`, `typed_array_start float64 x3`, `record_instance_start #0`.
+impl fmt::Display for DecodedValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodedValue::Null => write!(f, "null"),
+            DecodedValue::Bool(b) => write!(f, "{b}"),
+            DecodedValue::Int(n) => write!(f, "int {n}"),
+            DecodedValue::UInt(n) => write!(f, "uint {n}"),
+            DecodedValue::Float(n) => write!(f, "float {}", crate::value::Value::Float(*n)),
+            DecodedValue::BigNumber(bn) => write!(f, "big_number {}", bn.to_string_notation()),
+            DecodedValue::String(s) => write!(f, "string \"{}\"", s.escape_default()),
+            DecodedValue::ArrayStart => write!(f, "array_start"),
+            DecodedValue::ObjectStart => write!(f, "object_start"),
+            DecodedValue::ContainerEnd => write!(f, "container_end"),
+            DecodedValue::RecordInstanceStart(index) => write!(f, "record_instance_start #{index}"),
+            DecodedValue::TypedArrayStart { element_type_code, count } => {
+                let element = type_code::name(type_code::typed_array_scalar_code(*element_type_code));
+                write!(f, "typed_array_start {element} x{count}")
+            }
+        }
+    }
+}
+
 impl<'a> Decoder<'a> {
     /// Create a new decoder for the given data.
     #[must_use]
@@ -220,20 +955,76 @@ impl<'a> Decoder<'a> {
     /// Create a new decoder with custom configuration.
     #[must_use]
     pub fn with_config(data: &'a [u8], config: DecoderConfig) -> Self {
+        let next_progress = if config.progress.is_some() { config.progress_interval.max(1) } else { usize::MAX };
         Self {
             data,
             pos: 0,
             config,
             containers: Vec::new(),
-            record_definitions: Vec::new(),
+            record_definitions: Arc::new(Vec::new()),
+            allocated_bytes: 0,
+            elements_since_poll: 0,
+            next_progress,
+            metadata: None,
+            #[cfg(feature = "tracing")]
+            metrics: CodecMetrics::default(),
+        }
+    }
+
+    /// Create a decoder positioned at `pos`, with record definitions that were
+    /// already read from the start of the same document. It doesn't report
+    /// progress, since `data` may be only part of the document.
+    pub(crate) fn resume(
+        data: &'a [u8],
+        config: DecoderConfig,
+        record_definitions: Arc<Vec<Vec<String>>>,
+        pos: usize,
+    ) -> Self {
+        Self {
+            data,
+            pos,
+            config,
+            containers: Vec::new(),
+            record_definitions,
+            allocated_bytes: 0,
+            elements_since_poll: 0,
+            next_progress: usize::MAX,
+            metadata: None,
+            #[cfg(feature = "tracing")]
+            metrics: CodecMetrics::default(),
+        }
+    }
+
+    /// Create a decoder that owns its input, for decode state that has to
+    /// live across `.await` points or move to another task.
+    #[must_use]
+    pub fn from_vec(data: Vec<u8>) -> OwnedDecoder {
+        OwnedDecoder::new(data)
+    }
+
+    /// Move the decoder's state onto `data`, which must be the same input
+    /// (or an empty slice while the state is parked).
+    fn rebind<'b>(self, data: &'b [u8]) -> Decoder<'b> {
+        Decoder {
+            data,
+            pos: self.pos,
+            config: self.config,
+            containers: self.containers,
+            record_definitions: self.record_definitions,
+            allocated_bytes: self.allocated_bytes,
+            elements_since_poll: self.elements_since_poll,
+            next_progress: self.next_progress,
+            metadata: self.metadata,
+            #[cfg(feature = "tracing")]
+            metrics: self.metrics,
         }
     }
 
     /// Check document size limit (called once at start of decoding).
     #[inline]
     pub fn check_document_size(&self) -> Result<()> {
-        if self.data.len() > self.config.max_document_size {
-            return Err(Error::MaxDocumentSizeExceeded);
+        if self.data.len() > self.config.document_size_limit() {
+            return Err(DecodeError::MaxDocumentSizeExceeded);
         }
         Ok(())
     }
@@ -262,6 +1053,93 @@ impl<'a> Decoder<'a> {
         &self.config
     }
 
+    /// Get the number of bytes charged against `max_total_allocated_bytes` so far.
+    #[must_use]
+    pub fn allocated_bytes(&self) -> usize {
+        self.allocated_bytes
+    }
+
+    /// Count a value read without the decoding methods, such as a null
+    /// skipped after peeking at it.
+    #[inline]
+    pub(crate) fn count_value(&mut self) {
+        count_metric!(self, values);
+    }
+
+    /// Report the counters so far to `tracing` and the configured metrics sink.
+    #[inline]
+    pub(crate) fn report_metrics(&self) {
+        #[cfg(feature = "tracing")]
+        {
+            let metrics = CodecMetrics { bytes: self.pos, ..self.metrics };
+            crate::metrics::report(CodecOperation::Decode, &metrics, self.config.metrics_sink.as_ref());
+        }
+    }
+
+    /// Charge memory that decoding is about to allocate against `max_total_allocated_bytes`.
+    #[inline]
+    pub(crate) fn charge_allocation(&mut self, bytes: usize) -> Result<()> {
+        self.allocated_bytes = self.allocated_bytes.saturating_add(bytes);
+        if self.allocated_bytes > self.config.max_total_allocated_bytes {
+            return Err(DecodeError::MaxTotalAllocatedBytesExceeded);
+        }
+        Ok(())
+    }
+
+    /// Per-element bookkeeping for consumers that materialize a container:
+    /// charges the element (plus `extra_bytes`, e.g. its key) and polls for cancellation.
+    #[inline]
+    pub(crate) fn begin_element(&mut self, extra_bytes: usize) -> Result<()> {
+        self.charge_allocation(ELEMENT_ALLOCATION + extra_bytes)?;
+        self.poll_cancellation()
+    }
+
+    /// Check `deadline` and `cancel_token` once every [`CANCELLATION_POLL_INTERVAL`] calls.
+    #[inline]
+    pub(crate) fn poll_cancellation(&mut self) -> Result<()> {
+        self.elements_since_poll += 1;
+        if self.elements_since_poll < CANCELLATION_POLL_INTERVAL {
+            return Ok(());
+        }
+        self.elements_since_poll = 0;
+        if self.pos >= self.next_progress {
+            self.report_progress();
+        }
+        self.check_cancellation()
+    }
+
+    /// Call `progress` with the current position, and move the next call
+    /// `progress_interval` past it.
+    #[cold]
+    fn report_progress(&mut self) {
+        if let Some(progress) = &self.config.progress {
+            progress.report(self.pos, self.data.len());
+            self.next_progress = self.pos.saturating_add(self.config.progress_interval.max(1));
+        }
+    }
+
+    /// Tell `progress` where a successful whole-document decode ended.
+    pub(crate) fn finish_progress(&self) {
+        if let (Some(progress), true) = (&self.config.progress, self.next_progress != usize::MAX) {
+            progress.report(self.pos, self.data.len());
+        }
+    }
+
+    /// Check `deadline` and `cancel_token` now.
+    pub fn check_cancellation(&self) -> Result<()> {
+        if let Some(token) = &self.config.cancel_token {
+            if token.load(Ordering::Relaxed) {
+                return Err(DecodeError::Cancelled);
+            }
+        }
+        if let Some(deadline) = self.config.deadline {
+            if Instant::now() >= deadline {
+                return Err(DecodeError::DeadlineExceeded);
+            }
+        }
+        Ok(())
+    }
+
     /// Skip a single byte (for internal use after peeking).
     #[inline]
     pub(crate) fn skip_byte(&mut self) {
@@ -280,7 +1158,7 @@ impl<'a> Decoder<'a> {
     #[inline]
     fn read_byte(&mut self) -> Result<u8> {
         if self.pos >= self.data.len() {
-            return Err(Error::Truncated);
+            return Err(DecodeError::Truncated);
         }
         let byte = self.data[self.pos];
         self.pos += 1;
@@ -289,9 +1167,9 @@ impl<'a> Decoder<'a> {
 
     /// Read exactly n bytes.
     #[inline]
-    fn read_bytes(&mut self, n: usize) -> Result<&'a [u8]> {
-        if self.pos + n > self.data.len() {
-            return Err(Error::Truncated);
+    pub(crate) fn read_bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        if n > self.data.len() - self.pos {
+            return Err(DecodeError::Truncated);
         }
         let bytes = &self.data[self.pos..self.pos + n];
         self.pos += n;
@@ -313,7 +1191,7 @@ impl<'a> Decoder<'a> {
     #[inline]
     pub(crate) fn peek_type_code(&self) -> Result<u8> {
         if self.pos >= self.data.len() {
-            return Err(Error::Truncated);
+            return Err(DecodeError::Truncated);
         }
         Ok(self.data[self.pos])
     }
@@ -324,21 +1202,40 @@ impl<'a> Decoder<'a> {
         if self.pos < self.data.len() && self.data[self.pos] == type_code::CONTAINER_END {
             self.pos += 1;
             self.containers.pop();
+            if let Some(metadata) = &mut self.metadata {
+                metadata.container_end();
+            }
             Ok(true)
         } else if self.pos >= self.data.len() {
-            Err(Error::Truncated)
+            Err(DecodeError::Truncated)
         } else {
             Ok(false)
         }
     }
 
+    /// Move to the next element of the array being read, dropping values
+    /// skipped under `UnknownTypeCodeMode::SkipValue`. Returns false after
+    /// consuming the end marker; every array loop that hands elements to a
+    /// caller goes through here so skipped values never surface.
+    pub(crate) fn skip_to_array_element(&mut self) -> Result<bool> {
+        loop {
+            if self.try_consume_container_end()? {
+                return Ok(false);
+            }
+            if !self.skip_unknown_value()? {
+                return Ok(true);
+            }
+        }
+    }
+
     /// Expect and skip an array start marker.
     #[inline]
     pub(crate) fn expect_array_start(&mut self) -> Result<()> {
         let tc = self.read_byte()?;
         if tc != type_code::ARRAY {
-            return Err(Error::Custom(format!("expected array, got 0x{tc:02x}")));
+            return Err(DecodeError::UnexpectedType { expected: "array", found: tc });
         }
+        count_metric!(self, values);
         self.begin_container(false)
     }
 
@@ -347,8 +1244,9 @@ impl<'a> Decoder<'a> {
     pub(crate) fn expect_object_start(&mut self) -> Result<()> {
         let tc = self.read_byte()?;
         if tc != type_code::OBJECT {
-            return Err(Error::Custom(format!("expected object, got 0x{tc:02x}")));
+            return Err(DecodeError::UnexpectedType { expected: "object", found: tc });
         }
+        count_metric!(self, values);
         self.begin_container(true)
     }
 
@@ -357,6 +1255,7 @@ impl<'a> Decoder<'a> {
     #[allow(clippy::cast_possible_wrap)]
     pub(crate) fn decode_i64_direct(&mut self) -> Result<i64> {
         let tc = self.read_byte()?;
+        count_metric!(self, values);
 
         if type_code::is_small_int(tc) {
             return Ok(i64::from(type_code::small_int_value(tc)));
@@ -367,18 +1266,19 @@ impl<'a> Decoder<'a> {
             return if type_code::int_is_signed(tc) {
                 self.read_signed_int_sized(size)
             } else {
-                #[allow(clippy::cast_possible_wrap)]
-                Ok(self.read_unsigned_int_sized(size)? as i64)
+                i64::try_from(self.read_unsigned_int_sized(size)?)
+                    .map_err(|_| DecodeError::ValueOutOfRange)
             };
         }
 
-        Err(Error::Custom(format!("expected integer, got 0x{tc:02x}")))
+        Err(DecodeError::UnexpectedType { expected: "integer", found: tc })
     }
 
     /// Decode a u64 directly.
     #[inline]
     pub(crate) fn decode_u64_direct(&mut self) -> Result<u64> {
         let tc = self.read_byte()?;
+        count_metric!(self, values);
 
         if type_code::is_small_int(tc) {
             return Ok(u64::from(type_code::small_int_value(tc)));
@@ -389,7 +1289,7 @@ impl<'a> Decoder<'a> {
             return if type_code::int_is_signed(tc) {
                 let signed_val = self.read_signed_int_sized(size)?;
                 if signed_val < 0 {
-                    return Err(Error::ValueOutOfRange);
+                    return Err(DecodeError::ValueOutOfRange);
                 }
                 #[allow(clippy::cast_sign_loss)]
                 Ok(signed_val as u64)
@@ -398,17 +1298,53 @@ impl<'a> Decoder<'a> {
             };
         }
 
-        Err(Error::Custom(format!("expected unsigned integer, got 0x{tc:02x}")))
+        Err(DecodeError::UnexpectedType { expected: "unsigned integer", found: tc })
+    }
+
+    /// Decode an i128 directly, accepting integers and integral BigNumbers.
+    pub(crate) fn decode_i128_direct(&mut self) -> Result<i128> {
+        let tc = self.peek_type_code()?;
+        if tc == type_code::BIG_NUMBER {
+            self.skip_byte();
+            count_metric!(self, values);
+            let (sign, magnitude, exponent) = self.read_wide_big_number()?;
+            let magnitude = scale_u128(magnitude, exponent).ok_or(DecodeError::ValueOutOfRange)?;
+            return if sign < 0 {
+                0i128.checked_sub_unsigned(magnitude).ok_or(DecodeError::ValueOutOfRange)
+            } else {
+                i128::try_from(magnitude).map_err(|_| DecodeError::ValueOutOfRange)
+            };
+        }
+        if type_code::is_any_int(tc) && !type_code::int_is_signed(tc) {
+            return Ok(i128::from(self.decode_u64_direct()?));
+        }
+        Ok(i128::from(self.decode_i64_direct()?))
+    }
+
+    /// Decode a u128 directly, accepting integers and integral BigNumbers.
+    pub(crate) fn decode_u128_direct(&mut self) -> Result<u128> {
+        let tc = self.peek_type_code()?;
+        if tc == type_code::BIG_NUMBER {
+            self.skip_byte();
+            count_metric!(self, values);
+            let (sign, magnitude, exponent) = self.read_wide_big_number()?;
+            if sign < 0 && magnitude != 0 {
+                return Err(DecodeError::ValueOutOfRange);
+            }
+            return scale_u128(magnitude, exponent).ok_or(DecodeError::ValueOutOfRange);
+        }
+        Ok(u128::from(self.decode_u64_direct()?))
     }
 
     /// Decode a bool directly.
     #[inline]
     pub(crate) fn decode_bool_direct(&mut self) -> Result<bool> {
         let tc = self.read_byte()?;
+        count_metric!(self, values);
         match tc {
             type_code::TRUE => Ok(true),
             type_code::FALSE => Ok(false),
-            _ => Err(Error::Custom(format!("expected bool, got 0x{tc:02x}"))),
+            _ => Err(DecodeError::UnexpectedType { expected: "bool", found: tc }),
         }
     }
 
@@ -416,6 +1352,7 @@ impl<'a> Decoder<'a> {
     #[inline]
     pub(crate) fn decode_str_direct(&mut self) -> Result<&'a str> {
         let tc = self.read_byte()?;
+        count_metric!(self, values);
 
         if type_code::is_short_string(tc) {
             let len = type_code::short_string_len(tc);
@@ -426,7 +1363,7 @@ impl<'a> Decoder<'a> {
             return self.decode_long_string_content();
         }
 
-        Err(Error::ExpectedObjectKey)
+        Err(DecodeError::ExpectedObjectKey)
     }
 
     /// Decode an f64 directly.
@@ -435,6 +1372,7 @@ impl<'a> Decoder<'a> {
     #[allow(clippy::cast_precision_loss)]
     pub(crate) fn decode_f64_direct(&mut self) -> Result<f64> {
         let tc = self.read_byte()?;
+        count_metric!(self, values);
 
         if type_code::is_small_int(tc) {
             return Ok(f64::from(type_code::small_int_value(tc)));
@@ -454,7 +1392,14 @@ impl<'a> Decoder<'a> {
         match tc {
             type_code::FLOAT32 => self.read_float32(),
             type_code::FLOAT64 => self.read_float64(),
-            _ => Err(Error::Custom(format!("expected number, got 0x{tc:02x}"))),
+            type_code::BIG_NUMBER => {
+                let bn = self.read_big_number()?;
+                if crate::bignumber_exceeds_f64_range(&bn) {
+                    return Err(DecodeError::ValueOutOfRange);
+                }
+                Ok(bn.to_f64())
+            }
+            _ => Err(DecodeError::UnexpectedType { expected: "number", found: tc }),
         }
     }
 
@@ -465,7 +1410,7 @@ impl<'a> Decoder<'a> {
     /// Begin a container (push onto stack, check depth).
     fn begin_container(&mut self, is_object: bool) -> Result<()> {
         if self.containers.len() >= self.config.max_depth {
-            return Err(Error::MaxDepthExceeded);
+            return Err(DecodeError::MaxDepthExceeded);
         }
         self.containers.push(is_object);
         Ok(())
@@ -474,6 +1419,10 @@ impl<'a> Decoder<'a> {
     /// Decode a value given its type code.
     #[allow(clippy::cast_possible_wrap)]
     fn decode_value_with_type_code(&mut self, tc: u8) -> Result<DecodedValue<'a>> {
+        if tc != type_code::CONTAINER_END {
+            count_metric!(self, values);
+        }
+
         // Small integers: 0x00-0x64
         if type_code::is_small_int(tc) {
             return Ok(DecodedValue::Int(i64::from(type_code::small_int_value(tc))));
@@ -500,14 +1449,8 @@ impl<'a> Decoder<'a> {
 
         // Typed arrays: 0xf5-0xfe
         if type_code::is_typed_array(tc) {
-            let remaining = &self.data[self.pos..];
-            let (count_raw, consumed) = leb128_decode(remaining)
-                .ok_or(Error::Truncated)?;
-            self.pos += consumed;
-            let count = count_raw as usize;
-            if count > self.config.max_container_size {
-                return Err(Error::MaxContainerSizeExceeded);
-            }
+            let count = self.read_typed_array_count(tc)?;
+            count_metric!(self, typed_arrays);
             self.begin_container(false)?;
             return Ok(DecodedValue::TypedArrayStart { element_type_code: tc, count });
         }
@@ -526,7 +1469,7 @@ impl<'a> Decoder<'a> {
             type_code::FALSE => Ok(DecodedValue::Bool(false)),
             type_code::TRUE => Ok(DecodedValue::Bool(true)),
             type_code::CONTAINER_END => {
-                self.containers.pop().ok_or(Error::UnbalancedContainers)?;
+                self.containers.pop().ok_or(DecodeError::UnbalancedContainers)?;
                 Ok(DecodedValue::ContainerEnd)
             }
             type_code::ARRAY => {
@@ -539,20 +1482,21 @@ impl<'a> Decoder<'a> {
             }
             type_code::RECORD_DEF => {
                 // Record definitions must not appear in value position
-                Err(Error::InvalidTypeCode(tc))
+                Err(DecodeError::InvalidTypeCode(tc))
             }
             type_code::RECORD_INSTANCE => {
                 let remaining = &self.data[self.pos..];
                 let (index_raw, consumed) = leb128_decode(remaining)
-                    .ok_or(Error::Truncated)?;
+                    .ok_or(DecodeError::Truncated)?;
                 self.pos += consumed;
                 let def_index = index_raw as usize;
                 if def_index >= self.record_definitions.len() {
-                    return Err(Error::InvalidData(format!(
+                    return Err(DecodeError::InvalidData(format!(
                         "record definition index {} out of range (have {})",
                         def_index, self.record_definitions.len()
                     )));
                 }
+                count_metric!(self, records);
                 self.begin_container(false)?;
                 Ok(DecodedValue::RecordInstanceStart(def_index))
             }
@@ -560,7 +1504,46 @@ impl<'a> Decoder<'a> {
                 let s = self.decode_long_string_content_cow()?;
                 Ok(DecodedValue::String(s))
             }
-            _ => Err(Error::InvalidTypeCode(tc)),
+            tc if type_code::is_reserved(tc) && self.config.unknown_type_code != UnknownTypeCodeMode::Error => {
+                self.skip_unknown_payload()?;
+                Ok(DecodedValue::Null)
+            }
+            _ => Err(DecodeError::InvalidTypeCode(tc)),
+        }
+    }
+
+    /// Consume the LEB128 length and payload that follow a reserved type code.
+    fn skip_unknown_payload(&mut self) -> Result<()> {
+        let (len, consumed) = leb128_decode(&self.data[self.pos..]).ok_or(DecodeError::Truncated)?;
+        self.pos += consumed;
+        match usize::try_from(len) {
+            Ok(len) if len <= self.data.len() - self.pos => {
+                self.pos += len;
+                Ok(())
+            }
+            _ => Err(DecodeError::Truncated),
+        }
+    }
+
+    /// Under `UnknownTypeCodeMode::SkipValue`, consume the next value if its
+    /// type code is reserved, returning whether it did. Callers use this
+    /// where the value can be dropped entirely.
+    pub(crate) fn skip_unknown_value(&mut self) -> Result<bool> {
+        if self.config.unknown_type_code != UnknownTypeCodeMode::SkipValue {
+            return Ok(false);
+        }
+        match self.data.get(self.pos) {
+            Some(&tc) if type_code::is_reserved(tc) => {
+                let start = self.pos;
+                self.pos += 1;
+                count_metric!(self, values);
+                self.skip_unknown_payload()?;
+                if let Some(metadata) = &mut self.metadata {
+                    metadata.skipped(self.pos - start);
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
         }
     }
 
@@ -570,7 +1553,11 @@ impl<'a> Decoder<'a> {
         let bytes = self.read_bytes(size)?;
         let mut buf = [0u8; 8];
         buf[..size].copy_from_slice(bytes);
-        Ok(u64::from_le_bytes(buf))
+        let value = u64::from_le_bytes(buf);
+        if (self.config.require_canonical_ints || self.config.require_canonical_numbers) && 1 + size != crate::encoder::unsigned_int_encoding_size(value) {
+            return Err(non_canonical_int(value));
+        }
+        Ok(value)
     }
 
     /// Read a signed integer of given byte size (1, 2, 4, or 8).
@@ -580,7 +1567,11 @@ impl<'a> Decoder<'a> {
         let fill = ((bytes[size - 1] as i8) >> 7) as u8;
         let mut buf = [fill; 8];
         buf[..size].copy_from_slice(bytes);
-        Ok(i64::from_le_bytes(buf))
+        let value = i64::from_le_bytes(buf);
+        if (self.config.require_canonical_ints || self.config.require_canonical_numbers) && 1 + size != crate::encoder::signed_int_encoding_size(value) {
+            return Err(non_canonical_int(value));
+        }
+        Ok(value)
     }
 
     /// Read a float32 value.
@@ -589,6 +1580,9 @@ impl<'a> Decoder<'a> {
         let bytes = self.read_bytes(4)?;
         let value = f64::from(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
         self.check_float(value)?;
+        if self.config.require_canonical_numbers && !is_canonical_float(value, FloatWidth::F32) {
+            return Err(non_canonical_float(value));
+        }
         Ok(value)
     }
 
@@ -598,6 +1592,9 @@ impl<'a> Decoder<'a> {
         let bytes = self.read_bytes(8)?;
         let value = f64::from_le_bytes(bytes.try_into().unwrap());
         self.check_float(value)?;
+        if self.config.require_canonical_numbers && !is_canonical_float(value, FloatWidth::F64) {
+            return Err(non_canonical_float(value));
+        }
         Ok(value)
     }
 
@@ -606,29 +1603,58 @@ impl<'a> Decoder<'a> {
     fn check_float(&self, value: f64) -> Result<()> {
         if self.config.nan_infinity_mode == NanInfinityMode::Reject {
             if value.is_nan() {
-                return Err(Error::NanNotAllowed);
+                return Err(DecodeError::NanNotAllowed);
             }
             if value.is_infinite() {
-                return Err(Error::InfinityNotAllowed);
+                return Err(DecodeError::InfinityNotAllowed);
             }
         }
         Ok(())
     }
 
+    /// Check a string's bytes for valid UTF-8 and, unless `allow_nul`, for NUL.
+    #[inline]
+    fn validate_string(&self, bytes: &'a [u8]) -> Result<&'a str> {
+        if self.config.allow_nul {
+            return validate_utf8(bytes);
+        }
+        match self.config.string_validation {
+            StringValidation::Auto if bytes.len() <= COMBINED_VALIDATION_MAX_LEN => validate_utf8_no_nul(bytes),
+            StringValidation::Combined => validate_utf8_no_nul(bytes),
+            StringValidation::Auto | StringValidation::Separate => validate_utf8_then_nul(bytes),
+        }
+    }
+
+    /// Like [`validate_string`](Self::validate_string), but applies
+    /// `invalid_utf8_mode`: returns Cow::Borrowed for valid UTF-8, Cow::Owned
+    /// for replaced/deleted.
+    fn validate_string_cow(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>> {
+        match self.validate_string(bytes) {
+            Ok(s) => Ok(Cow::Borrowed(s)),
+            Err(DecodeError::InvalidUtf8) => {
+                let s = match self.config.invalid_utf8_mode {
+                    InvalidUtf8Mode::Reject => return Err(DecodeError::InvalidUtf8),
+                    InvalidUtf8Mode::Replace => String::from_utf8_lossy(bytes).into_owned(),
+                    InvalidUtf8Mode::Delete => delete_invalid_utf8(bytes),
+                };
+                if !self.config.allow_nul && memchr::memchr(0, bytes).is_some() {
+                    return Err(DecodeError::NulCharacter);
+                }
+                Ok(Cow::Owned(s))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     /// Decode string content (short string: after type code, known length).
     fn decode_string_content(&mut self, len: usize) -> Result<&'a str> {
         if len > self.config.max_string_length {
-            return Err(Error::MaxStringLengthExceeded);
+            return Err(DecodeError::MaxStringLengthExceeded);
         }
 
         let bytes = self.read_bytes(len)?;
-        let s = validate_utf8(bytes)?;
-
-        if !self.config.allow_nul && memchr::memchr(0, bytes).is_some() {
-            return Err(Error::NulCharacter);
-        }
-
-        Ok(s)
+        count_metric!(self, strings);
+        self.validate_string(bytes)
     }
 
     /// Decode long string content (FF-terminated: read until next 0xFF).
@@ -643,45 +1669,27 @@ impl<'a> Decoder<'a> {
 
             let len = offset;
             if len > self.config.max_string_length {
-                return Err(Error::MaxStringLengthExceeded);
+                return Err(DecodeError::MaxStringLengthExceeded);
             }
 
             let bytes = &self.data[start..end];
-            let s = validate_utf8(bytes)?;
-
-            if !self.config.allow_nul && memchr::memchr(0, bytes).is_some() {
-                return Err(Error::NulCharacter);
-            }
-
-            return Ok(s);
+            count_metric!(self, strings);
+            return self.validate_string(bytes);
         }
 
-        Err(Error::Truncated)
+        Err(DecodeError::Truncated)
     }
 
     /// Decode string content with invalid UTF-8 handling.
     /// Returns Cow::Borrowed for valid UTF-8, Cow::Owned for replaced/deleted.
     fn decode_string_content_cow(&mut self, len: usize) -> Result<Cow<'a, str>> {
         if len > self.config.max_string_length {
-            return Err(Error::MaxStringLengthExceeded);
+            return Err(DecodeError::MaxStringLengthExceeded);
         }
 
         let bytes = self.read_bytes(len)?;
-
-        let s = match validate_utf8(bytes) {
-            Ok(s) => Cow::Borrowed(s),
-            Err(_) => match self.config.invalid_utf8_mode {
-                InvalidUtf8Mode::Reject => return Err(Error::InvalidUtf8),
-                InvalidUtf8Mode::Replace => Cow::Owned(String::from_utf8_lossy(bytes).into_owned()),
-                InvalidUtf8Mode::Delete => Cow::Owned(delete_invalid_utf8(bytes)),
-            },
-        };
-
-        if !self.config.allow_nul && memchr::memchr(0, bytes).is_some() {
-            return Err(Error::NulCharacter);
-        }
-
-        Ok(s)
+        count_metric!(self, strings);
+        self.validate_string_cow(bytes)
     }
 
     /// Decode long string content with invalid UTF-8 handling.
@@ -695,37 +1703,45 @@ impl<'a> Decoder<'a> {
 
             let len = offset;
             if len > self.config.max_string_length {
-                return Err(Error::MaxStringLengthExceeded);
+                return Err(DecodeError::MaxStringLengthExceeded);
             }
 
             let bytes = &self.data[start..end];
-
-            let s = match validate_utf8(bytes) {
-                Ok(s) => Cow::Borrowed(s),
-                Err(_) => match self.config.invalid_utf8_mode {
-                    InvalidUtf8Mode::Reject => return Err(Error::InvalidUtf8),
-                    InvalidUtf8Mode::Replace => Cow::Owned(String::from_utf8_lossy(bytes).into_owned()),
-                    InvalidUtf8Mode::Delete => Cow::Owned(delete_invalid_utf8(bytes)),
-                },
-            };
-
-            if !self.config.allow_nul && memchr::memchr(0, bytes).is_some() {
-                return Err(Error::NulCharacter);
-            }
-
-            return Ok(s);
+            count_metric!(self, strings);
+            return self.validate_string_cow(bytes);
         }
 
-        Err(Error::Truncated)
+        Err(DecodeError::Truncated)
     }
 
     /// Decode a BigNumber (zigzag LEB128 exponent + zigzag LEB128 signed_length + LE magnitude).
     fn decode_big_number(&mut self) -> Result<DecodedValue<'a>> {
+        Ok(DecodedValue::BigNumber(self.read_big_number()?))
+    }
+
+    /// Read a BigNumber payload (after the type code), applying the configured limits.
+    fn read_big_number(&mut self) -> Result<BigNumber> {
+        let (sign, magnitude, exponent) = self.read_big_number_parts(self.config.max_bignumber_magnitude, 8)?;
+        #[allow(clippy::cast_possible_truncation)]
+        Ok(BigNumber::new(sign, magnitude as u64, exponent))
+    }
+
+    /// Read a BigNumber payload whose magnitude may use up to 16 bytes, for the
+    /// 128-bit targets. The target type bounds the work, so the configured
+    /// magnitude limit is raised to 16 bytes here.
+    fn read_wide_big_number(&mut self) -> Result<(i8, u128, i64)> {
+        self.read_big_number_parts(self.config.max_bignumber_magnitude.max(16), 16)
+    }
+
+    /// Read a BigNumber payload as (sign, magnitude, exponent). Magnitudes
+    /// longer than `limit` bytes fail unless stringifying; longer than
+    /// `max_bytes` always fail.
+    fn read_big_number_parts(&mut self, limit: usize, max_bytes: usize) -> Result<(i8, u128, i64)> {
         let remaining = &self.data[self.pos..];
 
         // Decode exponent
         let (exp_raw, exp_consumed) = leb128_decode(remaining)
-            .ok_or(Error::Truncated)?;
+            .ok_or(DecodeError::Truncated)?;
         self.pos += exp_consumed;
         let exponent = zigzag_decode(exp_raw);
 
@@ -733,35 +1749,37 @@ impl<'a> Decoder<'a> {
         if (exponent.unsigned_abs() as usize) > self.config.max_bignumber_exponent
             && self.config.out_of_range_mode != OutOfRangeMode::Stringify
         {
-            return Err(Error::MaxBignumberExponentExceeded);
+            return Err(DecodeError::MaxBignumberExponentExceeded);
         }
 
         // Decode signed_length
         let remaining = &self.data[self.pos..];
         let (slen_raw, slen_consumed) = leb128_decode(remaining)
-            .ok_or(Error::Truncated)?;
+            .ok_or(DecodeError::Truncated)?;
         self.pos += slen_consumed;
         let signed_length = zigzag_decode(slen_raw);
 
         if signed_length == 0 {
-            return Ok(DecodedValue::BigNumber(BigNumber::new(1, 0, exponent)));
+            return Ok((1, 0, exponent));
         }
 
         let sign: i8 = if signed_length < 0 { -1 } else { 1 };
         let byte_count = signed_length.unsigned_abs() as usize;
 
         // Check magnitude limit (also enforces u64 range since default max is 8)
-        if byte_count > self.config.max_bignumber_magnitude
+        if byte_count > limit
             && self.config.out_of_range_mode != OutOfRangeMode::Stringify
         {
-            return Err(Error::MaxBignumberMagnitudeExceeded);
+            return Err(DecodeError::MaxBignumberMagnitudeExceeded);
         }
 
-        // Hard safety cap for stringify mode, and always enforce u64 range
-        if byte_count > 8 {
-            return Err(Error::InvalidData(
-                "BigNumber magnitude exceeds u64 range".into(),
-            ));
+        // Hard safety cap for stringify mode, and always enforce the target range
+        if byte_count > max_bytes {
+            return Err(DecodeError::InvalidData(if max_bytes > 8 {
+                "BigNumber magnitude exceeds u128 range".into()
+            } else {
+                "BigNumber magnitude exceeds u64 range".into()
+            }));
         }
 
         // Read raw LE magnitude bytes
@@ -769,109 +1787,341 @@ impl<'a> Decoder<'a> {
 
         // Validate normalization: last byte (most significant) must be non-zero
         if magnitude_bytes[byte_count - 1] == 0 {
-            return Err(Error::InvalidData(
+            return Err(DecodeError::InvalidData(
                 "non-normalized BigNumber magnitude".into(),
             ));
         }
 
-        let mut buf = [0u8; 8];
+        let mut buf = [0u8; 16];
         buf[..byte_count].copy_from_slice(magnitude_bytes);
-        let significand = u64::from_le_bytes(buf);
 
-        Ok(DecodedValue::BigNumber(BigNumber::new(sign, significand, exponent)))
+        Ok((sign, u128::from_le_bytes(buf), exponent))
     }
 
     /// Read record definitions from the start of a document.
     /// Reads consecutive 0xB9 type codes; stops when a non-0xB9 byte is seen.
     pub fn read_record_definitions(&mut self) -> Result<()> {
         while self.pos < self.data.len() && self.data[self.pos] == type_code::RECORD_DEF {
+            if self.record_definitions.len() >= self.config.max_record_definitions {
+                return Err(DecodeError::MaxRecordDefinitionsExceeded);
+            }
             self.pos += 1; // consume 0xB9
             let mut keys = Vec::new();
             let mut seen_keys: std::collections::HashSet<String> = std::collections::HashSet::new();
             loop {
                 if self.pos >= self.data.len() {
-                    return Err(Error::Truncated);
+                    return Err(DecodeError::Truncated);
                 }
                 if self.data[self.pos] == type_code::CONTAINER_END {
                     self.pos += 1; // consume end marker
                     break;
                 }
                 if keys.len() >= self.config.max_container_size {
-                    return Err(Error::MaxContainerSizeExceeded);
+                    return Err(DecodeError::MaxContainerSizeExceeded);
+                }
+                if keys.len() >= self.config.max_record_keys {
+                    return Err(DecodeError::MaxRecordKeysExceeded);
                 }
                 // Read a string key
                 let tc = self.read_byte()?;
                 let key = if type_code::is_short_string(tc) {
-                    let len = type_code::short_string_len(tc);
-                    self.decode_string_content(len)?.to_string()
+                    self.decode_string_content(type_code::short_string_len(tc))?
                 } else if tc == type_code::STRING_LONG {
-                    self.decode_long_string_content()?.to_string()
+                    self.decode_long_string_content()?
                 } else {
-                    return Err(Error::InvalidData(
+                    return Err(DecodeError::InvalidData(
                         "record definition key must be a string".into(),
                     ));
                 };
+                if key.len() > self.config.max_record_key_length {
+                    return Err(DecodeError::MaxRecordKeyLengthExceeded);
+                }
+                let key = key.to_string();
                 // Check for duplicate keys within definition
                 if !seen_keys.insert(key.clone()) {
-                    return Err(Error::DuplicateKey);
+                    return Err(DecodeError::DuplicateKey);
                 }
+                self.charge_allocation(key.len())?;
                 keys.push(key);
             }
-            self.record_definitions.push(keys);
+            Arc::make_mut(&mut self.record_definitions).push(keys);
         }
         Ok(())
     }
 
-    /// Get the stored record definitions.
+    /// Get the stored record definitions: any given to
+    /// [`with_record_definitions`](Self::with_record_definitions), followed by
+    /// those read by [`read_record_definitions`](Self::read_record_definitions).
     #[must_use]
     pub fn record_definitions(&self) -> &[Vec<String>] {
         &self.record_definitions
     }
 
+    /// Start with `definitions` already stored, as if read from the document,
+    /// for documents whose record instances refer to a dictionary shared out
+    /// of band (per session or per connection). Definitions the document
+    /// carries itself are numbered after these. Pass an `Arc` to share one
+    /// dictionary between many decoders without copying it.
+    ///
+    /// The definitions are trusted: unlike those read from the input, they
+    /// aren't checked against the record limits or for duplicate keys, but
+    /// they do count toward `max_record_definitions`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_bonjson::decoder::{DecodedValue, Decoder};
+    ///
+    /// // An instance of definition #0, {"id": 1}, sent without the definition
+    /// let body = [0xba, 0x00, 0x01, 0xb6];
+    /// let mut decoder = Decoder::new(&body).with_record_definitions(vec![vec!["id".to_string()]]);
+    /// decoder.read_record_definitions().unwrap();
+    /// assert_eq!(decoder.decode_value().unwrap(), DecodedValue::RecordInstanceStart(0));
+    /// assert_eq!(decoder.record_definitions()[0], ["id"]);
+    /// ```
+    #[must_use]
+    pub fn with_record_definitions(mut self, definitions: impl Into<Arc<Vec<Vec<String>>>>) -> Self {
+        self.record_definitions = definitions.into();
+        self
+    }
+
+    /// Get a shared handle to the stored record definitions.
+    pub(crate) fn shared_record_definitions(&self) -> Arc<Vec<Vec<String>>> {
+        Arc::clone(&self.record_definitions)
+    }
+
     /// Read a single typed array element given the array's type code.
     pub fn read_typed_array_element(&mut self, element_type_code: u8) -> Result<DecodedValue<'a>> {
+        self.read_typed_element(element_type_code).map(DecodedValue::from)
+    }
+
+    /// Read the typed array at the current position in one step, returning its
+    /// payload as a slice of the input instead of decoding element by element.
+    ///
+    /// On little-endian targets the payload can be reinterpreted in place,
+    /// e.g. with `bytemuck::try_cast_slice::<u8, f32>` (which fails if the
+    /// slice happens to be misaligned). Float payloads are checked against
+    /// `nan_infinity_mode` as usual.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the next value is not a typed array, the payload is
+    /// truncated, the count exceeds `max_container_size`, or a float element
+    /// is NaN or infinite under `NanInfinityMode::Reject`.
+    pub fn read_typed_array_slice(&mut self) -> Result<TypedArraySlice<'a>> {
+        let tc = self.peek_type_code()?;
+        if !type_code::is_typed_array(tc) {
+            return Err(DecodeError::UnexpectedType { expected: "typed array", found: tc });
+        }
+        if self.containers.len() >= self.config.max_depth {
+            return Err(DecodeError::MaxDepthExceeded);
+        }
+        let (_, count) = self.read_typed_array_header()?;
+        // The header check guarantees this doesn't overflow
+        let len = count * type_code::typed_array_element_size(tc);
+        if len > self.remaining().len() {
+            return Err(DecodeError::Truncated);
+        }
+        let bytes = self.read_bytes(len)?;
+        if self.config.nan_infinity_mode == NanInfinityMode::Reject {
+            match tc {
+                type_code::TYPED_ARRAY_FLOAT32 => bytes
+                    .chunks_exact(4)
+                    .try_for_each(|b| self.check_float(f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]]))))?,
+                type_code::TYPED_ARRAY_FLOAT64 => bytes
+                    .chunks_exact(8)
+                    .try_for_each(|b| self.check_float(f64::from_le_bytes(b.try_into().unwrap())))?,
+                _ => {}
+            }
+        }
+        Ok(TypedArraySlice { element_type_code: tc, count, bytes })
+    }
+
+    /// Read a blob (a uint8 typed array, see [`blob`](crate::blob)), borrowing
+    /// its bytes from the input.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the next value isn't a uint8 typed array, or is truncated or
+    /// over the configured limits.
+    pub fn read_blob(&mut self) -> Result<&'a [u8]> {
+        let tc = self.peek_type_code()?;
+        if tc != type_code::TYPED_ARRAY_UINT8 {
+            return Err(DecodeError::UnexpectedType { expected: "blob (uint8 typed array)", found: tc });
+        }
+        Ok(self.read_typed_array_slice()?.bytes)
+    }
+
+    /// Read the typed array at the current position into `out`, returning the
+    /// number of elements written.
+    ///
+    /// The array's element type must be exactly `T`. Elements are converted
+    /// from little-endian in a loop over fixed-size chunks, which compiles to a
+    /// plain copy on little-endian targets and a byte swap on big-endian ones,
+    /// so the same code is correct on both.
+    ///
+    /// # Errors
+    ///
+    /// Fails as [`read_typed_array_slice`](Self::read_typed_array_slice)
+    /// does, or if the array's element type isn't `T` or it has more elements
+    /// than `out` holds. In those two cases nothing is consumed, so the array
+    /// can be read again another way.
+    pub fn read_typed_array_into<T: TypedArrayElement>(&mut self, out: &mut [T]) -> Result<usize> {
+        let start = self.pos;
+        let slice = self.read_typed_array_slice()?;
+        if slice.element_type_code != T::TYPE_CODE {
+            self.pos = start;
+            return Err(DecodeError::TypedArrayTypeMismatch { expected: T::TYPE_CODE, found: slice.element_type_code });
+        }
+        if slice.count > out.len() {
+            self.pos = start;
+            return Err(DecodeError::TypedArrayTooLarge { count: slice.count, capacity: out.len() });
+        }
+        // The element type matched, so T's size is the element size
+        for (element, bytes) in out.iter_mut().zip(slice.bytes.chunks_exact(std::mem::size_of::<T>())) {
+            *element = T::from_le_slice(bytes);
+        }
+        Ok(slice.count)
+    }
+
+    /// Read one element of a typed array at its encoded width.
+    pub(crate) fn read_typed_element(&mut self, element_type_code: u8) -> Result<TypedElement> {
         let size = type_code::typed_array_element_size(element_type_code);
         let bytes = self.read_bytes(size)?;
 
         if type_code::typed_array_is_float(element_type_code) {
             if element_type_code == type_code::TYPED_ARRAY_FLOAT32 {
-                let f = f64::from(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
-                self.check_float(f)?;
-                Ok(DecodedValue::Float(f))
+                let f = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                self.check_float(f64::from(f))?;
+                Ok(TypedElement::F32(f))
             } else {
                 let f = f64::from_le_bytes(bytes.try_into().unwrap());
                 self.check_float(f)?;
-                Ok(DecodedValue::Float(f))
+                Ok(TypedElement::F64(f))
             }
         } else if type_code::typed_array_is_signed_int(element_type_code) {
             let fill = ((bytes[size - 1] as i8) >> 7) as u8;
             let mut buf = [fill; 8];
             buf[..size].copy_from_slice(bytes);
-            Ok(DecodedValue::Int(i64::from_le_bytes(buf)))
+            Ok(TypedElement::Int(i64::from_le_bytes(buf)))
         } else {
             // Unsigned int
             let mut buf = [0u8; 8];
             buf[..size].copy_from_slice(bytes);
-            Ok(DecodedValue::UInt(u64::from_le_bytes(buf)))
+            Ok(TypedElement::UInt(u64::from_le_bytes(buf)))
         }
     }
 
+    /// Read a typed array's type code and element count, without opening a
+    /// container for it as [`decode_value`](Self::decode_value) does.
+    pub(crate) fn read_typed_array_header(&mut self) -> Result<(u8, usize)> {
+        let tc = self.read_byte()?;
+        let count = self.read_typed_array_count(tc)?;
+        count_metric!(self, values);
+        count_metric!(self, typed_arrays);
+        Ok((tc, count))
+    }
+
+    /// Read a typed array's element count, checking it against
+    /// `max_container_size` and its payload size against `max_typed_array_bytes`.
+    fn read_typed_array_count(&mut self, element_type_code: u8) -> Result<usize> {
+        let (count, consumed) = leb128_decode(&self.data[self.pos..]).ok_or(DecodeError::Truncated)?;
+        self.pos += consumed;
+        let count = usize::try_from(count).map_err(|_| DecodeError::MaxContainerSizeExceeded)?;
+        if count > self.config.max_container_size {
+            return Err(DecodeError::MaxContainerSizeExceeded);
+        }
+        match count.checked_mul(type_code::typed_array_element_size(element_type_code)) {
+            Some(len) if len <= self.config.typed_array_bytes_limit() => Ok(count),
+            _ => Err(DecodeError::MaxTypedArrayBytesExceeded),
+        }
+    }
+
+    /// How many elements of a typed array to preallocate for: its count, but
+    /// no more than the rest of the input could hold, so a forged count
+    /// can't reserve memory the document doesn't back.
+    pub(crate) fn typed_array_capacity(&self, element_type_code: u8, count: usize) -> usize {
+        count.min(self.remaining().len() / type_code::typed_array_element_size(element_type_code))
+    }
+
     /// Pop the container for a typed array (called after reading all elements).
     pub fn end_typed_array(&mut self) -> Result<()> {
-        self.containers.pop().ok_or(Error::UnbalancedContainers)?;
+        self.containers.pop().ok_or(DecodeError::UnbalancedContainers)?;
+        if let Some(metadata) = &mut self.metadata {
+            metadata.typed_array_end(self.pos);
+        }
         Ok(())
     }
 
     /// Decode the next value from the input.
     pub fn decode_value(&mut self) -> Result<DecodedValue<'a>> {
+        let start = self.pos;
         let tc = self.read_byte()?;
-        self.decode_value_with_type_code(tc)
+        let value = self.decode_value_with_type_code(tc)?;
+        if let Some(metadata) = &mut self.metadata {
+            metadata.value(&value, start, self.pos);
+        }
+        Ok(value)
+    }
+
+    /// Decode the next value from the input as a [`DecodedValueOwned`].
+    ///
+    /// Equivalent to `decode_value()?.into_owned()`.
+    pub fn decode_value_owned(&mut self) -> Result<DecodedValueOwned> {
+        Ok(self.decode_value()?.into_owned())
+    }
+
+    /// Whether a member of the object whose entries span `start..end` of the
+    /// input has a key for which `matches` holds. Re-reads the entries with a
+    /// separate decoder, leaving this one where it is.
+    pub(crate) fn object_has_key(&self, start: usize, end: usize, matches: impl Fn(&str) -> bool) -> Result<bool> {
+        let mut scan = Decoder::resume(self.data, self.config.clone(), Arc::clone(&self.record_definitions), start);
+        while scan.pos < end {
+            let DecodedValue::String(key) = scan.decode_value()? else {
+                return Err(DecodeError::ExpectedObjectKey);
+            };
+            if scan.skip_unknown_value()? {
+                continue;
+            }
+            if matches(&key) {
+                return Ok(true);
+            }
+            scan.skip_value()?;
+        }
+        Ok(false)
+    }
+
+    /// Skip over the next value, including everything inside it if it's a container.
+    pub(crate) fn skip_value(&mut self) -> Result<()> {
+        let mut depth: usize = 0;
+        loop {
+            match self.decode_value()? {
+                DecodedValue::ArrayStart
+                | DecodedValue::ObjectStart
+                | DecodedValue::RecordInstanceStart(_) => depth += 1,
+                // An end marker where a value should start
+                DecodedValue::ContainerEnd if depth == 0 => {
+                    return Err(DecodeError::UnexpectedType { expected: "value", found: type_code::CONTAINER_END });
+                }
+                DecodedValue::ContainerEnd => depth -= 1,
+                DecodedValue::TypedArrayStart { element_type_code, count } => {
+                    for _ in 0..count {
+                        self.read_typed_array_element(element_type_code)?;
+                    }
+                    self.end_typed_array()?;
+                }
+                _ => {}
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
     }
 
     /// Check if we're at the end of the current container (next byte is 0xFE).
     pub fn is_at_container_end(&self) -> Result<bool> {
         if self.pos >= self.data.len() {
-            return Err(Error::Truncated);
+            return Err(DecodeError::Truncated);
         }
         Ok(self.data[self.pos] == type_code::CONTAINER_END)
     }
@@ -880,24 +2130,177 @@ impl<'a> Decoder<'a> {
     pub fn end_container(&mut self) -> Result<()> {
         let tc = self.read_byte()?;
         if tc != type_code::CONTAINER_END {
-            return Err(Error::Custom(format!("expected container end, got 0x{tc:02x}")));
+            return Err(DecodeError::UnexpectedType { expected: "container end", found: tc });
+        }
+        self.containers.pop().ok_or(DecodeError::UnbalancedContainers)?;
+        if let Some(metadata) = &mut self.metadata {
+            metadata.container_end();
         }
-        self.containers.pop().ok_or(Error::UnbalancedContainers)?;
         Ok(())
     }
 
-    /// Finish decoding and check for errors.
-    pub fn finish(&self) -> Result<()> {
+    /// Start tallying [`DocumentMetadata`](crate::DocumentMetadata) for the
+    /// values decoded from here on.
+    pub(crate) fn collect_metadata(&mut self) {
+        self.metadata = Some(Box::default());
+    }
+
+    /// Stop tallying metadata, returning what was collected.
+    pub(crate) fn take_metadata(&mut self) -> Option<Collector> {
+        self.metadata.take().map(|metadata| *metadata)
+    }
+
+    /// Check that every container opened so far has been closed, without
+    /// checking for trailing bytes.
+    pub(crate) fn finish_value(&self) -> Result<()> {
         if !self.containers.is_empty() {
-            return Err(Error::UnclosedContainer);
+            return Err(DecodeError::UnclosedContainer);
         }
+        Ok(())
+    }
+
+    /// Finish decoding and check for errors.
+    pub fn finish(&self) -> Result<()> {
+        self.finish_value()?;
         if !self.config.allow_trailing_bytes && self.pos < self.data.len() {
-            return Err(Error::TrailingBytes);
+            return Err(DecodeError::TrailingBytes(self.pos));
         }
         Ok(())
     }
 }
 
+/// A [`Decoder`] that owns its input buffer.
+///
+/// A `Decoder<'a>` borrows its input, so holding one across an `.await`
+/// means keeping the buffer alive alongside it. An `OwnedDecoder` holds the
+/// buffer itself and has no lifetime: it is `Send`, and can be stored in a
+/// task or moved between threads mid-document. Its event methods return
+/// [`DecodedValueOwned`], since a value can't borrow from the decoder that
+/// produced it while the decoder is still being advanced.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::decoder::{Decoder, DecodedValueOwned};
+///
+/// let bytes = serde_bonjson::to_vec(&vec!["a", "b"]).unwrap();
+/// let mut decoder = Decoder::from_vec(bytes);
+/// let handle = std::thread::spawn(move || {
+///     assert_eq!(decoder.decode_value().unwrap(), DecodedValueOwned::ArrayStart);
+///     decoder
+/// });
+/// let mut decoder = handle.join().unwrap();
+/// assert_eq!(decoder.decode_value().unwrap(), DecodedValueOwned::String("a".into()));
+/// ```
+pub struct OwnedDecoder {
+    data: Vec<u8>,
+    /// The decoder state, bound to an empty slice between calls.
+    /// Only `None` while a call is in progress.
+    parked: Option<Decoder<'static>>,
+}
+
+impl OwnedDecoder {
+    /// Create a decoder that owns `data`, with the default configuration.
+    #[must_use]
+    pub fn new(data: Vec<u8>) -> Self {
+        Self::with_config(data, DecoderConfig::default())
+    }
+
+    /// Create a decoder that owns `data`, with a custom configuration.
+    #[must_use]
+    pub fn with_config(data: Vec<u8>, config: DecoderConfig) -> Self {
+        Self { data, parked: Some(Decoder::with_config(&[], config)) }
+    }
+
+    /// Run `f` on a decoder over the owned input, parking its state again afterwards.
+    fn with_decoder<T>(&mut self, f: impl FnOnce(&mut Decoder<'_>) -> T) -> T {
+        let mut decoder = self.parked.take().expect("decoder state is parked between calls").rebind(&self.data);
+        let result = f(&mut decoder);
+        self.parked = Some(decoder.rebind(&[]));
+        result
+    }
+
+    fn parked(&self) -> &Decoder<'static> {
+        self.parked.as_ref().expect("decoder state is parked between calls")
+    }
+
+    /// See [`Decoder::check_document_size`].
+    pub fn check_document_size(&mut self) -> Result<()> {
+        self.with_decoder(|decoder| decoder.check_document_size())
+    }
+
+    /// See [`Decoder::read_record_definitions`].
+    pub fn read_record_definitions(&mut self) -> Result<()> {
+        self.with_decoder(|decoder| decoder.read_record_definitions())
+    }
+
+    /// See [`Decoder::record_definitions`].
+    #[must_use]
+    pub fn record_definitions(&self) -> &[Vec<String>] {
+        self.parked().record_definitions()
+    }
+
+    /// Decode the next value. See [`Decoder::decode_value`].
+    pub fn decode_value(&mut self) -> Result<DecodedValueOwned> {
+        self.with_decoder(|decoder| decoder.decode_value_owned())
+    }
+
+    /// See [`Decoder::read_typed_array_element`].
+    pub fn read_typed_array_element(&mut self, element_type_code: u8) -> Result<DecodedValueOwned> {
+        self.with_decoder(|decoder| Ok(decoder.read_typed_array_element(element_type_code)?.into_owned()))
+    }
+
+    /// See [`Decoder::end_typed_array`].
+    pub fn end_typed_array(&mut self) -> Result<()> {
+        self.with_decoder(|decoder| decoder.end_typed_array())
+    }
+
+    /// See [`Decoder::is_at_container_end`].
+    pub fn is_at_container_end(&mut self) -> Result<bool> {
+        self.with_decoder(|decoder| decoder.is_at_container_end())
+    }
+
+    /// See [`Decoder::end_container`].
+    pub fn end_container(&mut self) -> Result<()> {
+        self.with_decoder(|decoder| decoder.end_container())
+    }
+
+    /// See [`Decoder::finish`].
+    pub fn finish(&mut self) -> Result<()> {
+        self.with_decoder(|decoder| decoder.finish())
+    }
+
+    /// Get the current position in the input.
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.parked().position()
+    }
+
+    /// The unconsumed input.
+    #[must_use]
+    pub fn remaining(&self) -> &[u8] {
+        &self.data[self.position().min(self.data.len())..]
+    }
+
+    /// Get the decoder configuration.
+    #[must_use]
+    pub fn config(&self) -> &DecoderConfig {
+        self.parked().config()
+    }
+
+    /// The whole input.
+    #[must_use]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consume the decoder, returning its input buffer.
+    #[must_use]
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1020,22 +2423,379 @@ mod tests {
     #[test]
     fn test_reserved_type_codes() {
         let mut dec = Decoder::new(&[0xbb]);
-        assert!(matches!(dec.decode_value(), Err(Error::InvalidTypeCode(0xbb))));
+        assert!(matches!(dec.decode_value(), Err(DecodeError::InvalidTypeCode(0xbb))));
 
         let mut dec = Decoder::new(&[0xf4]);
-        assert!(matches!(dec.decode_value(), Err(Error::InvalidTypeCode(0xf4))));
+        assert!(matches!(dec.decode_value(), Err(DecodeError::InvalidTypeCode(0xf4))));
     }
 
     #[test]
     fn test_truncated() {
         let mut dec = Decoder::new(&[0xad, 0xe8]); // Missing second byte of sint16
-        assert!(matches!(dec.decode_value(), Err(Error::Truncated)));
+        assert!(matches!(dec.decode_value(), Err(DecodeError::Truncated)));
     }
 
     #[test]
     fn test_trailing_bytes() {
         let mut dec = Decoder::new(&[0x00, 0x00]); // int 0 + extra byte
         dec.decode_value().unwrap();
-        assert!(matches!(dec.finish(), Err(Error::TrailingBytes)));
+        assert_eq!(dec.finish(), Err(DecodeError::TrailingBytes(1)));
+    }
+
+    #[test]
+    fn test_decode_value_owned() {
+        // {"a": 1} → B8 66 61 01 B6
+        let data = vec![0xb8, 0x66, 0x61, 0x01, 0xb6];
+        let mut events = Vec::new();
+        {
+            let mut dec = Decoder::new(&data);
+            for _ in 0..4 {
+                events.push(dec.decode_value_owned().unwrap());
+            }
+            dec.finish().unwrap();
+        }
+        drop(data);
+
+        // Owned events outlive the buffer and can cross threads
+        let events = std::thread::spawn(move || events).join().unwrap();
+        assert_eq!(
+            events,
+            vec![
+                DecodedValueOwned::ObjectStart,
+                DecodedValueOwned::String("a".to_string()),
+                DecodedValueOwned::Int(1),
+                DecodedValueOwned::ContainerEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decoded_value_into_owned() {
+        let value = DecodedValue::String(Cow::Borrowed("x"));
+        assert_eq!(value.into_owned(), DecodedValueOwned::String("x".to_string()));
+
+        let value = DecodedValue::TypedArrayStart { element_type_code: 0xfe, count: 3 };
+        assert_eq!(
+            DecodedValueOwned::from(value),
+            DecodedValueOwned::TypedArrayStart { element_type_code: 0xfe, count: 3 }
+        );
+    }
+
+    #[test]
+    fn test_decoded_value_display() {
+        let events = [
+            (DecodedValue::Null, "null"),
+            (DecodedValue::Bool(true), "true"),
+            (DecodedValue::Int(-5), "int -5"),
+            (DecodedValue::UInt(u64::MAX), "uint 18446744073709551615"),
+            (DecodedValue::Float(f64::NAN), "float NaN"),
+            (DecodedValue::BigNumber(BigNumber::new(-1, 15, -1)), "big_number -15e-1"),
+            (DecodedValue::String(Cow::Borrowed("a\"b")), "string \"a\\\"b\""),
+            (DecodedValue::ObjectStart, "object_start"),
+            (DecodedValue::RecordInstanceStart(2), "record_instance_start #2"),
+            (
+                DecodedValue::TypedArrayStart { element_type_code: type_code::TYPED_ARRAY_FLOAT64, count: 3 },
+                "typed_array_start float64 x3",
+            ),
+            (DecodedValue::ContainerEnd, "container_end"),
+        ];
+        for (event, text) in events {
+            assert_eq!(event.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_read_typed_array_slice() {
+        let mut data = vec![0x01, type_code::TYPED_ARRAY_FLOAT32, 3];
+        for f in [1.5f32, -2.0, 0.25] {
+            data.extend_from_slice(&f.to_le_bytes());
+        }
+        data.push(type_code::CONTAINER_END);
+        let mut all = vec![type_code::ARRAY];
+        all.extend_from_slice(&data);
+
+        let mut decoder = Decoder::new(&all);
+        assert_eq!(decoder.decode_value().unwrap(), DecodedValue::ArrayStart);
+        assert!(decoder.read_typed_array_slice().is_err());
+        assert_eq!(decoder.decode_value().unwrap(), DecodedValue::Int(1));
+        let slice = decoder.read_typed_array_slice().unwrap();
+        assert_eq!((slice.element_type_code, slice.count, slice.element_size()), (type_code::TYPED_ARRAY_FLOAT32, 3, 4));
+        assert_eq!(slice.bytes, &all[4..16]);
+        let floats: Vec<f32> = slice.bytes.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+        assert_eq!(floats, [1.5, -2.0, 0.25]);
+        decoder.end_container().unwrap();
+        decoder.finish().unwrap();
+
+        // Truncated payload, and NaN under the default NanInfinityMode::Reject
+        let truncated = &all[2..15];
+        assert_eq!(Decoder::new(truncated).read_typed_array_slice(), Err(DecodeError::Truncated));
+        let mut nan = vec![type_code::TYPED_ARRAY_FLOAT64, 1];
+        nan.extend_from_slice(&f64::NAN.to_le_bytes());
+        assert_eq!(Decoder::new(&nan).read_typed_array_slice(), Err(DecodeError::NanNotAllowed));
+        let config = DecoderConfig { nan_infinity_mode: NanInfinityMode::Allow, ..DecoderConfig::default() };
+        assert_eq!(Decoder::with_config(&nan, config).read_typed_array_slice().unwrap().count, 1);
+        let config = DecoderConfig { max_container_size: 2, ..DecoderConfig::default() };
+        assert_eq!(Decoder::with_config(&all[2..], config).read_typed_array_slice(), Err(DecodeError::MaxContainerSizeExceeded));
+    }
+
+    #[test]
+    fn test_read_typed_array_into() {
+        let mut data = vec![type_code::TYPED_ARRAY_SINT16, 3];
+        for n in [-2i16, 300, i16::MAX] {
+            data.extend_from_slice(&n.to_le_bytes());
+        }
+        data.extend_from_slice(&[type_code::TYPED_ARRAY_FLOAT64, 1]);
+        data.extend_from_slice(&0.5f64.to_le_bytes());
+
+        let mut decoder = Decoder::new(&data);
+        let mut small = [0i16; 2];
+        assert!(decoder.read_typed_array_into(&mut small).is_err());
+        let mut wrong_type = [0u16; 8];
+        let err = decoder.read_typed_array_into(&mut wrong_type).unwrap_err();
+        assert_eq!(err, DecodeError::TypedArrayTypeMismatch { expected: type_code::TYPED_ARRAY_UINT16, found: type_code::TYPED_ARRAY_SINT16 });
+        assert_eq!(err.to_string(), "expected typed array of uint16, got sint16");
+
+        // Nothing was consumed by the failed attempts
+        let mut out = [0i16; 4];
+        assert_eq!(decoder.read_typed_array_into(&mut out).unwrap(), 3);
+        assert_eq!(out, [-2, 300, i16::MAX, 0]);
+        let mut floats = [0f64; 1];
+        assert_eq!(decoder.read_typed_array_into(&mut floats).unwrap(), 1);
+        assert_eq!(floats, [0.5]);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn test_config_presets() {
+        use crate::{bonjson, decode_value_with_config, encode_value};
+
+        let nested = (0..20).fold(bonjson!(1), |v, _| crate::Value::Array(vec![v]));
+        let nested = encode_value(&nested).unwrap();
+        assert!(decode_value_with_config(&nested, DecoderConfig::default()).is_ok());
+        assert!(decode_value_with_config(&nested, DecoderConfig::strict()).is_ok());
+        assert_eq!(decode_value_with_config(&nested, DecoderConfig::embedded()), Err(crate::Error::MaxDepthExceeded));
+
+        let long = encode_value(&crate::Value::String("x".repeat(5000))).unwrap();
+        assert_eq!(decode_value_with_config(&long, DecoderConfig::embedded()), Err(crate::Error::MaxStringLengthExceeded));
+
+        // Duplicate key, a NaN, and a trailing byte
+        let mut doc = vec![type_code::OBJECT, 0x66, b'a', 0x01, 0x66, b'a', 0x02, 0x66, b'n', type_code::FLOAT32];
+        doc.extend_from_slice(&f32::NAN.to_le_bytes());
+        doc.extend_from_slice(&[type_code::CONTAINER_END, 0x00]);
+        assert!(decode_value_with_config(&doc, DecoderConfig::strict()).is_err());
+        let value = decode_value_with_config(&doc, DecoderConfig::permissive()).unwrap();
+        assert_eq!(value.get_key("a"), Some(&crate::Value::Int(2)));
+        assert!(value.get_key("n").and_then(crate::Value::as_f64).is_some_and(f64::is_nan));
+
+        #[derive(serde::Deserialize)]
+        struct Point {
+            #[allow(dead_code)]
+            x: i32,
+        }
+        let extra = encode_value(&bonjson!({"x": 1, "y": 2})).unwrap();
+        assert!(crate::from_slice_with_config::<Point>(&extra, DecoderConfig::default()).is_ok());
+        assert!(crate::from_slice_with_config::<Point>(&extra, DecoderConfig::strict()).is_err());
+
+        // Duplicate keys also fail under serde, where a HashMap would keep the last
+        let dup = [type_code::OBJECT, 0x66, b'a', 0x01, 0x66, b'a', 0x02, type_code::CONTAINER_END];
+        type Map = std::collections::HashMap<String, i32>;
+        assert!(crate::from_slice_with_config::<Map>(&dup, DecoderConfig::default()).is_ok());
+        assert!(matches!(crate::from_slice_with_config::<Map>(&dup, DecoderConfig::strict()), Err(crate::Error::DuplicateKey)));
+
+        // Non-canonical numbers: 1 as an int8, 2.0 as a float64
+        let padded = [type_code::ARRAY, type_code::SINT8, 0x01, type_code::CONTAINER_END];
+        assert!(crate::from_slice_with_config::<Vec<i32>>(&padded, DecoderConfig::default()).is_ok());
+        assert!(crate::from_slice_with_config::<Vec<i32>>(&padded, DecoderConfig::strict()).is_err());
+        let mut float = vec![type_code::FLOAT64];
+        float.extend_from_slice(&2.0f64.to_le_bytes());
+        assert!(decode_value_with_config(&float, DecoderConfig::default()).is_ok());
+        assert!(decode_value_with_config(&float, DecoderConfig::strict()).is_err());
+    }
+
+    #[test]
+    fn test_record_definition_limits() {
+        // Two definitions: ["a", "bb"] and ["ccc"], then null
+        let doc = [
+            type_code::RECORD_DEF, 0x66, b'a', 0x67, b'b', b'b', type_code::CONTAINER_END,
+            type_code::RECORD_DEF, 0x68, b'c', b'c', b'c', type_code::CONTAINER_END,
+            type_code::NULL,
+        ];
+        let decode = |config: DecoderConfig| {
+            let mut decoder = Decoder::with_config(&doc, config);
+            decoder.read_record_definitions()
+        };
+        assert!(decode(DecoderConfig::default()).is_ok());
+        assert!(decode(DecoderConfig { max_record_definitions: 2, max_record_keys: 2, max_record_key_length: 3, ..Default::default() }).is_ok());
+        assert_eq!(
+            decode(DecoderConfig { max_record_definitions: 1, ..Default::default() }),
+            Err(DecodeError::MaxRecordDefinitionsExceeded)
+        );
+        assert_eq!(decode(DecoderConfig { max_record_keys: 1, ..Default::default() }), Err(DecodeError::MaxRecordKeysExceeded));
+        assert_eq!(
+            decode(DecoderConfig { max_record_key_length: 2, ..Default::default() }),
+            Err(DecodeError::MaxRecordKeyLengthExceeded)
+        );
+
+        // Long string keys are held to the same length limit
+        let long = [
+            type_code::RECORD_DEF, type_code::STRING_LONG, b'd', b'd', b'd', b'd', type_code::STRING_LONG,
+            type_code::CONTAINER_END, type_code::NULL,
+        ];
+        let mut decoder = Decoder::with_config(&long, DecoderConfig { max_record_key_length: 3, ..Default::default() });
+        assert_eq!(decoder.read_record_definitions(), Err(DecodeError::MaxRecordKeyLengthExceeded));
+    }
+
+    #[test]
+    fn test_require_canonical_ints() {
+        use crate::{Encoder, EncoderConfig, EncodingProfile, IntSignedness};
+
+        let values = [0, 5, 100, 101, 200, -1, -200, 40_000, i64::MIN, i64::MAX];
+        let encode = |profile: EncodingProfile| {
+            let mut enc = Encoder::with_config(Vec::new(), EncoderConfig::default().profile(profile));
+            enc.begin_array().unwrap();
+            for value in values {
+                enc.write_i64(value).unwrap();
+            }
+            enc.end_container().unwrap();
+            enc.finish().unwrap()
+        };
+        let canonical_only = DecoderConfig { require_canonical_ints: true, ..Default::default() };
+        let decode = |bytes: &[u8], config: DecoderConfig| -> crate::Result<Vec<i64>> { crate::from_slice_with_config(bytes, config) };
+
+        let canonical = encode(EncodingProfile::CANONICAL);
+        assert_eq!(decode(&canonical, canonical_only.clone()).unwrap(), values);
+        for profile in [EncodingProfile::fixed(4, IntSignedness::Signed), EncodingProfile { min_int_bytes: 8, ..Default::default() }] {
+            let wide = encode(profile);
+            assert_eq!(decode(&wide, DecoderConfig::default()).unwrap(), values);
+            assert!(matches!(decode(&wide, canonical_only.clone()), Err(crate::Error::InvalidData(_))));
+        }
+
+        // Either signedness is minimal at the same width
+        for doc in [[type_code::UINT8, 101], [type_code::SINT8, 101]] {
+            let mut decoder = Decoder::with_config(&doc, canonical_only.clone());
+            assert!(decoder.decode_value().is_ok());
+        }
+        for doc in [&[type_code::UINT8, 100][..], &[type_code::SINT16, 0x7f, 0], &[type_code::UINT16, 0xff, 0]] {
+            let mut decoder = Decoder::with_config(doc, canonical_only.clone());
+            assert!(decoder.decode_value().is_err(), "{doc:02x?}");
+        }
+    }
+
+    #[test]
+    fn test_require_canonical_numbers() {
+        let strict = DecoderConfig::default().require_canonical_numbers(true).nan_infinity_mode(NanInfinityMode::Allow);
+        let float32 = |f: f32| [&[type_code::FLOAT32][..], &f.to_le_bytes()].concat();
+        let float64 = |f: f64| [&[type_code::FLOAT64][..], &f.to_le_bytes()].concat();
+        let decode = |doc: &[u8]| Decoder::with_config(doc, strict.clone()).decode_value().map(|_| ());
+
+        for doc in [float32(0.5), float32(-0.0), float32(f32::NAN), float64(0.1), float64(1e300), float64(f64::MIN_POSITIVE)] {
+            assert_eq!(decode(&doc), Ok(()), "{doc:02x?}");
+        }
+        // Whole numbers are integers, and float32 holds these exactly
+        for doc in [float32(5.0), float32(0.0), float64(5.0), float64(0.5), float64(-0.0), float64(f64::NAN), float64(f64::INFINITY)] {
+            assert!(matches!(decode(&doc), Err(DecodeError::InvalidData(_))), "{doc:02x?}");
+        }
+        // Integers are checked as under require_canonical_ints
+        assert!(decode(&[type_code::SINT16, 5, 0]).is_err());
+        assert!(Decoder::new(&float64(0.5)).decode_value().is_ok());
+
+        let value = crate::bonjson!([0.5, 0.1, (-0.0), 1e300, 7, (-40000), 3.25]);
+        let bytes = crate::encode_value(&value).unwrap();
+        assert_eq!(crate::decode_value_with_config(&bytes, strict.clone()).unwrap(), value);
+    }
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn test_skip_value_rejects_container_end() {
+        // An object whose first member's value is an end marker
+        let mut dec = Decoder::new(&[0xb8, 0x66, b'a', 0xb6, 0x66, b'b', 0x01, 0xb6]);
+        assert_eq!(dec.decode_value().unwrap(), DecodedValue::ObjectStart);
+        assert_eq!(dec.decode_value().unwrap(), DecodedValue::String(Cow::Borrowed("a")));
+        assert_eq!(
+            dec.skip_value(),
+            Err(DecodeError::UnexpectedType { expected: "value", found: type_code::CONTAINER_END })
+        );
+    }
+
+    #[test]
+    fn test_decoders_are_send() {
+        assert_send::<Decoder<'static>>();
+        assert_send::<OwnedDecoder>();
+        assert_send::<DecodedValueOwned>();
+        assert_send::<crate::lazy::LazyValue<'static>>();
+        assert_send::<crate::reader::ReaderDeserializer<std::fs::File>>();
+        assert_send::<crate::reader::ReaderStreamDeserializer<std::fs::File, crate::Value>>();
+    }
+
+    #[test]
+    fn test_owned_decoder() {
+        let bytes = crate::to_vec(&(1, "two", vec![3.5f32, 4.5])).unwrap();
+        let mut decoder = Decoder::from_vec(bytes.clone());
+        decoder.check_document_size().unwrap();
+        decoder.read_record_definitions().unwrap();
+        assert_eq!(decoder.decode_value().unwrap(), DecodedValueOwned::ArrayStart);
+
+        // The state survives a move to another thread mid-document
+        let mut decoder = std::thread::spawn(move || {
+            assert_eq!(decoder.decode_value().unwrap(), DecodedValueOwned::Int(1));
+            decoder
+        })
+        .join()
+        .unwrap();
+        assert_eq!(decoder.decode_value().unwrap(), DecodedValueOwned::String("two".into()));
+        let DecodedValueOwned::TypedArrayStart { element_type_code, count } = decoder.decode_value().unwrap() else {
+            panic!("expected a typed array");
+        };
+        assert_eq!(count, 2);
+        assert_eq!(decoder.read_typed_array_element(element_type_code).unwrap(), DecodedValueOwned::Float(3.5));
+        assert_eq!(decoder.read_typed_array_element(element_type_code).unwrap(), DecodedValueOwned::Float(4.5));
+        decoder.end_typed_array().unwrap();
+        assert!(decoder.is_at_container_end().unwrap());
+        decoder.end_container().unwrap();
+        decoder.finish().unwrap();
+        assert_eq!(decoder.position(), bytes.len());
+        assert!(decoder.remaining().is_empty());
+        assert_eq!(decoder.into_inner(), bytes);
+    }
+
+    #[test]
+    fn test_owned_decoder_errors_keep_state() {
+        let config = DecoderConfig { max_depth: 1, ..Default::default() };
+        let mut decoder = OwnedDecoder::with_config(vec![type_code::ARRAY, type_code::ARRAY], config);
+        assert_eq!(decoder.decode_value().unwrap(), DecodedValueOwned::ArrayStart);
+        assert!(matches!(decoder.decode_value(), Err(DecodeError::MaxDepthExceeded)));
+        assert_eq!(decoder.config().max_depth, 1);
+        assert!(matches!(decoder.finish(), Err(DecodeError::UnclosedContainer)));
+    }
+
+    #[test]
+    fn test_with_record_definitions() {
+        let dictionary = Arc::new(vec![vec!["id".to_string()], vec!["x".to_string(), "y".to_string()]]);
+
+        // The document defines ["name"], which becomes #2, and uses #1 and #2
+        let doc = [0xb9, 0x69, b'n', b'a', b'm', b'e', 0xb6, 0xb7, 0xba, 0x01, 0x01, 0x02, 0xb6, 0xba, 0x02, 0x66, b'a', 0xb6, 0xb6];
+        let mut decoder = Decoder::new(&doc).with_record_definitions(Arc::clone(&dictionary));
+        decoder.read_record_definitions().unwrap();
+        assert_eq!(decoder.record_definitions(), [&["id"][..], &["x", "y"], &["name"]]);
+        assert_eq!(decoder.decode_value().unwrap(), DecodedValue::ArrayStart);
+        assert_eq!(decoder.decode_value().unwrap(), DecodedValue::RecordInstanceStart(1));
+        decoder.skip_value().unwrap();
+        decoder.skip_value().unwrap();
+        decoder.end_container().unwrap();
+        assert_eq!(decoder.decode_value().unwrap(), DecodedValue::RecordInstanceStart(2));
+        // The shared dictionary itself is untouched
+        assert_eq!(dictionary.len(), 2);
+
+        // Without the dictionary, the same body refers to missing definitions
+        let body = [0xba, 0x01, 0x01, 0x02, 0xb6];
+        assert!(matches!(Decoder::new(&body).decode_value(), Err(DecodeError::InvalidData(_))));
+        let mut decoder = Decoder::new(&body).with_record_definitions(dictionary);
+        assert_eq!(decoder.decode_value().unwrap(), DecodedValue::RecordInstanceStart(1));
+
+        // Given definitions count toward max_record_definitions
+        let config = DecoderConfig { max_record_definitions: 2, ..Default::default() };
+        let mut decoder = Decoder::with_config(&doc, config).with_record_definitions(vec![vec![], vec![]]);
+        assert!(matches!(decoder.read_record_definitions(), Err(DecodeError::MaxRecordDefinitionsExceeded)));
     }
 }
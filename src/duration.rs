@@ -0,0 +1,104 @@
+// ABOUTME: Serde `with` modules that write a Duration as one integer count of nanoseconds or milliseconds.
+// ABOUTME: serde's default writes a {secs, nanos} struct; these keep latency payloads to a single number.
+
+use std::time::Duration;
+
+/// The unit of an integer holding a duration, for
+/// [`Value::as_duration`](crate::Value::as_duration).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeUnit {
+    /// Nanoseconds, as written by [`nanos`].
+    Nanos,
+    /// Milliseconds, as written by [`millis`].
+    Millis,
+}
+
+impl TimeUnit {
+    /// The duration of `count` of this unit.
+    #[must_use]
+    pub fn duration(self, count: u64) -> Duration {
+        match self {
+            TimeUnit::Nanos => Duration::from_nanos(count),
+            TimeUnit::Millis => Duration::from_millis(count),
+        }
+    }
+}
+
+/// Write a `Duration` as an unsigned count of nanoseconds, with
+/// `#[serde(with = "serde_bonjson::duration::nanos")]`.
+///
+/// Durations longer than `u64::MAX` nanoseconds (about 584 years) fail to serialize.
+///
+/// # Example
+///
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+/// use std::time::Duration;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Sample {
+///     #[serde(with = "serde_bonjson::duration::nanos")]
+///     latency: Duration,
+/// }
+///
+/// let bytes = serde_bonjson::to_vec(&Sample { latency: Duration::from_micros(1500) }).unwrap();
+/// let value = serde_bonjson::decode_value(&bytes).unwrap();
+/// assert_eq!(value, serde_bonjson::bonjson!({"latency": 1_500_000}));
+/// ```
+pub mod nanos {
+    use super::TimeUnit;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    /// Serialize `duration` as nanoseconds.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the duration doesn't fit a `u64` of nanoseconds.
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        let nanos = u64::try_from(duration.as_nanos())
+            .map_err(|_| S::Error::custom("duration too long for u64 nanoseconds"))?;
+        serializer.serialize_u64(nanos)
+    }
+
+    /// Deserialize a duration from nanoseconds.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value isn't an unsigned integer.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(|count| TimeUnit::Nanos.duration(count))
+    }
+}
+
+/// Write a `Duration` as an unsigned count of whole milliseconds, with
+/// `#[serde(with = "serde_bonjson::duration::millis")]`.
+///
+/// Anything below a millisecond is truncated, so `1.9ms` is written as `1`.
+pub mod millis {
+    use super::TimeUnit;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    /// Serialize `duration` as whole milliseconds.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the duration doesn't fit a `u64` of milliseconds.
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        let millis = u64::try_from(duration.as_millis())
+            .map_err(|_| S::Error::custom("duration too long for u64 milliseconds"))?;
+        serializer.serialize_u64(millis)
+    }
+
+    /// Deserialize a duration from milliseconds.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value isn't an unsigned integer.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(|count| TimeUnit::Millis.duration(count))
+    }
+}
@@ -0,0 +1,46 @@
+// ABOUTME: Unit tests for the duration serde modules.
+// ABOUTME: Tests integer encodings, truncation, overflow, and Value::as_duration.
+
+use crate::duration::TimeUnit;
+use crate::{bonjson, decode_value, from_slice, to_vec};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Histogram {
+    #[serde(with = "crate::duration::nanos")]
+    p50: Duration,
+    #[serde(with = "crate::duration::millis")]
+    p99: Duration,
+}
+
+#[test]
+fn test_durations_as_integers() {
+    let histogram = Histogram { p50: Duration::from_nanos(1_234_567), p99: Duration::from_millis(250) };
+    let bytes = to_vec(&histogram).unwrap();
+    let value = decode_value(&bytes).unwrap();
+    assert_eq!(value.get_key("p50").and_then(crate::Value::as_u64), Some(1_234_567));
+    assert_eq!(value.get_key("p99").and_then(crate::Value::as_u64), Some(250));
+    assert_eq!(from_slice::<Histogram>(&bytes).unwrap(), histogram);
+
+    // Far smaller than serde's default {secs, nanos} struct
+    assert!(bytes.len() < to_vec(&(histogram.p50, histogram.p99)).unwrap().len());
+
+    assert_eq!(value.get_key("p50").unwrap().as_duration(TimeUnit::Nanos), Some(histogram.p50));
+    assert_eq!(value.get_key("p99").unwrap().as_duration(TimeUnit::Millis), Some(histogram.p99));
+    assert_eq!(bonjson!(-1).as_duration(TimeUnit::Millis), None);
+    assert_eq!(bonjson!("1s").as_duration(TimeUnit::Millis), None);
+}
+
+#[test]
+fn test_duration_truncation_and_overflow() {
+    let histogram = Histogram { p50: Duration::ZERO, p99: Duration::from_micros(1900) };
+    let decoded: Histogram = from_slice(&to_vec(&histogram).unwrap()).unwrap();
+    assert_eq!(decoded.p99, Duration::from_millis(1));
+
+    let too_long = Histogram { p50: Duration::MAX, p99: Duration::ZERO };
+    assert!(to_vec(&too_long).is_err());
+
+    let negative = crate::encode_value(&bonjson!({"p50": (-5), "p99": 0})).unwrap();
+    assert!(from_slice::<Histogram>(&negative).is_err());
+}
@@ -0,0 +1,171 @@
+// ABOUTME: Targeted in-place edits of encoded BONJSON documents.
+// ABOUTME: Splices byte ranges to replace values or append to arrays without a full decode/encode.
+
+use crate::de::seek_path;
+use crate::decoder::{Decoder, DecoderConfig};
+use crate::encoder::Encoder;
+use crate::error::{Error, Result};
+use crate::jsonpath::JsonPath;
+use crate::types::type_code;
+use crate::value::Value;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Edits an encoded document in place by splicing byte ranges.
+///
+/// Each edit locates its target by skipping over the values in front of it
+/// and rewrites only that value's bytes; the rest of the document is moved
+/// at most, never re-encoded. A replacement that encodes to the same size as
+/// the original is written over it without moving anything, and appending to
+/// a root array with nothing after it only touches the end of the buffer.
+///
+/// New values are encoded without record definitions (those can only appear
+/// at the start of a document), but they can be written into existing record
+/// instances. Typed array elements can't be addressed individually; replace
+/// the whole array instead. Appending to a typed array rewrites it as a
+/// regular array.
+///
+/// The document's structure is checked once by [`Editor::new`]; edits keep it
+/// well-formed.
+pub struct Editor<'a> {
+    buf: &'a mut Vec<u8>,
+    config: DecoderConfig,
+    record_definitions: Arc<Vec<Vec<String>>>,
+    /// Offset of the root value (after any record definitions).
+    root_start: usize,
+    /// Offset just past the root value.
+    root_end: usize,
+}
+
+impl<'a> Editor<'a> {
+    /// Open an encoded document for editing.
+    pub fn new(buf: &'a mut Vec<u8>) -> Result<Self> {
+        Self::with_config(buf, DecoderConfig::default())
+    }
+
+    /// Open an encoded document for editing with custom configuration.
+    pub fn with_config(buf: &'a mut Vec<u8>, config: DecoderConfig) -> Result<Self> {
+        let mut decoder = Decoder::with_config(buf, config);
+        decoder.check_document_size()?;
+        decoder.read_record_definitions()?;
+        let root_start = decoder.position();
+        decoder.skip_value()?;
+        decoder.finish()?;
+        let root_end = decoder.position();
+        let record_definitions = decoder.shared_record_definitions();
+        let config = decoder.config().clone();
+        Ok(Self { buf, config, record_definitions, root_start, root_end })
+    }
+
+    /// Returns the document's current bytes.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.buf
+    }
+
+    /// Replace the value at `path` (e.g. `$.items[2].name`) with `value`.
+    pub fn replace(&mut self, path: &str, value: &Value) -> Result<()> {
+        let path: JsonPath = path.parse()?;
+        let range = self.locate(&path)?;
+        let bytes = encode_fragment(value)?;
+        self.splice(range, &bytes);
+        Ok(())
+    }
+
+    /// Replace the string at `path` with `s`.
+    ///
+    /// Unlike [`replace`](Self::replace), this fails if the existing value
+    /// isn't a string.
+    pub fn replace_str(&mut self, path: &str, s: &str) -> Result<()> {
+        let path: JsonPath = path.parse()?;
+        let range = self.locate(&path)?;
+        if !type_code::is_any_string(self.buf[range.start]) {
+            return Err(Error::Custom(format!("value at {path} is not a string")));
+        }
+        let bytes = encode_fragment(&Value::String(s.to_owned()))?;
+        self.splice(range, &bytes);
+        Ok(())
+    }
+
+    /// Append `value` to the root array.
+    ///
+    /// A typed array (as `encode_value` writes `[1, 2]`) is first rewritten
+    /// as a regular array, since `value` may not fit its element type.
+    pub fn append(&mut self, value: &Value) -> Result<()> {
+        self.append_at(self.root_start..self.root_end, &JsonPath::default(), value)
+    }
+
+    /// Append `value` to the array at `path`, rewriting a typed array as a
+    /// regular one as [`append`](Self::append) does.
+    pub fn append_to(&mut self, path: &str, value: &Value) -> Result<()> {
+        let path: JsonPath = path.parse()?;
+        let range = self.locate(&path)?;
+        self.append_at(range, &path, value)
+    }
+
+    fn append_at(&mut self, range: Range<usize>, path: &JsonPath, value: &Value) -> Result<()> {
+        let tc = self.buf[range.start];
+        let end_marker = if tc == type_code::ARRAY {
+            range.end - 1
+        } else if type_code::is_typed_array(tc) {
+            let elements = self.untyped_array(range.clone())?;
+            let end_marker = range.start + elements.len() - 1;
+            self.splice(range, &elements);
+            end_marker
+        } else {
+            return Err(Error::Custom(format!("value at {path} is not an array")));
+        };
+        let bytes = encode_fragment(value)?;
+        self.splice(end_marker..end_marker, &bytes);
+        Ok(())
+    }
+
+    /// The typed array at `range` encoded as a regular array.
+    fn untyped_array(&self, range: Range<usize>) -> Result<Vec<u8>> {
+        let mut decoder = Decoder::resume(self.buf, self.config.clone(), Arc::clone(&self.record_definitions), range.start);
+        let Value::Array(elements) = crate::decode_value_recursive(&mut decoder)? else {
+            return Err(Error::Custom("expected a typed array".into()));
+        };
+        let mut bytes = vec![type_code::ARRAY];
+        for element in &elements {
+            bytes.extend(encode_fragment(element)?);
+        }
+        bytes.push(type_code::CONTAINER_END);
+        Ok(bytes)
+    }
+
+    /// Find the byte range of the value at `path`.
+    fn locate(&self, path: &JsonPath) -> Result<Range<usize>> {
+        let mut decoder = Decoder::resume(
+            self.buf,
+            self.config.clone(),
+            Arc::clone(&self.record_definitions),
+            self.root_start,
+        );
+        if !seek_path(&mut decoder, path)? {
+            return Err(Error::Custom(format!("no value at {path}")));
+        }
+        let start = decoder.position();
+        decoder.skip_value()?;
+        Ok(start..decoder.position())
+    }
+
+    fn splice(&mut self, range: Range<usize>, bytes: &[u8]) {
+        let removed = range.len();
+        if removed == bytes.len() {
+            self.buf[range].copy_from_slice(bytes);
+            return;
+        }
+        self.buf.splice(range, bytes.iter().copied());
+        self.root_end = self.root_end + bytes.len() - removed;
+    }
+}
+
+/// Encode a value for insertion into an existing document (no record definitions).
+fn encode_fragment(value: &Value) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let mut encoder = Encoder::new(&mut buf);
+    crate::encode_value_recursive(&mut encoder, value)?;
+    encoder.finish()?;
+    Ok(buf)
+}
@@ -0,0 +1,119 @@
+// ABOUTME: Unit tests for the edit module.
+// ABOUTME: Tests in-place replacement and appends against full decode results.
+
+use crate::edit::Editor;
+use crate::{bonjson, decode_value, encode_value, type_code, Error};
+
+#[test]
+fn test_replace_same_size_in_place() {
+    let mut bytes = encode_value(&bonjson!({"a": 1, "b": "xy"})).unwrap();
+    let len = bytes.len();
+    let mut editor = Editor::new(&mut bytes).unwrap();
+    editor.replace("$.a", &bonjson!(7)).unwrap();
+    editor.replace_str("$.b", "zw").unwrap();
+    assert_eq!(editor.as_bytes().len(), len);
+    assert_eq!(decode_value(&bytes).unwrap(), bonjson!({"a": 7, "b": "zw"}));
+}
+
+#[test]
+fn test_replace_resizes() {
+    let mut bytes = encode_value(&bonjson!({"a": [1, 2], "b": "short", "c": true})).unwrap();
+    let mut editor = Editor::new(&mut bytes).unwrap();
+    editor.replace("$.b", &bonjson!("a considerably longer string")).unwrap();
+    editor.replace("$.a", &bonjson!(null)).unwrap();
+    assert_eq!(
+        decode_value(&bytes).unwrap(),
+        bonjson!({"a": null, "b": "a considerably longer string", "c": true})
+    );
+}
+
+#[test]
+fn test_replace_whole_document() {
+    let mut bytes = encode_value(&bonjson!([1, 2, 3])).unwrap();
+    Editor::new(&mut bytes).unwrap().replace("$", &bonjson!({"k": "v"})).unwrap();
+    assert_eq!(decode_value(&bytes).unwrap(), bonjson!({"k": "v"}));
+}
+
+#[test]
+fn test_append_to_root() {
+    let mut bytes = encode_value(&bonjson!([])).unwrap();
+    let mut editor = Editor::new(&mut bytes).unwrap();
+    for i in 0..3 {
+        editor.append(&bonjson!({"seq": i, "event": "tick"})).unwrap();
+    }
+    editor.replace("$[1].event", &bonjson!("tock")).unwrap();
+    assert_eq!(
+        decode_value(&bytes).unwrap(),
+        bonjson!([
+            {"event": "tick", "seq": 0},
+            {"event": "tock", "seq": 1},
+            {"event": "tick", "seq": 2}
+        ])
+    );
+}
+
+#[test]
+fn test_append_to_typed_array() {
+    // encode_value writes numeric arrays as typed arrays
+    let mut bytes = encode_value(&bonjson!([1, 2])).unwrap();
+    assert!(type_code::is_typed_array(bytes[0]));
+    let mut editor = Editor::new(&mut bytes).unwrap();
+    editor.append(&bonjson!("three")).unwrap();
+    editor.append(&bonjson!(4)).unwrap();
+    assert_eq!(decode_value(&bytes).unwrap(), bonjson!([1, 2, "three", 4]));
+
+    let mut bytes = encode_value(&bonjson!({"samples": [0.5, 1.5], "n": 2})).unwrap();
+    let mut editor = Editor::new(&mut bytes).unwrap();
+    editor.append_to("$.samples", &bonjson!(2.5)).unwrap();
+    editor.replace("$.n", &bonjson!(3)).unwrap();
+    assert_eq!(decode_value(&bytes).unwrap(), bonjson!({"samples": [0.5, 1.5, 2.5], "n": 3}));
+}
+
+#[test]
+fn test_append_to_nested() {
+    let mut bytes = encode_value(&bonjson!({"log": ["a"], "n": 1})).unwrap();
+    let mut editor = Editor::new(&mut bytes).unwrap();
+    editor.append_to("$.log", &bonjson!("b")).unwrap();
+    assert!(editor.append(&bonjson!(1)).is_err());
+    assert!(editor.append_to("$.n", &bonjson!(1)).is_err());
+    assert_eq!(decode_value(&bytes).unwrap(), bonjson!({"log": ["a", "b"], "n": 1}));
+}
+
+#[test]
+fn test_edit_record_instance() {
+    #[derive(serde::Serialize)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    let config = crate::SerializerConfig { records: true, ..Default::default() };
+    let mut bytes = crate::to_vec_with_config(&vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }], &config).unwrap();
+    assert_eq!(bytes[0], type_code::RECORD_DEF);
+
+    let mut editor = Editor::new(&mut bytes).unwrap();
+    editor.replace("$[1].x", &bonjson!(300)).unwrap();
+    // Objects appended after the definitions are written as plain objects
+    editor.append(&bonjson!({"x": 5, "y": 6})).unwrap();
+    assert_eq!(
+        decode_value(&bytes).unwrap(),
+        bonjson!([{"x": 1, "y": 2}, {"x": 300, "y": 4}, {"x": 5, "y": 6}])
+    );
+}
+
+#[test]
+fn test_edit_errors() {
+    let mut bytes = encode_value(&bonjson!({"a": 1, "t": [0.1, 0.2]})).unwrap();
+    let mut editor = Editor::new(&mut bytes).unwrap();
+    let err = editor.replace("$.missing", &bonjson!(1)).unwrap_err();
+    assert_eq!(err, Error::Custom("no value at $.missing".into()));
+    assert!(editor.replace_str("$.a", "x").is_err());
+    // Typed array elements aren't addressable
+    assert!(editor.replace("$.t[0]", &bonjson!(1.5)).is_err());
+    assert!(editor.replace("$.a", &bonjson!(f64::NAN)).is_err());
+    assert_eq!(decode_value(&bytes).unwrap(), bonjson!({"a": 1, "t": [0.1, 0.2]}));
+
+    let mut bytes = encode_value(&bonjson!([1])).unwrap();
+    bytes.push(0x00);
+    let len = bytes.len();
+    assert_eq!(Editor::new(&mut bytes).err(), Some(Error::TrailingBytes(len - 1)));
+}
@@ -2,15 +2,171 @@
 // ABOUTME: Encodes values using delimiter-terminated containers and FF-terminated long strings.
 
 
-use crate::error::{Error, Result};
-use crate::types::{type_code, BigNumber, zigzag_encode, leb128_encode, NATIVE_SIZE_INDEX};
-use std::io::Write;
+use crate::error::EncodeError;
+use crate::types::{type_code, BigNumber, FloatWidth, zigzag_encode, leb128_encode, length_field_size, NATIVE_SIZE_INDEX};
+use crate::compat::CapabilitySet;
+use crate::decoder::{NanInfinityMode, UnicodeNormalization};
+#[cfg(feature = "tracing")]
+use crate::metrics::{CodecMetrics, CodecOperation, MetricsSink};
+use std::collections::HashSet;
+use crate::backend::BonjsonWrite;
+use std::io::{ErrorKind, IoSlice, Read};
+#[cfg(feature = "tracing")]
+use std::sync::Arc;
+
+/// The result of an [`Encoder`] operation.
+pub type Result<T> = std::result::Result<T, EncodeError>;
+
+/// Writes of up to this many bytes (a type code, length field and payload)
+/// are assembled on the stack and issued as a single `write_all`.
+const SMALL_WRITE_MAX: usize = 32;
+
+/// How much of a string or blob the `Encoder::write_*_from_reader` methods
+/// read at a time.
+const STREAM_CHUNK_SIZE: usize = 64 << 10;
+
+/// The longest string with a short (length-in-type-code) encoding.
+const SHORT_STRING_MAX: usize = 66;
+
+/// What the encoder does when an object is given a key it already has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// Write the key anyway (default); the decoder's `duplicate_key_mode` decides
+    #[default]
+    Allow,
+    /// Fail with `EncodeError::DuplicateKey` before writing the repeated key
+    Error,
+}
+
+/// Which type code a sized integer is written with, under an [`EncodingProfile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntSignedness {
+    /// Whichever needs fewer bytes, signed when they tie (default)
+    #[default]
+    Minimal,
+    /// Signed, except for values above `i64::MAX`
+    Signed,
+    /// Unsigned, except for negative values
+    Unsigned,
+}
+
+/// How the encoder chooses among the encodings of an integer.
+///
+/// The default is the minimal encoding, which is what every other setting of
+/// this crate assumes. The others deliberately write integers wider than
+/// they need to be, to generate test vectors that exercise other
+/// implementations' decoders (and to be rejected by a decoder with
+/// [`DecoderConfig::require_canonical_ints`](crate::DecoderConfig::require_canonical_ints)).
+/// The encoding-size helpers such as [`signed_int_encoding_size`] always
+/// describe the minimal encoding.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{encode_value_with_config, EncoderConfig, EncodingProfile, IntSignedness};
+///
+/// // Every integer as a sint32, even 5
+/// let config = EncoderConfig::default().profile(EncodingProfile::fixed(4, IntSignedness::Signed));
+/// let bytes = encode_value_with_config(&serde_bonjson::Value::Int(5), config).unwrap();
+/// assert_eq!(bytes, [0xae, 5, 0, 0, 0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodingProfile {
+    /// Integers from 0 up to this are written as one-byte small ints
+    /// (default: 100, the most the format allows; larger settings act as 100).
+    /// `None` writes every integer with a sized type code.
+    pub small_int_max: Option<u8>,
+    /// The fewest payload bytes a sized integer is written with (default: 1).
+    /// Rounded up to 1, 2, 4 or 8; values that need more still get more.
+    pub min_int_bytes: u8,
+    /// Signed or unsigned type codes for sized integers (default: Minimal).
+    pub int_signedness: IntSignedness,
+}
+
+impl EncodingProfile {
+    /// The minimal encoding of every integer (the default).
+    pub const CANONICAL: Self = EncodingProfile {
+        small_int_max: Some(type_code::SMALLINT_MAX),
+        min_int_bytes: 1,
+        int_signedness: IntSignedness::Minimal,
+    };
+
+    /// No small ints, and sized integers at least `min_int_bytes` wide with
+    /// the given signedness; `fixed(4, IntSignedness::Signed)` writes sint32
+    /// for everything that fits one.
+    #[must_use]
+    pub const fn fixed(min_int_bytes: u8, int_signedness: IntSignedness) -> Self {
+        EncodingProfile { small_int_max: None, min_int_bytes, int_signedness }
+    }
+}
+
+impl Default for EncodingProfile {
+    fn default() -> Self {
+        Self::CANONICAL
+    }
+}
 
 /// Configuration options for the encoder.
 #[derive(Debug, Clone, Default)]
 pub struct EncoderConfig {
     /// Allow NUL characters in strings (default: false)
     pub allow_nul: bool,
+    /// Optional format features the output may use (default: all).
+    /// Writing a value that needs an excluded feature fails with
+    /// `EncodeError::UnsupportedCapability`; `encode_value` avoids excluded features
+    /// where the value allows it.
+    pub capabilities: CapabilitySet,
+    /// Whether an object may be given the same key twice (default: Allow).
+    /// With `Error`, the encoder keeps a set of each open object's keys (and of
+    /// each record definition's keys), so the check costs a hash and a copy per key.
+    pub duplicate_keys: DuplicateKeys,
+    /// Normalization applied to keys before the duplicate check (default: None).
+    /// Set it to match the decoder's `unicode_normalization`, so that keys it
+    /// would normalize to the same string are rejected here.
+    /// Requires the `unicode-normalization` feature for Nfc mode.
+    pub key_normalization: UnicodeNormalization,
+    /// Append a checksum trailer to the document (default: false), for
+    /// [`decode_value_verified`](crate::decode_value_verified) to check.
+    /// Applied by `encode_value_with_config` and `encode_value_to_writer_with_config`;
+    /// output from an `Encoder` used directly can be given one with
+    /// [`checksum::append_trailer`](crate::checksum::append_trailer).
+    pub checksum_trailer: bool,
+    /// How to write NaN and Infinity (default: Reject). `Reject` fails with
+    /// `EncodeError::NanNotAllowed` / `EncodeError::InfinityNotAllowed`, `Allow` writes
+    /// them as floats, and `Stringify` writes the strings `"NaN"`,
+    /// `"Infinity"` and `"-Infinity"` (what a decoder set to `Stringify` reads
+    /// them back as).
+    pub nan_infinity_mode: NanInfinityMode,
+    /// How integers are encoded (default: the minimal encoding). Non-minimal
+    /// profiles are for generating test vectors; see [`EncodingProfile`].
+    pub profile: EncodingProfile,
+    /// Receives [`CodecMetrics`] when a top-level encode call finishes (default: None).
+    #[cfg(feature = "tracing")]
+    pub metrics_sink: Option<Arc<dyn MetricsSink>>,
+}
+
+impl EncoderConfig {
+    config_setters! {
+        allow_nul: bool,
+        capabilities: CapabilitySet,
+        duplicate_keys: DuplicateKeys,
+        key_normalization: UnicodeNormalization,
+        checksum_trailer: bool,
+        nan_infinity_mode: NanInfinityMode,
+        profile: EncodingProfile,
+    }
+    config_setters! { Some:
+        #[cfg(feature = "tracing")]
+        metrics_sink: Arc<dyn MetricsSink>,
+    }
+
+    /// Restrict the output to the features that `capabilities` allows,
+    /// so that decoders limited to that set can read it.
+    #[must_use]
+    pub fn target_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
 }
 
 /// A BONJSON encoder that writes to a byte buffer.
@@ -19,30 +175,59 @@ pub struct EncoderConfig {
 ///
 /// # Performance Note
 ///
-/// The encoder writes small chunks (often single bytes) directly to the writer.
-/// For file or network I/O, wrap your writer in [`std::io::BufWriter`] to avoid
-/// excessive syscall overhead. For in-memory writers like `Vec<u8>`, no buffering
+/// The encoder writes each scalar (type code, length fields and payloads of up
+/// to 32 bytes in total) with a single `write_all`, but containers and larger
+/// values still take several small writes. For file or network I/O, wrap your
+/// writer in [`std::io::BufWriter`] to avoid excessive syscall overhead. For in-memory writers like `Vec<u8>`, no buffering
 /// is needed.
-pub struct Encoder<W: Write> {
+pub struct Encoder<W: BonjsonWrite> {
     writer: W,
     /// Stack of container states: true = object (expecting key/value alternation)
     containers: Vec<ContainerState>,
+    /// With `DuplicateKeys::Error`, one entry per open container (checked or
+    /// unchecked): the keys written so far for objects, None otherwise
+    key_scopes: Vec<Option<HashSet<String>>>,
     config: EncoderConfig,
+    #[cfg(feature = "tracing")]
+    metrics: CodecMetrics,
 }
 
 #[derive(Clone, Copy)]
 struct ContainerState {
     is_object: bool,
     expecting_key: bool,
+    /// Element count declared by `begin_array_sized` / `begin_object_sized`
+    expected: Option<usize>,
+    /// Elements (object entries) completed so far
+    written: usize,
+}
+
+impl Encoder<Vec<u8>> {
+    /// Reserve room for at least `additional` more bytes of output, for
+    /// callers that know (or can estimate) the encoded size up front.
+    pub fn reserve(&mut self, additional: usize) {
+        self.writer.reserve(additional);
+    }
+}
+
+impl Encoder<&mut Vec<u8>> {
+    /// Reserve room for at least `additional` more bytes of output, for
+    /// callers that know (or can estimate) the encoded size up front.
+    pub fn reserve(&mut self, additional: usize) {
+        self.writer.reserve(additional);
+    }
 }
 
-impl<W: Write> Encoder<W> {
+impl<W: BonjsonWrite> Encoder<W> {
     /// Create a new encoder that writes to the given writer.
     pub fn new(writer: W) -> Self {
         Self {
             writer,
             containers: Vec::new(),
+            key_scopes: Vec::new(),
             config: EncoderConfig::default(),
+            #[cfg(feature = "tracing")]
+            metrics: CodecMetrics::default(),
         }
     }
 
@@ -51,7 +236,10 @@ impl<W: Write> Encoder<W> {
         Self {
             writer,
             containers: Vec::new(),
+            key_scopes: Vec::new(),
             config,
+            #[cfg(feature = "tracing")]
+            metrics: CodecMetrics::default(),
         }
     }
 
@@ -65,6 +253,26 @@ impl<W: Write> Encoder<W> {
         &self.writer
     }
 
+    /// Get the encoder configuration.
+    pub fn config(&self) -> &EncoderConfig {
+        &self.config
+    }
+
+    /// Report the counters so far to `tracing` and the configured metrics sink.
+    #[inline]
+    pub(crate) fn report_metrics(&self) {
+        #[cfg(feature = "tracing")]
+        crate::metrics::report(CodecOperation::Encode, &self.metrics, self.config.metrics_sink.as_ref());
+    }
+
+    /// Count the values another encoder wrote, whose bytes this one copies
+    /// with [`write_raw_unchecked`](Self::write_raw_unchecked).
+    #[inline]
+    pub(crate) fn absorb_metrics<V: BonjsonWrite>(&mut self, _other: &Encoder<V>) {
+        #[cfg(feature = "tracing")]
+        self.metrics.add_values(&_other.metrics);
+    }
+
     /// Check if we're currently in an object and expecting a key.
     #[inline]
     fn expecting_object_key(&self) -> bool {
@@ -73,12 +281,18 @@ impl<W: Write> Encoder<W> {
             .is_some_and(|c| c.is_object && c.expecting_key)
     }
 
-    /// Toggle the key/value expectation in the current object.
+    /// Toggle the key/value expectation in the current object, and count
+    /// the element just completed.
     #[inline]
     fn toggle_object_state(&mut self) {
         if let Some(container) = self.containers.last_mut() {
             if container.is_object {
                 container.expecting_key = !container.expecting_key;
+                if container.expecting_key {
+                    container.written += 1;
+                }
+            } else {
+                container.written += 1;
             }
         }
     }
@@ -98,12 +312,14 @@ impl<W: Write> Encoder<W> {
     /// Encode a null value without container state checks.
     #[inline]
     pub(crate) fn write_null_unchecked(&mut self) -> Result<()> {
+        count_metric!(self, values);
         self.write_byte(type_code::NULL)
     }
 
     /// Encode a boolean value without state checks.
     #[inline]
     pub(crate) fn write_bool_unchecked(&mut self, value: bool) -> Result<()> {
+        count_metric!(self, values);
         self.write_byte(if value { type_code::TRUE } else { type_code::FALSE })
     }
 
@@ -119,6 +335,40 @@ impl<W: Write> Encoder<W> {
         self.write_signed_int(value)
     }
 
+    /// Encode a 128-bit signed integer without state checks.
+    ///
+    /// Values within the 64-bit ranges use the regular integer encodings;
+    /// anything larger becomes a BigNumber, with a magnitude wider than
+    /// 8 bytes when the significant digits don't fit a u64.
+    pub(crate) fn write_i128_unchecked(&mut self, value: i128) -> Result<()> {
+        if let Ok(v) = i64::try_from(value) {
+            return self.write_signed_int(v);
+        }
+        if let Ok(v) = u64::try_from(value) {
+            return self.write_unsigned_int(v);
+        }
+        match BigNumber::from_i128(value) {
+            Some(big) => self.write_big_number_payload(big),
+            None => self.write_wide_big_number(value < 0, value.unsigned_abs()),
+        }
+    }
+
+    /// Encode a 128-bit unsigned integer without state checks.
+    pub(crate) fn write_u128_unchecked(&mut self, value: u128) -> Result<()> {
+        if let Ok(v) = u64::try_from(value) {
+            return self.write_unsigned_int(v);
+        }
+        match BigNumber::from_u128(value) {
+            Some(big) => self.write_big_number_payload(big),
+            None => self.write_wide_big_number(false, value),
+        }
+    }
+
+    /// Encode a BigNumber without state checks.
+    pub(crate) fn write_big_number_unchecked(&mut self, value: BigNumber) -> Result<()> {
+        self.write_big_number_payload(value)
+    }
+
     /// Encode a 32-bit float without state checks.
     #[inline]
     pub(crate) fn write_f32_unchecked(&mut self, value: f32) -> Result<()> {
@@ -130,11 +380,8 @@ impl<W: Write> Encoder<W> {
     #[allow(clippy::cast_possible_truncation)]
     #[allow(clippy::cast_precision_loss)]
     pub(crate) fn write_f64_unchecked(&mut self, value: f64) -> Result<()> {
-        if value.is_nan() {
-            return Err(Error::NanNotAllowed);
-        }
-        if value.is_infinite() {
-            return Err(Error::InfinityNotAllowed);
+        if !value.is_finite() {
+            return self.write_non_finite(value);
         }
 
         // Negative zero must be encoded as float
@@ -143,9 +390,7 @@ impl<W: Write> Encoder<W> {
         }
 
         // Try to encode as integer if it's a whole number
-        let as_int = value as i64;
-        #[allow(clippy::float_cmp)]
-        if (as_int as f64) == value {
+        if let Some(as_int) = float_as_exact_i64(value) {
             return self.write_signed_int(as_int);
         }
 
@@ -154,42 +399,39 @@ impl<W: Write> Encoder<W> {
 
     /// Encode a string without state checks.
     #[inline]
-    #[allow(clippy::cast_possible_truncation)]
     pub(crate) fn write_str_unchecked(&mut self, value: &str) -> Result<()> {
-        let bytes = value.as_bytes();
-        let len = bytes.len();
-
-        if !self.config.allow_nul && memchr::memchr(0, bytes).is_some() {
-            return Err(Error::NulCharacter);
-        }
+        count_metric!(self, values);
+        self.write_str_raw(value)
+    }
 
-        if len <= 66 {
-            self.write_byte(type_code::STRING0 + len as u8)?;
-            self.write_bytes(bytes)?;
-        } else {
-            // Long string: FF + data + FF
-            self.write_byte(type_code::STRING_LONG)?;
-            self.write_bytes(bytes)?;
-            self.write_byte(type_code::STRING_LONG)?;
-        }
-        Ok(())
+    /// Encode an object key without state checks, other than the duplicate
+    /// key check.
+    #[inline]
+    pub(crate) fn write_key_unchecked(&mut self, key: &str) -> Result<()> {
+        self.check_duplicate_key(key)?;
+        self.write_str_unchecked(key)
     }
 
     /// Begin an array without state checks.
     #[inline]
     pub(crate) fn begin_array_unchecked(&mut self) -> Result<()> {
+        count_metric!(self, values);
+        self.open_key_scope(false);
         self.write_byte(type_code::ARRAY)
     }
 
     /// Begin an object without state checks.
     #[inline]
     pub(crate) fn begin_object_unchecked(&mut self) -> Result<()> {
+        count_metric!(self, values);
+        self.open_key_scope(true);
         self.write_byte(type_code::OBJECT)
     }
 
     /// Write a container end marker without state checks.
     #[inline]
     pub(crate) fn end_container_unchecked(&mut self) -> Result<()> {
+        self.key_scopes.pop();
         self.write_byte(type_code::CONTAINER_END)
     }
 
@@ -201,49 +443,130 @@ impl<W: Write> Encoder<W> {
         count: usize,
         data: &[u8],
     ) -> Result<()> {
-        self.write_byte(type_code_byte)?;
-        let mut buf = [0u8; 10];
-        let n = leb128_encode(count as u64, &mut buf);
-        self.write_bytes(&buf[..n])?;
-        self.write_bytes(data)
+        self.require(self.config.capabilities.typed_arrays, "typed_arrays")?;
+        count_metric!(self, values);
+        count_metric!(self, typed_arrays);
+        let mut header = [0u8; 11];
+        header[0] = type_code_byte;
+        let n = leb128_encode(count as u64, header[1..].first_chunk_mut().unwrap());
+        self.write_prefixed(&header[..=n], data)
     }
 
     /// Write a record definition without state checks.
     pub(crate) fn write_record_definition_unchecked(&mut self, keys: &[&str]) -> Result<()> {
+        self.require(self.config.capabilities.records, "records")?;
+        if self.config.duplicate_keys == DuplicateKeys::Error {
+            let mut seen = HashSet::new();
+            if !keys.iter().all(|key| seen.insert(self.normalized_key(key))) {
+                return Err(EncodeError::DuplicateKey);
+            }
+        }
         self.write_byte(type_code::RECORD_DEF)?;
         for key in keys {
-            self.write_str_unchecked(key)?;
+            self.write_str_raw(key)?;
         }
         self.write_byte(type_code::CONTAINER_END)
     }
 
     /// Begin a record instance without state checks.
     pub(crate) fn begin_record_instance_unchecked(&mut self, def_index: usize) -> Result<()> {
-        self.write_byte(type_code::RECORD_INSTANCE)?;
-        let mut buf = [0u8; 10];
-        let n = leb128_encode(def_index as u64, &mut buf);
-        self.write_bytes(&buf[..n])
+        count_metric!(self, values);
+        count_metric!(self, records);
+        self.open_key_scope(false);
+        let mut buf = [0u8; 11];
+        buf[0] = type_code::RECORD_INSTANCE;
+        let n = leb128_encode(def_index as u64, buf[1..].first_chunk_mut().unwrap());
+        self.write_bytes(&buf[..=n])
+    }
+
+    /// Write already-encoded bytes without state checks.
+    #[inline]
+    pub(crate) fn write_raw_unchecked(&mut self, bytes: &[u8]) -> Result<()> {
+        self.write_bytes(bytes)
+    }
+
+    /// With `DuplicateKeys::Error`, fail with `DuplicateKey` if the innermost
+    /// open object already has `key`, and otherwise remember it.
+    #[inline]
+    pub(crate) fn check_duplicate_key(&mut self, key: &str) -> Result<()> {
+        if let Some(Some(keys)) = self.key_scopes.last_mut() {
+            if !keys.insert(crate::maybe_nfc_normalize(self.config.key_normalization, key.to_owned())) {
+                return Err(EncodeError::DuplicateKey);
+            }
+        }
+        Ok(())
+    }
+
+    fn normalized_key(&self, key: &str) -> String {
+        crate::maybe_nfc_normalize(self.config.key_normalization, key.to_owned())
+    }
+
+    /// Track a newly opened container for the duplicate key check.
+    #[inline]
+    fn open_key_scope(&mut self, is_object: bool) {
+        if self.config.duplicate_keys == DuplicateKeys::Error {
+            self.key_scopes.push(is_object.then(HashSet::new));
+        }
+    }
+
+    /// Fail with `UnsupportedCapability` unless the target capabilities include `feature`.
+    #[inline]
+    fn require(&self, allowed: bool, feature: &'static str) -> Result<()> {
+        if allowed {
+            Ok(())
+        } else {
+            Err(EncodeError::UnsupportedCapability(feature))
+        }
     }
 
     /// Write a single byte.
     #[inline]
     fn write_byte(&mut self, byte: u8) -> Result<()> {
-        self.writer.write_all(&[byte])?;
+        count_metric!(self, bytes);
+        self.writer.write_bytes(&[byte])?;
         Ok(())
     }
 
     /// Write multiple bytes.
     #[inline]
     fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
-        self.writer.write_all(bytes)?;
+        count_metric!(self, bytes, bytes.len());
+        self.writer.write_bytes(bytes)?;
+        Ok(())
+    }
+
+    /// Write a type code and length prefix followed by its payload. When they
+    /// fit in [`SMALL_WRITE_MAX`] bytes they're copied into a stack buffer and
+    /// handed to the writer in one `write_all`, which matters for writers
+    /// that aren't a `Vec` (each call into a `BufWriter` has its own overhead).
+    /// Larger payloads go out with a vectored write instead.
+    #[inline]
+    fn write_prefixed(&mut self, prefix: &[u8], payload: &[u8]) -> Result<()> {
+        let len = prefix.len() + payload.len();
+        if len <= SMALL_WRITE_MAX {
+            let mut buf = [0u8; SMALL_WRITE_MAX];
+            buf[..prefix.len()].copy_from_slice(prefix);
+            buf[prefix.len()..len].copy_from_slice(payload);
+            return self.write_bytes(&buf[..len]);
+        }
+        self.write_all_vectored(&mut [IoSlice::new(prefix), IoSlice::new(payload)])
+    }
+
+    /// Write all of `slices`, letting writers that support vectored writes
+    /// take a large borrowed payload directly rather than through their
+    /// buffer (`BufWriter` passes oversized writes straight to the inner writer).
+    fn write_all_vectored(&mut self, slices: &mut [IoSlice<'_>]) -> Result<()> {
+        count_metric!(self, bytes, slices.iter().map(|slice| slice.len()).sum::<usize>());
+        self.writer.write_slices(slices)?;
         Ok(())
     }
 
     /// Encode a null value.
     pub fn write_null(&mut self) -> Result<()> {
         if self.expecting_object_key() {
-            return Err(Error::ExpectedObjectKey);
+            return Err(EncodeError::ExpectedObjectKey);
         }
+        count_metric!(self, values);
         self.write_byte(type_code::NULL)?;
         self.toggle_object_state();
         Ok(())
@@ -252,8 +575,9 @@ impl<W: Write> Encoder<W> {
     /// Encode a boolean value.
     pub fn write_bool(&mut self, value: bool) -> Result<()> {
         if self.expecting_object_key() {
-            return Err(Error::ExpectedObjectKey);
+            return Err(EncodeError::ExpectedObjectKey);
         }
+        count_metric!(self, values);
         self.write_byte(if value {
             type_code::TRUE
         } else {
@@ -266,7 +590,7 @@ impl<W: Write> Encoder<W> {
     /// Encode an unsigned integer.
     pub fn write_u64(&mut self, value: u64) -> Result<()> {
         if self.expecting_object_key() {
-            return Err(Error::ExpectedObjectKey);
+            return Err(EncodeError::ExpectedObjectKey);
         }
         self.write_unsigned_int(value)?;
         self.toggle_object_state();
@@ -276,7 +600,7 @@ impl<W: Write> Encoder<W> {
     /// Encode a signed integer.
     pub fn write_i64(&mut self, value: i64) -> Result<()> {
         if self.expecting_object_key() {
-            return Err(Error::ExpectedObjectKey);
+            return Err(EncodeError::ExpectedObjectKey);
         }
         self.write_signed_int(value)?;
         self.toggle_object_state();
@@ -288,14 +612,13 @@ impl<W: Write> Encoder<W> {
     #[allow(clippy::cast_precision_loss)]
     pub fn write_f64(&mut self, value: f64) -> Result<()> {
         if self.expecting_object_key() {
-            return Err(Error::ExpectedObjectKey);
+            return Err(EncodeError::ExpectedObjectKey);
         }
 
-        if value.is_nan() {
-            return Err(Error::NanNotAllowed);
-        }
-        if value.is_infinite() {
-            return Err(Error::InfinityNotAllowed);
+        if !value.is_finite() {
+            self.write_non_finite(value)?;
+            self.toggle_object_state();
+            return Ok(());
         }
 
         // Negative zero must be encoded as float
@@ -306,9 +629,7 @@ impl<W: Write> Encoder<W> {
         }
 
         // Try to encode as integer if it's a whole number
-        let as_int = value as i64;
-        #[allow(clippy::float_cmp)]
-        if (as_int as f64) == value {
+        if let Some(as_int) = float_as_exact_i64(value) {
             return self.write_i64(as_int);
         }
 
@@ -322,10 +643,70 @@ impl<W: Write> Encoder<W> {
         self.write_f64(f64::from(value))
     }
 
+    /// Encode a float as exactly a float32 or float64, without the narrowing
+    /// that [`write_f64`](Self::write_f64) applies to whole and float32-exact values.
+    ///
+    /// Fails with `EncodeError::ValueOutOfRange` if `value` would lose precision at
+    /// `width`. NaN and Infinity follow `nan_infinity_mode`, as with `write_f64`.
+    pub fn write_float_with_width(&mut self, value: f64, width: FloatWidth) -> Result<()> {
+        if self.expecting_object_key() {
+            return Err(EncodeError::ExpectedObjectKey);
+        }
+        if !value.is_finite() && self.config.nan_infinity_mode != NanInfinityMode::Allow {
+            self.write_non_finite(value)?;
+            self.toggle_object_state();
+            return Ok(());
+        }
+        if !width.holds(value) {
+            return Err(EncodeError::ValueOutOfRange);
+        }
+        self.write_float_payload(value, width)?;
+        self.toggle_object_state();
+        Ok(())
+    }
+
+    /// Encode an IEEE 754 half-precision float.
+    ///
+    /// BONJSON has no 16-bit float type, so the value is widened to `f32`
+    /// (which represents every `f16` exactly) and encoded like [`write_f32`](Self::write_f32).
+    #[cfg(feature = "half")]
+    pub fn write_f16_ieee(&mut self, value: half::f16) -> Result<()> {
+        self.write_f32(value.to_f32())
+    }
+
+    /// Encode a slice of IEEE 754 half-precision floats as a float32 typed array.
+    ///
+    /// Each element is widened to `f32` exactly. NaN and Infinity follow
+    /// `nan_infinity_mode`, as with [`write_f64`](Self::write_f64); under
+    /// `Stringify`, a slice holding them is written as a regular array.
+    #[cfg(feature = "half")]
+    pub fn write_f16_ieee_array(&mut self, values: &[half::f16]) -> Result<()> {
+        if self.config.nan_infinity_mode == NanInfinityMode::Stringify && values.iter().any(|v| !v.is_finite()) {
+            self.begin_array()?;
+            for value in values {
+                self.write_f16_ieee(*value)?;
+            }
+            return self.end_container();
+        }
+        let mut data = Vec::with_capacity(values.len() * 4);
+        for value in values {
+            if self.config.nan_infinity_mode == NanInfinityMode::Reject {
+                if value.is_nan() {
+                    return Err(EncodeError::NanNotAllowed);
+                }
+                if value.is_infinite() {
+                    return Err(EncodeError::InfinityNotAllowed);
+                }
+            }
+            data.extend_from_slice(&value.to_f32().to_le_bytes());
+        }
+        self.write_typed_array_raw(type_code::TYPED_ARRAY_FLOAT32, values.len(), &data)
+    }
+
     /// Encode a `BigNumber` using zigzag LEB128 metadata and LE magnitude bytes.
     pub fn write_big_number(&mut self, value: BigNumber) -> Result<()> {
         if self.expecting_object_key() {
-            return Err(Error::ExpectedObjectKey);
+            return Err(EncodeError::ExpectedObjectKey);
         }
 
         self.write_big_number_payload(value)?;
@@ -337,108 +718,217 @@ impl<W: Write> Encoder<W> {
     /// Write the BigNumber payload (type code + exponent + signed_length + magnitude).
     /// Shared between checked and unchecked paths.
     fn write_big_number_payload(&mut self, value: BigNumber) -> Result<()> {
-        self.write_byte(type_code::BIG_NUMBER)?;
+        self.write_big_number_parts(value.sign, u128::from(value.significand), value.exponent)
+    }
+
+    /// Encode an integer too large for a u64 significand as a BigNumber whose
+    /// magnitude takes up to 16 bytes. Trailing decimal zeros go into the exponent.
+    fn write_wide_big_number(&mut self, negative: bool, magnitude: u128) -> Result<()> {
+        let (mut magnitude, mut exponent) = (magnitude, 0);
+        while magnitude % 10 == 0 {
+            magnitude /= 10;
+            exponent += 1;
+        }
+        self.write_big_number_parts(if negative { -1 } else { 1 }, magnitude, exponent)
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn write_big_number_parts(&mut self, sign: i8, magnitude: u128, exponent: i64) -> Result<()> {
+        self.require(self.config.capabilities.big_numbers, "big_numbers")?;
+        count_metric!(self, values);
+        // Type code, two LEB128 fields and up to 16 magnitude bytes, written at once
+        let mut buf = [0u8; 37];
+        buf[0] = type_code::BIG_NUMBER;
 
         // Encode exponent as zigzag LEB128
-        let mut buf = [0u8; 10];
-        let n = leb128_encode(zigzag_encode(value.exponent), &mut buf);
-        self.write_bytes(&buf[..n])?;
+        let mut len = 1 + leb128_encode(zigzag_encode(exponent), buf[1..].first_chunk_mut().unwrap());
 
-        if value.significand == 0 {
+        if magnitude == 0 {
             // Zero significand: signed_length = 0, no magnitude bytes
-            self.write_byte(0x00)?;
-            return Ok(());
+            return self.write_bytes(&buf[..=len]);
         }
 
-        // Convert significand to LE bytes and find normalized length
-        let sig_bytes = value.significand.to_le_bytes();
-        let byte_count = 8 - sig_bytes.iter().rev().take_while(|&&b| b == 0).count();
+        // Convert magnitude to LE bytes and find normalized length
+        let mag_bytes = magnitude.to_le_bytes();
+        let byte_count = 16 - mag_bytes.iter().rev().take_while(|&&b| b == 0).count();
 
         // Encode signed_length: positive byte_count for positive, negative for negative
-        let signed_length: i64 = if value.sign < 0 {
+        let signed_length: i64 = if sign < 0 {
             -(byte_count as i64)
         } else {
             byte_count as i64
         };
-        let n = leb128_encode(zigzag_encode(signed_length), &mut buf);
-        self.write_bytes(&buf[..n])?;
+        len += leb128_encode(zigzag_encode(signed_length), buf[len..].first_chunk_mut().unwrap());
 
-        // Write raw LE magnitude bytes
-        self.write_bytes(&sig_bytes[..byte_count])
+        // Raw LE magnitude bytes
+        buf[len..len + byte_count].copy_from_slice(&mag_bytes[..byte_count]);
+        self.write_bytes(&buf[..len + byte_count])
     }
 
-    /// Encode a string.
-    #[allow(clippy::cast_possible_truncation)]
+    /// Encode a string. In key position, fails with `EncodeError::DuplicateKey` if
+    /// the object already has the key and `duplicate_keys` is `Error`.
     pub fn write_str(&mut self, value: &str) -> Result<()> {
-        let bytes = value.as_bytes();
-        let len = bytes.len();
+        if self.expecting_object_key() {
+            self.check_duplicate_key(value)?;
+        }
+        self.write_str_unchecked(value)?;
+        self.toggle_object_state();
+        Ok(())
+    }
 
-        if !self.config.allow_nul && memchr::memchr(0, bytes).is_some() {
-            return Err(Error::NulCharacter);
+    /// Encode a string read from `reader` a chunk at a time, so a large one
+    /// is never in memory whole.
+    ///
+    /// Long strings are delimited rather than length-prefixed, so the output
+    /// is what [`write_str`](Self::write_str) would write for the whole string
+    /// and the length needn't be known. If `len_hint` is given, the reader
+    /// must produce exactly that many bytes. In key position the string is
+    /// read whole, for the duplicate key check.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_bonjson::{decode_value, Encoder, Value};
+    ///
+    /// let blob = "ab".repeat(100_000);
+    /// let mut encoder = Encoder::new(Vec::new());
+    /// encoder.write_str_from_reader(blob.as_bytes(), None).unwrap();
+    /// let bytes = encoder.finish().unwrap();
+    /// assert_eq!(decode_value(&bytes).unwrap(), Value::String(blob));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `EncodeError::InvalidUtf8` or `EncodeError::NulCharacter` if the bytes
+    /// aren't a valid string, `EncodeError::InvalidData` if their length doesn't
+    /// match `len_hint`, and `EncodeError::Io` if reading or writing fails. The
+    /// output then ends partway through the string.
+    pub fn write_str_from_reader<R: Read>(&mut self, mut reader: R, len_hint: Option<u64>) -> Result<()> {
+        if self.expecting_object_key() {
+            let mut key = Vec::new();
+            reader.read_to_end(&mut key)?;
+            check_len_hint(key.len() as u64, len_hint)?;
+            return self.write_str(std::str::from_utf8(&key)?);
         }
 
-        if len <= 66 {
-            self.write_byte(type_code::STRING0 + len as u8)?;
-            self.write_bytes(bytes)?;
-        } else {
-            // Long string: FF + data + FF
-            self.write_byte(type_code::STRING_LONG)?;
-            self.write_bytes(bytes)?;
-            self.write_byte(type_code::STRING_LONG)?;
+        // Read one byte past the short string limit to pick the encoding
+        let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+        let mut filled = read_up_to(&mut reader, &mut buf[..=SHORT_STRING_MAX])?;
+        if filled <= SHORT_STRING_MAX {
+            check_len_hint(filled as u64, len_hint)?;
+            self.write_str_unchecked(std::str::from_utf8(&buf[..filled])?)?;
+            self.toggle_object_state();
+            return Ok(());
         }
 
+        self.require(self.config.capabilities.long_strings, "long_strings")?;
+        count_metric!(self, values);
+        count_metric!(self, strings);
+        self.write_byte(type_code::STRING_LONG)?;
+        let mut total = 0u64;
+        loop {
+            // A UTF-8 sequence split across reads waits for the rest of it
+            let valid = match std::str::from_utf8(&buf[..filled]) {
+                Ok(_) => filled,
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(_) => return Err(EncodeError::InvalidUtf8),
+            };
+            if !self.config.allow_nul && memchr::memchr(0, &buf[..valid]).is_some() {
+                return Err(EncodeError::NulCharacter);
+            }
+            self.write_bytes(&buf[..valid])?;
+            total += valid as u64;
+            buf.copy_within(valid..filled, 0);
+            filled -= valid;
+
+            let n = read_up_to(&mut reader, &mut buf[filled..])?;
+            if n == 0 {
+                if filled > 0 {
+                    return Err(EncodeError::InvalidUtf8);
+                }
+                break;
+            }
+            filled += n;
+        }
+        check_len_hint(total, len_hint)?;
+        self.write_byte(type_code::STRING_LONG)?;
         self.toggle_object_state();
         Ok(())
     }
 
-    /// Begin encoding an array (delimiter-terminated).
+    /// Begin encoding an array whose length isn't known up front.
+    ///
+    /// Writes `0xB7`; the elements follow and [`end_container`](Self::end_container)
+    /// writes `0xB6`. BONJSON containers are always delimiter-terminated, so
+    /// this is the only array encoding. The compact, count-prefixed form
+    /// exists for homogeneous numbers only: see
+    /// [`write_typed_array_raw`](Self::write_typed_array_raw).
     pub fn begin_array(&mut self) -> Result<()> {
-        if self.expecting_object_key() {
-            return Err(Error::ExpectedObjectKey);
-        }
-        self.write_byte(type_code::ARRAY)?;
-        self.containers.push(ContainerState {
-            is_object: false,
-            expecting_key: false,
-        });
-        Ok(())
+        self.begin_container(false, None)
+    }
+
+    /// Begin encoding an array of exactly `count` elements.
+    ///
+    /// Produces the same bytes as [`begin_array`](Self::begin_array), as the
+    /// format has no count-prefixed arrays, but
+    /// [`end_container`](Self::end_container) fails with
+    /// `EncodeError::ContainerSizeMismatch` unless `count` elements were written.
+    pub fn begin_array_sized(&mut self, count: usize) -> Result<()> {
+        self.begin_container(false, Some(count))
     }
 
-    /// Begin encoding an object (delimiter-terminated).
+    /// Begin encoding an object whose size isn't known up front.
+    ///
+    /// Writes `0xB8`; alternating keys and values follow and
+    /// [`end_container`](Self::end_container) writes `0xB6`.
     pub fn begin_object(&mut self) -> Result<()> {
+        self.begin_container(true, None)
+    }
+
+    /// Begin encoding an object of exactly `count` entries.
+    ///
+    /// Produces the same bytes as [`begin_object`](Self::begin_object);
+    /// [`end_container`](Self::end_container) fails with
+    /// `EncodeError::ContainerSizeMismatch` unless `count` key/value pairs were written.
+    pub fn begin_object_sized(&mut self, count: usize) -> Result<()> {
+        self.begin_container(true, Some(count))
+    }
+
+    fn begin_container(&mut self, is_object: bool, expected: Option<usize>) -> Result<()> {
         if self.expecting_object_key() {
-            return Err(Error::ExpectedObjectKey);
+            return Err(EncodeError::ExpectedObjectKey);
+        }
+        if is_object {
+            self.begin_object_unchecked()?;
+        } else {
+            self.begin_array_unchecked()?;
         }
-        self.write_byte(type_code::OBJECT)?;
         self.containers.push(ContainerState {
-            is_object: true,
-            expecting_key: true,
+            is_object,
+            expecting_key: is_object,
+            expected,
+            written: 0,
         });
         Ok(())
     }
 
     /// Write a record definition (type code 0xB9 + string keys + container end).
     pub fn write_record_definition(&mut self, keys: &[&str]) -> Result<()> {
-        self.write_byte(type_code::RECORD_DEF)?;
-        for key in keys {
-            self.write_str_raw(key)?;
-        }
-        self.write_byte(type_code::CONTAINER_END)
+        self.write_record_definition_unchecked(keys)
     }
 
     /// Begin a record instance (type code 0xBA + LEB128 definition index).
     /// Values should be written with normal write methods, terminated by end_container().
     pub fn begin_record_instance(&mut self, def_index: usize) -> Result<()> {
         if self.expecting_object_key() {
-            return Err(Error::ExpectedObjectKey);
+            return Err(EncodeError::ExpectedObjectKey);
         }
-        self.write_byte(type_code::RECORD_INSTANCE)?;
-        let mut buf = [0u8; 10];
-        let n = leb128_encode(def_index as u64, &mut buf);
-        self.write_bytes(&buf[..n])?;
+        self.begin_record_instance_unchecked(def_index)?;
         self.containers.push(ContainerState {
             is_object: false,
             expecting_key: false,
+            expected: None,
+            written: 0,
         });
         Ok(())
     }
@@ -448,34 +938,73 @@ impl<W: Write> Encoder<W> {
     /// `count` is the number of elements, `data` is the raw LE byte data.
     pub fn write_typed_array_raw(&mut self, type_code_byte: u8, count: usize, data: &[u8]) -> Result<()> {
         if self.expecting_object_key() {
-            return Err(Error::ExpectedObjectKey);
+            return Err(EncodeError::ExpectedObjectKey);
         }
-        self.write_byte(type_code_byte)?;
-        let mut buf = [0u8; 10];
-        let n = leb128_encode(count as u64, &mut buf);
-        self.write_bytes(&buf[..n])?;
-        self.write_bytes(data)?;
+        self.write_typed_array_raw_unchecked(type_code_byte, count, data)?;
         self.toggle_object_state();
         Ok(())
     }
 
-    /// Write a string without container state tracking (for record definition keys).
+    /// Write `bytes` as a blob: a uint8 typed array, this crate's
+    /// representation of binary data (see [`blob`](crate::blob)).
+    pub fn write_blob(&mut self, bytes: &[u8]) -> Result<()> {
+        self.write_typed_array_raw(type_code::TYPED_ARRAY_UINT8, bytes.len(), bytes)
+    }
+
+    /// Write a blob of `len` bytes read from `reader` a chunk at a time, so a
+    /// large one is never in memory whole. The typed array's count comes
+    /// first, so the length must be known up front.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EncodeError::InvalidData` if the reader ends before `len` bytes
+    /// (the output then ends partway through the blob), and `EncodeError::Io` if
+    /// reading or writing fails. Bytes past `len` are left unread.
+    pub fn write_blob_from_reader<R: Read>(&mut self, mut reader: R, len: u64) -> Result<()> {
+        if self.expecting_object_key() {
+            return Err(EncodeError::ExpectedObjectKey);
+        }
+        self.require(self.config.capabilities.typed_arrays, "typed_arrays")?;
+        count_metric!(self, values);
+        count_metric!(self, typed_arrays);
+        let mut header = [0u8; 11];
+        header[0] = type_code::TYPED_ARRAY_UINT8;
+        let n = leb128_encode(len, header[1..].first_chunk_mut().unwrap());
+        self.write_bytes(&header[..=n])?;
+
+        let mut buf = vec![0u8; usize::try_from(len).map_or(STREAM_CHUNK_SIZE, |len| len.min(STREAM_CHUNK_SIZE))];
+        let mut written = 0u64;
+        while written < len {
+            let want = buf.len().min(usize::try_from(len - written).unwrap_or(usize::MAX));
+            let n = read_up_to(&mut reader, &mut buf[..want])?;
+            self.write_bytes(&buf[..n])?;
+            written += n as u64;
+            if n < want {
+                return Err(EncodeError::InvalidData(format!("blob is {written} bytes, expected {len}")));
+            }
+        }
+        self.toggle_object_state();
+        Ok(())
+    }
+
+    /// Write a string without container state tracking. Record definition keys
+    /// are written with this directly, as they aren't values.
     #[allow(clippy::cast_possible_truncation)]
     fn write_str_raw(&mut self, value: &str) -> Result<()> {
+        count_metric!(self, strings);
         let bytes = value.as_bytes();
         let len = bytes.len();
 
         if !self.config.allow_nul && memchr::memchr(0, bytes).is_some() {
-            return Err(Error::NulCharacter);
+            return Err(EncodeError::NulCharacter);
         }
 
-        if len <= 66 {
-            self.write_byte(type_code::STRING0 + len as u8)?;
-            self.write_bytes(bytes)?;
+        if len <= SHORT_STRING_MAX {
+            self.write_prefixed(&[type_code::STRING0 + len as u8], bytes)?;
         } else {
-            self.write_byte(type_code::STRING_LONG)?;
-            self.write_bytes(bytes)?;
-            self.write_byte(type_code::STRING_LONG)?;
+            self.require(self.config.capabilities.long_strings, "long_strings")?;
+            let marker = [type_code::STRING_LONG];
+            self.write_all_vectored(&mut [IoSlice::new(&marker), IoSlice::new(bytes), IoSlice::new(&marker)])?;
         }
         Ok(())
     }
@@ -485,14 +1014,19 @@ impl<W: Write> Encoder<W> {
         let container = self
             .containers
             .pop()
-            .ok_or(Error::UnbalancedContainers)?;
+            .ok_or(EncodeError::UnbalancedContainers)?;
 
         // Can't close an object while expecting a value
         if container.is_object && !container.expecting_key {
-            return Err(Error::ExpectedObjectValue);
+            return Err(EncodeError::ExpectedObjectValue);
+        }
+        if let Some(expected) = container.expected {
+            if container.written != expected {
+                return Err(EncodeError::ContainerSizeMismatch { expected, written: container.written });
+            }
         }
 
-        self.write_byte(type_code::CONTAINER_END)?;
+        self.end_container_unchecked()?;
         self.toggle_object_state();
         Ok(())
     }
@@ -500,7 +1034,7 @@ impl<W: Write> Encoder<W> {
     /// Finish encoding and ensure all containers are closed.
     pub fn finish(self) -> Result<W> {
         if !self.containers.is_empty() {
-            return Err(Error::UnclosedContainer);
+            return Err(EncodeError::UnclosedContainer);
         }
         Ok(self.writer)
     }
@@ -512,6 +1046,10 @@ impl<W: Write> Encoder<W> {
     /// Write an unsigned integer using the optimal encoding.
     #[allow(clippy::cast_possible_truncation)]
     fn write_unsigned_int(&mut self, value: u64) -> Result<()> {
+        count_metric!(self, values);
+        if self.config.profile != EncodingProfile::CANONICAL {
+            return self.write_int_with_profile(i128::from(value));
+        }
         // Small integer range: 0-100
         if value <= 100 {
             return self.write_byte(value as u8);
@@ -529,15 +1067,17 @@ impl<W: Write> Encoder<W> {
             type_code::SINT8 + native_index
         };
 
-        self.write_byte(type_code)?;
-        let bytes = value.to_le_bytes();
-        self.write_bytes(&bytes[..native_bytes])
+        self.write_int_payload(type_code, value, native_bytes)
     }
 
     /// Write a signed integer using the optimal encoding.
     #[allow(clippy::cast_possible_truncation)]
     #[allow(clippy::cast_sign_loss)]
     fn write_signed_int(&mut self, value: i64) -> Result<()> {
+        count_metric!(self, values);
+        if self.config.profile != EncodingProfile::CANONICAL {
+            return self.write_int_with_profile(i128::from(value));
+        }
         // Small integer range: 0 to 100
         if (0..=100).contains(&value) {
             return self.write_byte(value as u8);
@@ -553,35 +1093,89 @@ impl<W: Write> Encoder<W> {
             let unsigned_native_index = NATIVE_SIZE_INDEX[unsigned_min - 1];
             let unsigned_native_bytes = 1usize << (unsigned_native_index as usize);
             if unsigned_native_bytes < native_bytes {
-                self.write_byte(type_code::UINT8 + unsigned_native_index)?;
-                let bytes = (value as u64).to_le_bytes();
-                return self.write_bytes(&bytes[..unsigned_native_bytes]);
+                return self.write_int_payload(
+                    type_code::UINT8 + unsigned_native_index,
+                    value as u64,
+                    unsigned_native_bytes,
+                );
             }
         }
 
-        self.write_byte(type_code::SINT8 + native_index)?;
-        let bytes = value.to_le_bytes();
-        self.write_bytes(&bytes[..native_bytes])
+        self.write_int_payload(type_code::SINT8 + native_index, value as u64, native_bytes)
     }
 
-    /// Write a float using the optimal encoding (32 or 64 bit).
+    /// Write an integer (from the `i64` or `u64` range) as a non-canonical
+    /// `profile` says.
     #[allow(clippy::cast_possible_truncation)]
-    fn write_float(&mut self, value: f64) -> Result<()> {
-        // Try f32
-        let f32_val = value as f32;
-        #[allow(clippy::float_cmp)]
-        if f64::from(f32_val) == value {
-            let mut buf = [0u8; 5];
-            buf[0] = type_code::FLOAT32;
-            buf[1..5].copy_from_slice(&f32_val.to_le_bytes());
-            return self.write_bytes(&buf);
+    #[allow(clippy::cast_sign_loss)]
+    fn write_int_with_profile(&mut self, value: i128) -> Result<()> {
+        let profile = self.config.profile;
+        if let Some(max) = profile.small_int_max {
+            if (0..=i128::from(max.min(type_code::SMALLINT_MAX))).contains(&value) {
+                return self.write_byte(value as u8);
+            }
         }
+        let min_bytes = usize::from(profile.min_int_bytes).clamp(1, 8);
+        let native = |bytes: usize| 1usize << NATIVE_SIZE_INDEX[bytes.max(min_bytes) - 1];
+        let signed_bytes = i64::try_from(value).ok().map(|v| native(required_signed_bytes_min1(v)));
+        let unsigned_bytes = u64::try_from(value).ok().map(|v| native(required_unsigned_bytes_min1(v)));
+        let (base, native_bytes) = match (signed_bytes, unsigned_bytes, profile.int_signedness) {
+            (Some(signed), Some(unsigned), IntSignedness::Minimal) if unsigned < signed => (type_code::UINT8, unsigned),
+            (Some(_), Some(unsigned), IntSignedness::Unsigned) | (None, Some(unsigned), _) => (type_code::UINT8, unsigned),
+            (Some(signed), _, _) => (type_code::SINT8, signed),
+            (None, None, _) => unreachable!("value comes from an i64 or a u64"),
+        };
+        let type_code = base + NATIVE_SIZE_INDEX[native_bytes - 1];
+        self.write_int_payload(type_code, value as u64, native_bytes)
+    }
 
-        // Use f64
+    /// Write an integer type code and the low `native_bytes` bytes of `bits`
+    /// (two's complement for signed values) in one write.
+    #[inline]
+    fn write_int_payload(&mut self, type_code: u8, bits: u64, native_bytes: usize) -> Result<()> {
         let mut buf = [0u8; 9];
-        buf[0] = type_code::FLOAT64;
-        buf[1..9].copy_from_slice(&value.to_le_bytes());
-        self.write_bytes(&buf)
+        buf[0] = type_code;
+        buf[1..].copy_from_slice(&bits.to_le_bytes());
+        self.write_bytes(&buf[..=native_bytes])
+    }
+
+    /// Write a NaN or Infinity as `nan_infinity_mode` says.
+    fn write_non_finite(&mut self, value: f64) -> Result<()> {
+        match self.config.nan_infinity_mode {
+            NanInfinityMode::Reject if value.is_nan() => Err(EncodeError::NanNotAllowed),
+            NanInfinityMode::Reject => Err(EncodeError::InfinityNotAllowed),
+            NanInfinityMode::Allow => self.write_float(value),
+            NanInfinityMode::Stringify => {
+                let name = NanInfinityMode::Stringify.stringify(value).expect("value isn't finite");
+                self.write_str_unchecked(name)
+            }
+        }
+    }
+
+    /// Write a float using the optimal encoding (32 or 64 bit).
+    fn write_float(&mut self, value: f64) -> Result<()> {
+        let width = if FloatWidth::F32.holds(value) { FloatWidth::F32 } else { FloatWidth::F64 };
+        self.write_float_payload(value, width)
+    }
+
+    /// Write a float type code and payload of the given width, which must hold `value`.
+    #[allow(clippy::cast_possible_truncation)]
+    fn write_float_payload(&mut self, value: f64, width: FloatWidth) -> Result<()> {
+        count_metric!(self, values);
+        match width {
+            FloatWidth::F32 => {
+                let mut buf = [0u8; 5];
+                buf[0] = type_code::FLOAT32;
+                buf[1..5].copy_from_slice(&(value as f32).to_le_bytes());
+                self.write_bytes(&buf)
+            }
+            FloatWidth::F64 => {
+                let mut buf = [0u8; 9];
+                buf[0] = type_code::FLOAT64;
+                buf[1..9].copy_from_slice(&value.to_le_bytes());
+                self.write_bytes(&buf)
+            }
+        }
     }
 }
 
@@ -591,12 +1185,35 @@ impl<W: Write> Encoder<W> {
 
 /// Calculate the number of bytes required to store an unsigned integer (minimum 1).
 #[inline]
+/// Fill `buf` from `reader`, stopping early only at the end of its input.
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+fn check_len_hint(len: u64, len_hint: Option<u64>) -> Result<()> {
+    match len_hint {
+        Some(expected) if expected != len => {
+            Err(EncodeError::InvalidData(format!("string is {len} bytes, expected {expected}")))
+        }
+        _ => Ok(()),
+    }
+}
+
 fn required_unsigned_bytes_min1(value: u64) -> usize {
     if value == 0 {
         return 1;
     }
     let bits = 64 - value.leading_zeros() as usize;
-    (bits + 7) / 8
+    bits.div_ceil(8)
 }
 
 /// Calculate the number of bytes required to store a signed integer (minimum 1).
@@ -614,7 +1231,7 @@ fn required_signed_bytes_min1(value: i64) -> usize {
 
     // We need at least one sign bit, so subtract 1 from redundant count
     let significant_bits = 64 - redundant + 1;
-    (significant_bits + 7) / 8
+    significant_bits.div_ceil(8)
 }
 
 // =============================================================================
@@ -673,15 +1290,64 @@ pub fn float_encoding_size(value: f64) -> usize {
     }
 
     // Try integer
-    let as_int = value as i64;
-    #[allow(clippy::float_cmp)]
-    if (as_int as f64) == value {
+    if let Some(as_int) = float_as_exact_i64(value) {
         return signed_int_encoding_size(as_int);
     }
 
     float_only_encoding_size(value)
 }
 
+/// Compute the encoded size of a string of `len` bytes (without writing anything).
+pub fn str_encoding_size(len: usize) -> usize {
+    if len <= 66 {
+        1 + len // type code + payload
+    } else {
+        2 + len // markers around the payload
+    }
+}
+
+/// Compute the encoded size of a BigNumber (without writing anything).
+/// Mirrors `write_big_number_payload`.
+#[allow(clippy::cast_possible_wrap)]
+pub fn big_number_encoding_size(value: &BigNumber) -> usize {
+    let exponent = length_field_size(zigzag_encode(value.exponent));
+    if value.significand == 0 {
+        return 2 + exponent;
+    }
+    let byte_count = required_unsigned_bytes_min1(value.significand);
+    1 + exponent + length_field_size(zigzag_encode(byte_count as i64)) + byte_count
+}
+
+/// Compute the encoded size of a 128-bit integer magnitude too wide for a
+/// `BigNumber`. Mirrors `write_wide_big_number`; the sign doesn't change the size.
+#[allow(clippy::cast_possible_wrap)]
+pub(crate) fn wide_big_number_encoding_size(magnitude: u128) -> usize {
+    let (mut magnitude, mut exponent) = (magnitude, 0);
+    while magnitude != 0 && magnitude % 10 == 0 {
+        magnitude /= 10;
+        exponent += 1;
+    }
+    let byte_count = (128 - magnitude.leading_zeros() as usize).div_ceil(8);
+    1 + length_field_size(zigzag_encode(exponent)) + length_field_size(zigzag_encode(byte_count as i64)) + byte_count
+}
+
+/// Return the value as an i64 if the float holds a whole number within i64 range.
+///
+/// A plain `as i64` cast saturates, so 2^63 would come back as `i64::MAX` and
+/// round-trip through f64 as equal. The explicit bounds check prevents that.
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn float_as_exact_i64(value: f64) -> Option<i64> {
+    // -2^63 and 2^63 are both exactly representable as f64
+    const MIN: f64 = -9_223_372_036_854_775_808.0;
+    const MAX_EXCLUSIVE: f64 = 9_223_372_036_854_775_808.0;
+    if value.fract() == 0.0 && (MIN..MAX_EXCLUSIVE).contains(&value) {
+        Some(value as i64)
+    } else {
+        None
+    }
+}
+
 /// Compute the encoded size for a value that will definitely be encoded as a float.
 #[allow(clippy::cast_possible_truncation)]
 fn float_only_encoding_size(value: f64) -> usize {
@@ -699,7 +1365,7 @@ fn float_only_encoding_size(value: f64) -> usize {
 // =============================================================================
 
 /// Encode a value to a byte vector.
-pub fn to_vec<T: serde::Serialize>(value: &T) -> Result<Vec<u8>> {
+pub fn to_vec<T: serde::Serialize>(value: &T) -> crate::error::Result<Vec<u8>> {
     let mut buf = Vec::with_capacity(128);
     let mut encoder = Encoder::new(&mut buf);
     value.serialize(&mut crate::ser::Serializer::new(&mut encoder))?;
@@ -710,6 +1376,7 @@ pub fn to_vec<T: serde::Serialize>(value: &T) -> Result<Vec<u8>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Write;
 
     #[test]
     fn test_required_unsigned_bytes() {
@@ -872,4 +1539,281 @@ mod tests {
         let mut enc = Encoder::new(&mut buf);
         assert!(enc.write_f64(f64::NEG_INFINITY).is_err());
     }
+
+    #[test]
+    fn test_nan_infinity_modes() {
+        let encode = |mode: NanInfinityMode, values: &[f64]| {
+            let config = EncoderConfig { nan_infinity_mode: mode, ..EncoderConfig::default() };
+            let mut enc = Encoder::with_config(Vec::new(), config);
+            enc.begin_array().unwrap();
+            for &value in values {
+                enc.write_f64(value)?;
+            }
+            enc.end_container().unwrap();
+            enc.finish()
+        };
+        let values = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1.5];
+
+        assert_eq!(encode(NanInfinityMode::Reject, &[f64::NAN]), Err(EncodeError::NanNotAllowed));
+        assert_eq!(encode(NanInfinityMode::Reject, &[f64::NEG_INFINITY]), Err(EncodeError::InfinityNotAllowed));
+
+        // Allow: as the smallest float that keeps the bits
+        let mut expected = vec![type_code::ARRAY, type_code::FLOAT32];
+        expected.extend_from_slice(&f32::NAN.to_le_bytes());
+        expected.push(type_code::FLOAT32);
+        expected.extend_from_slice(&f32::INFINITY.to_le_bytes());
+        expected.push(type_code::FLOAT32);
+        expected.extend_from_slice(&f32::NEG_INFINITY.to_le_bytes());
+        expected.extend_from_slice(&[type_code::FLOAT32, 0x00, 0x00, 0xc0, 0x3f, type_code::CONTAINER_END]);
+        assert_eq!(encode(NanInfinityMode::Allow, &values).unwrap(), expected);
+        let payload = f64::from_bits(0x7ff8_0000_0000_0001);
+        assert_eq!(encode(NanInfinityMode::Allow, &[payload]).unwrap()[1], type_code::FLOAT64);
+
+        // Stringify: as the names a Stringify decoder gives them
+        let bytes = encode(NanInfinityMode::Stringify, &values).unwrap();
+        assert_eq!(crate::decode_value(&bytes).unwrap(), crate::bonjson!(["NaN", "Infinity", "-Infinity", 1.5]));
+
+        // Explicit widths follow the mode too
+        let config = EncoderConfig { nan_infinity_mode: NanInfinityMode::Stringify, ..EncoderConfig::default() };
+        let mut enc = Encoder::with_config(Vec::new(), config);
+        enc.write_float_with_width(f64::NAN, FloatWidth::F64).unwrap();
+        assert_eq!(enc.finish().unwrap(), [type_code::STRING0 + 3, b'N', b'a', b'N']);
+        let config = EncoderConfig { nan_infinity_mode: NanInfinityMode::Allow, ..EncoderConfig::default() };
+        let mut enc = Encoder::with_config(Vec::new(), config);
+        enc.write_float_with_width(f64::INFINITY, FloatWidth::F64).unwrap();
+        assert_eq!(enc.finish().unwrap()[0], type_code::FLOAT64);
+        let mut enc = Encoder::new(Vec::new());
+        assert_eq!(enc.write_float_with_width(f64::INFINITY, FloatWidth::F32), Err(EncodeError::InfinityNotAllowed));
+    }
+
+    #[test]
+    fn test_small_values_use_one_write() {
+        struct WriteCounter(Vec<Vec<u8>>);
+        impl Write for WriteCounter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.push(buf.to_vec());
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut enc = Encoder::new(WriteCounter(Vec::new()));
+        enc.begin_array().unwrap();
+        enc.write_i64(-1000).unwrap();
+        enc.write_u64(u64::MAX).unwrap();
+        enc.write_f64(1.5).unwrap();
+        enc.write_str("twenty-four byte string!").unwrap();
+        enc.write_big_number(BigNumber::new(-1, u64::MAX, -300)).unwrap();
+        enc.write_typed_array_raw(type_code::TYPED_ARRAY_FLOAT32, 2, &[0; 8]).unwrap();
+        enc.begin_record_instance(300).unwrap();
+        enc.end_container().unwrap();
+        enc.end_container().unwrap();
+        let writes = enc.finish().unwrap().0;
+        // One write per value and container marker
+        assert_eq!(writes.len(), 10);
+        assert_eq!(writes[4].len(), 25);
+
+        // Larger payloads are written after their prefix
+        let mut enc = Encoder::new(WriteCounter(Vec::new()));
+        enc.write_str(&"x".repeat(40)).unwrap();
+        assert_eq!(enc.finish().unwrap().0.len(), 2);
+    }
+
+    #[test]
+    fn test_large_payloads_use_vectored_writes() {
+        // Takes at most 100 bytes per call, across however many slices
+        struct VectoredWriter {
+            out: Vec<u8>,
+            calls: usize,
+        }
+        impl Write for VectoredWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.write_vectored(&[IoSlice::new(buf)])
+            }
+            fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> std::io::Result<usize> {
+                self.calls += 1;
+                let mut budget = 100;
+                for buf in bufs {
+                    let n = buf.len().min(budget);
+                    self.out.extend_from_slice(&buf[..n]);
+                    budget -= n;
+                }
+                Ok(100 - budget)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let long = "y".repeat(150);
+        let mut enc = Encoder::new(VectoredWriter { out: Vec::new(), calls: 0 });
+        enc.write_str(&long).unwrap();
+        enc.write_typed_array_raw(type_code::TYPED_ARRAY_UINT8, 50, &[7; 50]).unwrap();
+        let writer = enc.finish().unwrap();
+        assert_eq!(writer.calls, 3);
+
+        let mut expected = Vec::new();
+        let mut enc = Encoder::new(&mut expected);
+        enc.write_str(&long).unwrap();
+        enc.write_typed_array_raw(type_code::TYPED_ARRAY_UINT8, 50, &[7; 50]).unwrap();
+        enc.finish().unwrap();
+        assert_eq!(writer.out, expected);
+    }
+
+    #[test]
+    fn test_sized_containers() {
+        // Same bytes as the unsized forms
+        let mut buf = Vec::new();
+        let mut enc = Encoder::new(&mut buf);
+        enc.begin_object_sized(2).unwrap();
+        enc.write_str("a").unwrap();
+        enc.begin_array_sized(2).unwrap();
+        enc.write_i64(1).unwrap();
+        enc.begin_array().unwrap();
+        enc.end_container().unwrap();
+        enc.end_container().unwrap();
+        enc.write_str("b").unwrap();
+        enc.write_null().unwrap();
+        enc.end_container().unwrap();
+        enc.finish().unwrap();
+        assert_eq!(buf, vec![0xb8, 0x66, b'a', 0xb7, 0x01, 0xb7, 0xb6, 0xb6, 0x66, b'b', 0xb3, 0xb6]);
+
+        let mut buf = Vec::new();
+        let mut enc = Encoder::new(&mut buf);
+        enc.begin_array_sized(2).unwrap();
+        enc.write_bool(true).unwrap();
+        assert_eq!(enc.end_container().unwrap_err(), EncodeError::ContainerSizeMismatch { expected: 2, written: 1 });
+
+        let mut buf = Vec::new();
+        let mut enc = Encoder::new(&mut buf);
+        enc.begin_object_sized(0).unwrap();
+        enc.write_str("k").unwrap();
+        enc.write_i64(5).unwrap();
+        assert_eq!(enc.end_container().unwrap_err(), EncodeError::ContainerSizeMismatch { expected: 0, written: 1 });
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let config = EncoderConfig { duplicate_keys: DuplicateKeys::Error, ..Default::default() };
+        let mut buf = Vec::new();
+        let mut enc = Encoder::with_config(&mut buf, config.clone());
+        enc.begin_object().unwrap();
+        enc.write_str("a").unwrap();
+        // Keys are tracked per object, and string values aren't keys
+        enc.begin_object().unwrap();
+        enc.write_str("a").unwrap();
+        enc.write_str("a").unwrap();
+        enc.end_container().unwrap();
+        enc.write_str("b").unwrap();
+        enc.begin_array().unwrap();
+        enc.write_str("b").unwrap();
+        enc.end_container().unwrap();
+        assert_eq!(enc.write_str("a").unwrap_err(), EncodeError::DuplicateKey);
+
+        let mut enc = Encoder::with_config(Vec::new(), config);
+        assert_eq!(enc.write_record_definition(&["x", "y", "x"]).unwrap_err(), EncodeError::DuplicateKey);
+
+        // Allowed by default
+        let mut enc = Encoder::new(Vec::new());
+        enc.begin_object().unwrap();
+        for _ in 0..2 {
+            enc.write_str("a").unwrap();
+            enc.write_null().unwrap();
+        }
+        enc.end_container().unwrap();
+        enc.write_record_definition(&["x", "x"]).unwrap();
+    }
+
+    #[test]
+    fn test_encoding_profiles() {
+        let encode = |profile: EncodingProfile, values: &[i128]| {
+            let mut enc = Encoder::with_config(Vec::new(), EncoderConfig::default().profile(profile));
+            for &value in values {
+                match i64::try_from(value) {
+                    Ok(v) => enc.write_i64(v).unwrap(),
+                    Err(_) => enc.write_u64(u64::try_from(value).unwrap()).unwrap(),
+                }
+            }
+            enc.finish().unwrap()
+        };
+
+        // Sized ints only, at least 4 bytes wide, as signed as possible
+        let sint32 = EncodingProfile::fixed(4, IntSignedness::Signed);
+        assert_eq!(encode(sint32, &[5]), [type_code::SINT32, 5, 0, 0, 0]);
+        assert_eq!(encode(sint32, &[-1]), [type_code::SINT32, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(encode(sint32, &[1 << 40]), [type_code::SINT64, 0, 0, 0, 0, 0, 1, 0, 0]);
+        assert_eq!(encode(sint32, &[i128::from(u64::MAX)]), [&[type_code::UINT64][..], &[0xff; 8]].concat());
+
+        // Unsigned wherever the value allows it; odd widths round up
+        let unsigned = EncodingProfile::fixed(3, IntSignedness::Unsigned);
+        assert_eq!(encode(unsigned, &[5, -5]), [type_code::UINT32, 5, 0, 0, 0, type_code::SINT32, 0xfb, 0xff, 0xff, 0xff]);
+
+        // A lower small int cutoff; beyond it the minimal sized encoding
+        let cutoff = EncodingProfile { small_int_max: Some(9), ..EncodingProfile::CANONICAL };
+        assert_eq!(encode(cutoff, &[9, 10, 200]), [9, type_code::SINT8, 10, type_code::UINT8, 200]);
+        let max = EncodingProfile { small_int_max: Some(u8::MAX), ..EncodingProfile::CANONICAL };
+        assert_eq!(encode(max, &[100, 101]), [100, type_code::SINT8, 101]);
+
+        // Minimal signedness at a minimum width ties to signed
+        let wide = EncodingProfile { min_int_bytes: 2, ..EncodingProfile::CANONICAL };
+        assert_eq!(encode(wide, &[200, 40_000]), [type_code::SINT16, 200, 0, type_code::UINT16, 0x40, 0x9c]);
+
+        // The canonical profile matches the encoding-size helpers
+        for value in [0, 100, 101, 127, 128, 255, 256, 32_768, 65_535, 65_536, -1, -128, -129, i128::from(i64::MIN), i128::from(u64::MAX)] {
+            let expected = i64::try_from(value)
+                .map_or_else(|_| unsigned_int_encoding_size(u64::MAX), signed_int_encoding_size);
+            assert_eq!(encode(EncodingProfile::default(), &[value]).len(), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn test_write_str_from_reader() {
+        /// Hands out at most 5 bytes per read, splitting multi-byte characters.
+        struct Trickle<'a>(&'a [u8]);
+
+        impl std::io::Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = buf.len().min(5).min(self.0.len());
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let streamed = |bytes: &[u8], len_hint: Option<u64>| {
+            let mut encoder = Encoder::new(Vec::new());
+            encoder.write_str_from_reader(Trickle(bytes), len_hint).map(|()| encoder.finish().unwrap())
+        };
+        let expected = |text: &str| {
+            let mut encoder = Encoder::new(Vec::new());
+            encoder.write_str(text).unwrap();
+            encoder.finish().unwrap()
+        };
+
+        let long = "één ☃ 𝄞 ".repeat(20_000);
+        for text in ["", "short", &"x".repeat(66), &"x".repeat(67), &long] {
+            assert_eq!(streamed(text.as_bytes(), None).unwrap(), expected(text), "{}", text.len());
+        }
+        assert_eq!(streamed(long.as_bytes(), Some(long.len() as u64)).unwrap(), expected(&long));
+        assert!(matches!(streamed(long.as_bytes(), Some(5)), Err(EncodeError::InvalidData(_))));
+        assert!(matches!(streamed(b"abc", Some(4)), Err(EncodeError::InvalidData(_))));
+
+        let mut bad = long.as_bytes().to_vec();
+        bad[70_000] = 0xff;
+        assert_eq!(streamed(&bad, None), Err(EncodeError::InvalidUtf8));
+        bad.truncate(100);
+        bad.push(0xe2);
+        assert_eq!(streamed(&bad, None), Err(EncodeError::InvalidUtf8));
+        assert_eq!(streamed(&[b'a'; 100].iter().chain(&[0]).copied().collect::<Vec<_>>(), None), Err(EncodeError::NulCharacter));
+
+        // As a key, with the duplicate check
+        let config = EncoderConfig { duplicate_keys: DuplicateKeys::Error, ..Default::default() };
+        let mut encoder = Encoder::with_config(Vec::new(), config);
+        encoder.begin_object().unwrap();
+        encoder.write_str_from_reader(Trickle(b"k"), Some(1)).unwrap();
+        encoder.write_str_from_reader(Trickle(long.as_bytes()), None).unwrap();
+        assert_eq!(encoder.write_str_from_reader(Trickle(b"k"), None), Err(EncodeError::DuplicateKey));
+    }
 }
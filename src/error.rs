@@ -1,21 +1,31 @@
-// ABOUTME: Error types for BONJSON encoding and decoding.
-// ABOUTME: Error variants map to the standardized error types in the BONJSON test spec.
+// ABOUTME: Error types for BONJSON encoding and decoding: DecodeError and EncodeError for the
+// ABOUTME: low-level Decoder and Encoder, and Error, which both convert into, for everything else.
 
+use crate::jsonpath::JsonPath;
+use crate::types::type_code;
 use std::fmt;
 
 /// The result type for BONJSON operations.
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// Errors that can occur during BONJSON encoding or decoding.
+///
+/// Returned by the convenience functions (`to_vec`, `from_slice`,
+/// `decode_value`, ...) and by serde. The low-level [`Decoder`](crate::Decoder)
+/// and [`Encoder`](crate::Encoder) return the narrower [`DecodeError`] and
+/// [`EncodeError`], which convert into this with `?`, keeping their variant
+/// names, messages and [`error_type`](Error::error_type)s.
+/// Variants map to the standardized error types in the BONJSON test spec.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Error {
     /// Unexpected end of input data.
     /// Test spec: "truncated"
     Truncated,
 
-    /// Unconsumed bytes after decoding a complete document.
+    /// Unconsumed bytes after decoding a complete document, starting at the given offset.
     /// Test spec: "trailing_bytes"
-    TrailingBytes,
+    TrailingBytes(usize),
 
     /// Unrecognized or reserved type code encountered.
     /// Test spec: "invalid_type_code"
@@ -49,6 +59,21 @@ pub enum Error {
     /// Test spec: "invalid_data"
     InvalidData(String),
 
+    /// Record instance value count doesn't match its definition's key count.
+    /// Test spec: "invalid_data"
+    RecordMismatch {
+        /// Index of the record definition
+        definition: usize,
+        /// Number of keys in the definition
+        keys: usize,
+        /// Number of values in the instance
+        values: usize,
+    },
+
+    /// Keys not declared by the target struct (with `deny_unknown_fields_globally`).
+    /// Holds the path of every unknown key in the document, e.g. `$.items[2].extra`.
+    UnknownFields(Vec<String>),
+
     /// Non-string used as object key.
     /// Test spec: "invalid_object_key"
     InvalidObjectKey,
@@ -73,6 +98,19 @@ pub enum Error {
     /// Test spec: "max_document_size_exceeded"
     MaxDocumentSizeExceeded,
 
+    /// Decoding would allocate more memory than `max_total_allocated_bytes` allows.
+    MaxTotalAllocatedBytesExceeded,
+
+    /// Decoding was aborted because the configured `cancel_token` was set.
+    Cancelled,
+
+    /// Decoding was aborted because the configured `deadline` passed.
+    DeadlineExceeded,
+
+    /// The value needs a format feature that the target capabilities exclude.
+    /// Holds the feature's name (e.g. "long_strings").
+    UnsupportedCapability(&'static str),
+
     /// BigNumber exponent exceeds configured limit.
     /// Test spec: "max_bignumber_exponent_exceeded"
     MaxBignumberExponentExceeded,
@@ -81,15 +119,69 @@ pub enum Error {
     /// Test spec: "max_bignumber_magnitude_exceeded"
     MaxBignumberMagnitudeExceeded,
 
+    /// Typed array payload (count times element size) is larger than the configured limit.
+    MaxTypedArrayBytesExceeded,
+
+    /// Document declares more record definitions than the configured limit.
+    MaxRecordDefinitionsExceeded,
+
+    /// Record definition has more keys than the configured limit.
+    MaxRecordKeysExceeded,
+
+    /// Record definition key is longer than the configured limit.
+    MaxRecordKeyLengthExceeded,
+
     /// Tried to close more containers than were opened.
     UnbalancedContainers,
 
     /// Expected an object key (string) but got a different type.
     ExpectedObjectKey,
 
+    /// A map key serialized as something that can't be an object key: not a
+    /// string, integer, char or unit variant. Holds the key's serde type
+    /// (e.g. "sequence" for a `Vec<u8>`) and the struct field holding the
+    /// map, if it's in one. Its `error_type()` is that of `ExpectedObjectKey`.
+    UnsupportedMapKey { key_type: &'static str, field: Option<&'static str> },
+
     /// Container ended while expecting an object value.
     ExpectedObjectValue,
 
+    /// A container begun with a declared size was closed after a different
+    /// number of elements (object entries count as one each).
+    ContainerSizeMismatch { expected: usize, written: usize },
+
+    /// Malformed JSONPath query.
+    InvalidQuery(String),
+
+    /// [`Value::deep_merge`](crate::Value::deep_merge) found conflicting
+    /// values under [`ConflictPolicy::Error`](crate::ConflictPolicy::Error).
+    /// Holds the path of the conflict.
+    MergeConflict(String),
+
+    /// A document's checksum trailer doesn't match its contents, so the
+    /// document was corrupted after it was written.
+    ChecksumMismatch {
+        /// The CRC-32 recorded in the trailer
+        expected: u32,
+        /// The CRC-32 of the document as read
+        actual: u32,
+    },
+
+    /// A document read with verification has no checksum trailer.
+    MissingChecksum,
+
+    /// An error from a typed conversion, with the path of the member where it
+    /// happened; see [`Value::try_into_struct`](crate::Value::try_into_struct)
+    /// and [`from_slice_traced`](crate::from_slice_traced).
+    AtPath {
+        /// Where in the value the error happened
+        path: JsonPath,
+        /// The input offset decoding had reached, when decoding bytes
+        offset: Option<usize>,
+        /// The error itself
+        error: Box<Error>,
+    },
+
     /// IO error during encoding.
     Io(String),
 
@@ -102,7 +194,7 @@ impl Error {
     #[must_use] pub fn error_type(&self) -> &'static str {
         match self {
             Error::Truncated => "truncated",
-            Error::TrailingBytes => "trailing_bytes",
+            Error::TrailingBytes(_) => "trailing_bytes",
             Error::InvalidTypeCode(_) => "invalid_type_code",
             Error::InvalidUtf8 => "invalid_utf8",
             Error::NulCharacter => "nul_character",
@@ -111,17 +203,33 @@ impl Error {
             Error::NanNotAllowed => "invalid_data",
             Error::InfinityNotAllowed => "invalid_data",
             Error::InvalidData(_) => "invalid_data",
+            Error::RecordMismatch { .. } => "invalid_data",
+            Error::UnknownFields(_) => "unknown_fields",
             Error::InvalidObjectKey => "invalid_object_key",
             Error::ValueOutOfRange => "value_out_of_range",
             Error::MaxDepthExceeded => "max_depth_exceeded",
             Error::MaxStringLengthExceeded => "max_string_length_exceeded",
             Error::MaxContainerSizeExceeded => "max_container_size_exceeded",
             Error::MaxDocumentSizeExceeded => "max_document_size_exceeded",
+            Error::MaxTotalAllocatedBytesExceeded => "max_total_allocated_bytes_exceeded",
+            Error::Cancelled => "cancelled",
+            Error::DeadlineExceeded => "deadline_exceeded",
+            Error::UnsupportedCapability(_) => "unsupported_capability",
             Error::MaxBignumberExponentExceeded => "max_bignumber_exponent_exceeded",
             Error::MaxBignumberMagnitudeExceeded => "max_bignumber_magnitude_exceeded",
+            Error::MaxTypedArrayBytesExceeded => "max_typed_array_bytes_exceeded",
+            Error::MaxRecordDefinitionsExceeded => "max_record_definitions_exceeded",
+            Error::MaxRecordKeysExceeded => "max_record_keys_exceeded",
+            Error::MaxRecordKeyLengthExceeded => "max_record_key_length_exceeded",
             Error::UnbalancedContainers => "unbalanced_containers",
-            Error::ExpectedObjectKey => "expected_object_key",
+            Error::ExpectedObjectKey | Error::UnsupportedMapKey { .. } => "expected_object_key",
             Error::ExpectedObjectValue => "expected_object_value",
+            Error::ContainerSizeMismatch { .. } => "container_size_mismatch",
+            Error::InvalidQuery(_) => "invalid_query",
+            Error::MergeConflict(_) => "merge_conflict",
+            Error::ChecksumMismatch { .. } => "checksum_mismatch",
+            Error::MissingChecksum => "missing_checksum",
+            Error::AtPath { error, .. } => error.error_type(),
             Error::Io(_) => "io_error",
             Error::Custom(_) => "custom",
         }
@@ -132,7 +240,7 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::Truncated => write!(f, "unexpected end of input"),
-            Error::TrailingBytes => write!(f, "trailing bytes after document"),
+            Error::TrailingBytes(offset) => write!(f, "trailing bytes after document at offset {offset}"),
             Error::InvalidTypeCode(code) => write!(f, "invalid type code: 0x{code:02x}"),
             Error::InvalidUtf8 => write!(f, "invalid UTF-8 sequence"),
             Error::NulCharacter => write!(f, "NUL character in string"),
@@ -141,17 +249,52 @@ impl fmt::Display for Error {
             Error::NanNotAllowed => write!(f, "NaN is not allowed"),
             Error::InfinityNotAllowed => write!(f, "Infinity is not allowed"),
             Error::InvalidData(msg) => write!(f, "invalid data: {msg}"),
+            Error::RecordMismatch { definition, keys, values } => write!(
+                f,
+                "record instance of definition {definition} has {values} values for {keys} keys"
+            ),
+            Error::UnknownFields(paths) => write!(f, "unknown fields: {}", paths.join(", ")),
             Error::InvalidObjectKey => write!(f, "non-string object key"),
             Error::ValueOutOfRange => write!(f, "value out of range"),
             Error::MaxDepthExceeded => write!(f, "maximum container depth exceeded"),
             Error::MaxStringLengthExceeded => write!(f, "maximum string length exceeded"),
             Error::MaxContainerSizeExceeded => write!(f, "maximum container size exceeded"),
             Error::MaxDocumentSizeExceeded => write!(f, "maximum document size exceeded"),
+            Error::MaxTotalAllocatedBytesExceeded => write!(f, "maximum total allocated bytes exceeded"),
+            Error::Cancelled => write!(f, "decoding was cancelled"),
+            Error::DeadlineExceeded => write!(f, "decoding deadline exceeded"),
+            Error::UnsupportedCapability(feature) => {
+                write!(f, "{feature} not supported by the target capabilities")
+            }
             Error::MaxBignumberExponentExceeded => write!(f, "BigNumber exponent exceeds limit"),
             Error::MaxBignumberMagnitudeExceeded => write!(f, "BigNumber magnitude exceeds limit"),
+            Error::MaxTypedArrayBytesExceeded => write!(f, "maximum typed array size exceeded"),
+            Error::MaxRecordDefinitionsExceeded => write!(f, "maximum record definitions exceeded"),
+            Error::MaxRecordKeysExceeded => write!(f, "maximum record definition keys exceeded"),
+            Error::MaxRecordKeyLengthExceeded => write!(f, "maximum record definition key length exceeded"),
             Error::UnbalancedContainers => write!(f, "tried to close too many containers"),
             Error::ExpectedObjectKey => write!(f, "expected object key (string)"),
+            Error::UnsupportedMapKey { key_type, field } => {
+                write!(f, "map key of type {key_type} can't be an object key")?;
+                if let Some(field) = field {
+                    write!(f, " (in field `{field}`)")?;
+                }
+                write!(f, "; SerializerConfig::stringify_map_keys writes such keys as text")
+            }
             Error::ExpectedObjectValue => write!(f, "expected object value"),
+            Error::ContainerSizeMismatch { expected, written } => {
+                write!(f, "container declared with {expected} elements was closed after {written}")
+            }
+            Error::InvalidQuery(msg) => write!(f, "invalid query: {msg}"),
+            Error::MergeConflict(path) => write!(f, "conflicting values at {path}"),
+            Error::ChecksumMismatch { expected, actual } => {
+                write!(f, "checksum mismatch: trailer has {expected:08x}, document has {actual:08x}")
+            }
+            Error::MissingChecksum => write!(f, "missing checksum trailer"),
+            Error::AtPath { path, offset: None, error } => write!(f, "{error} at {path}"),
+            Error::AtPath { path, offset: Some(offset), error } => {
+                write!(f, "{error} at {path} (offset {offset})")
+            }
             Error::Io(msg) => write!(f, "I/O error: {msg}"),
             Error::Custom(msg) => write!(f, "{msg}"),
         }
@@ -183,3 +326,272 @@ impl From<std::str::Utf8Error> for Error {
         Error::InvalidUtf8
     }
 }
+
+/// For generic conversions whose inner conversion can't fail, such as
+/// `Vec<Value>::try_from(value)`.
+impl From<std::convert::Infallible> for Error {
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
+    }
+}
+
+/// Errors from the low-level [`Decoder`](crate::Decoder): malformed input,
+/// exceeded limits, and calls that don't match the input.
+///
+/// Converts into [`Error`] (same variant names; the last three become
+/// `Error::Custom` with the same message), and has the same `Display` and
+/// [`error_type`](DecodeError::error_type) as the `Error` it converts to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// Unexpected end of input data.
+    Truncated,
+    /// Unconsumed bytes after decoding a complete document, starting at the given offset.
+    TrailingBytes(usize),
+    /// Unrecognized or reserved type code encountered.
+    InvalidTypeCode(u8),
+    /// Invalid UTF-8 byte sequence in string.
+    InvalidUtf8,
+    /// NUL (0x00) byte in string.
+    NulCharacter,
+    /// Duplicate key in object or record definition.
+    DuplicateKey,
+    /// Missing container end marker.
+    UnclosedContainer,
+    /// NaN value encountered where not allowed.
+    NanNotAllowed,
+    /// Infinity value encountered where not allowed.
+    InfinityNotAllowed,
+    /// Generic invalid data (e.g., invalid BigNumber).
+    InvalidData(String),
+    /// Record instance value count doesn't match its definition's key count.
+    RecordMismatch {
+        /// Index of the record definition
+        definition: usize,
+        /// Number of keys in the definition
+        keys: usize,
+        /// Number of values in the instance
+        values: usize,
+    },
+    /// Value exceeds allowed range.
+    ValueOutOfRange,
+    /// Container nesting too deep.
+    MaxDepthExceeded,
+    /// String exceeds length limit.
+    MaxStringLengthExceeded,
+    /// Container has too many elements.
+    MaxContainerSizeExceeded,
+    /// Document exceeds size limit.
+    MaxDocumentSizeExceeded,
+    /// Decoding would allocate more memory than `max_total_allocated_bytes` allows.
+    MaxTotalAllocatedBytesExceeded,
+    /// Decoding was aborted because the configured `cancel_token` was set.
+    Cancelled,
+    /// Decoding was aborted because the configured `deadline` passed.
+    DeadlineExceeded,
+    /// BigNumber exponent exceeds configured limit.
+    MaxBignumberExponentExceeded,
+    /// BigNumber magnitude exceeds configured limit.
+    MaxBignumberMagnitudeExceeded,
+    /// Typed array payload (count times element size) is larger than the configured limit.
+    MaxTypedArrayBytesExceeded,
+    /// Document declares more record definitions than the configured limit.
+    MaxRecordDefinitionsExceeded,
+    /// Record definition has more keys than the configured limit.
+    MaxRecordKeysExceeded,
+    /// Record definition key is longer than the configured limit.
+    MaxRecordKeyLengthExceeded,
+    /// Tried to close more containers than were opened.
+    UnbalancedContainers,
+    /// Expected an object key (string) but got a different type.
+    ExpectedObjectKey,
+    /// Container ended while expecting an object value.
+    ExpectedObjectValue,
+    /// A typed read (`decode_i64_direct`, `read_blob`, ...) found another kind of value.
+    UnexpectedType {
+        /// What the read expects, e.g. "integer" or "typed array"
+        expected: &'static str,
+        /// The type code found instead
+        found: u8,
+    },
+    /// [`read_typed_array_into`](crate::Decoder::read_typed_array_into) found
+    /// another element type than the buffer's.
+    TypedArrayTypeMismatch {
+        /// The typed array type code of the buffer's element type
+        expected: u8,
+        /// The typed array type code in the input
+        found: u8,
+    },
+    /// [`read_typed_array_into`](crate::Decoder::read_typed_array_into) found
+    /// more elements than the buffer holds.
+    TypedArrayTooLarge {
+        /// Elements in the typed array
+        count: usize,
+        /// Elements the buffer holds
+        capacity: usize,
+    },
+}
+
+impl DecodeError {
+    /// Returns the standardized error type name for test matching, as
+    /// [`Error::error_type`] does for the converted error.
+    #[must_use]
+    pub fn error_type(&self) -> &'static str {
+        Error::from(self.clone()).error_type()
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Error::from(self.clone()).fmt(f)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<DecodeError> for Error {
+    fn from(err: DecodeError) -> Self {
+        match err {
+            DecodeError::Truncated => Error::Truncated,
+            DecodeError::TrailingBytes(offset) => Error::TrailingBytes(offset),
+            DecodeError::InvalidTypeCode(code) => Error::InvalidTypeCode(code),
+            DecodeError::InvalidUtf8 => Error::InvalidUtf8,
+            DecodeError::NulCharacter => Error::NulCharacter,
+            DecodeError::DuplicateKey => Error::DuplicateKey,
+            DecodeError::UnclosedContainer => Error::UnclosedContainer,
+            DecodeError::NanNotAllowed => Error::NanNotAllowed,
+            DecodeError::InfinityNotAllowed => Error::InfinityNotAllowed,
+            DecodeError::InvalidData(msg) => Error::InvalidData(msg),
+            DecodeError::RecordMismatch { definition, keys, values } => Error::RecordMismatch { definition, keys, values },
+            DecodeError::ValueOutOfRange => Error::ValueOutOfRange,
+            DecodeError::MaxDepthExceeded => Error::MaxDepthExceeded,
+            DecodeError::MaxStringLengthExceeded => Error::MaxStringLengthExceeded,
+            DecodeError::MaxContainerSizeExceeded => Error::MaxContainerSizeExceeded,
+            DecodeError::MaxDocumentSizeExceeded => Error::MaxDocumentSizeExceeded,
+            DecodeError::MaxTotalAllocatedBytesExceeded => Error::MaxTotalAllocatedBytesExceeded,
+            DecodeError::Cancelled => Error::Cancelled,
+            DecodeError::DeadlineExceeded => Error::DeadlineExceeded,
+            DecodeError::MaxBignumberExponentExceeded => Error::MaxBignumberExponentExceeded,
+            DecodeError::MaxBignumberMagnitudeExceeded => Error::MaxBignumberMagnitudeExceeded,
+            DecodeError::MaxTypedArrayBytesExceeded => Error::MaxTypedArrayBytesExceeded,
+            DecodeError::MaxRecordDefinitionsExceeded => Error::MaxRecordDefinitionsExceeded,
+            DecodeError::MaxRecordKeysExceeded => Error::MaxRecordKeysExceeded,
+            DecodeError::MaxRecordKeyLengthExceeded => Error::MaxRecordKeyLengthExceeded,
+            DecodeError::UnbalancedContainers => Error::UnbalancedContainers,
+            DecodeError::ExpectedObjectKey => Error::ExpectedObjectKey,
+            DecodeError::ExpectedObjectValue => Error::ExpectedObjectValue,
+            DecodeError::UnexpectedType { expected, found } => {
+                Error::Custom(format!("expected {expected}, got 0x{found:02x}"))
+            }
+            DecodeError::TypedArrayTypeMismatch { expected, found } => Error::Custom(format!(
+                "expected typed array of {}, got {}",
+                type_code::name(type_code::typed_array_scalar_code(expected)),
+                type_code::name(type_code::typed_array_scalar_code(found)),
+            )),
+            DecodeError::TypedArrayTooLarge { count, capacity } => {
+                Error::Custom(format!("typed array has {count} elements but the buffer holds {capacity}"))
+            }
+        }
+    }
+}
+
+impl From<std::str::Utf8Error> for DecodeError {
+    fn from(_: std::str::Utf8Error) -> Self {
+        DecodeError::InvalidUtf8
+    }
+}
+
+/// Errors from the low-level [`Encoder`](crate::Encoder): values the
+/// configuration doesn't allow, calls out of order, and write failures.
+///
+/// Converts into the [`Error`] variant of the same name, and has the same
+/// `Display` and [`error_type`](EncodeError::error_type) as that `Error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// NaN value under `NanInfinityMode::Reject`.
+    NanNotAllowed,
+    /// Infinity value under `NanInfinityMode::Reject`.
+    InfinityNotAllowed,
+    /// Duplicate key in an object, under `DuplicateKeys::Error`.
+    DuplicateKey,
+    /// Invalid UTF-8 in string bytes written through the raw string API.
+    InvalidUtf8,
+    /// NUL (0x00) byte in a string, where the configuration rejects it.
+    NulCharacter,
+    /// A value that can't be encoded as given (e.g., an invalid BigNumber).
+    InvalidData(String),
+    /// Value exceeds the range its encoding allows.
+    ValueOutOfRange,
+    /// An object key was expected, but a value was written.
+    ExpectedObjectKey,
+    /// An object was closed after a key without its value.
+    ExpectedObjectValue,
+    /// Tried to close more containers than were opened.
+    UnbalancedContainers,
+    /// Finished with containers still open.
+    UnclosedContainer,
+    /// A container begun with a declared size was closed after a different
+    /// number of elements (object entries count as one each).
+    ContainerSizeMismatch {
+        /// The declared size
+        expected: usize,
+        /// The elements written
+        written: usize,
+    },
+    /// The value needs a format feature that the target capabilities exclude.
+    /// Holds the feature's name (e.g. "long_strings").
+    UnsupportedCapability(&'static str),
+    /// The output failed.
+    Io(String),
+}
+
+impl EncodeError {
+    /// Returns the standardized error type name for test matching, as
+    /// [`Error::error_type`] does for the converted error.
+    #[must_use]
+    pub fn error_type(&self) -> &'static str {
+        Error::from(self.clone()).error_type()
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Error::from(self.clone()).fmt(f)
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+impl From<EncodeError> for Error {
+    fn from(err: EncodeError) -> Self {
+        match err {
+            EncodeError::NanNotAllowed => Error::NanNotAllowed,
+            EncodeError::InfinityNotAllowed => Error::InfinityNotAllowed,
+            EncodeError::DuplicateKey => Error::DuplicateKey,
+            EncodeError::InvalidUtf8 => Error::InvalidUtf8,
+            EncodeError::NulCharacter => Error::NulCharacter,
+            EncodeError::InvalidData(msg) => Error::InvalidData(msg),
+            EncodeError::ValueOutOfRange => Error::ValueOutOfRange,
+            EncodeError::ExpectedObjectKey => Error::ExpectedObjectKey,
+            EncodeError::ExpectedObjectValue => Error::ExpectedObjectValue,
+            EncodeError::UnbalancedContainers => Error::UnbalancedContainers,
+            EncodeError::UnclosedContainer => Error::UnclosedContainer,
+            EncodeError::ContainerSizeMismatch { expected, written } => Error::ContainerSizeMismatch { expected, written },
+            EncodeError::UnsupportedCapability(feature) => Error::UnsupportedCapability(feature),
+            EncodeError::Io(msg) => Error::Io(msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for EncodeError {
+    fn from(err: std::io::Error) -> Self {
+        EncodeError::Io(err.to_string())
+    }
+}
+
+impl From<std::str::Utf8Error> for EncodeError {
+    fn from(_: std::str::Utf8Error) -> Self {
+        EncodeError::InvalidUtf8
+    }
+}
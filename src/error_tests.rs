@@ -1,17 +1,88 @@
 // ABOUTME: Unit tests for the BONJSON error module.
 // ABOUTME: Tests error type identification and display formatting.
 
-use crate::error::Error;
+use crate::error::{DecodeError, EncodeError, Error};
 
 #[test]
 fn test_error_types() {
     assert_eq!(Error::Truncated.error_type(), "truncated");
     assert_eq!(Error::InvalidTypeCode(0xbb).error_type(), "invalid_type_code");
     assert_eq!(Error::NulCharacter.error_type(), "nul_character");
+    assert_eq!(
+        Error::MaxTotalAllocatedBytesExceeded.error_type(),
+        "max_total_allocated_bytes_exceeded"
+    );
+    assert_eq!(Error::Cancelled.error_type(), "cancelled");
+    assert_eq!(Error::DeadlineExceeded.error_type(), "deadline_exceeded");
 }
 
 #[test]
 fn test_error_display() {
     let err = Error::InvalidTypeCode(0xbb);
     assert_eq!(format!("{}", err), "invalid type code: 0xbb");
+
+    let err = Error::TrailingBytes(17);
+    assert_eq!(err.error_type(), "trailing_bytes");
+    assert_eq!(format!("{}", err), "trailing bytes after document at offset 17");
+}
+
+#[test]
+fn test_record_mismatch_display() {
+    let err = Error::RecordMismatch { definition: 3, keys: 4, values: 2 };
+    assert_eq!(err.error_type(), "invalid_data");
+    assert_eq!(format!("{}", err), "record instance of definition 3 has 2 values for 4 keys");
+}
+
+#[test]
+fn test_container_size_mismatch_display() {
+    let err = Error::ContainerSizeMismatch { expected: 3, written: 2 };
+    assert_eq!(err.error_type(), "container_size_mismatch");
+    assert_eq!(format!("{}", err), "container declared with 3 elements was closed after 2");
+}
+
+#[test]
+fn test_merge_conflict_display() {
+    let err = Error::MergeConflict("$.server.port".into());
+    assert_eq!(err.error_type(), "merge_conflict");
+    assert_eq!(format!("{}", err), "conflicting values at $.server.port");
+}
+
+#[test]
+fn test_unknown_fields_display() {
+    let err = Error::UnknownFields(vec!["$.extra".into(), "$.items[1].color".into()]);
+    assert_eq!(err.error_type(), "unknown_fields");
+    assert_eq!(format!("{}", err), "unknown fields: $.extra, $.items[1].color");
+}
+
+#[test]
+fn test_checksum_display() {
+    let err = Error::ChecksumMismatch { expected: 0xCBF4_3926, actual: 0x0000_00FF };
+    assert_eq!(err.error_type(), "checksum_mismatch");
+    assert_eq!(format!("{}", err), "checksum mismatch: trailer has cbf43926, document has 000000ff");
+    assert_eq!(Error::MissingChecksum.error_type(), "missing_checksum");
+}
+
+#[test]
+fn test_decode_error_converts_to_error() {
+    let err = DecodeError::TrailingBytes(17);
+    assert_eq!(err.error_type(), "trailing_bytes");
+    assert_eq!(err.to_string(), "trailing bytes after document at offset 17");
+    assert_eq!(Error::from(err), Error::TrailingBytes(17));
+
+    let err = DecodeError::UnexpectedType { expected: "array", found: 0xb8 };
+    assert_eq!(err.error_type(), "custom");
+    assert_eq!(err.to_string(), "expected array, got 0xb8");
+    assert_eq!(Error::from(err), Error::Custom("expected array, got 0xb8".into()));
+}
+
+#[test]
+fn test_encode_error_converts_to_error() {
+    let err = EncodeError::ContainerSizeMismatch { expected: 3, written: 2 };
+    assert_eq!(err.error_type(), "container_size_mismatch");
+    assert_eq!(err.to_string(), "container declared with 3 elements was closed after 2");
+    assert_eq!(Error::from(err), Error::ContainerSizeMismatch { expected: 3, written: 2 });
+
+    let err = EncodeError::from(std::io::Error::other("disk full"));
+    assert_eq!(err.error_type(), "io_error");
+    assert_eq!(Error::from(err), Error::Io("disk full".into()));
 }
@@ -0,0 +1,244 @@
+// ABOUTME: Structural hashing of Values: a digest of a canonical encoding that ignores
+// ABOUTME: key order and numeric representation, plus the SHA-256 implementation behind it.
+
+use crate::encoder::{Encoder, EncoderConfig};
+use crate::normalize::{canonical_number, float_to_decimal, strip_trailing_zeros};
+use crate::types::type_code;
+use crate::value::Value;
+use std::io::Write;
+
+/// The digest used by [`Value::structural_hash`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum HashAlgo {
+    /// SHA-256 (FIPS 180-4)
+    #[default]
+    Sha256,
+}
+
+/// Which numbers [`Value::structural_hash_with`] treats as identical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberIdentity {
+    /// Numbers with the same value hash the same, whatever their variant:
+    /// `Int(5)`, `UInt(5)`, `Float(5.0)` and a BigNumber of 5 are one number,
+    /// as are `Float(0.5)` and a BigNumber of 5e-1 (floats compare by their
+    /// shortest round-trip decimal form), and `-0.0` is zero (default)
+    #[default]
+    Value,
+    /// The variant is part of the identity, and floats compare bit for bit
+    Variant,
+}
+
+/// Prefix of the hashed bytes, so that changes to the canonical form change every hash.
+const DOMAIN: &[u8] = b"bonjson-structural-hash-v1\0";
+
+/// Hash the canonical encoding of `value`.
+///
+/// The canonical encoding is the value's BONJSON encoding with object keys in
+/// byte order, no records or typed arrays, and every number written in one
+/// form chosen by `numbers`: with `NumberIdentity::Value`, integral values
+/// use the smallest integer encoding and everything else a BigNumber with no
+/// trailing zeros in its significand; with `NumberIdentity::Variant`, each
+/// number is its variant's own encoding behind a one-byte variant tag.
+/// NaN and infinities are written as float64, and all NaNs are one value.
+#[must_use]
+pub fn structural_hash(value: &Value, algo: HashAlgo, numbers: NumberIdentity) -> [u8; 32] {
+    match algo {
+        HashAlgo::Sha256 => {
+            let mut hasher = Sha256::new();
+            hasher.update(DOMAIN);
+            hasher.update(&[numbers as u8]);
+            let config = EncoderConfig { allow_nul: true, ..EncoderConfig::default() };
+            let mut encoder = Encoder::with_config(&mut hasher, config);
+            write_canonical(&mut encoder, value, numbers)
+                .expect("the canonical encoding writes only valid values to an infallible writer");
+            hasher.finish()
+        }
+    }
+}
+
+fn write_canonical<W: Write>(encoder: &mut Encoder<W>, value: &Value, numbers: NumberIdentity) -> crate::encoder::Result<()> {
+    match value {
+        Value::Null => encoder.write_null_unchecked(),
+        Value::Bool(b) => encoder.write_bool_unchecked(*b),
+        Value::String(s) => encoder.write_str_unchecked(s),
+        Value::Array(items) => {
+            encoder.begin_array_unchecked()?;
+            for item in items {
+                write_canonical(encoder, item, numbers)?;
+            }
+            encoder.end_container_unchecked()
+        }
+        Value::Object(map) => {
+            encoder.begin_object_unchecked()?;
+            for (key, item) in map {
+                encoder.write_str_unchecked(key)?;
+                write_canonical(encoder, item, numbers)?;
+            }
+            encoder.end_container_unchecked()
+        }
+        Value::Int(_) | Value::UInt(_) | Value::Float(_) | Value::SizedFloat(..) | Value::BigNumber(_) => match numbers {
+            NumberIdentity::Value => write_number_by_value(encoder, value),
+            NumberIdentity::Variant => write_number_by_variant(encoder, value),
+        },
+    }
+}
+
+fn write_number_by_value<W: Write>(encoder: &mut Encoder<W>, value: &Value) -> crate::encoder::Result<()> {
+    match canonical_number(value) {
+        Value::Int(n) => match u64::try_from(n) {
+            Ok(n) => encoder.write_u64_unchecked(n),
+            Err(_) => encoder.write_i64_unchecked(n),
+        },
+        Value::UInt(n) => encoder.write_u64_unchecked(n),
+        Value::Float(f) if !f.is_finite() => write_raw_f64(encoder, f),
+        // Non-integral numbers hash as a decimal, whichever form holds them
+        Value::Float(f) => encoder.write_big_number(strip_trailing_zeros(float_to_decimal(f))),
+        Value::BigNumber(bn) => encoder.write_big_number(bn),
+        _ => unreachable!("only called for numbers"),
+    }
+}
+
+fn write_number_by_variant<W: Write>(encoder: &mut Encoder<W>, value: &Value) -> crate::encoder::Result<()> {
+    // Tags sit where a type code would and are small-int codes, which never
+    // appear untagged in this form, so the byte stream stays unambiguous
+    match value {
+        Value::Int(n) => {
+            encoder.write_raw_unchecked(&[0])?;
+            encoder.write_i64_unchecked(*n)
+        }
+        Value::UInt(n) => {
+            encoder.write_raw_unchecked(&[1])?;
+            encoder.write_u64_unchecked(*n)
+        }
+        // The width is only how the float is written
+        Value::Float(f) | Value::SizedFloat(f, _) => {
+            encoder.write_raw_unchecked(&[2])?;
+            write_raw_f64(encoder, *f)
+        }
+        Value::BigNumber(bn) => {
+            encoder.write_raw_unchecked(&[3])?;
+            encoder.write_big_number(*bn)
+        }
+        _ => unreachable!("only called for numbers"),
+    }
+}
+
+/// Write a float64 as-is, with every NaN replaced by the canonical one.
+fn write_raw_f64<W: Write>(encoder: &mut Encoder<W>, value: f64) -> crate::encoder::Result<()> {
+    let value = if value.is_nan() { f64::NAN } else { value };
+    let mut buf = [type_code::FLOAT64; 9];
+    buf[1..].copy_from_slice(&value.to_le_bytes());
+    encoder.write_raw_unchecked(&buf)
+}
+
+// ============================================================================
+// SHA-256
+// ============================================================================
+
+#[rustfmt::skip]
+const SHA256_K: [u32; 64] = [
+    0x428a_2f98, 0x7137_4491, 0xb5c0_fbcf, 0xe9b5_dba5, 0x3956_c25b, 0x59f1_11f1, 0x923f_82a4, 0xab1c_5ed5,
+    0xd807_aa98, 0x1283_5b01, 0x2431_85be, 0x550c_7dc3, 0x72be_5d74, 0x80de_b1fe, 0x9bdc_06a7, 0xc19b_f174,
+    0xe49b_69c1, 0xefbe_4786, 0x0fc1_9dc6, 0x240c_a1cc, 0x2de9_2c6f, 0x4a74_84aa, 0x5cb0_a9dc, 0x76f9_88da,
+    0x983e_5152, 0xa831_c66d, 0xb003_27c8, 0xbf59_7fc7, 0xc6e0_0bf3, 0xd5a7_9147, 0x06ca_6351, 0x1429_2967,
+    0x27b7_0a85, 0x2e1b_2138, 0x4d2c_6dfc, 0x5338_0d13, 0x650a_7354, 0x766a_0abb, 0x81c2_c92e, 0x9272_2c85,
+    0xa2bf_e8a1, 0xa81a_664b, 0xc24b_8b70, 0xc76c_51a3, 0xd192_e819, 0xd699_0624, 0xf40e_3585, 0x106a_a070,
+    0x19a4_c116, 0x1e37_6c08, 0x2748_774c, 0x34b0_bcb5, 0x391c_0cb3, 0x4ed8_aa4a, 0x5b9c_ca4f, 0x682e_6ff3,
+    0x748f_82ee, 0x78a5_636f, 0x84c8_7814, 0x8cc7_0208, 0x90be_fffa, 0xa450_6ceb, 0xbef9_a3f7, 0xc671_78f2,
+];
+
+/// A streaming SHA-256 hasher, usable as an `io::Write` sink.
+pub(crate) struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    block_len: usize,
+    total_len: u64,
+}
+
+impl Sha256 {
+    pub(crate) fn new() -> Self {
+        Sha256 {
+            state: [
+                0x6a09_e667, 0xbb67_ae85, 0x3c6e_f372, 0xa54f_f53a, 0x510e_527f, 0x9b05_688c, 0x1f83_d9ab, 0x5be0_cd19,
+            ],
+            block: [0; 64],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+        while !data.is_empty() {
+            let take = (64 - self.block_len).min(data.len());
+            self.block[self.block_len..self.block_len + take].copy_from_slice(&data[..take]);
+            self.block_len += take;
+            data = &data[take..];
+            if self.block_len == 64 {
+                let block = self.block;
+                self.compress(&block);
+                self.block_len = 0;
+            }
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> [u8; 32] {
+        let bit_len = self.total_len.wrapping_mul(8);
+        let padding_len = if self.block_len < 56 { 56 - self.block_len } else { 120 - self.block_len };
+        let mut padding = [0u8; 72];
+        padding[0] = 0x80;
+        self.update(&padding[..padding_len]);
+        self.update(&bit_len.to_be_bytes());
+
+        let mut digest = [0u8; 32];
+        for (chunk, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut w = [0u32; 64];
+        for (i, chunk) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes(chunk.try_into().expect("chunks are 4 bytes"));
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+impl Write for Sha256 {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
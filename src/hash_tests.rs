@@ -0,0 +1,130 @@
+// ABOUTME: Unit tests for the hash module.
+// ABOUTME: Tests SHA-256 against FIPS vectors and the identities of Value::structural_hash.
+
+use crate::hash::{HashAlgo, NumberIdentity, Sha256};
+use crate::{bonjson, decode_value, BigNumber, Encoder, Value};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn sha256(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hex(&hasher.finish())
+}
+
+fn hash(value: &Value) -> [u8; 32] {
+    value.structural_hash(HashAlgo::Sha256)
+}
+
+fn hash_by_variant(value: &Value) -> [u8; 32] {
+    value.structural_hash_with(HashAlgo::Sha256, NumberIdentity::Variant)
+}
+
+#[test]
+fn test_sha256_vectors() {
+    assert_eq!(sha256(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    assert_eq!(sha256(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    assert_eq!(
+        sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+
+    // A million 'a's, fed in uneven pieces
+    let mut hasher = Sha256::new();
+    let chunk = [b'a'; 999];
+    for _ in 0..1001 {
+        hasher.update(&chunk);
+    }
+    hasher.update(&[b'a'; 1]);
+    assert_eq!(hex(&hasher.finish()), "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+}
+
+#[test]
+fn test_key_order_is_ignored() {
+    let mut encodings = Vec::new();
+    for keys in [["a", "b"], ["b", "a"]] {
+        let mut encoder = Encoder::new(Vec::new());
+        encoder.begin_object().unwrap();
+        for key in keys {
+            encoder.write_str(key).unwrap();
+            encoder.write_str(key).unwrap();
+        }
+        encoder.end_container().unwrap();
+        encodings.push(encoder.finish().unwrap());
+    }
+    assert_ne!(encodings[0], encodings[1]);
+    let values: Vec<Value> = encodings.iter().map(|bytes| decode_value(bytes).unwrap()).collect();
+    assert_eq!(hash(&values[0]), hash(&values[1]));
+}
+
+#[test]
+fn test_numbers_hash_by_value() {
+    let fives = [
+        Value::Int(5),
+        Value::UInt(5),
+        Value::Float(5.0),
+        Value::BigNumber(BigNumber::new(1, 5, 0)),
+        Value::BigNumber(BigNumber::new(1, 500, -2)),
+    ];
+    for five in &fives {
+        assert_eq!(hash(five), hash(&fives[0]));
+    }
+    assert_eq!(hash(&Value::Int(200)), hash(&Value::UInt(200)));
+    assert_eq!(hash(&Value::Float(-0.0)), hash(&Value::Int(0)));
+    assert_eq!(hash(&Value::Float(0.5)), hash(&Value::BigNumber(BigNumber::new(1, 5, -1))));
+    assert_eq!(hash(&Value::Float(-1.25e-7)), hash(&Value::BigNumber(BigNumber::new(-1, 125, -9))));
+    assert_eq!(hash(&Value::Float(1e30)), hash(&Value::BigNumber(BigNumber::new(1, 1000, 27))));
+    assert_eq!(hash(&Value::Float(f64::NAN)), hash(&Value::Float(-f64::NAN)));
+    assert_ne!(hash(&Value::Float(f64::INFINITY)), hash(&Value::Float(f64::NEG_INFINITY)));
+    assert_ne!(hash(&Value::Float(0.5)), hash(&Value::Float(0.25)));
+
+    // Nested numbers follow the same rule
+    assert_eq!(hash(&bonjson!({"n": [1, 2.0]})), hash(&bonjson!({"n": [1.0, 2]})));
+}
+
+#[test]
+fn test_numbers_hash_by_variant() {
+    let distinct = [
+        Value::Int(5),
+        Value::UInt(5),
+        Value::Float(5.0),
+        Value::BigNumber(BigNumber::new(1, 5, 0)),
+        Value::Float(-0.0),
+        Value::Float(0.0),
+    ];
+    for (i, a) in distinct.iter().enumerate() {
+        for b in &distinct[i + 1..] {
+            assert_ne!(hash_by_variant(a), hash_by_variant(b), "{a:?} vs {b:?}");
+        }
+    }
+    assert_eq!(hash_by_variant(&Value::Float(f64::NAN)), hash_by_variant(&Value::Float(-f64::NAN)));
+    // The modes hash into separate domains
+    assert_ne!(hash_by_variant(&Value::Null), hash(&Value::Null));
+}
+
+#[test]
+fn test_structure_is_significant() {
+    let values = [
+        bonjson!(null),
+        bonjson!([]),
+        bonjson!([[]]),
+        Value::Object(std::collections::BTreeMap::new()),
+        bonjson!([1, 2]),
+        bonjson!([2, 1]),
+        bonjson!({"a": 1}),
+        bonjson!({"a": "1"}),
+        bonjson!({"a": [1]}),
+        bonjson!(["a\0b"]),
+    ];
+    for (i, a) in values.iter().enumerate() {
+        for b in &values[i + 1..] {
+            assert_ne!(hash(a), hash(b), "{a:?} vs {b:?}");
+        }
+    }
+
+    // Pinned, so that any change to the canonical form is noticed
+    let sample = bonjson!({"id": 7, "tags": ["x", "y"], "score": 0.5, "extra": null});
+    assert_eq!(hex(&hash(&sample)), "24df1bd1e5a2f3d078c7ca779af17d8286167438f00a34f42f48386932645853");
+}
@@ -0,0 +1,381 @@
+// ABOUTME: Schema inference over sample documents: field names, optionality, integer ranges,
+// ABOUTME: array element types, plus Rust struct generation from the inferred schema.
+
+use crate::error::Result;
+use crate::value::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+
+/// The inferred shape of every value seen at one position in the samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    /// The kind of the non-null values.
+    pub kind: SchemaKind,
+    /// Whether null was seen here.
+    pub nullable: bool,
+}
+
+/// The kind of value at one position in the samples.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaKind {
+    /// Nothing but nulls seen (or the elements of arrays that were always empty).
+    Unknown,
+    /// Booleans.
+    Bool,
+    /// Integers, with the smallest and largest values seen.
+    Integer {
+        /// Smallest value seen
+        min: i128,
+        /// Largest value seen
+        max: i128,
+    },
+    /// Floats, or a mix of floats and integers.
+    Float,
+    /// BigNumbers, possibly mixed with other numbers.
+    BigNumber,
+    /// Strings.
+    String,
+    /// Arrays, with the merged schema of all their elements.
+    Array(Box<Schema>),
+    /// Objects, by key.
+    Object(BTreeMap<String, Field>),
+    /// Values of more than one incompatible kind, each merged with its own kind.
+    Mixed(Vec<SchemaKind>),
+}
+
+/// An object member in an inferred schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// The merged schema of the member's values.
+    pub schema: Schema,
+    /// Whether some sampled object lacked the member.
+    pub optional: bool,
+}
+
+impl Schema {
+    /// The schema of a single value.
+    #[must_use]
+    pub fn of(value: &Value) -> Schema {
+        let kind = match value {
+            Value::Null => return Schema { kind: SchemaKind::Unknown, nullable: true },
+            Value::Bool(_) => SchemaKind::Bool,
+            Value::Int(n) => SchemaKind::Integer { min: i128::from(*n), max: i128::from(*n) },
+            Value::UInt(n) => SchemaKind::Integer { min: i128::from(*n), max: i128::from(*n) },
+            Value::Float(_) | Value::SizedFloat(..) => SchemaKind::Float,
+            Value::BigNumber(_) => SchemaKind::BigNumber,
+            Value::String(_) => SchemaKind::String,
+            Value::Array(items) => {
+                let element = items
+                    .iter()
+                    .map(Schema::of)
+                    .reduce(Schema::merge)
+                    .unwrap_or(Schema { kind: SchemaKind::Unknown, nullable: false });
+                SchemaKind::Array(Box::new(element))
+            }
+            Value::Object(map) => SchemaKind::Object(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), Field { schema: Schema::of(v), optional: false }))
+                    .collect(),
+            ),
+        };
+        Schema { kind, nullable: false }
+    }
+
+    /// Combine two schemas into one that describes the values of both.
+    #[must_use]
+    pub fn merge(self, other: Schema) -> Schema {
+        Schema { kind: merge_kinds(self.kind, other.kind), nullable: self.nullable || other.nullable }
+    }
+
+    /// Generate Rust structs (deriving serde's traits) that can hold every
+    /// sampled document, with `root_name` naming the top-level type.
+    ///
+    /// Nested objects become structs named after their key. Optional and
+    /// nullable members become `Option`s, keys that aren't valid field names
+    /// are renamed with `#[serde(rename)]`, and mixed or unknown kinds fall
+    /// back to `serde_bonjson::Value`. Integers get the smallest of `i32`,
+    /// `u32`, `i64`, `u64` and `i128` that holds the sampled range: samples
+    /// rarely show a field's full range, so nothing narrower is chosen.
+    #[must_use]
+    pub fn to_rust(&self, root_name: &str) -> String {
+        let mut generator = RustGenerator::default();
+        let root_type = generator.type_of(self, root_name);
+        let mut out = String::new();
+        if !matches!(self.kind, SchemaKind::Object(_)) || self.nullable {
+            let _ = writeln!(out, "pub type {} = {root_type};", pascal_case(root_name));
+            if !generator.structs.is_empty() {
+                out.push('\n');
+            }
+        }
+        out.push_str(&generator.structs.join("\n"));
+        out
+    }
+}
+
+/// Infer the schema of a single value.
+#[must_use]
+pub fn infer(value: &Value) -> Schema {
+    Schema::of(value)
+}
+
+/// Decode each document and infer a schema that fits all of them.
+///
+/// With no documents, the schema is [`SchemaKind::Unknown`].
+///
+/// # Errors
+///
+/// Returns the first decoding error.
+pub fn infer_documents<I, D>(documents: I) -> Result<Schema>
+where
+    I: IntoIterator<Item = D>,
+    D: AsRef<[u8]>,
+{
+    let mut schema: Option<Schema> = None;
+    for document in documents {
+        let next = Schema::of(&crate::decode_value(document.as_ref())?);
+        schema = Some(match schema {
+            Some(schema) => schema.merge(next),
+            None => next,
+        });
+    }
+    Ok(schema.unwrap_or(Schema { kind: SchemaKind::Unknown, nullable: false }))
+}
+
+// ============================================================================
+// Merging
+// ============================================================================
+
+/// Kinds that merge with each other rather than forming a `Mixed`.
+fn family(kind: &SchemaKind) -> u8 {
+    match kind {
+        SchemaKind::Unknown => 0,
+        SchemaKind::Bool => 1,
+        SchemaKind::Integer { .. } | SchemaKind::Float | SchemaKind::BigNumber => 2,
+        SchemaKind::String => 3,
+        SchemaKind::Array(_) => 4,
+        SchemaKind::Object(_) => 5,
+        SchemaKind::Mixed(_) => 6,
+    }
+}
+
+fn merge_kinds(a: SchemaKind, b: SchemaKind) -> SchemaKind {
+    use SchemaKind as K;
+    match (a, b) {
+        (K::Unknown, k) | (k, K::Unknown) => k,
+        (K::Mixed(mut kinds), K::Mixed(others)) => {
+            for kind in others {
+                add_to_mixed(&mut kinds, kind);
+            }
+            K::Mixed(kinds)
+        }
+        (K::Mixed(mut kinds), k) | (k, K::Mixed(mut kinds)) => {
+            add_to_mixed(&mut kinds, k);
+            K::Mixed(kinds)
+        }
+        (K::Integer { min: a_min, max: a_max }, K::Integer { min, max }) => {
+            K::Integer { min: a_min.min(min), max: a_max.max(max) }
+        }
+        (K::BigNumber, k) | (k, K::BigNumber) if family(&k) == 2 => K::BigNumber,
+        (K::Float, k) | (k, K::Float) if family(&k) == 2 => K::Float,
+        (K::Array(a), K::Array(b)) => K::Array(Box::new(a.merge(*b))),
+        (K::Object(a), K::Object(b)) => K::Object(merge_fields(a, b)),
+        (a, b) if family(&a) == family(&b) => a,
+        (a, b) => K::Mixed(vec![a, b]),
+    }
+}
+
+fn add_to_mixed(kinds: &mut Vec<SchemaKind>, kind: SchemaKind) {
+    match kinds.iter().position(|k| family(k) == family(&kind)) {
+        Some(i) => {
+            let existing = std::mem::replace(&mut kinds[i], SchemaKind::Unknown);
+            kinds[i] = merge_kinds(existing, kind);
+        }
+        None => kinds.push(kind),
+    }
+}
+
+fn merge_fields(mut a: BTreeMap<String, Field>, b: BTreeMap<String, Field>) -> BTreeMap<String, Field> {
+    let mut merged = BTreeMap::new();
+    for (name, field) in b {
+        let field = match a.remove(&name) {
+            Some(existing) => Field {
+                schema: existing.schema.merge(field.schema),
+                optional: existing.optional || field.optional,
+            },
+            None => Field { optional: true, ..field },
+        };
+        merged.insert(name, field);
+    }
+    for (name, field) in a {
+        merged.insert(name, Field { optional: true, ..field });
+    }
+    merged
+}
+
+// ============================================================================
+// Rust code generation
+// ============================================================================
+
+#[rustfmt::skip]
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+#[derive(Default)]
+struct RustGenerator {
+    /// Generated struct definitions, outermost first
+    structs: Vec<String>,
+    struct_names: HashSet<String>,
+}
+
+impl RustGenerator {
+    /// The Rust type for `schema`, generating structs for any objects in it.
+    fn type_of(&mut self, schema: &Schema, name_hint: &str) -> String {
+        let base = match &schema.kind {
+            SchemaKind::Unknown | SchemaKind::Mixed(_) => return "serde_bonjson::Value".to_string(),
+            SchemaKind::Bool => "bool".to_string(),
+            SchemaKind::Integer { min, max } => integer_type(*min, *max).to_string(),
+            SchemaKind::Float | SchemaKind::BigNumber => "f64".to_string(),
+            SchemaKind::String => "String".to_string(),
+            SchemaKind::Array(element) => format!("Vec<{}>", self.type_of(element, &singular(name_hint))),
+            SchemaKind::Object(fields) => self.generate_struct(fields, name_hint),
+        };
+        if schema.nullable {
+            format!("Option<{base}>")
+        } else {
+            base
+        }
+    }
+
+    fn generate_struct(&mut self, fields: &BTreeMap<String, Field>, name_hint: &str) -> String {
+        let base = pascal_case(name_hint);
+        let mut name = base.clone();
+        let mut n = 2;
+        while !self.struct_names.insert(name.clone()) {
+            name = format!("{base}{n}");
+            n += 1;
+        }
+
+        // Reserve a slot so that the struct precedes the ones nested in it
+        let slot = self.structs.len();
+        self.structs.push(String::new());
+
+        let mut out = String::new();
+        out.push_str("#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n");
+        let _ = writeln!(out, "pub struct {name} {{");
+        let mut idents = HashSet::new();
+        for (key, field) in fields {
+            let base_ident = snake_case(key);
+            let mut ident = base_ident.clone();
+            let mut n = 2;
+            while !idents.insert(ident.clone()) {
+                ident = format!("{base_ident}_{n}");
+                n += 1;
+            }
+            if ident != *key {
+                let _ = writeln!(out, "    #[serde(rename = {key:?})]");
+            }
+            let mut ty = self.type_of(&field.schema, key);
+            if field.optional && !ty.starts_with("Option<") && ty != "serde_bonjson::Value" {
+                ty = format!("Option<{ty}>");
+            }
+            let _ = writeln!(out, "    pub {ident}: {ty},");
+        }
+        out.push_str("}\n");
+        self.structs[slot] = out;
+        name
+    }
+}
+
+fn integer_type(min: i128, max: i128) -> &'static str {
+    if min >= 0 {
+        if max <= i128::from(u32::MAX) {
+            "u32"
+        } else if max <= i128::from(u64::MAX) {
+            "u64"
+        } else {
+            "i128"
+        }
+    } else if min >= i128::from(i32::MIN) && max <= i128::from(i32::MAX) {
+        "i32"
+    } else if min >= i128::from(i64::MIN) && max <= i128::from(i64::MAX) {
+        "i64"
+    } else {
+        "i128"
+    }
+}
+
+/// Split a key into lowercase words at non-alphanumerics and lower-to-upper case changes.
+fn words(key: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in key.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_numeric();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn snake_case(key: &str) -> String {
+    let mut ident = words(key).join("_");
+    if ident.is_empty() {
+        ident.push_str("field");
+    }
+    if ident.starts_with(|c: char| c.is_numeric()) {
+        ident.insert(0, '_');
+    }
+    if RUST_KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
+
+fn pascal_case(name: &str) -> String {
+    let mut ident: String = words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect();
+    if ident.is_empty() {
+        ident.push_str("Root");
+    }
+    if ident.starts_with(|c: char| c.is_numeric()) {
+        ident.insert(0, '_');
+    }
+    // "Self" is the only PascalCase keyword
+    if ident == "Self" {
+        ident.push('_');
+    }
+    ident
+}
+
+/// A name for an array's elements: "items" becomes "item", "entries" becomes "entry".
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        format!("{stem}y")
+    } else if let Some(stem) = name.strip_suffix('s').filter(|stem| !stem.is_empty() && !stem.ends_with('s')) {
+        stem.to_string()
+    } else {
+        format!("{name}_item")
+    }
+}
@@ -0,0 +1,83 @@
+// ABOUTME: Unit tests for the infer module.
+// ABOUTME: Tests schema merging across samples and the generated Rust structs.
+
+use crate::infer::{infer, infer_documents, Field, Schema, SchemaKind};
+use crate::{bonjson, encode_value, Error};
+
+fn required(kind: SchemaKind) -> Field {
+    Field { schema: Schema { kind, nullable: false }, optional: false }
+}
+
+#[test]
+fn test_merge_samples() {
+    let docs = [
+        encode_value(&bonjson!({"id": 1, "score": 2, "tags": ["a"], "note": null})).unwrap(),
+        encode_value(&bonjson!({"id": (-300), "score": 2.5, "tags": [], "note": "n", "extra": true})).unwrap(),
+    ];
+    let schema = infer_documents(&docs).unwrap();
+    let SchemaKind::Object(fields) = schema.kind else { panic!("expected an object: {schema:?}") };
+
+    assert_eq!(fields["id"], required(SchemaKind::Integer { min: -300, max: 1 }));
+    assert_eq!(fields["score"], required(SchemaKind::Float));
+    let string = Schema { kind: SchemaKind::String, nullable: false };
+    assert_eq!(fields["tags"], required(SchemaKind::Array(Box::new(string))));
+    assert_eq!(fields["note"].schema, Schema { kind: SchemaKind::String, nullable: true });
+    assert!(!fields["note"].optional);
+    assert!(fields["extra"].optional);
+}
+
+#[test]
+fn test_mixed_kinds() {
+    let schema = infer(&bonjson!([1, "a", 2.5, true, "b", null]));
+    let SchemaKind::Array(element) = schema.kind else { panic!() };
+    assert!(element.nullable);
+    assert_eq!(element.kind, SchemaKind::Mixed(vec![SchemaKind::Float, SchemaKind::String, SchemaKind::Bool]));
+
+    assert_eq!(infer_documents(Vec::<Vec<u8>>::new()).unwrap().kind, SchemaKind::Unknown);
+    assert_eq!(infer_documents([[0xB7]]).unwrap_err(), Error::Truncated);
+}
+
+#[test]
+fn test_to_rust() {
+    let samples = [
+        bonjson!({"userId": 7, "type": "a", "2fa": false, "home address": {"zip": "1"}, "entries": [{"n": 1}]}),
+        bonjson!({"userId": 5_000_000_000u64, "type": "b", "2fa": true, "entries": [{"n": (-1), "v": null}]}),
+    ];
+    let schema = samples.iter().map(infer).reduce(Schema::merge).unwrap();
+    let expected = "\
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Event {
+    #[serde(rename = \"2fa\")]
+    pub _2fa: bool,
+    pub entries: Vec<Entry>,
+    #[serde(rename = \"home address\")]
+    pub home_address: Option<HomeAddress>,
+    #[serde(rename = \"type\")]
+    pub type_: String,
+    #[serde(rename = \"userId\")]
+    pub user_id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Entry {
+    pub n: i32,
+    pub v: serde_bonjson::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HomeAddress {
+    pub zip: String,
+}
+";
+    assert_eq!(schema.to_rust("event"), expected);
+
+    let expected = "\
+pub type Points = Vec<Point>;
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Point {
+    pub x: f64,
+}
+";
+    assert_eq!(infer(&bonjson!([{"x": 1.5}, {"x": 2}])).to_rust("points"), expected);
+}
@@ -0,0 +1,247 @@
+// ABOUTME: InstrumentedDecoder wraps the event decoder and records, per type code, how many values
+// ABOUTME: were read, their encoded bytes, and their decode times (in total and as a histogram).
+
+//! Profiling a workload's documents by value type.
+//!
+//! [`InstrumentedDecoder`] shows whether decoding time goes to strings, typed
+//! arrays, numbers or structure, which tells you what an optimization would
+//! be worth before reaching for a profiler:
+//!
+//! ```rust
+//! use serde_bonjson::instrumented::InstrumentedDecoder;
+//! use serde_bonjson::type_code::Category;
+//!
+//! let bytes = serde_bonjson::to_vec(&(vec!["label"; 10], vec![0.1f64; 1000])).unwrap();
+//! let mut decoder = InstrumentedDecoder::new(&bytes);
+//! decoder.decode_document().unwrap();
+//! let profile = decoder.into_profile();
+//! assert_eq!(profile.category(Category::ShortString).count, 10);
+//! assert!(profile.category(Category::TypedArray).bytes > 8000);
+//! println!("{profile}");
+//! ```
+
+use crate::decoder::{DecodedValue, Decoder, DecoderConfig, Result};
+use crate::types::type_code::{self, Category};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the [`TypeStats::time_buckets`] histogram: 100 ns, 1 µs,
+/// 10 µs, 100 µs and 1 ms. The last bucket holds everything slower.
+pub const TIME_BUCKET_BOUNDS: [Duration; 5] = [
+    Duration::from_nanos(100),
+    Duration::from_micros(1),
+    Duration::from_micros(10),
+    Duration::from_micros(100),
+    Duration::from_millis(1),
+];
+
+/// Number of [`TypeStats::time_buckets`].
+pub const TIME_BUCKETS: usize = TIME_BUCKET_BOUNDS.len() + 1;
+
+/// What decoding values of one type code cost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeStats {
+    /// Values read. A typed array counts once, however many elements it has.
+    pub count: usize,
+    /// Encoded bytes, including type codes, lengths and typed array elements.
+    pub bytes: usize,
+    /// Time spent decoding them.
+    pub time: Duration,
+    /// How many values took less than each of [`TIME_BUCKET_BOUNDS`], and
+    /// (last) how many took longer.
+    pub time_buckets: [usize; TIME_BUCKETS],
+}
+
+impl TypeStats {
+    fn record(&mut self, bytes: usize, time: Duration) {
+        self.count += 1;
+        self.bytes += bytes;
+        self.time += time;
+        let bucket = TIME_BUCKET_BOUNDS.iter().position(|bound| time < *bound).unwrap_or(TIME_BUCKETS - 1);
+        self.time_buckets[bucket] += 1;
+    }
+
+    fn add(&mut self, other: &TypeStats) {
+        self.count += other.count;
+        self.bytes += other.bytes;
+        self.time += other.time;
+        for (bucket, n) in self.time_buckets.iter_mut().zip(other.time_buckets) {
+            *bucket += n;
+        }
+    }
+}
+
+/// Per-type-code statistics gathered by an [`InstrumentedDecoder`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeProfile {
+    /// Statistics keyed by type code. Small ints are all counted under
+    /// [`type_code::SMALLINT_MIN`] and short strings under
+    /// [`type_code::STRING0`]; typed arrays are under their array type code.
+    pub by_type_code: BTreeMap<u8, TypeStats>,
+}
+
+impl DecodeProfile {
+    /// The statistics for every type code in `category` together.
+    #[must_use]
+    pub fn category(&self, category: Category) -> TypeStats {
+        let mut total = TypeStats::default();
+        for (_, stats) in self.by_type_code.iter().filter(|(code, _)| type_code::category(**code) == category) {
+            total.add(stats);
+        }
+        total
+    }
+
+    /// The statistics for every type code together.
+    #[must_use]
+    pub fn total(&self) -> TypeStats {
+        let mut total = TypeStats::default();
+        for stats in self.by_type_code.values() {
+            total.add(stats);
+        }
+        total
+    }
+
+    fn record(&mut self, code: u8, bytes: usize, time: Duration) {
+        let key = match type_code::category(code) {
+            Category::SmallInt => type_code::SMALLINT_MIN,
+            Category::ShortString => type_code::STRING0,
+            _ => code,
+        };
+        self.by_type_code.entry(key).or_default().record(bytes, time);
+    }
+}
+
+/// One line per type code: name, count, bytes and total time.
+impl fmt::Display for DecodeProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<22}{:>10}{:>14}{:>14}", "type", "count", "bytes", "time")?;
+        for (code, stats) in &self.by_type_code {
+            let time = format!("{:?}", stats.time);
+            writeln!(f, "{:<22}{:>10}{:>14}{time:>14}", type_code::name(*code), stats.count, stats.bytes)?;
+        }
+        Ok(())
+    }
+}
+
+/// A [`Decoder`] that records a [`DecodeProfile`] of what it reads.
+///
+/// The methods mirror the decoder's event API. Each call is timed with two
+/// [`Instant::now`] reads, so expect decoding to run slower than through a
+/// plain `Decoder`; compare the types' shares of the time rather than
+/// absolute numbers. A typed array is one value: its bytes and time add up
+/// from `decode_value` through `end_typed_array`, leaving out the caller's
+/// time between element reads.
+pub struct InstrumentedDecoder<'a> {
+    decoder: Decoder<'a>,
+    profile: DecodeProfile,
+    /// The typed array being read: its type code, and the bytes and time spent on it so far.
+    typed_array: Option<(u8, usize, Duration)>,
+}
+
+impl<'a> InstrumentedDecoder<'a> {
+    /// Create an instrumented decoder with the default configuration.
+    #[must_use]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self::with_config(data, DecoderConfig::default())
+    }
+
+    /// Create an instrumented decoder with a custom configuration.
+    #[must_use]
+    pub fn with_config(data: &'a [u8], config: DecoderConfig) -> Self {
+        Self { decoder: Decoder::with_config(data, config), profile: DecodeProfile::default(), typed_array: None }
+    }
+
+    /// The statistics gathered so far.
+    #[must_use]
+    pub fn profile(&self) -> &DecodeProfile {
+        &self.profile
+    }
+
+    /// Consume the decoder, returning its statistics.
+    #[must_use]
+    pub fn into_profile(self) -> DecodeProfile {
+        self.profile
+    }
+
+    /// The wrapped decoder, for its position and record definitions.
+    #[must_use]
+    pub fn decoder(&self) -> &Decoder<'a> {
+        &self.decoder
+    }
+
+    /// Run `read` on the decoder, returning its result with the bytes it consumed and the time it took.
+    fn timed<T>(&mut self, read: impl FnOnce(&mut Decoder<'a>) -> Result<T>) -> Result<(T, usize, Duration)> {
+        let start = self.decoder.position();
+        let began = Instant::now();
+        let result = read(&mut self.decoder)?;
+        Ok((result, self.decoder.position() - start, began.elapsed()))
+    }
+
+    /// Read the record definitions at the start of the document, if any.
+    /// See [`Decoder::read_record_definitions`].
+    pub fn read_record_definitions(&mut self) -> Result<()> {
+        let code = self.decoder.peek_type_code()?;
+        let ((), bytes, time) = self.timed(Decoder::read_record_definitions)?;
+        if bytes > 0 {
+            self.profile.record(code, bytes, time);
+        }
+        Ok(())
+    }
+
+    /// Decode the next value. See [`Decoder::decode_value`].
+    pub fn decode_value(&mut self) -> Result<DecodedValue<'a>> {
+        let code = self.decoder.peek_type_code()?;
+        let (value, bytes, time) = self.timed(Decoder::decode_value)?;
+        if let DecodedValue::TypedArrayStart { .. } = value {
+            self.typed_array = Some((code, bytes, time));
+        } else {
+            self.profile.record(code, bytes, time);
+        }
+        Ok(value)
+    }
+
+    /// Read one element of the current typed array. See
+    /// [`Decoder::read_typed_array_element`].
+    pub fn read_typed_array_element(&mut self, element_type_code: u8) -> Result<DecodedValue<'a>> {
+        let (value, bytes, time) = self.timed(|decoder| decoder.read_typed_array_element(element_type_code))?;
+        if let Some((_, total_bytes, total_time)) = &mut self.typed_array {
+            *total_bytes += bytes;
+            *total_time += time;
+        }
+        Ok(value)
+    }
+
+    /// Finish the current typed array, recording it. See [`Decoder::end_typed_array`].
+    pub fn end_typed_array(&mut self) -> Result<()> {
+        let ((), bytes, time) = self.timed(Decoder::end_typed_array)?;
+        if let Some((code, total_bytes, total_time)) = self.typed_array.take() {
+            self.profile.record(code, total_bytes + bytes, total_time + time);
+        }
+        Ok(())
+    }
+
+    /// Read a whole document (record definitions, one value, and the check for
+    /// trailing bytes), recording every value in it.
+    pub fn decode_document(&mut self) -> Result<()> {
+        self.decoder.check_document_size()?;
+        self.read_record_definitions()?;
+        let mut depth: usize = 0;
+        loop {
+            match self.decode_value()? {
+                DecodedValue::ArrayStart | DecodedValue::ObjectStart | DecodedValue::RecordInstanceStart(_) => depth += 1,
+                DecodedValue::ContainerEnd => depth -= 1,
+                DecodedValue::TypedArrayStart { element_type_code, count } => {
+                    for _ in 0..count {
+                        self.read_typed_array_element(element_type_code)?;
+                    }
+                    self.end_typed_array()?;
+                }
+                _ => {}
+            }
+            if depth == 0 {
+                return self.decoder.finish();
+            }
+        }
+    }
+}
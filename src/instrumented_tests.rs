@@ -0,0 +1,72 @@
+// ABOUTME: Unit tests for the instrumented module.
+// ABOUTME: Tests per-type-code counts and bytes, typed arrays as single values, and timing buckets.
+
+use crate::instrumented::{InstrumentedDecoder, TIME_BUCKETS};
+use crate::type_code::{self, Category};
+use crate::{to_vec_with, DecodeError, DecodedValue, TypedArrayStrategy};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Reading {
+    sensor: String,
+    samples: Vec<f64>,
+    ok: bool,
+}
+
+#[test]
+fn test_instrumented_document_profile() {
+    let readings: Vec<Reading> = (0..3)
+        .map(|i| Reading { sensor: format!("s{i}"), samples: vec![0.5; 10], ok: i != 1 })
+        .collect();
+    let bytes = to_vec_with(&(readings, "x".repeat(100), 7, 1000), |cfg| {
+        cfg.records(true).typed_array_strategy(TypedArrayStrategy::Always)
+    })
+    .unwrap();
+
+    let mut decoder = InstrumentedDecoder::new(&bytes);
+    decoder.decode_document().unwrap();
+    let profile = decoder.into_profile();
+
+    // Every byte is attributed to exactly one value
+    assert_eq!(profile.total().bytes, bytes.len());
+    let stats = |code| profile.by_type_code.get(&code).copied().unwrap_or_default();
+    assert_eq!(stats(type_code::RECORD_DEF).count, 1);
+    assert_eq!(stats(type_code::RECORD_INSTANCE).count, 3);
+    assert_eq!(stats(type_code::TRUE).count + stats(type_code::FALSE).count, 3);
+    assert_eq!(stats(type_code::TYPED_ARRAY_FLOAT64).count, 3);
+    assert_eq!(stats(type_code::TYPED_ARRAY_FLOAT64).bytes, 3 * (2 + 10 * 8));
+    assert_eq!(stats(type_code::STRING_LONG).count, 1);
+    assert_eq!(profile.category(Category::ShortString).count, 3);
+    assert_eq!(stats(type_code::SMALLINT_MIN).count, 1);
+    assert_eq!(profile.category(Category::Int).count, 1);
+
+    let total = profile.total();
+    assert_eq!(total.time_buckets.len(), TIME_BUCKETS);
+    assert_eq!(total.time_buckets.iter().sum::<usize>(), total.count);
+    let text = profile.to_string();
+    assert!(text.contains("typed_array_float64"), "{text}");
+    assert!(text.contains("record_instance"), "{text}");
+}
+
+#[test]
+fn test_instrumented_events() {
+    let bytes = to_vec_with(&vec![1u16, 2, 3], |cfg| cfg.typed_array_strategy(TypedArrayStrategy::Always)).unwrap();
+    let mut decoder = InstrumentedDecoder::new(&bytes);
+    let DecodedValue::TypedArrayStart { element_type_code, count } = decoder.decode_value().unwrap() else {
+        panic!("expected a typed array");
+    };
+    // Not recorded until the array ends
+    assert!(decoder.profile().by_type_code.is_empty());
+    for _ in 0..count {
+        decoder.read_typed_array_element(element_type_code).unwrap();
+    }
+    decoder.end_typed_array().unwrap();
+    let stats = decoder.profile().category(Category::TypedArray);
+    assert_eq!((stats.count, stats.bytes), (1, bytes.len()));
+    assert_eq!(decoder.decoder().position(), bytes.len());
+
+    let bytes = crate::to_vec(&("a", "b")).unwrap();
+    let mut decoder = InstrumentedDecoder::new(&bytes[..bytes.len() - 1]);
+    assert_eq!(decoder.decode_document(), Err(DecodeError::Truncated));
+    assert_eq!(decoder.profile().category(Category::ShortString).count, 2);
+}
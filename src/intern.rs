@@ -0,0 +1,263 @@
+// ABOUTME: String interning for decoded trees: CompactValue, a Value whose strings, keys and containers
+// ABOUTME: are shared behind Arcs, and the Interner that deduplicates strings across documents.
+
+//! Deduplicating the strings of decoded documents.
+//!
+//! [`Value`] owns each string and key separately, so a tree that repeats the
+//! same keys and enum-like values thousands of times holds thousands of
+//! copies. [`Value::intern_strings`] turns it into a [`CompactValue`], whose
+//! strings and keys are `Arc<str>`s handed out by an [`Interner`]: each
+//! distinct string is stored once, however often it appears, and one
+//! interner can serve many documents.
+//!
+//! ```rust
+//! use serde_bonjson::intern::Interner;
+//! use serde_bonjson::bonjson;
+//! use std::sync::Arc;
+//!
+//! let mut interner = Interner::new();
+//! let first = bonjson!([{"level": "info"}, {"level": "info"}]).intern_strings(&mut interner);
+//! let second = bonjson!({"level": "info"}).intern_strings(&mut interner);
+//! assert_eq!(interner.len(), 2); // "level" and "info"
+//!
+//! let a = first.get(0).unwrap().get_key("level").unwrap().as_str_arc().unwrap();
+//! let b = second.get_key("level").unwrap().as_str_arc().unwrap();
+//! assert!(Arc::ptr_eq(a, b));
+//! assert_eq!(second.to_value(), bonjson!({"level": "info"}));
+//! ```
+
+use crate::error::Result;
+use crate::types::{BigNumber, FloatWidth};
+use crate::value::Value;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+/// A set of shared strings, handing out one `Arc<str>` per distinct string.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+    lookups: usize,
+}
+
+impl Interner {
+    /// Create an empty interner.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared copy of `s`, stored on first use.
+    pub fn intern(&mut self, s: &str) -> Arc<str> {
+        self.lookups += 1;
+        if let Some(shared) = self.strings.get(s) {
+            return Arc::clone(shared);
+        }
+        let shared: Arc<str> = Arc::from(s);
+        self.strings.insert(Arc::clone(&shared));
+        shared
+    }
+
+    /// Number of distinct strings stored.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Whether no strings are stored.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Number of strings interned, counting repeats; `lookups() - len()` were
+    /// served from an existing copy.
+    #[must_use]
+    pub fn lookups(&self) -> usize {
+        self.lookups
+    }
+
+    /// Total bytes of the distinct strings stored.
+    #[must_use]
+    pub fn stored_bytes(&self) -> usize {
+        self.strings.iter().map(|s| s.len()).sum()
+    }
+}
+
+/// A read-only [`Value`] whose strings, keys, arrays and objects are shared
+/// behind `Arc`s, so `clone()` is O(1) at any depth and clones can go to other
+/// threads.
+///
+/// Built by [`Value::intern_strings`], which also shares equal strings, or
+/// by `From<Value>`; [`to_value`](Self::to_value) converts back. It
+/// serializes exactly as the `Value` it came from. With the `rc` feature it's
+/// also available as [`SharedValue`](crate::shared::SharedValue).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum CompactValue {
+    /// JSON null
+    #[default]
+    Null,
+    /// JSON boolean
+    Bool(bool),
+    /// A signed 64-bit integer
+    Int(i64),
+    /// An unsigned 64-bit integer
+    UInt(u64),
+    /// A 64-bit floating point number
+    Float(f64),
+    /// A floating point number that encodes at exactly the given width; see [`Value::SizedFloat`]
+    SizedFloat(f64, FloatWidth),
+    /// An arbitrary-precision decimal number
+    BigNumber(BigNumber),
+    /// A shared UTF-8 string
+    String(Arc<str>),
+    /// A shared JSON array
+    Array(Arc<[CompactValue]>),
+    /// A shared JSON object with shared keys
+    Object(Arc<BTreeMap<Arc<str>, CompactValue>>),
+}
+
+impl CompactValue {
+    /// Decode a BONJSON document into a shared tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`decode_value`](crate::decode_value).
+    pub fn decode(data: &[u8]) -> Result<CompactValue> {
+        crate::decode_value(data).map(CompactValue::from)
+    }
+
+    /// Returns the string if this is a string.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            CompactValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the shared string if this is a string, for cloning or
+    /// comparing with `Arc::ptr_eq`.
+    #[must_use]
+    pub fn as_str_arc(&self) -> Option<&Arc<str>> {
+        match self {
+            CompactValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements if this is an array.
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[CompactValue]> {
+        match self {
+            CompactValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns the members if this is an object.
+    #[must_use]
+    pub fn as_object(&self) -> Option<&BTreeMap<Arc<str>, CompactValue>> {
+        match self {
+            CompactValue::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    /// Get an array element by index.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&CompactValue> {
+        self.as_array().and_then(|items| items.get(index))
+    }
+
+    /// Get an object member by key.
+    #[must_use]
+    pub fn get_key(&self, key: &str) -> Option<&CompactValue> {
+        self.as_object().and_then(|members| members.get(key))
+    }
+
+    /// Whether both are the same allocation (or equal scalars), without
+    /// comparing contents: true for clones of one another.
+    #[must_use]
+    pub fn ptr_eq(&self, other: &CompactValue) -> bool {
+        match (self, other) {
+            (CompactValue::String(a), CompactValue::String(b)) => Arc::ptr_eq(a, b),
+            (CompactValue::Array(a), CompactValue::Array(b)) => Arc::ptr_eq(a, b),
+            (CompactValue::Object(a), CompactValue::Object(b)) => Arc::ptr_eq(a, b),
+            _ => self == other,
+        }
+    }
+
+    /// Convert back to a [`Value`], copying every string.
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        match self {
+            CompactValue::Null => Value::Null,
+            CompactValue::Bool(b) => Value::Bool(*b),
+            CompactValue::Int(n) => Value::Int(*n),
+            CompactValue::UInt(n) => Value::UInt(*n),
+            CompactValue::Float(f) => Value::Float(*f),
+            CompactValue::SizedFloat(f, width) => Value::SizedFloat(*f, *width),
+            CompactValue::BigNumber(bn) => Value::BigNumber(*bn),
+            CompactValue::String(s) => Value::String(s.to_string()),
+            CompactValue::Array(items) => Value::Array(items.iter().map(CompactValue::to_value).collect()),
+            CompactValue::Object(members) => {
+                Value::Object(members.iter().map(|(k, v)| (k.to_string(), v.to_value())).collect())
+            }
+        }
+    }
+}
+
+/// Convert `value`, releasing each of its strings as the shared copy replaces it.
+pub(crate) fn intern_value(value: Value, interner: &mut Interner) -> CompactValue {
+    share_value(value, &mut |s| interner.intern(&s))
+}
+
+/// Convert `value`, making each string and key shared with `share`.
+fn share_value(value: Value, share: &mut impl FnMut(String) -> Arc<str>) -> CompactValue {
+    match value {
+        Value::Null => CompactValue::Null,
+        Value::Bool(b) => CompactValue::Bool(b),
+        Value::Int(n) => CompactValue::Int(n),
+        Value::UInt(n) => CompactValue::UInt(n),
+        Value::Float(f) => CompactValue::Float(f),
+        Value::SizedFloat(f, width) => CompactValue::SizedFloat(f, width),
+        Value::BigNumber(bn) => CompactValue::BigNumber(bn),
+        Value::String(s) => CompactValue::String(share(s)),
+        Value::Array(items) => CompactValue::Array(items.into_iter().map(|item| share_value(item, share)).collect()),
+        Value::Object(members) => CompactValue::Object(Arc::new(
+            members.into_iter().map(|(k, v)| (share(k), share_value(v, share))).collect(),
+        )),
+    }
+}
+
+impl From<Value> for CompactValue {
+    /// Convert without deduplicating strings; see [`Value::intern_strings`].
+    fn from(value: Value) -> Self {
+        share_value(value, &mut Arc::from)
+    }
+}
+
+impl From<&CompactValue> for Value {
+    fn from(value: &CompactValue) -> Self {
+        value.to_value()
+    }
+}
+
+impl Serialize for CompactValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            CompactValue::String(s) => serializer.serialize_str(s),
+            CompactValue::Array(items) => serializer.collect_seq(items.iter()),
+            CompactValue::Object(members) => serializer.collect_map(members.iter().map(|(k, v)| (&**k, v))),
+            // Scalars serialize as the matching Value does
+            CompactValue::Null => Value::Null.serialize(serializer),
+            CompactValue::Bool(b) => Value::Bool(*b).serialize(serializer),
+            CompactValue::Int(n) => Value::Int(*n).serialize(serializer),
+            CompactValue::UInt(n) => Value::UInt(*n).serialize(serializer),
+            CompactValue::Float(f) => Value::Float(*f).serialize(serializer),
+            CompactValue::SizedFloat(f, width) => Value::SizedFloat(*f, *width).serialize(serializer),
+            CompactValue::BigNumber(bn) => Value::BigNumber(*bn).serialize(serializer),
+        }
+    }
+}
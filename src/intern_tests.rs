@@ -0,0 +1,66 @@
+// ABOUTME: Unit tests for the intern module.
+// ABOUTME: Tests string sharing within and across documents, conversion back to Value, and serialization.
+
+use crate::intern::{CompactValue, Interner};
+use crate::types::BigNumber;
+use crate::{bonjson, decode_value, encode_value, to_vec, Value};
+use std::sync::Arc;
+
+#[test]
+fn test_intern_strings_shares_copies() {
+    let services: Vec<Value> = (0..100).map(|i| bonjson!({"name": "svc", "region": "eu-west", "port": i})).collect();
+    let value = Value::Array(services);
+    let mut interner = Interner::new();
+    let compact = value.clone().intern_strings(&mut interner);
+
+    assert_eq!(interner.len(), 5); // name, region, port, svc, eu-west
+    assert_eq!(interner.lookups(), 100 * 5);
+    assert_eq!(interner.stored_bytes(), "nameregionportsvceu-west".len());
+
+    let CompactValue::Object(first) = compact.get(0).unwrap() else { panic!("expected an object") };
+    let CompactValue::Object(last) = compact.get(99).unwrap() else { panic!("expected an object") };
+    let (key_a, key_b) = (first.keys().next().unwrap(), last.keys().next().unwrap());
+    assert!(Arc::ptr_eq(key_a, key_b));
+    let region = |v: &CompactValue| Arc::clone(v.get_key("region").unwrap().as_str_arc().unwrap());
+    assert!(Arc::ptr_eq(&region(compact.get(3).unwrap()), &region(compact.get(42).unwrap())));
+    assert_eq!(compact.get(7).unwrap().get_key("name").unwrap().as_str(), Some("svc"));
+    assert_eq!(compact.to_value(), value);
+
+    // The same interner shares strings with later documents
+    let other = bonjson!(["eu-west"]).intern_strings(&mut interner);
+    assert_eq!(interner.len(), 5);
+    assert!(Arc::ptr_eq(other.get(0).unwrap().as_str_arc().unwrap(), &region(compact.get(0).unwrap())));
+}
+
+#[test]
+fn test_compact_value_serializes_like_value() {
+    let mut value = bonjson!({
+        "ints": [1, 2, 3],
+        "mixed": [null, true, (-5), 1.5, "s"],
+        "nested": {"a": {"b": []}}
+    });
+    if let Value::Object(members) = &mut value {
+        members.insert("big".into(), Value::BigNumber(BigNumber::new(-1, 12345, -40)));
+        members.insert("max".into(), Value::UInt(u64::MAX));
+    }
+    let compact = value.clone().intern_strings(&mut Interner::new());
+    assert_eq!(to_vec(&compact).unwrap(), to_vec(&value).unwrap());
+    assert_eq!(decode_value(&to_vec(&compact).unwrap()).unwrap(), decode_value(&encode_value(&value).unwrap()).unwrap());
+    assert_eq!(Value::Null.intern_strings(&mut Interner::new()), CompactValue::Null);
+}
+
+#[test]
+fn test_compact_value_clones_share_storage() {
+    let value = bonjson!({"rows": [[1, 2], [3, 4]], "name": "batch"});
+    let compact = value.clone().intern_strings(&mut Interner::new());
+    let copy = compact.clone();
+    assert!(copy.ptr_eq(&compact));
+    assert!(copy.get_key("rows").unwrap().ptr_eq(compact.get_key("rows").unwrap()));
+
+    // Without an interner, equal strings stay separate
+    let plain = CompactValue::from(bonjson!(["x", "x"]));
+    assert!(!plain.get(0).unwrap().ptr_eq(plain.get(1).unwrap()));
+    assert_eq!(plain.as_array().map(<[CompactValue]>::len), Some(2));
+    assert_eq!(CompactValue::decode(&encode_value(&value).unwrap()).unwrap(), compact);
+    assert_eq!(Value::from(&compact), value);
+}
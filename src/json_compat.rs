@@ -0,0 +1,917 @@
+// ABOUTME: serde_json-named items (Number, Map, from_str, to_string, json!, Error categories)
+// ABOUTME: so code written against serde_json's wider API compiles after aliasing this module.
+
+//! A `serde_json`-shaped facade.
+//!
+//! The crate root already mirrors `serde_json`'s binary-relevant surface
+//! (`to_vec`, `from_slice`, `Value`, `json!`). This module adds the rest of
+//! the names that code commonly reaches for, so a crate can alias it and keep
+//! compiling:
+//!
+//! ```rust
+//! use serde_bonjson::json_compat as serde_json;
+//! use serde_json::{json, Map, Number};
+//!
+//! let mut map = Map::new();
+//! map.insert("n".to_string(), Number::from(7).into());
+//! let text = serde_json::to_string(&map).unwrap();
+//! assert_eq!(text, r#"{"n":7}"#);
+//!
+//! let back: serde_json::Value = serde_json::from_str(&text).unwrap();
+//! assert_eq!(back, json!({"n": 7}));
+//! ```
+//!
+//! `from_str` and `to_string` speak JSON text, exactly like their `serde_json`
+//! namesakes; they go through [`Value`] and are meant for interop, not speed.
+//! [`Error::line`] and [`Error::column`] are always 0 because BONJSON errors
+//! carry no text position.
+
+use crate::error::{Error, Result};
+use crate::tools::write_json_string;
+use crate::types::BigNumber;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::collections::{btree_map, BTreeMap};
+use std::fmt;
+use std::fmt::Write as _;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+pub use crate::json;
+pub use crate::value::Value;
+pub use crate::{from_reader, from_slice, from_value, to_value, to_vec, to_writer};
+
+/// Nesting limit for [`from_str`], matching `serde_json`'s recursion limit.
+const MAX_TEXT_DEPTH: usize = 128;
+
+// ============================================================================
+// Number
+// ============================================================================
+
+/// A JSON number, mirroring `serde_json::Number`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Number(N);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum N {
+    PosInt(u64),
+    NegInt(i64),
+    Float(f64),
+}
+
+impl Number {
+    /// Returns true if the number is an integer between `i64::MIN` and `i64::MAX`.
+    #[must_use]
+    pub fn is_i64(&self) -> bool {
+        match self.0 {
+            N::PosInt(n) => i64::try_from(n).is_ok(),
+            N::NegInt(_) => true,
+            N::Float(_) => false,
+        }
+    }
+
+    /// Returns true if the number is a non-negative integer.
+    #[must_use]
+    pub fn is_u64(&self) -> bool {
+        matches!(self.0, N::PosInt(_))
+    }
+
+    /// Returns true if the number is not an integer.
+    #[must_use]
+    pub fn is_f64(&self) -> bool {
+        matches!(self.0, N::Float(_))
+    }
+
+    /// Returns the number as an `i64` if it is an integer in range.
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.0 {
+            N::PosInt(n) => i64::try_from(n).ok(),
+            N::NegInt(n) => Some(n),
+            N::Float(_) => None,
+        }
+    }
+
+    /// Returns the number as a `u64` if it is a non-negative integer.
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.0 {
+            N::PosInt(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as an `f64`, rounding integers that don't fit exactly.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, clippy::unnecessary_wraps)]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.0 {
+            N::PosInt(n) => Some(n as f64),
+            N::NegInt(n) => Some(n as f64),
+            N::Float(f) => Some(f),
+        }
+    }
+
+    /// Returns the number as an `i128` if it is an integer.
+    #[must_use]
+    pub fn as_i128(&self) -> Option<i128> {
+        match self.0 {
+            N::PosInt(n) => Some(i128::from(n)),
+            N::NegInt(n) => Some(i128::from(n)),
+            N::Float(_) => None,
+        }
+    }
+
+    /// Returns the number as a `u128` if it is a non-negative integer.
+    #[must_use]
+    pub fn as_u128(&self) -> Option<u128> {
+        self.as_u64().map(u128::from)
+    }
+
+    /// Converts a finite `f64` to a number. Returns `None` for NaN and infinities,
+    /// which JSON cannot represent.
+    #[must_use]
+    pub fn from_f64(f: f64) -> Option<Number> {
+        f.is_finite().then_some(Number(N::Float(f)))
+    }
+
+    /// Converts an `i128` to a number if it fits in an `i64` or `u64`.
+    #[must_use]
+    pub fn from_i128(i: i128) -> Option<Number> {
+        if let Ok(n) = u64::try_from(i) {
+            Some(Number(N::PosInt(n)))
+        } else {
+            i64::try_from(i).ok().map(|n| Number(N::NegInt(n)))
+        }
+    }
+
+    /// Converts a `u128` to a number if it fits in a `u64`.
+    #[must_use]
+    pub fn from_u128(i: u128) -> Option<Number> {
+        u64::try_from(i).ok().map(|n| Number(N::PosInt(n)))
+    }
+}
+
+macro_rules! number_from_unsigned {
+    ($($t:ty)*) => {$(
+        impl From<$t> for Number {
+            fn from(n: $t) -> Self {
+                Number(N::PosInt(n as u64))
+            }
+        }
+    )*};
+}
+
+macro_rules! number_from_signed {
+    ($($t:ty)*) => {$(
+        impl From<$t> for Number {
+            #[allow(clippy::cast_sign_loss)]
+            fn from(n: $t) -> Self {
+                if n < 0 {
+                    Number(N::NegInt(n as i64))
+                } else {
+                    Number(N::PosInt(n as u64))
+                }
+            }
+        }
+    )*};
+}
+
+number_from_unsigned!(u8 u16 u32 u64 usize);
+number_from_signed!(i8 i16 i32 i64 isize);
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            N::PosInt(n) => write!(f, "{n}"),
+            N::NegInt(n) => write!(f, "{n}"),
+            N::Float(n) => write!(f, "{n:?}"),
+        }
+    }
+}
+
+impl FromStr for Number {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = TextParser::new(s);
+        let value = parser.parse_number()?;
+        parser.finish()?;
+        Number::try_from(&value)
+    }
+}
+
+impl From<Number> for Value {
+    fn from(n: Number) -> Self {
+        match n.0 {
+            N::PosInt(n) => Value::UInt(n),
+            N::NegInt(n) => Value::Int(n),
+            N::Float(f) => Value::Float(f),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Number {
+    type Error = Error;
+
+    /// Converts a numeric [`Value`]. BigNumbers convert to an integer when
+    /// exact and to the nearest `f64` otherwise.
+    fn try_from(value: &Value) -> Result<Self> {
+        let number = match value {
+            Value::Int(n) => Number::from(*n),
+            Value::UInt(n) => Number::from(*n),
+            Value::Float(f) | Value::SizedFloat(f, _) => Number::from_f64(*f).ok_or(Error::ValueOutOfRange)?,
+            Value::BigNumber(bn) => match bn.to_i128().and_then(Number::from_i128) {
+                Some(n) => n,
+                None => Number::from_f64(bn.to_f64()).ok_or(Error::ValueOutOfRange)?,
+            },
+            _ => return Err(Error::Custom("expected a number".into())),
+        };
+        Ok(number)
+    }
+}
+
+impl Serialize for Number {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self.0 {
+            N::PosInt(n) => serializer.serialize_u64(n),
+            N::NegInt(n) => serializer.serialize_i64(n),
+            N::Float(f) => serializer.serialize_f64(f),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Number {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Number::try_from(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+// ============================================================================
+// Map
+// ============================================================================
+
+/// A JSON object with `serde_json::Map`'s method signatures.
+///
+/// Keys are kept sorted, like `serde_json` without its `preserve_order`
+/// feature. Converts to and from [`Value::Object`]'s `BTreeMap` for free.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Map<K = String, V = Value> {
+    map: BTreeMap<K, V>,
+}
+
+impl Map<String, Value> {
+    /// Makes a new empty map.
+    #[must_use]
+    pub fn new() -> Self {
+        Map { map: BTreeMap::new() }
+    }
+
+    /// Makes a new empty map. The capacity is ignored, as for a sorted `serde_json::Map`.
+    #[must_use]
+    pub fn with_capacity(_capacity: usize) -> Self {
+        Self::new()
+    }
+
+    /// Clears the map, removing all entries.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Returns a reference to the value for the key.
+    pub fn get<Q: ?Sized + Ord>(&self, key: &Q) -> Option<&Value>
+    where
+        String: Borrow<Q>,
+    {
+        self.map.get(key)
+    }
+
+    /// Returns true if the map contains a value for the key.
+    pub fn contains_key<Q: ?Sized + Ord>(&self, key: &Q) -> bool
+    where
+        String: Borrow<Q>,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Returns a mutable reference to the value for the key.
+    pub fn get_mut<Q: ?Sized + Ord>(&mut self, key: &Q) -> Option<&mut Value>
+    where
+        String: Borrow<Q>,
+    {
+        self.map.get_mut(key)
+    }
+
+    /// Returns the key-value pair for the key.
+    pub fn get_key_value<Q: ?Sized + Ord>(&self, key: &Q) -> Option<(&String, &Value)>
+    where
+        String: Borrow<Q>,
+    {
+        self.map.get_key_value(key)
+    }
+
+    /// Inserts a key-value pair, returning the value previously stored under the key.
+    pub fn insert(&mut self, k: String, v: Value) -> Option<Value> {
+        self.map.insert(k, v)
+    }
+
+    /// Removes a key, returning its value.
+    pub fn remove<Q: ?Sized + Ord>(&mut self, key: &Q) -> Option<Value>
+    where
+        String: Borrow<Q>,
+    {
+        self.map.remove(key)
+    }
+
+    /// Removes a key, returning the stored key and value.
+    pub fn remove_entry<Q: ?Sized + Ord>(&mut self, key: &Q) -> Option<(String, Value)>
+    where
+        String: Borrow<Q>,
+    {
+        self.map.remove_entry(key)
+    }
+
+    /// Moves all entries from `other` into `self`, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        self.map.append(&mut other.map);
+    }
+
+    /// Gets the entry for the key, for in-place manipulation.
+    pub fn entry<S: Into<String>>(&mut self, key: S) -> btree_map::Entry<'_, String, Value> {
+        self.map.entry(key.into())
+    }
+
+    /// Returns the number of entries.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the map has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterates over the entries in key order.
+    pub fn iter(&self) -> btree_map::Iter<'_, String, Value> {
+        self.map.iter()
+    }
+
+    /// Iterates mutably over the entries in key order.
+    pub fn iter_mut(&mut self) -> btree_map::IterMut<'_, String, Value> {
+        self.map.iter_mut()
+    }
+
+    /// Iterates over the keys in order.
+    pub fn keys(&self) -> btree_map::Keys<'_, String, Value> {
+        self.map.keys()
+    }
+
+    /// Iterates over the values in key order.
+    pub fn values(&self) -> btree_map::Values<'_, String, Value> {
+        self.map.values()
+    }
+
+    /// Iterates mutably over the values in key order.
+    pub fn values_mut(&mut self) -> btree_map::ValuesMut<'_, String, Value> {
+        self.map.values_mut()
+    }
+
+    /// Consumes the map, yielding its values in key order.
+    pub fn into_values(self) -> btree_map::IntoValues<String, Value> {
+        self.map.into_values()
+    }
+
+    /// Keeps only the entries for which `f` returns true.
+    pub fn retain<F: FnMut(&String, &mut Value) -> bool>(&mut self, f: F) {
+        self.map.retain(f);
+    }
+
+    /// Sorts the keys. A no-op, since the map is always sorted.
+    pub fn sort_keys(&mut self) {}
+}
+
+impl<Q: ?Sized + Ord> Index<&Q> for Map<String, Value>
+where
+    String: Borrow<Q>,
+{
+    type Output = Value;
+
+    fn index(&self, key: &Q) -> &Value {
+        self.map.index(key)
+    }
+}
+
+impl<Q: ?Sized + Ord> IndexMut<&Q> for Map<String, Value>
+where
+    String: Borrow<Q>,
+{
+    fn index_mut(&mut self, key: &Q) -> &mut Value {
+        self.map.get_mut(key).expect("no entry found for key")
+    }
+}
+
+impl IntoIterator for Map<String, Value> {
+    type Item = (String, Value);
+    type IntoIter = btree_map::IntoIter<String, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Map<String, Value> {
+    type Item = (&'a String, &'a Value);
+    type IntoIter = btree_map::Iter<'a, String, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Map<String, Value> {
+    type Item = (&'a String, &'a mut Value);
+    type IntoIter = btree_map::IterMut<'a, String, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter_mut()
+    }
+}
+
+impl FromIterator<(String, Value)> for Map<String, Value> {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Self {
+        Map { map: iter.into_iter().collect() }
+    }
+}
+
+impl Extend<(String, Value)> for Map<String, Value> {
+    fn extend<I: IntoIterator<Item = (String, Value)>>(&mut self, iter: I) {
+        self.map.extend(iter);
+    }
+}
+
+impl From<BTreeMap<String, Value>> for Map<String, Value> {
+    fn from(map: BTreeMap<String, Value>) -> Self {
+        Map { map }
+    }
+}
+
+impl From<Map<String, Value>> for BTreeMap<String, Value> {
+    fn from(map: Map<String, Value>) -> Self {
+        map.map
+    }
+}
+
+impl From<Map<String, Value>> for Value {
+    fn from(map: Map<String, Value>) -> Self {
+        Value::Object(map.map)
+    }
+}
+
+impl Serialize for Map<String, Value> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.map.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Map<String, Value> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        BTreeMap::deserialize(deserializer).map(|map| Map { map })
+    }
+}
+
+// ============================================================================
+// JSON text
+// ============================================================================
+
+/// Deserialize a value from JSON text.
+///
+/// Unlike `serde_json::from_str`, the target can't borrow from the input
+/// because the text is parsed into a [`Value`] first.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidData`] (naming the line and column) for malformed
+/// text, [`Error::Truncated`] if the text ends early, and any error from
+/// [`from_value`].
+pub fn from_str<T: DeserializeOwned>(s: &str) -> Result<T> {
+    let mut parser = TextParser::new(s);
+    parser.skip_whitespace();
+    let value = parser.parse_value(0)?;
+    parser.finish()?;
+    from_value(&value)
+}
+
+/// Serialize a value to compact JSON text.
+///
+/// BigNumbers are written as JSON numbers in exponent notation. Unlike
+/// `serde_json`, NaN and infinities are errors rather than `null`, because
+/// the value passes through the BONJSON encoder.
+///
+/// # Errors
+///
+/// Returns any error from [`to_value`].
+pub fn to_string<T: Serialize>(value: &T) -> Result<String> {
+    let mut out = String::new();
+    write_text(&mut out, &to_value(value)?, None, 0);
+    Ok(out)
+}
+
+/// Serialize a value to JSON text indented by two spaces, like
+/// `serde_json::to_string_pretty`.
+///
+/// # Errors
+///
+/// Returns any error from [`to_value`].
+pub fn to_string_pretty<T: Serialize>(value: &T) -> Result<String> {
+    let mut out = String::new();
+    write_text(&mut out, &to_value(value)?, Some("  "), 0);
+    Ok(out)
+}
+
+fn write_newline(out: &mut String, indent: Option<&str>, depth: usize) {
+    if let Some(indent) = indent {
+        out.push('\n');
+        for _ in 0..depth {
+            out.push_str(indent);
+        }
+    }
+}
+
+fn write_text(out: &mut String, value: &Value, indent: Option<&str>, depth: usize) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => {
+            let _ = write!(out, "{b}");
+        }
+        Value::Int(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Value::UInt(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Value::Float(f) | Value::SizedFloat(f, _) if f.is_finite() => {
+            let _ = write!(out, "{f:?}");
+        }
+        Value::Float(_) | Value::SizedFloat(..) => out.push_str("null"),
+        Value::BigNumber(bn) => out.push_str(&bn.to_string_notation()),
+        Value::String(s) => write_json_string(out, s),
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_newline(out, indent, depth + 1);
+                write_text(out, item, indent, depth + 1);
+            }
+            write_newline(out, indent, depth);
+            out.push(']');
+        }
+        Value::Object(map) if map.is_empty() => out.push_str("{}"),
+        Value::Object(map) => {
+            out.push('{');
+            for (i, (k, v)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_newline(out, indent, depth + 1);
+                write_json_string(out, k);
+                out.push_str(if indent.is_some() { ": " } else { ":" });
+                write_text(out, v, indent, depth + 1);
+            }
+            write_newline(out, indent, depth);
+            out.push('}');
+        }
+    }
+}
+
+struct TextParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> TextParser<'a> {
+    fn new(text: &'a str) -> Self {
+        TextParser { text, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<u8> {
+        let b = self.peek().ok_or(Error::Truncated)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn error(&self, what: &str) -> Error {
+        let before = &self.text[..self.pos.min(self.text.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        Error::InvalidData(format!("{what} at line {line} column {column}"))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\n' | b'\r' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.skip_whitespace();
+        if self.pos < self.text.len() {
+            return Err(self.error("trailing characters"));
+        }
+        Ok(())
+    }
+
+    fn expect_literal(&mut self, literal: &str, value: Value) -> Result<Value> {
+        let end = self.pos + literal.len();
+        match self.text.get(self.pos..end) {
+            Some(s) if s == literal => {
+                self.pos = end;
+                Ok(value)
+            }
+            _ if end > self.text.len() && literal.starts_with(&self.text[self.pos..]) => Err(Error::Truncated),
+            _ => Err(self.error("expected value")),
+        }
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Value> {
+        match self.peek().ok_or(Error::Truncated)? {
+            b'n' => self.expect_literal("null", Value::Null),
+            b't' => self.expect_literal("true", Value::Bool(true)),
+            b'f' => self.expect_literal("false", Value::Bool(false)),
+            b'"' => self.parse_string().map(Value::String),
+            b'-' | b'0'..=b'9' => self.parse_number(),
+            b'[' | b'{' if depth >= MAX_TEXT_DEPTH => Err(self.error("recursion limit exceeded")),
+            b'[' => self.parse_array(depth),
+            b'{' => self.parse_object(depth),
+            _ => Err(self.error("expected value")),
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<Value> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            self.skip_whitespace();
+            items.push(self.parse_value(depth + 1)?);
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => {}
+                b']' => return Ok(Value::Array(items)),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("expected `,` or `]`"));
+                }
+            }
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<Value> {
+        self.pos += 1;
+        let mut map = BTreeMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(map));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek().ok_or(Error::Truncated)? != b'"' {
+                return Err(self.error("key must be a string"));
+            }
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            if self.next()? != b':' {
+                self.pos -= 1;
+                return Err(self.error("expected `:`"));
+            }
+            self.skip_whitespace();
+            // Later duplicates win, as in serde_json
+            map.insert(key, self.parse_value(depth + 1)?);
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => {}
+                b'}' => return Ok(Value::Object(map)),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("expected `,` or `}`"));
+                }
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u16> {
+        let digits = self.text.as_bytes().get(self.pos..self.pos + 4).ok_or(Error::Truncated)?;
+        let mut n = 0u16;
+        for &b in digits {
+            let digit = char::from(b).to_digit(16).ok_or_else(|| self.error("invalid escape"))?;
+            n = n << 4 | digit as u16;
+        }
+        self.pos += 4;
+        Ok(n)
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let start = self.pos;
+            while !matches!(self.peek(), None | Some(b'"' | b'\\' | 0..=0x1F)) {
+                self.pos += 1;
+            }
+            out.push_str(&self.text[start..self.pos]);
+            match self.next()? {
+                b'"' => return Ok(out),
+                b'\\' => {
+                    let c = match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.parse_unicode_escape()?,
+                        _ => {
+                            self.pos -= 1;
+                            return Err(self.error("invalid escape"));
+                        }
+                    };
+                    out.push(c);
+                }
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("control character in string"));
+                }
+            }
+        }
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char> {
+        let high = self.parse_hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if self.text.get(self.pos..self.pos + 2) != Some("\\u") {
+                return Err(self.error("unpaired surrogate"));
+            }
+            self.pos += 2;
+            let low = self.parse_hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((u32::from(high) - 0xD800) << 10) + (u32::from(low) - 0xDC00)
+        } else {
+            u32::from(high)
+        };
+        char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate"))
+    }
+
+    /// Integers become `Int`/`UInt`, or an exact `BigNumber` past 64 bits;
+    /// everything else becomes `Float`, as `serde_json` does by default.
+    fn parse_number(&mut self) -> Result<Value> {
+        let start = self.pos;
+        let bytes = self.text.as_bytes();
+        let digits = |pos: &mut usize| {
+            let from = *pos;
+            while bytes.get(*pos).is_some_and(u8::is_ascii_digit) {
+                *pos += 1;
+            }
+            *pos - from
+        };
+        let mut pos = self.pos;
+        if bytes.get(pos) == Some(&b'-') {
+            pos += 1;
+        }
+        let int_digits = digits(&mut pos);
+        let leading_zero = int_digits > 1 && bytes[pos - int_digits] == b'0';
+        let mut integral = true;
+        let mut valid = int_digits > 0 && !leading_zero;
+        if bytes.get(pos) == Some(&b'.') {
+            pos += 1;
+            integral = false;
+            valid &= digits(&mut pos) > 0;
+        }
+        if matches!(bytes.get(pos), Some(b'e' | b'E')) {
+            pos += 1;
+            if matches!(bytes.get(pos), Some(b'+' | b'-')) {
+                pos += 1;
+            }
+            integral = false;
+            valid &= digits(&mut pos) > 0;
+        }
+        self.pos = pos;
+        if !valid {
+            return Err(if pos == bytes.len() { Error::Truncated } else { self.error("invalid number") });
+        }
+
+        let text = &self.text[start..pos];
+        if integral {
+            if let Ok(n) = text.parse::<u64>() {
+                return Ok(Value::UInt(n));
+            }
+            if let Ok(n) = text.parse::<i64>() {
+                return Ok(Value::Int(n));
+            }
+            if let Some(bn) = text.parse::<i128>().ok().and_then(BigNumber::from_i128) {
+                return Ok(Value::BigNumber(bn));
+            }
+        }
+        match text.parse::<f64>() {
+            Ok(f) if f.is_finite() => Ok(Value::Float(f)),
+            _ => Err(self.error("number out of range")),
+        }
+    }
+}
+
+// ============================================================================
+// Error
+// ============================================================================
+
+/// The broad class of an [`Error`], mirroring `serde_json::error::Category`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    /// Failure to read or write bytes on an IO stream.
+    Io,
+    /// Input that is not a well-formed document.
+    Syntax,
+    /// A well-formed document that doesn't fit the target type or the configured limits.
+    Data,
+    /// Input that ended before the document was complete.
+    Eof,
+}
+
+impl Error {
+    /// Always 0: BONJSON errors have no line. Kept for `serde_json` compatibility.
+    #[must_use]
+    pub fn line(&self) -> usize {
+        0
+    }
+
+    /// Always 0: BONJSON errors have no column. Kept for `serde_json` compatibility.
+    #[must_use]
+    pub fn column(&self) -> usize {
+        0
+    }
+
+    /// Classifies the error the way `serde_json::Error::classify` does.
+    #[must_use]
+    pub fn classify(&self) -> Category {
+        match self {
+            Error::Io(_) => Category::Io,
+            Error::Truncated => Category::Eof,
+            Error::TrailingBytes(_)
+            | Error::InvalidTypeCode(_)
+            | Error::InvalidUtf8
+            | Error::NulCharacter
+            | Error::UnclosedContainer
+            | Error::InvalidObjectKey
+            | Error::ExpectedObjectKey
+            | Error::ExpectedObjectValue => Category::Syntax,
+            _ => Category::Data,
+        }
+    }
+
+    /// Shorthand for [`Error::classify`], for code that calls it `category`.
+    #[must_use]
+    pub fn category(&self) -> Category {
+        self.classify()
+    }
+
+    /// Returns true if the error came from reading or writing an IO stream.
+    #[must_use]
+    pub fn is_io(&self) -> bool {
+        self.classify() == Category::Io
+    }
+
+    /// Returns true if the input was not a well-formed document.
+    #[must_use]
+    pub fn is_syntax(&self) -> bool {
+        self.classify() == Category::Syntax
+    }
+
+    /// Returns true if the document was well-formed but didn't fit the target.
+    #[must_use]
+    pub fn is_data(&self) -> bool {
+        self.classify() == Category::Data
+    }
+
+    /// Returns true if the input ended early.
+    #[must_use]
+    pub fn is_eof(&self) -> bool {
+        self.classify() == Category::Eof
+    }
+}
@@ -0,0 +1,136 @@
+// ABOUTME: Unit tests for the json_compat module.
+// ABOUTME: Tests Number, Map, JSON text round trips, and Error classification.
+
+use crate::json_compat::{self as serde_json, Category, Map, Number};
+use crate::{bonjson, BigNumber, Error, Value};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Config {
+    name: String,
+    ports: Vec<u16>,
+    ratio: f64,
+    extra: Option<Map>,
+}
+
+#[test]
+fn test_number() {
+    let n = Number::from(-5i32);
+    assert!(n.is_i64() && !n.is_u64() && !n.is_f64());
+    assert_eq!((n.as_i64(), n.as_u64(), n.as_f64()), (Some(-5), None, Some(-5.0)));
+
+    let n = Number::from(u64::MAX);
+    assert!(!n.is_i64() && n.is_u64());
+    assert_eq!(n.as_i64(), None);
+    assert_eq!(n.to_string(), u64::MAX.to_string());
+
+    assert_eq!(Number::from_f64(f64::NAN), None);
+    assert_eq!(Number::from_f64(1.5).unwrap().to_string(), "1.5");
+    assert_eq!(Number::from_i128(-1).unwrap().as_i64(), Some(-1));
+    assert_eq!(Number::from_i128(i128::MAX), None);
+    assert_eq!("2.0".parse::<Number>().unwrap(), Number::from_f64(2.0).unwrap());
+    assert!("2 3".parse::<Number>().is_err());
+
+    assert_eq!(Value::from(Number::from(7u8)), Value::UInt(7));
+    let big = Value::BigNumber(BigNumber::new(1, 12, 3));
+    assert_eq!(Number::try_from(&big).unwrap(), Number::from(12000));
+    assert!(Number::try_from(&Value::Null).is_err());
+
+    let bytes = crate::to_vec(&Number::from(-3)).unwrap();
+    assert_eq!(crate::from_slice::<Number>(&bytes).unwrap(), Number::from(-3));
+}
+
+#[test]
+fn test_map() {
+    let mut map = Map::with_capacity(4);
+    assert!(map.is_empty());
+    map.insert("b".into(), bonjson!(2));
+    map.insert("a".into(), bonjson!(1));
+    *map.entry("c").or_insert(Value::Null) = bonjson!(3);
+    assert_eq!(map.keys().map(String::as_str).collect::<Vec<_>>(), ["a", "b", "c"]);
+    assert_eq!(map["a"], bonjson!(1));
+    assert!(map.contains_key("b"));
+    map["b"] = bonjson!(20);
+    assert_eq!(map.get("b"), Some(&bonjson!(20)));
+
+    map.retain(|k, _| k != "c");
+    assert_eq!(map.remove_entry("a"), Some(("a".into(), bonjson!(1))));
+    let mut other: Map = [("z".to_string(), bonjson!(true))].into_iter().collect();
+    map.append(&mut other);
+    assert!(other.is_empty());
+    map.sort_keys();
+    assert_eq!(Value::from(map.clone()), bonjson!({"b": 20, "z": true}));
+
+    let bytes = crate::to_vec(&map).unwrap();
+    assert_eq!(crate::from_slice::<Map>(&bytes).unwrap(), map);
+    assert_eq!(map.into_values().count(), 2);
+}
+
+#[test]
+fn test_text_round_trip() {
+    let config = Config {
+        name: "svc \"a\"\n".into(),
+        ports: vec![80, 443],
+        ratio: 0.25,
+        extra: Some([("k".to_string(), bonjson!([null, false]))].into_iter().collect()),
+    };
+    let text = serde_json::to_string(&config).unwrap();
+    assert_eq!(
+        text,
+        r#"{"extra":{"k":[null,false]},"name":"svc \"a\"\n","ports":[80,443],"ratio":0.25}"#
+    );
+    assert_eq!(serde_json::from_str::<Config>(&text).unwrap(), config);
+
+    let pretty = serde_json::to_string_pretty(&bonjson!({"a": [1, {"x": null}], "b": []})).unwrap();
+    assert_eq!(pretty, "{\n  \"a\": [\n    1,\n    {\n      \"x\": null\n    }\n  ],\n  \"b\": []\n}");
+    assert_eq!(serde_json::from_str::<Value>(&pretty).unwrap(), bonjson!({"a": [1, {"x": null}], "b": []}));
+
+    assert_eq!(serde_json::to_string(&f64::NAN), Err(Error::NanNotAllowed));
+}
+
+#[test]
+fn test_from_str_values() {
+    let value: Value = serde_json::from_str(
+        r#" {"s": "\u00e9\ud83d\ude00\/", "n": [-1, 18446744073709551615, 100000000000000000000, 1e2, -0.5]} "#,
+    )
+    .unwrap();
+    assert_eq!(value.get_key("s").and_then(Value::as_str), Some("é😀/"));
+    let numbers = value.get_key("n").and_then(Value::as_array).unwrap();
+    assert_eq!(numbers[0], Value::Int(-1));
+    assert_eq!(numbers[1], Value::UInt(u64::MAX));
+    // Exact as a BigNumber in the parsed text, and through Value's serde form
+    assert_eq!(numbers[2], Value::BigNumber(BigNumber::new(1, 10_000_000_000_000_000_000, 1)));
+    assert_eq!(numbers[3].as_f64(), Some(100.0));
+    assert_eq!(numbers[4], Value::Float(-0.5));
+
+    let nested = "[".repeat(200) + &"]".repeat(200);
+    assert!(serde_json::from_str::<Value>(&nested).is_err());
+}
+
+#[test]
+fn test_from_str_errors() {
+    let err = serde_json::from_str::<Value>("{\n  \"a\": tru}").unwrap_err();
+    assert_eq!(err, Error::InvalidData("expected value at line 2 column 8".into()));
+    assert!(err.is_data());
+
+    for truncated in ["", "[1,", "{\"a\"", "\"abc", "tr", "-"] {
+        let err = serde_json::from_str::<Value>(truncated).unwrap_err();
+        assert!(err.is_eof(), "{truncated:?}: {err}");
+    }
+    for malformed in ["01", "[1 2]", "{1: 2}", "\"\\x\"", "\"\\ud800\"", "1 2", "\"a\tb\""] {
+        assert!(serde_json::from_str::<Value>(malformed).is_err(), "{malformed:?}");
+    }
+}
+
+#[test]
+fn test_error_classification() {
+    assert_eq!(Error::Io("broken pipe".into()).classify(), Category::Io);
+    assert_eq!(Error::Truncated.category(), Category::Eof);
+    assert_eq!(Error::InvalidTypeCode(0xC0).classify(), Category::Syntax);
+    assert_eq!(Error::ValueOutOfRange.classify(), Category::Data);
+
+    let err = crate::from_slice::<u8>(&[0x01, 0x02]).unwrap_err();
+    assert!(err.is_syntax());
+    assert!(crate::from_slice::<u8>(&[0x65]).unwrap_err().is_data());
+    assert_eq!((err.line(), err.column()), (0, 0));
+}
@@ -0,0 +1,926 @@
+// ABOUTME: JSONPath locations and queries over Value trees and raw BONJSON bytes.
+// ABOUTME: A JsonPath is a concrete location (`$.a.b[0]`); a Query selects values (`$.a[*].b`).
+
+use crate::decoder::{DecodedValue, Decoder, DecoderConfig, DuplicateKeyMode, RecordMismatchMode};
+use crate::error::{Error, Result};
+use crate::value::Value;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// One step in a [`JsonPath`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PathSegment {
+    /// An object member, by key.
+    Key(String),
+    /// An array element, by index.
+    Index(usize),
+}
+
+/// The location of a value within a document, relative to its root.
+///
+/// Displays in JSONPath notation: `$` for the root, `.key` for identifier-like
+/// keys, `['key']` for any other key, and `[n]` for array indices.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JsonPath {
+    segments: Vec<PathSegment>,
+}
+
+impl JsonPath {
+    /// The path of the document root.
+    #[must_use] pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if this is the root path.
+    #[must_use] pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the segments of this path, outermost first.
+    #[must_use] pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Returns the number of segments (the nesting depth below the root).
+    #[must_use] pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns true if this path has no segments. Equivalent to [`is_root`](Self::is_root).
+    #[must_use] pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the last segment, or None for the root.
+    #[must_use] pub fn last(&self) -> Option<&PathSegment> {
+        self.segments.last()
+    }
+
+    /// Returns the path of the containing value, or None for the root.
+    #[must_use] pub fn parent(&self) -> Option<JsonPath> {
+        let (_, rest) = self.segments.split_last()?;
+        Some(JsonPath { segments: rest.to_vec() })
+    }
+
+    /// Append an object key.
+    pub fn push_key(&mut self, key: impl Into<String>) {
+        self.segments.push(PathSegment::Key(key.into()));
+    }
+
+    /// Append an array index.
+    pub fn push_index(&mut self, index: usize) {
+        self.segments.push(PathSegment::Index(index));
+    }
+
+    /// Prepend a segment, for building a path up from the innermost value.
+    pub(crate) fn push_front(&mut self, segment: PathSegment) {
+        self.segments.insert(0, segment);
+    }
+
+    /// Remove and return the last segment.
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+
+    /// Returns a new path with an object key appended.
+    #[must_use] pub fn child_key(&self, key: impl Into<String>) -> JsonPath {
+        let mut path = self.clone();
+        path.push_key(key);
+        path
+    }
+
+    /// Returns a new path with an array index appended.
+    #[must_use] pub fn child_index(&self, index: usize) -> JsonPath {
+        let mut path = self.clone();
+        path.push_index(index);
+        path
+    }
+
+    /// Returns true if `self` is `other` or lies beneath it.
+    #[must_use] pub fn starts_with(&self, other: &JsonPath) -> bool {
+        self.segments.starts_with(&other.segments)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = Error;
+
+    /// Parse a concrete path such as `$.metrics['cpu load'][0]`.
+    ///
+    /// Only member and index steps are allowed; wildcards, filters and
+    /// recursive descent need a [`Query`].
+    fn from_str(s: &str) -> Result<JsonPath> {
+        let mut parser = Parser { src: s, pos: 0 };
+        let segments = parser
+            .parse_query()?
+            .into_iter()
+            .map(|step| match step {
+                Step { selector: Selector::Key(k), descendant: false } => Ok(PathSegment::Key(k)),
+                Step { selector: Selector::Index(i), descendant: false } => Ok(PathSegment::Index(i)),
+                _ => Err(Error::InvalidQuery(format!(
+                    "{s} is not a concrete path (wildcards, filters and '..' need a Query)"
+                ))),
+            })
+            .collect::<Result<_>>()?;
+        Ok(JsonPath { segments })
+    }
+}
+
+impl From<Vec<PathSegment>> for JsonPath {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        JsonPath { segments }
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "$")?;
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) if is_identifier(key) => write!(f, ".{key}")?,
+                PathSegment::Key(key) => {
+                    write!(f, "['")?;
+                    for c in key.chars() {
+                        match c {
+                            '\'' => write!(f, "\\'")?,
+                            '\\' => write!(f, "\\\\")?,
+                            c => write!(f, "{c}")?,
+                        }
+                    }
+                    write!(f, "']")?;
+                }
+                PathSegment::Index(i) => write!(f, "[{i}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// A parsed JSONPath query.
+///
+/// Supports a practical subset of JSONPath:
+///
+/// | Syntax | Meaning |
+/// |--------|---------|
+/// | `$` | The document root |
+/// | `.name`, `['name']` | Object member |
+/// | `[n]` | Array element (non-negative index) |
+/// | `.*`, `[*]` | All members or elements |
+/// | `..name`, `..*`, `..[n]` | Recursive descent |
+/// | `[?(expr)]`, `[?expr]` | Members or elements for which `expr` holds |
+///
+/// Filter expressions compare `@`-relative paths (`@.price`, `@[0]`, `@`) and
+/// literals (numbers, quoted strings, `true`, `false`, `null`) with `==`, `!=`,
+/// `<`, `<=`, `>`, `>=`, combined with `&&`, `||`, `!` and parentheses. A bare
+/// path such as `[?(@.isbn)]` tests for existence.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{bonjson, encode_value};
+/// use serde_bonjson::jsonpath::Query;
+///
+/// let doc = bonjson!({"store": {"book": [
+///     {"author": "Rees", "price": 8.95},
+///     {"author": "Waugh", "price": 12.99}
+/// ]}});
+/// let query: Query = "$.store.book[?(@.price < 10)].author".parse().unwrap();
+///
+/// let found = query.select(&doc);
+/// assert_eq!(found[0].1, &bonjson!("Rees"));
+///
+/// // The same query runs directly over encoded bytes
+/// let bytes = encode_value(&doc).unwrap();
+/// let found = query.select_bytes(&bytes).unwrap();
+/// assert_eq!(found[0].0.to_string(), "$.store.book[0].author");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    selector: Selector,
+    /// Recursive descent: the selector applies at any depth below.
+    descendant: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Key(String),
+    Index(usize),
+    Wildcard,
+    Filter(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Exists(Vec<PathSegment>),
+    Compare(Operand, CompareOp, Operand),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Current(Vec<PathSegment>),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// The position of a child within its parent, for selector matching.
+#[derive(Clone, Copy)]
+enum ChildPos<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+impl Query {
+    /// Parse a query string.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidQuery`] if the query is malformed or uses
+    /// unsupported syntax.
+    pub fn parse(query: &str) -> Result<Query> {
+        let mut parser = Parser { src: query, pos: 0 };
+        let steps = parser.parse_query()?;
+        Ok(Query { steps })
+    }
+
+    /// Select matching values from a `Value` tree, in the tree's order.
+    #[must_use] pub fn select<'a>(&self, value: &'a Value) -> Vec<(JsonPath, &'a Value)> {
+        let mut out = Vec::new();
+        self.visit(value, &mut JsonPath::root(), &[0], &mut out);
+        out
+    }
+
+    /// Select matching values directly from a BONJSON document, in encoded order.
+    ///
+    /// Only the matched values (and array elements or members tested by
+    /// filters) are decoded into `Value`s; everything else is skipped over.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`decode_value`](crate::decode_value).
+    pub fn select_bytes(&self, data: &[u8]) -> Result<Vec<(JsonPath, Value)>> {
+        self.select_bytes_with_config(data, DecoderConfig::default())
+    }
+
+    /// Select matching values from a BONJSON document with custom configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`decode_value_with_config`](crate::decode_value_with_config).
+    pub fn select_bytes_with_config(
+        &self,
+        data: &[u8],
+        config: DecoderConfig,
+    ) -> Result<Vec<(JsonPath, Value)>> {
+        let mut decoder = Decoder::with_config(data, config);
+        decoder.check_document_size()?;
+        decoder.read_record_definitions()?;
+        let mut out = Vec::new();
+        let mut path = JsonPath::root();
+        if self.steps.is_empty() {
+            out.push((path, crate::decode_value_recursive(&mut decoder)?));
+        } else {
+            let event = decoder.decode_value()?;
+            self.stream_container(&mut decoder, event, &mut path, &[0], &mut out)?;
+        }
+        decoder.finish()?;
+        Ok(out)
+    }
+
+    /// Compute the states of a child, given its parent's states.
+    ///
+    /// A state is the number of steps matched so far; a value whose states
+    /// include `steps.len()` is a result. `child` is only needed by filters.
+    fn child_states(&self, states: &[usize], pos: ChildPos<'_>, child: Option<&Value>) -> Vec<usize> {
+        let mut next = Vec::new();
+        for &state in states {
+            let Some(step) = self.steps.get(state) else { continue };
+            if step.descendant {
+                next.push(state);
+            }
+            if step.selector.matches(pos, child) {
+                next.push(state + 1);
+            }
+        }
+        next.sort_unstable();
+        next.dedup();
+        next
+    }
+
+    /// Returns true if any pending step filters on the children's values.
+    fn filters_children(&self, states: &[usize]) -> bool {
+        states.iter().any(|&state| {
+            matches!(self.steps.get(state), Some(Step { selector: Selector::Filter(_), .. }))
+        })
+    }
+
+    fn visit<'a>(
+        &self,
+        value: &'a Value,
+        path: &mut JsonPath,
+        states: &[usize],
+        out: &mut Vec<(JsonPath, &'a Value)>,
+    ) {
+        if states.contains(&self.steps.len()) {
+            out.push((path.clone(), value));
+        }
+        match value {
+            Value::Array(a) => {
+                for (i, child) in a.iter().enumerate() {
+                    let child_states = self.child_states(states, ChildPos::Index(i), Some(child));
+                    if !child_states.is_empty() {
+                        path.push_index(i);
+                        self.visit(child, path, &child_states, out);
+                        path.pop();
+                    }
+                }
+            }
+            Value::Object(o) => {
+                for (key, child) in o {
+                    let child_states = self.child_states(states, ChildPos::Key(key), Some(child));
+                    if !child_states.is_empty() {
+                        path.push_key(key.as_str());
+                        self.visit(child, path, &child_states, out);
+                        path.pop();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Run the remaining steps over an already-decoded child value.
+    fn visit_owned(
+        &self,
+        value: &Value,
+        path: &mut JsonPath,
+        states: &[usize],
+        out: &mut Vec<(JsonPath, Value)>,
+    ) {
+        let mut found = Vec::new();
+        self.visit(value, path, states, &mut found);
+        out.extend(found.into_iter().map(|(p, v)| (p, v.clone())));
+    }
+
+    /// Process the child at the decoder's position.
+    fn stream_child(
+        &self,
+        decoder: &mut Decoder<'_>,
+        pos: ChildPos<'_>,
+        path: &mut JsonPath,
+        states: &[usize],
+        out: &mut Vec<(JsonPath, Value)>,
+    ) -> Result<()> {
+        if self.filters_children(states) {
+            let value = crate::decode_value_recursive(decoder)?;
+            let child_states = self.child_states(states, pos, Some(&value));
+            self.visit_owned(&value, path, &child_states, out);
+            return Ok(());
+        }
+        let child_states = self.child_states(states, pos, None);
+        if child_states.is_empty() {
+            return Ok(decoder.skip_value()?);
+        }
+        if child_states.contains(&self.steps.len()) {
+            let value = crate::decode_value_recursive(decoder)?;
+            self.visit_owned(&value, path, &child_states, out);
+            return Ok(());
+        }
+        let event = decoder.decode_value()?;
+        self.stream_container(decoder, event, path, &child_states, out)
+    }
+
+    /// Process the contents of a container whose start event was just read.
+    /// Scalars have no contents and are ignored.
+    fn stream_container(
+        &self,
+        decoder: &mut Decoder<'_>,
+        event: DecodedValue<'_>,
+        path: &mut JsonPath,
+        states: &[usize],
+        out: &mut Vec<(JsonPath, Value)>,
+    ) -> Result<()> {
+        let max_size = decoder.config().max_container_size;
+        match event {
+            DecodedValue::ArrayStart => {
+                let mut index = 0;
+                while !decoder.is_at_container_end()? {
+                    if index >= max_size {
+                        return Err(Error::MaxContainerSizeExceeded);
+                    }
+                    path.push_index(index);
+                    self.stream_child(decoder, ChildPos::Index(index), path, states, out)?;
+                    path.pop();
+                    index += 1;
+                }
+                Ok(decoder.end_container()?)
+            }
+            DecodedValue::ObjectStart => {
+                let mut seen = HashSet::new();
+                while !decoder.is_at_container_end()? {
+                    if seen.len() >= max_size {
+                        return Err(Error::MaxContainerSizeExceeded);
+                    }
+                    let key = match decoder.decode_value()? {
+                        DecodedValue::String(s) => crate::maybe_nfc_normalize(
+                            decoder.config().unicode_normalization,
+                            s.into_owned(),
+                        ),
+                        _ => return Err(Error::ExpectedObjectKey),
+                    };
+                    self.stream_member(decoder, key, &mut seen, path, states, out)?;
+                }
+                Ok(decoder.end_container()?)
+            }
+            DecodedValue::RecordInstanceStart(def_index) => {
+                let keys = decoder.record_definitions()[def_index].clone();
+                let mismatch_mode = decoder.config().record_mismatch;
+                let mut seen = HashSet::new();
+                let mut value_count = 0;
+                let mut extra_count = 0;
+                while !decoder.is_at_container_end()? {
+                    if value_count + extra_count >= max_size {
+                        return Err(Error::MaxContainerSizeExceeded);
+                    }
+                    if value_count >= keys.len() {
+                        decoder.skip_value()?;
+                        extra_count += 1;
+                        continue;
+                    }
+                    let key = crate::maybe_nfc_normalize(
+                        decoder.config().unicode_normalization,
+                        keys[value_count].clone(),
+                    );
+                    self.stream_member(decoder, key, &mut seen, path, states, out)?;
+                    value_count += 1;
+                }
+                decoder.end_container()?;
+
+                let too_many = extra_count > 0 && mismatch_mode != RecordMismatchMode::Truncate;
+                let too_few = value_count < keys.len() && mismatch_mode == RecordMismatchMode::Error;
+                if too_many || too_few {
+                    return Err(Error::RecordMismatch {
+                        definition: def_index,
+                        keys: keys.len(),
+                        values: value_count + extra_count,
+                    });
+                }
+                if mismatch_mode == RecordMismatchMode::NullFill {
+                    for key in keys.iter().skip(value_count) {
+                        let child_states = self.child_states(states, ChildPos::Key(key), Some(&Value::Null));
+                        path.push_key(key.as_str());
+                        self.visit_owned(&Value::Null, path, &child_states, out);
+                        path.pop();
+                    }
+                }
+                Ok(())
+            }
+            DecodedValue::TypedArrayStart { element_type_code, count } => {
+                for index in 0..count {
+                    let elem = decoder.read_typed_array_element(element_type_code)?;
+                    let pos = ChildPos::Index(index);
+                    let value = crate::typed_array_element_value(decoder.config(), element_type_code, elem);
+                    let child_states = self.child_states(states, pos, Some(&value));
+                    if child_states.contains(&self.steps.len()) {
+                        out.push((path.child_index(index), value));
+                    }
+                }
+                Ok(decoder.end_typed_array()?)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Process an object or record member, applying the duplicate key mode.
+    fn stream_member(
+        &self,
+        decoder: &mut Decoder<'_>,
+        key: String,
+        seen: &mut HashSet<String>,
+        path: &mut JsonPath,
+        states: &[usize],
+        out: &mut Vec<(JsonPath, Value)>,
+    ) -> Result<()> {
+        path.push_key(key.as_str());
+        if !seen.insert(key.clone()) {
+            match decoder.config().duplicate_key_mode {
+                DuplicateKeyMode::Error => return Err(Error::DuplicateKey),
+                DuplicateKeyMode::KeepFirst => {
+                    path.pop();
+                    return Ok(decoder.skip_value()?);
+                }
+                DuplicateKeyMode::KeepLast => {
+                    // Retract anything found under the earlier occurrence
+                    out.retain(|(p, _)| !p.starts_with(path));
+                }
+            }
+        }
+        self.stream_child(decoder, ChildPos::Key(&key), path, states, out)?;
+        path.pop();
+        Ok(())
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Query> {
+        Query::parse(s)
+    }
+}
+
+/// Select values matching a query from a `Value` tree.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidQuery`] if the query is malformed.
+pub fn select<'a>(value: &'a Value, query: &str) -> Result<Vec<&'a Value>> {
+    let query = Query::parse(query)?;
+    Ok(query.select(value).into_iter().map(|(_, v)| v).collect())
+}
+
+/// Select values matching a query directly from a BONJSON document.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidQuery`] if the query is malformed, or a decoding
+/// error if the document is invalid.
+pub fn select_bytes(data: &[u8], query: &str) -> Result<Vec<Value>> {
+    let query = Query::parse(query)?;
+    Ok(query.select_bytes(data)?.into_iter().map(|(_, v)| v).collect())
+}
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+impl Selector {
+    fn matches(&self, pos: ChildPos<'_>, child: Option<&Value>) -> bool {
+        match (self, pos) {
+            (Selector::Wildcard, _) => true,
+            (Selector::Key(k), ChildPos::Key(key)) => k == key,
+            (Selector::Index(i), ChildPos::Index(index)) => *i == index,
+            (Selector::Filter(expr), _) => {
+                expr.eval(child.expect("filters are evaluated on decoded values"))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Expr {
+    fn eval(&self, current: &Value) -> bool {
+        match self {
+            Expr::Exists(path) => resolve(current, path).is_some(),
+            Expr::Compare(lhs, op, rhs) => compare(lhs.resolve(current), *op, rhs.resolve(current)),
+            Expr::Not(e) => !e.eval(current),
+            Expr::And(a, b) => a.eval(current) && b.eval(current),
+            Expr::Or(a, b) => a.eval(current) || b.eval(current),
+        }
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, current: &'a Value) -> Option<&'a Value> {
+        match self {
+            Operand::Current(path) => resolve(current, path),
+            Operand::Literal(value) => Some(value),
+        }
+    }
+}
+
+fn resolve<'a>(value: &'a Value, path: &[PathSegment]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |v, segment| match (segment, v) {
+        (PathSegment::Key(k), Value::Object(o)) => o.get(k),
+        (PathSegment::Index(i), Value::Array(a)) => a.get(*i),
+        _ => None,
+    })
+}
+
+/// Compare two operands. A missing operand equals only another missing
+/// operand, and is never ordered.
+fn compare(lhs: Option<&Value>, op: CompareOp, rhs: Option<&Value>) -> bool {
+    let ordering = match (lhs, rhs) {
+        (None, None) => Some(Ordering::Equal),
+        (Some(a), Some(b)) => order_values(a, b),
+        _ => None,
+    };
+    match op {
+        CompareOp::Eq => ordering == Some(Ordering::Equal),
+        CompareOp::Ne => ordering != Some(Ordering::Equal),
+        CompareOp::Lt => lhs.is_some() && ordering == Some(Ordering::Less),
+        CompareOp::Le => lhs.is_some() && matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        CompareOp::Gt => lhs.is_some() && ordering == Some(Ordering::Greater),
+        CompareOp::Ge => lhs.is_some() && matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+/// Order two values: numbers numerically (across representations), strings
+/// lexically. Other values are only equal or unordered.
+fn order_values(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::String(_), Value::String(_)) => Some(a.total_cmp(b)),
+        _ if a.is_number() && b.is_number() => Some(a.total_cmp(b)),
+        _ if a == b => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> Error {
+        Error::InvalidQuery(format!("{msg} at offset {}", self.pos))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.src[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{token}'")))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn parse_query(&mut self) -> Result<Vec<Step>> {
+        self.skip_whitespace();
+        self.expect("$")?;
+        let mut steps = Vec::new();
+        loop {
+            if self.eat("..") {
+                let selector = if self.peek() == Some('[') {
+                    self.parse_bracket()?
+                } else {
+                    self.parse_dot_member()?
+                };
+                steps.push(Step { selector, descendant: true });
+            } else if self.eat(".") {
+                let selector = self.parse_dot_member()?;
+                steps.push(Step { selector, descendant: false });
+            } else if self.peek() == Some('[') {
+                let selector = self.parse_bracket()?;
+                steps.push(Step { selector, descendant: false });
+            } else {
+                break;
+            }
+        }
+        self.skip_whitespace();
+        if self.pos < self.src.len() {
+            return Err(self.error("unexpected character"));
+        }
+        Ok(steps)
+    }
+
+    fn parse_dot_member(&mut self) -> Result<Selector> {
+        if self.eat("*") {
+            return Ok(Selector::Wildcard);
+        }
+        Ok(Selector::Key(self.parse_name()?))
+    }
+
+    fn parse_name(&mut self) -> Result<String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+            self.bump();
+        }
+        if self.pos == start {
+            return Err(self.error("expected member name"));
+        }
+        Ok(self.src[start..self.pos].to_string())
+    }
+
+    fn parse_bracket(&mut self) -> Result<Selector> {
+        self.expect("[")?;
+        self.skip_whitespace();
+        let selector = match self.peek() {
+            Some('*') => {
+                self.bump();
+                Selector::Wildcard
+            }
+            Some('\'' | '"') => Selector::Key(self.parse_string()?),
+            Some('?') => {
+                self.bump();
+                self.skip_whitespace();
+                Selector::Filter(self.parse_or()?)
+            }
+            Some(c) if c.is_ascii_digit() => Selector::Index(self.parse_index()?),
+            Some('-') => return Err(self.error("negative indices are not supported")),
+            _ => return Err(self.error("expected selector")),
+        };
+        self.skip_whitespace();
+        self.expect("]")?;
+        Ok(selector)
+    }
+
+    fn parse_index(&mut self) -> Result<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        self.src[start..self.pos].parse().map_err(|_| self.error("invalid index"))
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        let quote = self.bump().ok_or_else(|| self.error("expected string"))?;
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some('\\') => match self.bump() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some(c) => out.push(c),
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some(c) if c == quote => return Ok(out),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_and()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("||") {
+                return Ok(expr);
+            }
+            self.skip_whitespace();
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_unary()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("&&") {
+                return Ok(expr);
+            }
+            self.skip_whitespace();
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        self.skip_whitespace();
+        if self.peek() == Some('!') && !self.src[self.pos..].starts_with("!=") {
+            self.bump();
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.eat("(") {
+            let expr = self.parse_or()?;
+            self.skip_whitespace();
+            self.expect(")")?;
+            return Ok(expr);
+        }
+        let lhs = self.parse_operand()?;
+        self.skip_whitespace();
+        let op = if self.eat("==") {
+            CompareOp::Eq
+        } else if self.eat("!=") {
+            CompareOp::Ne
+        } else if self.eat("<=") {
+            CompareOp::Le
+        } else if self.eat(">=") {
+            CompareOp::Ge
+        } else if self.eat("<") {
+            CompareOp::Lt
+        } else if self.eat(">") {
+            CompareOp::Gt
+        } else {
+            return match lhs {
+                Operand::Current(path) => Ok(Expr::Exists(path)),
+                Operand::Literal(_) => Err(self.error("expected comparison")),
+            };
+        };
+        self.skip_whitespace();
+        let rhs = self.parse_operand()?;
+        Ok(Expr::Compare(lhs, op, rhs))
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand> {
+        match self.peek() {
+            Some('@') => {
+                self.bump();
+                Ok(Operand::Current(self.parse_relative_path()?))
+            }
+            Some('\'' | '"') => Ok(Operand::Literal(Value::String(self.parse_string()?))),
+            Some(c) if c == '-' || c.is_ascii_digit() => Ok(Operand::Literal(self.parse_number()?)),
+            _ => {
+                if self.eat("true") {
+                    Ok(Operand::Literal(Value::Bool(true)))
+                } else if self.eat("false") {
+                    Ok(Operand::Literal(Value::Bool(false)))
+                } else if self.eat("null") {
+                    Ok(Operand::Literal(Value::Null))
+                } else {
+                    Err(self.error("expected '@' or a literal"))
+                }
+            }
+        }
+    }
+
+    fn parse_relative_path(&mut self) -> Result<Vec<PathSegment>> {
+        let mut path = Vec::new();
+        loop {
+            if self.peek() == Some('.') && !self.src[self.pos..].starts_with("..") {
+                self.bump();
+                path.push(PathSegment::Key(self.parse_name()?));
+            } else if self.eat("[") {
+                self.skip_whitespace();
+                let segment = match self.peek() {
+                    Some('\'' | '"') => PathSegment::Key(self.parse_string()?),
+                    Some(c) if c.is_ascii_digit() => PathSegment::Index(self.parse_index()?),
+                    _ => return Err(self.error("expected key or index")),
+                };
+                self.skip_whitespace();
+                self.expect("]")?;
+                path.push(segment);
+            } else {
+                return Ok(path);
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Value> {
+        let start = self.pos;
+        self.eat("-");
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+        {
+            self.bump();
+        }
+        let text = &self.src[start..self.pos];
+        if let Ok(n) = text.parse::<i64>() {
+            return Ok(Value::Int(n));
+        }
+        if let Ok(n) = text.parse::<u64>() {
+            return Ok(Value::UInt(n));
+        }
+        text.parse::<f64>().map(Value::Float).map_err(|_| self.error("invalid number"))
+    }
+}
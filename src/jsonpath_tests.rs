@@ -0,0 +1,189 @@
+// ABOUTME: Unit tests for the JSONPath module.
+// ABOUTME: Tests path construction, navigation, and display notation.
+
+use crate::jsonpath::{JsonPath, PathSegment};
+
+#[test]
+fn test_path_display() {
+    assert_eq!(JsonPath::root().to_string(), "$");
+
+    let path = JsonPath::root().child_key("store").child_key("book").child_index(0);
+    assert_eq!(path.to_string(), "$.store.book[0]");
+
+    // Keys that aren't identifiers use bracket notation
+    let path = JsonPath::root().child_key("a b").child_key("it's").child_key("9x");
+    assert_eq!(path.to_string(), "$['a b']['it\\'s']['9x']");
+}
+
+#[test]
+fn test_path_navigation() {
+    let mut path = JsonPath::root();
+    assert!(path.is_root());
+    assert_eq!(path.parent(), None);
+
+    path.push_key("a");
+    path.push_index(2);
+    assert_eq!(path.len(), 2);
+    assert_eq!(path.last(), Some(&PathSegment::Index(2)));
+    assert_eq!(path.parent(), Some(JsonPath::root().child_key("a")));
+    assert!(path.starts_with(&JsonPath::root().child_key("a")));
+    assert!(!path.starts_with(&JsonPath::root().child_key("b")));
+
+    assert_eq!(path.pop(), Some(PathSegment::Index(2)));
+    assert_eq!(path.segments(), &[PathSegment::Key("a".into())]);
+}
+
+// ============================================================================
+// Query tests
+// ============================================================================
+
+use crate::jsonpath::{select, select_bytes, Query};
+use crate::{bonjson, encode_value, Value};
+
+fn store() -> Value {
+    bonjson!({
+        "store": {
+            "book": [
+                {"category": "reference", "author": "Rees", "price": 8.95},
+                {"category": "fiction", "author": "Waugh", "price": 12.99},
+                {"category": "fiction", "author": "Melville", "price": 8.99, "isbn": "0-553"},
+                {"category": "fiction", "author": "Tolkien", "price": 22.99, "isbn": "0-395"}
+            ],
+            "bicycle": {"color": "red", "price": 19.95}
+        }
+    })
+}
+
+/// Run a query over both the Value and its encoding, checking they agree.
+fn query_both(query: &str) -> Vec<(String, Value)> {
+    let doc = store();
+    let query = Query::parse(query).unwrap();
+    let from_value: Vec<(String, Value)> =
+        query.select(&doc).into_iter().map(|(p, v)| (p.to_string(), v.clone())).collect();
+    let bytes = encode_value(&doc).unwrap();
+    let from_bytes: Vec<(String, Value)> =
+        query.select_bytes(&bytes).unwrap().into_iter().map(|(p, v)| (p.to_string(), v)).collect();
+    assert_eq!(from_value, from_bytes);
+    from_value
+}
+
+fn values(results: Vec<(String, Value)>) -> Vec<Value> {
+    results.into_iter().map(|(_, v)| v).collect()
+}
+
+#[test]
+fn test_query_members_and_indices() {
+    assert_eq!(values(query_both("$.store.book[1].author")), vec![bonjson!("Waugh")]);
+    assert_eq!(values(query_both("$['store']['bicycle'].color")), vec![bonjson!("red")]);
+    assert!(query_both("$.store.book[9]").is_empty());
+    assert!(query_both("$.nothing.here").is_empty());
+
+    let root = query_both("$");
+    assert_eq!(root, vec![("$".to_string(), store())]);
+}
+
+#[test]
+fn test_query_wildcards() {
+    let authors = values(query_both("$.store.book[*].author"));
+    assert_eq!(authors, vec![bonjson!("Rees"), bonjson!("Waugh"), bonjson!("Melville"), bonjson!("Tolkien")]);
+
+    let members = query_both("$.store.*");
+    assert_eq!(members.len(), 2);
+    assert_eq!(members[0].0, "$.store.bicycle");
+}
+
+#[test]
+fn test_query_recursive_descent() {
+    let prices = query_both("$..price");
+    let paths: Vec<&str> = prices.iter().map(|(p, _)| p.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "$.store.bicycle.price",
+            "$.store.book[0].price",
+            "$.store.book[1].price",
+            "$.store.book[2].price",
+            "$.store.book[3].price",
+        ]
+    );
+    assert_eq!(query_both("$..book[2].author")[0].1, bonjson!("Melville"));
+}
+
+#[test]
+fn test_query_filters() {
+    let cheap = values(query_both("$.store.book[?(@.price < 10)].author"));
+    assert_eq!(cheap, vec![bonjson!("Rees"), bonjson!("Melville")]);
+
+    let with_isbn = values(query_both("$..book[?(@.isbn)].author"));
+    assert_eq!(with_isbn, vec![bonjson!("Melville"), bonjson!("Tolkien")]);
+
+    let combined = values(query_both(
+        "$.store.book[?@.category == 'fiction' && !(@.price >= 20 || @.isbn)].author",
+    ));
+    assert_eq!(combined, vec![bonjson!("Waugh")]);
+
+    let not_fiction = values(query_both(r#"$.store.book[?(@.category != "fiction")].price"#));
+    assert_eq!(not_fiction, vec![bonjson!(8.95)]);
+
+    // Filters on scalar elements use a bare @
+    let doc = bonjson!({"n": [1, 5, 10, 15]});
+    let big = select(&doc, "$.n[?(@ >= 10)]").unwrap();
+    assert_eq!(big, vec![&bonjson!(10), &bonjson!(15)]);
+}
+
+#[test]
+fn test_query_numeric_comparison_across_types() {
+    let max = u64::MAX;
+    let doc = bonjson!([{"v": 3}, {"v": 3.0}, {"v": max}, {"v": "3"}]);
+    assert_eq!(select(&doc, "$[?(@.v == 3)]").unwrap().len(), 2);
+    assert_eq!(select(&doc, "$[?(@.v > 3)]").unwrap(), vec![&bonjson!({"v": max})]);
+}
+
+#[test]
+fn test_select_bytes_typed_arrays_and_records() {
+    #[derive(serde::Serialize)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    let config = crate::SerializerConfig { records: true, ..Default::default() };
+    let points = vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+    let bytes = crate::to_vec_with_config(&points, &config).unwrap();
+    assert_eq!(select_bytes(&bytes, "$[*].y").unwrap(), vec![bonjson!(2), bonjson!(4)]);
+    assert_eq!(select_bytes(&bytes, "$[?(@.x > 2)]").unwrap(), vec![bonjson!({"x": 3, "y": 4})]);
+
+    let bytes = crate::to_vec(&vec![0.1f64, 0.2, 0.3]).unwrap();
+    assert_eq!(bytes[0], crate::type_code::TYPED_ARRAY_FLOAT64);
+    assert_eq!(select_bytes(&bytes, "$[1]").unwrap(), vec![bonjson!(0.2)]);
+    assert_eq!(select_bytes(&bytes, "$[?(@ > 0.15)]").unwrap(), vec![bonjson!(0.2), bonjson!(0.3)]);
+}
+
+#[test]
+fn test_select_bytes_validates_document() {
+    let mut bytes = encode_value(&store()).unwrap();
+    bytes.push(0x00);
+    assert_eq!(select_bytes(&bytes, "$.store").unwrap_err(), crate::Error::TrailingBytes(bytes.len() - 1));
+}
+
+#[test]
+fn test_query_parse_errors() {
+    for bad in ["", "store", "$.", "$[", "$[-1]", "$['a", "$[?(@.a <)]", "$[?(1)]", "$.a b"] {
+        let err = Query::parse(bad).unwrap_err();
+        assert_eq!(err.error_type(), "invalid_query", "query {bad:?}");
+    }
+}
+
+#[test]
+fn test_select_bytes_duplicate_keys() {
+    // {"a": {"b": 1}, "a": {"b": 2}}
+    let bytes = [0xb8, 0x66, b'a', 0xb8, 0x66, b'b', 0x01, 0xb6, 0x66, b'a', 0xb8, 0x66, b'b', 0x02, 0xb6, 0xb6];
+    let query = Query::parse("$.a.b").unwrap();
+    assert_eq!(query.select_bytes(&bytes).unwrap_err(), crate::Error::DuplicateKey);
+
+    let config = |mode| crate::DecoderConfig { duplicate_key_mode: mode, ..Default::default() };
+    let first = query.select_bytes_with_config(&bytes, config(crate::DuplicateKeyMode::KeepFirst)).unwrap();
+    assert_eq!(first[0].1, bonjson!(1));
+    let last = query.select_bytes_with_config(&bytes, config(crate::DuplicateKeyMode::KeepLast)).unwrap();
+    assert_eq!(last.len(), 1);
+    assert_eq!(last[0].1, bonjson!(2));
+}
@@ -0,0 +1,459 @@
+// ABOUTME: Lazily-decoded views over encoded BONJSON documents.
+// ABOUTME: A LazyValue navigates by skipping siblings and decodes only the children it's asked for.
+
+use crate::de::Deserializer;
+use crate::decoder::{DecodedValue, Decoder, DecoderConfig, RecordMismatchMode};
+use crate::error::{Error, Result};
+use crate::types::type_code;
+use crate::value::Value;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// The kind of value a [`LazyValue`] refers to, determined without decoding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LazyKind {
+    Null,
+    Bool,
+    Number,
+    String,
+    /// An array, including typed arrays.
+    Array,
+    /// An object, including record instances.
+    Object,
+}
+
+/// A view of one value inside an encoded document, decoded on demand.
+///
+/// Navigating with [`get_key`](Self::get_key), [`index`](Self::index),
+/// [`iter`](Self::iter), or [`entries`](Self::entries) skips over sibling
+/// values without building them, so reading a few fields out of a large
+/// document costs little more than scanning the bytes in front of them.
+/// Leaves are materialized with [`decode`](Self::decode) or
+/// [`deserialize`](Self::deserialize).
+///
+/// Creating the root reads the document's record definitions, but nothing
+/// else is validated up front: malformed data is reported by whichever call
+/// first touches it.
+///
+/// Cloning is cheap; clones share the document's record definitions.
+#[derive(Debug, Clone)]
+pub struct LazyValue<'a> {
+    data: &'a [u8],
+    shared: Arc<Shared>,
+    repr: Repr,
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: DecoderConfig,
+    record_definitions: Arc<Vec<Vec<String>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Repr {
+    /// A complete encoded value starting at this offset.
+    Encoded(usize),
+    /// One element of a typed array: raw little-endian bytes at this offset.
+    TypedElement { element_type_code: u8, offset: usize },
+    /// A null filled in for a record instance with missing trailing values.
+    Missing,
+}
+
+impl<'a> LazyValue<'a> {
+    /// Create a lazy view of a document's root value.
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        Self::parse_with_config(data, DecoderConfig::default())
+    }
+
+    /// Create a lazy view of a document's root value with custom configuration.
+    pub fn parse_with_config(data: &'a [u8], config: DecoderConfig) -> Result<Self> {
+        let mut decoder = Decoder::with_config(data, config);
+        decoder.check_document_size()?;
+        decoder.read_record_definitions()?;
+        let start = decoder.position();
+        let record_definitions = decoder.shared_record_definitions();
+        let config = decoder.config().clone();
+        Ok(Self {
+            data,
+            shared: Arc::new(Shared { config, record_definitions }),
+            repr: Repr::Encoded(start),
+        })
+    }
+
+    /// Create a lazy view of a root value encoded without record
+    /// definitions, which refers to definitions read elsewhere.
+    pub(crate) fn with_record_definitions(
+        data: &'a [u8],
+        config: DecoderConfig,
+        record_definitions: Arc<Vec<Vec<String>>>,
+    ) -> Self {
+        Self { data, shared: Arc::new(Shared { config, record_definitions }), repr: Repr::Encoded(0) }
+    }
+
+    fn decoder_at(&self, pos: usize) -> Decoder<'a> {
+        Decoder::resume(
+            self.data,
+            self.shared.config.clone(),
+            Arc::clone(&self.shared.record_definitions),
+            pos,
+        )
+    }
+
+    fn child(&self, repr: Repr) -> Self {
+        Self { data: self.data, shared: Arc::clone(&self.shared), repr }
+    }
+
+    /// Returns the kind of this value by peeking at its type code.
+    pub fn kind(&self) -> Result<LazyKind> {
+        let offset = match self.repr {
+            Repr::Encoded(offset) => offset,
+            Repr::TypedElement { .. } => return Ok(LazyKind::Number),
+            Repr::Missing => return Ok(LazyKind::Null),
+        };
+        let tc = *self.data.get(offset).ok_or(Error::Truncated)?;
+        Ok(match tc {
+            type_code::NULL => LazyKind::Null,
+            type_code::FALSE | type_code::TRUE => LazyKind::Bool,
+            type_code::ARRAY => LazyKind::Array,
+            type_code::OBJECT | type_code::RECORD_INSTANCE => LazyKind::Object,
+            tc if type_code::is_any_string(tc) => LazyKind::String,
+            tc if type_code::is_typed_array(tc) => LazyKind::Array,
+            tc if type_code::is_small_int(tc)
+                || type_code::is_any_int(tc)
+                || tc == type_code::FLOAT32
+                || tc == type_code::FLOAT64
+                || tc == type_code::BIG_NUMBER =>
+            {
+                LazyKind::Number
+            }
+            tc => return Err(Error::InvalidTypeCode(tc)),
+        })
+    }
+
+    /// Returns true if this value is an array (including typed arrays).
+    pub fn is_array(&self) -> Result<bool> {
+        Ok(self.kind()? == LazyKind::Array)
+    }
+
+    /// Returns true if this value is an object (including record instances).
+    pub fn is_object(&self) -> Result<bool> {
+        Ok(self.kind()? == LazyKind::Object)
+    }
+
+    /// Returns the encoded bytes of this value.
+    ///
+    /// For a typed array element this is the element's raw little-endian
+    /// bytes; for a null filled in for a short record instance it is empty.
+    pub fn raw_bytes(&self) -> Result<&'a [u8]> {
+        match self.repr {
+            Repr::Encoded(offset) => {
+                let mut decoder = self.decoder_at(offset);
+                decoder.skip_value()?;
+                Ok(&self.data[offset..decoder.position()])
+            }
+            Repr::TypedElement { element_type_code, offset } => {
+                let size = type_code::typed_array_element_size(element_type_code);
+                self.data.get(offset..offset + size).ok_or(Error::Truncated)
+            }
+            Repr::Missing => Ok(&[]),
+        }
+    }
+
+    /// Fully decode this value (and everything beneath it) into a [`Value`].
+    pub fn decode(&self) -> Result<Value> {
+        match self.repr {
+            Repr::Encoded(offset) => crate::decode_value_recursive(&mut self.decoder_at(offset)),
+            Repr::TypedElement { element_type_code, offset } => {
+                let elem = self.decoder_at(offset).read_typed_array_element(element_type_code)?;
+                Ok(crate::typed_array_element_value(&self.shared.config, element_type_code, elem))
+            }
+            Repr::Missing => Ok(Value::Null),
+        }
+    }
+
+    /// Deserialize this value into `T`.
+    ///
+    /// Borrowed strings in `T` borrow from the document.
+    pub fn deserialize<T: Deserialize<'a>>(&self) -> Result<T> {
+        match self.repr {
+            Repr::Encoded(offset) => {
+                let mut de = Deserializer::from_decoder(self.decoder_at(offset));
+                T::deserialize(&mut de)
+            }
+            Repr::TypedElement { element_type_code, offset } => {
+                let element = self.decoder_at(offset).read_typed_element(element_type_code)?;
+                crate::de::deserialize_typed_element(std::marker::PhantomData, element, crate::de::ElementModes::of(&self.shared.config))
+            }
+            Repr::Missing => T::deserialize(serde::de::value::UnitDeserializer::<Error>::new()),
+        }
+    }
+
+    /// Look up an object member (or record instance field) by key.
+    ///
+    /// Returns `Ok(None)` if the key is absent or this is not an object. With
+    /// duplicate keys, the first occurrence wins.
+    pub fn get_key(&self, key: &str) -> Result<Option<LazyValue<'a>>> {
+        let mut cursor = self.cursor()?;
+        while let Some((k, value)) = cursor.next_child()? {
+            if k.as_deref() == Some(key) {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Look up an array element by index.
+    ///
+    /// Returns `Ok(None)` if the index is out of bounds or this is not an
+    /// array. Typed array elements are located without scanning.
+    pub fn index(&self, index: usize) -> Result<Option<LazyValue<'a>>> {
+        let mut cursor = self.cursor()?;
+        if let Children::TypedArray { element_type_code, count, start } = cursor.children {
+            if index >= count {
+                return Ok(None);
+            }
+            let size = type_code::typed_array_element_size(element_type_code);
+            let end = count.checked_mul(size).and_then(|len| len.checked_add(start));
+            if end.is_none_or(|end| end > self.data.len()) {
+                return Err(Error::Truncated);
+            }
+            let offset = start + index * size;
+            return Ok(Some(self.child(Repr::TypedElement { element_type_code, offset })));
+        }
+        if !matches!(cursor.children, Children::Array) {
+            return Ok(None);
+        }
+        for _ in 0..index {
+            if cursor.next_child()?.is_none() {
+                return Ok(None);
+            }
+        }
+        Ok(cursor.next_child()?.map(|(_, value)| value))
+    }
+
+    /// Iterate over the elements of an array, or the values of an object.
+    ///
+    /// Scalars have no children and yield nothing.
+    pub fn iter(&self) -> Result<LazyIter<'a>> {
+        Ok(LazyIter { cursor: self.cursor()? })
+    }
+
+    /// Iterate over the members of an object as `(key, value)` pairs.
+    ///
+    /// Arrays and scalars yield nothing.
+    pub fn entries(&self) -> Result<LazyEntries<'a>> {
+        let mut cursor = self.cursor()?;
+        if !matches!(cursor.children, Children::Object | Children::Record { .. }) {
+            cursor.children = Children::None;
+        }
+        Ok(LazyEntries { cursor })
+    }
+
+    /// Returns the number of children (array elements or object members).
+    ///
+    /// Scalars have zero. Arrays and objects are counted by skipping over
+    /// their children; typed arrays are counted from their header.
+    pub fn len(&self) -> Result<usize> {
+        let mut cursor = self.cursor()?;
+        if let Children::TypedArray { count, .. } = cursor.children {
+            return Ok(count);
+        }
+        let mut count = 0;
+        while cursor.next_child()?.is_some() {
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Returns true if this value has no children.
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    fn cursor(&self) -> Result<Cursor<'a>> {
+        let Repr::Encoded(offset) = self.repr else {
+            return Ok(Cursor::empty(self));
+        };
+        let tc = *self.data.get(offset).ok_or(Error::Truncated)?;
+        if tc != type_code::ARRAY
+            && tc != type_code::OBJECT
+            && tc != type_code::RECORD_INSTANCE
+            && !type_code::is_typed_array(tc)
+        {
+            return Ok(Cursor::empty(self));
+        }
+        let mut decoder = self.decoder_at(offset);
+        let children = match decoder.decode_value()? {
+            DecodedValue::ArrayStart => Children::Array,
+            DecodedValue::ObjectStart => Children::Object,
+            DecodedValue::RecordInstanceStart(def_index) => Children::Record { def_index, index: 0 },
+            DecodedValue::TypedArrayStart { element_type_code, count } => Children::TypedArray {
+                element_type_code,
+                count,
+                start: decoder.position(),
+            },
+            _ => unreachable!("type code was checked to be a container"),
+        };
+        Ok(Cursor { value: self.clone(), decoder, children, index: 0 })
+    }
+}
+
+// ============================================================================
+// Iteration
+// ============================================================================
+
+enum Children {
+    None,
+    Array,
+    Object,
+    Record { def_index: usize, index: usize },
+    TypedArray { element_type_code: u8, count: usize, start: usize },
+}
+
+/// Walks the children of one container, skipping each value after yielding it.
+struct Cursor<'a> {
+    value: LazyValue<'a>,
+    decoder: Decoder<'a>,
+    children: Children,
+    /// Typed array elements yielded so far.
+    index: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn empty(value: &LazyValue<'a>) -> Self {
+        Cursor {
+            value: value.clone(),
+            decoder: value.decoder_at(0),
+            children: Children::None,
+            index: 0,
+        }
+    }
+
+    /// Yields the next child with its key (None for array elements).
+    fn next_child(&mut self) -> Result<Option<(Option<String>, LazyValue<'a>)>> {
+        match &mut self.children {
+            Children::None => Ok(None),
+            Children::Array => {
+                if self.decoder.is_at_container_end()? {
+                    self.children = Children::None;
+                    return Ok(None);
+                }
+                let start = self.decoder.position();
+                self.decoder.skip_value()?;
+                Ok(Some((None, self.value.child(Repr::Encoded(start)))))
+            }
+            Children::Object => {
+                if self.decoder.is_at_container_end()? {
+                    self.children = Children::None;
+                    return Ok(None);
+                }
+                let key = match self.decoder.decode_value()? {
+                    DecodedValue::String(s) => crate::maybe_nfc_normalize(
+                        self.decoder.config().unicode_normalization,
+                        s.into_owned(),
+                    ),
+                    _ => return Err(Error::ExpectedObjectKey),
+                };
+                let start = self.decoder.position();
+                self.decoder.skip_value()?;
+                Ok(Some((Some(key), self.value.child(Repr::Encoded(start)))))
+            }
+            Children::Record { def_index, index } => {
+                let (def_index, i) = (*def_index, *index);
+                let keys = &self.value.shared.record_definitions[def_index];
+                let mode = self.value.shared.config.record_mismatch;
+                let at_end = self.decoder.is_at_container_end()?;
+                if i >= keys.len() {
+                    if at_end || mode == RecordMismatchMode::Truncate {
+                        self.children = Children::None;
+                        return Ok(None);
+                    }
+                    let mut extra = 0;
+                    while !self.decoder.is_at_container_end()? {
+                        self.decoder.skip_value()?;
+                        extra += 1;
+                    }
+                    return Err(Error::RecordMismatch {
+                        definition: def_index,
+                        keys: keys.len(),
+                        values: keys.len() + extra,
+                    });
+                }
+                let key = keys[i].clone();
+                let repr = if at_end {
+                    match mode {
+                        RecordMismatchMode::NullFill => Repr::Missing,
+                        RecordMismatchMode::Truncate => {
+                            self.children = Children::None;
+                            return Ok(None);
+                        }
+                        RecordMismatchMode::Error => {
+                            return Err(Error::RecordMismatch {
+                                definition: def_index,
+                                keys: keys.len(),
+                                values: i,
+                            });
+                        }
+                    }
+                } else {
+                    let start = self.decoder.position();
+                    self.decoder.skip_value()?;
+                    Repr::Encoded(start)
+                };
+                self.children = Children::Record { def_index, index: i + 1 };
+                Ok(Some((Some(key), self.value.child(repr))))
+            }
+            Children::TypedArray { element_type_code, count, start } => {
+                if self.index >= *count {
+                    return Ok(None);
+                }
+                let element_type_code = *element_type_code;
+                let size = type_code::typed_array_element_size(element_type_code);
+                let offset = *start + self.index * size;
+                if offset + size > self.value.data.len() {
+                    return Err(Error::Truncated);
+                }
+                self.index += 1;
+                Ok(Some((None, self.value.child(Repr::TypedElement { element_type_code, offset }))))
+            }
+        }
+    }
+}
+
+/// Iterator over the children of a [`LazyValue`], from [`LazyValue::iter`].
+pub struct LazyIter<'a> {
+    cursor: Cursor<'a>,
+}
+
+impl<'a> Iterator for LazyIter<'a> {
+    type Item = Result<LazyValue<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.cursor.next_child() {
+            Ok(child) => child.map(|(_, value)| Ok(value)),
+            Err(e) => {
+                self.cursor.children = Children::None;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Iterator over the members of a lazy object, from [`LazyValue::entries`].
+pub struct LazyEntries<'a> {
+    cursor: Cursor<'a>,
+}
+
+impl<'a> Iterator for LazyEntries<'a> {
+    type Item = Result<(String, LazyValue<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.cursor.next_child() {
+            Ok(child) => child.map(|(key, value)| Ok((key.unwrap_or_default(), value))),
+            Err(e) => {
+                self.cursor.children = Children::None;
+                Some(Err(e))
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// ABOUTME: Unit tests for the lazy value module.
+// ABOUTME: Tests on-demand navigation, typed arrays, records, and error reporting.
+
+use crate::lazy::{LazyKind, LazyValue};
+use crate::{bonjson, encode_value, type_code, DecoderConfig, Error, RecordMismatchMode, Value};
+
+fn document() -> Value {
+    bonjson!({
+        "name": "inventory",
+        "items": [
+            {"id": 1, "tags": ["a", "b"]},
+            {"id": 2, "tags": []},
+            {"id": 3, "tags": ["c"]}
+        ],
+        "total": 3
+    })
+}
+
+#[test]
+fn test_lazy_navigation() {
+    let bytes = encode_value(&document()).unwrap();
+    let root = LazyValue::parse(&bytes).unwrap();
+    assert_eq!(root.kind().unwrap(), LazyKind::Object);
+    assert_eq!(root.len().unwrap(), 3);
+
+    let items = root.get_key("items").unwrap().unwrap();
+    assert!(items.is_array().unwrap());
+    assert_eq!(items.len().unwrap(), 3);
+
+    let tag = items.index(2).unwrap().unwrap().get_key("tags").unwrap().unwrap().index(0).unwrap().unwrap();
+    assert_eq!(tag.kind().unwrap(), LazyKind::String);
+    assert_eq!(tag.deserialize::<&str>().unwrap(), "c");
+
+    assert!(root.get_key("missing").unwrap().is_none());
+    assert!(items.index(3).unwrap().is_none());
+    // Navigating into the wrong kind of value finds nothing
+    assert!(items.get_key("id").unwrap().is_none());
+    assert!(root.index(0).unwrap().is_none());
+    assert!(tag.get_key("x").unwrap().is_none());
+    assert_eq!(tag.len().unwrap(), 0);
+}
+
+#[test]
+fn test_lazy_decode_matches_eager() {
+    let doc = document();
+    let bytes = encode_value(&doc).unwrap();
+    let root = LazyValue::parse(&bytes).unwrap();
+    assert_eq!(root.decode().unwrap(), doc);
+    assert_eq!(root.get_key("items").unwrap().unwrap().decode().unwrap(), *doc.get_key("items").unwrap());
+    assert_eq!(root.get_key("total").unwrap().unwrap().deserialize::<u32>().unwrap(), 3);
+}
+
+#[test]
+fn test_lazy_iteration() {
+    let bytes = encode_value(&document()).unwrap();
+    let root = LazyValue::parse(&bytes).unwrap();
+
+    let keys: Vec<String> = root.entries().unwrap().map(|e| e.unwrap().0).collect();
+    assert_eq!(keys, vec!["items", "name", "total"]);
+
+    let items = root.get_key("items").unwrap().unwrap();
+    let ids: Vec<i64> = items
+        .iter()
+        .unwrap()
+        .map(|item| item.unwrap().get_key("id").unwrap().unwrap().deserialize().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+
+    // Arrays have no entries; scalars have no children at all
+    assert_eq!(items.entries().unwrap().count(), 0);
+    let name = root.get_key("name").unwrap().unwrap();
+    assert_eq!(name.iter().unwrap().count(), 0);
+}
+
+#[test]
+fn test_lazy_raw_bytes() {
+    let bytes = encode_value(&document()).unwrap();
+    let root = LazyValue::parse(&bytes).unwrap();
+    // The items share a record definition, which precedes the root value
+    assert_eq!(bytes[0], type_code::RECORD_DEF);
+    let raw = root.raw_bytes().unwrap();
+    assert!(raw.len() < bytes.len() && bytes.ends_with(raw));
+
+    let tags = root.get_key("items").unwrap().unwrap().index(0).unwrap().unwrap().get_key("tags").unwrap().unwrap();
+    let expected = encode_value(&bonjson!(["a", "b"])).unwrap();
+    assert_eq!(tags.raw_bytes().unwrap(), &expected[..]);
+}
+
+#[test]
+fn test_lazy_typed_array() {
+    let bytes = crate::to_vec(&vec![0.1f64, 0.2, 0.3]).unwrap();
+    assert_eq!(bytes[0], type_code::TYPED_ARRAY_FLOAT64);
+    let root = LazyValue::parse(&bytes).unwrap();
+    assert!(root.is_array().unwrap());
+    assert_eq!(root.len().unwrap(), 3);
+
+    let elem = root.index(1).unwrap().unwrap();
+    assert_eq!(elem.kind().unwrap(), LazyKind::Number);
+    assert_eq!(elem.decode().unwrap(), bonjson!(0.2));
+    assert_eq!(elem.deserialize::<f64>().unwrap(), 0.2);
+    assert_eq!(elem.raw_bytes().unwrap(), &0.2f64.to_le_bytes());
+    assert!(root.index(3).unwrap().is_none());
+
+    let all: Vec<Value> = root.iter().unwrap().map(|v| v.unwrap().decode().unwrap()).collect();
+    assert_eq!(all, vec![bonjson!(0.1), bonjson!(0.2), bonjson!(0.3)]);
+}
+
+#[test]
+fn test_lazy_records() {
+    #[derive(serde::Serialize)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    let config = crate::SerializerConfig { records: true, ..Default::default() };
+    let points = vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+    let bytes = crate::to_vec_with_config(&points, &config).unwrap();
+    assert_eq!(bytes[0], type_code::RECORD_DEF);
+
+    let root = LazyValue::parse(&bytes).unwrap();
+    let second = root.index(1).unwrap().unwrap();
+    assert!(second.is_object().unwrap());
+    assert_eq!(second.get_key("y").unwrap().unwrap().deserialize::<i32>().unwrap(), 4);
+    assert_eq!(second.decode().unwrap(), bonjson!({"x": 3, "y": 4}));
+    let keys: Vec<String> = second.entries().unwrap().map(|e| e.unwrap().0).collect();
+    assert_eq!(keys, vec!["x", "y"]);
+}
+
+#[test]
+fn test_lazy_short_record_instance() {
+    // Definition {a, b}, then an instance holding only a value for "a"
+    let bytes = [
+        type_code::RECORD_DEF, 0x66, b'a', 0x66, b'b', type_code::CONTAINER_END,
+        type_code::RECORD_INSTANCE, 0x00, 0x01, type_code::CONTAINER_END,
+    ];
+    let root = LazyValue::parse(&bytes).unwrap();
+    let b = root.get_key("b").unwrap().unwrap();
+    assert_eq!(b.kind().unwrap(), LazyKind::Null);
+    assert_eq!(b.decode().unwrap(), Value::Null);
+    assert_eq!(b.deserialize::<Option<i32>>().unwrap(), None);
+    assert!(b.raw_bytes().unwrap().is_empty());
+
+    let config = DecoderConfig { record_mismatch: RecordMismatchMode::Truncate, ..Default::default() };
+    let root = LazyValue::parse_with_config(&bytes, config).unwrap();
+    assert!(root.get_key("b").unwrap().is_none());
+    assert_eq!(root.len().unwrap(), 1);
+
+    let config = DecoderConfig { record_mismatch: RecordMismatchMode::Error, ..Default::default() };
+    let root = LazyValue::parse_with_config(&bytes, config).unwrap();
+    assert!(root.get_key("a").unwrap().is_some());
+    assert!(matches!(root.get_key("b").unwrap_err(), Error::RecordMismatch { values: 1, .. }));
+}
+
+#[test]
+fn test_lazy_errors_surface_on_access() {
+    let bytes = encode_value(&document()).unwrap();
+    let truncated = &bytes[..bytes.len() - 4];
+    let root = LazyValue::parse(truncated).unwrap();
+    // The first member is intact, so reading it succeeds
+    assert!(root.get_key("items").unwrap().is_some());
+    assert_eq!(root.get_key("total").unwrap_err(), Error::Truncated);
+    assert!(root.decode().is_err());
+
+    let mut errors = root.iter().unwrap().filter(Result::is_err);
+    assert!(errors.next().is_some());
+    assert!(errors.next().is_none());
+
+    // An end marker in value position is an error, not an underflow
+    let bytes = [0xb8, 0x66, 0x61, 0xb6, 0x66, 0x62, 0xfa, 0x03, 0x01, 0x02, 0x03, 0x66, 0x63, 0xb8, 0x66, 0x64, 0x6a, 0x68, 0x65];
+    let root = LazyValue::parse(&bytes).unwrap();
+    assert!(root.get_key("c").is_err());
+    assert!(crate::for_each_element(&bytes, "$.c", |_: Value| {}).is_err());
+    assert!(crate::jsonpath::Query::parse("$.c").unwrap().select_bytes(&bytes).is_err());
+    assert!(crate::edit::Editor::new(&mut bytes.to_vec()).is_err());
+}
@@ -113,6 +113,22 @@
 //! - Maximum container size: 1,000,000 elements
 //! - Maximum string length: 10 MB
 //!
+//! Documents and typed arrays over 2 GB need `allow_huge_documents` as well as
+//! raised limits, as an explicit opt-in:
+//!
+//! ```rust
+//! use serde_bonjson::DecoderConfig;
+//!
+//! let config = DecoderConfig::default()
+//!     .allow_huge_documents(true)
+//!     .max_document_size(8 << 30)
+//!     .max_typed_array_bytes(8 << 30)
+//!     .max_container_size(usize::MAX);
+//! ```
+//!
+//! A document that size can be decoded from a memory map with `from_slice`,
+//! or streamed one element at a time with `ReaderDeserializer::array_elements`.
+//!
 //! ## Optional Features
 //!
 //! ### `simd-utf8`
@@ -130,36 +146,235 @@
 //! [dependencies]
 //! serde_bonjson = { version = "0.1", features = ["simd-utf8"] }
 //! ```
+//!
+//! ### `forbid-unsafe`
+//!
+//! Compiles the crate with `#![forbid(unsafe_code)]`. The two places that skip
+//! re-validating UTF-8 the decoder has already checked do the check again
+//! instead, which costs a little on strings with a non-ASCII tail and on
+//! `InvalidUtf8Mode::Delete`. Dependencies such as `memchr` keep their own
+//! unsafe code.
+//!
+//! ### `arbitrary`
+//!
+//! Implements [`arbitrary::Arbitrary`](https://docs.rs/arbitrary) for
+//! [`Value`], [`BigNumber`], [`DecoderConfig`] and [`SerializerConfig`], for
+//! property tests and fuzzers. The same input always generates the same
+//! result, and input that runs out picks the simplest choice (null, empty
+//! containers, default config fields), so shrinking the input shrinks the
+//! value. Generated values encode and decode under the default configs:
+//! floats are finite, strings have no NUL, BigNumbers stay in the f64 range,
+//! and nesting stops at depth 4. Generated configs vary modes, flags and
+//! limits (never above the defaults), leaving hooks, deadlines and
+//! pre-registered record definitions unset.
+//!
+//! ### `rc`
+//!
+//! Adds `SharedValue`, a read-only `Value` whose strings, arrays and objects
+//! are behind `Arc`s, so cloning a document or any subtree is O(1) and the
+//! clones can be sent to other threads: for fanning one decoded document
+//! out to many workers.
+
+#![cfg_attr(feature = "forbid-unsafe", forbid(unsafe_code))]
+
+/// Bump a [`metrics::CodecMetrics`] counter in `$owner.metrics` (a decoder or
+/// encoder). Expands to nothing without the `tracing` feature.
+macro_rules! count_metric {
+    ($owner:expr, $counter:ident) => {
+        count_metric!($owner, $counter, 1)
+    };
+    ($owner:expr, $counter:ident, $n:expr) => {
+        #[cfg(feature = "tracing")]
+        {
+            $owner.metrics.$counter += $n;
+        }
+    };
+}
 
+/// Enter a `tracing` span until the end of the enclosing block. Expands to
+/// nothing without the `tracing` feature.
+macro_rules! codec_span {
+    ($name:literal $(, $field:ident = $value:expr)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($name $(, $field = $value)*).entered();
+    };
+}
+
+/// Chainable setters for config fields, each named after its field. `Some`
+/// fields take the value to wrap.
+macro_rules! config_setters {
+    ($($(#[$attr:meta])* $field:ident: $ty:ty),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            #[doc = concat!("Set [`", stringify!($field), "`](Self::", stringify!($field), ").")]
+            #[must_use]
+            pub fn $field(mut self, $field: $ty) -> Self {
+                self.$field = $field;
+                self
+            }
+        )*
+    };
+    (Some: $($(#[$attr:meta])* $field:ident: $ty:ty),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            #[doc = concat!("Set [`", stringify!($field), "`](Self::", stringify!($field), ").")]
+            #[must_use]
+            pub fn $field(mut self, $field: $ty) -> Self {
+                self.$field = Some($field);
+                self
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls;
+pub mod archive;
+#[cfg(feature = "arena")]
+pub mod arena;
+pub mod backend;
+pub mod bench_corpus;
+pub mod blob;
+#[cfg(feature = "bytes")]
+pub mod bytes_mut;
+pub mod checksum;
+pub mod compare;
+pub mod compat;
+#[cfg(feature = "conformance")]
+pub mod conformance;
 pub mod de;
 pub mod decoder;
+pub mod duration;
+pub mod edit;
 pub mod encoder;
 pub mod error;
+pub mod hash;
+pub mod infer;
+pub mod instrumented;
+pub mod intern;
+pub mod json_compat;
+pub mod jsonpath;
+pub mod lazy;
+pub mod metadata;
+#[cfg(feature = "tracing")]
+pub mod metrics;
+pub mod normalize;
+pub mod optional_field;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod reader;
+pub mod relay;
+pub mod repair;
 pub mod ser;
+#[cfg(feature = "rc")]
+pub mod shared;
+pub mod subtrees;
+pub mod systemtime;
+pub mod tools;
+mod traced;
 pub mod types;
 pub mod value;
 
+#[cfg(all(test, feature = "arbitrary"))]
+mod arbitrary_impls_tests;
+#[cfg(test)]
+mod archive_tests;
+#[cfg(all(test, feature = "arena"))]
+mod arena_tests;
+#[cfg(test)]
+mod backend_tests;
+#[cfg(test)]
+mod bench_corpus_tests;
+#[cfg(test)]
+mod blob_tests;
+#[cfg(all(test, feature = "bytes"))]
+mod bytes_mut_tests;
+#[cfg(test)]
+mod checksum_tests;
+#[cfg(test)]
+mod compare_tests;
+#[cfg(test)]
+mod compat_tests;
 #[cfg(test)]
 mod de_tests;
 #[cfg(test)]
+mod duration_tests;
+#[cfg(test)]
 mod ser_tests;
+#[cfg(all(test, feature = "rc"))]
+mod shared_tests;
 #[cfg(test)]
 mod lib_tests;
 #[cfg(test)]
+mod hash_tests;
+#[cfg(test)]
+mod infer_tests;
+#[cfg(test)]
+mod instrumented_tests;
+#[cfg(test)]
+mod intern_tests;
+#[cfg(test)]
+mod json_compat_tests;
+#[cfg(test)]
+mod jsonpath_tests;
+#[cfg(test)]
+mod lazy_tests;
+#[cfg(test)]
+mod metadata_tests;
+#[cfg(all(test, feature = "tracing"))]
+mod metrics_tests;
+#[cfg(test)]
+mod normalize_tests;
+#[cfg(test)]
+mod optional_field_tests;
+#[cfg(all(test, feature = "rayon"))]
+mod parallel_tests;
+#[cfg(test)]
+mod reader_tests;
+#[cfg(test)]
+mod relay_tests;
+#[cfg(test)]
+mod repair_tests;
+#[cfg(test)]
+mod edit_tests;
+#[cfg(test)]
 mod error_tests;
 #[cfg(test)]
+mod subtrees_tests;
+#[cfg(test)]
+mod systemtime_tests;
+#[cfg(test)]
+mod tools_tests;
+#[cfg(test)]
 mod types_tests;
 #[cfg(test)]
 mod value_tests;
 
 // Re-export commonly used items at the crate root
-pub use de::{from_slice, from_slice_with_config, Deserializer};
-pub use decoder::{DecodedValue, Decoder, DecoderConfig, DuplicateKeyMode, InvalidUtf8Mode, NanInfinityMode, OutOfRangeMode, UnicodeNormalization};
-pub use encoder::{Encoder, EncoderConfig};
-pub use error::{Error, Result};
-pub use ser::{Serializer, SerializerConfig};
-pub use types::{limits, type_code, BigNumber};
-pub use value::Value;
+pub use backend::{BonjsonRead, BonjsonWrite};
+pub use compat::{build_info, check_compatibility, spec_version, BuildInfo, CapabilitySet, FormatReport};
+pub use de::{for_each_element, for_each_element_with_config, from_slice, from_slice_partial, from_slice_partial_with_config, from_slice_prefix, from_slice_prefix_with_config, from_slice_traced, from_slice_traced_with_config, from_slice_with, from_slice_with_config, from_slice_with_report, DecodeReport, Deserializer, StreamDeserializer};
+pub use decoder::{DecodedValue, DecodedValueOwned, Decoder, DecoderConfig, DuplicateKeyCheck, DuplicateKeyMode, FieldMatching, InvalidUtf8Mode, NanInfinityMode, OutOfRangeMode, ProgressHook, RecordMismatchMode, StringTransform, StringValidation, TypedArrayElement, TypedArraySlice, UnicodeNormalization, UnknownTypeCodeMode, COMBINED_VALIDATION_MAX_LEN};
+pub use edit::Editor;
+pub use encoder::{DuplicateKeys, Encoder, EncoderConfig, EncodingProfile, IntSignedness};
+pub use error::{DecodeError, EncodeError, Error, Result};
+pub use hash::{HashAlgo, NumberIdentity};
+pub use instrumented::InstrumentedDecoder;
+pub use intern::{CompactValue, Interner};
+pub use jsonpath::JsonPath;
+pub use lazy::LazyValue;
+pub use metadata::DocumentMetadata;
+pub use optional_field::OptionalField;
+#[cfg(feature = "rayon")]
+pub use parallel::{encode_value_parallel, encode_value_parallel_with_config};
+pub use reader::ReaderDeserializer;
+pub use repair::{repair, RepairOptions, RepairReport, Utf8Repair};
+pub use ser::{Serializer, SerializerConfig, TypedArrayStrategy};
+#[cfg(feature = "rc")]
+pub use shared::SharedValue;
+pub use tools::to_debug_string;
+pub use types::{decode_length_field, encode_length_field, length_field_size, limits, type_code, BigNumber, FloatWidth};
+pub use value::{ArrayMerge, CollectValue, ConflictPolicy, MergePolicy, Value};
 
 // The bonjson! and json! macros are automatically exported at crate root via #[macro_export]
 
@@ -195,15 +410,45 @@ pub fn to_vec<T: Serialize>(value: &T) -> Result<Vec<u8>> {
 
 /// Serialize a value to a BONJSON byte vector with custom configuration.
 ///
+/// When `config.records` runs the counting pass, the pass also predicts the
+/// encoded size and the vector is allocated once at that size, rather than
+/// growing (and copying) as the output is written.
+///
 /// # Errors
 ///
 /// Returns an error if serialization fails (e.g., NaN/infinity floats).
 pub fn to_vec_with_config<T: Serialize>(value: &T, config: &SerializerConfig) -> Result<Vec<u8>> {
-    let mut buf = Vec::with_capacity(128);
-    to_writer_with_config(&mut buf, value, config)?;
+    codec_span!("bonjson.serialize");
+    let plan = plan_records(value, config)?;
+    let capacity = plan.as_ref().map_or(128, |plan| plan.predicted_size);
+    let mut buf = Vec::with_capacity(capacity);
+    serialize_planned(&mut buf, value, config, plan)?;
     Ok(buf)
 }
 
+/// Serialize a value to a BONJSON byte vector, adjusting the default
+/// configuration with the config's setters.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::to_vec_with;
+///
+/// let bytes = to_vec_with(&vec![1u16, 2, 3], |cfg| cfg.records(true).typed_arrays(false)).unwrap();
+/// assert_eq!(bytes[0], serde_bonjson::type_code::ARRAY);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if serialization fails (e.g., NaN/infinity floats).
+pub fn to_vec_with<T, F>(value: &T, configure: F) -> Result<Vec<u8>>
+where
+    T: Serialize,
+    F: FnOnce(SerializerConfig) -> SerializerConfig,
+{
+    to_vec_with_config(value, &configure(SerializerConfig::default()))
+}
+
 /// Serialize a value to a writer.
 ///
 /// Uses the default [`SerializerConfig`] (typed arrays enabled, records disabled).
@@ -230,6 +475,9 @@ pub fn to_writer<W: Write, T: Serialize>(writer: W, value: &T) -> Result<()> {
 /// 1. Count struct types (lightweight, no I/O)
 /// 2. Emit record definitions for types appearing 2+ times, then serialize
 ///
+/// If `config.record_definitions` is non-empty, those definitions are emitted
+/// instead and the counting pass is skipped.
+///
 /// # Errors
 ///
 /// Returns an error if serialization fails or writing to the writer fails.
@@ -238,50 +486,129 @@ pub fn to_writer_with_config<W: Write, T: Serialize>(
     value: &T,
     config: &SerializerConfig,
 ) -> Result<()> {
-    use ser::CountingSerializer;
+    codec_span!("bonjson.serialize");
+    let plan = plan_records(value, config)?;
+    serialize_planned(writer, value, config, plan)
+}
+
+/// Serialize a value to a writer, adjusting the default configuration with
+/// the config's setters.
+///
+/// # Errors
+///
+/// Returns an error if serialization fails or writing to the writer fails.
+pub fn to_writer_with<W, T, F>(writer: W, value: &T, configure: F) -> Result<()>
+where
+    W: Write,
+    T: Serialize,
+    F: FnOnce(SerializerConfig) -> SerializerConfig,
+{
+    to_writer_with_config(writer, value, &configure(SerializerConfig::default()))
+}
+
+/// Record definitions chosen by the counting pass, and the output size it predicts.
+struct RecordPlan {
+    definitions: Vec<(&'static str, Vec<&'static str>)>,
+    predicted_size: usize,
+}
+
+/// Run the counting pass if `config` calls for one: records are enabled and
+/// allowed, and no definitions are pre-registered.
+fn plan_records<T: Serialize>(value: &T, config: &SerializerConfig) -> Result<Option<RecordPlan>> {
+    if !config.records || !config.capabilities.records || !config.record_definitions.is_empty() {
+        return Ok(None);
+    }
+    let mut counter = ser::CountingSerializer::for_config(config);
+    value.serialize(&mut counter)?;
+
+    // Structs appearing 2+ times with a consistent key layout, sorted for
+    // deterministic output
+    let mut definitions: Vec<(&'static str, Vec<&'static str>)> = counter
+        .struct_counts
+        .iter()
+        .filter(|(name, (_, count))| *count >= 2 && !counter.mixed_shapes.contains(*name))
+        .map(|(name, (keys, _))| (*name, keys.clone()))
+        .collect();
+    definitions.sort_by_key(|(name, _)| *name);
+
+    let predicted_size = counter.predicted_size(&definitions);
+    Ok(Some(RecordPlan { definitions, predicted_size }))
+}
+
+/// Encode a value whose counting pass (if any) has already run, followed by
+/// a checksum trailer if the config asks for one.
+fn serialize_planned<W: Write, T: Serialize>(
+    writer: W,
+    value: &T,
+    config: &SerializerConfig,
+    plan: Option<RecordPlan>,
+) -> Result<()> {
+    if !config.checksum_trailer {
+        return serialize_encoded(writer, value, config, plan);
+    }
+    let mut writer = checksum::ChecksumWriter::new(writer);
+    serialize_encoded(&mut writer, value, config, plan)?;
+    writer.finish()?;
+    Ok(())
+}
+
+fn serialize_encoded<W: Write, T: Serialize>(
+    writer: W,
+    value: &T,
+    config: &SerializerConfig,
+    plan: Option<RecordPlan>,
+) -> Result<()> {
+    let encoder_config = EncoderConfig {
+        capabilities: config.capabilities,
+        duplicate_keys: config.duplicate_keys,
+        key_normalization: config.key_normalization,
+        nan_infinity_mode: config.nan_infinity_mode,
+        #[cfg(feature = "tracing")]
+        metrics_sink: config.metrics_sink.clone(),
+        ..Default::default()
+    };
+    let mut encoder = Encoder::with_config(writer, encoder_config);
+    let result = serialize_document(&mut encoder, value, config, plan);
+    encoder.report_metrics();
+    result?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Write the record definitions (if any) and the value for `to_writer_with_config`.
+fn serialize_document<W: Write, T: Serialize>(
+    encoder: &mut Encoder<W>,
+    value: &T,
+    config: &SerializerConfig,
+    plan: Option<RecordPlan>,
+) -> Result<()> {
     use std::collections::HashMap;
 
-    let mut encoder = Encoder::new(writer);
-
-    // If records are enabled, run the counting pass first
-    let record_defs = if config.records {
-        let mut counter = CountingSerializer::new();
-        value.serialize(&mut counter)?;
-
-        // Filter to structs appearing 2+ times
-        let qualifying: Vec<(&'static str, Vec<&'static str>)> = counter
-            .struct_counts
-            .into_iter()
-            .filter(|(_, (_, count))| *count >= 2)
-            .map(|(name, (keys, _))| (name, keys))
-            .collect();
-
-        if qualifying.is_empty() {
-            None
-        } else {
-            // Sort for deterministic output
-            let mut sorted = qualifying;
-            sorted.sort_by_key(|(name, _)| *name);
-
-            // Write record definitions and build the lookup map
+    // Pre-registered definitions take precedence; otherwise use the
+    // definitions the counting pass chose
+    let definitions = if !config.capabilities.records {
+        None
+    } else if !config.record_definitions.is_empty() {
+        Some(config.record_definitions.as_slice())
+    } else {
+        plan.as_ref().map(|plan| plan.definitions.as_slice()).filter(|defs| !defs.is_empty())
+    };
+
+    // Write record definitions and build the lookup map
+    let record_defs = match definitions {
+        Some(definitions) => {
             let mut defs = HashMap::new();
-            for (def_index, (name, keys)) in sorted.iter().enumerate() {
-                let key_refs: Vec<&str> = keys.to_vec();
-                encoder.write_record_definition_unchecked(&key_refs)?;
+            for (def_index, (name, keys)) in definitions.iter().enumerate() {
+                encoder.write_record_definition_unchecked(keys)?;
                 defs.insert(*name, (keys.clone(), def_index));
             }
             Some(defs)
         }
-    } else {
-        None
+        None => None,
     };
 
-    {
-        let mut serializer = Serializer::with_config(&mut encoder, config.clone(), record_defs);
-        value.serialize(&mut serializer)?;
-    }
-    encoder.finish()?;
-    Ok(())
+    let mut serializer = Serializer::with_config(encoder, config.clone(), record_defs);
+    value.serialize(&mut serializer)
 }
 
 /// Deserialize from a reader.
@@ -300,7 +627,8 @@ pub fn to_writer_with_config<W: Write, T: Serialize>(
 /// # Performance Note
 ///
 /// This function reads the entire input into memory before parsing.
-/// For large files, consider memory-mapping or streaming approaches.
+/// For large root arrays, [`ReaderDeserializer::array_elements`] streams
+/// the elements instead, holding one element's bytes at a time.
 /// For better performance with unbuffered readers (files, network),
 /// wrap them in [`std::io::BufReader`]:
 ///
@@ -340,6 +668,21 @@ pub fn from_reader_with_config<R: Read, T: for<'de> Deserialize<'de>>(
     from_slice_with_config(&buf, config)
 }
 
+/// Deserialize from a reader, adjusting the default configuration with the
+/// config's setters.
+///
+/// # Errors
+///
+/// Returns an error if reading fails or deserialization fails.
+pub fn from_reader_with<R, T, F>(reader: R, configure: F) -> Result<T>
+where
+    R: Read,
+    T: for<'de> Deserialize<'de>,
+    F: FnOnce(DecoderConfig) -> DecoderConfig,
+{
+    from_reader_with_config(reader, configure(DecoderConfig::default()))
+}
+
 /// Convert a `T` into a [`Value`].
 ///
 /// This is useful when you have a typed struct but need a dynamic `Value`
@@ -428,12 +771,7 @@ pub fn from_value<T: for<'de> Deserialize<'de>>(value: &Value) -> Result<T> {
 /// - The data is malformed or truncated
 /// - There are trailing bytes after the value
 pub fn decode_value(data: &[u8]) -> Result<Value> {
-    let mut decoder = Decoder::new(data);
-    decoder.check_document_size()?;
-    decoder.read_record_definitions()?;
-    let value = decode_value_recursive(&mut decoder)?;
-    decoder.finish()?;
-    Ok(value)
+    decode_value_with_config(data, DecoderConfig::default())
 }
 
 /// Decode a BONJSON document into a `Value` with custom configuration.
@@ -445,17 +783,254 @@ pub fn decode_value(data: &[u8]) -> Result<Value> {
 /// - The data is malformed or truncated
 /// - There are trailing bytes (unless `allow_trailing_bytes` is set)
 pub fn decode_value_with_config(data: &[u8], config: DecoderConfig) -> Result<Value> {
+    codec_span!("bonjson.decode_value", input_bytes = data.len());
+    let mut decoder = Decoder::with_config(data, config);
+    let result = decode_document(&mut decoder);
+    decoder.report_metrics();
+    if result.is_ok() {
+        decoder.finish_progress();
+    }
+    result
+}
+
+/// Decode a BONJSON document into a `Value`, adjusting the default
+/// configuration with the config's setters.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{bonjson, decode_value_with, encode_value, Error};
+///
+/// let bytes = encode_value(&bonjson!([[[1]]])).unwrap();
+/// assert_eq!(decode_value_with(&bytes, |cfg| cfg.max_depth(2)), Err(Error::MaxDepthExceeded));
+/// ```
+///
+/// # Errors
+///
+/// Returns the error `decode_value_with_config` would.
+pub fn decode_value_with<F>(data: &[u8], configure: F) -> Result<Value>
+where
+    F: FnOnce(DecoderConfig) -> DecoderConfig,
+{
+    decode_value_with_config(data, configure(DecoderConfig::default()))
+}
+
+/// Decode the first `max_elements` elements of a document's root array,
+/// returning them and whether the array has more.
+///
+/// Only the elements returned (and the type code of the next one) are read,
+/// so previewing the head of a very large document costs the same as a small
+/// one. When the whole array was read, the result is what [`decode_value`]
+/// returns, trailing bytes check included.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{bonjson, decode_value_prefix, encode_value};
+///
+/// let bytes = encode_value(&bonjson!(["a", "b", "c"])).unwrap();
+/// assert_eq!(decode_value_prefix(&bytes, 2).unwrap(), (bonjson!(["a", "b"]), true));
+/// assert_eq!(decode_value_prefix(&bytes, 3).unwrap(), (bonjson!(["a", "b", "c"]), false));
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the root value isn't an array (regular or typed), or
+/// if the part read is malformed or exceeds the limits.
+pub fn decode_value_prefix(data: &[u8], max_elements: usize) -> Result<(Value, bool)> {
+    decode_value_prefix_with_config(data, max_elements, DecoderConfig::default())
+}
+
+/// Decode the first `max_elements` elements of a document's root array with
+/// custom configuration.
+///
+/// See [`decode_value_prefix`]. `max_document_size` applies to the bytes
+/// read, unless the whole array was.
+///
+/// # Errors
+///
+/// Returns the errors `decode_value_prefix` would, with the configured limits.
+pub fn decode_value_prefix_with_config(data: &[u8], max_elements: usize, config: DecoderConfig) -> Result<(Value, bool)> {
+    codec_span!("bonjson.decode_value_prefix", input_bytes = data.len());
+    let max_document_size = config.document_size_limit();
     let mut decoder = Decoder::with_config(data, config);
+    decoder.read_record_definitions()?;
+    let mut elements = Vec::new();
+    let more = match decoder.decode_value()? {
+        DecodedValue::ArrayStart => {
+            let max_size = decoder.config().max_container_size;
+            loop {
+                if !decoder.skip_to_array_element()? {
+                    break false;
+                }
+                if elements.len() == max_elements {
+                    break true;
+                }
+                if elements.len() >= max_size {
+                    return Err(Error::MaxContainerSizeExceeded);
+                }
+                decoder.begin_element(0)?;
+                elements.push(decode_value_recursive(&mut decoder)?);
+            }
+        }
+        DecodedValue::TypedArrayStart { element_type_code, count } => {
+            let len = count.min(max_elements);
+            decoder.charge_allocation(len.saturating_mul(decoder::ELEMENT_ALLOCATION))?;
+            elements.reserve(decoder.typed_array_capacity(element_type_code, len));
+            for _ in 0..len {
+                decoder.poll_cancellation()?;
+                let element = decoder.read_typed_array_element(element_type_code)?;
+                elements.push(typed_array_element_value(decoder.config(), element_type_code, element));
+            }
+            if len == count {
+                decoder.end_typed_array()?;
+            }
+            len < count
+        }
+        _ => return Err(Error::Custom("root value is not an array".into())),
+    };
+    if more {
+        if decoder.position() > max_document_size {
+            return Err(Error::MaxDocumentSizeExceeded);
+        }
+    } else {
+        decoder.check_document_size()?;
+        decoder.finish()?;
+    }
+    decoder.report_metrics();
+    Ok((Value::Array(elements), more))
+}
+
+/// Decode a BONJSON document that ends in a checksum trailer, checking the
+/// trailer before decoding anything.
+///
+/// Write such documents with `EncoderConfig::checksum_trailer` or
+/// `SerializerConfig::checksum_trailer`. A corrupted document is reported as
+/// `Error::ChecksumMismatch` rather than as whatever decode error the damage
+/// would otherwise cause. For serde targets, pass the bytes through
+/// [`checksum::verify_trailer`] first.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{decode_value_verified, encode_value_with_config, bonjson, EncoderConfig, Error};
+///
+/// let config = EncoderConfig { checksum_trailer: true, ..EncoderConfig::default() };
+/// let mut bytes = encode_value_with_config(&bonjson!({"reading": 21.5}), config).unwrap();
+/// assert_eq!(decode_value_verified(&bytes).unwrap(), bonjson!({"reading": 21.5}));
+///
+/// bytes[3] ^= 0x10;
+/// assert!(matches!(decode_value_verified(&bytes), Err(Error::ChecksumMismatch { .. })));
+/// ```
+///
+/// # Errors
+///
+/// Returns `Error::MissingChecksum` or `Error::ChecksumMismatch` if the
+/// trailer is absent or doesn't match, and otherwise the same errors as [`decode_value`].
+pub fn decode_value_verified(data: &[u8]) -> Result<Value> {
+    decode_value_verified_with_config(data, DecoderConfig::default())
+}
+
+/// Decode a BONJSON document that ends in a checksum trailer, with custom configuration.
+///
+/// # Errors
+///
+/// Returns the same errors as [`decode_value_verified`], with the configured limits.
+pub fn decode_value_verified_with_config(data: &[u8], config: DecoderConfig) -> Result<Value> {
+    decode_value_with_config(checksum::verify_trailer(data)?, config)
+}
+
+fn decode_document(decoder: &mut Decoder<'_>) -> Result<Value> {
     decoder.check_document_size()?;
     decoder.read_record_definitions()?;
-    let value = decode_value_recursive(&mut decoder)?;
+    let value = decode_value_recursive(decoder)?;
     decoder.finish()?;
     Ok(value)
 }
 
+/// Decode a BONJSON document into a `Value`, along with metadata describing it.
+///
+/// The metadata lists the document's record definitions and how often each
+/// was used, typed array statistics, and a breakdown of where the bytes went.
+/// It's tallied by the same decoder that builds the `Value`, so the document
+/// is read once.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{decode_with_metadata, encode_value, bonjson};
+///
+/// let bytes = encode_value(&bonjson!({"samples": [1.5, 2.5, 3.5]})).unwrap();
+/// let (value, metadata) = decode_with_metadata(&bytes).unwrap();
+/// assert!(value.is_object());
+/// assert_eq!(metadata.sizes.total, bytes.len());
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`decode_value`].
+pub fn decode_with_metadata(data: &[u8]) -> Result<(Value, DocumentMetadata)> {
+    decode_with_metadata_with_config(data, DecoderConfig::default())
+}
+
+/// Decode a BONJSON document into a `Value` and its metadata, with custom configuration.
+///
+/// # Errors
+///
+/// Returns the same errors as [`decode_value_with_config`].
+pub fn decode_with_metadata_with_config(
+    data: &[u8],
+    config: DecoderConfig,
+) -> Result<(Value, DocumentMetadata)> {
+    codec_span!("bonjson.decode_with_metadata", input_bytes = data.len());
+    let mut decoder = Decoder::with_config(data, config);
+    decoder.check_document_size()?;
+    decoder.read_record_definitions()?;
+    let definitions_end = decoder.position();
+    decoder.collect_metadata();
+    let value = decode_value_recursive(&mut decoder)?;
+    decoder.finish()?;
+    decoder.report_metrics();
+    decoder.finish_progress();
+    let metadata = decoder.take_metadata().unwrap_or_default().finish(&decoder, definitions_end);
+    Ok((value, metadata))
+}
+
+/// Read the record definitions at the start of a document, without decoding
+/// the value that follows them.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{bonjson, encode_value, read_record_definitions};
+///
+/// let bytes = encode_value(&bonjson!([{"x": 1, "y": 2}, {"x": 3, "y": 4}])).unwrap();
+/// assert_eq!(read_record_definitions(&bytes).unwrap(), [["x", "y"]]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the definitions are malformed or exceed the default
+/// limits, or if the document is larger than `max_document_size`.
+pub fn read_record_definitions(data: &[u8]) -> Result<Vec<Vec<String>>> {
+    read_record_definitions_with_config(data, DecoderConfig::default())
+}
+
+/// Read the record definitions at the start of a document, with custom configuration.
+///
+/// # Errors
+///
+/// Returns the same errors as [`read_record_definitions`], with the configured limits.
+pub fn read_record_definitions_with_config(data: &[u8], config: DecoderConfig) -> Result<Vec<Vec<String>>> {
+    let mut decoder = Decoder::with_config(data, config);
+    decoder.check_document_size()?;
+    decoder.read_record_definitions()?;
+    Ok(decoder.record_definitions().to_vec())
+}
+
 /// Apply NFC normalization if configured and the feature is enabled.
 #[cfg(feature = "unicode-normalization")]
-fn maybe_nfc_normalize(mode: decoder::UnicodeNormalization, s: String) -> String {
+pub(crate) fn maybe_nfc_normalize(mode: decoder::UnicodeNormalization, s: String) -> String {
     if mode == decoder::UnicodeNormalization::Nfc {
         use unicode_normalization::UnicodeNormalization;
         let normalized: String = s.nfc().collect();
@@ -467,10 +1042,46 @@ fn maybe_nfc_normalize(mode: decoder::UnicodeNormalization, s: String) -> String
 
 /// No-op when unicode-normalization feature is not enabled.
 #[cfg(not(feature = "unicode-normalization"))]
-fn maybe_nfc_normalize(_mode: decoder::UnicodeNormalization, s: String) -> String {
+pub(crate) fn maybe_nfc_normalize(_mode: decoder::UnicodeNormalization, s: String) -> String {
     s
 }
 
+/// Apply NFC normalization to a borrowed string if configured, borrowing it
+/// still when it's already in NFC.
+#[cfg(feature = "unicode-normalization")]
+pub(crate) fn nfc_normalize_borrowed(mode: decoder::UnicodeNormalization, s: &str) -> std::borrow::Cow<'_, str> {
+    if mode == decoder::UnicodeNormalization::Nfc && !unicode_normalization::is_nfc(s) {
+        use unicode_normalization::UnicodeNormalization;
+        std::borrow::Cow::Owned(s.nfc().collect())
+    } else {
+        std::borrow::Cow::Borrowed(s)
+    }
+}
+
+/// No-op when unicode-normalization feature is not enabled.
+#[cfg(not(feature = "unicode-normalization"))]
+pub(crate) fn nfc_normalize_borrowed(_mode: decoder::UnicodeNormalization, s: &str) -> std::borrow::Cow<'_, str> {
+    std::borrow::Cow::Borrowed(s)
+}
+
+/// A decoded object key after normalization and the configured `key_transform`.
+fn finish_key(config: &DecoderConfig, key: String) -> String {
+    let key = maybe_nfc_normalize(config.unicode_normalization, key);
+    match &config.key_transform {
+        Some(transform) => transform.apply_owned(key),
+        None => key,
+    }
+}
+
+/// A decoded string value after normalization and the configured `string_transform`.
+fn finish_string(config: &DecoderConfig, s: String) -> String {
+    let s = maybe_nfc_normalize(config.unicode_normalization, s);
+    match &config.string_transform {
+        Some(transform) => transform.apply_owned(s),
+        None => s,
+    }
+}
+
 /// Check if a BigNumber's value exceeds the representable range of f64.
 fn bignumber_exceeds_f64_range(bn: &BigNumber) -> bool {
     if bn.significand == 0 {
@@ -487,56 +1098,82 @@ fn bignumber_exceeds_f64_range(bn: &BigNumber) -> bool {
     value.is_infinite()
 }
 
-fn decode_value_recursive(decoder: &mut Decoder<'_>) -> Result<Value> {
+/// The string a BigNumber becomes under `OutOfRangeMode::Stringify` when it
+/// exceeds the configured limits or the f64 range, or None if it stays a number.
+/// Without `Stringify`, one beyond the f64 range is an error.
+pub(crate) fn stringify_big_number(config: &DecoderConfig, bn: &BigNumber) -> Result<Option<String>> {
+    let exceeds_f64 = bignumber_exceeds_f64_range(bn);
+    if config.out_of_range_mode == decoder::OutOfRangeMode::Stringify {
+        let exp_exceeded = (bn.exponent.unsigned_abs() as usize) > config.max_bignumber_exponent;
+        // Check magnitude byte count
+        let mag_bytes = if bn.significand == 0 { 0 } else { ((64 - bn.significand.leading_zeros()) as usize).div_ceil(8) };
+        let mag_exceeded = mag_bytes > config.max_bignumber_magnitude;
+        if exp_exceeded || mag_exceeded || exceeds_f64 {
+            return Ok(Some(bn.to_string_notation()));
+        }
+    } else if exceeds_f64 {
+        return Err(Error::ValueOutOfRange);
+    }
+    Ok(None)
+}
+
+/// A decoded float as a `Value`, keeping the width of the type code it was
+/// read with under `preserve_float_width`.
+fn float_value(config: &DecoderConfig, f: f64, tc: u8) -> Value {
+    match types::FloatWidth::from_type_code(tc) {
+        Some(width) if config.preserve_float_width => Value::SizedFloat(f, width),
+        _ => Value::Float(f),
+    }
+}
+
+/// Convert a typed array element into a `Value`, applying the NaN/Infinity mode.
+fn typed_array_element_value(config: &DecoderConfig, element_type_code: u8, elem: DecodedValue<'_>) -> Value {
+    match elem {
+        DecodedValue::Int(n) => Value::Int(n),
+        DecodedValue::UInt(n) => Value::UInt(n),
+        DecodedValue::Float(f) => match config.nan_infinity_mode.stringify(f) {
+            Some(name) => Value::String(name.into()),
+            None => float_value(config, f, element_type_code),
+        },
+        _ => unreachable!("typed array element must be numeric"),
+    }
+}
+
+pub(crate) fn decode_value_recursive(decoder: &mut Decoder<'_>) -> Result<Value> {
     use decoder::DuplicateKeyMode;
-    use decoder::NanInfinityMode;
-    use decoder::OutOfRangeMode;
+    use decoder::RecordMismatchMode;
 
+    // Only floats need their type code once decoded
+    let tc = if decoder.config().preserve_float_width { decoder.peek_type_code().ok() } else { None };
     match decoder.decode_value()? {
         DecodedValue::Null => Ok(Value::Null),
         DecodedValue::Bool(b) => Ok(Value::Bool(b)),
         DecodedValue::Int(n) => Ok(Value::Int(n)),
         DecodedValue::UInt(n) => Ok(Value::UInt(n)),
-        DecodedValue::Float(f) => {
-            if decoder.config().nan_infinity_mode == NanInfinityMode::Stringify {
-                if f.is_nan() {
-                    return Ok(Value::String("NaN".into()));
-                }
-                if f == f64::INFINITY {
-                    return Ok(Value::String("Infinity".into()));
-                }
-                if f == f64::NEG_INFINITY {
-                    return Ok(Value::String("-Infinity".into()));
-                }
-            }
-            Ok(Value::Float(f))
-        }
-        DecodedValue::BigNumber(bn) => {
-            let exceeds_f64 = bignumber_exceeds_f64_range(&bn);
-            if decoder.config().out_of_range_mode == OutOfRangeMode::Stringify {
-                let exp_exceeded = (bn.exponent.unsigned_abs() as usize) > decoder.config().max_bignumber_exponent;
-                // Check magnitude byte count
-                let mag_bytes = if bn.significand == 0 { 0 } else { ((64 - bn.significand.leading_zeros()) as usize + 7) / 8 };
-                let mag_exceeded = mag_bytes > decoder.config().max_bignumber_magnitude;
-                if exp_exceeded || mag_exceeded || exceeds_f64 {
-                    return Ok(Value::String(bn.to_string_notation()));
-                }
-            } else if exceeds_f64 {
-                return Err(Error::ValueOutOfRange);
-            }
-            Ok(Value::BigNumber(bn))
-        }
+        DecodedValue::Float(f) => match decoder.config().nan_infinity_mode.stringify(f) {
+            Some(name) => Ok(Value::String(name.into())),
+            None => Ok(tc.map_or(Value::Float(f), |tc| float_value(decoder.config(), f, tc))),
+        },
+        DecodedValue::BigNumber(bn) => match stringify_big_number(decoder.config(), &bn)? {
+            Some(s) => Ok(Value::String(s)),
+            None => Ok(Value::BigNumber(bn)),
+        },
         DecodedValue::String(s) => {
+            decoder.charge_allocation(s.len())?;
             let owned = s.into_owned();
-            Ok(Value::String(maybe_nfc_normalize(decoder.config().unicode_normalization, owned)))
+            Ok(Value::String(finish_string(decoder.config(), owned)))
         }
         DecodedValue::ArrayStart => {
             let max_size = decoder.config().max_container_size;
             let mut arr = Vec::new();
             while !decoder.is_at_container_end()? {
+                if decoder.skip_unknown_value()? {
+                    continue;
+                }
                 if arr.len() >= max_size {
                     return Err(Error::MaxContainerSizeExceeded);
                 }
+                decoder.begin_element(0)?;
                 arr.push(decode_value_recursive(decoder)?);
             }
             decoder.end_container()?;
@@ -554,10 +1191,14 @@ fn decode_value_recursive(decoder: &mut Decoder<'_>) -> Result<Value> {
                 let key_value = decoder.decode_value()?;
                 let key = match key_value {
                     DecodedValue::String(s) => {
-                        maybe_nfc_normalize(decoder.config().unicode_normalization, s.into_owned())
+                        decoder.begin_element(s.len())?;
+                        finish_key(decoder.config(), s.into_owned())
                     }
                     _ => return Err(Error::ExpectedObjectKey),
                 };
+                if decoder.skip_unknown_value()? {
+                    continue;
+                }
                 let value = decode_value_recursive(decoder)?;
                 // Check for duplicate key
                 if map.contains_key(&key) {
@@ -579,25 +1220,32 @@ fn decode_value_recursive(decoder: &mut Decoder<'_>) -> Result<Value> {
             Ok(Value::Object(map))
         }
         DecodedValue::RecordInstanceStart(def_index) => {
-            let keys = decoder.record_definitions()[def_index].clone();
+            let definitions = decoder.shared_record_definitions();
+            let keys = &definitions[def_index];
             let dup_mode = decoder.config().duplicate_key_mode;
             let max_size = decoder.config().max_container_size;
+            let mismatch_mode = decoder.config().record_mismatch;
             let mut map = std::collections::BTreeMap::new();
             let mut value_count: usize = 0;
+            let mut extra_count: usize = 0;
 
             while !decoder.is_at_container_end()? {
+                if value_count + extra_count >= max_size {
+                    return Err(Error::MaxContainerSizeExceeded);
+                }
                 if value_count >= keys.len() {
-                    return Err(Error::InvalidData(
-                        "record instance has more values than keys".into(),
-                    ));
+                    // Decode extras so the error can report the real count
+                    decoder.begin_element(0)?;
+                    decode_value_recursive(decoder)?;
+                    extra_count += 1;
+                    continue;
                 }
-                if value_count >= max_size {
-                    return Err(Error::MaxContainerSizeExceeded);
+                decoder.begin_element(keys[value_count].len())?;
+                if decoder.skip_unknown_value()? {
+                    value_count += 1;
+                    continue;
                 }
-                let key = maybe_nfc_normalize(
-                    decoder.config().unicode_normalization,
-                    keys[value_count].clone(),
-                );
+                let key = finish_key(decoder.config(), keys[value_count].clone());
                 let value = decode_value_recursive(decoder)?;
                 if map.contains_key(&key) {
                     match dup_mode {
@@ -613,41 +1261,33 @@ fn decode_value_recursive(decoder: &mut Decoder<'_>) -> Result<Value> {
                 value_count += 1;
             }
             decoder.end_container()?;
-            // Remaining keys get Value::Null
-            for key in keys.iter().skip(value_count) {
-                let key = maybe_nfc_normalize(
-                    decoder.config().unicode_normalization,
-                    key.clone(),
-                );
-                map.entry(key).or_insert(Value::Null);
+
+            let too_many = extra_count > 0 && mismatch_mode != RecordMismatchMode::Truncate;
+            let too_few = value_count < keys.len() && mismatch_mode == RecordMismatchMode::Error;
+            if too_many || too_few {
+                return Err(Error::RecordMismatch {
+                    definition: def_index,
+                    keys: keys.len(),
+                    values: value_count + extra_count,
+                });
+            }
+
+            if mismatch_mode == RecordMismatchMode::NullFill {
+                for key in keys.iter().skip(value_count) {
+                    decoder.begin_element(key.len())?;
+                    let key = finish_key(decoder.config(), key.clone());
+                    map.entry(key).or_insert(Value::Null);
+                }
             }
             Ok(Value::Object(map))
         }
         DecodedValue::TypedArrayStart { element_type_code, count } => {
-            let mut arr = Vec::with_capacity(count);
+            decoder.charge_allocation(count.saturating_mul(decoder::ELEMENT_ALLOCATION))?;
+            let mut arr = Vec::with_capacity(decoder.typed_array_capacity(element_type_code, count));
             for _ in 0..count {
+                decoder.poll_cancellation()?;
                 let elem = decoder.read_typed_array_element(element_type_code)?;
-                let value = match elem {
-                    DecodedValue::Int(n) => Value::Int(n),
-                    DecodedValue::UInt(n) => Value::UInt(n),
-                    DecodedValue::Float(f) => {
-                        if decoder.config().nan_infinity_mode == NanInfinityMode::Stringify {
-                            if f.is_nan() {
-                                Value::String("NaN".into())
-                            } else if f == f64::INFINITY {
-                                Value::String("Infinity".into())
-                            } else if f == f64::NEG_INFINITY {
-                                Value::String("-Infinity".into())
-                            } else {
-                                Value::Float(f)
-                            }
-                        } else {
-                            Value::Float(f)
-                        }
-                    }
-                    _ => unreachable!("typed array element must be numeric"),
-                };
-                arr.push(value);
+                arr.push(typed_array_element_value(decoder.config(), element_type_code, elem));
             }
             decoder.end_typed_array()?;
             Ok(Value::Array(arr))
@@ -683,10 +1323,7 @@ pub fn encode_value(value: &Value) -> Result<Vec<u8>> {
 ///
 /// Returns an error if encoding fails or writing to the writer fails.
 pub fn encode_value_to_writer<W: Write>(writer: W, value: &Value) -> Result<()> {
-    let mut encoder = Encoder::new(writer);
-    encode_value_with_records(&mut encoder, value)?;
-    encoder.finish()?;
-    Ok(())
+    encode_value_to_writer_with_config(writer, value, EncoderConfig::default())
 }
 
 /// Encode a `Value` to BONJSON bytes with the given configuration.
@@ -700,20 +1337,50 @@ pub fn encode_value_with_config(value: &Value, config: EncoderConfig) -> Result<
     Ok(buf)
 }
 
+/// Encode a `Value` to BONJSON bytes, adjusting the default configuration
+/// with the config's setters.
+///
+/// # Errors
+///
+/// Returns an error if encoding fails (e.g., NaN/infinity floats in the value).
+pub fn encode_value_with<F>(value: &Value, configure: F) -> Result<Vec<u8>>
+where
+    F: FnOnce(EncoderConfig) -> EncoderConfig,
+{
+    encode_value_with_config(value, configure(EncoderConfig::default()))
+}
+
 /// Encode a `Value` to a writer with the given configuration.
 ///
 /// # Errors
 ///
 /// Returns an error if encoding fails or writing to the writer fails.
 pub fn encode_value_to_writer_with_config<W: Write>(writer: W, value: &Value, config: EncoderConfig) -> Result<()> {
+    codec_span!("bonjson.encode_value");
+    if config.checksum_trailer {
+        let mut writer = checksum::ChecksumWriter::new(writer);
+        encode_document(&mut writer, value, config)?;
+        writer.finish()?;
+        return Ok(());
+    }
+    encode_document(writer, value, config)
+}
+
+fn encode_document<W: Write>(writer: W, value: &Value, config: EncoderConfig) -> Result<()> {
     let mut encoder = Encoder::with_config(writer, config);
-    encode_value_with_records(&mut encoder, value)?;
+    let result = encode_value_with_records(&mut encoder, value);
+    encoder.report_metrics();
+    result?;
     encoder.finish()?;
     Ok(())
 }
 
 /// Encode a value with automatic record definition detection.
 fn encode_value_with_records<W: Write>(encoder: &mut Encoder<W>, value: &Value) -> Result<()> {
+    if !encoder.config().capabilities.records {
+        return encode_value_recursive(encoder, value);
+    }
+
     // Collect record definitions (key sets appearing 2+ times)
     let defs = collect_record_definitions(value);
 
@@ -736,11 +1403,11 @@ fn encode_value_with_records<W: Write>(encoder: &mut Encoder<W>, value: &Value)
     }
 
     // Encode values using record instances where applicable
-    encode_value_recursive_inner(encoder, value, &defs, &def_index_map)
+    Ok(encode_value_recursive_inner(encoder, value, &defs, &def_index_map)?)
 }
 
 /// Detect if an array can be encoded as a typed array and return the type code if so.
-fn detect_typed_array(arr: &[Value]) -> Option<u8> {
+pub(crate) fn detect_typed_array(arr: &[Value]) -> Option<u8> {
     use crate::types::type_code as tc;
     if arr.is_empty() {
         return None;
@@ -754,6 +1421,8 @@ fn detect_typed_array(arr: &[Value]) -> Option<u8> {
     let mut min_signed: i64 = 0;
     let mut max_signed: i64 = 0;
     let mut needs_f64 = false;
+    // A float32 typed array can't hold a SizedFloat of F64, nor the reverse
+    let mut needs_f32 = false;
 
     for v in arr {
         match v {
@@ -796,11 +1465,25 @@ fn detect_typed_array(arr: &[Value]) -> Option<u8> {
                     needs_f64 = true;
                 }
             }
+            Value::SizedFloat(f, width) => {
+                all_int = false;
+                all_uint = false;
+                if !f.is_finite() || !width.holds(*f) {
+                    return None;
+                }
+                match width {
+                    FloatWidth::F32 => needs_f32 = true,
+                    FloatWidth::F64 => needs_f64 = true,
+                }
+            }
             _ => return None, // Non-numeric element
         }
     }
 
     if all_float {
+        if needs_f32 && needs_f64 {
+            return None;
+        }
         return Some(if needs_f64 { tc::TYPED_ARRAY_FLOAT64 } else { tc::TYPED_ARRAY_FLOAT32 });
     }
 
@@ -847,7 +1530,7 @@ fn encode_typed_array_data(arr: &[Value], element_type_code: u8) -> Vec<u8> {
             tc::TYPED_ARRAY_FLOAT32 => {
                 #[allow(clippy::cast_possible_truncation)]
                 let f = match v {
-                    Value::Float(f) => *f as f32,
+                    Value::Float(f) | Value::SizedFloat(f, _) => *f as f32,
                     Value::Int(n) => *n as f32,
                     Value::UInt(n) => *n as f32,
                     _ => unreachable!(),
@@ -856,7 +1539,7 @@ fn encode_typed_array_data(arr: &[Value], element_type_code: u8) -> Vec<u8> {
             }
             tc::TYPED_ARRAY_FLOAT64 => {
                 let f = match v {
-                    Value::Float(f) => *f,
+                    Value::Float(f) | Value::SizedFloat(f, _) => *f,
                     Value::Int(n) => *n as f64,
                     Value::UInt(n) => *n as f64,
                     _ => unreachable!(),
@@ -916,7 +1599,11 @@ fn value_as_u64(v: &Value) -> u64 {
 fn collect_record_definitions(value: &Value) -> Vec<Vec<String>> {
     let mut key_set_counts: std::collections::HashMap<Vec<String>, usize> = std::collections::HashMap::new();
     count_key_sets(value, &mut key_set_counts);
+    record_definitions_from_counts(key_set_counts)
+}
 
+/// The key sets counted at least twice, sorted.
+pub(crate) fn record_definitions_from_counts(key_set_counts: std::collections::HashMap<Vec<String>, usize>) -> Vec<Vec<String>> {
     let mut defs: Vec<Vec<String>> = key_set_counts
         .into_iter()
         .filter(|(_, count)| *count >= 2)
@@ -927,7 +1614,7 @@ fn collect_record_definitions(value: &Value) -> Vec<Vec<String>> {
     defs
 }
 
-fn count_key_sets(value: &Value, counts: &mut std::collections::HashMap<Vec<String>, usize>) {
+pub(crate) fn count_key_sets(value: &Value, counts: &mut std::collections::HashMap<Vec<String>, usize>) {
     match value {
         Value::Object(map) => {
             if !map.is_empty() {
@@ -948,29 +1635,43 @@ fn count_key_sets(value: &Value, counts: &mut std::collections::HashMap<Vec<Stri
 }
 
 fn encode_value_recursive<W: Write>(encoder: &mut Encoder<W>, value: &Value) -> Result<()> {
-    encode_value_recursive_inner(encoder, value, &[], &std::collections::HashMap::new())
+    Ok(encode_value_recursive_inner(encoder, value, &[], &std::collections::HashMap::new())?)
 }
 
 #[allow(clippy::only_used_in_recursion)]
-fn encode_value_recursive_inner<W: Write>(
+pub(crate) fn encode_value_recursive_inner<W: Write>(
     encoder: &mut Encoder<W>,
     value: &Value,
     record_defs: &[Vec<String>],
     def_index_map: &std::collections::HashMap<Vec<String>, usize>,
-) -> Result<()> {
+) -> encoder::Result<()> {
     match value {
         Value::Null => encoder.write_null(),
         Value::Bool(b) => encoder.write_bool(*b),
         Value::Int(n) => encoder.write_i64(*n),
         Value::UInt(n) => encoder.write_u64(*n),
         Value::Float(f) => encoder.write_f64(*f),
-        Value::BigNumber(bn) => encoder.write_big_number(*bn),
+        Value::SizedFloat(f, width) => encoder.write_float_with_width(*f, *width),
+        Value::BigNumber(bn) => {
+            // Integral BigNumbers have a plain integer encoding to fall back on
+            if !encoder.config().capabilities.big_numbers {
+                if let Some(n) = bn.to_i64() {
+                    return encoder.write_i64(n);
+                }
+                if let Some(n) = bn.to_u64() {
+                    return encoder.write_u64(n);
+                }
+            }
+            encoder.write_big_number(*bn)
+        }
         Value::String(s) => encoder.write_str(s),
         Value::Array(arr) => {
             // Try typed array encoding
-            if let Some(element_tc) = detect_typed_array(arr) {
-                let data = encode_typed_array_data(arr, element_tc);
-                return encoder.write_typed_array_raw(element_tc, arr.len(), &data);
+            if encoder.config().capabilities.typed_arrays {
+                if let Some(element_tc) = detect_typed_array(arr) {
+                    let data = encode_typed_array_data(arr, element_tc);
+                    return encoder.write_typed_array_raw(element_tc, arr.len(), &data);
+                }
             }
             encoder.begin_array()?;
             for item in arr {
@@ -1009,11 +1710,11 @@ impl Serialize for Value {
             Value::Bool(b) => serializer.serialize_bool(*b),
             Value::Int(n) => serializer.serialize_i64(*n),
             Value::UInt(n) => serializer.serialize_u64(*n),
-            Value::Float(f) => serializer.serialize_f64(*f),
-            Value::BigNumber(bn) => {
-                // Serialize BigNumber as f64 for compatibility
-                serializer.serialize_f64(bn.to_f64())
-            }
+            Value::Float(f) | Value::SizedFloat(f, FloatWidth::F64) => serializer.serialize_f64(*f),
+            #[allow(clippy::cast_possible_truncation)]
+            Value::SizedFloat(f, FloatWidth::F32) => serializer.serialize_f32(*f as f32),
+            // Exact in BONJSON; other formats get its decimal string
+            Value::BigNumber(bn) => bn.serialize(serializer),
             Value::String(s) => serializer.serialize_str(s),
             Value::Array(arr) => {
                 use serde::ser::SerializeSeq;
@@ -1064,6 +1765,27 @@ impl<'de> Deserialize<'de> for Value {
                 }
             }
 
+            fn visit_i128<E: serde::de::Error>(self, v: i128) -> std::result::Result<Value, E> {
+                if let Ok(n) = i64::try_from(v) {
+                    return Ok(Value::Int(n));
+                }
+                if let Ok(n) = u64::try_from(v) {
+                    return Ok(Value::UInt(n));
+                }
+                BigNumber::from_i128(v)
+                    .map(Value::BigNumber)
+                    .ok_or_else(|| E::custom("i128 value has too many significant digits"))
+            }
+
+            fn visit_u128<E: serde::de::Error>(self, v: u128) -> std::result::Result<Value, E> {
+                if let Ok(n) = u64::try_from(v) {
+                    return Ok(Value::from(n));
+                }
+                BigNumber::from_u128(v)
+                    .map(Value::BigNumber)
+                    .ok_or_else(|| E::custom("u128 value has too many significant digits"))
+            }
+
             fn visit_f64<E>(self, v: f64) -> std::result::Result<Value, E> {
                 Ok(Value::Float(v))
             }
@@ -1102,18 +1824,32 @@ impl<'de> Deserialize<'de> for Value {
                 Ok(Value::Array(arr))
             }
 
+            fn visit_newtype_struct<D: serde::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> std::result::Result<Value, D::Error> {
+                deserializer.deserialize_any(self)
+            }
+
             fn visit_map<A: serde::de::MapAccess<'de>>(
                 self,
                 mut map: A,
             ) -> std::result::Result<Value, A::Error> {
                 let mut obj = std::collections::BTreeMap::new();
-                while let Some((key, val)) = map.next_entry()? {
-                    obj.insert(key, val);
+                while let Some(key) = map.next_key::<String>()? {
+                    // How the Deserializer hands over an exact BigNumber
+                    if obj.is_empty() && key == types::BIG_NUMBER_TOKEN {
+                        let digits: String = map.next_value()?;
+                        return digits.parse().map(Value::BigNumber).map_err(serde::de::Error::custom);
+                    }
+                    obj.insert(key, map.next_value()?);
                 }
                 Ok(Value::Object(obj))
             }
         }
 
-        deserializer.deserialize_any(ValueVisitor)
+        // Asking for a newtype lets this crate's Deserializer pass BigNumbers
+        // through exactly; other deserializers read the value as usual
+        deserializer.deserialize_newtype_struct(types::VALUE_TOKEN, ValueVisitor)
     }
 }
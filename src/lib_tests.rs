@@ -267,3 +267,508 @@ fn test_json_macro_alias() {
     assert_eq!(json!("hello"), bonjson!("hello"));
     assert_eq!(json!([1, 2, 3]), bonjson!([1, 2, 3]));
 }
+
+// =========================================================================
+// Lossless u64 tests
+// =========================================================================
+
+const LARGE_U64S: [u64; 4] = [i64::MAX as u64 + 1, 1 << 63 | 1, u64::MAX - 1, u64::MAX];
+
+#[test]
+fn test_large_u64_to_value_from_value() {
+    for n in LARGE_U64S {
+        let value = to_value(&n).unwrap();
+        assert_eq!(value, Value::UInt(n));
+        assert_eq!(from_value::<u64>(&value).unwrap(), n);
+    }
+}
+
+#[test]
+fn test_large_u64_encode_decode_value() {
+    for n in LARGE_U64S {
+        let bytes = encode_value(&Value::UInt(n)).unwrap();
+        assert_eq!(decode_value(&bytes).unwrap(), Value::UInt(n));
+        assert_eq!(from_slice::<u64>(&bytes).unwrap(), n);
+    }
+}
+
+#[test]
+fn test_large_u64_serde_value_bridge() {
+    for n in LARGE_U64S {
+        // Typed -> bytes -> Value via serde
+        let bytes = to_vec(&n).unwrap();
+        assert_eq!(from_slice::<Value>(&bytes).unwrap(), Value::UInt(n));
+
+        // Value -> bytes via serde -> typed
+        let bytes = to_vec(&Value::UInt(n)).unwrap();
+        assert_eq!(from_slice::<u64>(&bytes).unwrap(), n);
+    }
+}
+
+#[test]
+fn test_large_u64_in_containers() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Counter {
+        id: u64,
+        history: Vec<u64>,
+    }
+
+    let original = Counter {
+        id: u64::MAX,
+        history: LARGE_U64S.to_vec(),
+    };
+
+    let value = to_value(&original).unwrap();
+    assert_eq!(value.get_key("id"), Some(&Value::UInt(u64::MAX)));
+    let history: Vec<Value> = LARGE_U64S.iter().map(|n| Value::UInt(*n)).collect();
+    assert_eq!(value.get_key("history"), Some(&Value::Array(history)));
+
+    let decoded: Counter = from_value(&value).unwrap();
+    assert_eq!(decoded, original);
+
+    let bytes = encode_value(&value).unwrap();
+    let decoded: Counter = from_slice(&bytes).unwrap();
+    assert_eq!(decoded, original);
+}
+
+#[test]
+fn test_large_u64_into_i64_is_out_of_range() {
+    let bytes = to_vec(&u64::MAX).unwrap();
+    assert!(from_slice::<i64>(&bytes).is_err());
+    assert!(from_value::<i64>(&Value::UInt(i64::MAX as u64 + 1)).is_err());
+}
+
+#[test]
+fn test_float_two_pow_63_is_not_saturated() {
+    let two_pow_63 = 9_223_372_036_854_775_808.0_f64;
+
+    let bytes = encode_value(&Value::Float(two_pow_63)).unwrap();
+    assert_eq!(decode_value(&bytes).unwrap(), Value::Float(two_pow_63));
+
+    let bytes = to_vec(&two_pow_63).unwrap();
+    assert_eq!(from_slice::<f64>(&bytes).unwrap(), two_pow_63);
+    assert_ne!(from_slice::<Value>(&bytes).unwrap(), Value::Int(i64::MAX));
+}
+
+// =========================================================================
+// Record mismatch tests
+// =========================================================================
+
+#[test]
+fn test_decode_value_record_mismatch_modes() {
+    use crate::{decode_value_with_config, Error, RecordMismatchMode};
+
+    // Record definition ["a", "b"], instances {1} and {1, 2, 3}
+    let fewer = [0xb9, 0x66, b'a', 0x66, b'b', 0xb6, 0xba, 0x00, 0x01, 0xb6];
+    let extra = [0xb9, 0x66, b'a', 0x66, b'b', 0xb6, 0xba, 0x00, 0x01, 0x02, 0x03, 0xb6];
+    let config = |mode| DecoderConfig {
+        record_mismatch: mode,
+        ..Default::default()
+    };
+
+    // NullFill (default)
+    assert_eq!(decode_value(&fewer).unwrap(), bonjson!({"a": 1, "b": null}));
+    assert_eq!(
+        decode_value(&extra).unwrap_err(),
+        Error::RecordMismatch { definition: 0, keys: 2, values: 3 }
+    );
+
+    // Truncate
+    let truncate = config(RecordMismatchMode::Truncate);
+    assert_eq!(decode_value_with_config(&fewer, truncate.clone()).unwrap(), bonjson!({"a": 1}));
+    assert_eq!(decode_value_with_config(&extra, truncate).unwrap(), bonjson!({"a": 1, "b": 2}));
+
+    // Error
+    let error = config(RecordMismatchMode::Error);
+    assert_eq!(
+        decode_value_with_config(&fewer, error.clone()).unwrap_err(),
+        Error::RecordMismatch { definition: 0, keys: 2, values: 1 }
+    );
+    assert!(decode_value_with_config(&extra, error).is_err());
+}
+
+#[test]
+fn test_nested_record_instances_count_toward_max_depth() {
+    use crate::{decode_value_with_config, from_slice_with_config, Error, RecordMismatchMode};
+
+    // Definition ["a"], then `depth` instances each holding the next, around a 1
+    let nested = |depth: usize| {
+        let mut bytes = vec![0xb9, 0x66, b'a', 0xb6];
+        bytes.extend([0xba, 0x00].repeat(depth));
+        bytes.push(0x01);
+        bytes.extend(std::iter::repeat_n(0xb6, depth));
+        bytes
+    };
+    let config = DecoderConfig { max_depth: 10, ..Default::default() };
+    assert!(decode_value_with_config(&nested(10), config.clone()).is_ok());
+    assert_eq!(decode_value_with_config(&nested(11), config.clone()).unwrap_err(), Error::MaxDepthExceeded);
+    assert!(from_slice_with_config::<Value>(&nested(10), config.clone()).is_ok());
+    assert_eq!(from_slice_with_config::<Value>(&nested(11), config.clone()).unwrap_err(), Error::MaxDepthExceeded);
+    assert!(from_slice_with_config::<serde_json::Value>(&nested(11), config.clone()).is_err());
+
+    // Records and plain containers share one depth count
+    let mut mixed = vec![0xb9, 0x66, b'a', 0xb6];
+    mixed.extend([0xba, 0x00, 0xb7, 0xb8, 0x66, b'k'].repeat(3));
+    mixed.push(0x01);
+    mixed.extend([0xb6; 9]);
+    let config = |max_depth| DecoderConfig { max_depth, ..Default::default() };
+    assert!(decode_value_with_config(&mixed, config(9)).is_ok());
+    assert_eq!(decode_value_with_config(&mixed, config(8)).unwrap_err(), Error::MaxDepthExceeded);
+    assert_eq!(from_slice_with_config::<Value>(&mixed, config(8)).unwrap_err(), Error::MaxDepthExceeded);
+
+    // Far beyond the limit, decoding fails cleanly instead of overflowing the stack
+    let deep = nested(100_000);
+    assert_eq!(decode_value_with_config(&deep, config(64)).unwrap_err(), Error::MaxDepthExceeded);
+    assert_eq!(from_slice_with_config::<Value>(&deep, config(64)).unwrap_err(), Error::MaxDepthExceeded);
+
+    // Values past the definition's keys are elements like any other
+    let mut extra = vec![0xb9, 0x66, b'a', 0xb6, 0xba, 0x00];
+    extra.extend([0x01; 20]);
+    extra.push(0xb6);
+    let truncate = DecoderConfig { record_mismatch: RecordMismatchMode::Truncate, ..Default::default() };
+    let limited = DecoderConfig { max_container_size: 10, ..truncate.clone() };
+    assert_eq!(decode_value_with_config(&extra, truncate.clone()).unwrap(), bonjson!({"a": 1}));
+    assert_eq!(decode_value_with_config(&extra, limited.clone()).unwrap_err(), Error::MaxContainerSizeExceeded);
+    assert_eq!(from_slice_with_config::<Value>(&extra, truncate.clone()).unwrap(), bonjson!({"a": 1}));
+    assert_eq!(from_slice_with_config::<Value>(&extra, limited).unwrap_err(), Error::MaxContainerSizeExceeded);
+    let budget = DecoderConfig { max_total_allocated_bytes: 10 * crate::decoder::ELEMENT_ALLOCATION, ..truncate };
+    assert_eq!(decode_value_with_config(&extra, budget.clone()).unwrap_err(), Error::MaxTotalAllocatedBytesExceeded);
+    assert_eq!(from_slice_with_config::<Value>(&extra, budget).unwrap_err(), Error::MaxTotalAllocatedBytesExceeded);
+}
+
+#[test]
+fn test_decode_value_max_total_allocated_bytes() {
+    use crate::{decode_value_with_config, Error};
+
+    // Ten 100-byte strings: each is under max_string_length, together they aren't
+    let strings: Vec<String> = (0..10).map(|i| format!("{i:0>100}")).collect();
+    let bytes = to_vec(&strings).unwrap();
+    let config = |limit| DecoderConfig { max_total_allocated_bytes: limit, ..Default::default() };
+
+    assert_eq!(
+        decode_value_with_config(&bytes, config(500)).unwrap_err(),
+        Error::MaxTotalAllocatedBytesExceeded
+    );
+    assert!(decode_value_with_config(&bytes, config(2000)).is_ok());
+
+    // Container elements are charged even when they hold no string data
+    let bytes = to_vec(&vec![vec![(); 0]; 100]).unwrap();
+    assert!(decode_value_with_config(&bytes, config(100)).is_err());
+
+    // Typed arrays are charged for their element count up front
+    let bytes = to_vec(&vec![0.1f64; 1000]).unwrap();
+    assert_eq!(bytes[0], crate::type_code::TYPED_ARRAY_FLOAT64);
+    assert!(decode_value_with_config(&bytes, config(8000)).is_err());
+}
+
+#[test]
+fn test_decode_value_cancellation() {
+    use crate::{decode_value_with_config, from_slice_with_config, Error};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    let long = to_vec(&vec![vec![1, 2]; 5000]).unwrap();
+    let short = to_vec(&vec![1, 2, 3]).unwrap();
+
+    let token = Arc::new(AtomicBool::new(false));
+    let config = DecoderConfig { cancel_token: Some(Arc::clone(&token)), ..Default::default() };
+    assert!(decode_value_with_config(&long, config.clone()).is_ok());
+    token.store(true, Ordering::Relaxed);
+    assert_eq!(decode_value_with_config(&long, config.clone()).unwrap_err(), Error::Cancelled);
+    assert_eq!(
+        from_slice_with_config::<Vec<Vec<i32>>>(&long, config.clone()).unwrap_err(),
+        Error::Cancelled
+    );
+    // Polling is periodic, so a document shorter than the interval still decodes
+    assert!(decode_value_with_config(&short, config).is_ok());
+
+    let expired = DecoderConfig { deadline: Some(Instant::now()), ..Default::default() };
+    assert_eq!(decode_value_with_config(&long, expired.clone()).unwrap_err(), Error::DeadlineExceeded);
+    let typed = to_vec(&vec![0.1f64; 5000]).unwrap();
+    assert_eq!(decode_value_with_config(&typed, expired).unwrap_err(), Error::DeadlineExceeded);
+
+    let generous = DecoderConfig {
+        deadline: Some(Instant::now() + Duration::from_secs(3600)),
+        ..Default::default()
+    };
+    assert!(decode_value_with_config(&long, generous).is_ok());
+}
+
+#[test]
+fn test_decode_value_string_transforms() {
+    use crate::{decode_value_with_config, StringTransform};
+    use std::borrow::Cow;
+
+    let config = DecoderConfig {
+        key_transform: Some(StringTransform::ascii_lowercase()),
+        string_transform: Some(StringTransform::new(|s| Cow::Borrowed(s.trim()))),
+        ..Default::default()
+    };
+    let bytes = encode_value(&bonjson!({"Name": " Ada ", "TAGS": [" x", "y "], "ok": {"Inner": "v"}})).unwrap();
+    assert_eq!(
+        decode_value_with_config(&bytes, config.clone()).unwrap(),
+        bonjson!({"name": "Ada", "tags": ["x", "y"], "ok": {"inner": "v"}})
+    );
+
+    // Keys that collide after the transform are duplicates
+    let mut encoder = crate::Encoder::new(Vec::new());
+    encoder.begin_object().unwrap();
+    for key in ["id", "ID"] {
+        encoder.write_str(key).unwrap();
+        encoder.write_i64(1).unwrap();
+    }
+    encoder.end_container().unwrap();
+    let bytes = encoder.finish().unwrap();
+    assert_eq!(decode_value_with_config(&bytes, config).unwrap_err(), crate::Error::DuplicateKey);
+}
+
+#[test]
+fn test_decode_value_unknown_type_codes() {
+    use crate::{decode_value_with_config, Error, UnknownTypeCodeMode};
+
+    let config = |mode| DecoderConfig { unknown_type_code: mode, ..Default::default() };
+    // [1, <0xc0 with a 2-byte payload>, 2] and {"a": <0xf4, empty>, "b": 1}
+    let array = [0xb7, 0x01, 0xc0, 0x02, 0xaa, 0xbb, 0x02, 0xb6];
+    let object = [0xb8, 0x66, b'a', 0xf4, 0x00, 0x66, b'b', 0x01, 0xb6];
+
+    assert_eq!(decode_value(&array).unwrap_err(), Error::InvalidTypeCode(0xc0));
+    let null = config(UnknownTypeCodeMode::Null);
+    assert_eq!(decode_value_with_config(&array, null.clone()).unwrap(), bonjson!([1, null, 2]));
+    assert_eq!(decode_value_with_config(&object, null.clone()).unwrap(), bonjson!({"a": null, "b": 1}));
+
+    let skip = config(UnknownTypeCodeMode::SkipValue);
+    assert_eq!(decode_value_with_config(&array, skip.clone()).unwrap(), bonjson!([1, 2]));
+    assert_eq!(decode_value_with_config(&object, skip.clone()).unwrap(), bonjson!({"b": 1}));
+    // At the root there's nothing to drop it from
+    assert_eq!(decode_value_with_config(&[0xbb, 0x01, 0x00], skip.clone()).unwrap(), Value::Null);
+
+    // The payload length must fit in the document
+    assert_eq!(decode_value_with_config(&[0xb7, 0xc0, 0x05, 0x00, 0xb6], skip).unwrap_err(), Error::Truncated);
+    assert_eq!(decode_value_with_config(&[0xc0], null).unwrap_err(), Error::Truncated);
+}
+
+#[test]
+fn test_decode_value_typed_array_sizes() {
+    use crate::types::{leb128_encode, type_code};
+    use crate::{decode_value_with_config, Error};
+
+    let typed_array = |count: u64, payload: &[u8]| {
+        let mut buf = [0u8; 10];
+        let n = leb128_encode(count, &mut buf);
+        let mut doc = vec![type_code::TYPED_ARRAY_FLOAT64];
+        doc.extend_from_slice(&buf[..n]);
+        doc.extend_from_slice(payload);
+        doc
+    };
+    let unbounded = DecoderConfig { max_container_size: usize::MAX, ..Default::default() };
+
+    // A count whose byte size overflows
+    let forged = typed_array(u64::MAX / 2, &[]);
+    assert_eq!(decode_value(&forged).unwrap_err(), Error::MaxContainerSizeExceeded);
+    assert_eq!(decode_value_with_config(&forged, unbounded.clone()).unwrap_err(), Error::MaxTypedArrayBytesExceeded);
+
+    // A plausible count with nothing behind it fails without reserving for it
+    let hollow = typed_array(100_000_000, &1.5f64.to_le_bytes());
+    assert_eq!(decode_value_with_config(&hollow, unbounded).unwrap_err(), Error::Truncated);
+
+    let three = typed_array(3, &[0u8; 24]);
+    assert_eq!(decode_value(&three).unwrap(), bonjson!([0.0, 0.0, 0.0]));
+    let config = DecoderConfig { max_typed_array_bytes: 16, ..Default::default() };
+    assert_eq!(decode_value_with_config(&three, config).unwrap_err(), Error::MaxTypedArrayBytesExceeded);
+}
+
+#[test]
+fn test_huge_document_limits() {
+    use crate::types::{leb128_encode, limits, type_code};
+    use crate::{decode_value_with_config, Error};
+
+    // A 4 GB float64 typed array with nothing behind its header
+    let mut buf = [0u8; 10];
+    let n = leb128_encode(500_000_000, &mut buf);
+    let mut doc = vec![type_code::TYPED_ARRAY_FLOAT64];
+    doc.extend_from_slice(&buf[..n]);
+
+    let raised = DecoderConfig::default()
+        .max_container_size(usize::MAX)
+        .max_document_size(usize::MAX)
+        .max_typed_array_bytes(usize::MAX);
+    assert_eq!(raised.document_size_limit(), limits::MAX_DOCUMENT_SIZE);
+    assert_eq!(decode_value_with_config(&doc, raised.clone()).unwrap_err(), Error::MaxTypedArrayBytesExceeded);
+
+    let huge = raised.allow_huge_documents(true);
+    assert_eq!(huge.document_size_limit(), usize::MAX);
+    assert_eq!(huge.typed_array_bytes_limit(), usize::MAX);
+    assert_eq!(decode_value_with_config(&doc, huge).unwrap_err(), Error::Truncated);
+
+    // Limits below 2 GB apply either way
+    let small = DecoderConfig::default().allow_huge_documents(true).max_document_size(4);
+    assert_eq!(decode_value_with_config(&[0x00; 8], small).unwrap_err(), Error::MaxDocumentSizeExceeded);
+}
+
+#[test]
+fn test_decode_value_preserve_float_width() {
+    use crate::types::type_code;
+    use crate::{decode_value_with_config, Encoder, Error, FloatWidth};
+
+    // Floats that encode_value would otherwise narrow to float32 or an
+    // integer, with the keys in the order a Value writes them
+    let mut encoder = Encoder::new(Vec::new());
+    encoder.begin_object().unwrap();
+    encoder.write_str("narrow").unwrap();
+    encoder.write_float_with_width(1.5, FloatWidth::F64).unwrap();
+    encoder.write_str("single").unwrap();
+    encoder.write_float_with_width(-0.25, FloatWidth::F32).unwrap();
+    encoder.write_str("typed").unwrap();
+    let data: Vec<u8> = [1.0f64, 0.5].iter().flat_map(|f| f.to_le_bytes()).collect();
+    encoder.write_typed_array_raw(type_code::TYPED_ARRAY_FLOAT64, 2, &data).unwrap();
+    encoder.write_str("whole").unwrap();
+    encoder.write_float_with_width(2.0, FloatWidth::F64).unwrap();
+    encoder.write_str("wide").unwrap();
+    encoder.write_float_with_width(0.1, FloatWidth::F64).unwrap();
+    encoder.end_container().unwrap();
+    let bytes = encoder.finish().unwrap();
+
+    // By default the widths are lost
+    let plain = decode_value(&bytes).unwrap();
+    assert_eq!(plain, bonjson!({"narrow": 1.5, "single": (-0.25), "typed": [1.0, 0.5], "whole": 2.0, "wide": 0.1}));
+    assert_ne!(encode_value(&plain).unwrap(), bytes);
+
+    let config = DecoderConfig { preserve_float_width: true, ..Default::default() };
+    let sized = decode_value_with_config(&bytes, config.clone()).unwrap();
+    assert_eq!(sized.get_key("whole"), Some(&Value::SizedFloat(2.0, FloatWidth::F64)));
+    assert_eq!(sized.get_key("single"), Some(&Value::SizedFloat(-0.25, FloatWidth::F32)));
+    assert_eq!(sized.get_key("typed").and_then(Value::as_array).unwrap()[1], Value::SizedFloat(0.5, FloatWidth::F64));
+    assert_eq!(sized.get_key("narrow").and_then(Value::as_f64), Some(1.5));
+    assert_eq!(encode_value(&sized).unwrap(), bytes);
+
+    // Mixed widths can't share a typed array, so each element keeps its own
+    let mixed = Value::Array(vec![Value::SizedFloat(1.5, FloatWidth::F32), Value::SizedFloat(1.5, FloatWidth::F64)]);
+    assert_eq!(decode_value_with_config(&encode_value(&mixed).unwrap(), config).unwrap(), mixed);
+
+    // A width too narrow for the value
+    assert_eq!(encode_value(&Value::SizedFloat(0.1, FloatWidth::F32)).unwrap_err(), Error::ValueOutOfRange);
+}
+
+#[test]
+fn test_config_setters_and_closure_wrappers() {
+    use crate::{
+        decode_value_with, encode_value_with, from_reader_with, from_slice_with, to_vec_with, to_vec_with_config,
+        to_writer_with, DuplicateKeyMode, EncoderConfig, Error, NanInfinityMode, SerializerConfig,
+    };
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    // Setters produce the same config as struct update syntax
+    let built = DecoderConfig::default().max_depth(3).duplicate_key_mode(DuplicateKeyMode::Error).allow_nul(true);
+    assert_eq!(built.max_depth, 3);
+    assert_eq!(built.duplicate_key_mode, DuplicateKeyMode::Error);
+    assert!(built.allow_nul);
+    assert!(DecoderConfig::strict().max_depth(8).deny_unknown_fields_globally);
+    assert!(DecoderConfig::default().deadline(std::time::Instant::now()).deadline.is_some());
+    let encoder = EncoderConfig::default().nan_infinity_mode(NanInfinityMode::Allow);
+    assert_eq!(encoder.nan_infinity_mode, NanInfinityMode::Allow);
+
+    let points = vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+    let config = SerializerConfig { records: true, typed_arrays: false, ..Default::default() };
+    let bytes = to_vec_with(&points, |cfg| cfg.records(true).typed_arrays(false)).unwrap();
+    assert_eq!(bytes, to_vec_with_config(&points, &config).unwrap());
+    let mut written = Vec::new();
+    to_writer_with(&mut written, &points, |cfg| cfg.records(true).typed_arrays(false)).unwrap();
+    assert_eq!(written, bytes);
+
+    let decoded: Vec<Point> = from_slice_with(&bytes, |cfg| cfg.max_depth(2)).unwrap();
+    assert_eq!(decoded, points);
+    let decoded: Vec<Point> = from_reader_with(Cursor::new(&bytes), |cfg| cfg.max_depth(2)).unwrap();
+    assert_eq!(decoded, points);
+    assert_eq!(from_slice_with::<Vec<Point>, _>(&bytes, |cfg| cfg.max_depth(1)), Err(Error::MaxDepthExceeded));
+
+    let value = bonjson!({"n": (f64::NAN)});
+    assert!(encode_value_with(&value, |cfg| cfg).is_err());
+    let bytes = encode_value_with(&value, |cfg| cfg.nan_infinity_mode(NanInfinityMode::Stringify)).unwrap();
+    assert_eq!(decode_value_with(&bytes, |cfg| cfg).unwrap(), bonjson!({"n": "NaN"}));
+}
+
+#[test]
+fn test_progress_hook() {
+    use crate::{ProgressHook, ReaderDeserializer};
+    use std::sync::{Arc, Mutex};
+
+    let bytes = to_vec(&vec!["a line of text for the progress test"; 20_000]).unwrap();
+    let recording = || {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&calls);
+        let hook = ProgressHook::new(move |done, total| sink.lock().unwrap().push((done, total)));
+        (DecoderConfig::default().progress(hook).progress_interval(64 << 10), calls)
+    };
+
+    let (config, calls) = recording();
+    crate::decode_value_with_config(&bytes, config).unwrap();
+    let (config, serde_calls) = recording();
+    let _: Vec<String> = crate::from_slice_with_config(&bytes, config).unwrap();
+    for calls in [calls, serde_calls] {
+        let calls = calls.lock().unwrap();
+        // One call per 64 KiB passed, give or take a poll interval, then the end
+        assert!(calls.len() >= bytes.len() / (128 << 10), "{} calls", calls.len());
+        assert!(calls.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(calls.iter().all(|&(_, total)| total == bytes.len()));
+        assert_eq!(calls.last(), Some(&(bytes.len(), bytes.len())));
+    }
+
+    // No final call for a failed decode
+    let (config, calls) = recording();
+    assert!(crate::decode_value_with_config(&bytes[..bytes.len() - 1], config).is_err());
+    assert!(calls.lock().unwrap().iter().all(|&(done, total)| done < total));
+
+    // The incremental reader never sees the whole document
+    let (config, calls) = recording();
+    let mut de = ReaderDeserializer::with_config(bytes.as_slice(), config);
+    assert_eq!(de.array_elements::<String>().count(), 20_000);
+    assert!(calls.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_decode_value_prefix() {
+    use crate::{decode_value_prefix, decode_value_prefix_with_config, encode_value_with_config, EncoderConfig, Error};
+
+    let rows: Vec<Value> = (0..1000).map(|i| bonjson!({"id": i, "name": (format!("row {i}"))})).collect();
+    let bytes = encode_value(&Value::Array(rows.clone())).unwrap();
+    assert_eq!(decode_value_prefix(&bytes, 3).unwrap(), (Value::Array(rows[..3].to_vec()), true));
+    assert_eq!(decode_value_prefix(&bytes, 0).unwrap(), (bonjson!([]), true));
+    let decoded = decode_value(&bytes).unwrap();
+    assert_eq!(decode_value_prefix(&bytes, 1000).unwrap(), (decoded.clone(), false));
+    assert_eq!(decode_value_prefix(&bytes, usize::MAX).unwrap(), (decoded, false));
+
+    // Nothing past the returned elements is read
+    let head = &bytes[..bytes.len() / 10];
+    assert_eq!(decode_value_prefix(head, 5).unwrap(), (Value::Array(rows[..5].to_vec()), true));
+    assert_eq!(decode_value_prefix(head, 1000), Err(Error::Truncated));
+
+    // Typed arrays
+    let samples = Value::Array((0..500).map(|i| Value::Float(f64::from(i) + 0.5)).collect());
+    let bytes = encode_value(&samples).unwrap();
+    assert_eq!(decode_value_prefix(&bytes[..40], 2).unwrap(), (bonjson!([0.5, 1.5]), true));
+    assert_eq!(decode_value_prefix(&bytes, 500).unwrap(), (samples, false));
+
+    // A fully read array gets decode_value's checks; a partly read one only the size limit
+    let mut trailing = encode_value(&bonjson!([1, 2])).unwrap();
+    trailing.push(0x00);
+    assert_eq!(decode_value_prefix(&trailing, 1).unwrap(), (bonjson!([1]), true));
+    assert_eq!(decode_value_prefix(&trailing, 2), Err(Error::TrailingBytes(4)));
+    let big = encode_value_with_config(&bonjson!(["aaaaaaaaaaaaaaaaaaaa", "b"]), EncoderConfig::default()).unwrap();
+    let config = DecoderConfig::default().max_document_size(10);
+    assert_eq!(decode_value_prefix_with_config(&big, 1, config.clone()), Err(Error::MaxDocumentSizeExceeded));
+    assert_eq!(decode_value_prefix_with_config(&big, 0, config), Ok((bonjson!([]), true)));
+
+    assert_eq!(decode_value_prefix(&encode_value(&bonjson!({"a": 1})).unwrap(), 1), Err(Error::Custom("root value is not an array".into())));
+
+    // [1, <0xd0, empty>, 2, <0xd0 with a 1-byte payload>]
+    let bytes = [0xb7, 0x01, 0xd0, 0x00, 0x02, 0xd0, 0x01, 0xff, 0xb6];
+    let config = DecoderConfig { unknown_type_code: crate::UnknownTypeCodeMode::SkipValue, ..Default::default() };
+    assert_eq!(decode_value_prefix_with_config(&bytes, 1, config.clone()).unwrap(), (bonjson!([1]), true));
+    assert_eq!(decode_value_prefix_with_config(&bytes, 2, config).unwrap(), (bonjson!([1, 2]), false));
+}
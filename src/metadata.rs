@@ -0,0 +1,163 @@
+// ABOUTME: Document-level metadata gathered while decoding BONJSON.
+// ABOUTME: Reports record definitions and usage, typed array statistics, and a byte size breakdown.
+
+use crate::decoder::{DecodedValue, Decoder};
+use std::collections::BTreeMap;
+
+/// Metadata describing the structure of a decoded document.
+///
+/// Returned alongside the decoded value by
+/// [`decode_with_metadata`](crate::decode_with_metadata).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentMetadata {
+    /// Record definitions in document order, with how often each was used.
+    pub record_definitions: Vec<RecordDefinitionInfo>,
+    /// Typed array statistics, keyed by element type code
+    /// (e.g. [`type_code::TYPED_ARRAY_FLOAT64`](crate::type_code::TYPED_ARRAY_FLOAT64)).
+    pub typed_arrays: BTreeMap<u8, TypedArrayStats>,
+    /// Where the document's bytes went.
+    pub sizes: SizeBreakdown,
+}
+
+/// A record definition and its usage within a document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordDefinitionInfo {
+    /// The definition's keys, in order.
+    pub keys: Vec<String>,
+    /// Number of record instances that reference this definition.
+    pub instance_count: usize,
+}
+
+/// Aggregate statistics for typed arrays of one element type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypedArrayStats {
+    /// Number of typed arrays.
+    pub array_count: usize,
+    /// Total elements across all arrays.
+    pub element_count: usize,
+    /// Element count of the longest array.
+    pub max_elements: usize,
+    /// Encoded bytes, including type codes and count fields.
+    pub bytes: usize,
+}
+
+/// A breakdown of a document's encoded size by category.
+///
+/// Every byte of the document is attributed to exactly one category, so the
+/// categories sum to `total`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SizeBreakdown {
+    /// Total document size in bytes.
+    pub total: usize,
+    /// Record definitions at the start of the document.
+    pub record_definitions: usize,
+    /// Container start/end markers and record instance headers.
+    pub containers: usize,
+    /// Strings, including object keys.
+    pub strings: usize,
+    /// Integers, floats, and BigNumbers outside typed arrays.
+    pub numbers: usize,
+    /// Typed arrays (headers and element data).
+    pub typed_arrays: usize,
+    /// Null and boolean values.
+    pub literals: usize,
+    /// Trailing bytes after the document (only with `allow_trailing_bytes`).
+    pub trailing: usize,
+}
+
+/// Tallies a document's metadata from inside the [`Decoder`] that decodes it,
+/// so the bytes are read once.
+///
+/// The decoder feeds it every event it consumes, with the event's encoded
+/// size. Skipped unknown values count as literals, like the nulls they
+/// decode as elsewhere.
+#[derive(Debug, Default)]
+pub(crate) struct Collector {
+    meta: DocumentMetadata,
+    instance_counts: Vec<usize>,
+    /// Start position and element type code of the open typed array
+    typed_array: Option<(usize, u8, usize)>,
+}
+
+impl Collector {
+    /// Tally a value event that spanned `start..end`.
+    pub(crate) fn value(&mut self, event: &DecodedValue<'_>, start: usize, end: usize) {
+        let sizes = &mut self.meta.sizes;
+        match *event {
+            DecodedValue::Null | DecodedValue::Bool(_) => sizes.literals += end - start,
+            DecodedValue::Int(_) | DecodedValue::UInt(_) | DecodedValue::Float(_) | DecodedValue::BigNumber(_) => {
+                sizes.numbers += end - start;
+            }
+            DecodedValue::String(_) => sizes.strings += end - start,
+            DecodedValue::ArrayStart | DecodedValue::ObjectStart | DecodedValue::ContainerEnd => {
+                sizes.containers += end - start;
+            }
+            DecodedValue::RecordInstanceStart(index) => {
+                sizes.containers += end - start;
+                if self.instance_counts.len() <= index {
+                    self.instance_counts.resize(index + 1, 0);
+                }
+                self.instance_counts[index] += 1;
+            }
+            DecodedValue::TypedArrayStart { element_type_code, count } => {
+                self.typed_array = Some((start, element_type_code, count));
+            }
+        }
+    }
+
+    /// Tally a container end marker consumed outside `decode_value`.
+    pub(crate) fn container_end(&mut self) {
+        self.meta.sizes.containers += 1;
+    }
+
+    /// Tally an unknown value dropped under `UnknownTypeCodeMode::SkipValue`.
+    pub(crate) fn skipped(&mut self, bytes: usize) {
+        self.meta.sizes.literals += bytes;
+    }
+
+    /// Close the open typed array, which ends at `end`.
+    pub(crate) fn typed_array_end(&mut self, end: usize) {
+        let Some((start, element_type_code, count)) = self.typed_array.take() else {
+            return;
+        };
+        let bytes = end - start;
+        self.meta.sizes.typed_arrays += bytes;
+        let stats = self.meta.typed_arrays.entry(element_type_code).or_default();
+        stats.array_count += 1;
+        stats.element_count += count;
+        stats.max_elements = stats.max_elements.max(count);
+        stats.bytes += bytes;
+    }
+
+    /// The metadata of a document that was decoded up to `decoder`'s
+    /// position, with its record definitions ending at `definitions_end`.
+    pub(crate) fn finish(mut self, decoder: &Decoder<'_>, definitions_end: usize) -> DocumentMetadata {
+        self.meta.sizes.trailing = decoder.remaining().len();
+        self.meta.sizes.total = decoder.position() + self.meta.sizes.trailing;
+        self.meta.sizes.record_definitions = definitions_end;
+        self.meta.record_definitions = decoder
+            .record_definitions()
+            .iter()
+            .enumerate()
+            .map(|(i, keys)| RecordDefinitionInfo {
+                keys: keys.clone(),
+                instance_count: self.instance_counts.get(i).copied().unwrap_or(0),
+            })
+            .collect();
+        self.meta
+    }
+}
+
+impl DocumentMetadata {
+    /// Returns the statistics for typed arrays with the given element type code.
+    #[must_use]
+    pub fn typed_array_stats(&self, element_type_code: u8) -> Option<&TypedArrayStats> {
+        self.typed_arrays.get(&element_type_code)
+    }
+
+    /// Returns the number of record instances across all definitions.
+    #[must_use]
+    pub fn record_instance_count(&self) -> usize {
+        self.record_definitions.iter().map(|d| d.instance_count).sum()
+    }
+}
@@ -0,0 +1,128 @@
+// ABOUTME: Unit tests for the BONJSON document metadata module.
+// ABOUTME: Tests record usage counts, typed array statistics, and size breakdowns.
+
+use crate::metadata::{RecordDefinitionInfo, SizeBreakdown};
+use crate::{
+    bonjson, decode_with_metadata, decode_with_metadata_with_config, encode_value, to_vec,
+    to_writer_with_config, type_code, DecoderConfig, SerializerConfig,
+};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Serialize)]
+struct Label {
+    text: String,
+}
+
+#[derive(Serialize)]
+struct Scene {
+    points: Vec<Point>,
+    labels: Vec<Label>,
+}
+
+#[test]
+fn test_metadata_record_usage() {
+    let scene = Scene {
+        points: vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }, Point { x: 5, y: 6 }],
+        labels: vec![Label { text: "a".into() }, Label { text: "b".into() }],
+    };
+    let mut bytes = Vec::new();
+    let config = SerializerConfig {
+        records: true,
+        ..Default::default()
+    };
+    to_writer_with_config(&mut bytes, &scene, &config).unwrap();
+
+    let (_, meta) = decode_with_metadata(&bytes).unwrap();
+    let defs = &meta.record_definitions;
+    assert!(defs.contains(&RecordDefinitionInfo {
+        keys: vec!["x".into(), "y".into()],
+        instance_count: 3,
+    }));
+    assert!(defs.contains(&RecordDefinitionInfo {
+        keys: vec!["text".into()],
+        instance_count: 2,
+    }));
+    assert!(meta.sizes.record_definitions > 0);
+    assert_eq!(meta.record_instance_count(), 5);
+}
+
+#[test]
+fn test_metadata_typed_arrays() {
+    // Values that need full f64 precision, so typed arrays beat per-element encoding
+    let data = (vec![0.1f64, 0.2, 0.3], vec![0.7f64], vec![200u8, 201, 202, 203]);
+    let bytes = to_vec(&data).unwrap();
+
+    let (_, meta) = decode_with_metadata(&bytes).unwrap();
+    let floats = meta.typed_array_stats(type_code::TYPED_ARRAY_FLOAT64).unwrap();
+    assert_eq!(floats.array_count, 2);
+    assert_eq!(floats.element_count, 4);
+    assert_eq!(floats.max_elements, 3);
+    // Type code + 1-byte count + 8 bytes per element, per array
+    assert_eq!(floats.bytes, (2 + 3 * 8) + (2 + 8));
+
+    let bytes_stats = meta.typed_array_stats(type_code::TYPED_ARRAY_UINT8).unwrap();
+    assert_eq!(bytes_stats.element_count, 4);
+    assert!(meta.typed_array_stats(type_code::TYPED_ARRAY_SINT32).is_none());
+}
+
+#[test]
+fn test_metadata_size_breakdown() {
+    // {"a": null, "b": "xy", "c": 1000}
+    let value = bonjson!({"a": null, "b": "xy", "c": 1000});
+    let bytes = encode_value(&value).unwrap();
+
+    let (decoded, meta) = decode_with_metadata(&bytes).unwrap();
+    assert_eq!(decoded, value);
+    assert_eq!(
+        meta.sizes,
+        SizeBreakdown {
+            total: bytes.len(),
+            record_definitions: 0,
+            containers: 2,
+            strings: 3 * 2 + 3,
+            numbers: 3,
+            typed_arrays: 0,
+            literals: 1,
+            trailing: 0,
+        }
+    );
+}
+
+#[test]
+fn test_metadata_trailing_bytes() {
+    let mut bytes = to_vec(&42).unwrap();
+    bytes.extend_from_slice(&[0x00, 0x00]);
+
+    assert!(decode_with_metadata(&bytes).is_err());
+
+    let config = DecoderConfig {
+        allow_trailing_bytes: true,
+        ..Default::default()
+    };
+    let (_, meta) = decode_with_metadata_with_config(&bytes, config).unwrap();
+    assert_eq!(meta.sizes.numbers, 1);
+    assert_eq!(meta.sizes.trailing, 2);
+    assert_eq!(meta.sizes.total, 3);
+}
+
+#[test]
+fn test_metadata_skipped_values() {
+    // {"a": [1, <0xd0, empty>], "b": <0xd0 with a 1-byte payload>}
+    let bytes = [0xb8, 0x66, b'a', 0xb7, 0x01, 0xd0, 0x00, 0xb6, 0x66, b'b', 0xd0, 0x01, 0xff, 0xb6];
+    let config = DecoderConfig {
+        unknown_type_code: crate::UnknownTypeCodeMode::SkipValue,
+        ..Default::default()
+    };
+    let (value, meta) = decode_with_metadata_with_config(&bytes, config).unwrap();
+    assert_eq!(value, bonjson!({"a": [1]}));
+    assert_eq!(
+        meta.sizes,
+        SizeBreakdown { total: 14, containers: 4, strings: 4, numbers: 1, literals: 5, ..Default::default() }
+    );
+}
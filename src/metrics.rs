@@ -0,0 +1,101 @@
+// ABOUTME: Codec cost counters for the `tracing` feature.
+// ABOUTME: Defines CodecMetrics and the MetricsSink trait that top-level encode and decode calls report to.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// Which direction a [`CodecMetrics`] report covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecOperation {
+    /// Serializing or encoding a value.
+    Encode,
+    /// Deserializing or decoding a document.
+    Decode,
+}
+
+/// Work done by one encode or decode call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CodecMetrics {
+    /// Bytes written, or read up to where decoding stopped.
+    pub bytes: usize,
+    /// Values written or read, including object keys and containers. A typed
+    /// array counts as one value, not one per element.
+    pub values: usize,
+    /// Strings written or validated as UTF-8, including keys in objects and
+    /// record definitions.
+    pub strings: usize,
+    /// Typed arrays written or read.
+    pub typed_arrays: usize,
+    /// Record instances written or read.
+    pub records: usize,
+}
+
+impl CodecMetrics {
+    /// Add `other`'s counters, except `bytes`, to these.
+    pub(crate) fn add_values(&mut self, other: &CodecMetrics) {
+        self.values += other.values;
+        self.strings += other.strings;
+        self.typed_arrays += other.typed_arrays;
+        self.records += other.records;
+    }
+}
+
+/// Receives a [`CodecMetrics`] report from every top-level encode and decode
+/// call configured with it, for attributing codec cost per request.
+///
+/// Set it as `metrics_sink` on [`DecoderConfig`](crate::DecoderConfig),
+/// [`EncoderConfig`](crate::EncoderConfig) or
+/// [`SerializerConfig`](crate::SerializerConfig). Reports are made whether
+/// the call succeeds or fails.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::metrics::{CodecMetrics, CodecOperation, MetricsSink};
+/// use serde_bonjson::{from_slice_with_config, to_vec, DecoderConfig};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// #[derive(Default)]
+/// struct BytesDecoded(AtomicUsize);
+///
+/// impl MetricsSink for BytesDecoded {
+///     fn record(&self, operation: CodecOperation, metrics: &CodecMetrics) {
+///         if operation == CodecOperation::Decode {
+///             self.0.fetch_add(metrics.bytes, Ordering::Relaxed);
+///         }
+///     }
+/// }
+///
+/// let sink = Arc::new(BytesDecoded::default());
+/// let config = DecoderConfig { metrics_sink: Some(sink.clone()), ..Default::default() };
+/// let bytes = to_vec(&vec!["a", "b"]).unwrap();
+/// let _: Vec<String> = from_slice_with_config(&bytes, config).unwrap();
+/// assert_eq!(sink.0.load(Ordering::Relaxed), bytes.len());
+/// ```
+pub trait MetricsSink: Send + Sync {
+    /// Called once as each encode or decode call finishes.
+    fn record(&self, operation: CodecOperation, metrics: &CodecMetrics);
+}
+
+impl fmt::Debug for dyn MetricsSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MetricsSink")
+    }
+}
+
+/// Emit `metrics` as a `tracing` event in the current span and pass them to `sink`.
+pub(crate) fn report(operation: CodecOperation, metrics: &CodecMetrics, sink: Option<&Arc<dyn MetricsSink>>) {
+    tracing::debug!(
+        ?operation,
+        bytes = metrics.bytes,
+        values = metrics.values,
+        strings = metrics.strings,
+        typed_arrays = metrics.typed_arrays,
+        records = metrics.records,
+        "codec metrics"
+    );
+    if let Some(sink) = sink {
+        sink.record(operation, metrics);
+    }
+}
@@ -0,0 +1,95 @@
+// ABOUTME: Unit tests for the metrics module (tracing feature).
+// ABOUTME: Tests that encode and decode calls report matching counters to a MetricsSink.
+
+use crate::metrics::{CodecMetrics, CodecOperation, MetricsSink};
+use crate::{
+    bonjson, decode_value_with_config, encode_value_with_config, from_slice_with_config, to_vec_with_config,
+    DecoderConfig, EncoderConfig, Error, SerializerConfig,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Recorder(Mutex<Vec<(CodecOperation, CodecMetrics)>>);
+
+impl MetricsSink for Recorder {
+    fn record(&self, operation: CodecOperation, metrics: &CodecMetrics) {
+        self.0.lock().unwrap().push((operation, *metrics));
+    }
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<(CodecOperation, CodecMetrics)> {
+        std::mem::take(&mut self.0.lock().unwrap())
+    }
+}
+
+fn sinks() -> (Arc<Recorder>, DecoderConfig) {
+    let recorder = Arc::new(Recorder::default());
+    let config = DecoderConfig { metrics_sink: Some(recorder.clone()), ..Default::default() };
+    (recorder, config)
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Sample {
+    id: u32,
+    name: String,
+    readings: Vec<f64>,
+}
+
+#[test]
+fn test_value_metrics() {
+    let (recorder, decoder_config) = sinks();
+    let encoder_config = EncoderConfig { metrics_sink: Some(recorder.clone()), ..Default::default() };
+    let value = bonjson!({"a": [1, "x"], "b": null});
+    let bytes = encode_value_with_config(&value, encoder_config).unwrap();
+    assert_eq!(decode_value_with_config(&bytes, decoder_config).unwrap(), value);
+
+    let expected = CodecMetrics { bytes: bytes.len(), values: 7, strings: 3, typed_arrays: 0, records: 0 };
+    assert_eq!(recorder.take(), [(CodecOperation::Encode, expected), (CodecOperation::Decode, expected)]);
+}
+
+#[test]
+fn test_serde_metrics() {
+    let (recorder, decoder_config) = sinks();
+    let config = SerializerConfig { records: true, metrics_sink: Some(recorder.clone()), ..Default::default() };
+    let samples: Vec<Sample> =
+        (0..3).map(|id| Sample { id, name: format!("s{id}"), readings: vec![0.1, 0.2] }).collect();
+    let bytes = to_vec_with_config(&samples, &config).unwrap();
+    let decoded: Vec<Sample> = from_slice_with_config(&bytes, decoder_config).unwrap();
+    assert_eq!(decoded, samples);
+
+    let reports = recorder.take();
+    let (encoded, decoded) = (reports[0].1, reports[1].1);
+    assert_eq!(encoded, decoded);
+    assert_eq!(encoded.bytes, bytes.len());
+    assert_eq!((encoded.typed_arrays, encoded.records), (3, 3));
+    // The array, and per sample the record, id, name and readings
+    assert_eq!(encoded.values, 13);
+    // Three definition keys and a name per sample
+    assert_eq!(encoded.strings, 6);
+
+    // Sorted map entries are encoded separately before being copied in
+    let config = SerializerConfig { sort_map_keys: true, metrics_sink: Some(recorder.clone()), ..Default::default() };
+    let map: HashMap<String, Vec<&str>> = (0..5).map(|i| (format!("k{i}"), vec!["v"; i])).collect();
+    let bytes = to_vec_with_config(&map, &config).unwrap();
+    let decoder_config = DecoderConfig { metrics_sink: Some(recorder.clone()), ..Default::default() };
+    let _: BTreeMap<String, Vec<String>> = from_slice_with_config(&bytes, decoder_config).unwrap();
+    let reports = recorder.take();
+    assert_eq!(reports[0].1, reports[1].1);
+    assert_eq!(reports[0].1.values, 1 + 5 * 2 + 10);
+}
+
+#[test]
+fn test_metrics_reported_on_error() {
+    let (recorder, decoder_config) = sinks();
+    let bytes = crate::to_vec(&vec!["a", "b", "c"]).unwrap();
+    let err = decode_value_with_config(&bytes[..bytes.len() - 3], decoder_config).unwrap_err();
+    assert_eq!(err, Error::Truncated);
+
+    let reports = recorder.take();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].0, CodecOperation::Decode);
+    assert_eq!((reports[0].1.values, reports[0].1.strings), (3, 2));
+}
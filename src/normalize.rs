@@ -0,0 +1,160 @@
+// ABOUTME: Single-pass normalization of Values: NFC strings, canonical numbers, and
+// ABOUTME: removal of null members and empty containers, configured by NormalizeConfig.
+
+use crate::decoder::UnicodeNormalization;
+use crate::error::{Error, Result};
+use crate::types::BigNumber;
+use crate::value::Value;
+use std::collections::BTreeMap;
+
+/// Which steps [`normalize`] applies.
+///
+/// There's no step for sorting keys: objects are `BTreeMap`s, so their keys
+/// are always in byte order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NormalizeConfig {
+    /// NFC-normalize string values and object keys (requires the
+    /// `unicode-normalization` feature; a no-op without it).
+    pub unicode_normalization: UnicodeNormalization,
+    /// Write every number in one form per value (see [`canonical_number`]),
+    /// so that numbers which are equal compare equal as `Value`s.
+    pub canonical_numbers: bool,
+    /// Remove object members whose value is null, like [`Value::strip_nulls`].
+    pub strip_nulls: bool,
+    /// Remove object members whose value is an empty array or object,
+    /// including those left empty by the other steps.
+    pub collapse_empty_containers: bool,
+}
+
+impl NormalizeConfig {
+    /// Every step enabled, with NFC normalization.
+    #[must_use]
+    pub fn all() -> Self {
+        NormalizeConfig {
+            unicode_normalization: UnicodeNormalization::Nfc,
+            canonical_numbers: true,
+            strip_nulls: true,
+            collapse_empty_containers: true,
+        }
+    }
+}
+
+/// Apply the steps in `config` to `value` and everything beneath it, in one
+/// traversal.
+///
+/// Children are normalized before their parent, so a container emptied by
+/// `strip_nulls` is itself removed by `collapse_empty_containers`. Array
+/// elements are never removed, since that would shift positions; only object
+/// members are.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::bonjson;
+/// use serde_bonjson::normalize::{normalize, NormalizeConfig};
+///
+/// let mut doc = bonjson!({"a": 2.0, "b": null, "c": {"d": null}, "e": [null, []]});
+/// let config = NormalizeConfig { canonical_numbers: true, strip_nulls: true, collapse_empty_containers: true, ..Default::default() };
+/// normalize(&mut doc, &config).unwrap();
+/// assert_eq!(doc, bonjson!({"a": 2, "e": [null, []]}));
+/// ```
+///
+/// # Errors
+///
+/// Returns `Error::DuplicateKey` if two keys of an object are equal after NFC
+/// normalization; `value` is left partly normalized.
+pub fn normalize(value: &mut Value, config: &NormalizeConfig) -> Result<()> {
+    match value {
+        Value::String(s) => *s = crate::maybe_nfc_normalize(config.unicode_normalization, std::mem::take(s)),
+        Value::Array(items) => {
+            for item in items {
+                normalize(item, config)?;
+            }
+        }
+        Value::Object(map) => {
+            let mut normalized = BTreeMap::new();
+            for (key, mut item) in std::mem::take(map) {
+                normalize(&mut item, config)?;
+                if (config.strip_nulls && item.is_null()) || (config.collapse_empty_containers && is_empty_container(&item)) {
+                    continue;
+                }
+                let key = crate::maybe_nfc_normalize(config.unicode_normalization, key);
+                if normalized.insert(key, item).is_some() {
+                    return Err(Error::DuplicateKey);
+                }
+            }
+            *map = normalized;
+        }
+        Value::Int(_) | Value::UInt(_) | Value::Float(_) | Value::SizedFloat(..) | Value::BigNumber(_) => {
+            if config.canonical_numbers {
+                *value = canonical_number(value);
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+    Ok(())
+}
+
+fn is_empty_container(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => items.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+/// The one form a number takes after normalization.
+///
+/// Integral values that fit are `Int`, or `UInt` above `i64::MAX`. Other
+/// values are `Float` when a float64 holds them exactly (including NaN and
+/// the infinities, and `-0.0` becomes `Int(0)`), and otherwise a `BigNumber`
+/// with no trailing zeros in its significand. Anything that isn't a number is
+/// returned unchanged.
+#[must_use]
+pub fn canonical_number(value: &Value) -> Value {
+    let decimal = match value {
+        Value::Int(n) => return Value::Int(*n),
+        Value::UInt(n) => return i64::try_from(*n).map_or(Value::UInt(*n), Value::Int),
+        Value::Float(f) | Value::SizedFloat(f, _) if !f.is_finite() => return Value::Float(*f),
+        Value::Float(f) | Value::SizedFloat(f, _) => float_to_decimal(*f),
+        Value::BigNumber(bn) => *bn,
+        other => return other.clone(),
+    };
+    let decimal = strip_trailing_zeros(decimal);
+    if let Some(n) = decimal.to_i64() {
+        return Value::Int(n);
+    }
+    if let Some(n) = decimal.to_u64() {
+        return Value::UInt(n);
+    }
+    let float = decimal.to_f64();
+    if float.is_finite() && strip_trailing_zeros(float_to_decimal(float)) == decimal {
+        Value::Float(float)
+    } else {
+        Value::BigNumber(decimal)
+    }
+}
+
+/// The shortest decimal that round-trips to `value`, which must be finite.
+pub(crate) fn float_to_decimal(value: f64) -> BigNumber {
+    // `{:e}` gives the shortest round-trip digits, e.g. "-1.25e-7"
+    let text = format!("{:e}", value.abs());
+    let (mantissa, exponent) = text.split_once('e').expect("`{:e}` always has an exponent");
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{int_part}{frac_part}");
+    let significand = digits.parse().expect("f64 has at most 17 significant digits");
+    let exponent: i64 = exponent.parse().expect("`{:e}` writes a decimal exponent");
+    let sign = if value < 0.0 { -1 } else { 1 };
+    BigNumber::new(sign, significand, exponent - i64::try_from(frac_part.len()).unwrap_or(0))
+}
+
+pub(crate) fn strip_trailing_zeros(mut value: BigNumber) -> BigNumber {
+    if value.significand == 0 {
+        return BigNumber::zero();
+    }
+    while value.significand.is_multiple_of(10) {
+        value.significand /= 10;
+        value.exponent += 1;
+    }
+    value
+}
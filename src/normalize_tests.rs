@@ -0,0 +1,95 @@
+// ABOUTME: Unit tests for the normalize module.
+// ABOUTME: Tests each normalization step, their interaction, and canonical number forms.
+
+use crate::normalize::{canonical_number, normalize, NormalizeConfig};
+use crate::{bonjson, BigNumber, FloatWidth, Value};
+
+#[test]
+fn test_default_config_changes_nothing() {
+    let original = bonjson!({"a": 2.0, "b": null, "c": {}, "d": [null, {"e": []}]});
+    let mut doc = original.clone();
+    normalize(&mut doc, &NormalizeConfig::default()).unwrap();
+    assert_eq!(doc, original);
+}
+
+#[test]
+fn test_strip_nulls_and_collapse() {
+    let original = bonjson!({"a": null, "b": {"c": null}, "d": [null, {}], "e": {"f": []}, "g": 1});
+
+    let mut doc = original.clone();
+    normalize(&mut doc, &NormalizeConfig { strip_nulls: true, ..Default::default() }).unwrap();
+    assert_eq!(doc, bonjson!({"b": {}, "d": [null, {}], "e": {"f": []}, "g": 1}));
+
+    let mut doc = original.clone();
+    normalize(&mut doc, &NormalizeConfig { collapse_empty_containers: true, ..Default::default() }).unwrap();
+    assert_eq!(doc, bonjson!({"a": null, "b": {"c": null}, "d": [null, {}], "g": 1}));
+
+    // Stripping a null empties "b", which is then collapsed in the same pass
+    let mut doc = original;
+    normalize(&mut doc, &NormalizeConfig { strip_nulls: true, collapse_empty_containers: true, ..Default::default() }).unwrap();
+    assert_eq!(doc, bonjson!({"d": [null, {}], "g": 1}));
+}
+
+#[test]
+fn test_canonical_numbers() {
+    assert_eq!(canonical_number(&Value::Float(3.0)), Value::Int(3));
+    assert_eq!(canonical_number(&Value::Float(-0.0)), Value::Int(0));
+    assert_eq!(canonical_number(&Value::UInt(7)), Value::Int(7));
+    assert_eq!(canonical_number(&Value::UInt(u64::MAX)), Value::UInt(u64::MAX));
+    assert_eq!(canonical_number(&Value::SizedFloat(0.5, FloatWidth::F32)), Value::Float(0.5));
+    assert_eq!(canonical_number(&Value::BigNumber(BigNumber::new(1, 500, -3))), Value::Float(0.5));
+    assert_eq!(canonical_number(&Value::BigNumber(BigNumber::new(-1, 4200, -2))), Value::Int(-42));
+    assert_eq!(canonical_number(&Value::Float(1e20)), Value::Float(1e20));
+    assert_eq!(canonical_number(&Value::BigNumber(BigNumber::new(1, 1, 20))), Value::Float(1e20));
+    // Not exact as a float64
+    assert_eq!(
+        canonical_number(&Value::BigNumber(BigNumber::new(1, 12_345_678_901_234_567_890, -30))),
+        Value::BigNumber(BigNumber::new(1, 1_234_567_890_123_456_789, -29))
+    );
+    assert_eq!(
+        canonical_number(&Value::BigNumber(BigNumber::new(1, 10, 400))),
+        Value::BigNumber(BigNumber::new(1, 1, 401))
+    );
+    assert!(matches!(canonical_number(&Value::Float(f64::NAN)), Value::Float(f) if f.is_nan()));
+    assert_eq!(canonical_number(&Value::Float(f64::INFINITY)), Value::Float(f64::INFINITY));
+    assert_eq!(canonical_number(&Value::from("x")), Value::from("x"));
+
+    let mut doc = bonjson!([1.0, {"n": (Value::UInt(2))}, "3.0"]);
+    doc.normalize(&NormalizeConfig { canonical_numbers: true, ..Default::default() }).unwrap();
+    assert_eq!(doc, bonjson!([1, {"n": 2}, "3.0"]));
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn test_nfc() {
+    use crate::{Error, UnicodeNormalization};
+
+    let config = NormalizeConfig { unicode_normalization: UnicodeNormalization::Nfc, ..Default::default() };
+    let mut doc = bonjson!({"cafe\u{301}": ["cre\u{300}me"]});
+    normalize(&mut doc, &config).unwrap();
+    assert_eq!(doc, bonjson!({"caf\u{e9}": ["cr\u{e8}me"]}));
+
+    let mut doc = bonjson!({"caf\u{e9}": 1, "cafe\u{301}": 2});
+    assert!(matches!(normalize(&mut doc, &config), Err(Error::DuplicateKey)));
+
+    // A duplicate that's stripped first isn't a conflict
+    let mut doc = bonjson!({"caf\u{e9}": 1, "cafe\u{301}": null});
+    normalize(&mut doc, &NormalizeConfig { strip_nulls: true, ..config }).unwrap();
+    assert_eq!(doc, bonjson!({"caf\u{e9}": 1}));
+}
+
+#[test]
+fn test_all_matches_structural_hash_identity() {
+    use crate::HashAlgo;
+
+    let a = bonjson!({"x": 1.5, "y": [2.0, (Value::UInt(3))], "z": null});
+    let mut b = bonjson!({"x": (Value::BigNumber(BigNumber::new(1, 150, -2))), "y": [2, 3]});
+    let mut a_normalized = a.clone();
+    a_normalized.normalize(&NormalizeConfig::all()).unwrap();
+    b.normalize(&NormalizeConfig::all()).unwrap();
+    assert_eq!(a_normalized, b);
+    assert_eq!(
+        a_normalized.structural_hash(HashAlgo::Sha256),
+        bonjson!({"x": 1.5, "y": [2, 3]}).structural_hash(HashAlgo::Sha256)
+    );
+}
@@ -0,0 +1,114 @@
+// ABOUTME: OptionalField, a struct field type that tells an omitted member from an explicit null.
+// ABOUTME: For PATCH-style updates, where leaving a field out and setting it to null mean different things.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A struct field that tells a member left out of the document from one set
+/// to null, which `Option<T>` reads alike.
+///
+/// The field must be marked `#[serde(default)]`: serde only calls the
+/// default for a missing member, and reads a missing `Option`-like member as
+/// null otherwise. To write `Missing` fields as absent rather than null, also
+/// mark it `#[serde(skip_serializing_if = "OptionalField::is_missing")]`.
+///
+/// Record instances carry their values by position, so a member missing from
+/// a record instance is only `Missing` under
+/// [`RecordMismatchMode::Truncate`](crate::RecordMismatchMode::Truncate); the
+/// default `NullFill` fills it in with a real null, which reads as `Null`.
+///
+/// # Example
+///
+/// ```rust
+/// use serde::Deserialize;
+/// use serde_bonjson::{bonjson, encode_value, from_slice, OptionalField};
+///
+/// #[derive(Deserialize)]
+/// struct Patch {
+///     #[serde(default)]
+///     email: OptionalField<String>,
+///     #[serde(default)]
+///     nickname: OptionalField<String>,
+/// }
+///
+/// let bytes = encode_value(&bonjson!({"nickname": null})).unwrap();
+/// let patch: Patch = from_slice(&bytes).unwrap();
+/// assert_eq!(patch.email, OptionalField::Missing); // leave unchanged
+/// assert_eq!(patch.nickname, OptionalField::Null); // clear
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OptionalField<T> {
+    /// The member wasn't in the document (default).
+    #[default]
+    Missing,
+    /// The member was null.
+    Null,
+    /// The member had a value.
+    Value(T),
+}
+
+impl<T> OptionalField<T> {
+    /// Returns true if the member wasn't in the document.
+    #[must_use]
+    pub fn is_missing(&self) -> bool {
+        matches!(self, OptionalField::Missing)
+    }
+
+    /// Returns true if the member was null.
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, OptionalField::Null)
+    }
+
+    /// The value, if the member had one.
+    #[must_use]
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            OptionalField::Value(value) => Some(value),
+            OptionalField::Missing | OptionalField::Null => None,
+        }
+    }
+
+    /// Take the value, if the member had one.
+    #[must_use]
+    pub fn into_value(self) -> Option<T> {
+        match self {
+            OptionalField::Value(value) => Some(value),
+            OptionalField::Missing | OptionalField::Null => None,
+        }
+    }
+
+    /// `None` if the member was missing, otherwise the `Option<T>` it held.
+    #[must_use]
+    pub fn into_option(self) -> Option<Option<T>> {
+        match self {
+            OptionalField::Missing => None,
+            OptionalField::Null => Some(None),
+            OptionalField::Value(value) => Some(Some(value)),
+        }
+    }
+}
+
+impl<T> From<Option<T>> for OptionalField<T> {
+    /// `None` becomes `Null`: an `Option` that's there at all was given.
+    fn from(value: Option<T>) -> Self {
+        value.map_or(OptionalField::Null, OptionalField::Value)
+    }
+}
+
+impl<T: Serialize> Serialize for OptionalField<T> {
+    /// `Missing` and `Null` both serialize as null; skip `Missing` fields
+    /// with `skip_serializing_if` to leave them out.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            OptionalField::Missing | OptionalField::Null => serializer.serialize_none(),
+            OptionalField::Value(value) => serializer.serialize_some(value),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for OptionalField<T> {
+    /// Only ever `Null` or `Value`; `Missing` comes from `#[serde(default)]`.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Option::<T>::deserialize(deserializer).map(OptionalField::from)
+    }
+}
@@ -0,0 +1,80 @@
+// ABOUTME: Unit tests for OptionalField.
+// ABOUTME: Tests missing vs null vs value through objects, records, Value and serialization.
+
+use crate::{bonjson, encode_value, from_slice, from_slice_with_config, from_value, to_value, to_vec, to_vec_with_config};
+use crate::{DecoderConfig, OptionalField, RecordMismatchMode, SerializerConfig};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Patch {
+    id: u32,
+    #[serde(default, skip_serializing_if = "OptionalField::is_missing")]
+    name: OptionalField<String>,
+    #[serde(default, skip_serializing_if = "OptionalField::is_missing")]
+    age: OptionalField<u8>,
+}
+
+#[test]
+fn test_missing_null_and_value() {
+    let bytes = encode_value(&bonjson!({"id": 1, "name": null, "age": 30})).unwrap();
+    let patch: Patch = from_slice(&bytes).unwrap();
+    assert_eq!(patch.name, OptionalField::Null);
+    assert_eq!(patch.age, OptionalField::Value(30));
+
+    let bytes = encode_value(&bonjson!({"id": 1})).unwrap();
+    let patch: Patch = from_slice(&bytes).unwrap();
+    assert!(patch.name.is_missing());
+    assert!(patch.age.is_missing());
+
+    // The same through a Value
+    let patch: Patch = from_value(&bonjson!({"id": 1, "age": null})).unwrap();
+    assert_eq!((patch.name, patch.age), (OptionalField::Missing, OptionalField::Null));
+}
+
+#[test]
+fn test_serialize_round_trip() {
+    let patches = [
+        Patch { id: 1, name: OptionalField::Missing, age: OptionalField::Null },
+        Patch { id: 2, name: OptionalField::Value("x".into()), age: OptionalField::Missing },
+    ];
+    for patch in &patches {
+        let decoded: Patch = from_slice(&to_vec(patch).unwrap()).unwrap();
+        assert_eq!(&decoded, patch);
+    }
+    assert_eq!(to_value(&patches[0]).unwrap(), bonjson!({"id": 1, "age": null}));
+
+    // Skipped fields vary the key set, so these never share a record definition
+    let records = SerializerConfig { records: true, ..Default::default() };
+    let decoded: Vec<Patch> = from_slice(&to_vec_with_config(&patches, &records).unwrap()).unwrap();
+    assert_eq!(decoded, patches);
+}
+
+#[test]
+fn test_short_record_instances() {
+    // Definition [id, name, age], instance with only id and name
+    let bytes = [
+        0xb9, 0x67, b'i', b'd', 0x69, b'n', b'a', b'm', b'e', 0x68, b'a', b'g', b'e', 0xb6,
+        0xba, 0x00, 0x07, 0x66, b'x', 0xb6,
+    ];
+
+    // NullFill (the default) pads with real nulls
+    let patch: Patch = from_slice(&bytes).unwrap();
+    assert_eq!(patch.age, OptionalField::Null);
+
+    let config = DecoderConfig { record_mismatch: RecordMismatchMode::Truncate, ..Default::default() };
+    let patch: Patch = from_slice_with_config(&bytes, config).unwrap();
+    assert_eq!(patch, Patch { id: 7, name: OptionalField::Value("x".into()), age: OptionalField::Missing });
+}
+
+#[test]
+fn test_accessors() {
+    let value = OptionalField::Value(3);
+    assert_eq!(value.value(), Some(&3));
+    assert_eq!(value.into_option(), Some(Some(3)));
+    assert_eq!(OptionalField::<i32>::Null.into_option(), Some(None));
+    assert_eq!(OptionalField::<i32>::Missing.into_option(), None);
+    assert_eq!(OptionalField::<i32>::Null.into_value(), None);
+    assert!(OptionalField::<i32>::from(None).is_null());
+    assert_eq!(OptionalField::from(Some(1)), OptionalField::Value(1));
+    assert!(OptionalField::<String>::default().is_missing());
+}
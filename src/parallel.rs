@@ -0,0 +1,121 @@
+// ABOUTME: Parallel encoding of large root arrays (rayon feature): chunks of elements are encoded
+// ABOUTME: on the rayon pool into separate buffers and concatenated behind shared record definitions.
+
+//! Encoding huge arrays on every core.
+//!
+//! [`encode_value`](crate::encode_value) is single-threaded, so a root array
+//! of millions of objects encodes at one core's speed.
+//! [`encode_value_parallel`] splits the array's elements into chunks, encodes
+//! each chunk into its own buffer on the rayon thread pool, and concatenates
+//! the buffers. Record definitions are decided up front from key sets counted
+//! in parallel, so every chunk refers to the same definitions and the output
+//! is byte for byte what `encode_value` writes.
+//!
+//! ```rust
+//! use serde_bonjson::{bonjson, encode_value, parallel::encode_value_parallel, Value};
+//!
+//! let rows: Vec<Value> = (0..10_000).map(|i| bonjson!({"id": i, "name": (format!("row {i}"))})).collect();
+//! let value = Value::Array(rows);
+//! assert_eq!(encode_value_parallel(&value).unwrap(), encode_value(&value).unwrap());
+//! ```
+
+use crate::checksum;
+use crate::encoder::{Encoder, EncoderConfig};
+use crate::error::Result;
+use crate::value::Value;
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+/// The fewest elements a chunk gets; smaller arrays are encoded on the calling thread.
+pub const MIN_CHUNK_LEN: usize = 1024;
+
+/// Encode a `Value` to BONJSON bytes, encoding the elements of a large root
+/// array in parallel.
+///
+/// # Errors
+///
+/// Returns the same errors as [`encode_value`](crate::encode_value).
+pub fn encode_value_parallel(value: &Value) -> Result<Vec<u8>> {
+    encode_value_parallel_with_config(value, EncoderConfig::default())
+}
+
+/// Encode a `Value` to BONJSON bytes with the given configuration, encoding
+/// the elements of a large root array in parallel.
+///
+/// The output is identical to
+/// [`encode_value_with_config`](crate::encode_value_with_config). Anything
+/// other than a root array of more than [`MIN_CHUNK_LEN`] elements, and
+/// arrays that encode as typed arrays, go through it unchanged.
+///
+/// # Errors
+///
+/// Returns the same errors as `encode_value_with_config`. When several
+/// elements fail, the error is the first one's in document order.
+pub fn encode_value_parallel_with_config(value: &Value, config: EncoderConfig) -> Result<Vec<u8>> {
+    let Value::Array(elements) = value else {
+        return crate::encode_value_with_config(value, config);
+    };
+    // Several chunks per thread, so an uneven chunk doesn't leave the others idle
+    let chunk_len = elements.len().div_ceil(rayon::current_num_threads() * 4).max(MIN_CHUNK_LEN);
+    let typed = config.capabilities.typed_arrays && crate::detect_typed_array(elements).is_some();
+    if elements.len() <= chunk_len || typed {
+        return crate::encode_value_with_config(value, config);
+    }
+    codec_span!("bonjson.encode_value_parallel", elements = elements.len());
+
+    let record_defs = if config.capabilities.records { record_definitions(elements, chunk_len) } else { Vec::new() };
+    let def_index_map: HashMap<Vec<String>, usize> =
+        record_defs.iter().enumerate().map(|(i, keys)| (keys.clone(), i)).collect();
+
+    let chunks: Vec<Result<Encoder<Vec<u8>>>> = elements
+        .par_chunks(chunk_len)
+        .map(|chunk| {
+            let mut encoder = Encoder::with_config(Vec::new(), config.clone());
+            for element in chunk {
+                crate::encode_value_recursive_inner(&mut encoder, element, &record_defs, &def_index_map)?;
+            }
+            Ok(encoder)
+        })
+        .collect();
+
+    let mut encoder = Encoder::with_config(Vec::new(), config.clone());
+    for def in &record_defs {
+        let keys: Vec<&str> = def.iter().map(String::as_str).collect();
+        encoder.write_record_definition(&keys)?;
+    }
+    encoder.begin_array()?;
+    let chunks = chunks.into_iter().collect::<Result<Vec<_>>>()?;
+    encoder.reserve(chunks.iter().map(|chunk| chunk.get_ref().len()).sum::<usize>() + 1);
+    for chunk in &chunks {
+        encoder.absorb_metrics(chunk);
+        encoder.write_raw_unchecked(chunk.get_ref())?;
+    }
+    encoder.end_container()?;
+    encoder.report_metrics();
+    let mut buf = encoder.finish()?;
+    if config.checksum_trailer {
+        checksum::append_trailer(&mut buf);
+    }
+    Ok(buf)
+}
+
+/// The record definitions `encode_value` would emit for an array of
+/// `elements`, counting key sets one chunk per task.
+fn record_definitions(elements: &[Value], chunk_len: usize) -> Vec<Vec<String>> {
+    let counts = elements
+        .par_chunks(chunk_len)
+        .map(|chunk| {
+            let mut counts = HashMap::new();
+            for element in chunk {
+                crate::count_key_sets(element, &mut counts);
+            }
+            counts
+        })
+        .reduce(HashMap::new, |mut total, counts| {
+            for (keys, n) in counts {
+                *total.entry(keys).or_insert(0) += n;
+            }
+            total
+        });
+    crate::record_definitions_from_counts(counts)
+}
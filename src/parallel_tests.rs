@@ -0,0 +1,59 @@
+// ABOUTME: Unit tests for the parallel module (rayon feature).
+// ABOUTME: Tests that parallel output matches encode_value, the fallbacks, the trailer and error order.
+
+use crate::parallel::{encode_value_parallel, encode_value_parallel_with_config, MIN_CHUNK_LEN};
+use crate::{assert_bonjson_eq, bonjson, decode_value, encode_value, encode_value_with_config, CapabilitySet, EncoderConfig, Error, Value};
+
+fn rows(n: usize) -> Vec<Value> {
+    (0..n as i64)
+        .map(|i| match i % 3 {
+            0 => bonjson!({"id": i, "name": (format!("row {i}")), "tags": ["a", "b"]}),
+            1 => bonjson!({"id": i, "score": (i as f64 / 8.0)}),
+            _ => bonjson!([i, "loose", null, {"unique": true}]),
+        })
+        .collect()
+}
+
+#[test]
+fn test_parallel_matches_serial() {
+    let value = Value::Array(rows(20 * MIN_CHUNK_LEN + 17));
+    let bytes = encode_value_parallel(&value).unwrap();
+    assert_eq!(bytes, encode_value(&value).unwrap());
+    assert_bonjson_eq!(decode_value(&bytes).unwrap(), value);
+
+    // Without records, every object is written as a plain object
+    let config = EncoderConfig::default().capabilities(CapabilitySet { records: false, ..CapabilitySet::ALL });
+    let bytes = encode_value_parallel_with_config(&value, config.clone()).unwrap();
+    assert_eq!(bytes, encode_value_with_config(&value, config).unwrap());
+}
+
+#[test]
+fn test_parallel_falls_back() {
+    // Scalars, small arrays and typed arrays are encoded serially
+    let small = Value::Array(rows(10));
+    let numbers = Value::Array((0..5 * MIN_CHUNK_LEN).map(|i| Value::Int(i as i64 * 1000)).collect());
+    for value in [bonjson!({"a": 1}), bonjson!("text"), small, numbers] {
+        assert_eq!(encode_value_parallel(&value).unwrap(), encode_value(&value).unwrap());
+    }
+}
+
+#[test]
+fn test_parallel_checksum_trailer() {
+    let value = Value::Array(rows(4 * MIN_CHUNK_LEN));
+    let config = EncoderConfig::default().checksum_trailer(true);
+    let bytes = encode_value_parallel_with_config(&value, config.clone()).unwrap();
+    assert_eq!(bytes, encode_value_with_config(&value, config).unwrap());
+    assert_bonjson_eq!(crate::decode_value_verified(&bytes).unwrap(), value);
+}
+
+#[test]
+fn test_parallel_reports_first_error() {
+    let mut elements = rows(8 * MIN_CHUNK_LEN);
+    elements[3 * MIN_CHUNK_LEN] = bonjson!({"bad": "nul\0"});
+    elements[6 * MIN_CHUNK_LEN] = Value::Float(f64::NAN);
+    let value = Value::Array(elements);
+    assert!(matches!(encode_value(&value), Err(Error::NulCharacter)));
+    for _ in 0..5 {
+        assert!(matches!(encode_value_parallel(&value), Err(Error::NulCharacter)));
+    }
+}
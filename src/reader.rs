@@ -0,0 +1,452 @@
+// ABOUTME: Incremental deserialization from an io::Read, keeping only the bytes of the current value in memory.
+// ABOUTME: Refills a buffer as needed, re-parsing values that span a refill, and iterates root array elements.
+
+use crate::de::{deserialize_typed_element, Deserializer, ElementModes};
+use crate::decoder::{DecodedValue, Decoder, DecoderConfig, CANCELLATION_POLL_INTERVAL};
+use crate::error::{Error, Result};
+use serde::de::DeserializeOwned;
+use crate::backend::BonjsonRead;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Bytes requested from the reader per refill, at minimum. Refills grow with
+/// the buffer, so a value spanning many refills is re-parsed only O(log n) times.
+const READ_CHUNK: usize = 64 * 1024;
+
+/// A deserializer that pulls BONJSON from an [`io::Read`](std::io::Read) on demand.
+///
+/// Unlike [`from_reader`](crate::from_reader), which reads the whole input
+/// first, this buffers only what the value being decoded needs: iterating a
+/// 4 GB root array with [`array_elements`](Self::array_elements) holds one
+/// element's bytes at a time. Values that straddle a refill are re-parsed once
+/// the rest of their bytes arrive.
+///
+/// `max_document_size` is checked against the total bytes read. Each array
+/// element is decoded on its own, so `max_total_allocated_bytes` applies per
+/// element rather than to the whole document.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{to_vec, ReaderDeserializer};
+///
+/// let bytes = to_vec(&vec!["first line", "second line"]).unwrap();
+/// let mut de = ReaderDeserializer::new(bytes.as_slice());
+/// for line in de.array_elements::<String>() {
+///     println!("{}", line.unwrap());
+/// }
+/// ```
+pub struct ReaderDeserializer<R: BonjsonRead> {
+    reader: R,
+    config: DecoderConfig,
+    buffer: Vec<u8>,
+    /// Start of the unconsumed part of `buffer`.
+    start: usize,
+    /// Stream offset of `buffer[0]`.
+    buffer_offset: usize,
+    eof: bool,
+    /// Stream offset where the current document starts.
+    document_start: usize,
+    /// Record definitions from the start of the document, once read.
+    record_definitions: Arc<Vec<Vec<String>>>,
+    header_read: bool,
+    /// While resynchronizing, the most bytes a candidate document may span
+    /// before it's abandoned as `Truncated` instead of refilled further.
+    refill_limit: Option<usize>,
+}
+
+impl<R: BonjsonRead> ReaderDeserializer<R> {
+    /// Create a deserializer reading from `reader`.
+    ///
+    /// Reads are issued in chunks of at least 64 KiB, so unbuffered readers
+    /// don't need a `BufReader`.
+    #[must_use]
+    pub fn new(reader: R) -> Self {
+        Self::with_config(reader, DecoderConfig::default())
+    }
+
+    /// Create a deserializer with custom configuration.
+    #[must_use]
+    pub fn with_config(reader: R, config: DecoderConfig) -> Self {
+        Self {
+            reader,
+            config,
+            buffer: Vec::new(),
+            start: 0,
+            buffer_offset: 0,
+            eof: false,
+            document_start: 0,
+            record_definitions: Arc::new(Vec::new()),
+            header_read: false,
+            refill_limit: None,
+        }
+    }
+
+    /// The number of bytes of the stream consumed so far.
+    #[must_use]
+    pub fn byte_offset(&self) -> usize {
+        self.buffer_offset + self.start
+    }
+
+    /// Deserialize the whole document as a `T`.
+    ///
+    /// The document is still buffered in full while it's decoded; use
+    /// [`array_elements`](Self::array_elements) to stream a root array.
+    pub fn deserialize<T: DeserializeOwned>(&mut self) -> Result<T> {
+        let value = self.deserialize_root()?;
+        self.end()?;
+        Ok(value)
+    }
+
+    /// Iterate over back-to-back documents, deserializing each as a `T`.
+    ///
+    /// Each document has its own record definitions and its own
+    /// `max_document_size` budget. See [`Deserializer::into_iter`] for the
+    /// byte slice equivalent.
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter<T: DeserializeOwned>(self) -> ReaderStreamDeserializer<R, T> {
+        ReaderStreamDeserializer {
+            de: self,
+            failed: false,
+            skip_padding: false,
+            resync_on_error: false,
+            resync: Resync::Off,
+            resync_window: READ_CHUNK,
+            skipped: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Deserialize the record definitions and root value of a document.
+    fn deserialize_root<T: DeserializeOwned>(&mut self) -> Result<T> {
+        self.read_header()?;
+        self.with_window(|decoder| {
+            let mut de = Deserializer::from_decoder(decoder);
+            let value = T::deserialize(&mut de)?;
+            let decoder = de.into_decoder();
+            decoder.finish_value()?;
+            Ok((value, decoder.position()))
+        })
+    }
+
+    /// Returns true if no input is left, reading more if the buffer is used up.
+    fn at_eof(&mut self) -> Result<bool> {
+        while self.start == self.buffer.len() && !self.eof {
+            self.fill()?;
+        }
+        Ok(self.start == self.buffer.len())
+    }
+
+    /// Iterate over the elements of the document's root array (regular or
+    /// typed), deserializing each as a `T` as it's read.
+    ///
+    /// After the last element, the iterator checks that nothing but trailing
+    /// bytes allowed by the config follow the array. It stops after the first
+    /// error.
+    pub fn array_elements<T: DeserializeOwned>(&mut self) -> ArrayElements<'_, R, T> {
+        ArrayElements { de: self, state: ArrayState::Start, count: 0, _marker: PhantomData }
+    }
+
+    /// Check that the input ends here, unless `allow_trailing_bytes` is set.
+    fn end(&mut self) -> Result<()> {
+        if self.config.allow_trailing_bytes || self.at_eof()? {
+            return Ok(());
+        }
+        Err(Error::TrailingBytes(self.byte_offset()))
+    }
+
+    /// Read the record definitions at the start of the document, if not yet done.
+    fn read_header(&mut self) -> Result<()> {
+        if self.header_read {
+            return Ok(());
+        }
+        self.record_definitions = self.with_window(|mut decoder| {
+            decoder.read_record_definitions()?;
+            // More definitions may follow past the end of the buffer
+            decoder.peek_type_code()?;
+            Ok((decoder.shared_record_definitions(), decoder.position()))
+        })?;
+        self.header_read = true;
+        Ok(())
+    }
+
+    /// Run `parse` over the unconsumed bytes, refilling and retrying while it
+    /// runs out of input. `parse` returns its result and the bytes it consumed.
+    fn with_window<X>(&mut self, mut parse: impl FnMut(Decoder<'_>) -> Result<(X, usize)>) -> Result<X> {
+        loop {
+            let decoder = Decoder::resume(
+                &self.buffer[self.start..],
+                self.config.clone(),
+                Arc::clone(&self.record_definitions),
+                0,
+            );
+            match parse(decoder) {
+                Ok((value, consumed)) => {
+                    self.start += consumed;
+                    if self.byte_offset() - self.document_start > self.config.document_size_limit() {
+                        return Err(Error::MaxDocumentSizeExceeded);
+                    }
+                    return Ok(value);
+                }
+                Err(Error::Truncated) if !self.eof => self.fill()?,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Drop consumed bytes and read more, at least as many as are buffered.
+    fn fill(&mut self) -> Result<()> {
+        // Refills happen mid-value, so the unconsumed bytes all belong to the current document
+        let buffered = self.buffer_offset + self.buffer.len() - self.document_start;
+        if buffered > self.config.document_size_limit() {
+            return Err(Error::MaxDocumentSizeExceeded);
+        }
+        if self.refill_limit.is_some_and(|limit| buffered >= limit) {
+            return Err(Error::Truncated);
+        }
+        if self.start > 0 {
+            self.buffer.drain(..self.start);
+            self.buffer_offset += self.start;
+            self.start = 0;
+        }
+        let want = self.buffer.len().max(READ_CHUNK);
+        let read = self.reader.read_into(&mut self.buffer, want)?;
+        self.eof = read == 0;
+        Ok(())
+    }
+}
+
+/// Iterator over a root array's elements; see [`ReaderDeserializer::array_elements`].
+pub struct ArrayElements<'a, R: BonjsonRead, T> {
+    de: &'a mut ReaderDeserializer<R>,
+    state: ArrayState,
+    count: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+enum ArrayState {
+    Start,
+    Array,
+    TypedArray { element_type_code: u8, remaining: usize },
+    Done,
+}
+
+impl<R: BonjsonRead, T: DeserializeOwned> ArrayElements<'_, R, T> {
+    fn open(&mut self) -> Result<()> {
+        self.de.read_header()?;
+        self.state = self.de.with_window(|mut decoder| {
+            let tc = decoder.peek_type_code()?;
+            let state = match decoder.decode_value()? {
+                DecodedValue::ArrayStart => ArrayState::Array,
+                DecodedValue::TypedArrayStart { element_type_code, count } => {
+                    ArrayState::TypedArray { element_type_code, remaining: count }
+                }
+                _ => return Err(Error::Custom(format!("expected array, got 0x{tc:02x}"))),
+            };
+            Ok((state, decoder.position()))
+        })?;
+        Ok(())
+    }
+
+    fn next_element(&mut self) -> Result<Option<T>> {
+        if matches!(self.state, ArrayState::Start) {
+            self.open()?;
+        }
+        // Elements are decoded independently, so poll for cancellation here
+        let poll = self.count > 0 && self.count.is_multiple_of(CANCELLATION_POLL_INTERVAL);
+        let element = match &mut self.state {
+            ArrayState::Array => self.de.with_window(|mut decoder| {
+                if !decoder.skip_to_array_element()? {
+                    return Ok((None, decoder.position()));
+                }
+                if poll {
+                    decoder.check_cancellation()?;
+                }
+                let mut de = Deserializer::from_decoder(decoder);
+                let value = T::deserialize(&mut de)?;
+                Ok((Some(value), de.into_decoder().position()))
+            })?,
+            ArrayState::TypedArray { remaining: 0, .. } => None,
+            ArrayState::TypedArray { element_type_code, remaining } => {
+                let element_type_code = *element_type_code;
+                *remaining -= 1;
+                self.de.with_window(|mut decoder| {
+                    if poll {
+                        decoder.check_cancellation()?;
+                    }
+                    let element = decoder.read_typed_element(element_type_code)?;
+                    let value = deserialize_typed_element(PhantomData, element, ElementModes::of(decoder.config()))?;
+                    Ok((Some(value), decoder.position()))
+                })?
+            }
+            ArrayState::Start | ArrayState::Done => None,
+        };
+        match element {
+            Some(_) => {
+                self.count += 1;
+                if self.count > self.de.config.max_container_size {
+                    return Err(Error::MaxContainerSizeExceeded);
+                }
+            }
+            None => {
+                self.state = ArrayState::Done;
+                self.de.end()?;
+            }
+        }
+        Ok(element)
+    }
+}
+
+impl<R: BonjsonRead, T: DeserializeOwned> Iterator for ArrayElements<'_, R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if matches!(self.state, ArrayState::Done) {
+            return None;
+        }
+        match self.next_element() {
+            Ok(element) => element.map(Ok),
+            Err(e) => {
+                self.state = ArrayState::Done;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Iterator over back-to-back documents; see [`ReaderDeserializer::into_iter`].
+pub struct ReaderStreamDeserializer<R: BonjsonRead, T> {
+    de: ReaderDeserializer<R>,
+    failed: bool,
+    skip_padding: bool,
+    resync_on_error: bool,
+    resync: Resync,
+    resync_window: usize,
+    skipped: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+/// Where a `ReaderStreamDeserializer` is in recovering from an error.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Resync {
+    Off,
+    /// A document failed and the error was yielded; resume past its start.
+    Pending,
+    /// Looking for the next offset where a document decodes.
+    Scanning,
+}
+
+impl<R: BonjsonRead, T> ReaderStreamDeserializer<R, T> {
+    /// The stream position after the documents yielded so far and any bytes
+    /// skipped. After an error, this is the offset of the document that failed.
+    #[must_use]
+    pub fn byte_offset(&self) -> usize {
+        self.de.document_start
+    }
+
+    /// Skip `0x00` bytes between documents; see
+    /// [`StreamDeserializer::skip_padding`](crate::StreamDeserializer::skip_padding).
+    #[must_use]
+    pub fn skip_padding(mut self, skip: bool) -> Self {
+        self.skip_padding = skip;
+        self
+    }
+
+    /// Keep going after a document fails to decode; see
+    /// [`StreamDeserializer::resync_on_error`](crate::StreamDeserializer::resync_on_error).
+    ///
+    /// I/O errors still end the stream.
+    ///
+    /// While scanning, each candidate offset is given at most
+    /// [`resync_window`](Self::resync_window) bytes (64 KiB by default) to
+    /// decode in. A stray array or object start in the damaged stretch would
+    /// otherwise read the rest of the stream as its contents, buffering up to
+    /// `max_document_size` per candidate. A scan thus buffers at most one
+    /// window beyond the candidate and costs up to a window's worth of parsing
+    /// per byte skipped; a real document longer than the window right after
+    /// the damage is passed over too.
+    #[must_use]
+    pub fn resync_on_error(mut self, resync: bool) -> Self {
+        self.resync_on_error = resync;
+        self
+    }
+
+    /// The most bytes a candidate document may span while resynchronizing
+    /// (default: 64 KiB); see [`resync_on_error`](Self::resync_on_error).
+    #[must_use]
+    pub fn resync_window(mut self, bytes: usize) -> Self {
+        self.resync_window = bytes;
+        self
+    }
+
+    /// The number of padding and resynchronization bytes skipped so far.
+    #[must_use]
+    pub fn skipped_bytes(&self) -> usize {
+        self.skipped
+    }
+
+    /// Skip the `0x00` bytes at the current position.
+    fn skip_zeros(&mut self) -> Result<()> {
+        while !self.de.at_eof()? && self.de.buffer[self.de.start] == 0 {
+            self.de.start += 1;
+            self.skipped += 1;
+        }
+        self.de.document_start = self.de.byte_offset();
+        Ok(())
+    }
+
+    /// Move to the byte after the start of the document that failed. If its
+    /// start has already been dropped from the buffer (after a refill past
+    /// its record definitions), resume at the oldest byte still buffered.
+    fn resume_past_document_start(&mut self) {
+        let de = &mut self.de;
+        let resume = (de.document_start + 1).max(de.buffer_offset);
+        de.start = resume - de.buffer_offset;
+        self.skipped += resume - de.document_start;
+        de.document_start = resume;
+    }
+}
+
+impl<R: BonjsonRead, T: DeserializeOwned> Iterator for ReaderStreamDeserializer<R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        loop {
+            if self.failed {
+                return None;
+            }
+            if self.resync == Resync::Pending {
+                self.resume_past_document_start();
+                self.resync = Resync::Scanning;
+            }
+            let padding = if self.skip_padding { self.skip_zeros() } else { Ok(()) };
+            let result = match padding.and_then(|()| self.de.at_eof()) {
+                Ok(true) => return None,
+                Ok(false) => {
+                    self.de.header_read = false;
+                    self.de.refill_limit = (self.resync == Resync::Scanning).then_some(self.resync_window);
+                    let result = self.de.deserialize_root();
+                    self.de.refill_limit = None;
+                    result
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(value) => {
+                    self.de.document_start = self.de.byte_offset();
+                    self.resync = Resync::Off;
+                    return Some(Ok(value));
+                }
+                Err(e) if !self.resync_on_error || matches!(e, Error::Io(_)) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+                Err(_) if self.resync == Resync::Scanning => self.resume_past_document_start(),
+                Err(e) => {
+                    self.resync = Resync::Pending;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+// ABOUTME: Unit tests for the reader module.
+// ABOUTME: Tests incremental array iteration, values spanning refills, and limits over io::Read.
+
+use crate::{to_vec, to_vec_with_config, DecoderConfig, Error, ReaderDeserializer, SerializerConfig};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::io::{self, Read};
+use std::rc::Rc;
+
+/// A reader that hands out at most `step` bytes per call and counts what it has handed out.
+struct Trickle<'a> {
+    data: &'a [u8],
+    step: usize,
+    served: Rc<Cell<usize>>,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.step).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        self.served.set(self.served.get() + n);
+        Ok(n)
+    }
+}
+
+fn trickle(data: &[u8], step: usize) -> (Trickle<'_>, Rc<Cell<usize>>) {
+    let served = Rc::new(Cell::new(0));
+    (Trickle { data, step, served: Rc::clone(&served) }, served)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct Entry {
+    id: u32,
+    message: String,
+    samples: Vec<f64>,
+}
+
+fn entries(count: u32) -> Vec<Entry> {
+    (0..count)
+        .map(|id| Entry { id, message: format!("entry {id} {}", "x".repeat(id as usize % 90)), samples: vec![0.1; 3] })
+        .collect()
+}
+
+#[test]
+fn test_array_elements_across_refills() {
+    // Enough elements that many straddle a refill, with record instances
+    let values = entries(20_000);
+    let config = SerializerConfig { records: true, ..Default::default() };
+    let bytes = to_vec_with_config(&values, &config).unwrap();
+    assert_eq!(bytes[0], crate::type_code::RECORD_DEF);
+    assert!(bytes.len() > 1_000_000);
+
+    let (reader, served) = trickle(&bytes, 1000);
+    let mut de = ReaderDeserializer::new(reader);
+    let mut elements = de.array_elements::<Entry>();
+    assert_eq!(elements.next().unwrap().unwrap(), values[0]);
+    assert!(served.get() < 200_000, "read {} bytes for the first element", served.get());
+    let rest: Vec<Entry> = elements.collect::<crate::Result<_>>().unwrap();
+    assert_eq!(rest, values[1..]);
+    assert_eq!(de.byte_offset(), bytes.len());
+
+    // A single element larger than several refills
+    let values = vec!["y".repeat(300_000), "z".to_string()];
+    let bytes = to_vec(&values).unwrap();
+    let (reader, _) = trickle(&bytes, 4096);
+    let decoded: Vec<String> = ReaderDeserializer::new(reader).array_elements().collect::<crate::Result<_>>().unwrap();
+    assert_eq!(decoded, values);
+
+    // Typed array roots stream element by element too
+    let floats = vec![0.1f64, 0.2, 0.3];
+    let bytes = to_vec(&floats).unwrap();
+    assert!(crate::type_code::is_typed_array(bytes[0]));
+    let decoded: Vec<f64> =
+        ReaderDeserializer::new(bytes.as_slice()).array_elements().collect::<crate::Result<_>>().unwrap();
+    assert_eq!(decoded, floats);
+}
+
+#[test]
+fn test_array_elements_skips_unknown_type_codes() {
+    // [1, <0xd0, empty>, 2, <0xd0 with a 1-byte payload>]
+    let bytes = [0xb7, 0x01, 0xd0, 0x00, 0x02, 0xd0, 0x01, 0xff, 0xb6];
+    let config = DecoderConfig { unknown_type_code: crate::UnknownTypeCodeMode::SkipValue, ..Default::default() };
+    let mut de = ReaderDeserializer::with_config(bytes.as_slice(), config);
+    let decoded: Vec<u8> = de.array_elements().collect::<crate::Result<_>>().unwrap();
+    assert_eq!(decoded, vec![1, 2]);
+    assert_eq!(de.byte_offset(), bytes.len());
+
+    let results: Vec<crate::Result<u8>> = ReaderDeserializer::new(bytes.as_slice()).array_elements().collect();
+    assert!(matches!(results.get(1), Some(Err(_))), "{results:?}");
+}
+
+#[test]
+fn test_reader_deserialize_document() {
+    let values = entries(5);
+    let bytes = to_vec(&values).unwrap();
+    let (reader, _) = trickle(&bytes, 7);
+    assert_eq!(ReaderDeserializer::new(reader).deserialize::<Vec<Entry>>().unwrap(), values);
+
+    let mut trailing = bytes.clone();
+    trailing.push(0x01);
+    let err = ReaderDeserializer::new(trailing.as_slice()).deserialize::<Vec<Entry>>().unwrap_err();
+    assert_eq!(err, Error::TrailingBytes(bytes.len()));
+    let config = DecoderConfig { allow_trailing_bytes: true, ..Default::default() };
+    let mut de = ReaderDeserializer::with_config(trailing.as_slice(), config);
+    assert_eq!(de.deserialize::<Vec<Entry>>().unwrap(), values);
+    assert_eq!(de.byte_offset(), bytes.len());
+
+    let truncated = &bytes[..bytes.len() - 2];
+    let err = ReaderDeserializer::new(truncated).deserialize::<Vec<Entry>>().unwrap_err();
+    assert_eq!(err, Error::Truncated);
+}
+
+#[test]
+fn test_array_elements_errors() {
+    // The error is yielded in place, after the elements that decoded
+    let bytes = to_vec(&vec!["a", "b", "c"]).unwrap();
+    let truncated = &bytes[..bytes.len() - 3];
+    let results: Vec<crate::Result<String>> = ReaderDeserializer::new(truncated).array_elements().collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[2], Err(Error::Truncated));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0x01);
+    let results: Vec<crate::Result<String>> = ReaderDeserializer::new(trailing.as_slice()).array_elements().collect();
+    assert_eq!(results.last(), Some(&Err(Error::TrailingBytes(bytes.len()))));
+
+    let bytes = to_vec(&"not an array").unwrap();
+    let mut de = ReaderDeserializer::new(bytes.as_slice());
+    assert!(de.array_elements::<String>().next().unwrap().is_err());
+
+    let bytes = to_vec(&entries(100)).unwrap();
+    let config = DecoderConfig { max_document_size: 1000, ..Default::default() };
+    let mut de = ReaderDeserializer::with_config(bytes.as_slice(), config);
+    let err = de.array_elements::<Entry>().find_map(Result::err);
+    assert_eq!(err, Some(Error::MaxDocumentSizeExceeded));
+
+    let config = DecoderConfig { max_container_size: 2, ..Default::default() };
+    let mut de = ReaderDeserializer::with_config(bytes.as_slice(), config);
+    let results: Vec<_> = de.array_elements::<Entry>().collect();
+    assert_eq!(results.last(), Some(&Err(Error::MaxContainerSizeExceeded)));
+}
+
+#[test]
+fn test_reader_stream_deserializer() {
+    let records = SerializerConfig { records: true, ..Default::default() };
+    let batches: Vec<Vec<Entry>> = (0..50).map(|i| entries(i % 7 + 1)).collect();
+    let mut log = Vec::new();
+    for (i, batch) in batches.iter().enumerate() {
+        let config = if i % 2 == 0 { records.clone() } else { SerializerConfig::default() };
+        log.extend(to_vec_with_config(batch, &config).unwrap());
+    }
+
+    let (reader, _) = trickle(&log, 100);
+    let mut stream = ReaderDeserializer::new(reader).into_iter::<Vec<Entry>>();
+    for batch in &batches {
+        assert_eq!(&stream.next().unwrap().unwrap(), batch);
+    }
+    assert!(stream.next().is_none());
+    assert_eq!(stream.byte_offset(), log.len());
+
+    // Each document gets its own size budget
+    let document_len = to_vec(&batches[0]).unwrap().len();
+    let config = DecoderConfig { max_document_size: document_len, ..Default::default() };
+    let log: Vec<u8> = (0..500).flat_map(|_| to_vec(&batches[0]).unwrap()).collect();
+    let stream = ReaderDeserializer::with_config(log.as_slice(), config).into_iter::<Vec<Entry>>();
+    assert_eq!(stream.map(Result::unwrap).count(), 500);
+
+    // Small documents over many refills, so document offsets pass the buffer length
+    let log: Vec<u8> = (0..40_000).flat_map(|i| to_vec(&format!("doc {i}")).unwrap()).collect();
+    assert!(log.len() > 4 * 64 * 1024);
+    let stream = ReaderDeserializer::new(io::Cursor::new(&log)).into_iter::<String>();
+    let decoded: Vec<String> = stream.collect::<crate::Result<_>>().unwrap();
+    assert_eq!((decoded.len(), decoded[39_999].as_str()), (40_000, "doc 39999"));
+
+    let mut log = to_vec(&batches[0]).unwrap();
+    log.extend(&[0xb7, 0xb8]);
+    let mut stream = ReaderDeserializer::new(log.as_slice()).into_iter::<Vec<Entry>>();
+    assert!(stream.next().unwrap().is_ok());
+    assert_eq!(stream.next().unwrap(), Err(Error::Truncated));
+    assert_eq!(stream.byte_offset(), document_len);
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn test_reader_stream_padding_and_resync() {
+    let records = SerializerConfig { records: true, ..Default::default() };
+    let batches: Vec<Vec<Entry>> = (0..30).map(|i| entries(i % 5 + 1)).collect();
+    let mut log = Vec::new();
+    let mut padding = 0;
+    let mut garbage = 0;
+    for (i, batch) in batches.iter().enumerate() {
+        let config = if i % 2 == 0 { records.clone() } else { SerializerConfig::default() };
+        log.extend(to_vec_with_config(batch, &config).unwrap());
+        log.extend(vec![0; i % 4]);
+        padding += i % 4;
+        if i % 10 == 9 {
+            // Record definitions, then a damaged root
+            log.extend([0xb9, 0x66, b'i', 0x64, 0xb6, 0xb6, 0xba, 0x00, 0xff]);
+            garbage += 9;
+        }
+    }
+
+    let (reader, _) = trickle(&log, 37);
+    let mut stream = ReaderDeserializer::new(reader).into_iter::<Vec<Entry>>().skip_padding(true);
+    for batch in &batches[..10] {
+        assert_eq!(&stream.next().unwrap().unwrap(), batch);
+    }
+    assert!(stream.next().unwrap().is_err());
+    assert!(stream.next().is_none());
+
+    let (reader, _) = trickle(&log, 37);
+    let stream = ReaderDeserializer::new(reader).into_iter::<Vec<Entry>>().skip_padding(true).resync_on_error(true);
+    let mut decoded = Vec::new();
+    let mut errors = 0;
+    for result in stream {
+        match result {
+            Ok(batch) => decoded.push(batch),
+            Err(_) => errors += 1,
+        }
+    }
+    assert_eq!(decoded, batches);
+    assert_eq!(errors, 3);
+
+    let (reader, _) = trickle(&log, 37);
+    let mut stream = ReaderDeserializer::new(reader).into_iter::<Vec<Entry>>().skip_padding(true).resync_on_error(true);
+    while stream.next().is_some() {}
+    assert_eq!(stream.skipped_bytes(), padding + garbage);
+    assert_eq!(stream.byte_offset(), log.len());
+}
+
+#[test]
+fn test_reader_stream_resync_window() {
+    // Damage, then a stray object start: as a candidate, it would read every
+    // string document after it as a key or value
+    let mut log = to_vec(&"first").unwrap();
+    log.extend([0xbb, 0x00, 0x00, crate::type_code::OBJECT]);
+    let docs: Vec<String> = (0..100_000).map(|i| format!("doc {i}")).collect();
+    for doc in &docs {
+        log.extend(to_vec(doc).unwrap());
+    }
+    assert!(log.len() > 900_000);
+
+    let (reader, served) = trickle(&log, 4096);
+    let mut stream = ReaderDeserializer::new(reader).into_iter::<crate::Value>().skip_padding(true).resync_on_error(true);
+    assert_eq!(stream.next().unwrap().unwrap(), crate::Value::from("first"));
+    assert!(stream.next().unwrap().is_err());
+    assert_eq!(stream.next().unwrap().unwrap(), crate::Value::from("doc 0"));
+    assert!(served.get() < 300_000, "read {} bytes to resync", served.get());
+    assert_eq!(stream.skipped_bytes(), 4);
+    let rest: Vec<crate::Value> = stream.collect::<crate::Result<_>>().unwrap();
+    assert_eq!(rest.len(), docs.len() - 1);
+
+    // Within a smaller window, the first refill is enough
+    let (reader, served) = trickle(&log, 4096);
+    let mut stream = ReaderDeserializer::new(reader).into_iter::<crate::Value>().skip_padding(true).resync_on_error(true).resync_window(4096);
+    assert_eq!(stream.nth(2).unwrap().unwrap(), crate::Value::from("doc 0"));
+    assert_eq!(served.get(), 64 * 1024);
+}
@@ -0,0 +1,98 @@
+// ABOUTME: Validate-and-forward for proxies that must pass documents on byte for byte.
+// ABOUTME: relay() checks a document like decode_value does and keeps the original bytes to forward.
+
+use crate::decoder::DecoderConfig;
+use crate::error::Result;
+use crate::value::Value;
+
+/// A document that passed validation, with its decoded value and the exact
+/// bytes it arrived as.
+///
+/// Forward [`bytes`](Self::bytes), not a re-encoding of [`value`](Self::value):
+/// `encode_value` writes the smallest encoding of each value, sorts object
+/// keys, and picks its own records and typed arrays, so re-encoding only
+/// reproduces documents that were written that way (see [`reencodes_exactly`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelayedDocument<'a> {
+    bytes: &'a [u8],
+    value: Value,
+}
+
+impl<'a> RelayedDocument<'a> {
+    /// The document as received, to be forwarded unchanged.
+    #[must_use]
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The decoded document, for inspecting or routing on its contents.
+    #[must_use]
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Take the decoded document.
+    #[must_use]
+    pub fn into_value(self) -> Value {
+        self.value
+    }
+}
+
+/// Validate a document for forwarding.
+///
+/// The document is decoded with `config`, so it's held to exactly the limits
+/// and checks `decode_value_with_config` applies; the bytes handed back are
+/// the input slice itself, so signatures or checksums computed over it stay
+/// valid downstream. Trailing bytes allowed by `allow_trailing_bytes` are
+/// kept and forwarded too.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::relay::relay;
+/// use serde_bonjson::{bonjson, DecoderConfig, Encoder};
+///
+/// // Keys out of order, and a float encode_value would write as an integer
+/// let mut encoder = Encoder::new(Vec::new());
+/// encoder.begin_object().unwrap();
+/// encoder.write_str("b").unwrap();
+/// encoder.write_float_with_width(1.0, serde_bonjson::FloatWidth::F64).unwrap();
+/// encoder.write_str("a").unwrap();
+/// encoder.write_bool(true).unwrap();
+/// encoder.end_container().unwrap();
+/// let bytes = encoder.finish().unwrap();
+///
+/// // strict() would reject the float64 as non-canonical, so relax that check
+/// let doc = relay(&bytes, DecoderConfig::strict().require_canonical_numbers(false)).unwrap();
+/// assert_eq!(doc.value(), &bonjson!({"a": true, "b": 1.0}));
+/// assert_eq!(doc.bytes(), &bytes[..]);
+/// ```
+///
+/// # Errors
+///
+/// Returns the error `decode_value_with_config` would.
+pub fn relay(data: &[u8], config: DecoderConfig) -> Result<RelayedDocument<'_>> {
+    let value = crate::decode_value_with_config(data, config)?;
+    Ok(RelayedDocument { bytes: data, value })
+}
+
+/// Returns true if decoding `data` into a `Value` and encoding it again with
+/// `encode_value` gives back exactly `data`.
+///
+/// Floats are decoded with `preserve_float_width`, so their widths never
+/// account for a difference. What can differ is everything else
+/// `encode_value` chooses for itself: integer widths, key order, which
+/// objects become record instances, which arrays become typed arrays, and
+/// BigNumbers that fit an integer. When this is false, forward the original
+/// bytes with [`relay`] instead.
+///
+/// # Errors
+///
+/// Returns the error `decode_value_with_config` would, or the error
+/// `encode_value` would for a value it can't write (such as a NaN that
+/// `config` lets through).
+pub fn reencodes_exactly(data: &[u8], config: DecoderConfig) -> Result<bool> {
+    let config = DecoderConfig { preserve_float_width: true, ..config };
+    let value = crate::decode_value_with_config(data, config)?;
+    Ok(crate::encode_value(&value)? == data)
+}
@@ -0,0 +1,67 @@
+// ABOUTME: Unit tests for the relay module.
+// ABOUTME: Tests that relayed bytes are the input, validation matches decode_value, and the re-encoding check.
+
+use crate::relay::{reencodes_exactly, relay};
+use crate::types::type_code;
+use crate::{bonjson, encode_value, DecoderConfig, Encoder, Error, FloatWidth};
+
+/// An object whose keys aren't in sorted order, holding a float64 that
+/// `encode_value` would write as an integer.
+fn hand_written() -> Vec<u8> {
+    let mut encoder = Encoder::new(Vec::new());
+    encoder.begin_object().unwrap();
+    encoder.write_str("z").unwrap();
+    encoder.write_float_with_width(3.0, FloatWidth::F64).unwrap();
+    encoder.write_str("a").unwrap();
+    encoder.write_str("x").unwrap();
+    encoder.end_container().unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn test_relay_forwards_original_bytes() {
+    let bytes = hand_written();
+    let doc = relay(&bytes, DecoderConfig::default()).unwrap();
+    assert_eq!(doc.bytes(), &bytes[..]);
+    assert_eq!(doc.value(), &bonjson!({"a": "x", "z": 3.0}));
+    assert_ne!(encode_value(doc.value()).unwrap(), bytes);
+    let value = doc.value().clone();
+    assert_eq!(doc.into_value(), value);
+
+    // Trailing bytes the config allows are forwarded with the document
+    let mut padded = bytes.clone();
+    padded.push(type_code::NULL);
+    let config = DecoderConfig { allow_trailing_bytes: true, ..Default::default() };
+    assert_eq!(relay(&padded, config).unwrap().bytes(), &padded[..]);
+}
+
+#[test]
+fn test_relay_validates_like_decode_value() {
+    // Duplicate key
+    let dup = [0xb8, 0x66, b'a', 0x01, 0x66, b'a', 0x02, 0xb6];
+    assert_eq!(relay(&dup, DecoderConfig::default()).unwrap_err(), Error::DuplicateKey);
+    assert_eq!(relay(&hand_written()[..4], DecoderConfig::default()).unwrap_err(), Error::Truncated);
+
+    let config = DecoderConfig { max_document_size: 4, ..Default::default() };
+    assert_eq!(relay(&hand_written(), config).unwrap_err(), Error::MaxDocumentSizeExceeded);
+}
+
+#[test]
+fn test_reencodes_exactly() {
+    let config = DecoderConfig::default;
+    let canonical = encode_value(&bonjson!({"id": 7, "ratio": 0.1, "tags": ["a", "b"]})).unwrap();
+    assert!(reencodes_exactly(&canonical, config()).unwrap());
+
+    // Float widths are preserved, so only the key order gives this one away
+    assert!(!reencodes_exactly(&hand_written(), config()).unwrap());
+    let mut encoder = Encoder::new(Vec::new());
+    encoder.begin_array().unwrap();
+    encoder.write_float_with_width(3.0, FloatWidth::F64).unwrap();
+    encoder.write_str("x").unwrap();
+    encoder.end_container().unwrap();
+    assert!(reencodes_exactly(&encoder.finish().unwrap(), config()).unwrap());
+
+    // A wider integer encoding than needed
+    assert!(!reencodes_exactly(&[type_code::UINT16, 0x05, 0x00], config()).unwrap());
+    assert_eq!(reencodes_exactly(&[type_code::UINT16, 0x05], config()).unwrap_err(), Error::Truncated);
+}
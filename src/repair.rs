@@ -0,0 +1,309 @@
+// ABOUTME: Best-effort repair of damaged BONJSON documents, such as buffers cut off mid-write.
+// ABOUTME: Keeps every complete value, closes open containers, fixes invalid strings, and strips trailing garbage.
+
+use crate::decoder::{DecodedValue, Decoder, DecoderConfig, InvalidUtf8Mode, RecordMismatchMode};
+use crate::encoder::{Encoder, EncoderConfig};
+use crate::error::Error;
+use crate::types::{leb128_encode, type_code};
+use std::borrow::Cow;
+
+/// What [`repair`] does with strings that aren't valid UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Utf8Repair {
+    /// Replace invalid bytes with U+FFFD (default)
+    #[default]
+    Replace,
+    /// Remove the string: an object key goes with its value, an object value
+    /// with its key. Record instance values are positional, so they (and a
+    /// string root) become null instead.
+    Drop,
+}
+
+/// Options for [`repair`].
+#[derive(Debug, Clone, Default)]
+pub struct RepairOptions {
+    /// What to do with strings that aren't valid UTF-8 (default: Replace)
+    pub invalid_utf8: Utf8Repair,
+    /// Rules the repaired document must follow; a value that breaks them is
+    /// treated as damage. `invalid_utf8_mode` and `allow_trailing_bytes` are
+    /// ignored.
+    pub decoder_config: DecoderConfig,
+}
+
+/// What [`repair`] changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepairReport {
+    /// Offset of the damage, if any: the input was cut off here and
+    /// everything from this offset on was discarded.
+    pub discarded_from: Option<usize>,
+    /// The decode error found at `discarded_from`.
+    pub error: Option<Error>,
+    /// Containers that were still open at the damage and had to be closed.
+    pub closed_containers: usize,
+    /// Strings whose invalid UTF-8 was replaced.
+    pub replaced_strings: usize,
+    /// Strings dropped (or nulled) for invalid UTF-8.
+    pub dropped_strings: usize,
+    /// Bytes stripped from after the root value.
+    pub trailing_bytes: usize,
+    /// True if nothing of the root value could be salvaged, so it was replaced by null.
+    pub root_replaced: bool,
+}
+
+impl RepairReport {
+    /// Returns true if the document needed no repair.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Repair a damaged document as far as possible.
+///
+/// Meant for recovering buffers left behind by a crash mid-write. The output
+/// keeps every value that was complete before the damage (a truncated typed
+/// array keeps its complete elements), closes the containers left open,
+/// fills missing record instance values with null, fixes or drops strings
+/// with invalid UTF-8 (see [`Utf8Repair`]), and strips anything after the
+/// root value. An object key whose value was lost goes too. If nothing of
+/// the root survives, the output is `null`.
+///
+/// The output always decodes under `options.decoder_config`. Duplicate keys
+/// are not checked.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::{bonjson, decode_value, encode_value, repair, RepairOptions};
+///
+/// let bytes = encode_value(&bonjson!({"events": ["open", "write", "close"]})).unwrap();
+/// // The last write never finished
+/// let (fixed, report) = repair(&bytes[..bytes.len() - 3], RepairOptions::default());
+/// assert_eq!(decode_value(&fixed).unwrap(), bonjson!({"events": ["open", "write"]}));
+/// assert_eq!(report.closed_containers, 2);
+/// ```
+#[must_use]
+pub fn repair(data: &[u8], options: RepairOptions) -> (Vec<u8>, RepairReport) {
+    let mut config = options.decoder_config;
+    // Only damaged strings decode to owned strings in this mode, which is how they're spotted
+    config.invalid_utf8_mode = InvalidUtf8Mode::Replace;
+    config.allow_trailing_bytes = true;
+    let record_mismatch = config.record_mismatch;
+    let mut repairer = Repairer {
+        data,
+        decoder: Decoder::with_config(data, config),
+        invalid_utf8: options.invalid_utf8,
+        record_mismatch,
+        out: Vec::with_capacity(data.len()),
+        stack: Vec::new(),
+        report: RepairReport::default(),
+    };
+    repairer.run();
+    (repairer.out, repairer.report)
+}
+
+// ============================================================================
+// Repairer
+// ============================================================================
+
+enum Frame {
+    Array,
+    /// `key_start` is the output offset of a key still waiting for its value.
+    Object { key_start: Option<usize> },
+    /// `remaining` counts the fields not yet written.
+    Record { remaining: usize },
+}
+
+struct Repairer<'a> {
+    data: &'a [u8],
+    decoder: Decoder<'a>,
+    invalid_utf8: Utf8Repair,
+    record_mismatch: RecordMismatchMode,
+    out: Vec<u8>,
+    stack: Vec<Frame>,
+    report: RepairReport,
+}
+
+type Damage = (usize, Error);
+
+impl Repairer<'_> {
+    fn run(&mut self) {
+        if let Err(e) = self.decoder.read_record_definitions() {
+            // The root follows the definitions, so none of it is left
+            self.replace_root(0, e.into());
+            return;
+        }
+        self.out.extend_from_slice(&self.data[..self.decoder.position()]);
+        let root_start = self.out.len();
+        match self.copy_root() {
+            Ok(()) => self.report.trailing_bytes = self.data.len() - self.decoder.position(),
+            Err((offset, error)) if self.out.len() == root_start => self.replace_root(offset, error),
+            Err((offset, error)) => {
+                self.report.discarded_from = Some(offset);
+                self.report.error = Some(error);
+                self.close_containers();
+            }
+        }
+    }
+
+    /// Replace the whole document with a null root, for damage at `offset`
+    /// before any of the root could be copied.
+    fn replace_root(&mut self, offset: usize, error: Error) {
+        self.out.clear();
+        self.out.push(type_code::NULL);
+        self.report.discarded_from = Some(offset);
+        self.report.error = Some(error);
+        self.report.root_replaced = true;
+    }
+
+    /// Copy the root value to the output, stopping at the first damage.
+    fn copy_root(&mut self) -> std::result::Result<(), Damage> {
+        loop {
+            let start = self.decoder.position();
+            let value_start = self.out.len();
+            let event = self.decoder.decode_value().map_err(|e| (start, e.into()))?;
+            let expecting_key = matches!(self.stack.last(), Some(Frame::Object { key_start: None }));
+
+            match event {
+                DecodedValue::ContainerEnd => {
+                    match self.stack.last() {
+                        Some(Frame::Object { key_start: Some(_) }) => return Err((start, Error::ExpectedObjectValue)),
+                        Some(Frame::Record { remaining }) if *remaining > 0 && self.record_mismatch == RecordMismatchMode::Error => {
+                            return Err((start, Error::InvalidData("record instance is missing values".into())));
+                        }
+                        _ => {}
+                    }
+                    self.stack.pop();
+                    self.copy(start);
+                    if self.complete(value_start, false) {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                _ if expecting_key && !matches!(event, DecodedValue::String(_)) => {
+                    return Err((start, Error::InvalidObjectKey));
+                }
+                _ if matches!(self.stack.last(), Some(Frame::Record { remaining: 0 }))
+                    && self.record_mismatch != RecordMismatchMode::Truncate =>
+                {
+                    let e = Error::InvalidData("record instance has more values than its definition has keys".into());
+                    return Err((start, e));
+                }
+                DecodedValue::String(Cow::Owned(s)) => {
+                    if !self.repair_string(start, &s, expecting_key)? {
+                        continue;
+                    }
+                }
+                DecodedValue::ArrayStart => {
+                    self.copy(start);
+                    self.stack.push(Frame::Array);
+                    continue;
+                }
+                DecodedValue::ObjectStart => {
+                    self.copy(start);
+                    self.stack.push(Frame::Object { key_start: None });
+                    continue;
+                }
+                DecodedValue::RecordInstanceStart(def_index) => {
+                    self.copy(start);
+                    let remaining = self.decoder.record_definitions()[def_index].len();
+                    self.stack.push(Frame::Record { remaining });
+                    continue;
+                }
+                DecodedValue::TypedArrayStart { element_type_code, count } => {
+                    self.copy_typed_array(start, element_type_code, count)?;
+                }
+                _ => self.copy(start),
+            }
+
+            if self.complete(value_start, expecting_key) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Copy the input bytes from `start` to the decoder's position.
+    fn copy(&mut self, start: usize) {
+        self.out.extend_from_slice(&self.data[start..self.decoder.position()]);
+    }
+
+    /// Record that a key or value starting at output offset `value_start` was
+    /// written. Returns true if it was the root.
+    fn complete(&mut self, value_start: usize, is_key: bool) -> bool {
+        match self.stack.last_mut() {
+            None => return true,
+            Some(Frame::Object { key_start }) => *key_start = is_key.then_some(value_start),
+            Some(Frame::Record { remaining }) => *remaining = remaining.saturating_sub(1),
+            Some(Frame::Array) => {}
+        }
+        false
+    }
+
+    /// Handle a string at `start` with invalid UTF-8, whose replaced form is
+    /// `replaced`. Returns true if a key or value took its place in the output.
+    fn repair_string(&mut self, start: usize, replaced: &str, is_key: bool) -> std::result::Result<bool, Damage> {
+        if self.invalid_utf8 == Utf8Repair::Replace {
+            self.report.replaced_strings += 1;
+            // The decoder has already checked for NUL against the configuration
+            let config = EncoderConfig { allow_nul: true, ..Default::default() };
+            Encoder::with_config(&mut self.out, config)
+                .write_str(replaced)
+                .expect("encoding a string into a Vec can't fail");
+            return Ok(true);
+        }
+
+        self.report.dropped_strings += 1;
+        match self.stack.last_mut() {
+            Some(Frame::Object { .. }) if is_key => {
+                self.decoder.skip_value().map_err(|e| (start, e.into()))?;
+                Ok(false)
+            }
+            Some(Frame::Object { key_start }) => {
+                let key = key_start.take().expect("a value follows its key");
+                self.out.truncate(key);
+                Ok(false)
+            }
+            Some(Frame::Array) => Ok(false),
+            Some(Frame::Record { .. }) | None => {
+                self.out.push(type_code::NULL);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Copy a typed array that starts at `start`. If an element is damaged,
+    /// the elements before it are kept under a header with the reduced count.
+    fn copy_typed_array(&mut self, start: usize, element_type_code: u8, count: usize) -> std::result::Result<(), Damage> {
+        let elements_start = self.decoder.position();
+        for read in 0..count {
+            let offset = self.decoder.position();
+            if let Err(e) = self.decoder.read_typed_array_element(element_type_code) {
+                let mut header = [0u8; 10];
+                let n = leb128_encode(read as u64, &mut header);
+                self.out.push(element_type_code);
+                self.out.extend_from_slice(&header[..n]);
+                self.out.extend_from_slice(&self.data[elements_start..offset]);
+                self.complete(0, false);
+                return Err((offset, e.into()));
+            }
+        }
+        self.decoder.end_typed_array().map_err(|e| (start, e.into()))?;
+        self.copy(start);
+        Ok(())
+    }
+
+    /// Close every open container, dropping keys that lost their value and
+    /// filling record instances' missing values with null.
+    fn close_containers(&mut self) {
+        while let Some(frame) = self.stack.pop() {
+            match frame {
+                Frame::Object { key_start: Some(key) } => self.out.truncate(key),
+                Frame::Record { remaining } => self.out.resize(self.out.len() + remaining, type_code::NULL),
+                Frame::Array | Frame::Object { key_start: None } => {}
+            }
+            self.out.push(type_code::CONTAINER_END);
+            self.report.closed_containers += 1;
+            self.complete(0, false);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+// ABOUTME: Unit tests for the repair module.
+// ABOUTME: Tests truncated documents, invalid UTF-8 strings, trailing garbage, and unsalvageable input.
+
+use crate::{
+    bonjson, decode_value, decode_value_with_config, encode_value, repair, to_vec_with_config, DecoderConfig, Error,
+    RecordMismatchMode, RepairOptions, SerializerConfig, Utf8Repair, Value,
+};
+use serde::Serialize;
+
+fn repaired(data: &[u8]) -> (Value, crate::RepairReport) {
+    let (fixed, report) = repair(data, RepairOptions::default());
+    (decode_value(&fixed).unwrap(), report)
+}
+
+#[derive(Serialize)]
+struct Point {
+    x: i32,
+    y: i32,
+    label: String,
+}
+
+#[test]
+fn test_repair_clean_document() {
+    let bytes = encode_value(&bonjson!({"a": [1, 2.5, "three"], "b": {"c": null}})).unwrap();
+    let (fixed, report) = repair(&bytes, RepairOptions::default());
+    assert_eq!(fixed, bytes);
+    assert!(report.is_clean());
+}
+
+#[test]
+fn test_repair_every_truncation() {
+    let points: Vec<Point> = (0..3).map(|i| Point { x: i, y: -i, label: format!("p{i}") }).collect();
+    let config = SerializerConfig { records: true, ..Default::default() };
+    let bytes = to_vec_with_config(&(points, vec![1.5f64, 2.5], "tail"), &config).unwrap();
+
+    // Every prefix repairs to something that decodes, even under strict record matching
+    let strict = DecoderConfig { record_mismatch: RecordMismatchMode::Error, ..Default::default() };
+    for len in 0..bytes.len() {
+        let options = RepairOptions { decoder_config: strict.clone(), ..Default::default() };
+        let (fixed, report) = repair(&bytes[..len], options);
+        assert!(decode_value_with_config(&fixed, strict.clone()).is_ok(), "prefix of {len} bytes: {fixed:02x?}");
+        assert_eq!(report.discarded_from.map(|offset| offset <= len), Some(true));
+        assert_eq!(report.error, Some(Error::Truncated), "prefix of {len} bytes");
+    }
+}
+
+#[test]
+fn test_repair_truncated_containers() {
+    let bytes = encode_value(&bonjson!({"a": 1, "b": [true, {"c": "d"}]})).unwrap();
+
+    // Cut inside the innermost key: the key is dropped and every container closed
+    let cut = bytes.len() - 6;
+    let (value, report) = repaired(&bytes[..cut]);
+    let empty = Value::Object(crate::Map::new());
+    assert_eq!(value, bonjson!({"a": 1, "b": [true, empty]}));
+    assert_eq!(report.closed_containers, 3);
+    assert_eq!(report.discarded_from, Some(cut - 1));
+
+    // A key whose value never arrived goes too
+    let bytes = encode_value(&bonjson!({"a": 1, "b": "two"})).unwrap();
+    let (value, _) = repaired(&bytes[..bytes.len() - 2]);
+    assert_eq!(value, bonjson!({"a": 1}));
+
+    // A typed array keeps its complete elements
+    let bytes = crate::to_vec(&vec![0.1f64, 0.2, 0.3]).unwrap();
+    assert_eq!(bytes[0], crate::type_code::TYPED_ARRAY_FLOAT64);
+    let (value, report) = repaired(&bytes[..bytes.len() - 4]);
+    assert_eq!(value, bonjson!([0.1, 0.2]));
+    assert_eq!(report.discarded_from, Some(bytes.len() - 8));
+    assert_eq!(report.closed_containers, 0);
+
+    // Missing record values are filled with null
+    let config = SerializerConfig { records: true, ..Default::default() };
+    let points = vec![Point { x: 1, y: 2, label: "a".into() }, Point { x: 3, y: 4, label: "b".into() }];
+    let bytes = to_vec_with_config(&points, &config).unwrap();
+    let (value, _) = repaired(&bytes[..bytes.len() - 4]);
+    assert_eq!(value, bonjson!([{"x": 1, "y": 2, "label": "a"}, {"x": 3, "y": 4, "label": null}]));
+}
+
+#[test]
+fn test_repair_damage_and_trailing_bytes() {
+    // An invalid type code cuts the document there
+    let bytes = [0xb7, 0x01, 0x02, 0xc0, 0x03, 0xb6];
+    let (value, report) = repaired(&bytes);
+    assert_eq!(value, bonjson!([1, 2]));
+    assert_eq!(report.discarded_from, Some(3));
+    assert_eq!(report.error, Some(Error::InvalidTypeCode(0xc0)));
+
+    // A non-string key too
+    let (value, report) = repaired(&[0xb8, 0x66, b'a', 0x01, 0x02, 0x03, 0xb6]);
+    assert_eq!(value, bonjson!({"a": 1}));
+    assert_eq!(report.error, Some(Error::InvalidObjectKey));
+
+    let mut bytes = encode_value(&bonjson!([1, 2])).unwrap();
+    let len = bytes.len();
+    bytes.extend_from_slice(&[0xde, 0xad]);
+    let (fixed, report) = repair(&bytes, RepairOptions::default());
+    assert_eq!(fixed, bytes[..len]);
+    assert_eq!(report.trailing_bytes, 2);
+    assert_eq!(report.discarded_from, None);
+
+    // Nothing salvageable
+    for data in [&[][..], &[0xc0], &[0xb9, 0x66]] {
+        let (fixed, report) = repair(data, RepairOptions::default());
+        assert_eq!(fixed, [crate::type_code::NULL]);
+        assert!(report.root_replaced);
+    }
+}
+
+#[test]
+fn test_repair_invalid_utf8() {
+    // {"k": "a\xffb", "\xff": 1, "l": ["x", "\xff", 2]}
+    let bytes = [
+        0xb8, 0x66, b'k', 0x68, b'a', 0xff, b'b', 0x66, 0xff, 0x01, 0x66, b'l', 0xb7, 0x66, b'x', 0x66, 0xff, 0x02,
+        0xb6, 0xb6,
+    ];
+    assert_eq!(decode_value(&bytes).unwrap_err(), Error::InvalidUtf8);
+
+    let (value, report) = repaired(&bytes);
+    assert_eq!(value, bonjson!({"k": "a\u{fffd}b", "\u{fffd}": 1, "l": ["x", "\u{fffd}", 2]}));
+    assert_eq!((report.replaced_strings, report.dropped_strings), (3, 0));
+    assert_eq!(report.discarded_from, None);
+
+    let options = RepairOptions { invalid_utf8: Utf8Repair::Drop, ..Default::default() };
+    let (fixed, report) = repair(&bytes, options.clone());
+    assert_eq!(decode_value(&fixed).unwrap(), bonjson!({"l": ["x", 2]}));
+    assert_eq!(report.dropped_strings, 3);
+
+    // Positional values become null
+    let (fixed, _) = repair(&[0x66, 0xff], options);
+    assert_eq!(fixed, [crate::type_code::NULL]);
+}
@@ -1,12 +1,15 @@
 // ABOUTME: Serde Serializer implementation for BONJSON encoding.
 // ABOUTME: Supports typed arrays (buffered sequences) and records (two-pass struct optimization).
 
-use crate::encoder::{self, Encoder};
+use crate::compat::CapabilitySet;
+use crate::decoder::{NanInfinityMode, UnicodeNormalization};
+use crate::encoder::{self, DuplicateKeys, Encoder};
 use crate::error::{Error, Result};
-use crate::types::type_code;
+use crate::types::{length_field_size, type_code, BigNumber, BIG_NUMBER_TOKEN};
 use serde::ser::{self, Serialize};
-use std::collections::HashMap;
-use std::io::Write;
+use std::collections::{HashMap, HashSet};
+use crate::backend::BonjsonWrite;
+use std::ops::Range;
 
 /// Configuration for the serde serializer.
 #[derive(Debug, Clone)]
@@ -19,6 +22,81 @@ pub struct SerializerConfig {
     /// When enabled, requires a two-pass traversal: the first pass counts struct
     /// types, the second pass emits record definitions for structs appearing 2+ times.
     pub records: bool,
+    /// Pre-registered record definitions as (struct name, field names) pairs (default: empty).
+    /// When non-empty, these definitions are written up front and the counting pass
+    /// is skipped entirely; `records` has no further effect. Structs whose names
+    /// aren't listed are written as regular objects. Field names must match the
+    /// struct's serialized fields in order, or serialization fails.
+    pub record_definitions: Vec<(&'static str, Vec<&'static str>)>,
+    /// Omit struct fields whose value serializes as null, such as `None` (default: false).
+    /// Equivalent to `#[serde(skip_serializing_if = "Option::is_none")]` on every field.
+    /// Record instances are positional, so the counting pass never turns a struct
+    /// into a record if any instance has a null field; pre-registered record
+    /// definitions still write such fields as null.
+    pub skip_null_struct_fields: bool,
+    /// How to choose between typed and regular arrays for homogeneous numeric
+    /// sequences (default: SizeOptimal). Has no effect when `typed_arrays` is false.
+    pub typed_array_strategy: TypedArrayStrategy,
+    /// Sequences with fewer elements than this are always regular arrays (default: 0).
+    /// When the sequence reports its length up front, shorter ones skip buffering entirely.
+    pub typed_array_min_len: usize,
+    /// Maximum bytes of element data buffered while probing a sequence (default: unlimited).
+    /// A sequence that exceeds the budget is written as a regular array, streaming
+    /// the buffered elements out and the rest as they arrive.
+    pub typed_array_max_buffer_bytes: usize,
+    /// Encode sequences of identical structs whose fields are all numbers as
+    /// an object of typed arrays, one per field (default: false). For example
+    /// `[{"x":1,"y":2},{"x":3,"y":4}]` becomes `{"x":[1,3],"y":[2,4]}`, with
+    /// each column a typed array. The deserializer reassembles the rows when
+    /// it expects a sequence and finds such an object.
+    ///
+    /// Only that serde path reads the output row by row. Everything that walks
+    /// the encoded document sees the object of columns: `decode_value`,
+    /// [`LazyValue`](crate::LazyValue), JSONPath queries (`$[*]` selects the
+    /// columns), [`for_each_element`](crate::for_each_element) and
+    /// [`ReaderDeserializer::array_elements`](crate::ReaderDeserializer::array_elements),
+    /// which fail because the root isn't an array. Leave this off for
+    /// documents that are read that way.
+    ///
+    /// Follows the typed array settings: there are no columns when typed
+    /// arrays are off, the row count is subject to `typed_array_min_len`, the
+    /// buffered columns to `typed_array_max_buffer_bytes`, and under
+    /// `SizeOptimal` the columns must encode smaller than the objects (or,
+    /// with `records`, than the record instances they would become).
+    pub columnar_structs: bool,
+    /// Optional format features the output may use (default: all). Excluding
+    /// records or typed arrays overrides `records`, `record_definitions` and
+    /// `typed_arrays`; values that need an excluded BigNumber or long string
+    /// fail with `Error::UnsupportedCapability`.
+    pub capabilities: CapabilitySet,
+    /// Emit map entries sorted by key (default: false), so that maps with a random
+    /// iteration order such as `HashMap` always encode to the same bytes.
+    /// Each map's entries are buffered until the map ends. Applies to maps only;
+    /// struct fields keep their declaration order.
+    pub sort_map_keys: bool,
+    /// Whether a map may produce the same key twice, e.g. from distinct keys
+    /// that serialize to the same string (default: Allow). See
+    /// [`EncoderConfig::duplicate_keys`](crate::EncoderConfig::duplicate_keys).
+    pub duplicate_keys: DuplicateKeys,
+    /// Normalization applied to keys before the duplicate check (default: None).
+    /// See [`EncoderConfig::key_normalization`](crate::EncoderConfig::key_normalization).
+    pub key_normalization: UnicodeNormalization,
+    /// Append a checksum trailer to the document (default: false).
+    /// See [`EncoderConfig::checksum_trailer`](crate::EncoderConfig::checksum_trailer).
+    pub checksum_trailer: bool,
+    /// How to write NaN and Infinity (default: Reject).
+    /// See [`EncoderConfig::nan_infinity_mode`](crate::EncoderConfig::nan_infinity_mode).
+    pub nan_infinity_mode: NanInfinityMode,
+    /// Write map keys that can't be object keys, such as `Vec<u8>`, tuples,
+    /// floats and bools, as the text of their value (default: false):
+    /// `[1, 2]`, `1.5`, `true`. Lossy, since the text doesn't deserialize back
+    /// into the key type. Without it such keys fail with
+    /// `Error::UnsupportedMapKey`.
+    pub stringify_map_keys: bool,
+    /// Receives [`CodecMetrics`](crate::metrics::CodecMetrics) when a top-level
+    /// serialize call finishes (default: None).
+    #[cfg(feature = "tracing")]
+    pub metrics_sink: Option<std::sync::Arc<dyn crate::metrics::MetricsSink>>,
 }
 
 impl Default for SerializerConfig {
@@ -26,26 +104,80 @@ impl Default for SerializerConfig {
         Self {
             typed_arrays: true,
             records: false,
+            record_definitions: Vec::new(),
+            skip_null_struct_fields: false,
+            typed_array_strategy: TypedArrayStrategy::default(),
+            typed_array_min_len: 0,
+            typed_array_max_buffer_bytes: usize::MAX,
+            columnar_structs: false,
+            capabilities: CapabilitySet::ALL,
+            sort_map_keys: false,
+            duplicate_keys: DuplicateKeys::default(),
+            key_normalization: UnicodeNormalization::default(),
+            checksum_trailer: false,
+            nan_infinity_mode: NanInfinityMode::default(),
+            stringify_map_keys: false,
+            #[cfg(feature = "tracing")]
+            metrics_sink: None,
         }
     }
 }
 
+impl SerializerConfig {
+    config_setters! {
+        typed_arrays: bool,
+        records: bool,
+        record_definitions: Vec<(&'static str, Vec<&'static str>)>,
+        skip_null_struct_fields: bool,
+        typed_array_strategy: TypedArrayStrategy,
+        typed_array_min_len: usize,
+        typed_array_max_buffer_bytes: usize,
+        columnar_structs: bool,
+        capabilities: CapabilitySet,
+        sort_map_keys: bool,
+        duplicate_keys: DuplicateKeys,
+        key_normalization: UnicodeNormalization,
+        checksum_trailer: bool,
+        nan_infinity_mode: NanInfinityMode,
+        stringify_map_keys: bool,
+    }
+    config_setters! { Some:
+        #[cfg(feature = "tracing")]
+        metrics_sink: std::sync::Arc<dyn crate::metrics::MetricsSink>,
+    }
+}
+
+/// How the serializer decides whether a homogeneous numeric sequence becomes a typed array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TypedArrayStrategy {
+    /// Never emit typed arrays; sequences stream as regular arrays without buffering.
+    Never,
+    /// Emit a typed array only when it encodes smaller than the regular array (default).
+    #[default]
+    SizeOptimal,
+    /// Emit a typed array whenever every element has the same numeric type.
+    Always,
+}
+
 /// A serde Serializer that writes BONJSON.
-pub struct Serializer<'a, W: Write> {
+pub struct Serializer<'a, W: BonjsonWrite> {
     encoder: &'a mut Encoder<W>,
     config: SerializerConfig,
     /// Record definitions for the serde path: struct_name → (keys, def_index).
     /// Populated by the two-pass record detection when `config.records` is true.
     record_defs: Option<HashMap<&'static str, (Vec<&'static str>, usize)>>,
+    /// Keys of each record definition, indexed by definition index.
+    record_keys: Vec<Vec<&'static str>>,
 }
 
-impl<'a, W: Write> Serializer<'a, W> {
+impl<'a, W: BonjsonWrite> Serializer<'a, W> {
     /// Create a new Serializer wrapping an Encoder.
     pub fn new(encoder: &'a mut Encoder<W>) -> Self {
         Self {
             encoder,
             config: SerializerConfig::default(),
             record_defs: None,
+            record_keys: Vec::new(),
         }
     }
 
@@ -55,87 +187,110 @@ impl<'a, W: Write> Serializer<'a, W> {
         config: SerializerConfig,
         record_defs: Option<HashMap<&'static str, (Vec<&'static str>, usize)>>,
     ) -> Self {
+        let mut record_keys = Vec::new();
+        if let Some(ref defs) = record_defs {
+            let len = defs.values().map(|(_, def_index)| def_index + 1).max().unwrap_or(0);
+            record_keys.resize(len, Vec::new());
+            for (keys, def_index) in defs.values() {
+                record_keys[*def_index].clone_from(keys);
+            }
+        }
         Self {
             encoder,
             config,
             record_defs,
+            record_keys,
         }
     }
 }
 
-impl<'a, 'b, W: Write> ser::Serializer for &'a mut Serializer<'b, W> {
+impl<'a, 'b, W: BonjsonWrite> ser::Serializer for &'a mut Serializer<'b, W> {
     type Ok = ();
     type Error = Error;
     type SerializeSeq = BufferedSeqSerializer<'a, 'b, W>;
     type SerializeTuple = &'a mut Serializer<'b, W>;
     type SerializeTupleStruct = &'a mut Serializer<'b, W>;
     type SerializeTupleVariant = &'a mut Serializer<'b, W>;
-    type SerializeMap = &'a mut Serializer<'b, W>;
+    type SerializeMap = MapSerializer<'a, 'b, W>;
     type SerializeStruct = StructSerializer<'a, 'b, W>;
     type SerializeStructVariant = &'a mut Serializer<'b, W>;
 
     fn serialize_bool(self, v: bool) -> Result<()> {
-        self.encoder.write_bool_unchecked(v)
+        Ok(self.encoder.write_bool_unchecked(v)?)
     }
 
     fn serialize_i8(self, v: i8) -> Result<()> {
-        self.encoder.write_i64_unchecked(i64::from(v))
+        Ok(self.encoder.write_i64_unchecked(i64::from(v))?)
     }
 
     fn serialize_i16(self, v: i16) -> Result<()> {
-        self.encoder.write_i64_unchecked(i64::from(v))
+        Ok(self.encoder.write_i64_unchecked(i64::from(v))?)
     }
 
     fn serialize_i32(self, v: i32) -> Result<()> {
-        self.encoder.write_i64_unchecked(i64::from(v))
+        Ok(self.encoder.write_i64_unchecked(i64::from(v))?)
     }
 
     fn serialize_i64(self, v: i64) -> Result<()> {
-        self.encoder.write_i64_unchecked(v)
+        Ok(self.encoder.write_i64_unchecked(v)?)
     }
 
     fn serialize_u8(self, v: u8) -> Result<()> {
-        self.encoder.write_u64_unchecked(u64::from(v))
+        Ok(self.encoder.write_u64_unchecked(u64::from(v))?)
     }
 
     fn serialize_u16(self, v: u16) -> Result<()> {
-        self.encoder.write_u64_unchecked(u64::from(v))
+        Ok(self.encoder.write_u64_unchecked(u64::from(v))?)
     }
 
     fn serialize_u32(self, v: u32) -> Result<()> {
-        self.encoder.write_u64_unchecked(u64::from(v))
+        Ok(self.encoder.write_u64_unchecked(u64::from(v))?)
     }
 
     fn serialize_u64(self, v: u64) -> Result<()> {
-        self.encoder.write_u64_unchecked(v)
+        Ok(self.encoder.write_u64_unchecked(v)?)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        Ok(self.encoder.write_i128_unchecked(v)?)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        Ok(self.encoder.write_u128_unchecked(v)?)
     }
 
     fn serialize_f32(self, v: f32) -> Result<()> {
-        self.encoder.write_f32_unchecked(v)
+        Ok(self.encoder.write_f32_unchecked(v)?)
     }
 
     fn serialize_f64(self, v: f64) -> Result<()> {
-        self.encoder.write_f64_unchecked(v)
+        Ok(self.encoder.write_f64_unchecked(v)?)
     }
 
     fn serialize_char(self, v: char) -> Result<()> {
         let mut buf = [0u8; 4];
         let s = v.encode_utf8(&mut buf);
-        self.encoder.write_str_unchecked(s)
+        Ok(self.encoder.write_str_unchecked(s)?)
     }
 
     fn serialize_str(self, v: &str) -> Result<()> {
-        self.encoder.write_str_unchecked(v)
+        Ok(self.encoder.write_str_unchecked(v)?)
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
-        // Always emit as typed uint8 array — equal or better than regular array
-        self.encoder
-            .write_typed_array_raw_unchecked(type_code::TYPED_ARRAY_UINT8, v.len(), v)
+        if !self.config.capabilities.typed_arrays {
+            self.encoder.begin_array_unchecked()?;
+            for &b in v {
+                self.encoder.write_u64_unchecked(u64::from(b))?;
+            }
+            return Ok(self.encoder.end_container_unchecked()?);
+        }
+        // Emit as typed uint8 array — equal or better than regular array
+        Ok(self.encoder.write_typed_array_raw_unchecked(type_code::TYPED_ARRAY_UINT8, v.len(), v)?)
     }
 
     fn serialize_none(self) -> Result<()> {
-        self.encoder.write_null_unchecked()
+        Ok(self.encoder.write_null_unchecked()?)
     }
 
     fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
@@ -143,11 +298,11 @@ impl<'a, 'b, W: Write> ser::Serializer for &'a mut Serializer<'b, W> {
     }
 
     fn serialize_unit(self) -> Result<()> {
-        self.encoder.write_null_unchecked()
+        Ok(self.encoder.write_null_unchecked()?)
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
-        self.encoder.write_null_unchecked()
+        Ok(self.encoder.write_null_unchecked()?)
     }
 
     fn serialize_unit_variant(
@@ -156,14 +311,21 @@ impl<'a, 'b, W: Write> ser::Serializer for &'a mut Serializer<'b, W> {
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<()> {
-        self.encoder.write_str_unchecked(variant)
+        Ok(self.encoder.write_str_unchecked(variant)?)
     }
 
     fn serialize_newtype_struct<T: ?Sized + Serialize>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<()> {
+        #[cfg(feature = "half")]
+        if let Some(f) = half_float_value(name, value) {
+            return self.serialize_f32(f);
+        }
+        if name == BIG_NUMBER_TOKEN {
+            return Ok(self.encoder.write_big_number_unchecked(value.serialize(BigNumberProbe)?)?);
+        }
         value.serialize(self)
     }
 
@@ -177,11 +339,15 @@ impl<'a, 'b, W: Write> ser::Serializer for &'a mut Serializer<'b, W> {
         self.encoder.begin_object_unchecked()?;
         self.encoder.write_str_unchecked(variant)?;
         value.serialize(&mut *self)?;
-        self.encoder.end_container_unchecked()
+        Ok(self.encoder.end_container_unchecked()?)
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
-        if self.config.typed_arrays {
+        let probe = self.config.typed_arrays
+            && self.config.capabilities.typed_arrays
+            && self.config.typed_array_strategy != TypedArrayStrategy::Never
+            && len.is_none_or(|len| len >= self.config.typed_array_min_len);
+        if probe {
             Ok(BufferedSeqSerializer::new_probing(self, len))
         } else {
             self.encoder.begin_array_unchecked()?;
@@ -217,8 +383,11 @@ impl<'a, 'b, W: Write> ser::Serializer for &'a mut Serializer<'b, W> {
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        if self.config.sort_map_keys {
+            return Ok(MapSerializer::Sorted { ser: self, buffer: Vec::new(), entries: Vec::new() });
+        }
         self.encoder.begin_object_unchecked()?;
-        Ok(self)
+        Ok(MapSerializer::Direct(self))
     }
 
     fn serialize_struct(
@@ -229,8 +398,9 @@ impl<'a, 'b, W: Write> ser::Serializer for &'a mut Serializer<'b, W> {
         // Check if this struct has a record definition
         if let Some(ref defs) = self.record_defs {
             if let Some((_, def_index)) = defs.get(name) {
-                self.encoder.begin_record_instance_unchecked(*def_index)?;
-                return Ok(StructSerializer::Record(self));
+                let def_index = *def_index;
+                self.encoder.begin_record_instance_unchecked(def_index)?;
+                return Ok(StructSerializer::Record { ser: self, name, def_index, field_index: 0 });
             }
         }
         self.encoder.begin_object_unchecked()?;
@@ -308,24 +478,27 @@ enum SeqMode {
         /// Accumulated regular encoding size (sum of each element's optimal size).
         regular_size: usize,
     },
+    /// Buffering structs, checking if they can be written as columns.
+    Columnar(Columns),
     /// Fell back to streaming regular array.
     Regular,
 }
 
 /// Serializer for sequences that probes for typed array optimization.
-pub struct BufferedSeqSerializer<'a, 'b, W: Write> {
+pub struct BufferedSeqSerializer<'a, 'b, W: BonjsonWrite> {
     ser: &'a mut Serializer<'b, W>,
     mode: SeqMode,
 }
 
-impl<'a, 'b, W: Write> BufferedSeqSerializer<'a, 'b, W> {
+impl<'a, 'b, W: BonjsonWrite> BufferedSeqSerializer<'a, 'b, W> {
     fn new_probing(ser: &'a mut Serializer<'b, W>, len: Option<usize>) -> Self {
-        let capacity = len.unwrap_or(0);
+        let capacity = len.unwrap_or(0).saturating_mul(4); // reasonable estimate
+        let capacity = capacity.min(ser.config.typed_array_max_buffer_bytes);
         Self {
             ser,
             mode: SeqMode::Probing {
                 kind: None,
-                data: Vec::with_capacity(capacity * 4), // reasonable estimate
+                data: Vec::with_capacity(capacity),
                 count: 0,
                 regular_size: 0,
             },
@@ -341,27 +514,27 @@ impl<'a, 'b, W: Write> BufferedSeqSerializer<'a, 'b, W> {
 
     /// Flush buffered probing data as a regular array.
     fn flush_as_regular(&mut self) -> Result<()> {
-        if let SeqMode::Probing {
-            kind,
-            ref data,
-            count,
-            ..
-        } = self.mode
-        {
-            self.ser.encoder.begin_array_unchecked()?;
-            if count > 0 {
-                if let Some(k) = kind {
-                    write_buffered_elements(self.ser.encoder, k, data, count)?;
+        match std::mem::replace(&mut self.mode, SeqMode::Regular) {
+            SeqMode::Probing { kind, data, count, .. } => {
+                self.ser.encoder.begin_array_unchecked()?;
+                if count > 0 {
+                    if let Some(k) = kind {
+                        write_buffered_elements(self.ser.encoder, k, &data, count)?;
+                    }
                 }
             }
+            SeqMode::Columnar(columns) => {
+                self.ser.encoder.begin_array_unchecked()?;
+                columns.write_rows(self.ser)?;
+            }
+            SeqMode::Regular => {}
         }
-        self.mode = SeqMode::Regular;
         Ok(())
     }
 }
 
 /// Write buffered element data back as regular (non-typed-array) values.
-fn write_buffered_elements<W: Write>(
+fn write_buffered_elements<W: BonjsonWrite>(
     encoder: &mut Encoder<W>,
     kind: ElementKind,
     data: &[u8],
@@ -415,16 +588,7 @@ fn write_buffered_elements<W: Write>(
     Ok(())
 }
 
-/// Compute the LEB128 encoded size of a value.
-fn leb128_size(value: u64) -> usize {
-    if value == 0 {
-        return 1;
-    }
-    let bits = 64 - value.leading_zeros() as usize;
-    (bits + 6) / 7
-}
-
-impl<W: Write> ser::SerializeSeq for BufferedSeqSerializer<'_, '_, W> {
+impl<W: BonjsonWrite> ser::SerializeSeq for BufferedSeqSerializer<'_, '_, W> {
     type Ok = ();
     type Error = Error;
 
@@ -433,7 +597,19 @@ impl<W: Write> ser::SerializeSeq for BufferedSeqSerializer<'_, '_, W> {
             SeqMode::Regular => {
                 value.serialize(&mut *self.ser)
             }
-            SeqMode::Probing { .. } => {
+            SeqMode::Columnar(ref mut columns) => {
+                if let Some(row) = capture_row(value)? {
+                    let budget = self.ser.config.typed_array_max_buffer_bytes;
+                    if columns.matches(&row) && columns.buffered_bytes() + row.bytes() <= budget {
+                        columns.push(row);
+                        return Ok(());
+                    }
+                }
+                // A different shape, or over budget — flush and stream the rest
+                self.flush_as_regular()?;
+                value.serialize(&mut *self.ser)
+            }
+            SeqMode::Probing { count, .. } => {
                 // Use the element serializer to capture the element
                 let mut elem_ser = SeqElementSerializer {
                     result: None,
@@ -449,7 +625,12 @@ impl<W: Write> ser::SerializeSeq for BufferedSeqSerializer<'_, '_, W> {
                             unreachable!()
                         };
 
-                        if count == 0 {
+                        let buffered = if let SeqMode::Probing { data, .. } = &self.mode { data.len() } else { 0 };
+                        if buffered + raw_bytes.len() > self.ser.config.typed_array_max_buffer_bytes {
+                            // Over budget — flush and stream the rest
+                            self.flush_as_regular()?;
+                            value.serialize(&mut *self.ser)?;
+                        } else if count == 0 {
                             // First element establishes the type
                             if let SeqMode::Probing {
                                 kind: ref mut k,
@@ -484,6 +665,15 @@ impl<W: Write> ser::SerializeSeq for BufferedSeqSerializer<'_, '_, W> {
                         }
                     }
                     None => {
+                        // A first element that is a struct of numbers may start columns
+                        if count == 0 && self.ser.config.columnar_structs {
+                            if let Some(row) = capture_row(value)? {
+                                if row.bytes() <= self.ser.config.typed_array_max_buffer_bytes {
+                                    self.mode = SeqMode::Columnar(Columns::new(row));
+                                    return Ok(());
+                                }
+                            }
+                        }
                         // Non-numeric element — flush and fall back
                         self.flush_as_regular()?;
                         value.serialize(&mut *self.ser)?;
@@ -497,7 +687,16 @@ impl<W: Write> ser::SerializeSeq for BufferedSeqSerializer<'_, '_, W> {
     fn end(self) -> Result<()> {
         match self.mode {
             SeqMode::Regular => {
-                self.ser.encoder.end_container_unchecked()
+                Ok(self.ser.encoder.end_container_unchecked()?)
+            }
+            SeqMode::Columnar(ref columns) => {
+                if columns.use_columns(&self.ser.config) {
+                    columns.write_columns(self.ser.encoder)
+                } else {
+                    self.ser.encoder.begin_array_unchecked()?;
+                    columns.write_rows(self.ser)?;
+                    Ok(self.ser.encoder.end_container_unchecked()?)
+                }
             }
             SeqMode::Probing {
                 kind,
@@ -508,38 +707,419 @@ impl<W: Write> ser::SerializeSeq for BufferedSeqSerializer<'_, '_, W> {
                 if count == 0 || kind.is_none() {
                     // Empty sequence or no elements buffered — emit empty regular array
                     self.ser.encoder.begin_array_unchecked()?;
-                    return self.ser.encoder.end_container_unchecked();
+                    return Ok(self.ser.encoder.end_container_unchecked()?);
                 }
 
                 let k = kind.unwrap();
+                let config = &self.ser.config;
+                let use_typed = if count < config.typed_array_min_len {
+                    false
+                } else if config.typed_array_strategy == TypedArrayStrategy::Always {
+                    true
+                } else {
+                    // Compute typed array size:
+                    // type_code (1) + LEB128(count) + count * elem_size
+                    let typed_size =
+                        1 + length_field_size(count as u64) + count * k.element_size();
+
+                    // Compute regular array size:
+                    // ARRAY marker (1) + sum of element sizes + CONTAINER_END (1)
+                    let regular_total = 1 + regular_size + 1;
+                    typed_size < regular_total
+                };
 
-                // Compute typed array size:
-                // type_code (1) + LEB128(count) + count * elem_size
-                let typed_size =
-                    1 + leb128_size(count as u64) + count * k.element_size();
-
-                // Compute regular array size:
-                // ARRAY marker (1) + sum of element sizes + CONTAINER_END (1)
-                let regular_total = 1 + regular_size + 1;
-
-                if typed_size < regular_total {
+                if use_typed {
                     // Emit typed array
-                    self.ser.encoder.write_typed_array_raw_unchecked(
-                        k.typed_array_code(),
-                        count,
-                        data,
-                    )
+                    Ok(self.ser.encoder.write_typed_array_raw_unchecked(k.typed_array_code(), count, data)?)
                 } else {
-                    // Regular is smaller or equal — emit regular array
+                    // Regular is smaller or equal, or the sequence is too short
                     self.ser.encoder.begin_array_unchecked()?;
                     write_buffered_elements(self.ser.encoder, k, data, count)?;
-                    self.ser.encoder.end_container_unchecked()
+                    Ok(self.ser.encoder.end_container_unchecked()?)
                 }
             }
         }
     }
 }
 
+// =============================================================================
+// Columns — sequences of numeric structs as objects of typed arrays
+// =============================================================================
+
+/// A struct captured while probing for columns: its name, and per field the
+/// key, element kind, raw LE bytes and regular encoding size.
+struct Row {
+    name: &'static str,
+    fields: Vec<(&'static str, ElementKind, Vec<u8>, usize)>,
+}
+
+impl Row {
+    /// Bytes of element data the row adds to the columns.
+    fn bytes(&self) -> usize {
+        self.fields.iter().map(|(_, _, bytes, _)| bytes.len()).sum()
+    }
+}
+
+/// Capture a struct whose fields would each fit a typed array. Anything else,
+/// including a struct with no fields, gives None.
+fn capture_row<T: ?Sized + Serialize>(value: &T) -> Result<Option<Row>> {
+    let mut capture = RowCapture { row: None, rejected: false };
+    value.serialize(&mut capture)?;
+    if capture.rejected {
+        return Ok(None);
+    }
+    Ok(capture.row.filter(|row| !row.fields.is_empty()))
+}
+
+/// Structs buffered while a sequence could still be written as columns: one
+/// typed array's worth of raw LE bytes per field.
+struct Columns {
+    name: &'static str,
+    keys: Vec<&'static str>,
+    kinds: Vec<ElementKind>,
+    data: Vec<Vec<u8>>,
+    rows: usize,
+    /// Accumulated regular encoding size of the rows as objects.
+    regular_size: usize,
+}
+
+impl Columns {
+    fn new(row: Row) -> Self {
+        let mut columns = Columns {
+            name: row.name,
+            keys: row.fields.iter().map(|(key, ..)| *key).collect(),
+            kinds: row.fields.iter().map(|(_, kind, ..)| *kind).collect(),
+            data: vec![Vec::new(); row.fields.len()],
+            rows: 0,
+            regular_size: 0,
+        };
+        columns.push(row);
+        columns
+    }
+
+    /// Whether a row is the same struct, with the same fields of the same kinds.
+    fn matches(&self, row: &Row) -> bool {
+        row.name == self.name
+            && row.fields.len() == self.keys.len()
+            && row
+                .fields
+                .iter()
+                .zip(self.keys.iter().zip(&self.kinds))
+                .all(|((key, kind, ..), (k, kd))| key == k && kind == kd)
+    }
+
+    fn push(&mut self, row: Row) {
+        // Object marker and end, then each key and value
+        self.regular_size += 2;
+        for (column, (key, _, bytes, size)) in self.data.iter_mut().zip(row.fields) {
+            column.extend_from_slice(&bytes);
+            self.regular_size += encoder::str_encoding_size(key.len()) + size;
+        }
+        self.rows += 1;
+    }
+
+    fn buffered_bytes(&self) -> usize {
+        self.data.iter().map(Vec::len).sum()
+    }
+
+    /// Size of the rows written as an object of typed arrays.
+    fn columnar_size(&self) -> usize {
+        let header = 1 + length_field_size(self.rows as u64);
+        let columns: usize = self
+            .keys
+            .iter()
+            .zip(&self.kinds)
+            .map(|(key, kind)| encoder::str_encoding_size(key.len()) + header + self.rows * kind.element_size())
+            .sum();
+        2 + columns
+    }
+
+    /// Size of the rows written as an array of objects.
+    fn regular_total(&self) -> usize {
+        2 + self.regular_size
+    }
+
+    /// Size of the rows written as an array of record instances, including
+    /// their definition. The definition index is taken to fit one byte, since
+    /// neither pass knows it when deciding.
+    fn record_total(&self) -> usize {
+        let keys_size: usize = self.keys.iter().map(|key| encoder::str_encoding_size(key.len())).sum();
+        let values_size = self.regular_size - self.rows * (2 + keys_size);
+        2 + (2 + keys_size) + self.rows * 3 + values_size
+    }
+
+    /// Whether to write columns rather than an array of objects, by the same
+    /// rules as a typed array. With records enabled the rows would become
+    /// record instances, so the columns have to beat those instead.
+    fn use_columns(&self, config: &SerializerConfig) -> bool {
+        if self.rows < config.typed_array_min_len {
+            false
+        } else if config.typed_array_strategy == TypedArrayStrategy::Always {
+            true
+        } else if config.records && config.capabilities.records {
+            self.columnar_size() < self.record_total().min(self.regular_total())
+        } else {
+            self.columnar_size() < self.regular_total()
+        }
+    }
+
+    fn write_columns<W: BonjsonWrite>(&self, encoder: &mut Encoder<W>) -> Result<()> {
+        encoder.begin_object_unchecked()?;
+        for ((key, kind), data) in self.keys.iter().zip(&self.kinds).zip(&self.data) {
+            encoder.write_key_unchecked(key)?;
+            encoder.write_typed_array_raw_unchecked(kind.typed_array_code(), self.rows, data)?;
+        }
+        Ok(encoder.end_container_unchecked()?)
+    }
+
+    /// Write the rows back as structs, which may become record instances.
+    fn write_rows<W: BonjsonWrite>(&self, serializer: &mut Serializer<'_, W>) -> Result<()> {
+        use ser::SerializeStruct;
+        for row in 0..self.rows {
+            let mut fields = ser::Serializer::serialize_struct(&mut *serializer, self.name, self.keys.len())?;
+            for (index, key) in self.keys.iter().enumerate() {
+                let kind = self.kinds[index];
+                let size = kind.element_size();
+                let bytes = &self.data[index][row * size..(row + 1) * size];
+                fields.serialize_field(key, &BufferedNumber { kind, bytes })?;
+            }
+            fields.end()?;
+        }
+        Ok(())
+    }
+}
+
+/// A buffered typed array element, serialized as the number it was captured from.
+struct BufferedNumber<'a> {
+    kind: ElementKind,
+    bytes: &'a [u8],
+}
+
+impl Serialize for BufferedNumber<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let b = self.bytes;
+        match self.kind {
+            ElementKind::I8 => serializer.serialize_i8(i8::from_le_bytes([b[0]])),
+            ElementKind::I16 => serializer.serialize_i16(i16::from_le_bytes([b[0], b[1]])),
+            ElementKind::I32 => serializer.serialize_i32(i32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            ElementKind::I64 => serializer.serialize_i64(i64::from_le_bytes(b.try_into().unwrap())),
+            ElementKind::U8 => serializer.serialize_u8(b[0]),
+            ElementKind::U16 => serializer.serialize_u16(u16::from_le_bytes([b[0], b[1]])),
+            ElementKind::U32 => serializer.serialize_u32(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            ElementKind::U64 => serializer.serialize_u64(u64::from_le_bytes(b.try_into().unwrap())),
+            ElementKind::F32 => serializer.serialize_f32(f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            ElementKind::F64 => serializer.serialize_f64(f64::from_le_bytes(b.try_into().unwrap())),
+        }
+    }
+}
+
+/// Internal serializer that captures a struct of numbers for [`capture_row`].
+/// Accepts only a struct, and rejects it if any field isn't a number.
+struct RowCapture {
+    row: Option<Row>,
+    rejected: bool,
+}
+
+impl<'a> ser::Serializer for &'a mut RowCapture {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = NoOpCompound;
+    type SerializeTuple = NoOpCompound;
+    type SerializeTupleStruct = NoOpCompound;
+    type SerializeTupleVariant = NoOpCompound;
+    type SerializeMap = NoOpCompound;
+    type SerializeStruct = &'a mut RowCapture;
+    type SerializeStructVariant = NoOpCompound;
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.row = Some(Row { name, fields: Vec::with_capacity(len) });
+        Ok(self)
+    }
+
+    // Everything else leaves row as None
+    fn serialize_bool(self, _v: bool) -> Result<()> { Ok(()) }
+    fn serialize_i8(self, _v: i8) -> Result<()> { Ok(()) }
+    fn serialize_i16(self, _v: i16) -> Result<()> { Ok(()) }
+    fn serialize_i32(self, _v: i32) -> Result<()> { Ok(()) }
+    fn serialize_i64(self, _v: i64) -> Result<()> { Ok(()) }
+    fn serialize_u8(self, _v: u8) -> Result<()> { Ok(()) }
+    fn serialize_u16(self, _v: u16) -> Result<()> { Ok(()) }
+    fn serialize_u32(self, _v: u32) -> Result<()> { Ok(()) }
+    fn serialize_u64(self, _v: u64) -> Result<()> { Ok(()) }
+    fn serialize_f32(self, _v: f32) -> Result<()> { Ok(()) }
+    fn serialize_f64(self, _v: f64) -> Result<()> { Ok(()) }
+    fn serialize_char(self, _v: char) -> Result<()> { Ok(()) }
+    fn serialize_str(self, _v: &str) -> Result<()> { Ok(()) }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> { Ok(()) }
+    fn serialize_none(self) -> Result<()> { Ok(()) }
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<()> { Ok(()) }
+    fn serialize_unit(self) -> Result<()> { Ok(()) }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> { Ok(()) }
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str) -> Result<()> { Ok(()) }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, _value: &T) -> Result<()> { Ok(()) }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<()> { Ok(()) }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> { Ok(NoOpCompound) }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> { Ok(NoOpCompound) }
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> { Ok(NoOpCompound) }
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant> { Ok(NoOpCompound) }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> { Ok(NoOpCompound) }
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant> { Ok(NoOpCompound) }
+}
+
+impl ser::SerializeStruct for &mut RowCapture {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        if self.rejected {
+            return Ok(());
+        }
+        let mut element = SeqElementSerializer { result: None };
+        value.serialize(&mut element)?;
+        match (element.result, &mut self.row) {
+            (Some((kind, bytes, size)), Some(row)) => row.fields.push((key, kind, bytes, size)),
+            _ => self.rejected = true,
+        }
+        Ok(())
+    }
+
+    fn skip_field(&mut self, _key: &'static str) -> Result<()> {
+        // A skipped field would leave a hole in its column
+        self.rejected = true;
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> { Ok(()) }
+}
+
+/// Recognize `half::f16` and `half::bf16`, which serialize as newtype structs
+/// wrapping their bit patterns, and return the value they represent.
+///
+/// Serde passes on nothing but the struct name and the inner value, so with
+/// the `half` feature the names `f16` and `bf16` are reserved: a newtype
+/// struct of either name wrapping a `u16` is written as that float, whichever
+/// crate defines it. Newtypes of those names around anything else serialize
+/// as usual.
+#[cfg(feature = "half")]
+fn half_float_value<T: ?Sized + Serialize>(name: &'static str, value: &T) -> Option<f32> {
+    if std::any::type_name::<T>() != "u16" {
+        return None;
+    }
+    let to_f32: fn(u16) -> f32 = match name {
+        "f16" => |bits| half::f16::from_bits(bits).to_f32(),
+        "bf16" => |bits| half::bf16::from_bits(bits).to_f32(),
+        _ => return None,
+    };
+    value.serialize(U16Probe).ok().map(to_f32)
+}
+
+/// A serializer that accepts only a `u16`, used to read `half` float bit patterns.
+#[cfg(feature = "half")]
+struct U16Probe;
+
+#[cfg(feature = "half")]
+impl ser::Serializer for U16Probe {
+    type Ok = u16;
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<u16, Error>;
+    type SerializeTuple = ser::Impossible<u16, Error>;
+    type SerializeTupleStruct = ser::Impossible<u16, Error>;
+    type SerializeTupleVariant = ser::Impossible<u16, Error>;
+    type SerializeMap = ser::Impossible<u16, Error>;
+    type SerializeStruct = ser::Impossible<u16, Error>;
+    type SerializeStructVariant = ser::Impossible<u16, Error>;
+
+    fn serialize_u16(self, v: u16) -> Result<u16> { Ok(v) }
+
+    fn serialize_none(self) -> Result<u16> { Err(not_u16()) }
+    fn serialize_unit(self) -> Result<u16> { Err(not_u16()) }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<u16> { Err(not_u16()) }
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<u16> { Err(not_u16()) }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, _value: &T) -> Result<u16> { Err(not_u16()) }
+    fn serialize_bool(self, _v: bool) -> Result<u16> { Err(not_u16()) }
+    fn serialize_i8(self, _v: i8) -> Result<u16> { Err(not_u16()) }
+    fn serialize_i16(self, _v: i16) -> Result<u16> { Err(not_u16()) }
+    fn serialize_i32(self, _v: i32) -> Result<u16> { Err(not_u16()) }
+    fn serialize_i64(self, _v: i64) -> Result<u16> { Err(not_u16()) }
+    fn serialize_i128(self, _v: i128) -> Result<u16> { Err(not_u16()) }
+    fn serialize_u8(self, _v: u8) -> Result<u16> { Err(not_u16()) }
+    fn serialize_u32(self, _v: u32) -> Result<u16> { Err(not_u16()) }
+    fn serialize_u64(self, _v: u64) -> Result<u16> { Err(not_u16()) }
+    fn serialize_u128(self, _v: u128) -> Result<u16> { Err(not_u16()) }
+    fn serialize_f32(self, _v: f32) -> Result<u16> { Err(not_u16()) }
+    fn serialize_f64(self, _v: f64) -> Result<u16> { Err(not_u16()) }
+    fn serialize_char(self, _v: char) -> Result<u16> { Err(not_u16()) }
+    fn serialize_str(self, _v: &str) -> Result<u16> { Err(not_u16()) }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<u16> { Err(not_u16()) }
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str) -> Result<u16> { Err(not_u16()) }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<u16> { Err(not_u16()) }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> { Err(not_u16()) }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> { Err(not_u16()) }
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> { Err(not_u16()) }
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant> { Err(not_u16()) }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> { Err(not_u16()) }
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> { Err(not_u16()) }
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant> { Err(not_u16()) }
+}
+
+/// Early-out used by `U16Probe` for anything but a `u16` (never surfaced to callers).
+#[cfg(feature = "half")]
+fn not_u16() -> Error {
+    Error::Custom(String::new())
+}
+
+/// A serializer that accepts only the decimal string a `BigNumber` serializes
+/// inside its newtype, and parses it back.
+struct BigNumberProbe;
+
+impl ser::Serializer for BigNumberProbe {
+    type Ok = BigNumber;
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<BigNumber, Error>;
+    type SerializeTuple = ser::Impossible<BigNumber, Error>;
+    type SerializeTupleStruct = ser::Impossible<BigNumber, Error>;
+    type SerializeTupleVariant = ser::Impossible<BigNumber, Error>;
+    type SerializeMap = ser::Impossible<BigNumber, Error>;
+    type SerializeStruct = ser::Impossible<BigNumber, Error>;
+    type SerializeStructVariant = ser::Impossible<BigNumber, Error>;
+
+    fn serialize_str(self, v: &str) -> Result<BigNumber> { v.parse() }
+
+    fn serialize_none(self) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_unit(self) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, _value: &T) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_bool(self, _v: bool) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_i8(self, _v: i8) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_i16(self, _v: i16) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_i32(self, _v: i32) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_i64(self, _v: i64) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_i128(self, _v: i128) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_u8(self, _v: u8) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_u16(self, _v: u16) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_u32(self, _v: u32) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_u64(self, _v: u64) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_u128(self, _v: u128) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_f32(self, _v: f32) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_f64(self, _v: f64) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_char(self, _v: char) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<BigNumber> { Err(not_big_number()) }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> { Err(not_big_number()) }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> { Err(not_big_number()) }
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> { Err(not_big_number()) }
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant> { Err(not_big_number()) }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> { Err(not_big_number()) }
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> { Err(not_big_number()) }
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant> { Err(not_big_number()) }
+}
+
+fn not_big_number() -> Error {
+    Error::Custom(format!("{BIG_NUMBER_TOKEN} must wrap a decimal string"))
+}
+
 /// Internal serializer used to capture individual sequence elements during probing.
 /// It only accepts numeric types and records their raw LE bytes + regular encoding size.
 struct SeqElementSerializer {
@@ -630,7 +1210,11 @@ impl ser::Serializer for &mut SeqElementSerializer {
         Ok(())
     }
 
+    // NaN and Infinity fall back, so the encoder applies `nan_infinity_mode` to them
     fn serialize_f32(self, v: f32) -> Result<()> {
+        if !v.is_finite() {
+            return Ok(());
+        }
         self.result = Some((
             ElementKind::F32,
             v.to_le_bytes().to_vec(),
@@ -640,6 +1224,9 @@ impl ser::Serializer for &mut SeqElementSerializer {
     }
 
     fn serialize_f64(self, v: f64) -> Result<()> {
+        if !v.is_finite() {
+            return Ok(());
+        }
         self.result = Some((
             ElementKind::F64,
             v.to_le_bytes().to_vec(),
@@ -648,6 +1235,10 @@ impl ser::Serializer for &mut SeqElementSerializer {
         Ok(())
     }
 
+    // 128-bit integers have no typed array form, so they also fall back
+    fn serialize_i128(self, _v: i128) -> Result<()> { Ok(()) }
+    fn serialize_u128(self, _v: u128) -> Result<()> { Ok(()) }
+
     // All non-numeric types signal fallback by leaving result as None
     fn serialize_bool(self, _v: bool) -> Result<()> { Ok(()) }
     fn serialize_char(self, _v: char) -> Result<()> { Ok(()) }
@@ -658,7 +1249,13 @@ impl ser::Serializer for &mut SeqElementSerializer {
     fn serialize_unit(self) -> Result<()> { Ok(()) }
     fn serialize_unit_struct(self, _name: &'static str) -> Result<()> { Ok(()) }
     fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str) -> Result<()> { Ok(()) }
-    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, _value: &T) -> Result<()> { Ok(()) }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, _value: &T) -> Result<()> {
+        #[cfg(feature = "half")]
+        if let Some(f) = half_float_value(_name, _value) {
+            return self.serialize_f32(f);
+        }
+        Ok(())
+    }
     fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<()> { Ok(()) }
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> { Ok(NoOpCompound) }
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> { Ok(NoOpCompound) }
@@ -723,19 +1320,100 @@ impl ser::SerializeStructVariant for NoOpCompound {
     fn end(self) -> Result<()> { Ok(()) }
 }
 
+// =============================================================================
+// NullProbe — detects values that serialize as null
+// =============================================================================
+
+/// Returns true if the value would be encoded as null.
+fn serializes_as_null<T: ?Sized + Serialize>(value: &T) -> bool {
+    // Any error (including the early-out for compound types) means "not null";
+    // genuine serialization errors resurface when the value is actually written.
+    value.serialize(NullProbe).unwrap_or(false)
+}
+
+/// A serializer that reports whether a value is null without encoding it.
+/// Compound types bail out immediately with an error rather than visiting children.
+struct NullProbe;
+
+impl ser::Serializer for NullProbe {
+    type Ok = bool;
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<bool, Error>;
+    type SerializeTuple = ser::Impossible<bool, Error>;
+    type SerializeTupleStruct = ser::Impossible<bool, Error>;
+    type SerializeTupleVariant = ser::Impossible<bool, Error>;
+    type SerializeMap = ser::Impossible<bool, Error>;
+    type SerializeStruct = ser::Impossible<bool, Error>;
+    type SerializeStructVariant = ser::Impossible<bool, Error>;
+
+    fn serialize_none(self) -> Result<bool> { Ok(true) }
+    fn serialize_unit(self) -> Result<bool> { Ok(true) }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<bool> { Ok(true) }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<bool> { value.serialize(self) }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<bool> { value.serialize(self) }
+
+    fn serialize_bool(self, _v: bool) -> Result<bool> { Ok(false) }
+    fn serialize_i8(self, _v: i8) -> Result<bool> { Ok(false) }
+    fn serialize_i16(self, _v: i16) -> Result<bool> { Ok(false) }
+    fn serialize_i32(self, _v: i32) -> Result<bool> { Ok(false) }
+    fn serialize_i64(self, _v: i64) -> Result<bool> { Ok(false) }
+    fn serialize_i128(self, _v: i128) -> Result<bool> { Ok(false) }
+    fn serialize_u8(self, _v: u8) -> Result<bool> { Ok(false) }
+    fn serialize_u16(self, _v: u16) -> Result<bool> { Ok(false) }
+    fn serialize_u32(self, _v: u32) -> Result<bool> { Ok(false) }
+    fn serialize_u64(self, _v: u64) -> Result<bool> { Ok(false) }
+    fn serialize_u128(self, _v: u128) -> Result<bool> { Ok(false) }
+    fn serialize_f32(self, _v: f32) -> Result<bool> { Ok(false) }
+    fn serialize_f64(self, _v: f64) -> Result<bool> { Ok(false) }
+    fn serialize_char(self, _v: char) -> Result<bool> { Ok(false) }
+    fn serialize_str(self, _v: &str) -> Result<bool> { Ok(false) }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<bool> { Ok(false) }
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str) -> Result<bool> { Ok(false) }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<bool> { Ok(false) }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> { Err(not_null()) }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> { Err(not_null()) }
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> { Err(not_null()) }
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant> { Err(not_null()) }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> { Err(not_null()) }
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> { Err(not_null()) }
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant> { Err(not_null()) }
+}
+
+/// Early-out used by `NullProbe` for compound values (never surfaced to callers).
+fn not_null() -> Error {
+    Error::Custom(String::new())
+}
+
 // =============================================================================
 // StructSerializer — supports both regular and record instance modes
 // =============================================================================
 
 /// Serializer for struct fields that can emit as a regular object or a record instance.
-pub enum StructSerializer<'a, 'b, W: Write> {
+pub enum StructSerializer<'a, 'b, W: BonjsonWrite> {
     /// Regular object: emit key + value for each field.
     Regular(&'a mut Serializer<'b, W>),
     /// Record instance: only emit values (keys come from the definition).
-    Record(&'a mut Serializer<'b, W>),
+    Record {
+        ser: &'a mut Serializer<'b, W>,
+        name: &'static str,
+        def_index: usize,
+        field_index: usize,
+    },
 }
 
-impl<W: Write> ser::SerializeStruct for StructSerializer<'_, '_, W> {
+fn record_field_mismatch(name: &str, key: &str, expected: Option<&str>) -> Error {
+    match expected {
+        Some(expected) => Error::Custom(format!(
+            "struct `{name}` field `{key}` does not match record definition (expected `{expected}`)"
+        )),
+        None => Error::Custom(format!(
+            "struct `{name}` field `{key}` is not in its record definition"
+        )),
+    }
+}
+
+impl<W: BonjsonWrite> ser::SerializeStruct for StructSerializer<'_, '_, W> {
     type Ok = ();
     type Error = Error;
 
@@ -746,29 +1424,45 @@ impl<W: Write> ser::SerializeStruct for StructSerializer<'_, '_, W> {
     ) -> Result<()> {
         match self {
             StructSerializer::Regular(ser) => {
-                ser.encoder.write_str_unchecked(key)?;
-                value.serialize(&mut **ser)
+                if ser.config.skip_null_struct_fields && serializes_as_null(value) {
+                    return Ok(());
+                }
+                ser.encoder.write_key_unchecked(key)?;
+                value.serialize(&mut **ser).map_err(|e| in_field(e, key))
             }
-            StructSerializer::Record(ser) => {
-                // Skip key — definition provides it
-                value.serialize(&mut **ser)
+            StructSerializer::Record { ser, name, def_index, field_index } => {
+                // Skip key — definition provides it, but values are positional,
+                // so the field must be the one the definition expects here.
+                let expected = ser.record_keys[*def_index].get(*field_index).copied();
+                if expected != Some(key) {
+                    return Err(record_field_mismatch(name, key, expected));
+                }
+                *field_index += 1;
+                value.serialize(&mut **ser).map_err(|e| in_field(e, key))
             }
         }
     }
 
     fn end(self) -> Result<()> {
         match self {
-            StructSerializer::Regular(ser) => ser.encoder.end_container_unchecked(),
-            StructSerializer::Record(ser) => ser.encoder.end_container_unchecked(),
+            StructSerializer::Regular(ser) => Ok(ser.encoder.end_container_unchecked()?),
+            StructSerializer::Record { ser, name, def_index, field_index } => {
+                if let Some(missing) = ser.record_keys[def_index].get(field_index) {
+                    return Err(Error::Custom(format!(
+                        "struct `{name}` is missing field `{missing}` required by its record definition"
+                    )));
+                }
+                Ok(ser.encoder.end_container_unchecked()?)
+            }
         }
     }
 }
 
 // =============================================================================
-// Tuple and Map impls — unchanged, just use named lifetimes
+// Tuple and variant impls — unchanged, just use named lifetimes
 // =============================================================================
 
-impl<W: Write> ser::SerializeTuple for &mut Serializer<'_, W> {
+impl<W: BonjsonWrite> ser::SerializeTuple for &mut Serializer<'_, W> {
     type Ok = ();
     type Error = Error;
 
@@ -777,11 +1471,11 @@ impl<W: Write> ser::SerializeTuple for &mut Serializer<'_, W> {
     }
 
     fn end(self) -> Result<()> {
-        self.encoder.end_container_unchecked()
+        Ok(self.encoder.end_container_unchecked()?)
     }
 }
 
-impl<W: Write> ser::SerializeTupleStruct for &mut Serializer<'_, W> {
+impl<W: BonjsonWrite> ser::SerializeTupleStruct for &mut Serializer<'_, W> {
     type Ok = ();
     type Error = Error;
 
@@ -790,11 +1484,11 @@ impl<W: Write> ser::SerializeTupleStruct for &mut Serializer<'_, W> {
     }
 
     fn end(self) -> Result<()> {
-        self.encoder.end_container_unchecked()
+        Ok(self.encoder.end_container_unchecked()?)
     }
 }
 
-impl<W: Write> ser::SerializeTupleVariant for &mut Serializer<'_, W> {
+impl<W: BonjsonWrite> ser::SerializeTupleVariant for &mut Serializer<'_, W> {
     type Ok = ();
     type Error = Error;
 
@@ -805,44 +1499,135 @@ impl<W: Write> ser::SerializeTupleVariant for &mut Serializer<'_, W> {
     fn end(self) -> Result<()> {
         // Close the inner array and the outer object
         self.encoder.end_container_unchecked()?;
-        self.encoder.end_container_unchecked()
+        Ok(self.encoder.end_container_unchecked()?)
     }
 }
 
-impl<W: Write> ser::SerializeMap for &mut Serializer<'_, W> {
+
+impl<W: BonjsonWrite> ser::SerializeStructVariant for &mut Serializer<'_, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
-        key.serialize(MapKeySerializer { ser: &mut **self })
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        if self.config.skip_null_struct_fields && serializes_as_null(value) {
+            return Ok(());
+        }
+        self.encoder.write_key_unchecked(key)?;
+        value.serialize(&mut **self).map_err(|e| in_field(e, key))
     }
 
-    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
-        value.serialize(&mut **self)
+    fn end(self) -> Result<()> {
+        // Close the inner object and the outer object
+        self.encoder.end_container_unchecked()?;
+        Ok(self.encoder.end_container_unchecked()?)
     }
+}
 
-    fn end(self) -> Result<()> {
-        self.encoder.end_container_unchecked()
+// =============================================================================
+// MapSerializer — streams entries, or buffers them to emit sorted by key
+// =============================================================================
+
+/// Serializer for map entries that writes them as they arrive or, with
+/// `sort_map_keys`, buffers them and writes them in key order at the end.
+pub enum MapSerializer<'a, 'b, W: BonjsonWrite> {
+    /// Write each key and value straight through.
+    Direct(&'a mut Serializer<'b, W>),
+    /// Encode entries into `buffer`, remembering each entry's key and value ranges.
+    Sorted {
+        ser: &'a mut Serializer<'b, W>,
+        buffer: Vec<u8>,
+        entries: Vec<(Range<usize>, Range<usize>)>,
+    },
+}
+
+impl<W: BonjsonWrite> Serializer<'_, W> {
+    /// Encode a map key or value onto the end of `buffer`, with the same
+    /// configuration and record definitions as this serializer, and return
+    /// the range it occupies.
+    fn encode_into<F>(&mut self, buffer: &mut Vec<u8>, encode: F) -> Result<Range<usize>>
+    where
+        F: FnOnce(&mut Serializer<'_, Vec<u8>>) -> Result<()>,
+    {
+        let start = buffer.len();
+        let mut encoder = Encoder::with_config(std::mem::take(buffer), self.encoder.config().clone());
+        // Lend our state to the nested serializer rather than cloning it per entry
+        let mut nested = Serializer {
+            encoder: &mut encoder,
+            config: std::mem::take(&mut self.config),
+            record_defs: self.record_defs.take(),
+            record_keys: std::mem::take(&mut self.record_keys),
+        };
+        let result = encode(&mut nested);
+        self.config = nested.config;
+        self.record_defs = nested.record_defs;
+        self.record_keys = nested.record_keys;
+        self.encoder.absorb_metrics(&encoder);
+        *buffer = encoder.into_inner();
+        result.map(|()| start..buffer.len())
+    }
+}
+
+/// The UTF-8 payload of an encoded string.
+fn encoded_str_payload(bytes: &[u8]) -> &[u8] {
+    if bytes[0] == type_code::STRING_LONG {
+        &bytes[1..bytes.len() - 1]
+    } else {
+        &bytes[1..]
     }
 }
 
-impl<W: Write> ser::SerializeStructVariant for &mut Serializer<'_, W> {
+impl<W: BonjsonWrite> ser::SerializeMap for MapSerializer<'_, '_, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized + Serialize>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<()> {
-        self.encoder.write_str_unchecked(key)?;
-        value.serialize(&mut **self)
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        match self {
+            MapSerializer::Direct(ser) => serialize_map_key(ser, key),
+            MapSerializer::Sorted { ser, buffer, entries } => {
+                let key = ser.encode_into(buffer, |nested| serialize_map_key(nested, key))?;
+                entries.push((key, 0..0));
+                Ok(())
+            }
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        match self {
+            MapSerializer::Direct(ser) => value.serialize(&mut **ser),
+            MapSerializer::Sorted { ser, buffer, entries } => {
+                let range = ser.encode_into(buffer, |nested| value.serialize(nested))?;
+                if let Some(entry) = entries.last_mut() {
+                    entry.1 = range;
+                }
+                Ok(())
+            }
+        }
     }
 
     fn end(self) -> Result<()> {
-        // Close the inner object and the outer object
-        self.encoder.end_container_unchecked()?;
-        self.encoder.end_container_unchecked()
+        match self {
+            MapSerializer::Direct(ser) => Ok(ser.encoder.end_container_unchecked()?),
+            MapSerializer::Sorted { ser, buffer, mut entries } => {
+                // Stable, so entries with equal keys keep their insertion order
+                entries.sort_by(|a, b| {
+                    encoded_str_payload(&buffer[a.0.clone()]).cmp(encoded_str_payload(&buffer[b.0.clone()]))
+                });
+                ser.encoder.begin_object_unchecked()?;
+                for (key, value) in entries {
+                    // Keys were encoded from a &str, so the payload is UTF-8
+                    if let Ok(key) = std::str::from_utf8(encoded_str_payload(&buffer[key.clone()])) {
+                        ser.encoder.check_duplicate_key(key)?;
+                    }
+                    ser.encoder.write_raw_unchecked(&buffer[key])?;
+                    ser.encoder.write_raw_unchecked(&buffer[value])?;
+                }
+                Ok(ser.encoder.end_container_unchecked()?)
+            }
+        }
     }
 }
 
@@ -850,12 +1635,43 @@ impl<W: Write> ser::SerializeStructVariant for &mut Serializer<'_, W> {
 // MapKeySerializer — ensures map keys are strings
 // =============================================================================
 
+/// The error for a map key of serde type `key_type`, which can't be an object key.
+#[cold]
+fn unsupported_key(key_type: &'static str) -> Error {
+    Error::UnsupportedMapKey { key_type, field: None }
+}
+
+/// Name `field` in a map key error from within its value, unless a struct
+/// nested deeper already has.
+fn in_field(error: Error, field: &'static str) -> Error {
+    match error {
+        Error::UnsupportedMapKey { key_type, field: None } => Error::UnsupportedMapKey { key_type, field: Some(field) },
+        other => other,
+    }
+}
+
+/// Write a map key, or under `stringify_map_keys` the text of one that
+/// can't be an object key.
+fn serialize_map_key<W: BonjsonWrite, T: ?Sized + Serialize>(ser: &mut Serializer<'_, W>, key: &T) -> Result<()> {
+    match key.serialize(MapKeySerializer { ser: &mut *ser }) {
+        // Rejected keys haven't written anything yet
+        Err(Error::UnsupportedMapKey { .. }) if ser.config.stringify_map_keys => {
+            let text = match crate::to_value(&key)? {
+                crate::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            Ok(ser.encoder.write_key_unchecked(&text)?)
+        }
+        result => result,
+    }
+}
+
 /// A helper serializer for map keys that ensures they are strings.
-struct MapKeySerializer<'a, 'b, W: Write> {
+struct MapKeySerializer<'a, 'b, W: BonjsonWrite> {
     ser: &'a mut Serializer<'b, W>,
 }
 
-impl<W: Write> ser::Serializer for MapKeySerializer<'_, '_, W> {
+impl<W: BonjsonWrite> ser::Serializer for MapKeySerializer<'_, '_, W> {
     type Ok = ();
     type Error = Error;
     type SerializeSeq = ser::Impossible<(), Error>;
@@ -867,7 +1683,7 @@ impl<W: Write> ser::Serializer for MapKeySerializer<'_, '_, W> {
     type SerializeStructVariant = ser::Impossible<(), Error>;
 
     fn serialize_str(self, v: &str) -> Result<()> {
-        self.ser.encoder.write_str_unchecked(v)
+        Ok(self.ser.encoder.write_key_unchecked(v)?)
     }
 
     fn serialize_i8(self, v: i8) -> Result<()> {
@@ -894,15 +1710,21 @@ impl<W: Write> ser::Serializer for MapKeySerializer<'_, '_, W> {
     fn serialize_u64(self, v: u64) -> Result<()> {
         self.serialize_str(&v.to_string())
     }
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.serialize_str(&v.to_string())
+    }
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.serialize_str(&v.to_string())
+    }
 
     fn serialize_bool(self, _v: bool) -> Result<()> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("bool"))
     }
     fn serialize_f32(self, _v: f32) -> Result<()> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("f32"))
     }
     fn serialize_f64(self, _v: f64) -> Result<()> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("f64"))
     }
     fn serialize_char(self, v: char) -> Result<()> {
         let mut buf = [0u8; 4];
@@ -910,19 +1732,19 @@ impl<W: Write> ser::Serializer for MapKeySerializer<'_, '_, W> {
         self.serialize_str(s)
     }
     fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("bytes"))
     }
     fn serialize_none(self) -> Result<()> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("none"))
     }
     fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<()> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("option"))
     }
     fn serialize_unit(self) -> Result<()> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("unit"))
     }
     fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("unit struct"))
     }
     fn serialize_unit_variant(
         self,
@@ -946,20 +1768,20 @@ impl<W: Write> ser::Serializer for MapKeySerializer<'_, '_, W> {
         _variant: &'static str,
         _value: &T,
     ) -> Result<()> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("newtype variant"))
     }
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("sequence"))
     }
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("tuple"))
     }
     fn serialize_tuple_struct(
         self,
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("tuple struct"))
     }
     fn serialize_tuple_variant(
         self,
@@ -968,13 +1790,13 @@ impl<W: Write> ser::Serializer for MapKeySerializer<'_, '_, W> {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("tuple variant"))
     }
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("map"))
     }
     fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("struct"))
     }
     fn serialize_struct_variant(
         self,
@@ -983,7 +1805,7 @@ impl<W: Write> ser::Serializer for MapKeySerializer<'_, '_, W> {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        Err(Error::ExpectedObjectKey)
+        Err(unsupported_key("struct variant"))
     }
 }
 
@@ -992,16 +1814,121 @@ impl<W: Write> ser::Serializer for MapKeySerializer<'_, '_, W> {
 // =============================================================================
 
 /// A no-output serializer that counts struct type occurrences for record detection.
-/// After serialization, `struct_counts` contains struct_name → (keys, count).
+/// After serialization, `struct_counts` contains struct_name → (keys, count), and
+/// `mixed_shapes` lists structs whose instances don't all share one key layout
+/// (such structs can't become records).
+///
+/// It also predicts the size of the second pass's output, making the same
+/// typed array decisions as [`Serializer`], so that `to_vec_with_config` can
+/// allocate the destination once; see [`predicted_size`](Self::predicted_size).
 #[derive(Default)]
 pub struct CountingSerializer {
     pub struct_counts: HashMap<&'static str, (Vec<&'static str>, usize)>,
+    pub mixed_shapes: HashSet<&'static str>,
+    /// Mirror of `SerializerConfig::skip_null_struct_fields`: structs with null
+    /// fields are treated as mixed-shape, since those fields would be omitted.
+    pub skip_null_struct_fields: bool,
+    /// The serializer's configuration, for typed array decisions.
+    config: SerializerConfig,
+    /// Bytes the serializer writes, with every struct as a regular object.
+    encoded_size: usize,
+    /// Set while counting a map key, which the serializer writes as a string.
+    in_map_key: bool,
 }
 
 impl CountingSerializer {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// A counting serializer that predicts the output of a [`Serializer`]
+    /// with `config`.
+    #[must_use]
+    pub fn for_config(config: &SerializerConfig) -> Self {
+        Self {
+            skip_null_struct_fields: config.skip_null_struct_fields,
+            config: config.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// The size of the serializer's output for the value counted, when
+    /// `definitions` (struct name and keys, in definition index order) are
+    /// written up front and their structs as record instances.
+    ///
+    /// Exact as long as the value serializes the same way on both passes.
+    #[must_use]
+    pub fn predicted_size(&self, definitions: &[(&'static str, Vec<&'static str>)]) -> usize {
+        let mut size = self.encoded_size;
+        for (def_index, (name, keys)) in definitions.iter().enumerate() {
+            let keys_size: usize = keys.iter().map(|key| encoder::str_encoding_size(key.len())).sum();
+            // The definition, then per instance the keys an object would have
+            // written, less the instance's definition index
+            size += 2 + keys_size;
+            let count = self.struct_counts.get(name).map_or(0, |(_, count)| *count);
+            size -= count * keys_size;
+            size += count * length_field_size(def_index as u64);
+        }
+        size
+    }
+
+    /// Count `instances` occurrences of a struct with the given keys.
+    fn count_struct(&mut self, name: &'static str, keys: Vec<&'static str>, instances: usize) {
+        match self.struct_counts.entry(name) {
+            std::collections::hash_map::Entry::Occupied(mut e) => {
+                let (counted_keys, count) = e.get_mut();
+                if *counted_keys != keys {
+                    // e.g. skip_serializing_if omitted a field in one instance
+                    self.mixed_shapes.insert(name);
+                }
+                *count += instances;
+            }
+            std::collections::hash_map::Entry::Vacant(e) => {
+                e.insert((keys, instances));
+            }
+        }
+    }
+
+    #[inline]
+    fn add(&mut self, size: usize) -> Result<()> {
+        self.encoded_size += size;
+        Ok(())
+    }
+
+    /// Count an integer, which a map key writes in decimal.
+    #[inline]
+    fn add_int<T: std::fmt::Display>(&mut self, value: T, size: usize) -> Result<()> {
+        if self.in_map_key {
+            return self.add(encoder::str_encoding_size(value.to_string().len()));
+        }
+        self.add(size)
+    }
+
+    fn add_i128(&mut self, value: i128) -> Result<()> {
+        let size = if let Ok(v) = i64::try_from(value) {
+            encoder::signed_int_encoding_size(v)
+        } else if let Ok(v) = u64::try_from(value) {
+            encoder::unsigned_int_encoding_size(v)
+        } else {
+            BigNumber::from_i128(value).map_or_else(
+                || encoder::wide_big_number_encoding_size(value.unsigned_abs()),
+                |big| encoder::big_number_encoding_size(&big),
+            )
+        };
+        self.add_int(value, size)
+    }
+
+    fn add_u128(&mut self, value: u128) -> Result<()> {
+        let size = if let Ok(v) = u64::try_from(value) {
+            encoder::unsigned_int_encoding_size(v)
+        } else {
+            BigNumber::from_u128(value).map_or_else(
+                || encoder::wide_big_number_encoding_size(value),
+                |big| encoder::big_number_encoding_size(&big),
+            )
+        };
+        self.add_int(value, size)
+    }
 }
 
 impl<'a> ser::Serializer for &'a mut CountingSerializer {
@@ -1015,78 +1942,198 @@ impl<'a> ser::Serializer for &'a mut CountingSerializer {
     type SerializeStruct = CountingStruct<'a>;
     type SerializeStructVariant = CountingStruct<'a>;
 
-    fn serialize_bool(self, _v: bool) -> Result<()> { Ok(()) }
-    fn serialize_i8(self, _v: i8) -> Result<()> { Ok(()) }
-    fn serialize_i16(self, _v: i16) -> Result<()> { Ok(()) }
-    fn serialize_i32(self, _v: i32) -> Result<()> { Ok(()) }
-    fn serialize_i64(self, _v: i64) -> Result<()> { Ok(()) }
-    fn serialize_u8(self, _v: u8) -> Result<()> { Ok(()) }
-    fn serialize_u16(self, _v: u16) -> Result<()> { Ok(()) }
-    fn serialize_u32(self, _v: u32) -> Result<()> { Ok(()) }
-    fn serialize_u64(self, _v: u64) -> Result<()> { Ok(()) }
-    fn serialize_f32(self, _v: f32) -> Result<()> { Ok(()) }
-    fn serialize_f64(self, _v: f64) -> Result<()> { Ok(()) }
-    fn serialize_char(self, _v: char) -> Result<()> { Ok(()) }
-    fn serialize_str(self, _v: &str) -> Result<()> { Ok(()) }
-    fn serialize_bytes(self, _v: &[u8]) -> Result<()> { Ok(()) }
-    fn serialize_none(self) -> Result<()> { Ok(()) }
+    fn serialize_bool(self, _v: bool) -> Result<()> { self.add(1) }
+    fn serialize_i8(self, v: i8) -> Result<()> { self.add_int(v, encoder::signed_int_encoding_size(i64::from(v))) }
+    fn serialize_i16(self, v: i16) -> Result<()> { self.add_int(v, encoder::signed_int_encoding_size(i64::from(v))) }
+    fn serialize_i32(self, v: i32) -> Result<()> { self.add_int(v, encoder::signed_int_encoding_size(i64::from(v))) }
+    fn serialize_i64(self, v: i64) -> Result<()> { self.add_int(v, encoder::signed_int_encoding_size(v)) }
+    fn serialize_u8(self, v: u8) -> Result<()> { self.add_int(v, encoder::unsigned_int_encoding_size(u64::from(v))) }
+    fn serialize_u16(self, v: u16) -> Result<()> { self.add_int(v, encoder::unsigned_int_encoding_size(u64::from(v))) }
+    fn serialize_u32(self, v: u32) -> Result<()> { self.add_int(v, encoder::unsigned_int_encoding_size(u64::from(v))) }
+    fn serialize_u64(self, v: u64) -> Result<()> { self.add_int(v, encoder::unsigned_int_encoding_size(v)) }
+    fn serialize_i128(self, v: i128) -> Result<()> { self.add_i128(v) }
+    fn serialize_u128(self, v: u128) -> Result<()> { self.add_u128(v) }
+    fn serialize_f32(self, v: f32) -> Result<()> { self.add(encoder::float_encoding_size(f64::from(v))) }
+    fn serialize_f64(self, v: f64) -> Result<()> { self.add(encoder::float_encoding_size(v)) }
+    fn serialize_char(self, v: char) -> Result<()> { self.add(encoder::str_encoding_size(v.len_utf8())) }
+    fn serialize_str(self, v: &str) -> Result<()> { self.add(encoder::str_encoding_size(v.len())) }
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        if self.config.capabilities.typed_arrays {
+            return self.add(1 + length_field_size(v.len() as u64) + v.len());
+        }
+        let elements: usize = v.iter().map(|&b| encoder::unsigned_int_encoding_size(u64::from(b))).sum();
+        self.add(2 + elements)
+    }
+    fn serialize_none(self) -> Result<()> { self.add(1) }
     fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> { value.serialize(self) }
-    fn serialize_unit(self) -> Result<()> { Ok(()) }
-    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> { Ok(()) }
-    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str) -> Result<()> { Ok(()) }
-    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<()> { value.serialize(self) }
-    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, value: &T) -> Result<()> { value.serialize(self) }
+    fn serialize_unit(self) -> Result<()> { self.add(1) }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> { self.add(1) }
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<()> {
+        self.add(encoder::str_encoding_size(variant.len()))
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, name: &'static str, value: &T) -> Result<()> {
+        #[cfg(feature = "half")]
+        if let Some(f) = half_float_value(name, value) {
+            return self.serialize_f32(f);
+        }
+        if name == BIG_NUMBER_TOKEN {
+            return self.add(encoder::big_number_encoding_size(&value.serialize(BigNumberProbe)?));
+        }
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, variant: &'static str, value: &T) -> Result<()> {
+        self.add(2 + encoder::str_encoding_size(variant.len()))?;
+        value.serialize(self)
+    }
 
-    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
-        Ok(CountingSeq(self))
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        let config = &self.config;
+        let probe = config.typed_arrays
+            && config.capabilities.typed_arrays
+            && config.typed_array_strategy != TypedArrayStrategy::Never
+            && len.is_none_or(|len| len >= config.typed_array_min_len);
+        if probe {
+            return Ok(CountingSeq { counter: self, probe: Some(SeqProbe::default()), columns: None });
+        }
+        self.add(2)?;
+        Ok(CountingSeq { counter: self, probe: None, columns: None })
     }
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
-        Ok(CountingSeq(self))
+        self.add(2)?;
+        Ok(CountingSeq { counter: self, probe: None, columns: None })
     }
     fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
-        Ok(CountingSeq(self))
+        self.add(2)?;
+        Ok(CountingSeq { counter: self, probe: None, columns: None })
     }
-    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant> {
-        Ok(CountingSeq(self))
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant> {
+        self.add(4 + encoder::str_encoding_size(variant.len()))?;
+        Ok(CountingSeq { counter: self, probe: None, columns: None })
     }
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        Ok(CountingSeq(self))
+        self.add(2)?;
+        Ok(CountingSeq { counter: self, probe: None, columns: None })
     }
     fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        self.add(2)?;
         Ok(CountingStruct {
             counter: self,
             name,
             keys: Vec::new(),
+            has_null: false,
         })
     }
-    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant> {
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant> {
         // Struct variants are wrapped in an object — we don't track them as records
         // but we still need to visit children to find nested structs
+        self.add(4 + encoder::str_encoding_size(variant.len()))?;
         Ok(CountingStruct {
             counter: self,
             name: "",
             keys: Vec::new(),
+            has_null: false,
         })
     }
 }
 
+/// A sequence's typed array probe, mirroring `SeqMode::Probing`.
+#[derive(Default)]
+pub struct SeqProbe {
+    kind: Option<ElementKind>,
+    count: usize,
+    regular_size: usize,
+}
+
 /// Visits children of sequences/maps to find nested structs.
-pub struct CountingSeq<'a>(&'a mut CountingSerializer);
+pub struct CountingSeq<'a> {
+    counter: &'a mut CountingSerializer,
+    /// Present while a sequence could still become a typed array.
+    probe: Option<SeqProbe>,
+    /// Present while a sequence could still become columns, mirroring `SeqMode::Columnar`.
+    columns: Option<Columns>,
+}
+
+impl CountingSeq<'_> {
+    /// Count the probed elements as a regular array and stop probing.
+    fn fall_back(&mut self) {
+        if let Some(probe) = self.probe.take() {
+            self.counter.encoded_size += 2 + probe.regular_size;
+        }
+        if let Some(columns) = self.columns.take() {
+            self.counter.encoded_size += columns.regular_total();
+            self.counter.count_struct(columns.name, columns.keys, columns.rows);
+        }
+    }
+}
 
 impl ser::SerializeSeq for CountingSeq<'_> {
     type Ok = ();
     type Error = Error;
     fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
-        value.serialize(&mut *self.0)
+        let budget = self.counter.config.typed_array_max_buffer_bytes;
+        if let Some(columns) = &mut self.columns {
+            if let Some(row) = capture_row(value)? {
+                if columns.matches(&row) && columns.buffered_bytes() + row.bytes() <= budget {
+                    columns.push(row);
+                    return Ok(());
+                }
+            }
+            self.fall_back();
+        }
+        if let Some(probe) = &mut self.probe {
+            let mut element = SeqElementSerializer { result: None };
+            value.serialize(&mut element)?;
+            if let Some((kind, raw_bytes, regular_size)) = element.result {
+                let buffered = probe.kind.map_or(0, |kind| probe.count * kind.element_size());
+                let over_budget = buffered + raw_bytes.len() > budget;
+                if !over_budget && probe.kind.is_none_or(|probed| probed == kind) {
+                    probe.kind = Some(kind);
+                    probe.count += 1;
+                    probe.regular_size += regular_size;
+                    return Ok(());
+                }
+            } else if probe.count == 0 && self.counter.config.columnar_structs {
+                if let Some(row) = capture_row(value)? {
+                    if row.bytes() <= budget {
+                        self.probe = None;
+                        self.columns = Some(Columns::new(row));
+                        return Ok(());
+                    }
+                }
+            }
+            self.fall_back();
+        }
+        value.serialize(&mut *self.counter)
+    }
+    fn end(mut self) -> Result<()> {
+        if let Some(columns) = &self.columns {
+            if columns.use_columns(&self.counter.config) {
+                return self.counter.add(columns.columnar_size());
+            }
+            self.fall_back();
+            return Ok(());
+        }
+        let Some(probe) = self.probe else { return Ok(()) };
+        let Some(kind) = probe.kind else { return self.counter.add(2) };
+        let config = &self.counter.config;
+        let typed_size = 1 + length_field_size(probe.count as u64) + probe.count * kind.element_size();
+        let regular_total = 1 + probe.regular_size + 1;
+        let use_typed = if probe.count < config.typed_array_min_len {
+            false
+        } else if config.typed_array_strategy == TypedArrayStrategy::Always {
+            true
+        } else {
+            typed_size < regular_total
+        };
+        self.counter.add(if use_typed { typed_size } else { regular_total })
     }
-    fn end(self) -> Result<()> { Ok(()) }
 }
 
 impl ser::SerializeTuple for CountingSeq<'_> {
     type Ok = ();
     type Error = Error;
     fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
-        value.serialize(&mut *self.0)
+        value.serialize(&mut *self.counter)
     }
     fn end(self) -> Result<()> { Ok(()) }
 }
@@ -1095,7 +2142,7 @@ impl ser::SerializeTupleStruct for CountingSeq<'_> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
-        value.serialize(&mut *self.0)
+        value.serialize(&mut *self.counter)
     }
     fn end(self) -> Result<()> { Ok(()) }
 }
@@ -1104,7 +2151,7 @@ impl ser::SerializeTupleVariant for CountingSeq<'_> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
-        value.serialize(&mut *self.0)
+        value.serialize(&mut *self.counter)
     }
     fn end(self) -> Result<()> { Ok(()) }
 }
@@ -1112,9 +2159,14 @@ impl ser::SerializeTupleVariant for CountingSeq<'_> {
 impl ser::SerializeMap for CountingSeq<'_> {
     type Ok = ();
     type Error = Error;
-    fn serialize_key<T: ?Sized + Serialize>(&mut self, _key: &T) -> Result<()> { Ok(()) }
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        self.counter.in_map_key = true;
+        let result = key.serialize(&mut *self.counter);
+        self.counter.in_map_key = false;
+        result
+    }
     fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
-        value.serialize(&mut *self.0)
+        value.serialize(&mut *self.counter)
     }
     fn end(self) -> Result<()> { Ok(()) }
 }
@@ -1124,6 +2176,22 @@ pub struct CountingStruct<'a> {
     counter: &'a mut CountingSerializer,
     name: &'static str,
     keys: Vec<&'static str>,
+    has_null: bool,
+}
+
+impl CountingStruct<'_> {
+    /// Count a field's key and value, or neither if the field is skipped as null.
+    fn count_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<bool> {
+        let skipped = self.counter.skip_null_struct_fields && serializes_as_null(value);
+        let before = self.counter.encoded_size;
+        value.serialize(&mut *self.counter)?;
+        if skipped {
+            self.counter.encoded_size = before;
+        } else {
+            self.counter.encoded_size += encoder::str_encoding_size(key.len());
+        }
+        Ok(skipped)
+    }
 }
 
 impl ser::SerializeStruct for CountingStruct<'_> {
@@ -1135,16 +2203,19 @@ impl ser::SerializeStruct for CountingStruct<'_> {
         key: &'static str,
         value: &T,
     ) -> Result<()> {
+        if self.count_field(key, value)? {
+            self.has_null = true;
+        }
         self.keys.push(key);
-        value.serialize(&mut *self.counter)
+        Ok(())
     }
 
     fn end(self) -> Result<()> {
         if !self.name.is_empty() {
-            let entry = self.counter.struct_counts.entry(self.name);
-            entry
-                .and_modify(|(_, count)| *count += 1)
-                .or_insert((self.keys, 1));
+            if self.has_null {
+                self.counter.mixed_shapes.insert(self.name);
+            }
+            self.counter.count_struct(self.name, self.keys, 1);
         }
         Ok(())
     }
@@ -1156,10 +2227,10 @@ impl ser::SerializeStructVariant for CountingStruct<'_> {
 
     fn serialize_field<T: ?Sized + Serialize>(
         &mut self,
-        _key: &'static str,
+        key: &'static str,
         value: &T,
     ) -> Result<()> {
-        value.serialize(&mut *self.counter)
+        self.count_field(key, value).map(|_| ())
     }
 
     fn end(self) -> Result<()> { Ok(()) }
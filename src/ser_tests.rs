@@ -2,7 +2,7 @@
 // ABOUTME: Tests serde integration including typed arrays, records, and fallback behavior.
 
 use crate::encoder::Encoder;
-use crate::ser::{Serializer, SerializerConfig};
+use crate::ser::{Serializer, SerializerConfig, TypedArrayStrategy};
 use crate::types::type_code;
 use serde::Serialize;
 
@@ -65,6 +65,67 @@ fn test_serialize_vec_typed_arrays_disabled() {
     assert_eq!(*bytes.last().unwrap(), type_code::CONTAINER_END);
 }
 
+#[test]
+fn test_typed_array_strategy() {
+    // Always: small ints become a typed array even though it's larger
+    let config = SerializerConfig { typed_array_strategy: TypedArrayStrategy::Always, ..Default::default() };
+    let bytes = serialize_with_config(&vec![1i32, 2, 3], config);
+    assert_eq!(bytes[0], type_code::TYPED_ARRAY_SINT32);
+    assert_eq!(crate::from_slice::<Vec<i32>>(&bytes).unwrap(), vec![1, 2, 3]);
+
+    // Never: large values stay regular
+    let config = SerializerConfig { typed_array_strategy: TypedArrayStrategy::Never, ..Default::default() };
+    let bytes = serialize_with_config(&vec![0.1f64, 0.2, 0.3], config);
+    assert_eq!(bytes[0], type_code::ARRAY);
+
+    // Always still requires a homogeneous numeric sequence
+    let config = SerializerConfig { typed_array_strategy: TypedArrayStrategy::Always, ..Default::default() };
+    let bytes = serialize_with_config(&vec![Some(1i32), None], config);
+    assert_eq!(bytes, vec![0xb7, 0x01, 0xb3, 0xb6]);
+}
+
+#[test]
+fn test_typed_array_min_len() {
+    let config = SerializerConfig { typed_array_min_len: 4, ..Default::default() };
+    let bytes = serialize_with_config(&vec![0.1f64, 0.2, 0.3], config.clone());
+    assert_eq!(bytes[0], type_code::ARRAY);
+    let bytes = serialize_with_config(&vec![0.1f64, 0.2, 0.3, 0.4], config.clone());
+    assert_eq!(bytes[0], type_code::TYPED_ARRAY_FLOAT64);
+
+    // Without a length hint the sequence is buffered, then checked at the end
+    let short = ElementsWithoutLen(vec![0.1, 0.2, 0.3]);
+    assert_eq!(serialize_with_config(&short, config.clone())[0], type_code::ARRAY);
+    let long = ElementsWithoutLen(vec![0.1, 0.2, 0.3, 0.4]);
+    assert_eq!(serialize_with_config(&long, config)[0], type_code::TYPED_ARRAY_FLOAT64);
+}
+
+#[test]
+fn test_typed_array_max_buffer_bytes() {
+    let data: Vec<f64> = (0..10).map(|i| f64::from(i) + 0.1).collect();
+    let config = SerializerConfig { typed_array_max_buffer_bytes: 32, ..Default::default() };
+    let bytes = serialize_with_config(&data, config);
+    // Budget exceeded after 4 elements: the whole sequence is a regular array
+    assert_eq!(bytes[0], type_code::ARRAY);
+    assert_eq!(crate::from_slice::<Vec<f64>>(&bytes).unwrap(), data);
+
+    let config = SerializerConfig { typed_array_max_buffer_bytes: 80, ..Default::default() };
+    assert_eq!(serialize_with_config(&data, config)[0], type_code::TYPED_ARRAY_FLOAT64);
+}
+
+/// Serializes as a sequence without reporting its length.
+struct ElementsWithoutLen(Vec<f64>);
+
+impl Serialize for ElementsWithoutLen {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+        let mut seq = serializer.serialize_seq(None)?;
+        for v in &self.0 {
+            seq.serialize_element(v)?;
+        }
+        seq.end()
+    }
+}
+
 #[test]
 fn test_serialize_vec_f64_typed_array() {
     // Vec<f64> with values that need f64: typed array should be emitted
@@ -344,6 +405,7 @@ fn test_typed_array_and_records_together() {
     let config = SerializerConfig {
         typed_arrays: true,
         records: true,
+        ..Default::default()
     };
 
     let data = vec![
@@ -384,3 +446,736 @@ fn test_roundtrip_vec_u64() {
     let decoded: Vec<u64> = crate::from_slice(&bytes).unwrap();
     assert_eq!(decoded, values);
 }
+
+// =========================================================================
+// 128-bit integer tests
+// =========================================================================
+
+#[test]
+fn test_serialize_i128_small_uses_int_encoding() {
+    assert_eq!(serialize(&42i128), serialize(&42i64));
+    assert_eq!(serialize(&-5000i128), serialize(&-5000i64));
+    assert_eq!(serialize(&u128::from(u64::MAX)), serialize(&u64::MAX));
+    assert_eq!(serialize(&i128::from(u64::MAX)), serialize(&u64::MAX));
+}
+
+#[test]
+fn test_serialize_i128_large_uses_bignumber() {
+    let bytes = serialize(&(i128::from(u64::MAX) * 1000));
+    assert_eq!(bytes[0], type_code::BIG_NUMBER);
+
+    let bytes = serialize(&-(10i128.pow(30)));
+    assert_eq!(bytes[0], type_code::BIG_NUMBER);
+}
+
+#[test]
+fn test_serialize_i128_wide_magnitude() {
+    // i128::MAX has 39 significant digits, so its magnitude needs all 16 bytes
+    let bytes = serialize(&i128::MAX);
+    assert_eq!(bytes[0], type_code::BIG_NUMBER);
+    assert_eq!(bytes.len(), 1 + 1 + 1 + 16);
+    assert_eq!(&bytes[3..], &i128::MAX.to_le_bytes());
+
+    // Trailing zeros still move into the exponent
+    let bytes = serialize(&(12_345_678_901_234_567_890_123u128 * 1000));
+    assert_eq!(bytes[1], 6); // zigzag(3)
+    assert_eq!(bytes.len(), 1 + 1 + 1 + 10);
+}
+
+#[test]
+fn test_roundtrip_i128_u128_boundaries() {
+    let values: Vec<i128> = vec![i128::MIN, i128::MIN + 1, i128::MAX, -12_345_678_901_234_567_890_123, 12_345_678_901_234_567_890_123];
+    let bytes = serialize(&values);
+    let decoded: Vec<i128> = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, values);
+
+    let values: Vec<u128> = vec![u128::MAX, u128::MAX - 1, u128::from(u64::MAX) + 1, 12_345_678_901_234_567_890_123];
+    let bytes = serialize(&values);
+    let decoded: Vec<u128> = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, values);
+
+    // Each target still rejects values outside its own range
+    assert!(matches!(crate::from_slice::<i128>(&serialize(&u128::MAX)), Err(crate::Error::ValueOutOfRange)));
+    assert!(matches!(crate::from_slice::<u128>(&serialize(&i128::MIN)), Err(crate::Error::ValueOutOfRange)));
+    assert!(matches!(crate::from_slice::<i128>(&serialize(&(i128::MAX as u128 + 1))), Err(crate::Error::ValueOutOfRange)));
+}
+
+#[test]
+fn test_roundtrip_i128_u128() {
+    let values: Vec<i128> = vec![0, -1, i128::from(i64::MIN), i128::from(u64::MAX) * 10, -(10i128.pow(38))];
+    let bytes = serialize(&values);
+    let decoded: Vec<i128> = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, values);
+
+    let values: Vec<u128> = vec![0, u128::from(u64::MAX), 10u128.pow(38), 255 * 10u128.pow(30)];
+    let bytes = serialize(&values);
+    let decoded: Vec<u128> = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, values);
+}
+
+#[test]
+fn test_roundtrip_i128_struct_field() {
+    #[derive(Debug, Serialize, serde::Deserialize, PartialEq)]
+    struct Ledger {
+        balance: i128,
+        total: u128,
+    }
+
+    let ledger = Ledger {
+        balance: -(i128::from(u64::MAX) * 100),
+        total: 12_000_000_000_000_000_000_000,
+    };
+    let bytes = serialize(&ledger);
+    let decoded: Ledger = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, ledger);
+}
+
+// =========================================================================
+// Pre-registered record definition tests
+// =========================================================================
+
+#[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+struct Sample {
+    id: u32,
+    value: f64,
+}
+
+fn sample_config(keys: Vec<&'static str>) -> SerializerConfig {
+    SerializerConfig {
+        record_definitions: vec![("Sample", keys)],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_preregistered_records() {
+    let data = vec![Sample { id: 1, value: 0.5 }, Sample { id: 2, value: 1.5 }];
+    let bytes = serialize_with_config(&data, sample_config(vec!["id", "value"]));
+
+    // Definition comes first even though `records` is false
+    assert_eq!(
+        &bytes[..11],
+        &[type_code::RECORD_DEF, 0x67, b'i', b'd', 0x6a, b'v', b'a', b'l', b'u', b'e', type_code::CONTAINER_END]
+    );
+    let decoded: Vec<Sample> = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, data);
+}
+
+#[test]
+fn test_preregistered_records_single_instance() {
+    // Unlike the counting pass, registered definitions apply even to one instance
+    let sample = Sample { id: 7, value: 0.25 };
+    let bytes = serialize_with_config(&sample, sample_config(vec!["id", "value"]));
+    assert_eq!(bytes[0], type_code::RECORD_DEF);
+    assert_eq!(bytes[11], type_code::RECORD_INSTANCE);
+    let decoded: Sample = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, sample);
+}
+
+#[test]
+fn test_preregistered_records_unlisted_struct_is_object() {
+    #[derive(Serialize)]
+    struct Other {
+        id: u32,
+    }
+
+    let bytes = serialize_with_config(&vec![Other { id: 1 }, Other { id: 2 }], sample_config(vec!["id", "value"]));
+    // Registered-but-unused definition, then a plain array of objects
+    assert_eq!(bytes[11], type_code::ARRAY);
+    assert_eq!(bytes[12], type_code::OBJECT);
+}
+
+#[test]
+fn test_preregistered_records_field_mismatch() {
+    let sample = Sample { id: 7, value: 0.25 };
+
+    // Wrong order
+    let result = crate::to_vec_with_config(&sample, &sample_config(vec!["value", "id"]));
+    assert!(result.is_err());
+
+    // Missing definition key
+    let result = crate::to_vec_with_config(&sample, &sample_config(vec!["id", "value", "extra"]));
+    assert!(result.is_err());
+
+    // Extra struct field
+    let result = crate::to_vec_with_config(&sample, &sample_config(vec!["id"]));
+    assert!(result.is_err());
+}
+
+// =========================================================================
+// skip_null_struct_fields tests
+// =========================================================================
+
+#[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+struct Contact {
+    name: String,
+    email: Option<String>,
+    phone: Option<String>,
+}
+
+fn skip_null_config(records: bool) -> SerializerConfig {
+    SerializerConfig {
+        skip_null_struct_fields: true,
+        records,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_skip_null_struct_fields() {
+    let contact = Contact { name: "a".into(), email: None, phone: Some("1".into()) };
+    let bytes = serialize_with_config(&contact, skip_null_config(false));
+    let value = crate::decode_value(&bytes).unwrap();
+    assert_eq!(value, crate::bonjson!({"name": "a", "phone": "1"}));
+
+    // Missing Option fields deserialize back to None
+    let decoded: Contact = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, contact);
+
+    // Default config keeps nulls
+    let value = crate::decode_value(&serialize(&contact)).unwrap();
+    assert_eq!(value.get_key("email"), Some(&crate::Value::Null));
+}
+
+#[test]
+fn test_skip_null_struct_fields_nested_and_variants() {
+    #[derive(Serialize)]
+    enum Event {
+        Update { id: u32, note: Option<String> },
+    }
+
+    #[derive(Serialize)]
+    struct Wrapper {
+        inner: Option<Option<u32>>,
+        unit: (),
+        list: Vec<Option<u32>>,
+        event: Event,
+    }
+
+    let wrapper = Wrapper {
+        inner: Some(None),
+        unit: (),
+        list: vec![None, Some(1)],
+        event: Event::Update { id: 1, note: None },
+    };
+    let bytes = serialize_with_config(&wrapper, skip_null_config(false));
+    let value = crate::decode_value(&bytes).unwrap();
+    // Array elements keep their nulls; only struct fields are dropped
+    assert_eq!(
+        value,
+        crate::bonjson!({"list": [null, 1], "event": {"Update": {"id": 1}}})
+    );
+}
+
+#[test]
+fn test_counting_pass_predicts_encoded_size() {
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect(u32, u32),
+        Poly { points: Vec<(i16, i16)>, label: Option<String> },
+    }
+
+    #[derive(Serialize)]
+    struct Item {
+        id: u64,
+        name: String,
+        tag: Option<char>,
+        shape: Shape,
+        readings: Vec<f32>,
+        counts: Vec<u8>,
+        mixed: Vec<i64>,
+        by_year: BTreeMap<i32, String>,
+        huge: i128,
+    }
+
+    let items: Vec<Item> = (0..6)
+        .map(|i| Item {
+            id: u64::from(i) * 1_000_003,
+            name: "n".repeat(usize::from(i) * 30),
+            tag: (i % 2 == 0).then_some('é'),
+            shape: match i % 4 {
+                0 => Shape::Empty,
+                1 => Shape::Circle(f64::from(i) + 0.5),
+                2 => Shape::Rect(i.into(), 70_000),
+                _ => Shape::Poly { points: vec![(1, -2), (300, 4)], label: None },
+            },
+            readings: (0..i).map(|r| f32::from(r) * 0.1).collect(),
+            counts: vec![i; usize::from(i)],
+            mixed: vec![1, -200, 1 << 40],
+            by_year: [(1999, "x".into()), (-5, "y".into())].into_iter().collect(),
+            huge: -i128::from(i) * 10i128.pow(30) - i128::from(i % 2),
+        })
+        .collect();
+
+    let configs = [
+        SerializerConfig::default(),
+        SerializerConfig { typed_array_strategy: TypedArrayStrategy::Always, ..Default::default() },
+        SerializerConfig { typed_array_strategy: TypedArrayStrategy::Never, ..Default::default() },
+        SerializerConfig { typed_array_min_len: 3, ..Default::default() },
+        SerializerConfig { typed_array_max_buffer_bytes: 10, ..Default::default() },
+        SerializerConfig { typed_arrays: false, ..Default::default() },
+        SerializerConfig { skip_null_struct_fields: true, ..Default::default() },
+        SerializerConfig { sort_map_keys: true, ..Default::default() },
+    ];
+    let bytes = crate::to_vec_with_config(&items, &SerializerConfig { records: true, ..Default::default() }).unwrap();
+    assert_eq!(bytes[0], type_code::RECORD_DEF);
+    for config in configs {
+        let config = SerializerConfig { records: true, ..config };
+        let plan = crate::plan_records(&items, &config).unwrap().unwrap();
+        let bytes = crate::to_vec_with_config(&items, &config).unwrap();
+        assert_eq!(plan.predicted_size, bytes.len());
+        assert_eq!(bytes.capacity(), bytes.len());
+
+        // Without records (Item has null fields under skip_null_struct_fields)
+        let plan = crate::plan_records(&items[0].shape, &config).unwrap().unwrap();
+        assert_eq!(plan.predicted_size, crate::to_vec_with_config(&items[0].shape, &config).unwrap().len());
+    }
+
+    // Manual encoders can reserve up front
+    let mut encoder = Encoder::new(Vec::new());
+    encoder.reserve(1000);
+    encoder.write_str("x").unwrap();
+    assert!(encoder.finish().unwrap().capacity() >= 1000);
+}
+
+#[test]
+fn test_skip_null_struct_fields_with_records() {
+    // Contacts with nulls can't be positional records, so they stay objects
+    let data = vec![
+        Contact { name: "a".into(), email: None, phone: Some("1".into()) },
+        Contact { name: "b".into(), email: Some("b@x".into()), phone: None },
+    ];
+    let bytes = serialize_with_config(&data, skip_null_config(true));
+    assert_eq!(bytes[0], type_code::ARRAY);
+    let decoded: Vec<Contact> = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, data);
+
+    // Without nulls, records are still used
+    let data = vec![
+        Contact { name: "a".into(), email: Some("a@x".into()), phone: Some("1".into()) },
+        Contact { name: "b".into(), email: Some("b@x".into()), phone: Some("2".into()) },
+    ];
+    let bytes = serialize_with_config(&data, skip_null_config(true));
+    assert_eq!(bytes[0], type_code::RECORD_DEF);
+    let decoded: Vec<Contact> = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, data);
+}
+
+#[test]
+fn test_skip_null_struct_fields_preregistered_records_keep_nulls() {
+    let config = SerializerConfig {
+        skip_null_struct_fields: true,
+        record_definitions: vec![("Contact", vec!["name", "email", "phone"])],
+        ..Default::default()
+    };
+    let contact = Contact { name: "a".into(), email: None, phone: None };
+    let bytes = serialize_with_config(&contact, config);
+    let value = crate::decode_value(&bytes).unwrap();
+    assert_eq!(value, crate::bonjson!({"name": "a", "email": null, "phone": null}));
+}
+
+#[test]
+fn test_records_skip_inconsistent_shapes() {
+    // skip_serializing_if changes the field list per instance, so no record
+    #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+    struct Sparse {
+        a: u32,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        b: Option<u32>,
+    }
+
+    let data = vec![Sparse { a: 1, b: None }, Sparse { a: 2, b: Some(3) }];
+    let config = SerializerConfig {
+        records: true,
+        ..Default::default()
+    };
+    let bytes = serialize_with_config(&data, config);
+    assert_eq!(bytes[0], type_code::ARRAY);
+    let decoded: Vec<Sparse> = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, data);
+}
+
+// ============================================================================
+// IEEE half-precision tests (`half` feature)
+// ============================================================================
+
+#[test]
+fn test_sort_map_keys() {
+    use std::collections::HashMap;
+
+    let config = SerializerConfig { sort_map_keys: true, ..Default::default() };
+    let long_key = "k".repeat(80);
+    let map: HashMap<String, HashMap<u32, bool>> = [
+        ("b".to_string(), HashMap::from([(10, true), (2, false), (1, true)])),
+        (long_key.clone(), HashMap::new()),
+        ("a".to_string(), HashMap::new()),
+        ("ab".to_string(), HashMap::new()),
+    ]
+    .into_iter()
+    .collect();
+    let bytes = serialize_with_config(&map, config.clone());
+
+    // Byte order of the keys, at every level, regardless of hash order
+    let empty = crate::Value::Object(crate::Map::new());
+    let expected = crate::encode_value(&crate::Value::Object(
+        [
+            ("a".to_string(), empty.clone()),
+            ("ab".to_string(), empty.clone()),
+            ("b".to_string(), crate::bonjson!({"1": true, "10": true, "2": false})),
+            (long_key, empty),
+        ]
+        .into_iter()
+        .collect(),
+    ))
+    .unwrap();
+    assert_eq!(bytes, expected);
+    assert_eq!(serialize_with_config(&map, config), bytes);
+}
+
+#[test]
+fn test_sort_map_keys_with_records_and_flatten() {
+    use std::collections::HashMap;
+
+    #[derive(Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Tagged {
+        z: u8,
+        #[serde(flatten)]
+        extra: HashMap<String, u8>,
+    }
+
+    let config = SerializerConfig { sort_map_keys: true, records: true, ..Default::default() };
+    let points: HashMap<String, Point> =
+        (0..5).map(|i| (format!("p{i}"), Point { x: i, y: -i })).collect();
+    let bytes = serialize_with_config(&points, config.clone());
+    // Buffered values still use the shared record definition
+    assert_eq!(bytes[0], type_code::RECORD_DEF);
+    assert_eq!(crate::from_slice::<HashMap<String, Point>>(&bytes).unwrap(), points);
+    let keys: Vec<String> = crate::decode_value(&bytes).unwrap().as_object().unwrap().keys().cloned().collect();
+    assert_eq!(keys, ["p0", "p1", "p2", "p3", "p4"]);
+
+    // Flattened structs go through the map path, so their fields are sorted too
+    let tagged = Tagged { z: 1, extra: HashMap::from([("b".to_string(), 2), ("a".to_string(), 3)]) };
+    let bytes = serialize_with_config(&tagged, config);
+    assert_eq!(bytes, crate::encode_value(&crate::bonjson!({"a": 3, "b": 2, "z": 1})).unwrap());
+}
+
+#[cfg(feature = "half")]
+#[test]
+fn test_f16_serializes_as_float() {
+    use half::f16;
+
+    #[derive(Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Reading {
+        temp: f16,
+    }
+
+    let reading = Reading { temp: f16::from_f32(21.375) };
+    let bytes = crate::to_vec(&reading).unwrap();
+    let value = crate::decode_value(&bytes).unwrap();
+    assert_eq!(value.get_key("temp"), Some(&crate::Value::Float(21.375)));
+    assert_eq!(crate::from_slice::<Reading>(&bytes).unwrap(), reading);
+
+    // Values that aren't exact in decimal survive because f32 holds every f16 exactly
+    let tenth = f16::from_f32(0.1);
+    let decoded: f16 = crate::from_slice(&crate::to_vec(&tenth).unwrap()).unwrap();
+    assert_eq!(decoded.to_bits(), tenth.to_bits());
+
+    // Other newtypes named f16 are only taken for half floats when they wrap a u16
+    {
+        struct Code(u16);
+        impl Serialize for Code {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_u16(self.0)
+            }
+        }
+        #[allow(non_camel_case_types)]
+        #[derive(Serialize)]
+        struct f16(Code);
+        assert_eq!(crate::to_vec(&f16(Code(0x3c00))).unwrap(), crate::to_vec(&0x3c00u16).unwrap());
+    }
+}
+
+#[cfg(feature = "half")]
+#[test]
+fn test_f16_vec_uses_float32_typed_array() {
+    use half::f16;
+
+    let values: Vec<f16> = [0.1f32, 0.2, 0.3, -65504.0].iter().map(|&f| f16::from_f32(f)).collect();
+    let bytes = crate::to_vec(&values).unwrap();
+    assert_eq!(bytes[0], crate::type_code::TYPED_ARRAY_FLOAT32);
+    let decoded: Vec<f16> = crate::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, values);
+
+    // bf16 is handled the same way
+    let values = vec![half::bf16::from_f32(1.5), half::bf16::from_f32(-0.25)];
+    let decoded: Vec<half::bf16> = crate::from_slice(&crate::to_vec(&values).unwrap()).unwrap();
+    assert_eq!(decoded, values);
+}
+
+#[cfg(feature = "half")]
+#[test]
+fn test_encoder_write_f16_ieee() {
+    use half::f16;
+
+    let mut enc = crate::Encoder::new(Vec::new());
+    enc.write_f16_ieee(f16::from_f32(0.5)).unwrap();
+    let bytes = enc.finish().unwrap();
+    assert_eq!(crate::decode_value(&bytes).unwrap(), crate::Value::Float(0.5));
+
+    let values = [f16::from_f32(1.5), f16::from_f32(0.1)];
+    let mut enc = crate::Encoder::new(Vec::new());
+    enc.write_f16_ieee_array(&values).unwrap();
+    let bytes = enc.finish().unwrap();
+    assert_eq!(bytes[0], crate::type_code::TYPED_ARRAY_FLOAT32);
+    let decoded = crate::decode_value(&bytes).unwrap();
+    assert_eq!(decoded, crate::bonjson!([1.5, (f16::from_f32(0.1).to_f64())]));
+    assert_eq!(crate::Value::from(values[1]), crate::Value::Float(values[1].to_f64()));
+
+    let mut enc = crate::Encoder::new(Vec::new());
+    assert_eq!(enc.write_f16_ieee_array(&[f16::NAN]).unwrap_err(), crate::EncodeError::NanNotAllowed);
+
+    // Allow keeps the typed array; Stringify has to write a regular one
+    let values = [f16::from_f32(1.5), f16::INFINITY];
+    for (mode, expected) in [
+        (crate::NanInfinityMode::Allow, crate::bonjson!([1.5, (f64::INFINITY)])),
+        (crate::NanInfinityMode::Stringify, crate::bonjson!([1.5, "Infinity"])),
+    ] {
+        let config = crate::EncoderConfig { nan_infinity_mode: mode, ..crate::EncoderConfig::default() };
+        let mut enc = crate::Encoder::with_config(Vec::new(), config);
+        enc.write_f16_ieee_array(&values).unwrap();
+        let bytes = enc.finish().unwrap();
+        assert_eq!(bytes[0] == crate::type_code::TYPED_ARRAY_FLOAT32, mode == crate::NanInfinityMode::Allow);
+        let decoder_config = crate::DecoderConfig { nan_infinity_mode: crate::NanInfinityMode::Allow, ..Default::default() };
+        assert_eq!(crate::decode_value_with_config(&bytes, decoder_config).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_serde_duplicate_keys() {
+    use crate::{DuplicateKeys, Error};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Tagged {
+        id: u32,
+        #[serde(flatten)]
+        extra: BTreeMap<String, u32>,
+    }
+
+    let value = Tagged { id: 1, extra: [("id".to_string(), 2), ("n".to_string(), 3)].into() };
+    // Written as-is by default; the decoder rejects it
+    let bytes = crate::to_vec(&value).unwrap();
+    assert_eq!(crate::decode_value(&bytes).unwrap_err(), Error::DuplicateKey);
+
+    for sort_map_keys in [false, true] {
+        let config = SerializerConfig { duplicate_keys: DuplicateKeys::Error, sort_map_keys, ..Default::default() };
+        assert_eq!(crate::to_vec_with_config(&value, &config).unwrap_err(), Error::DuplicateKey);
+
+        let value = Tagged { id: 1, extra: [("n".to_string(), 3)].into() };
+        let bytes = crate::to_vec_with_config(&value, &config).unwrap();
+        assert_eq!(crate::decode_value(&bytes).unwrap(), crate::bonjson!({"id": 1, "n": 3}));
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn test_duplicate_keys_after_normalization() {
+    use crate::{DuplicateKeys, Error, UnicodeNormalization};
+    use std::collections::BTreeMap;
+
+    // Precomposed and decomposed forms of "é" are distinct map keys
+    let map: BTreeMap<&str, u32> = [("\u{e9}", 1), ("e\u{301}", 2)].into();
+    let config = SerializerConfig { duplicate_keys: DuplicateKeys::Error, ..Default::default() };
+    assert!(crate::to_vec_with_config(&map, &config).is_ok());
+
+    let config = SerializerConfig { key_normalization: UnicodeNormalization::Nfc, ..config };
+    assert_eq!(crate::to_vec_with_config(&map, &config).unwrap_err(), Error::DuplicateKey);
+}
+
+#[test]
+fn test_columnar_structs() {
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Point {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Cloud {
+        name: String,
+        points: Vec<Point>,
+        origin: Vec<Point>,
+    }
+
+    let points: Vec<Point> = (0..10u8).map(|i| Point { x: f32::from(i) * 0.5, y: -f32::from(i) }).collect();
+    let columnar = SerializerConfig { columnar_structs: true, ..Default::default() };
+    let bytes = serialize_with_config(&points, columnar.clone());
+    assert_eq!(bytes[..4], [type_code::OBJECT, 0x66, b'x', type_code::TYPED_ARRAY_FLOAT32]);
+    assert!(bytes.len() < serialize(&points).len());
+    let value = crate::decode_value(&bytes).unwrap();
+    assert_eq!(value.get_key("y").and_then(crate::Value::as_array).map(|y| y[3].clone()), Some(crate::Value::Float(-3.0)));
+    assert_eq!(crate::from_slice::<Vec<Point>>(&bytes).unwrap(), points);
+
+    // Only the serde sequence path reassembles rows; element-wise readers see the columns
+    let mut de = crate::ReaderDeserializer::new(bytes.as_slice());
+    assert!(de.array_elements::<Point>().next().unwrap().is_err());
+    assert!(crate::for_each_element(&bytes, "$", |_: Point| ()).is_err());
+    let selected = crate::jsonpath::Query::parse("$[*]").unwrap().select_bytes(&bytes).unwrap();
+    assert_eq!(selected.len(), 2);
+
+    // Nested, alongside records: the counting pass sees the same columns
+    let cloud = Cloud { name: "c".into(), points, origin: vec![Point { x: 0.0, y: 0.0 }] };
+    let config = SerializerConfig { records: true, typed_array_strategy: TypedArrayStrategy::Always, ..columnar.clone() };
+    let plan = crate::plan_records(&cloud, &config).unwrap().unwrap();
+    assert!(plan.definitions.is_empty());
+    let bytes = serialize_with_config(&cloud, config);
+    assert_eq!(plan.predicted_size, bytes.len());
+    assert_eq!(crate::from_slice::<Cloud>(&bytes).unwrap(), cloud);
+
+    // Too few rows, or over the buffer budget, and the structs stay objects
+    let config = SerializerConfig { typed_array_min_len: 11, ..columnar.clone() };
+    assert_eq!(serialize_with_config(&cloud.points, config), serialize(&cloud.points));
+    let config = SerializerConfig { typed_array_max_buffer_bytes: 20, records: true, ..columnar.clone() };
+    let plan = crate::plan_records(&cloud.points, &config).unwrap().unwrap();
+    assert_eq!(plan.definitions, [("Point", vec!["x", "y"])]);
+    let bytes = serialize_with_config(&cloud.points, config);
+    assert_eq!(plan.predicted_size, bytes.len());
+    assert_eq!(crate::from_slice::<Vec<Point>>(&bytes).unwrap(), cloud.points);
+
+    // Columns are priced against record instances when those would be written
+    #[derive(Serialize)]
+    struct Cell {
+        x: i32,
+        y: i32,
+    }
+    let cells: Vec<Cell> = (0..50).map(|i| Cell { x: i, y: i * 1000 }).collect();
+    let records = SerializerConfig { records: true, ..Default::default() };
+    let config = SerializerConfig { records: true, ..columnar.clone() };
+    assert!(serialize_with_config(&cells, columnar).len() < serialize(&cells).len());
+    let plan = crate::plan_records(&cells, &config).unwrap().unwrap();
+    let bytes = serialize_with_config(&cells, config);
+    assert_eq!(bytes, serialize_with_config(&cells, records));
+    assert_eq!(plan.predicted_size, bytes.len());
+}
+
+#[test]
+fn test_columnar_structs_fallback() {
+    #[derive(Serialize)]
+    struct Tagged {
+        x: f32,
+        tag: &'static str,
+    }
+
+    #[derive(Serialize)]
+    struct Other {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Serialize)]
+    #[serde(untagged)]
+    enum Either {
+        Other(Other),
+        Point { x: f32, y: f32 },
+    }
+
+    let columnar = SerializerConfig { columnar_structs: true, ..Default::default() };
+    let tagged = [Tagged { x: 1.5, tag: "a" }, Tagged { x: 2.5, tag: "b" }];
+    assert_eq!(serialize_with_config(&tagged, columnar.clone()), serialize(&tagged));
+
+    // A struct of another name partway through flushes the buffered rows as objects
+    let mixed: Vec<Either> = (0..4u8)
+        .map(|i| match i {
+            3 => Either::Point { x: 1.0, y: 2.0 },
+            _ => Either::Other(Other { x: f32::from(i), y: 0.5 }),
+        })
+        .collect();
+    let bytes = serialize_with_config(&mixed, columnar);
+    assert_eq!(bytes, serialize(&mixed));
+    assert_eq!(crate::decode_value(&bytes).unwrap().as_array().map(Vec::len), Some(4));
+}
+
+#[test]
+fn test_serde_nan_infinity_modes() {
+    use crate::{decode_value_with_config, to_vec_with_config, DecoderConfig, Error, NanInfinityMode, Value};
+
+    // Long enough for a typed array, which can't hold a stringified NaN
+    let mut values = vec![0.1f64; 20];
+    values.push(f64::NAN);
+    let config = |mode| SerializerConfig { nan_infinity_mode: mode, ..SerializerConfig::default() };
+
+    assert_eq!(to_vec_with_config(&values, &config(NanInfinityMode::Reject)), Err(Error::NanNotAllowed));
+    assert_eq!(crate::to_vec(&(1, f32::INFINITY)), Err(Error::InfinityNotAllowed));
+
+    let bytes = to_vec_with_config(&values, &config(NanInfinityMode::Stringify)).unwrap();
+    let decoded = crate::decode_value(&bytes).unwrap();
+    assert_eq!(decoded.as_array().unwrap().len(), 21);
+    assert_eq!(decoded.get(20), Some(&Value::String("NaN".into())));
+
+    let bytes = to_vec_with_config(&values, &config(NanInfinityMode::Allow)).unwrap();
+    let allow = DecoderConfig { nan_infinity_mode: NanInfinityMode::Allow, ..DecoderConfig::default() };
+    assert!(decode_value_with_config(&bytes, allow).unwrap().get(20).and_then(Value::as_f64).unwrap().is_nan());
+
+    // Stringified values read back through a Stringify decoder as the floats' names either way
+    let stringify = DecoderConfig { nan_infinity_mode: NanInfinityMode::Stringify, ..DecoderConfig::default() };
+    let names: Vec<String> = crate::from_slice_with_config(
+        &to_vec_with_config(&[f64::NEG_INFINITY], &config(NanInfinityMode::Allow)).unwrap(),
+        stringify,
+    )
+    .unwrap();
+    assert_eq!(names, ["-Infinity"]);
+}
+
+#[test]
+fn test_unsupported_map_keys() {
+    use crate::{bonjson, decode_value, to_vec, to_vec_with_config, Error};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Inner {
+        by_bytes: BTreeMap<Vec<u8>, u32>,
+    }
+    #[derive(Serialize)]
+    struct Outer {
+        name: &'static str,
+        inner: Inner,
+    }
+    let outer = Outer { name: "x", inner: Inner { by_bytes: BTreeMap::from([(vec![1, 2], 7)]) } };
+
+    // The innermost struct field holding the map is named
+    let err = to_vec(&outer).unwrap_err();
+    assert_eq!(err, Error::UnsupportedMapKey { key_type: "sequence", field: Some("by_bytes") });
+    assert_eq!(err.error_type(), "expected_object_key");
+    assert!(err.to_string().starts_with("map key of type sequence can't be an object key (in field `by_bytes`)"), "{err}");
+
+    let err = to_vec(&BTreeMap::from([(true, 1)])).unwrap_err();
+    assert_eq!(err, Error::UnsupportedMapKey { key_type: "bool", field: None });
+    let err = to_vec(&BTreeMap::from([((1, "a"), 1)])).unwrap_err();
+    assert_eq!(err, Error::UnsupportedMapKey { key_type: "tuple", field: None });
+
+    // Numbers, chars and strings are keys as before
+    assert_eq!(decode_value(&to_vec(&BTreeMap::from([(5u8, 1), (9u8, 2)])).unwrap()).unwrap(), bonjson!({"5": 1, "9": 2}));
+
+    let lossy = SerializerConfig::default().stringify_map_keys(true);
+    let bytes = to_vec_with_config(&outer, &lossy).unwrap();
+    assert_eq!(decode_value(&bytes).unwrap(), bonjson!({"name": "x", "inner": {"by_bytes": {"[1, 2]": 7}}}));
+    let keys = BTreeMap::from([((1, "a"), 1)]);
+    assert_eq!(decode_value(&to_vec_with_config(&keys, &lossy).unwrap()).unwrap(), bonjson!({"[1, \"a\"]": 1}));
+    let keys = BTreeMap::from([(Some("k"), 1), (None, 2)]);
+    assert_eq!(decode_value(&to_vec_with_config(&keys, &lossy).unwrap()).unwrap(), bonjson!({"null": 2, "k": 1}));
+    let sorted = lossy.clone().sort_map_keys(true);
+    let keys = BTreeMap::from([(false, 1), (true, 2)]);
+    assert_eq!(decode_value(&to_vec_with_config(&keys, &sorted).unwrap()).unwrap(), bonjson!({"false": 1, "true": 2}));
+}
@@ -0,0 +1,32 @@
+// ABOUTME: SharedValue (rc feature): the Arc-backed CompactValue tree under the name for documents
+// ABOUTME: shared across threads, where cloning a whole document or subtree is a reference count bump.
+
+//! Documents that many tasks read at once.
+//!
+//! Cloning a [`Value`](crate::Value) copies the whole tree. A [`SharedValue`] keeps every
+//! string, array and object behind an `Arc`, so `clone()` is O(1) at any
+//! depth and the clones can go to other threads. It's read-only: to change
+//! one, convert it back with [`to_value`](SharedValue::to_value).
+//!
+//! ```rust
+//! use serde_bonjson::shared::SharedValue;
+//! use serde_bonjson::bonjson;
+//!
+//! let doc = SharedValue::from(bonjson!({"jobs": [{"id": 1}, {"id": 2}]}));
+//! let workers: Vec<_> = (0..4)
+//!     .map(|_| {
+//!         let doc = doc.clone();
+//!         std::thread::spawn(move || doc.get_key("jobs").unwrap().as_array().unwrap().len())
+//!     })
+//!     .collect();
+//! for worker in workers {
+//!     assert_eq!(worker.join().unwrap(), 2);
+//! }
+//! ```
+
+use crate::intern::CompactValue;
+
+/// A read-only [`Value`](crate::Value) that clones in O(1): the same tree as
+/// [`CompactValue`], built with `From<Value>` or
+/// [`decode`](CompactValue::decode) rather than through an interner.
+pub type SharedValue = CompactValue;
@@ -0,0 +1,39 @@
+// ABOUTME: Unit tests for the shared module (rc feature).
+// ABOUTME: Tests O(1) clones sharing storage, conversions, decoding, serialization and thread safety.
+
+use crate::shared::SharedValue;
+use crate::{bonjson, encode_value, to_vec, Value};
+
+#[test]
+fn test_shared_value_clones_share_storage() {
+    let value = bonjson!({"rows": [[1, 2], [3, 4]], "name": "batch", "scale": 0.5, "null": null});
+    let shared = SharedValue::from(value.clone());
+    let copy = shared.clone();
+    assert!(copy.ptr_eq(&shared));
+    assert!(copy.get_key("rows").unwrap().ptr_eq(shared.get_key("rows").unwrap()));
+    assert!(copy.get_key("name").unwrap().ptr_eq(shared.get_key("name").unwrap()));
+
+    // Equal contents in separate allocations aren't the same allocation
+    let rebuilt = SharedValue::from(value.clone());
+    assert_eq!(rebuilt, shared);
+    assert!(!rebuilt.ptr_eq(&shared));
+
+    assert_eq!(shared.get_key("rows").unwrap().get(1).unwrap().as_array().unwrap().len(), 2);
+    assert_eq!(shared.get_key("name").unwrap().as_str(), Some("batch"));
+    assert_eq!(shared.get_key("missing"), None);
+    assert_eq!(shared.to_value(), value);
+    assert_eq!(Value::from(&shared), value);
+}
+
+#[test]
+fn test_shared_value_decode_and_serialize() {
+    let value = bonjson!({"ints": [1, 2, 3], "text": "x", "nested": {"list": [true, null, (-7), 2.25]}});
+    let bytes = encode_value(&value).unwrap();
+    let shared = SharedValue::decode(&bytes).unwrap();
+    assert_eq!(shared.to_value(), value);
+    assert_eq!(to_vec(&shared).unwrap(), to_vec(&value).unwrap());
+    assert!(SharedValue::decode(&bytes[..bytes.len() - 1]).is_err());
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedValue>();
+}
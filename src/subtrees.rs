@@ -0,0 +1,140 @@
+// ABOUTME: Encode-side analysis of identical subtrees repeated within a document.
+// ABOUTME: Measures how many bytes the repeats cost, to judge whether restructuring pays.
+
+use crate::encoder::Encoder;
+use crate::error::Result;
+use crate::value::Value;
+use std::collections::HashMap;
+
+/// An array or object that appears more than once in a document, value for value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepeatedSubtree {
+    /// The repeated value.
+    pub value: Value,
+    /// How many times it appears outside other repeated subtrees.
+    pub count: usize,
+    /// Its size when encoded on its own, without record definitions.
+    pub encoded_size: usize,
+}
+
+impl RepeatedSubtree {
+    /// Bytes spent on every copy after the first.
+    #[must_use]
+    pub fn redundant_bytes(&self) -> usize {
+        (self.count - 1) * self.encoded_size
+    }
+}
+
+/// What [`analyze`] found in a document.
+///
+/// BONJSON has no way to refer back to a value, so the repeats can't be
+/// shared in the encoding itself. Record definitions already share the key
+/// sets of repeated objects (`encode_value` emits one for every key set used
+/// twice), so `redundant_bytes` is an upper bound on what moving the values
+/// out of the document (into a lookup table the application owns) would save.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtreeReport {
+    /// The document's size as `encode_value` writes it, with record definitions.
+    pub document_size: usize,
+    /// Repeated subtrees, most redundant bytes first. A subtree that only
+    /// repeats inside a larger repeated subtree isn't listed separately.
+    pub subtrees: Vec<RepeatedSubtree>,
+}
+
+impl SubtreeReport {
+    /// Bytes spent on repeats across all listed subtrees.
+    #[must_use]
+    pub fn redundant_bytes(&self) -> usize {
+        self.subtrees.iter().map(RepeatedSubtree::redundant_bytes).sum()
+    }
+}
+
+/// Find the arrays and objects that repeat within `value`, ignoring those
+/// that encode to fewer than `min_size` bytes.
+///
+/// Every container is encoded on its own to compare it with the others, so
+/// this costs time proportional to the document size times its depth; it's
+/// meant for profiling payloads, not for every encode.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_bonjson::bonjson;
+/// use serde_bonjson::subtrees::analyze;
+///
+/// let status = bonjson!({"code": 200, "message": "OK"});
+/// let doc = bonjson!([{"id": 1, "status": (status.clone())}, {"id": 2, "status": (status.clone())}]);
+/// let report = analyze(&doc, 8).unwrap();
+/// assert_eq!(report.subtrees.len(), 1);
+/// assert_eq!(report.subtrees[0].value, status);
+/// assert_eq!(report.subtrees[0].count, 2);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the value can't be encoded (e.g. it holds a NaN).
+pub fn analyze(value: &Value, min_size: usize) -> Result<SubtreeReport> {
+    let document_size = crate::encode_value(value)?.len();
+
+    let mut encodings = HashMap::new();
+    count_subtrees(value, &mut encodings)?;
+    // Keep only what repeats, then count the outermost occurrences
+    encodings.retain(|encoding, count| *count > 1 && encoding.len() >= min_size);
+    let mut occurrences = HashMap::new();
+    count_outermost(value, &encodings, &mut occurrences)?;
+
+    let mut subtrees: Vec<RepeatedSubtree> = occurrences
+        .into_iter()
+        .filter(|(_, (count, _))| *count > 1)
+        .map(|(encoding, (count, value))| RepeatedSubtree { value: value.clone(), count, encoded_size: encoding.len() })
+        .collect();
+    subtrees.sort_by(|a, b| {
+        b.redundant_bytes().cmp(&a.redundant_bytes()).then_with(|| a.value.total_cmp(&b.value))
+    });
+    Ok(SubtreeReport { document_size, subtrees })
+}
+
+/// A container's encoding on its own, which is equal only for equal values.
+fn encode_subtree(value: &Value) -> Result<Vec<u8>> {
+    let mut encoder = Encoder::new(Vec::new());
+    crate::encode_value_recursive_inner(&mut encoder, value, &[], &HashMap::new())?;
+    Ok(encoder.finish()?)
+}
+
+fn children(value: &Value) -> Box<dyn Iterator<Item = &Value> + '_> {
+    match value {
+        Value::Array(arr) => Box::new(arr.iter()),
+        Value::Object(map) => Box::new(map.values()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+fn is_container(value: &Value) -> bool {
+    matches!(value, Value::Array(_) | Value::Object(_))
+}
+
+/// Count every container in the tree by its encoding.
+fn count_subtrees(value: &Value, counts: &mut HashMap<Vec<u8>, usize>) -> Result<()> {
+    if !is_container(value) {
+        return Ok(());
+    }
+    *counts.entry(encode_subtree(value)?).or_insert(0) += 1;
+    children(value).try_for_each(|child| count_subtrees(child, counts))
+}
+
+/// Count the repeated containers that aren't inside another repeated container.
+fn count_outermost<'v>(
+    value: &'v Value,
+    repeated: &HashMap<Vec<u8>, usize>,
+    occurrences: &mut HashMap<Vec<u8>, (usize, &'v Value)>,
+) -> Result<()> {
+    if !is_container(value) {
+        return Ok(());
+    }
+    let encoding = encode_subtree(value)?;
+    if repeated.contains_key(&encoding) {
+        occurrences.entry(encoding).or_insert((0, value)).0 += 1;
+        return Ok(());
+    }
+    children(value).try_for_each(|child| count_outermost(child, repeated, occurrences))
+}
@@ -0,0 +1,44 @@
+// ABOUTME: Unit tests for the subtrees module.
+// ABOUTME: Tests repeat counting, nesting of repeated subtrees, and the size measurements.
+
+use crate::subtrees::{analyze, RepeatedSubtree};
+use crate::{bonjson, encode_value, Value};
+
+#[test]
+fn test_outermost_repeats() {
+    let point = bonjson!({"x": 1, "y": 2});
+    let shape = bonjson!({"kind": "line", "from": (point.clone()), "to": [3, 4]});
+    let doc = bonjson!({"a": (shape.clone()), "b": (shape.clone()), "c": (shape.clone()), "d": (point.clone()), "e": [3, 4]});
+    let report = analyze(&doc, 0).unwrap();
+
+    let point_size = encode_value(&point).unwrap().len();
+    let shape_size = encode_value(&shape).unwrap().len();
+    assert_eq!(
+        report.subtrees,
+        vec![RepeatedSubtree { value: shape, count: 3, encoded_size: shape_size }]
+    );
+    // `point` and [3, 4] appear once each outside the repeated shape
+    assert!(point_size < shape_size);
+    assert_eq!(report.redundant_bytes(), 2 * shape_size);
+    assert_eq!(report.document_size, encode_value(&doc).unwrap().len());
+}
+
+#[test]
+fn test_min_size_and_ordering() {
+    let small = bonjson!([1]);
+    let large = bonjson!({"message": "a longer value than the other one"});
+    let doc = bonjson!([(small.clone()), (small.clone()), (small.clone()), (large.clone()), (large.clone())]);
+
+    let report = analyze(&doc, 0).unwrap();
+    let counts: Vec<(Value, usize)> = report.subtrees.iter().map(|s| (s.value.clone(), s.count)).collect();
+    assert_eq!(counts, vec![(large.clone(), 2), (small, 3)]);
+
+    let report = analyze(&doc, 8).unwrap();
+    assert_eq!(report.subtrees.len(), 1);
+    assert_eq!(report.subtrees[0].value, large);
+
+    // Equal-looking numbers of different kinds aren't the same value
+    let report = analyze(&bonjson!([[1, 2], [1.0, 2]]), 0).unwrap();
+    assert!(report.subtrees.is_empty());
+    assert_eq!(report.redundant_bytes(), 0);
+}
@@ -0,0 +1,77 @@
+// ABOUTME: Serde `with` module that writes a SystemTime as signed milliseconds since the Unix epoch.
+// ABOUTME: serde's default writes a {secs_since_epoch, nanos_since_epoch} struct and rejects pre-epoch times.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The time `millis` milliseconds after the Unix epoch (before it, if
+/// negative), or `None` if the platform's `SystemTime` can't hold it.
+#[must_use]
+pub fn from_unix_millis(millis: i64) -> Option<SystemTime> {
+    let offset = Duration::from_millis(millis.unsigned_abs());
+    if millis >= 0 {
+        UNIX_EPOCH.checked_add(offset)
+    } else {
+        UNIX_EPOCH.checked_sub(offset)
+    }
+}
+
+/// Milliseconds from the Unix epoch to `time`, truncated toward the epoch;
+/// negative for times before it. `None` if it doesn't fit an `i64`.
+#[must_use]
+pub fn to_unix_millis(time: SystemTime) -> Option<i64> {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => i64::try_from(after.as_millis()).ok(),
+        Err(before) => i64::try_from(before.duration().as_millis()).ok().map(|millis| -millis),
+    }
+}
+
+/// Write a `SystemTime` as signed milliseconds since the Unix epoch, with
+/// `#[serde(with = "serde_bonjson::systemtime::unix_millis")]`.
+///
+/// Anything below a millisecond is truncated toward the epoch.
+///
+/// # Example
+///
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+/// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Event {
+///     #[serde(with = "serde_bonjson::systemtime::unix_millis")]
+///     at: SystemTime,
+/// }
+///
+/// let at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+/// let bytes = serde_bonjson::to_vec(&Event { at }).unwrap();
+/// let value = serde_bonjson::decode_value(&bytes).unwrap();
+/// assert_eq!(value, serde_bonjson::bonjson!({"at": 1_700_000_000_123_i64}));
+/// assert_eq!(value.get_key("at").unwrap().as_system_time(), Some(at));
+/// ```
+pub mod unix_millis {
+    use serde::de::Error as _;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::SystemTime;
+
+    /// Serialize `time` as milliseconds since the Unix epoch.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the offset from the epoch doesn't fit an `i64` of milliseconds.
+    pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        let millis = super::to_unix_millis(*time)
+            .ok_or_else(|| S::Error::custom("time too far from the Unix epoch for i64 milliseconds"))?;
+        serializer.serialize_i64(millis)
+    }
+
+    /// Deserialize a time from milliseconds since the Unix epoch.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value isn't an integer, or is outside the range of `SystemTime`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        let millis = i64::deserialize(deserializer)?;
+        super::from_unix_millis(millis).ok_or_else(|| D::Error::custom("time outside the range of SystemTime"))
+    }
+}
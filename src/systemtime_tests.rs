@@ -0,0 +1,36 @@
+// ABOUTME: Unit tests for the systemtime serde module.
+// ABOUTME: Tests epoch-millisecond encoding on both sides of the epoch and Value::as_system_time.
+
+use crate::systemtime::{from_unix_millis, to_unix_millis};
+use crate::{bonjson, decode_value, from_slice, to_vec};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Event {
+    #[serde(with = "crate::systemtime::unix_millis")]
+    at: SystemTime,
+}
+
+#[test]
+fn test_unix_millis() {
+    let at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+    let bytes = to_vec(&Event { at }).unwrap();
+    assert_eq!(decode_value(&bytes).unwrap(), bonjson!({"at": 1_700_000_000_123_i64}));
+    assert_eq!(from_slice::<Event>(&bytes).unwrap(), Event { at });
+
+    // Before the epoch, which serde's default rejects
+    let before = UNIX_EPOCH - Duration::from_millis(86_400_000);
+    let bytes = to_vec(&Event { at: before }).unwrap();
+    assert_eq!(decode_value(&bytes).unwrap(), bonjson!({"at": (-86_400_000_i64)}));
+    assert_eq!(from_slice::<Event>(&bytes).unwrap(), Event { at: before });
+    assert_eq!(bonjson!(-86_400_000_i64).as_system_time(), Some(before));
+    assert_eq!(bonjson!("yesterday").as_system_time(), None);
+}
+
+#[test]
+fn test_truncation_toward_epoch() {
+    assert_eq!(to_unix_millis(UNIX_EPOCH + Duration::from_micros(2500)), Some(2));
+    assert_eq!(to_unix_millis(UNIX_EPOCH - Duration::from_micros(2500)), Some(-2));
+    assert_eq!(from_unix_millis(0), Some(UNIX_EPOCH));
+}
@@ -0,0 +1,710 @@
+// ABOUTME: Inspection tools for raw BONJSON bytes: dissect() builds a byte-level parse tree (offsets, lengths,
+// ABOUTME: type names, values) as JSON or an event log, and pretty_text() renders annotated, indented text.
+
+use crate::decoder::{DecodedValue, Decoder, DecoderConfig};
+use crate::types::type_code;
+use crate::value::Value;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+/// One node of a [`dissect`] parse tree.
+///
+/// Containers list their contents, including the final `container_end`
+/// marker, as children. Within objects, each key string is its own node and
+/// the value that follows it carries the key as its `label`; record instance
+/// values are labeled with the key from their definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DissectNode {
+    /// Offset of the node's first byte.
+    pub offset: usize,
+    /// Number of bytes the node spans, including its children.
+    pub length: usize,
+    /// The type code, or `None` for typed array elements and synthetic
+    /// nodes (`document`, `trailing`, `error`).
+    pub type_code: Option<u8>,
+    /// The type code's [name](type_code::name), the element type of a typed
+    /// array element (e.g. `float64`), or the synthetic node's kind.
+    pub type_name: &'static str,
+    /// The object key or record field this value belongs to.
+    pub label: Option<String>,
+    /// The decoded value of scalars and typed array elements; the element
+    /// count of typed arrays; the definition index of record instances; the
+    /// message of `error` nodes.
+    pub value: Option<Value>,
+    /// Nested nodes, in byte order.
+    pub children: Vec<DissectNode>,
+}
+
+impl DissectNode {
+    fn new(offset: usize, type_code: Option<u8>, type_name: &'static str) -> Self {
+        Self { offset, length: 0, type_code, type_name, label: None, value: None, children: Vec::new() }
+    }
+
+    fn coded(offset: usize, code: u8) -> Self {
+        Self::new(offset, Some(code), type_code::name(code))
+    }
+
+    /// Returns the first `error` node in the tree, if dissection stopped early.
+    #[must_use]
+    pub fn error(&self) -> Option<&DissectNode> {
+        if self.type_name == "error" {
+            return Some(self);
+        }
+        self.children.iter().find_map(DissectNode::error)
+    }
+
+    /// Render the tree as compact JSON.
+    ///
+    /// Each node is an object with `offset`, `length`, and `type`, plus
+    /// `code`, `label`, `value`, and `children` when present. Non-finite
+    /// floats and BigNumbers are rendered as strings.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    /// Render the tree as an indented event log, one node per line.
+    ///
+    /// Each line starts with the node's hex offset, then the node indented by
+    /// nesting depth: its label (if any), type name, and value. The node
+    /// itself is not printed, only its descendants, so rendering a `document`
+    /// lists its top-level nodes at depth zero.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for child in &self.children {
+            child.write_text(&mut out, 0);
+        }
+        out
+    }
+
+    fn write_text(&self, out: &mut String, depth: usize) {
+        let _ = write!(out, "{:04x}  {:indent$}", self.offset, "", indent = depth * 2);
+        if let Some(label) = &self.label {
+            let _ = write!(out, "\"{}\": ", label.escape_default());
+        }
+        out.push_str(self.type_name);
+        match (&self.value, self.type_name) {
+            (Some(Value::String(message)), "error") => {
+                let _ = write!(out, ": {message}");
+            }
+            (_, "trailing") => {
+                let _ = write!(out, " ({} bytes)", self.length);
+            }
+            (Some(count), _) if type_code::is_typed_array(self.type_code.unwrap_or(0)) => {
+                let _ = write!(out, " x{count}");
+            }
+            (Some(index), "record_instance") => {
+                let _ = write!(out, " #{index}");
+            }
+            (Some(Value::Null | Value::Bool(_)), _) | (None, _) => {}
+            (Some(value), _) => {
+                let _ = write!(out, " {value}");
+            }
+        }
+        out.push('\n');
+        for child in &self.children {
+            child.write_text(out, depth + 1);
+        }
+    }
+
+    fn write_json(&self, out: &mut String) {
+        let _ = write!(out, "{{\"offset\":{},\"length\":{},\"type\":\"{}\"", self.offset, self.length, self.type_name);
+        if let Some(code) = self.type_code {
+            let _ = write!(out, ",\"code\":{code}");
+        }
+        if let Some(label) = &self.label {
+            out.push_str(",\"label\":");
+            write_json_string(out, label);
+        }
+        if let Some(value) = &self.value {
+            out.push_str(",\"value\":");
+            write_json_value(out, value);
+        }
+        if !self.children.is_empty() {
+            out.push_str(",\"children\":[");
+            for (i, child) in self.children.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                child.write_json(out);
+            }
+            out.push(']');
+        }
+        out.push('}');
+    }
+}
+
+/// Dissect a document into a tree of byte ranges.
+///
+/// Unlike decoding, this never fails: when the data is malformed, the tree
+/// covers everything up to the problem and ends with an `error` node at the
+/// offending offset (see [`DissectNode::error`]).
+#[must_use]
+pub fn dissect(data: &[u8]) -> DissectNode {
+    dissect_with_config(data, DecoderConfig::default())
+}
+
+/// Dissect a document with custom decoder configuration (limits, trailing bytes, ...).
+#[must_use]
+pub fn dissect_with_config(data: &[u8], config: DecoderConfig) -> DissectNode {
+    let mut document = DissectNode::new(0, None, "document");
+    document.length = data.len();
+    if let Err(e) = Dissector::run(data, config, &mut document.children) {
+        document.children.push(e);
+    }
+    document
+}
+
+/// Dissect a document and render it with [`DissectNode::to_json`].
+#[must_use]
+pub fn dissect_json(data: &[u8]) -> String {
+    dissect(data).to_json()
+}
+
+/// Print a document's event stream one event per line, indented by nesting
+/// depth and prefixed with byte offsets (see [`DissectNode::to_text`]).
+///
+/// Meant for asserting on structure in unit tests and for log output:
+///
+/// ```rust
+/// use serde_bonjson::{bonjson, encode_value, tools};
+///
+/// let bytes = encode_value(&bonjson!({"a": [true, 1]})).unwrap();
+/// assert_eq!(tools::fmt_events(&bytes), "\
+/// 0000  object
+/// 0001    short_string \"a\"
+/// 0003    \"a\": array
+/// 0004      true
+/// 0005      small_int 1
+/// 0006      container_end
+/// 0007    container_end
+/// ");
+/// ```
+#[must_use]
+pub fn fmt_events(data: &[u8]) -> String {
+    dissect(data).to_text()
+}
+
+/// Print a document's event stream with custom decoder configuration.
+#[must_use]
+pub fn fmt_events_with_config(data: &[u8], config: DecoderConfig) -> String {
+    dissect_with_config(data, config).to_text()
+}
+
+// ============================================================================
+// Dissector
+// ============================================================================
+
+fn error_node(offset: usize, error: &crate::Error) -> DissectNode {
+    let mut node = DissectNode::new(offset, None, "error");
+    node.value = Some(Value::String(error.to_string()));
+    node
+}
+
+enum FrameKind {
+    Array,
+    Object { pending_key: Option<String>, expect_key: bool },
+    Record { def_index: usize, next: usize },
+}
+
+struct Frame {
+    node: DissectNode,
+    kind: FrameKind,
+}
+
+struct Dissector<'a> {
+    data: &'a [u8],
+    decoder: Decoder<'a>,
+    stack: Vec<Frame>,
+}
+
+impl<'a> Dissector<'a> {
+    /// Dissect into `out`. On failure, returns the error node; anything
+    /// dissected so far (including partially read containers) is already in `out`.
+    fn run(data: &'a [u8], config: DecoderConfig, out: &mut Vec<DissectNode>) -> std::result::Result<(), DissectNode> {
+        let mut decoder = Decoder::with_config(data, config.clone());
+        decoder.check_document_size().map_err(|e| error_node(0, &e.into()))?;
+
+        let definitions_end = dissect_record_definitions(data, &config, out)?;
+        decoder.read_record_definitions().map_err(|e| error_node(0, &e.into()))?;
+        debug_assert_eq!(decoder.position(), definitions_end);
+
+        let mut dissector = Dissector { data, decoder, stack: Vec::new() };
+        let result = dissector.dissect_root(out);
+        if let Err(error) = result {
+            // Close any containers left open so their partial contents are kept
+            let end = dissector.decoder.position();
+            let mut pending = Some(error);
+            while let Some(mut frame) = dissector.stack.pop() {
+                frame.node.children.extend(pending.take());
+                frame.node.length = end - frame.node.offset;
+                pending = Some(frame.node);
+            }
+            out.extend(pending);
+            return Ok(());
+        }
+
+        let end = dissector.decoder.position();
+        if end < data.len() {
+            let mut trailing = DissectNode::new(end, None, "trailing");
+            trailing.length = data.len() - end;
+            out.push(trailing);
+        }
+        dissector.decoder.finish().map_err(|e| error_node(end, &e.into()))
+    }
+
+    fn dissect_root(&mut self, out: &mut Vec<DissectNode>) -> std::result::Result<(), DissectNode> {
+        loop {
+            let start = self.decoder.position();
+            let event = self.decoder.decode_value().map_err(|e| error_node(start, &e.into()))?;
+            let tc = self.data[start];
+            let label = self.next_label(&event);
+
+            let mut node = DissectNode::coded(start, tc);
+            node.label = label;
+            let complete = match event {
+                DecodedValue::ArrayStart => {
+                    self.stack.push(Frame { node, kind: FrameKind::Array });
+                    None
+                }
+                DecodedValue::ObjectStart => {
+                    let kind = FrameKind::Object { pending_key: None, expect_key: true };
+                    self.stack.push(Frame { node, kind });
+                    None
+                }
+                DecodedValue::RecordInstanceStart(def_index) => {
+                    node.value = Some(Value::from(def_index as u64));
+                    self.stack.push(Frame { node, kind: FrameKind::Record { def_index, next: 0 } });
+                    None
+                }
+                DecodedValue::ContainerEnd => {
+                    node.length = self.decoder.position() - start;
+                    let mut frame = self.stack.pop().expect("decoder checks container balance");
+                    frame.node.children.push(node);
+                    frame.node.length = self.decoder.position() - frame.node.offset;
+                    Some(frame.node)
+                }
+                DecodedValue::TypedArrayStart { element_type_code, count } => {
+                    node.value = Some(Value::from(count as u64));
+                    let scalar_name = type_code::name(type_code::typed_array_scalar_code(element_type_code));
+                    for _ in 0..count {
+                        let offset = self.decoder.position();
+                        let elem = match self.decoder.read_typed_array_element(element_type_code) {
+                            Ok(elem) => elem,
+                            Err(e) => {
+                                // Keep the elements read so far, with the error after them
+                                self.stack.push(Frame { node, kind: FrameKind::Array });
+                                return Err(error_node(offset, &e.into()));
+                            }
+                        };
+                        let mut child = DissectNode::new(offset, None, scalar_name);
+                        child.length = self.decoder.position() - offset;
+                        child.value = Some(crate::typed_array_element_value(self.decoder.config(), element_type_code, elem));
+                        node.children.push(child);
+                    }
+                    self.decoder.end_typed_array().map_err(|e| error_node(start, &e.into()))?;
+                    node.length = self.decoder.position() - start;
+                    Some(node)
+                }
+                scalar => {
+                    node.length = self.decoder.position() - start;
+                    node.value = Some(scalar_value(scalar));
+                    Some(node)
+                }
+            };
+
+            if let Some(node) = complete {
+                match self.stack.last_mut() {
+                    Some(parent) => parent.node.children.push(node),
+                    None => {
+                        out.push(node);
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Work out the label for the value about to be added to the current
+    /// container, advancing the container's key state.
+    fn next_label(&mut self, event: &DecodedValue<'_>) -> Option<String> {
+        if matches!(event, DecodedValue::ContainerEnd) {
+            return None;
+        }
+        let frame = self.stack.last_mut()?;
+        match &mut frame.kind {
+            FrameKind::Array => None,
+            FrameKind::Object { pending_key, expect_key } => {
+                if *expect_key {
+                    *expect_key = false;
+                    if let DecodedValue::String(key) = event {
+                        *pending_key = Some(key.to_string());
+                    }
+                    None
+                } else {
+                    *expect_key = true;
+                    pending_key.take()
+                }
+            }
+            FrameKind::Record { def_index, next } => {
+                let label = self.decoder.record_definitions()[*def_index].get(*next).cloned();
+                *next += 1;
+                label
+            }
+        }
+    }
+}
+
+/// Dissect the record definitions at the start of the document into `out`,
+/// returning the offset just past them.
+fn dissect_record_definitions(
+    data: &[u8],
+    config: &DecoderConfig,
+    out: &mut Vec<DissectNode>,
+) -> std::result::Result<usize, DissectNode> {
+    let mut pos = 0;
+    while data.get(pos) == Some(&type_code::RECORD_DEF) {
+        let mut def = DissectNode::coded(pos, type_code::RECORD_DEF);
+        let mut keys = Decoder::resume(data, config.clone(), Arc::default(), pos + 1);
+        let error = loop {
+            let start = keys.position();
+            let Some(&tc) = data.get(start) else {
+                break Some(error_node(start, &crate::Error::Truncated));
+            };
+            if tc == type_code::CONTAINER_END {
+                let mut end = DissectNode::coded(start, tc);
+                end.length = 1;
+                def.children.push(end);
+                break None;
+            }
+            match keys.decode_value() {
+                Ok(DecodedValue::String(key)) => {
+                    let mut node = DissectNode::coded(start, tc);
+                    node.length = keys.position() - start;
+                    node.value = Some(Value::String(key.into_owned()));
+                    def.children.push(node);
+                }
+                Ok(_) => {
+                    let e = crate::Error::InvalidData("record definition key must be a string".into());
+                    break Some(error_node(start, &e));
+                }
+                Err(e) => break Some(error_node(start, &e.into())),
+            }
+        };
+        def.length = def.children.last().map_or(1, |last| last.offset + last.length - pos);
+        out.push(def);
+        if let Some(error) = error {
+            return Err(error);
+        }
+        pos += out.last().map_or(0, |def| def.length);
+    }
+    Ok(pos)
+}
+
+fn scalar_value(event: DecodedValue<'_>) -> Value {
+    match event {
+        DecodedValue::Null => Value::Null,
+        DecodedValue::Bool(b) => Value::Bool(b),
+        DecodedValue::Int(n) => Value::Int(n),
+        DecodedValue::UInt(n) => Value::UInt(n),
+        DecodedValue::Float(f) => Value::Float(f),
+        DecodedValue::BigNumber(bn) => Value::BigNumber(bn),
+        DecodedValue::String(s) => Value::String(s.into_owned()),
+        _ => unreachable!("containers are handled by the caller"),
+    }
+}
+
+// ============================================================================
+// Pretty text
+// ============================================================================
+
+/// Render a document as indented, JSON-like text for logs and test snapshots.
+///
+/// Objects and arrays are laid out one member per line, indented by two
+/// spaces, with comments marking what JSON can't show: `/* bignumber */`
+/// before a BigNumber (in its `significand e exponent` form), `/* record #N */`
+/// before a record instance (with the keys from its definition), and
+/// `/* float64[3] */` before a typed array, whose elements stay on one line.
+/// The text is for reading, not for parsing back.
+///
+/// ```rust
+/// use serde_bonjson::tools;
+///
+/// let bytes = serde_bonjson::to_vec(&(vec![0.1f64, 0.2, 0.3], "label")).unwrap();
+/// assert_eq!(tools::pretty_text(&bytes).unwrap(), "\
+/// [
+///   /* float64[3] */ [0.1, 0.2, 0.3],
+///   \"label\"
+/// ]");
+/// ```
+///
+/// # Errors
+///
+/// Returns any error from decoding the document.
+pub fn pretty_text(data: &[u8]) -> crate::Result<String> {
+    pretty_text_with_config(data, DecoderConfig::default())
+}
+
+/// Render a document as [`pretty_text`] does, with custom decoder configuration.
+///
+/// # Errors
+///
+/// Returns any error from decoding the document.
+pub fn pretty_text_with_config(data: &[u8], config: DecoderConfig) -> crate::Result<String> {
+    let mut decoder = Decoder::with_config(data, config);
+    decoder.check_document_size()?;
+    decoder.read_record_definitions()?;
+
+    let mut writer = PrettyWriter::default();
+    let mut stack: Vec<FrameKind> = Vec::new();
+    loop {
+        let event = decoder.decode_value()?;
+        if matches!(event, DecodedValue::ContainerEnd) {
+            let frame = stack.pop().expect("decoder checks container balance");
+            writer.close(if matches!(frame, FrameKind::Array) { ']' } else { '}' });
+        } else {
+            let key = match stack.last_mut() {
+                Some(FrameKind::Object { pending_key, expect_key }) => {
+                    *expect_key = !*expect_key;
+                    if !*expect_key {
+                        // This is the key; the value that follows is written with it
+                        let DecodedValue::String(key) = event else {
+                            return Err(crate::Error::ExpectedObjectKey);
+                        };
+                        *pending_key = Some(key.into_owned());
+                        continue;
+                    }
+                    pending_key.take()
+                }
+                Some(FrameKind::Record { def_index, next }) => {
+                    *next += 1;
+                    decoder.record_definitions()[*def_index].get(*next - 1).cloned()
+                }
+                Some(FrameKind::Array) | None => None,
+            };
+            let key = key.as_deref();
+            match event {
+                DecodedValue::ArrayStart => {
+                    writer.open(key, None, '[');
+                    stack.push(FrameKind::Array);
+                }
+                DecodedValue::ObjectStart => {
+                    writer.open(key, None, '{');
+                    stack.push(FrameKind::Object { pending_key: None, expect_key: true });
+                }
+                DecodedValue::RecordInstanceStart(def_index) => {
+                    writer.open(key, Some(&format!("record #{def_index}")), '{');
+                    stack.push(FrameKind::Record { def_index, next: 0 });
+                }
+                DecodedValue::TypedArrayStart { element_type_code, count } => {
+                    let scalar_name = type_code::name(type_code::typed_array_scalar_code(element_type_code));
+                    let mut elements = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        let elem = decoder.read_typed_array_element(element_type_code)?;
+                        let (_, text) = pretty_scalar(&crate::typed_array_element_value(decoder.config(), element_type_code, elem));
+                        elements.push(text);
+                    }
+                    decoder.end_typed_array()?;
+                    let annotation = format!("{scalar_name}[{count}]");
+                    writer.scalar(key, Some(&annotation), &format!("[{}]", elements.join(", ")));
+                }
+                scalar => {
+                    let (annotation, text) = pretty_scalar(&scalar_value(scalar));
+                    writer.scalar(key, annotation, &text);
+                }
+            }
+        }
+        if stack.is_empty() {
+            decoder.finish()?;
+            return Ok(writer.out);
+        }
+    }
+}
+
+/// Serialize a value and render it as [`pretty_text`] does, for logging what
+/// a type encodes to.
+///
+/// ```rust
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Reading {
+///     sensor: &'static str,
+///     samples: Vec<f32>,
+/// }
+///
+/// let text = serde_bonjson::to_debug_string(&Reading { sensor: "t1", samples: vec![0.5, 0.25] }).unwrap();
+/// assert_eq!(text, "{\n  \"sensor\": \"t1\",\n  \"samples\": /* float32[2] */ [0.5, 0.25]\n}");
+/// ```
+///
+/// # Errors
+///
+/// Returns any error from serializing the value.
+pub fn to_debug_string<T: serde::Serialize>(value: &T) -> crate::Result<String> {
+    pretty_text(&crate::to_vec(value)?)
+}
+
+/// The text of [`Value::to_pretty_text`].
+pub(crate) fn value_pretty_text(value: &Value) -> String {
+    let mut writer = PrettyWriter::default();
+    write_pretty_value(&mut writer, None, value);
+    writer.out
+}
+
+fn write_pretty_value(writer: &mut PrettyWriter, key: Option<&str>, value: &Value) {
+    match value {
+        Value::Array(items) => {
+            writer.open(key, None, '[');
+            for item in items {
+                write_pretty_value(writer, None, item);
+            }
+            writer.close(']');
+        }
+        Value::Object(map) => {
+            writer.open(key, None, '{');
+            for (k, v) in map {
+                write_pretty_value(writer, Some(k), v);
+            }
+            writer.close('}');
+        }
+        scalar => {
+            let (annotation, text) = pretty_scalar(scalar);
+            writer.scalar(key, annotation, &text);
+        }
+    }
+}
+
+/// A scalar's annotation, if it has one, and its text.
+fn pretty_scalar(value: &Value) -> (Option<&'static str>, String) {
+    match value {
+        Value::Float(f) if f.is_finite() => (None, format!("{f:?}")),
+        Value::SizedFloat(f, width) => {
+            let text = if f.is_finite() { format!("{f:?}") } else { Value::Float(*f).to_string() };
+            (Some(if *width == crate::FloatWidth::F32 { "float32" } else { "float64" }), text)
+        }
+        Value::BigNumber(bn) => (Some("bignumber"), bn.to_string()),
+        Value::String(s) => {
+            let mut text = String::new();
+            write_json_string(&mut text, s);
+            (None, text)
+        }
+        // Null, booleans, integers, and NaN or infinities as NaN, Infinity and -Infinity
+        other => (None, other.to_string()),
+    }
+}
+
+/// Lays out the lines of [`pretty_text`].
+#[derive(Default)]
+struct PrettyWriter {
+    out: String,
+    /// For each open container, whether anything has been written in it.
+    open: Vec<bool>,
+}
+
+impl PrettyWriter {
+    /// Start a member of the current container: separator, indent, key and annotation.
+    fn begin_member(&mut self, key: Option<&str>, annotation: Option<&str>) {
+        if let Some(written) = self.open.last_mut() {
+            if *written {
+                self.out.push(',');
+            }
+            *written = true;
+            self.out.push('\n');
+            let _ = write!(self.out, "{:indent$}", "", indent = self.open.len() * 2);
+        }
+        if let Some(key) = key {
+            write_json_string(&mut self.out, key);
+            self.out.push_str(": ");
+        }
+        if let Some(annotation) = annotation {
+            let _ = write!(self.out, "/* {annotation} */ ");
+        }
+    }
+
+    fn scalar(&mut self, key: Option<&str>, annotation: Option<&str>, text: &str) {
+        self.begin_member(key, annotation);
+        self.out.push_str(text);
+    }
+
+    fn open(&mut self, key: Option<&str>, annotation: Option<&str>, bracket: char) {
+        self.begin_member(key, annotation);
+        self.out.push(bracket);
+        self.open.push(false);
+    }
+
+    fn close(&mut self, bracket: char) {
+        if self.open.pop() == Some(true) {
+            self.out.push('\n');
+            let _ = write!(self.out, "{:indent$}", "", indent = self.open.len() * 2);
+        }
+        self.out.push(bracket);
+    }
+}
+
+// ============================================================================
+// JSON output
+// ============================================================================
+
+pub(crate) fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_json_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => {
+            let _ = write!(out, "{b}");
+        }
+        Value::Int(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Value::UInt(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Value::Float(f) | Value::SizedFloat(f, _) if f.is_finite() => {
+            let _ = write!(out, "{f:?}");
+        }
+        Value::Float(f) | Value::SizedFloat(f, _) => write_json_string(out, &Value::Float(*f).to_string()),
+        Value::BigNumber(bn) => write_json_string(out, &bn.to_string_notation()),
+        Value::String(s) => write_json_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            out.push('{');
+            for (i, (k, v)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json_string(out, k);
+                out.push(':');
+                write_json_value(out, v);
+            }
+            out.push('}');
+        }
+    }
+}
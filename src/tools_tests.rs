@@ -0,0 +1,190 @@
+// ABOUTME: Unit tests for the tools module.
+// ABOUTME: Tests dissection trees, error reporting, JSON output, the event log, and pretty text.
+
+use crate::tools::{dissect, dissect_json, fmt_events, fmt_events_with_config, pretty_text, DissectNode};
+use crate::{bonjson, encode_value, to_debug_string, type_code, BigNumber, DecoderConfig, FloatWidth, Value};
+
+fn names(node: &DissectNode) -> Vec<&'static str> {
+    node.children.iter().map(|c| c.type_name).collect()
+}
+
+#[test]
+fn test_dissect_object() {
+    let bytes = encode_value(&bonjson!({"a": 1, "b": [true, null]})).unwrap();
+    let doc = dissect(&bytes);
+    assert!(doc.error().is_none());
+    assert_eq!(doc.length, bytes.len());
+
+    let root = &doc.children[0];
+    assert_eq!(root.type_name, "object");
+    assert_eq!((root.offset, root.length), (0, bytes.len()));
+    assert_eq!(
+        names(root),
+        vec!["short_string", "small_int", "short_string", "array", "container_end"]
+    );
+    let a = &root.children[1];
+    assert_eq!(a.label.as_deref(), Some("a"));
+    assert_eq!(a.value, Some(bonjson!(1)));
+    assert_eq!((a.offset, a.length), (3, 1));
+
+    let b = &root.children[3];
+    assert_eq!(b.label.as_deref(), Some("b"));
+    assert_eq!(names(b), vec!["true", "null", "container_end"]);
+    assert_eq!(b.length, 4);
+}
+
+#[test]
+fn test_dissect_records_and_typed_arrays() {
+    let value = bonjson!([{"x": 0.1, "y": "p"}, {"x": 0.2, "y": "q"}, [0.1, 0.2, 0.3]]);
+    let bytes = encode_value(&value).unwrap();
+    let doc = dissect(&bytes);
+    assert!(doc.error().is_none());
+
+    let def = &doc.children[0];
+    assert_eq!(def.type_name, "record_definition");
+    assert_eq!(names(def), vec!["short_string", "short_string", "container_end"]);
+    assert_eq!(def.children[0].value, Some(bonjson!("x")));
+
+    let root = &doc.children[1];
+    assert_eq!(root.offset, def.length);
+    let instance = &root.children[0];
+    assert_eq!(instance.type_name, "record_instance");
+    assert_eq!(instance.value, Some(Value::from(0u64)));
+    assert_eq!(instance.children[0].label.as_deref(), Some("x"));
+    assert_eq!(instance.children[1].label.as_deref(), Some("y"));
+
+    let typed = &root.children[2];
+    assert_eq!(typed.type_code, Some(type_code::TYPED_ARRAY_FLOAT64));
+    assert_eq!(typed.value, Some(Value::from(3u64)));
+    assert_eq!(names(typed), vec!["float64"; 3]);
+    assert_eq!(typed.children[1].value, Some(bonjson!(0.2)));
+    assert_eq!(typed.children[1].length, 8);
+}
+
+#[test]
+fn test_dissect_reports_errors_in_place() {
+    let mut bytes = encode_value(&bonjson!({"list": [1, "two", 3]})).unwrap();
+    bytes.truncate(bytes.len() - 3); // cut inside the list
+    let doc = dissect(&bytes);
+
+    let error = doc.error().expect("truncated document");
+    assert_eq!(error.offset, bytes.len());
+    // The partially read containers are kept, with the error at the innermost level
+    let list = &doc.children[0].children[1];
+    assert_eq!(names(list), vec!["small_int", "short_string", "error"]);
+
+    // Likewise inside a typed array
+    let mut bytes = encode_value(&bonjson!([0.1, 0.2, 0.3])).unwrap();
+    bytes.truncate(bytes.len() - 4);
+    let doc = dissect(&bytes);
+    assert_eq!(names(&doc.children[0]), vec!["float64", "float64", "error"]);
+
+    let mut bytes = encode_value(&bonjson!(1)).unwrap();
+    bytes.push(0x02);
+    let doc = dissect(&bytes);
+    assert_eq!(names(&doc), vec!["small_int", "trailing", "error"]);
+    assert_eq!(doc.children[1].length, 1);
+}
+
+#[test]
+fn test_dissect_json_output() {
+    let bytes = encode_value(&bonjson!({"k\"": 1.5})).unwrap();
+    let json = dissect_json(&bytes);
+    assert!(json.starts_with(r#"{"offset":0,"length":10,"type":"document","children":[{"offset":0,"#));
+    assert!(json.contains(r#""type":"short_string","code":103,"value":"k\"""#));
+    assert!(json.contains(r#""type":"float32","code":176,"label":"k\"","value":1.5"#));
+
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed["children"][0]["type"], "object");
+}
+
+#[test]
+fn test_fmt_events() {
+    let value = bonjson!([{"x": 1, "y": "p"}, {"x": 2, "y": "q"}, [0.1, 0.2]]);
+    let bytes = encode_value(&value).unwrap();
+    assert_eq!(
+        fmt_events(&bytes),
+        "\
+0000  record_definition
+0001    short_string \"x\"
+0003    short_string \"y\"
+0005    container_end
+0006  array
+0007    record_instance #0
+0009      \"x\": small_int 1
+000a      \"y\": short_string \"p\"
+000c      container_end
+000d    record_instance #0
+000f      \"x\": small_int 2
+0010      \"y\": short_string \"q\"
+0012      container_end
+0013    typed_array_float64 x2
+0015      float64 0.1
+001d      float64 0.2
+0025    container_end
+"
+    );
+}
+
+#[test]
+fn test_fmt_events_errors_and_trailing() {
+    let text = fmt_events(&[0xb7, 0x01]);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[..2], ["0000  array", "0001    small_int 1"]);
+    assert!(lines[2].starts_with("0002    error: "));
+
+    let config = DecoderConfig { allow_trailing_bytes: true, ..Default::default() };
+    assert_eq!(fmt_events_with_config(&[0x01, 0x02, 0x03], config), "0000  small_int 1\n0001  trailing (2 bytes)\n");
+}
+
+#[test]
+fn test_pretty_text_records_and_big_numbers() {
+    let value = bonjson!({
+        "points": [{"x": 1, "y": 2}, {"x": 3, "y": 4}],
+        "price": (Value::BigNumber(BigNumber::new(1, 12345, -2))),
+        "empty": [],
+        "none": {}
+    });
+    let bytes = encode_value(&value).unwrap();
+    assert_eq!(
+        pretty_text(&bytes).unwrap(),
+        r#"{
+  "empty": [],
+  "none": {},
+  "points": [
+    /* record #0 */ {
+      "x": 1,
+      "y": 2
+    },
+    /* record #0 */ {
+      "x": 3,
+      "y": 4
+    }
+  ],
+  "price": /* bignumber */ 12345e-2
+}"#
+    );
+    // The Value form has no records, but the same layout
+    let text = value.to_pretty_text();
+    assert!(text.contains("  \"points\": [\n    {\n      \"x\": 1,"), "{text}");
+    assert!(text.ends_with("  \"price\": /* bignumber */ 12345e-2\n}"), "{text}");
+}
+
+#[test]
+fn test_pretty_text_scalars() {
+    assert_eq!(pretty_text(&encode_value(&bonjson!("a\"b\n")).unwrap()).unwrap(), r#""a\"b\n""#);
+    assert_eq!(pretty_text(&encode_value(&bonjson!([null, true, (-7), 0.5])).unwrap()).unwrap(), "[\n  null,\n  true,\n  -7,\n  0.5\n]");
+    assert_eq!(Value::SizedFloat(1.5, FloatWidth::F64).to_pretty_text(), "/* float64 */ 1.5");
+    assert_eq!(bonjson!([(f64::NAN), 2.0]).to_pretty_text(), "[\n  NaN,\n  2.0\n]");
+    assert_eq!(to_debug_string(&vec![-1000i16, 3000, 2000, -2000]).unwrap(), "/* sint16[4] */ [-1000, 3000, 2000, -2000]");
+}
+
+#[test]
+fn test_pretty_text_errors() {
+    let bytes = encode_value(&bonjson!({"a": [1, 2]})).unwrap();
+    assert!(matches!(pretty_text(&bytes[..bytes.len() - 1]), Err(crate::Error::Truncated)));
+    assert!(pretty_text(&[type_code::OBJECT, 0x01, 0x02, type_code::CONTAINER_END]).is_err());
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(pretty_text(&trailing), Err(crate::Error::TrailingBytes(_))));
+}
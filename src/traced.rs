@@ -0,0 +1,489 @@
+// ABOUTME: A Deserializer wrapper that records the path of the value being deserialized when an error occurs.
+// ABOUTME: Used to annotate typed-conversion errors with where in the document they happened.
+
+use crate::error::Error;
+use crate::jsonpath::{JsonPath, PathSegment};
+use serde::de::{self, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Where the value being deserialized sits, as a chain of stack frames that
+/// is only turned into a [`JsonPath`] when something fails.
+enum Chain<'a> {
+    Root,
+    Index { parent: &'a Chain<'a>, index: usize },
+    /// A member or enum variant; a key that wasn't a string or number is `None`
+    /// and adds no segment.
+    Key { parent: &'a Chain<'a>, key: Option<String> },
+}
+
+static ROOT: Chain<'static> = Chain::Root;
+
+impl Chain<'_> {
+    fn to_path(&self) -> JsonPath {
+        let mut segments = Vec::new();
+        let mut chain = self;
+        loop {
+            chain = match chain {
+                Chain::Root => break,
+                Chain::Index { parent, index } => {
+                    segments.push(PathSegment::Index(*index));
+                    parent
+                }
+                Chain::Key { parent, key } => {
+                    segments.extend(key.clone().map(PathSegment::Key));
+                    parent
+                }
+            };
+        }
+        segments.reverse();
+        JsonPath::from(segments)
+    }
+}
+
+/// Remembers the path of the first error, which is the innermost one: errors
+/// are recorded on their way out, so outer frames see them after it.
+#[derive(Default)]
+pub(crate) struct Track {
+    path: RefCell<Option<JsonPath>>,
+}
+
+impl Track {
+    /// A seed that deserializes a `T` from the document root, recording here
+    /// where any error happens.
+    pub(crate) fn seed<T>(&self) -> TracedSeed<'_, PhantomData<T>> {
+        TracedSeed { seed: PhantomData, chain: &ROOT, track: self }
+    }
+
+    /// Wrap `error` with the path where it happened, unless that's the root
+    /// and there's no `offset` to add either. An error outside the traced
+    /// value (in the record definitions, say) counts as at the root.
+    pub(crate) fn annotate(self, error: Error, offset: Option<usize>) -> Error {
+        let path = self.path.into_inner().unwrap_or_default();
+        if path.is_root() && offset.is_none() {
+            return error;
+        }
+        Error::AtPath { path, offset, error: Box::new(error) }
+    }
+
+    fn trigger(&self, chain: &Chain<'_>) {
+        let mut path = self.path.borrow_mut();
+        if path.is_none() {
+            *path = Some(chain.to_path());
+        }
+    }
+
+    fn observe<T, E>(&self, chain: &Chain<'_>, result: Result<T, E>) -> Result<T, E> {
+        if result.is_err() {
+            self.trigger(chain);
+        }
+        result
+    }
+}
+
+/// Deserializes through the wrapped seed, tracing every nested value.
+pub(crate) struct TracedSeed<'a, S> {
+    seed: S,
+    chain: &'a Chain<'a>,
+    track: &'a Track,
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for TracedSeed<'_, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<S::Value, D::Error> {
+        self.seed.deserialize(Traced { inner: deserializer, chain: self.chain, track: self.track })
+    }
+}
+
+/// Forward each `deserialize_*` method through the wrapper's `forward`.
+macro_rules! forward_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, D::Error> {
+                self.forward(visitor, |inner, visitor| inner.$method($($arg,)* visitor))
+            }
+        )*
+    };
+}
+
+macro_rules! deserializer_methods {
+    () => {
+        forward_deserialize! {
+            deserialize_any();
+            deserialize_bool();
+            deserialize_i8();
+            deserialize_i16();
+            deserialize_i32();
+            deserialize_i64();
+            deserialize_i128();
+            deserialize_u8();
+            deserialize_u16();
+            deserialize_u32();
+            deserialize_u64();
+            deserialize_u128();
+            deserialize_f32();
+            deserialize_f64();
+            deserialize_char();
+            deserialize_str();
+            deserialize_string();
+            deserialize_bytes();
+            deserialize_byte_buf();
+            deserialize_option();
+            deserialize_unit();
+            deserialize_unit_struct(name: &'static str);
+            deserialize_newtype_struct(name: &'static str);
+            deserialize_seq();
+            deserialize_tuple(len: usize);
+            deserialize_tuple_struct(name: &'static str, len: usize);
+            deserialize_map();
+            deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+            deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+            deserialize_identifier();
+            deserialize_ignored_any();
+        }
+
+        fn is_human_readable(&self) -> bool {
+            self.inner.is_human_readable()
+        }
+    };
+}
+
+/// Forward the scalar `visit_*` methods unchanged, running `$before` (if
+/// given) on the value first.
+macro_rules! forward_visit {
+    ($self:ident, $v:ident; $($method:ident($ty:ty);)*) => {
+        $(
+            fn $method<E: de::Error>($self, $v: $ty) -> Result<Self::Value, E> {
+                $self.inner.$method($v)
+            }
+        )*
+    };
+    ($self:ident, $v:ident => $before:expr; $($method:ident($ty:ty);)*) => {
+        $(
+            fn $method<E: de::Error>($self, $v: $ty) -> Result<Self::Value, E> {
+                $before;
+                $self.inner.$method($v)
+            }
+        )*
+    };
+}
+
+/// A Deserializer whose errors are recorded with the path of its value.
+struct Traced<'a, D> {
+    inner: D,
+    chain: &'a Chain<'a>,
+    track: &'a Track,
+}
+
+impl<'a, D> Traced<'a, D> {
+    fn forward<'de, V, F>(self, visitor: V, f: F) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+        V: Visitor<'de>,
+        F: FnOnce(D, TracedVisitor<'a, V>) -> Result<V::Value, D::Error>,
+    {
+        let visitor = TracedVisitor { inner: visitor, chain: self.chain, track: self.track };
+        self.track.observe(self.chain, f(self.inner, visitor))
+    }
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for Traced<'_, D> {
+    type Error = D::Error;
+
+    deserializer_methods!();
+}
+
+/// Passes scalars straight through and wraps the accessors for containers,
+/// options and newtypes so their contents are traced too.
+struct TracedVisitor<'a, V> {
+    inner: V,
+    chain: &'a Chain<'a>,
+    track: &'a Track,
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for TracedVisitor<'_, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    forward_visit! {
+        self, v;
+        visit_bool(bool);
+        visit_i8(i8);
+        visit_i16(i16);
+        visit_i32(i32);
+        visit_i64(i64);
+        visit_i128(i128);
+        visit_u8(u8);
+        visit_u16(u16);
+        visit_u32(u32);
+        visit_u64(u64);
+        visit_u128(u128);
+        visit_f32(f32);
+        visit_f64(f64);
+        visit_char(char);
+        visit_str(&str);
+        visit_borrowed_str(&'de str);
+        visit_string(String);
+        visit_bytes(&[u8]);
+        visit_borrowed_bytes(&'de [u8]);
+        visit_byte_buf(Vec<u8>);
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.inner.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.inner.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.inner.visit_some(Traced { inner: deserializer, chain: self.chain, track: self.track })
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.inner.visit_newtype_struct(Traced { inner: deserializer, chain: self.chain, track: self.track })
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        self.inner.visit_seq(TracedSeq { inner: seq, chain: self.chain, track: self.track, index: 0 })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        self.inner.visit_map(TracedMap { inner: map, chain: self.chain, track: self.track, key: None })
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        self.inner.visit_enum(TracedEnum { inner: data, chain: self.chain, track: self.track })
+    }
+}
+
+struct TracedSeq<'a, A> {
+    inner: A,
+    chain: &'a Chain<'a>,
+    track: &'a Track,
+    index: usize,
+}
+
+impl<'de, A: SeqAccess<'de>> SeqAccess<'de> for TracedSeq<'_, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, A::Error> {
+        let chain = Chain::Index { parent: self.chain, index: self.index };
+        self.index += 1;
+        let result = self.inner.next_element_seed(TracedSeed { seed, chain: &chain, track: self.track });
+        self.track.observe(&chain, result)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+struct TracedMap<'a, A> {
+    inner: A,
+    chain: &'a Chain<'a>,
+    track: &'a Track,
+    /// The key just read, for the path of its value.
+    key: Option<String>,
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for TracedMap<'_, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error> {
+        self.key = None;
+        let result = self.inner.next_key_seed(CaptureKeySeed { seed, key: &mut self.key });
+        // A rejected key (an unknown field, say) is reported at its own path
+        result.inspect_err(|_| self.track.trigger(&Chain::Key { parent: self.chain, key: self.key.clone() }))
+    }
+
+    fn next_value_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value, A::Error> {
+        let chain = Chain::Key { parent: self.chain, key: self.key.take() };
+        let result = self.inner.next_value_seed(TracedSeed { seed, chain: &chain, track: self.track });
+        self.track.observe(&chain, result)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+struct TracedEnum<'a, A> {
+    inner: A,
+    chain: &'a Chain<'a>,
+    track: &'a Track,
+}
+
+impl<'a, 'de, A: EnumAccess<'de>> EnumAccess<'de> for TracedEnum<'a, A> {
+    type Error = A::Error;
+    type Variant = TracedVariant<'a, A::Variant>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), A::Error> {
+        let mut variant = None;
+        let result = self.inner.variant_seed(CaptureKeySeed { seed, key: &mut variant });
+        let (value, access) = self.track.observe(self.chain, result)?;
+        Ok((value, TracedVariant { inner: access, parent: self.chain, variant, track: self.track }))
+    }
+}
+
+/// A variant's contents, traced under the variant's name.
+struct TracedVariant<'a, A> {
+    inner: A,
+    parent: &'a Chain<'a>,
+    variant: Option<String>,
+    track: &'a Track,
+}
+
+impl<'de, A: VariantAccess<'de>> VariantAccess<'de> for TracedVariant<'_, A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        let chain = Chain::Key { parent: self.parent, key: self.variant };
+        self.track.observe(&chain, self.inner.unit_variant())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, A::Error> {
+        let chain = Chain::Key { parent: self.parent, key: self.variant };
+        let result = self.inner.newtype_variant_seed(TracedSeed { seed, chain: &chain, track: self.track });
+        self.track.observe(&chain, result)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, A::Error> {
+        let chain = Chain::Key { parent: self.parent, key: self.variant };
+        let visitor = TracedVisitor { inner: visitor, chain: &chain, track: self.track };
+        let result = self.inner.tuple_variant(len, visitor);
+        self.track.observe(&chain, result)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        let chain = Chain::Key { parent: self.parent, key: self.variant };
+        let visitor = TracedVisitor { inner: visitor, chain: &chain, track: self.track };
+        let result = self.inner.struct_variant(fields, visitor);
+        self.track.observe(&chain, result)
+    }
+}
+
+/// Deserializes a map key or variant name, keeping a copy of it for the path.
+struct CaptureKeySeed<'k, S> {
+    seed: S,
+    key: &'k mut Option<String>,
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for CaptureKeySeed<'_, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<S::Value, D::Error> {
+        self.seed.deserialize(CaptureKey { inner: deserializer, key: self.key })
+    }
+}
+
+struct CaptureKey<'k, D> {
+    inner: D,
+    key: &'k mut Option<String>,
+}
+
+impl<'k, D> CaptureKey<'k, D> {
+    fn forward<'de, V, F>(self, visitor: V, f: F) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+        V: Visitor<'de>,
+        F: FnOnce(D, CaptureKeyVisitor<'k, V>) -> Result<V::Value, D::Error>,
+    {
+        f(self.inner, CaptureKeyVisitor { inner: visitor, key: self.key })
+    }
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for CaptureKey<'_, D> {
+    type Error = D::Error;
+
+    deserializer_methods!();
+}
+
+/// Records string, number and boolean keys before passing them on; other
+/// kinds of key go through unrecorded.
+struct CaptureKeyVisitor<'k, V> {
+    inner: V,
+    key: &'k mut Option<String>,
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for CaptureKeyVisitor<'_, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    forward_visit! {
+        self, v => *self.key = Some(v.to_string());
+        visit_bool(bool);
+        visit_i8(i8);
+        visit_i16(i16);
+        visit_i32(i32);
+        visit_i64(i64);
+        visit_i128(i128);
+        visit_u8(u8);
+        visit_u16(u16);
+        visit_u32(u32);
+        visit_u64(u64);
+        visit_u128(u128);
+        visit_char(char);
+        visit_str(&str);
+        visit_borrowed_str(&'de str);
+        visit_string(String);
+    }
+
+    forward_visit! {
+        self, v => *self.key = Some(String::from_utf8_lossy(v).into_owned());
+        visit_bytes(&[u8]);
+        visit_borrowed_bytes(&'de [u8]);
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        *self.key = Some(String::from_utf8_lossy(&v).into_owned());
+        self.inner.visit_byte_buf(v)
+    }
+
+    forward_visit! {
+        self, v;
+        visit_f32(f32);
+        visit_f64(f64);
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.inner.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.inner.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.inner.visit_some(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.inner.visit_newtype_struct(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        self.inner.visit_seq(seq)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        self.inner.visit_map(map)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        self.inner.visit_enum(data)
+    }
+}
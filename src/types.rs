@@ -4,6 +4,8 @@
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::cast_sign_loss)]
 
+use crate::error::{Error, Result};
+
 /// Type codes for BONJSON values.
 /// These match the BONJSON specification exactly.
 pub mod type_code {
@@ -212,6 +214,351 @@ pub mod type_code {
     pub const fn is_reserved(code: u8) -> bool {
         code >= 0xbb && code <= 0xf4
     }
+
+    /// The broad kind of value (or structural marker) a type code introduces.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Category {
+        /// 0x00-0x64: the code is the value.
+        SmallInt,
+        /// 0x65-0xa7: the code carries the byte length.
+        ShortString,
+        /// 0xff: delimiter-terminated string.
+        LongString,
+        /// 0xa8-0xaf: fixed-size little-endian integer.
+        Int,
+        /// 0xb0-0xb1: IEEE 754 float.
+        Float,
+        /// 0xb2: arbitrary-precision decimal.
+        BigNumber,
+        /// 0xb3.
+        Null,
+        /// 0xb4-0xb5.
+        Bool,
+        /// 0xb6: ends an array, object, record definition, or record instance.
+        ContainerEnd,
+        /// 0xb7.
+        Array,
+        /// 0xb8.
+        Object,
+        /// 0xb9.
+        RecordDefinition,
+        /// 0xba.
+        RecordInstance,
+        /// 0xf5-0xfe: length-prefixed homogeneous numeric array.
+        TypedArray,
+        /// 0xbb-0xf4: not valid in a document.
+        Reserved,
+    }
+
+    /// Get the category of a type code.
+    #[must_use]
+    pub const fn category(code: u8) -> Category {
+        match code {
+            SMALLINT_MIN..=SMALLINT_MAX => Category::SmallInt,
+            STRING0..=STRING_MAX => Category::ShortString,
+            UINT8..=SINT64 => Category::Int,
+            FLOAT32 | FLOAT64 => Category::Float,
+            BIG_NUMBER => Category::BigNumber,
+            NULL => Category::Null,
+            FALSE | TRUE => Category::Bool,
+            CONTAINER_END => Category::ContainerEnd,
+            ARRAY => Category::Array,
+            OBJECT => Category::Object,
+            RECORD_DEF => Category::RecordDefinition,
+            RECORD_INSTANCE => Category::RecordInstance,
+            TYPED_ARRAY_FLOAT64..=TYPED_ARRAY_UINT8 => Category::TypedArray,
+            STRING_LONG => Category::LongString,
+            _ => Category::Reserved,
+        }
+    }
+
+    /// Get a short, stable name for a type code (e.g. `"uint16"`, `"short_string"`,
+    /// `"typed_array_float32"`), suitable for tooling output.
+    #[must_use]
+    pub const fn name(code: u8) -> &'static str {
+        match code {
+            SMALLINT_MIN..=SMALLINT_MAX => "small_int",
+            STRING0..=STRING_MAX => "short_string",
+            UINT8 => "uint8",
+            UINT16 => "uint16",
+            UINT32 => "uint32",
+            UINT64 => "uint64",
+            SINT8 => "sint8",
+            SINT16 => "sint16",
+            SINT32 => "sint32",
+            SINT64 => "sint64",
+            FLOAT32 => "float32",
+            FLOAT64 => "float64",
+            BIG_NUMBER => "big_number",
+            NULL => "null",
+            FALSE => "false",
+            TRUE => "true",
+            CONTAINER_END => "container_end",
+            ARRAY => "array",
+            OBJECT => "object",
+            RECORD_DEF => "record_definition",
+            RECORD_INSTANCE => "record_instance",
+            TYPED_ARRAY_FLOAT64 => "typed_array_float64",
+            TYPED_ARRAY_FLOAT32 => "typed_array_float32",
+            TYPED_ARRAY_SINT64 => "typed_array_sint64",
+            TYPED_ARRAY_SINT32 => "typed_array_sint32",
+            TYPED_ARRAY_SINT16 => "typed_array_sint16",
+            TYPED_ARRAY_SINT8 => "typed_array_sint8",
+            TYPED_ARRAY_UINT64 => "typed_array_uint64",
+            TYPED_ARRAY_UINT32 => "typed_array_uint32",
+            TYPED_ARRAY_UINT16 => "typed_array_uint16",
+            TYPED_ARRAY_UINT8 => "typed_array_uint8",
+            STRING_LONG => "long_string",
+            _ => "reserved",
+        }
+    }
+
+    /// Get the number of bytes that follow a type code, when that is fixed by
+    /// the code alone.
+    ///
+    /// Returns `Some(0)` for codes that are complete by themselves (small
+    /// ints, null, booleans, container markers), the byte length for short
+    /// strings and fixed-size numbers, and `None` for everything whose size
+    /// depends on the payload (long strings, BigNumbers, typed arrays,
+    /// record instances) or is reserved.
+    #[must_use]
+    pub const fn fixed_payload_size(code: u8) -> Option<usize> {
+        match category(code) {
+            Category::SmallInt
+            | Category::Null
+            | Category::Bool
+            | Category::ContainerEnd
+            | Category::Array
+            | Category::Object
+            | Category::RecordDefinition => Some(0),
+            Category::ShortString => Some(short_string_len(code)),
+            Category::Int => Some(int_size(code)),
+            Category::Float => Some(if code == FLOAT32 { 4 } else { 8 }),
+            Category::LongString
+            | Category::BigNumber
+            | Category::RecordInstance
+            | Category::TypedArray
+            | Category::Reserved => None,
+        }
+    }
+
+    /// How the bytes after a type code are framed.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum PayloadKind {
+        /// Nothing follows; the code is the whole value or marker.
+        None,
+        /// A fixed number of bytes follow (short string contents, integers, floats).
+        Fixed(usize),
+        /// String bytes up to a closing `STRING_LONG`.
+        Delimited,
+        /// Zigzag LEB128 exponent and signed length, then the magnitude bytes.
+        BigNumber,
+        /// Values up to a `CONTAINER_END` (arrays, objects, record definitions).
+        Children,
+        /// A LEB128 record definition index, then values up to a `CONTAINER_END`.
+        RecordInstance,
+        /// A LEB128 element count, then elements of `element_size` bytes each.
+        TypedArray { element_size: usize },
+    }
+
+    /// A non-reserved type code as an enum, for exhaustive matching.
+    ///
+    /// The variants follow the specification rather than this module's
+    /// constants, so new constants or helpers don't change them; a variant is
+    /// only added or removed with a semver-incompatible release.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub enum TypeCode {
+        /// 0x00-0x64, holding the value (0-100).
+        SmallInt(u8),
+        /// 0x65-0xa7, holding the byte length (0-66).
+        ShortString(u8),
+        UInt8,
+        UInt16,
+        UInt32,
+        UInt64,
+        SInt8,
+        SInt16,
+        SInt32,
+        SInt64,
+        Float32,
+        Float64,
+        BigNumber,
+        Null,
+        False,
+        True,
+        ContainerEnd,
+        Array,
+        Object,
+        RecordDefinition,
+        RecordInstance,
+        TypedArrayFloat64,
+        TypedArrayFloat32,
+        TypedArraySInt64,
+        TypedArraySInt32,
+        TypedArraySInt16,
+        TypedArraySInt8,
+        TypedArrayUInt64,
+        TypedArrayUInt32,
+        TypedArrayUInt16,
+        TypedArrayUInt8,
+        LongString,
+    }
+
+    impl TypeCode {
+        /// Get the enum for a byte, or `None` if the code is reserved.
+        #[must_use]
+        pub const fn from_u8(code: u8) -> Option<Self> {
+            Some(match code {
+                SMALLINT_MIN..=SMALLINT_MAX => TypeCode::SmallInt(small_int_value(code)),
+                STRING0..=STRING_MAX => TypeCode::ShortString(code - STRING0),
+                UINT8 => TypeCode::UInt8,
+                UINT16 => TypeCode::UInt16,
+                UINT32 => TypeCode::UInt32,
+                UINT64 => TypeCode::UInt64,
+                SINT8 => TypeCode::SInt8,
+                SINT16 => TypeCode::SInt16,
+                SINT32 => TypeCode::SInt32,
+                SINT64 => TypeCode::SInt64,
+                FLOAT32 => TypeCode::Float32,
+                FLOAT64 => TypeCode::Float64,
+                BIG_NUMBER => TypeCode::BigNumber,
+                NULL => TypeCode::Null,
+                FALSE => TypeCode::False,
+                TRUE => TypeCode::True,
+                CONTAINER_END => TypeCode::ContainerEnd,
+                ARRAY => TypeCode::Array,
+                OBJECT => TypeCode::Object,
+                RECORD_DEF => TypeCode::RecordDefinition,
+                RECORD_INSTANCE => TypeCode::RecordInstance,
+                TYPED_ARRAY_FLOAT64 => TypeCode::TypedArrayFloat64,
+                TYPED_ARRAY_FLOAT32 => TypeCode::TypedArrayFloat32,
+                TYPED_ARRAY_SINT64 => TypeCode::TypedArraySInt64,
+                TYPED_ARRAY_SINT32 => TypeCode::TypedArraySInt32,
+                TYPED_ARRAY_SINT16 => TypeCode::TypedArraySInt16,
+                TYPED_ARRAY_SINT8 => TypeCode::TypedArraySInt8,
+                TYPED_ARRAY_UINT64 => TypeCode::TypedArrayUInt64,
+                TYPED_ARRAY_UINT32 => TypeCode::TypedArrayUInt32,
+                TYPED_ARRAY_UINT16 => TypeCode::TypedArrayUInt16,
+                TYPED_ARRAY_UINT8 => TypeCode::TypedArrayUInt8,
+                STRING_LONG => TypeCode::LongString,
+                _ => return None,
+            })
+        }
+
+        /// Get the byte this code is written as. Out-of-range `SmallInt`
+        /// values and `ShortString` lengths are clamped to the largest code.
+        #[must_use]
+        pub const fn to_u8(self) -> u8 {
+            match self {
+                TypeCode::SmallInt(value) => {
+                    if value > SMALLINT_MAX { SMALLINT_MAX } else { small_int_code(value) }
+                }
+                TypeCode::ShortString(len) => {
+                    if len > STRING_MAX - STRING0 { STRING_MAX } else { STRING0 + len }
+                }
+                TypeCode::UInt8 => UINT8,
+                TypeCode::UInt16 => UINT16,
+                TypeCode::UInt32 => UINT32,
+                TypeCode::UInt64 => UINT64,
+                TypeCode::SInt8 => SINT8,
+                TypeCode::SInt16 => SINT16,
+                TypeCode::SInt32 => SINT32,
+                TypeCode::SInt64 => SINT64,
+                TypeCode::Float32 => FLOAT32,
+                TypeCode::Float64 => FLOAT64,
+                TypeCode::BigNumber => BIG_NUMBER,
+                TypeCode::Null => NULL,
+                TypeCode::False => FALSE,
+                TypeCode::True => TRUE,
+                TypeCode::ContainerEnd => CONTAINER_END,
+                TypeCode::Array => ARRAY,
+                TypeCode::Object => OBJECT,
+                TypeCode::RecordDefinition => RECORD_DEF,
+                TypeCode::RecordInstance => RECORD_INSTANCE,
+                TypeCode::TypedArrayFloat64 => TYPED_ARRAY_FLOAT64,
+                TypeCode::TypedArrayFloat32 => TYPED_ARRAY_FLOAT32,
+                TypeCode::TypedArraySInt64 => TYPED_ARRAY_SINT64,
+                TypeCode::TypedArraySInt32 => TYPED_ARRAY_SINT32,
+                TypeCode::TypedArraySInt16 => TYPED_ARRAY_SINT16,
+                TypeCode::TypedArraySInt8 => TYPED_ARRAY_SINT8,
+                TypeCode::TypedArrayUInt64 => TYPED_ARRAY_UINT64,
+                TypeCode::TypedArrayUInt32 => TYPED_ARRAY_UINT32,
+                TypeCode::TypedArrayUInt16 => TYPED_ARRAY_UINT16,
+                TypeCode::TypedArrayUInt8 => TYPED_ARRAY_UINT8,
+                TypeCode::LongString => STRING_LONG,
+            }
+        }
+
+        /// Get the code's stable [name] (e.g. `"uint16"`).
+        #[must_use]
+        pub const fn name(self) -> &'static str {
+            name(self.to_u8())
+        }
+
+        /// Get the code's [category].
+        #[must_use]
+        pub const fn category(self) -> Category {
+            category(self.to_u8())
+        }
+
+        /// Get how the bytes after the code are framed.
+        #[must_use]
+        pub const fn payload_kind(self) -> PayloadKind {
+            let code = self.to_u8();
+            match category(code) {
+                Category::LongString => PayloadKind::Delimited,
+                Category::BigNumber => PayloadKind::BigNumber,
+                Category::Array | Category::Object | Category::RecordDefinition => PayloadKind::Children,
+                Category::RecordInstance => PayloadKind::RecordInstance,
+                Category::TypedArray => PayloadKind::TypedArray { element_size: typed_array_element_size(code) },
+                _ => match fixed_payload_size(code) {
+                    Some(0) | None => PayloadKind::None,
+                    Some(size) => PayloadKind::Fixed(size),
+                },
+            }
+        }
+    }
+
+    impl TryFrom<u8> for TypeCode {
+        type Error = crate::error::Error;
+
+        /// Fails with `Error::InvalidTypeCode` for reserved codes.
+        fn try_from(code: u8) -> Result<Self, Self::Error> {
+            TypeCode::from_u8(code).ok_or(crate::error::Error::InvalidTypeCode(code))
+        }
+    }
+
+    impl From<TypeCode> for u8 {
+        fn from(code: TypeCode) -> u8 {
+            code.to_u8()
+        }
+    }
+
+    impl std::fmt::Display for TypeCode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.name())
+        }
+    }
+
+    /// Get the scalar type code matching a typed array's element type
+    /// (e.g. `TYPED_ARRAY_SINT16` → `SINT16`).
+    /// Only valid when `is_typed_array()` returns true.
+    #[inline]
+    #[must_use]
+    pub const fn typed_array_scalar_code(code: u8) -> u8 {
+        match code {
+            TYPED_ARRAY_FLOAT64 => FLOAT64,
+            TYPED_ARRAY_FLOAT32 => FLOAT32,
+            TYPED_ARRAY_SINT64 => SINT64,
+            TYPED_ARRAY_SINT32 => SINT32,
+            TYPED_ARRAY_SINT16 => SINT16,
+            TYPED_ARRAY_SINT8 => SINT8,
+            TYPED_ARRAY_UINT64 => UINT64,
+            TYPED_ARRAY_UINT32 => UINT32,
+            TYPED_ARRAY_UINT16 => UINT16,
+            _ => UINT8,
+        }
+    }
 }
 
 /// Encode a signed i64 as zigzag encoding: 0→0, -1→1, 1→2, -2→3, ...
@@ -264,6 +611,38 @@ pub fn leb128_decode(data: &[u8]) -> Option<(u64, usize)> {
     None // Truncated
 }
 
+/// Get the number of bytes `value` takes as a LEB128 length field (1-10).
+#[inline]
+#[must_use]
+pub const fn length_field_size(value: u64) -> usize {
+    if value == 0 {
+        return 1;
+    }
+    let bits = 64 - value.leading_zeros() as usize;
+    bits.div_ceil(7)
+}
+
+/// Append a length field (LEB128), as used for typed array element counts
+/// and record definition indices. Returns the number of bytes written.
+pub fn encode_length_field(value: u64, out: &mut Vec<u8>) -> usize {
+    let mut buf = [0u8; 10];
+    let n = leb128_encode(value, &mut buf);
+    out.extend_from_slice(&buf[..n]);
+    n
+}
+
+/// Read a length field (LEB128) from the start of `data`.
+/// Returns the value and the number of bytes consumed.
+pub fn decode_length_field(data: &[u8]) -> Result<(u64, usize)> {
+    leb128_decode(data).ok_or_else(|| {
+        if data.iter().take(10).all(|b| b & 0x80 != 0) && data.len() < 10 {
+            Error::Truncated
+        } else {
+            Error::InvalidData("length field overflows 64 bits".into())
+        }
+    })
+}
+
 /// Lookup table mapping minimum byte count to CPU-native size index (0-3).
 /// Index 1→0, 2→1, 3→2, 4→2, 5→3, 6→3, 7→3, 8→3
 /// Used as: type_code = UINT8 + NATIVE_SIZE_INDEX[byte_count - 1]
@@ -273,6 +652,39 @@ pub const NATIVE_SIZE_INDEX: [u8; 8] = [0, 1, 2, 2, 3, 3, 3, 3];
 /// Index 1→1, 2→2, 3→4, 4→4, 5→8, 6→8, 7→8, 8→8
 pub const NATIVE_SIZE_BYTES: [usize; 8] = [1, 2, 4, 4, 8, 8, 8, 8];
 
+/// The width of an IEEE 754 float on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatWidth {
+    /// binary32, written with `type_code::FLOAT32`
+    F32,
+    /// binary64, written with `type_code::FLOAT64`
+    F64,
+}
+
+impl FloatWidth {
+    /// The width a float type code (scalar or typed array) is written with,
+    /// or None if `tc` isn't a float type code.
+    #[must_use]
+    pub const fn from_type_code(tc: u8) -> Option<Self> {
+        match tc {
+            type_code::FLOAT32 | type_code::TYPED_ARRAY_FLOAT32 => Some(FloatWidth::F32),
+            type_code::FLOAT64 | type_code::TYPED_ARRAY_FLOAT64 => Some(FloatWidth::F64),
+            _ => None,
+        }
+    }
+
+    /// Returns true if `value` survives a round trip through this width, bit
+    /// for bit (so a NaN only if float32 keeps its payload).
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn holds(self, value: f64) -> bool {
+        match self {
+            FloatWidth::F32 => f64::from(value as f32).to_bits() == value.to_bits(),
+            FloatWidth::F64 => true,
+        }
+    }
+}
+
 /// A big number with arbitrary precision base-10 representation.
 ///
 /// The value is: sign(signed_length) × magnitude × 10^exponent
@@ -344,14 +756,11 @@ impl BigNumber {
             return None;
         }
 
-        let multiplier = 10i64.checked_pow(self.exponent as u32)?;
-        let abs_value = (self.significand as i64).checked_mul(multiplier)?;
-
-        if self.sign < 0 {
-            abs_value.checked_neg()
-        } else {
-            Some(abs_value)
-        }
+        // Work in i128 so significands above i64::MAX are range-checked
+        // instead of wrapping.
+        let multiplier = 10i128.checked_pow(self.exponent as u32)?;
+        let value = self.signed_significand().checked_mul(multiplier)?;
+        i64::try_from(value).ok()
     }
 
     /// Try to convert this `BigNumber` to a u64.
@@ -373,11 +782,44 @@ impl BigNumber {
         self.significand.checked_mul(multiplier)
     }
 
+    /// Try to convert this `BigNumber` to an i128.
+    /// Returns None if the value cannot be represented exactly.
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.exponent < 0 || self.exponent > 38 {
+            return None;
+        }
+
+        let multiplier = 10i128.checked_pow(self.exponent as u32)?;
+        self.signed_significand().checked_mul(multiplier)
+    }
+
+    /// Try to convert this `BigNumber` to a u128.
+    /// Returns None if the value cannot be represented exactly.
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.sign < 0 && self.significand != 0 {
+            return None;
+        }
+        if self.exponent < 0 || self.exponent > 38 {
+            return None;
+        }
+
+        let multiplier = 10u128.checked_pow(self.exponent as u32)?;
+        u128::from(self.significand).checked_mul(multiplier)
+    }
+
     /// Try to convert this `BigNumber` to an f64.
     /// This may lose precision for very large or very precise numbers.
     #[must_use]
     #[allow(clippy::cast_precision_loss)]
     pub fn to_f64(&self) -> f64 {
+        // Zero times 10^309 and beyond would be 0 * inf
+        if self.significand == 0 {
+            return 0.0;
+        }
         let sign = if self.sign < 0 { -1.0 } else { 1.0 };
         let significand = self.significand as f64;
         let exponent = 10.0f64.powi(self.exponent as i32);
@@ -401,16 +843,43 @@ impl BigNumber {
         Self::new(1, value, 0)
     }
 
+    /// Create a `BigNumber` from an i128.
+    /// Returns None if the value has more significant digits than fit in a u64.
+    #[must_use]
+    pub fn from_i128(value: i128) -> Option<Self> {
+        let sign = if value < 0 { -1 } else { 1 };
+        Self::from_u128_magnitude(sign, value.unsigned_abs())
+    }
+
+    /// Create a `BigNumber` from a u128.
+    /// Returns None if the value has more significant digits than fit in a u64.
+    #[must_use]
+    pub fn from_u128(value: u128) -> Option<Self> {
+        Self::from_u128_magnitude(1, value)
+    }
+
+    /// Move trailing decimal zeros into the exponent until the magnitude fits the significand.
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_u128_magnitude(sign: i8, mut magnitude: u128) -> Option<Self> {
+        let mut exponent = 0i64;
+        while magnitude > u128::from(u64::MAX) {
+            if !magnitude.is_multiple_of(10) {
+                return None;
+            }
+            magnitude /= 10;
+            exponent += 1;
+        }
+        Some(Self::new(sign, magnitude as u64, exponent))
+    }
+
     /// Format this BigNumber as a string in exponential notation.
     /// Format: ["-"]<significand>"e"<exponent>
     /// Examples: "1e6", "-1e6", "15e5", "0e0"
+    ///
+    /// The same as its `Display` form; [`str::parse`] reads it back.
     #[must_use]
     pub fn to_string_notation(&self) -> String {
-        if self.significand == 0 {
-            return "0e0".to_string();
-        }
-        let sign = if self.sign < 0 { "-" } else { "" };
-        format!("{}{}e{}", sign, self.significand, self.exponent)
+        self.to_string()
     }
 }
 
@@ -420,6 +889,166 @@ impl Default for BigNumber {
     }
 }
 
+impl std::fmt::Display for BigNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.significand == 0 {
+            return f.write_str("0e0");
+        }
+        let sign = if self.sign < 0 { "-" } else { "" };
+        write!(f, "{}{}e{}", sign, self.significand, self.exponent)
+    }
+}
+
+impl std::str::FromStr for BigNumber {
+    type Err = Error;
+
+    /// Parse decimal notation: an optional sign, digits with an optional
+    /// decimal point, and an optional exponent (`12`, `-0.5`, `1.23e+45`,
+    /// `15e-1`).
+    ///
+    /// The digits are kept as written (`1.50` is `150e-2`), except that
+    /// trailing zeros move into the exponent when the significand would
+    /// otherwise overflow a u64. Zero parses as positive zero, since
+    /// BigNumber can't represent negative zero.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidData(format!("invalid BigNumber: {s:?}"));
+        let (sign, rest) = match s.as_bytes().first() {
+            Some(b'-') => (-1, &s[1..]),
+            Some(b'+') => (1, &s[1..]),
+            _ => (1, s),
+        };
+        let (mantissa, exponent) = match rest.find(['e', 'E']) {
+            Some(at) => (&rest[..at], Some(&rest[at + 1..])),
+            None => (rest, None),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if integer.len() + fraction.len() == 0 || !is_digits(integer) || !is_digits(fraction) {
+            return Err(invalid());
+        }
+        let exponent = match exponent {
+            Some(e) => {
+                let digits = e.strip_prefix(['+', '-']).unwrap_or(e);
+                if digits.is_empty() || !is_digits(digits) {
+                    return Err(invalid());
+                }
+                e.parse::<i64>().map_err(|_| Error::ValueOutOfRange)?
+            }
+            None => 0,
+        };
+        let exponent = i64::try_from(fraction.len())
+            .ok()
+            .and_then(|len| exponent.checked_sub(len))
+            .ok_or(Error::ValueOutOfRange)?;
+
+        let digits = format!("{integer}{fraction}");
+        let digits = digits.trim_start_matches('0');
+        if digits.is_empty() {
+            return Ok(Self::zero());
+        }
+        // Only zeros that don't fit the significand move into the exponent
+        let significant = digits.trim_end_matches('0');
+        let keep = digits.len().min(significant.len().max(20));
+        let mut shift = digits.len() - keep;
+        let mut significand: u128 = digits[..keep].parse().map_err(|_| Error::ValueOutOfRange)?;
+        while significand > u128::from(u64::MAX) && significand.is_multiple_of(10) {
+            significand /= 10;
+            shift += 1;
+        }
+        let significand = u64::try_from(significand).map_err(|_| Error::ValueOutOfRange)?;
+        let exponent = i64::try_from(shift)
+            .ok()
+            .and_then(|shift| exponent.checked_add(shift))
+            .ok_or(Error::ValueOutOfRange)?;
+        Ok(Self::new(sign, significand, exponent))
+    }
+}
+
+impl TryFrom<&str> for BigNumber {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self> {
+        s.parse()
+    }
+}
+
+/// The newtype name a `BigNumber` serializes under. This crate's Serializer
+/// and Deserializer recognize it and read or write a BONJSON BigNumber;
+/// other formats see the decimal string inside.
+pub(crate) const BIG_NUMBER_TOKEN: &str = "$serde_bonjson::private::BigNumber";
+
+/// The newtype name `Value` deserializes through, so that this crate's
+/// Deserializer can hand it BigNumbers exactly. Unlike a `BigNumber` target,
+/// a `Value` gets the same out-of-range handling as `decode_value`.
+pub(crate) const VALUE_TOKEN: &str = "$serde_bonjson::private::Value";
+
+impl serde::Serialize for BigNumber {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(BIG_NUMBER_TOKEN, &self.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for BigNumber {
+    /// Reads a BONJSON BigNumber exactly, or any integer, finite float or
+    /// decimal string (from this or another format).
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(BIG_NUMBER_TOKEN, BigNumberVisitor)
+    }
+}
+
+/// Builds a `BigNumber` from whatever number form the deserializer offers.
+pub(crate) struct BigNumberVisitor;
+
+impl<'de> serde::de::Visitor<'de> for BigNumberVisitor {
+    type Value = BigNumber;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a number or a decimal string")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<BigNumber, E> {
+        Ok(BigNumber::from_i64(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<BigNumber, E> {
+        Ok(BigNumber::from_u64(v))
+    }
+
+    fn visit_i128<E: serde::de::Error>(self, v: i128) -> std::result::Result<BigNumber, E> {
+        BigNumber::from_i128(v).ok_or_else(|| E::custom("i128 value has too many significant digits"))
+    }
+
+    fn visit_u128<E: serde::de::Error>(self, v: u128) -> std::result::Result<BigNumber, E> {
+        BigNumber::from_u128(v).ok_or_else(|| E::custom("u128 value has too many significant digits"))
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, v: f64) -> std::result::Result<BigNumber, E> {
+        if !v.is_finite() {
+            return Err(E::custom("BigNumber can't hold NaN or Infinity"));
+        }
+        // The shortest decimal that round-trips to the same float
+        format!("{v:e}").parse().map_err(E::custom)
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> std::result::Result<BigNumber, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_newtype_struct<D: serde::Deserializer<'de>>(self, deserializer: D) -> std::result::Result<BigNumber, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> std::result::Result<BigNumber, A::Error> {
+        match map.next_key::<String>()? {
+            Some(key) if key == BIG_NUMBER_TOKEN => {
+                let digits: String = map.next_value()?;
+                digits.parse().map_err(serde::de::Error::custom)
+            }
+            _ => Err(serde::de::Error::custom("expected a BigNumber")),
+        }
+    }
+}
+
 /// Default resource limits per the BONJSON specification.
 pub mod limits {
     /// Maximum document size in bytes (2 billion)
@@ -439,6 +1068,18 @@ pub mod limits {
 
     /// Maximum BigNumber magnitude in bytes
     pub const MAX_BIGNUMBER_MAGNITUDE: usize = 8;
+
+    /// Maximum typed array payload size in bytes
+    pub const MAX_TYPED_ARRAY_BYTES: usize = MAX_DOCUMENT_SIZE;
+
+    /// Maximum record definitions in a document
+    pub const MAX_RECORD_DEFINITIONS: usize = 65_536;
+
+    /// Maximum keys in a single record definition
+    pub const MAX_RECORD_KEYS: usize = 65_536;
+
+    /// Maximum length of a record definition key in bytes
+    pub const MAX_RECORD_KEY_LENGTH: usize = 65_536;
 }
 
 #[cfg(test)]
@@ -1,7 +1,8 @@
 // ABOUTME: Unit tests for the BONJSON types module.
 // ABOUTME: Tests type codes, BigNumber, and related utilities.
 
-use crate::types::{type_code, BigNumber};
+use crate::types::{decode_length_field, encode_length_field, length_field_size, type_code, BigNumber};
+use crate::Error;
 
 #[test]
 fn test_small_int_codes() {
@@ -31,10 +32,65 @@ fn test_short_string_codes() {
     assert_eq!(type_code::short_string_len(0xa7), 66);
 }
 
+#[test]
+fn test_type_code_introspection() {
+    use type_code::{category, fixed_payload_size, name, typed_array_scalar_code, Category};
+
+    assert_eq!(name(0x2a), "small_int");
+    assert_eq!(name(type_code::STRING0 + 3), "short_string");
+    assert_eq!(name(type_code::UINT16), "uint16");
+    assert_eq!(name(type_code::TYPED_ARRAY_FLOAT32), "typed_array_float32");
+    assert_eq!(name(type_code::STRING_LONG), "long_string");
+    assert_eq!(name(0xc0), "reserved");
+
+    assert_eq!(category(type_code::SINT64), Category::Int);
+    assert_eq!(category(type_code::TRUE), Category::Bool);
+    assert_eq!(category(type_code::RECORD_DEF), Category::RecordDefinition);
+    assert_eq!(category(type_code::TYPED_ARRAY_UINT8), Category::TypedArray);
+    assert_eq!(category(0xbb), Category::Reserved);
+    assert_eq!(category(0xf4), Category::Reserved);
+
+    assert_eq!(fixed_payload_size(0x00), Some(0));
+    assert_eq!(fixed_payload_size(type_code::STRING0 + 5), Some(5));
+    assert_eq!(fixed_payload_size(type_code::SINT32), Some(4));
+    assert_eq!(fixed_payload_size(type_code::FLOAT32), Some(4));
+    assert_eq!(fixed_payload_size(type_code::FLOAT64), Some(8));
+    assert_eq!(fixed_payload_size(type_code::OBJECT), Some(0));
+    assert_eq!(fixed_payload_size(type_code::BIG_NUMBER), None);
+    assert_eq!(fixed_payload_size(type_code::TYPED_ARRAY_SINT8), None);
+
+    assert_eq!(typed_array_scalar_code(type_code::TYPED_ARRAY_SINT16), type_code::SINT16);
+    assert_eq!(typed_array_scalar_code(type_code::TYPED_ARRAY_UINT8), type_code::UINT8);
+    // Every code has a name and category that agree on reserved-ness
+    for code in 0..=u8::MAX {
+        assert_eq!(name(code) == "reserved", type_code::is_reserved(code), "code {code:#04x}");
+        assert_eq!(category(code) == Category::Reserved, type_code::is_reserved(code));
+    }
+}
+
+#[test]
+fn test_length_fields() {
+    for value in [0u64, 1, 127, 128, 300, 16_383, 16_384, u64::from(u32::MAX), u64::MAX] {
+        let mut buf = Vec::new();
+        let n = encode_length_field(value, &mut buf);
+        assert_eq!(n, buf.len());
+        assert_eq!(n, length_field_size(value));
+        buf.push(0xaa); // trailing data isn't consumed
+        assert_eq!(decode_length_field(&buf).unwrap(), (value, n));
+    }
+    assert_eq!(length_field_size(u64::MAX), 10);
+
+    assert_eq!(decode_length_field(&[]).unwrap_err(), Error::Truncated);
+    assert_eq!(decode_length_field(&[0x80, 0x80]).unwrap_err(), Error::Truncated);
+    let overflow = [0xff; 11];
+    assert_eq!(decode_length_field(&overflow).unwrap_err().error_type(), "invalid_data");
+}
+
 #[test]
 fn test_big_number() {
     let bn = BigNumber::new(1, 15, -1);
     assert_eq!(bn.to_f64(), 1.5);
+    assert_eq!(BigNumber::new(1, 0, 400).to_f64(), 0.0);
 
     let bn = BigNumber::from_i64(-1000);
     assert_eq!(bn.sign, -1);
@@ -42,3 +98,136 @@ fn test_big_number() {
     assert_eq!(bn.exponent, 0);
     assert_eq!(bn.to_i64(), Some(-1000));
 }
+
+#[test]
+fn test_big_number_large_significand() {
+    let bn = BigNumber::from_u64(u64::MAX);
+    assert_eq!(bn.to_i64(), None);
+    assert_eq!(bn.to_u64(), Some(u64::MAX));
+
+    let bn = BigNumber::new(-1, 1 << 63, 0);
+    assert_eq!(bn.to_i64(), Some(i64::MIN));
+
+    let bn = BigNumber::new(1, 1 << 63, 0);
+    assert_eq!(bn.to_i64(), None);
+}
+
+#[test]
+fn test_big_number_i128_conversions() {
+    let n = i128::from(u64::MAX) * 1000;
+    let bn = BigNumber::from_i128(n).unwrap();
+    assert_eq!(bn, BigNumber::new(1, u64::MAX, 3));
+    assert_eq!(bn.to_i128(), Some(n));
+    assert_eq!(bn.to_u128(), Some(n as u128));
+
+    let bn = BigNumber::from_i128(-n).unwrap();
+    assert_eq!(bn.to_i128(), Some(-n));
+    assert_eq!(bn.to_u128(), None);
+
+    // Too many significant digits for a u64 significand
+    assert_eq!(BigNumber::from_i128(i128::MAX), None);
+    assert_eq!(BigNumber::from_u128(u128::MAX), None);
+
+    // Out of i128 range, or not an integer
+    assert_eq!(BigNumber::new(1, 1, 39).to_i128(), None);
+    assert_eq!(BigNumber::new(1, 15, -1).to_i128(), None);
+}
+
+#[test]
+fn test_big_number_parse() {
+    let parse = |s: &str| s.parse::<BigNumber>();
+    assert_eq!(parse("12").unwrap(), BigNumber::new(1, 12, 0));
+    assert_eq!(parse("-0.5").unwrap(), BigNumber::new(-1, 5, -1));
+    assert_eq!(parse("+1.50").unwrap(), BigNumber::new(1, 150, -2));
+    assert_eq!(parse("1.23e+45").unwrap(), BigNumber::new(1, 123, 43));
+    assert_eq!(parse("15E-1").unwrap(), BigNumber::new(1, 15, -1));
+    assert_eq!(parse(".5").unwrap(), BigNumber::new(1, 5, -1));
+    assert_eq!(parse("007").unwrap(), BigNumber::new(1, 7, 0));
+    assert_eq!(parse("-0.000").unwrap(), BigNumber::zero());
+    assert_eq!(BigNumber::try_from("1e6").unwrap(), BigNumber::new(1, 1, 6));
+
+    // Zeros move into the exponent only when the significand would overflow
+    assert_eq!(parse("18446744073709551615").unwrap(), BigNumber::new(1, u64::MAX, 0));
+    assert_eq!(parse("184467440737095516150000").unwrap(), BigNumber::new(1, u64::MAX, 4));
+    assert_eq!(parse("18446744073709551616").unwrap_err().error_type(), "value_out_of_range");
+    assert_eq!(parse("1e9223372036854775808").unwrap_err().error_type(), "value_out_of_range");
+    assert_eq!(parse("1.5e-9223372036854775808").unwrap_err().error_type(), "value_out_of_range");
+
+    for bad in ["", "-", "+", ".", "e5", "1e", "1e+", "1.2.3", "--1", "1e+-5", "0x10", "NaN", "Infinity", " 1", "1 "] {
+        assert_eq!(parse(bad).unwrap_err().error_type(), "invalid_data", "{bad:?}");
+    }
+}
+
+#[test]
+fn test_big_number_display_roundtrip() {
+    assert_eq!(BigNumber::new(-1, 15, -1).to_string(), "-15e-1");
+    assert_eq!(BigNumber::new(1, 0, 7).to_string(), "0e0");
+    assert_eq!(BigNumber::new(1, 15, 5).to_string_notation(), "15e5");
+
+    // Every non-zero BigNumber survives Display and parse unchanged
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for _ in 0..10_000 {
+        let significand = next() >> (next() % 64);
+        let exponent = next() as i64 >> (next() % 64);
+        let sign = if next() % 2 == 0 { 1 } else { -1 };
+        let bn = BigNumber::new(sign, significand.max(1), exponent);
+        assert_eq!(bn.to_string().parse::<BigNumber>().unwrap(), bn, "{bn}");
+    }
+    assert_eq!(BigNumber::zero().to_string().parse::<BigNumber>().unwrap(), BigNumber::zero());
+}
+
+#[test]
+fn test_big_number_stringify_is_reversible() {
+    use crate::{from_slice_with_config, DecoderConfig, OutOfRangeMode, Value};
+
+    let config = DecoderConfig { out_of_range_mode: OutOfRangeMode::Stringify, ..Default::default() };
+    for bn in [BigNumber::new(1, 1, 400), BigNumber::new(-1, u64::MAX, 400)] {
+        let bytes = crate::encode_value(&Value::BigNumber(bn)).unwrap();
+        let s: String = from_slice_with_config(&bytes, config.clone()).unwrap();
+        assert_eq!(s.parse::<BigNumber>().unwrap(), bn);
+    }
+}
+
+#[test]
+fn test_type_code_enum() {
+    use type_code::{Category, PayloadKind, TypeCode};
+
+    assert_eq!(TypeCode::try_from(0x2a).unwrap(), TypeCode::SmallInt(42));
+    assert_eq!(TypeCode::try_from(type_code::STRING0 + 3).unwrap(), TypeCode::ShortString(3));
+    assert_eq!(TypeCode::try_from(type_code::UINT16).unwrap(), TypeCode::UInt16);
+    assert_eq!(TypeCode::try_from(type_code::STRING_LONG).unwrap(), TypeCode::LongString);
+    assert!(matches!(TypeCode::try_from(0xc0), Err(Error::InvalidTypeCode(0xc0))));
+
+    assert_eq!(TypeCode::Float32.name(), "float32");
+    assert_eq!(TypeCode::TypedArraySInt16.to_string(), "typed_array_sint16");
+    assert_eq!(TypeCode::RecordInstance.category(), Category::RecordInstance);
+
+    assert_eq!(TypeCode::SmallInt(7).payload_kind(), PayloadKind::None);
+    assert_eq!(TypeCode::ShortString(0).payload_kind(), PayloadKind::None);
+    assert_eq!(TypeCode::ShortString(5).payload_kind(), PayloadKind::Fixed(5));
+    assert_eq!(TypeCode::SInt32.payload_kind(), PayloadKind::Fixed(4));
+    assert_eq!(TypeCode::Float64.payload_kind(), PayloadKind::Fixed(8));
+    assert_eq!(TypeCode::LongString.payload_kind(), PayloadKind::Delimited);
+    assert_eq!(TypeCode::BigNumber.payload_kind(), PayloadKind::BigNumber);
+    assert_eq!(TypeCode::Object.payload_kind(), PayloadKind::Children);
+    assert_eq!(TypeCode::RecordInstance.payload_kind(), PayloadKind::RecordInstance);
+    assert_eq!(TypeCode::TypedArrayUInt16.payload_kind(), PayloadKind::TypedArray { element_size: 2 });
+
+    // Every non-reserved byte round-trips and agrees with the free functions
+    for code in 0..=u8::MAX {
+        match TypeCode::from_u8(code) {
+            Some(tc) => {
+                assert_eq!(u8::from(tc), code);
+                assert_eq!(tc.name(), type_code::name(code));
+                assert_eq!(tc.category(), type_code::category(code));
+            }
+            None => assert!(type_code::is_reserved(code), "code {code:#04x}"),
+        }
+    }
+}
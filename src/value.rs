@@ -2,14 +2,34 @@
 // ABOUTME: Similar to serde_json::Value but includes BigNumber for lossless representation.
 
 
-use crate::types::BigNumber;
+use crate::decoder::FieldMatching;
+use crate::duration::TimeUnit;
+use crate::error::{Error, Result};
+use crate::hash::{HashAlgo, NumberIdentity};
+use crate::jsonpath::{JsonPath, PathSegment};
+use crate::normalize::NormalizeConfig;
+use crate::types::{BigNumber, FloatWidth};
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::fmt;
+use std::ops::Bound;
+use std::time::{Duration, SystemTime};
 
 /// A BONJSON value that can hold any JSON-compatible type.
 ///
 /// This is similar to `serde_json::Value` but includes support for
 /// `BigNumber` to enable lossless round-tripping of arbitrary-precision numbers.
+///
+/// # Integer range
+///
+/// The full `u64` range is preserved losslessly. Integers that fit in `i64` are
+/// represented as [`Value::Int`]; larger unsigned integers are always
+/// [`Value::UInt`], never a float or a wrapped negative. This holds for every
+/// path in and out of `Value`: [`to_value`](crate::to_value) /
+/// [`from_value`](crate::from_value), [`encode_value`](crate::encode_value) /
+/// [`decode_value`](crate::decode_value), and serde via `to_vec` / `from_slice`.
+/// Deserializing an out-of-range integer into a narrower type is an error
+/// rather than a truncation.
 #[derive(Clone, PartialEq, Default)]
 pub enum Value {
     /// JSON null
@@ -23,6 +43,14 @@ pub enum Value {
     UInt(u64),
     /// A 64-bit floating point number
     Float(f64),
+    /// A floating point number that encodes at exactly the given width.
+    ///
+    /// Decoding produces these only with
+    /// [`DecoderConfig::preserve_float_width`](crate::DecoderConfig::preserve_float_width),
+    /// so that re-encoding reproduces the original float bytes; `encode_value`
+    /// otherwise writes a `Float` as an integer or float32 when that's exact.
+    /// Everywhere else it behaves like `Float`.
+    SizedFloat(f64, FloatWidth),
     /// An arbitrary-precision decimal number
     BigNumber(BigNumber),
     /// A UTF-8 string
@@ -48,7 +76,7 @@ impl Value {
     #[must_use] pub fn is_number(&self) -> bool {
         matches!(
             self,
-            Value::Int(_) | Value::UInt(_) | Value::Float(_) | Value::BigNumber(_)
+            Value::Int(_) | Value::UInt(_) | Value::Float(_) | Value::SizedFloat(..) | Value::BigNumber(_)
         )
     }
 
@@ -84,7 +112,8 @@ impl Value {
         match self {
             Value::Int(n) => Some(*n),
             Value::UInt(n) if i64::try_from(*n).is_ok() => Some(*n as i64),
-            Value::Float(f) if f.fract() == 0.0 && *f >= i64::MIN as f64 && *f <= i64::MAX as f64 => {
+            // i64::MAX as f64 rounds up to 2^63, which is out of range, hence `<`
+            Value::Float(f) | Value::SizedFloat(f, _) if f.fract() == 0.0 && *f >= i64::MIN as f64 && *f < i64::MAX as f64 => {
                 Some(*f as i64)
             }
             Value::BigNumber(bn) => bn.to_i64(),
@@ -101,7 +130,8 @@ impl Value {
         match self {
             Value::UInt(n) => Some(*n),
             Value::Int(n) if *n >= 0 => Some(*n as u64),
-            Value::Float(f) if f.fract() == 0.0 && *f >= 0.0 && *f <= u64::MAX as f64 => {
+            // u64::MAX as f64 rounds up to 2^64, which is out of range, hence `<`
+            Value::Float(f) | Value::SizedFloat(f, _) if f.fract() == 0.0 && *f >= 0.0 && *f < u64::MAX as f64 => {
                 Some(*f as u64)
             }
             Value::BigNumber(bn) => bn.to_u64(),
@@ -114,7 +144,7 @@ impl Value {
     #[allow(clippy::cast_precision_loss)] // Intentional: int-to-float conversion may lose precision
     pub fn as_f64(&self) -> Option<f64> {
         match self {
-            Value::Float(f) => Some(*f),
+            Value::Float(f) | Value::SizedFloat(f, _) => Some(*f),
             Value::Int(n) => Some(*n as f64),
             Value::UInt(n) => Some(*n as f64),
             Value::BigNumber(bn) => Some(bn.to_f64()),
@@ -122,6 +152,30 @@ impl Value {
         }
     }
 
+    /// If this is an unsigned integer, returns it as a duration of `unit`,
+    /// as written by the [`duration`](crate::duration) modules.
+    #[must_use] pub fn as_duration(&self, unit: TimeUnit) -> Option<Duration> {
+        self.as_u64().map(|count| unit.duration(count))
+    }
+
+    /// If this is an integer, returns it as a time in milliseconds since the
+    /// Unix epoch, as written by [`systemtime::unix_millis`](crate::systemtime::unix_millis).
+    #[must_use] pub fn as_system_time(&self) -> Option<SystemTime> {
+        self.as_i64().and_then(crate::systemtime::from_unix_millis)
+    }
+
+    /// A blob holding `bytes`: an array of `UInt`s, which `encode_value`
+    /// writes as a uint8 typed array (see [`blob`](crate::blob)).
+    #[must_use] pub fn from_blob(bytes: &[u8]) -> Value {
+        Value::Array(bytes.iter().map(|&b| Value::UInt(u64::from(b))).collect())
+    }
+
+    /// If this is an array of integers 0-255 (such as a decoded blob),
+    /// returns them as bytes.
+    #[must_use] pub fn as_blob(&self) -> Option<Vec<u8>> {
+        self.as_array()?.iter().map(|v| v.as_u64().and_then(|n| u8::try_from(n).ok())).collect()
+    }
+
     /// If this is a string, returns a reference to it.
     #[must_use] pub fn as_str(&self) -> Option<&str> {
         match self {
@@ -171,6 +225,547 @@ impl Value {
     #[must_use] pub fn get_key(&self, key: &str) -> Option<&Value> {
         self.as_object().and_then(|o| o.get(key))
     }
+
+    /// Index into an object by key, ignoring case, as for HTTP header names.
+    /// An exact match wins; otherwise the first matching key in key order.
+    /// Returns None if not an object or no key matches.
+    ///
+    /// ```rust
+    /// use serde_bonjson::bonjson;
+    ///
+    /// let headers = bonjson!({"Content-Type": "text/plain", "X-Trace": "a1", "X-Span": "b2"});
+    /// assert_eq!(headers.get_key_ci("content-type").and_then(|v| v.as_str()), Some("text/plain"));
+    /// let custom: Vec<&str> = headers.keys_with_prefix("X-").map(|(k, _)| k.as_str()).collect();
+    /// assert_eq!(custom, ["X-Span", "X-Trace"]);
+    /// ```
+    #[must_use] pub fn get_key_ci(&self, key: &str) -> Option<&Value> {
+        let members = self.as_object()?;
+        members.get(key).or_else(|| {
+            members.iter().find(|(k, _)| FieldMatching::CaseInsensitive.matches(k, key)).map(|(_, v)| v)
+        })
+    }
+
+    /// Iterate over the members of an object whose keys start with `prefix`
+    /// (case-sensitively), in key order. Yields nothing if not an object.
+    ///
+    /// Finds the first match by binary search, so it only visits the matching
+    /// members rather than the whole object.
+    pub fn keys_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a String, &'a Value)> + 'a {
+        self.as_object().into_iter().flat_map(move |members| {
+            members
+                .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+                .take_while(move |(k, _)| k.starts_with(prefix))
+        })
+    }
+
+    /// Recursively remove object members whose value is null.
+    ///
+    /// Null array elements are kept, since removing them would shift positions.
+    pub fn strip_nulls(&mut self) {
+        match self {
+            Value::Array(a) => a.iter_mut().for_each(Value::strip_nulls),
+            Value::Object(o) => {
+                o.retain(|_, v| !v.is_null());
+                o.values_mut().for_each(Value::strip_nulls);
+            }
+            _ => {}
+        }
+    }
+
+    /// Iterate over this value and everything beneath it, in document order.
+    ///
+    /// Yields each value with its path, parents before children. The root is
+    /// yielded first with path `$`.
+    #[must_use] pub fn walk(&self) -> Walk<'_> {
+        Walk { stack: vec![(JsonPath::root(), self)] }
+    }
+
+    /// Iterate over the paths of this value and everything beneath it, in document order.
+    pub fn paths(&self) -> impl Iterator<Item = JsonPath> + '_ {
+        self.walk().map(|(path, _)| path)
+    }
+
+    /// Collect every value (at any depth, including the root) matching a predicate.
+    pub fn find_all<F>(&self, mut predicate: F) -> Vec<(JsonPath, &Value)>
+    where
+        F: FnMut(&JsonPath, &Value) -> bool,
+    {
+        self.walk().filter(|(path, value)| predicate(path, value)).collect()
+    }
+
+    /// Recursively remove object members and array elements for which the
+    /// predicate returns false.
+    ///
+    /// The predicate sees each value before its children, so a removed value's
+    /// children are never visited. The root itself is always kept. Removing
+    /// array elements shifts later elements down, but paths passed to the
+    /// predicate are their original positions.
+    pub fn retain_paths<F>(&mut self, mut predicate: F)
+    where
+        F: FnMut(&JsonPath, &Value) -> bool,
+    {
+        let mut path = JsonPath::root();
+        self.retain_children(&mut path, &mut predicate);
+    }
+
+    fn retain_children<F>(&mut self, path: &mut JsonPath, predicate: &mut F)
+    where
+        F: FnMut(&JsonPath, &Value) -> bool,
+    {
+        match self {
+            Value::Array(a) => {
+                let mut index = 0;
+                a.retain_mut(|v| {
+                    path.push_index(index);
+                    index += 1;
+                    let keep = predicate(path, v);
+                    if keep {
+                        v.retain_children(path, predicate);
+                    }
+                    path.pop();
+                    keep
+                });
+            }
+            Value::Object(o) => {
+                o.retain(|k, v| {
+                    path.push_key(k.as_str());
+                    let keep = predicate(path, v);
+                    if keep {
+                        v.retain_children(path, predicate);
+                    }
+                    path.pop();
+                    keep
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+// ============================================================================
+// Ordering, sorting and deduplication
+// ============================================================================
+
+impl Value {
+    /// Compare two values using a total order over all value types.
+    ///
+    /// Types order as null < bool < number < string < array < object. Numbers
+    /// compare by numeric value regardless of representation (so `Int(3)` equals
+    /// `Float(3.0)`), with NaN after all other numbers. Strings compare
+    /// bytewise, arrays element by element, and objects member by member in
+    /// key order.
+    #[must_use] pub fn total_cmp(&self, other: &Value) -> Ordering {
+        fn rank(v: &Value) -> u8 {
+            match v {
+                Value::Null => 0,
+                Value::Bool(_) => 1,
+                Value::Int(_) | Value::UInt(_) | Value::Float(_) | Value::SizedFloat(..) | Value::BigNumber(_) => 2,
+                Value::String(_) => 3,
+                Value::Array(_) => 4,
+                Value::Object(_) => 5,
+            }
+        }
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Array(a), Value::Array(b)) => {
+                a.iter().map(SortKey).cmp(b.iter().map(SortKey))
+            }
+            (Value::Object(a), Value::Object(b)) => a
+                .iter()
+                .map(|(k, v)| (k, SortKey(v)))
+                .cmp(b.iter().map(|(k, v)| (k, SortKey(v)))),
+            _ if self.is_number() && other.is_number() => compare_numbers(self, other),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+
+    /// Stable-sort an array's elements with a comparator. Does nothing if this
+    /// is not an array.
+    pub fn sort_array_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&Value, &Value) -> Ordering,
+    {
+        if let Value::Array(a) = self {
+            a.sort_by(compare);
+        }
+    }
+
+    /// Stable-sort an array's elements by [`total_cmp`](Self::total_cmp).
+    /// Does nothing if this is not an array.
+    pub fn sort_array(&mut self) {
+        self.sort_array_by(Value::total_cmp);
+    }
+
+    /// Stable-sort an array of objects by the value of one member, using
+    /// [`total_cmp`](Self::total_cmp).
+    ///
+    /// Elements that lack the member, or aren't objects, sort first. Does
+    /// nothing if this is not an array.
+    pub fn sort_array_by_key(&mut self, key: &str) {
+        self.sort_array_by(|a, b| match (a.get_key(key), b.get_key(key)) {
+            (Some(x), Some(y)) => x.total_cmp(y),
+            (x, y) => x.is_some().cmp(&y.is_some()),
+        });
+    }
+
+    /// Remove array elements that duplicate an earlier element, keeping the
+    /// first occurrence. Duplicates need not be adjacent. Does nothing if this
+    /// is not an array.
+    pub fn dedup_array_by<F>(&mut self, mut same: F)
+    where
+        F: FnMut(&Value, &Value) -> bool,
+    {
+        if let Value::Array(a) = self {
+            let mut kept: Vec<Value> = Vec::with_capacity(a.len());
+            for v in a.drain(..) {
+                if !kept.iter().any(|k| same(k, &v)) {
+                    kept.push(v);
+                }
+            }
+            *a = kept;
+        }
+    }
+
+    /// Remove objects from an array whose value for `key` equals that of an
+    /// earlier element, keeping the first occurrence.
+    ///
+    /// Values are compared with [`total_cmp`](Self::total_cmp), so `1` and
+    /// `1.0` are duplicates. Elements that lack the member, or aren't objects,
+    /// are always kept. Does nothing if this is not an array.
+    pub fn dedup_array_by_key(&mut self, key: &str) {
+        self.dedup_array_by(|a, b| match (a.get_key(key), b.get_key(key)) {
+            (Some(x), Some(y)) => x.total_cmp(y) == Ordering::Equal,
+            _ => false,
+        });
+    }
+}
+
+// ============================================================================
+// Structural hashing
+// ============================================================================
+
+impl Value {
+    /// A content hash that is stable across processes and releases, for
+    /// deduplicating documents.
+    ///
+    /// Objects hash the same whatever their keys' insertion order, and numbers
+    /// hash by value: `Int(5)`, `UInt(5)`, `Float(5.0)` and a BigNumber of 5
+    /// are identical. See [`structural_hash_with`](Self::structural_hash_with)
+    /// to make the numeric variant significant.
+    #[must_use]
+    pub fn structural_hash(&self, algo: HashAlgo) -> [u8; 32] {
+        self.structural_hash_with(algo, NumberIdentity::Value)
+    }
+
+    /// Like [`structural_hash`](Self::structural_hash), with a choice of
+    /// which numbers count as identical. The canonical form being hashed is
+    /// described at [`hash::structural_hash`](crate::hash::structural_hash).
+    #[must_use]
+    pub fn structural_hash_with(&self, algo: HashAlgo, numbers: NumberIdentity) -> [u8; 32] {
+        crate::hash::structural_hash(self, algo, numbers)
+    }
+}
+
+// ============================================================================
+// Normalization
+// ============================================================================
+
+impl Value {
+    /// Normalize this value in place; see [`normalize::normalize`](crate::normalize::normalize).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::DuplicateKey` if two keys of an object are equal after
+    /// NFC normalization.
+    pub fn normalize(&mut self, config: &NormalizeConfig) -> Result<()> {
+        crate::normalize::normalize(self, config)
+    }
+}
+
+// ============================================================================
+// Typed conversion
+// ============================================================================
+
+impl Value {
+    /// Convert this value into a `T`, like [`from_value`](crate::from_value),
+    /// but an error inside the value says where it happened.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    /// use serde_bonjson::bonjson;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct User {
+    ///     name: String,
+    ///     age: u32,
+    /// }
+    ///
+    /// let value = bonjson!([{"name": "Ann", "age": 31}, {"name": "Bo"}]);
+    /// let err = value.try_into_struct::<Vec<User>>().unwrap_err();
+    /// assert_eq!(err.to_string(), "missing field `age` at $[1]");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::AtPath`] with the path of the innermost value that
+    /// doesn't match `T`, or the plain error if the root itself doesn't.
+    pub fn try_into_struct<T: for<'de> serde::Deserialize<'de>>(&self) -> Result<T> {
+        crate::de::from_slice_at_path(&crate::encode_value(self)?)
+    }
+
+    /// Build a value from any serializable `T`; the same as
+    /// [`to_value`](crate::to_value).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `T` fails to serialize.
+    pub fn from_struct<T: serde::Serialize>(value: &T) -> Result<Value> {
+        crate::to_value(value)
+    }
+}
+
+// ============================================================================
+// Interning
+// ============================================================================
+
+impl Value {
+    /// Convert this value into a [`CompactValue`](crate::intern::CompactValue)
+    /// whose strings and object keys are shared through `interner`, so each
+    /// distinct string is stored once. See the [`intern`](crate::intern) module.
+    ///
+    /// A `Value` owns its strings, so the sharing needs the other type; each
+    /// string of `self` is freed as it's replaced.
+    #[must_use]
+    pub fn intern_strings(self, interner: &mut crate::intern::Interner) -> crate::intern::CompactValue {
+        crate::intern::intern_value(self, interner)
+    }
+
+    /// Render as indented, JSON-like text for logs and test snapshots, as
+    /// [`tools::pretty_text`](crate::tools::pretty_text) renders a document:
+    /// BigNumbers and sized floats are marked with a `/* ... */` comment.
+    ///
+    /// ```rust
+    /// use serde_bonjson::bonjson;
+    ///
+    /// assert_eq!(bonjson!({"a": [1, "x"], "b": {}}).to_pretty_text(), "{\n  \"a\": [\n    1,\n    \"x\"\n  ],\n  \"b\": {}\n}");
+    /// ```
+    #[must_use]
+    pub fn to_pretty_text(&self) -> String {
+        crate::tools::value_pretty_text(self)
+    }
+}
+
+// ============================================================================
+// Merging
+// ============================================================================
+
+/// How [`Value::deep_merge`] combines two documents.
+///
+/// The default layers the other document over this one: its arrays replace
+/// ours and its scalars win conflicts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergePolicy {
+    /// How to combine two arrays at the same path.
+    pub arrays: ArrayMerge,
+    /// What to do when two values at the same path differ and can't be merged.
+    pub conflicts: ConflictPolicy,
+}
+
+/// How [`Value::deep_merge`] combines two arrays at the same path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// Append the other array's elements to ours.
+    Concat,
+    /// Replace our array with the other one.
+    #[default]
+    Replace,
+    /// Treat both arrays as sets of objects identified by the named member.
+    ///
+    /// An element of the other array whose member equals that of one of ours
+    /// (by [`Value::total_cmp`]) is deep-merged into it. Any other element is
+    /// appended, unless it equals one already present.
+    UnionByKey(String),
+}
+
+/// What [`Value::deep_merge`] does when two values at the same path differ
+/// and can't be merged: two different scalars, or values of different types.
+/// Values that compare equal by [`Value::total_cmp`] never conflict.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep this document's value.
+    PreferLeft,
+    /// Take the other document's value.
+    #[default]
+    PreferRight,
+    /// Fail with [`Error::MergeConflict`].
+    Error,
+}
+
+impl Value {
+    /// Recursively merge another document into this one.
+    ///
+    /// Objects merge member by member, with members only in `other` added.
+    /// Arrays combine according to `policy.arrays`, and everything else
+    /// according to `policy.conflicts`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MergeConflict`] with the path of the first conflict if
+    /// the policy is [`ConflictPolicy::Error`]. `self` is unchanged on error.
+    pub fn deep_merge(&mut self, other: Value, policy: &MergePolicy) -> Result<()> {
+        let mut path = JsonPath::root();
+        if policy.conflicts == ConflictPolicy::Error {
+            let mut merged = self.clone();
+            merged.merge_at(other, policy, &mut path)?;
+            *self = merged;
+            Ok(())
+        } else {
+            self.merge_at(other, policy, &mut path)
+        }
+    }
+
+    fn merge_at(&mut self, other: Value, policy: &MergePolicy, path: &mut JsonPath) -> Result<()> {
+        match (&mut *self, other) {
+            (Value::Object(ours), Value::Object(theirs)) => {
+                for (key, value) in theirs {
+                    match ours.get_mut(&key) {
+                        Some(existing) => {
+                            path.push_key(key.as_str());
+                            existing.merge_at(value, policy, path)?;
+                            path.pop();
+                        }
+                        None => {
+                            ours.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (Value::Array(ours), Value::Array(theirs)) => match &policy.arrays {
+                ArrayMerge::Concat => ours.extend(theirs),
+                ArrayMerge::Replace => *ours = theirs,
+                ArrayMerge::UnionByKey(key) => {
+                    for value in theirs {
+                        let same_key = value.get_key(key).and_then(|id| {
+                            ours.iter().position(|v| {
+                                v.get_key(key).is_some_and(|v| v.total_cmp(id) == Ordering::Equal)
+                            })
+                        });
+                        if let Some(index) = same_key {
+                            path.push_index(index);
+                            ours[index].merge_at(value, policy, path)?;
+                            path.pop();
+                        } else if !ours.iter().any(|v| v.total_cmp(&value) == Ordering::Equal) {
+                            ours.push(value);
+                        }
+                    }
+                }
+            },
+            (ours, theirs) => {
+                if ours.total_cmp(&theirs) != Ordering::Equal {
+                    match policy.conflicts {
+                        ConflictPolicy::PreferLeft => {}
+                        ConflictPolicy::PreferRight => *ours = theirs,
+                        ConflictPolicy::Error => return Err(Error::MergeConflict(path.to_string())),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adapter that orders values by [`Value::total_cmp`].
+struct SortKey<'a>(&'a Value);
+
+impl PartialEq for SortKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.total_cmp(other.0) == Ordering::Equal
+    }
+}
+
+impl Eq for SortKey<'_> {}
+
+impl PartialOrd for SortKey<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SortKey<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(other.0)
+    }
+}
+
+/// Compare two numeric values exactly. Integers compare as integers; anything
+/// involving a float or BigNumber compares its f64 value exactly against the
+/// other side, so the order stays transitive even beyond 2^53.
+fn compare_numbers(a: &Value, b: &Value) -> Ordering {
+    fn as_integer(v: &Value) -> Option<i128> {
+        match v {
+            Value::Int(n) => Some(i128::from(*n)),
+            Value::UInt(n) => Some(i128::from(*n)),
+            _ => None,
+        }
+    }
+    match (as_integer(a), as_integer(b)) {
+        (Some(x), Some(y)) => x.cmp(&y),
+        (Some(x), None) => compare_int_float(x, b.as_f64().unwrap_or(f64::NAN)),
+        (None, Some(y)) => compare_int_float(y, a.as_f64().unwrap_or(f64::NAN)).reverse(),
+        (None, None) => {
+            let (x, y) = (a.as_f64().unwrap_or(f64::NAN), b.as_f64().unwrap_or(f64::NAN));
+            match (x.is_nan(), y.is_nan()) {
+                (false, false) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+                (nan_x, nan_y) => nan_x.cmp(&nan_y),
+            }
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)] // Range checked before cast
+#[allow(clippy::cast_precision_loss)] // Bounds are exact powers of two
+fn compare_int_float(i: i128, f: f64) -> Ordering {
+    if f.is_nan() {
+        return Ordering::Less;
+    }
+    // i128 values from i64/u64 are well inside ±2^127
+    if f >= i128::MAX as f64 {
+        return Ordering::Less;
+    }
+    if f < i128::MIN as f64 {
+        return Ordering::Greater;
+    }
+    let floor = f.floor();
+    match i.cmp(&(floor as i128)) {
+        Ordering::Equal if f > floor => Ordering::Less,
+        ordering => ordering,
+    }
+}
+
+/// A depth-first iterator over a [`Value`] tree, returned by [`Value::walk`].
+pub struct Walk<'a> {
+    stack: Vec<(JsonPath, &'a Value)>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (JsonPath, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, value) = self.stack.pop()?;
+        match value {
+            Value::Array(a) => {
+                self.stack.extend(a.iter().enumerate().rev().map(|(i, v)| (path.child_index(i), v)));
+            }
+            Value::Object(o) => {
+                self.stack.extend(o.iter().rev().map(|(k, v)| (path.child_key(k.as_str()), v)));
+            }
+            _ => {}
+        }
+        Some((path, value))
+    }
 }
 
 impl fmt::Debug for Value {
@@ -181,6 +776,7 @@ impl fmt::Debug for Value {
             Value::Int(n) => write!(f, "Int({n})"),
             Value::UInt(n) => write!(f, "UInt({n})"),
             Value::Float(n) => write!(f, "Float({n})"),
+            Value::SizedFloat(n, width) => write!(f, "SizedFloat({n}, {width:?})"),
             Value::BigNumber(bn) => write!(f, "BigNumber({bn:?})"),
             Value::String(s) => write!(f, "String({s:?})"),
             Value::Array(a) => f.debug_tuple("Array").field(a).finish(),
@@ -197,7 +793,7 @@ impl fmt::Display for Value {
             Value::Bool(b) => write!(f, "{b}"),
             Value::Int(n) => write!(f, "{n}"),
             Value::UInt(n) => write!(f, "{n}"),
-            Value::Float(n) => {
+            Value::Float(n) | Value::SizedFloat(n, _) => {
                 if n.is_finite() {
                     write!(f, "{n}")
                 } else if n.is_nan() {
@@ -313,6 +909,20 @@ impl From<f64> for Value {
     }
 }
 
+#[cfg(feature = "half")]
+impl From<half::f16> for Value {
+    fn from(f: half::f16) -> Self {
+        Value::Float(f64::from(f))
+    }
+}
+
+#[cfg(feature = "half")]
+impl From<half::bf16> for Value {
+    fn from(f: half::bf16) -> Self {
+        Value::Float(f64::from(f))
+    }
+}
+
 impl From<String> for Value {
     fn from(s: String) -> Self {
         Value::String(s)
@@ -337,12 +947,325 @@ impl From<BigNumber> for Value {
     }
 }
 
+impl From<usize> for Value {
+    fn from(n: usize) -> Self {
+        Value::from(n as u64)
+    }
+}
+
+impl From<isize> for Value {
+    fn from(n: isize) -> Self {
+        Value::Int(n as i64)
+    }
+}
+
+/// `None` becomes null.
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<BTreeMap<String, T>> for Value {
+    fn from(map: BTreeMap<String, T>) -> Self {
+        Value::Object(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+// Typed conversions out of a Value: the inverses of the From impls above.
+// Numbers convert between representations when the value fits (an integral
+// Float or BigNumber into an integer, an integer into a float), and fail with
+// ValueOutOfRange when it doesn't; any other mismatch is an error naming
+// both types. Errors inside arrays and objects come back as Error::AtPath.
+
+/// The kind of value, for error messages.
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Int(_) | Value::UInt(_) => "integer",
+        Value::Float(_) | Value::SizedFloat(..) => "float",
+        Value::BigNumber(_) => "BigNumber",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn invalid_type(value: &Value, expected: &str) -> Error {
+    Error::Custom(format!("invalid type: {}, expected {expected}", kind(value)))
+}
+
+/// Record that `error` happened at `segment` within the value being converted.
+fn within(error: Error, segment: PathSegment) -> Error {
+    match error {
+        Error::AtPath { mut path, offset, error } => {
+            path.push_front(segment);
+            Error::AtPath { path, offset, error }
+        }
+        error => {
+            let mut path = JsonPath::root();
+            path.push_front(segment);
+            Error::AtPath { path, offset: None, error: Box::new(error) }
+        }
+    }
+}
+
+fn integer<N: TryFrom<i64> + TryFrom<u64>>(value: &Value, expected: &str) -> Result<N> {
+    let converted = match value.as_i64() {
+        Some(n) => N::try_from(n).ok(),
+        None => value.as_u64().and_then(|n| N::try_from(n).ok()),
+    };
+    match converted {
+        Some(n) => Ok(n),
+        None if value.as_f64().is_some_and(|f| f.fract() == 0.0) => Err(Error::ValueOutOfRange),
+        None => Err(invalid_type(value, expected)),
+    }
+}
+
+macro_rules! try_from_value_integer {
+    ($($ty:ty),*) => {$(
+        impl TryFrom<Value> for $ty {
+            type Error = Error;
+            fn try_from(value: Value) -> Result<Self> {
+                integer(&value, stringify!($ty))
+            }
+        }
+    )*};
+}
+
+try_from_value_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl TryFrom<Value> for f64 {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        match value.as_f64() {
+            // Only a BigNumber can be finite and still overflow f64
+            Some(f) if f.is_infinite() && matches!(value, Value::BigNumber(_)) => Err(Error::ValueOutOfRange),
+            Some(f) => Ok(f),
+            None => Err(invalid_type(&value, "f64")),
+        }
+    }
+}
+
+impl TryFrom<Value> for f32 {
+    type Error = Error;
+    #[allow(clippy::cast_possible_truncation)] // Overflow checked below
+    fn try_from(value: Value) -> Result<Self> {
+        let f = f64::try_from(value)?;
+        let narrowed = f as f32;
+        if narrowed.is_infinite() && f.is_finite() {
+            return Err(Error::ValueOutOfRange);
+        }
+        Ok(narrowed)
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            other => Err(invalid_type(&other, "bool")),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::String(s) => Ok(s),
+            other => Err(invalid_type(&other, "string")),
+        }
+    }
+}
+
+impl<T> TryFrom<Value> for Vec<T>
+where
+    T: TryFrom<Value>,
+    Error: From<T::Error>,
+{
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::Array(items) => items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| T::try_from(item).map_err(|e| within(e.into(), PathSegment::Index(i))))
+                .collect(),
+            other => Err(invalid_type(&other, "array")),
+        }
+    }
+}
+
+impl<T> TryFrom<Value> for BTreeMap<String, T>
+where
+    T: TryFrom<Value>,
+    Error: From<T::Error>,
+{
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(members) => members
+                .into_iter()
+                .map(|(k, v)| match T::try_from(v) {
+                    Ok(v) => Ok((k, v)),
+                    Err(e) => Err(within(e.into(), PathSegment::Key(k))),
+                })
+                .collect(),
+            other => Err(invalid_type(&other, "object")),
+        }
+    }
+}
+
+// `Option<T>` can't have one generic impl: std's `From<T> for Option<T>`
+// already makes `Option<Value>::try_from` wrap any value (null included) in
+// `Some`. Every other target gets null as `None`.
+macro_rules! try_from_value_option {
+    ($($ty:ty),*) => {$(
+        impl TryFrom<Value> for Option<$ty> {
+            type Error = Error;
+            fn try_from(value: Value) -> Result<Self> {
+                value.into_option()
+            }
+        }
+    )*};
+}
+
+try_from_value_option!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64, bool, String);
+
+impl<T> TryFrom<Value> for Option<Vec<T>>
+where
+    T: TryFrom<Value>,
+    Error: From<T::Error>,
+{
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        value.into_option()
+    }
+}
+
+impl<T> TryFrom<Value> for Option<BTreeMap<String, T>>
+where
+    T: TryFrom<Value>,
+    Error: From<T::Error>,
+{
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self> {
+        value.into_option()
+    }
+}
+
+impl Value {
+    /// `None` for null, otherwise the value converted to `T`.
+    fn into_option<T>(self) -> Result<Option<T>>
+    where
+        T: TryFrom<Value, Error = Error>,
+    {
+        match self {
+            Value::Null => Ok(None),
+            value => T::try_from(value).map(Some),
+        }
+    }
+}
+
 impl<T: Into<Value>> FromIterator<T> for Value {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
         Value::Array(iter.into_iter().map(Into::into).collect())
     }
 }
 
+/// Collects key/value pairs into an object; a repeated key keeps the last value.
+impl FromIterator<(String, Value)> for Value {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Self {
+        Value::Object(iter.into_iter().collect())
+    }
+}
+
+impl Value {
+    /// Append `items` to this array. A `Null` becomes an empty array first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, leaving the value unchanged, if it's neither an
+    /// array nor null.
+    pub fn extend_array<I, T>(&mut self, items: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Value>,
+    {
+        if self.is_null() {
+            *self = Value::Array(Vec::new());
+        }
+        match self {
+            Value::Array(array) => {
+                array.extend(items.into_iter().map(Into::into));
+                Ok(())
+            }
+            other => Err(invalid_type(other, "array")),
+        }
+    }
+
+    /// Insert `members` into this object, replacing the values of keys it
+    /// already has. A `Null` becomes an empty object first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, leaving the value unchanged, if it's neither an
+    /// object nor null.
+    pub fn extend_object<I, K, V>(&mut self, members: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        if self.is_null() {
+            *self = Value::Object(BTreeMap::new());
+        }
+        match self {
+            Value::Object(object) => {
+                object.extend(members.into_iter().map(|(k, v)| (k.into(), v.into())));
+                Ok(())
+            }
+            other => Err(invalid_type(other, "object")),
+        }
+    }
+}
+
+/// Collecting iterators into [`Value`]s, for pipelines whose items aren't
+/// already `Value`s or `(String, Value)` pairs.
+///
+/// ```rust
+/// use serde_bonjson::value::CollectValue;
+/// use serde_bonjson::bonjson;
+///
+/// let counts = [("a", 1), ("b", 2)].into_iter().collect_object();
+/// assert_eq!(counts, bonjson!({"a": 1, "b": 2}));
+/// assert_eq!((1..=3).map(|n| n * n).collect_array(), bonjson!([1, 4, 9]));
+/// ```
+pub trait CollectValue: Iterator + Sized {
+    /// Collect `(key, value)` pairs into an object; a repeated key keeps the last value.
+    fn collect_object<K, V>(self) -> Value
+    where
+        Self: Iterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        Value::Object(self.map(|(k, v)| (k.into(), v.into())).collect())
+    }
+
+    /// Collect the items into an array.
+    fn collect_array(self) -> Value
+    where
+        Self::Item: Into<Value>,
+    {
+        Value::Array(self.map(Into::into).collect())
+    }
+}
+
+impl<I: Iterator> CollectValue for I {}
+
 /// Macro for creating BONJSON values easily.
 ///
 /// This is the BONJSON-specific name. For drop-in `serde_json` compatibility,
@@ -380,6 +1303,9 @@ macro_rules! bonjson {
     };
 
     // object
+    ({}) => {
+        $crate::Value::Object(std::collections::BTreeMap::new())
+    };
     ({ $($key:tt : $value:tt),* $(,)? }) => {
         {
             let mut map = std::collections::BTreeMap::new();
@@ -1,7 +1,7 @@
 // ABOUTME: Unit tests for the BONJSON value module.
 // ABOUTME: Tests Value type, accessors, conversions, and the bonjson! macro.
 
-use crate::{bonjson, Value};
+use crate::{bonjson, ArrayMerge, ConflictPolicy, Error, MergePolicy, Value};
 use std::collections::BTreeMap;
 
 #[test]
@@ -52,3 +52,417 @@ fn test_bonjson_macro() {
     assert!(v.is_object());
     assert_eq!(v.get_key("name").and_then(|v| v.as_str()), Some("test"));
 }
+
+#[test]
+fn test_value_large_u64_accessors() {
+    assert_eq!(Value::from(u64::MAX), Value::UInt(u64::MAX));
+    assert_eq!(Value::from(i64::MAX as u64), Value::Int(i64::MAX));
+    assert_eq!(Value::UInt(u64::MAX).as_u64(), Some(u64::MAX));
+    assert_eq!(Value::UInt(u64::MAX).as_i64(), None);
+
+    // Float boundaries round up to 2^63 / 2^64 and must not saturate
+    assert_eq!(Value::Float(9_223_372_036_854_775_808.0).as_i64(), None);
+    assert_eq!(Value::Float(18_446_744_073_709_551_616.0).as_u64(), None);
+    assert_eq!(
+        Value::Float(9_223_372_036_854_775_808.0).as_u64(),
+        Some(1 << 63)
+    );
+}
+
+#[test]
+fn test_value_deserialize_i128() {
+    use serde::de::value::{I128Deserializer, U128Deserializer};
+    use serde::Deserialize;
+    type E = serde::de::value::Error;
+
+    let v = Value::deserialize(I128Deserializer::<E>::new(-7)).unwrap();
+    assert_eq!(v, Value::Int(-7));
+
+    let v = Value::deserialize(U128Deserializer::<E>::new(u128::from(u64::MAX))).unwrap();
+    assert_eq!(v, Value::UInt(u64::MAX));
+
+    let v = Value::deserialize(I128Deserializer::<E>::new(-(i128::from(u64::MAX) * 1000))).unwrap();
+    assert_eq!(v, Value::BigNumber(crate::BigNumber::new(-1, u64::MAX, 3)));
+
+    assert!(Value::deserialize(U128Deserializer::<E>::new(u128::MAX)).is_err());
+}
+
+#[test]
+fn test_value_strip_nulls() {
+    let mut v = bonjson!({
+        "a": null,
+        "b": 1,
+        "c": {"d": null, "e": [null, {"f": null, "g": 2}]}
+    });
+    v.strip_nulls();
+    assert_eq!(v, bonjson!({"b": 1, "c": {"e": [null, {"g": 2}]}}));
+
+    // Top-level null and scalars are left alone
+    let mut v = bonjson!(null);
+    v.strip_nulls();
+    assert!(v.is_null());
+}
+
+// ============================================================================
+// Traversal tests
+// ============================================================================
+
+fn traversal_doc() -> Value {
+    bonjson!({
+        "b": [1, {"c": true}],
+        "a": "x"
+    })
+}
+
+#[test]
+fn test_value_walk_document_order() {
+    let v = traversal_doc();
+    let paths: Vec<String> = v.paths().map(|p| p.to_string()).collect();
+    assert_eq!(paths, vec!["$", "$.a", "$.b", "$.b[0]", "$.b[1]", "$.b[1].c"]);
+
+    let (path, value) = v.walk().nth(5).unwrap();
+    assert_eq!(path.len(), 3);
+    assert_eq!(value, &Value::Bool(true));
+}
+
+#[test]
+fn test_value_walk_scalar() {
+    let v = Value::Int(3);
+    let items: Vec<_> = v.walk().collect();
+    assert_eq!(items.len(), 1);
+    assert!(items[0].0.is_root());
+}
+
+#[test]
+fn test_value_find_all() {
+    let v = traversal_doc();
+    let found = v.find_all(|_, v| v.is_number() || v.is_bool());
+    let paths: Vec<String> = found.iter().map(|(p, _)| p.to_string()).collect();
+    assert_eq!(paths, vec!["$.b[0]", "$.b[1].c"]);
+
+    let under_b = v.find_all(|p, _| p.len() == 2);
+    assert_eq!(under_b.len(), 2);
+}
+
+#[test]
+fn test_value_retain_paths() {
+    let mut v = bonjson!({
+        "keep": {"drop": 1, "stay": 2},
+        "drop": [1, 2, 3],
+        "list": [10, 20, 30]
+    });
+    let mut visited = Vec::new();
+    v.retain_paths(|path, value| {
+        visited.push(path.to_string());
+        let key_dropped = matches!(path.last(), Some(crate::jsonpath::PathSegment::Key(k)) if k == "drop");
+        !key_dropped && value != &Value::Int(20)
+    });
+    assert_eq!(v, bonjson!({"keep": {"stay": 2}, "list": [10, 30]}));
+
+    // Children of removed values are not visited; indices are original positions
+    assert!(!visited.contains(&"$.drop[0]".to_string()));
+    assert!(visited.contains(&"$.list[2]".to_string()));
+}
+
+// ============================================================================
+// Sorting and deduplication tests
+// ============================================================================
+
+#[test]
+fn test_value_total_cmp() {
+    use std::cmp::Ordering;
+
+    let ordered = [
+        bonjson!(null),
+        bonjson!(false),
+        bonjson!(true),
+        bonjson!(-1.5),
+        bonjson!(2),
+        bonjson!("a"),
+        bonjson!([1]),
+        bonjson!({"a": 1}),
+    ];
+    for pair in ordered.windows(2) {
+        assert_eq!(pair[0].total_cmp(&pair[1]), Ordering::Less, "{:?} < {:?}", pair[0], pair[1]);
+    }
+
+    // Numbers compare by value across representations
+    assert_eq!(Value::Int(3).total_cmp(&Value::Float(3.0)), Ordering::Equal);
+    assert_eq!(Value::UInt(u64::MAX).total_cmp(&Value::Int(-1)), Ordering::Greater);
+    assert_eq!(Value::Int(3).total_cmp(&Value::Float(3.5)), Ordering::Less);
+    assert_eq!(Value::Int(-3).total_cmp(&Value::Float(-3.5)), Ordering::Greater);
+    assert_eq!(Value::Float(f64::NAN).total_cmp(&Value::UInt(u64::MAX)), Ordering::Greater);
+    assert_eq!(
+        Value::BigNumber(crate::BigNumber::new(1, 15, -1)).total_cmp(&Value::Int(2)),
+        Ordering::Less
+    );
+    // Exact beyond 2^53, where f64 can't distinguish neighbouring integers
+    let big = 1i64 << 53;
+    assert_eq!(Value::Int(big + 1).total_cmp(&Value::Float(big as f64)), Ordering::Greater);
+}
+
+#[test]
+fn test_value_sort_array() {
+    let mut v = bonjson!([3, "b", null, 1.5, "a", true, [0]]);
+    v.sort_array();
+    assert_eq!(v, bonjson!([null, true, 1.5, 3, "a", "b", [0]]));
+
+    let mut v = bonjson!([3, 1, 2]);
+    v.sort_array_by(|a, b| b.total_cmp(a));
+    assert_eq!(v, bonjson!([3, 2, 1]));
+
+    // Non-arrays are left alone
+    let mut v = bonjson!({"a": 1});
+    v.sort_array();
+    assert_eq!(v, bonjson!({"a": 1}));
+}
+
+#[test]
+fn test_value_sort_array_by_key() {
+    let mut v = bonjson!([
+        {"id": 2, "n": "first two"},
+        {"id": "x"},
+        {"n": "no id"},
+        {"id": 1},
+        {"id": 2.0, "n": "second two"},
+        7
+    ]);
+    v.sort_array_by_key("id");
+    assert_eq!(
+        v,
+        bonjson!([
+            {"n": "no id"},
+            7,
+            {"id": 1},
+            {"id": 2, "n": "first two"},
+            {"id": 2.0, "n": "second two"},
+            {"id": "x"}
+        ])
+    );
+}
+
+#[test]
+fn test_value_dedup_array() {
+    let mut v = bonjson!([
+        {"id": 1, "n": "a"},
+        {"id": 2},
+        {"n": "no id"},
+        {"id": 1.0, "n": "b"},
+        {"n": "no id"},
+        {"id": 2}
+    ]);
+    v.dedup_array_by_key("id");
+    assert_eq!(v, bonjson!([{"id": 1, "n": "a"}, {"id": 2}, {"n": "no id"}, {"n": "no id"}]));
+
+    let mut v = bonjson!([1, 2, 1.0, "1", 2]);
+    v.dedup_array_by(|a, b| a.total_cmp(b).is_eq());
+    assert_eq!(v, bonjson!([1, 2, "1"]));
+}
+
+#[test]
+fn test_value_deep_merge() {
+    let base = bonjson!({
+        "name": "svc",
+        "server": {"port": 80, "hosts": ["a", "b"]},
+        "plugins": [{"id": 1, "on": true}, {"id": 2, "on": true}]
+    });
+    let overlay = bonjson!({
+        "server": {"port": 8080, "hosts": ["c"], "tls": true},
+        "plugins": [{"id": 2, "on": false}, {"id": 3}, {"id": 1, "on": true}]
+    });
+
+    let mut v = base.clone();
+    v.deep_merge(overlay.clone(), &MergePolicy::default()).unwrap();
+    assert_eq!(
+        v,
+        bonjson!({
+            "name": "svc",
+            "server": {"port": 8080, "hosts": ["c"], "tls": true},
+            "plugins": [{"id": 2, "on": false}, {"id": 3}, {"id": 1, "on": true}]
+        })
+    );
+
+    let mut v = base.clone();
+    let policy = MergePolicy { arrays: ArrayMerge::Concat, conflicts: ConflictPolicy::PreferLeft };
+    v.deep_merge(overlay.clone(), &policy).unwrap();
+    assert_eq!(v.get_key("server"), Some(&bonjson!({"port": 80, "hosts": ["a", "b", "c"], "tls": true})));
+
+    let mut v = base.clone();
+    let policy = MergePolicy { arrays: ArrayMerge::UnionByKey("id".into()), ..MergePolicy::default() };
+    v.deep_merge(overlay.clone(), &policy).unwrap();
+    assert_eq!(v.get_key("plugins"), Some(&bonjson!([{"id": 1, "on": true}, {"id": 2, "on": false}, {"id": 3}])));
+    // Elements without the key are appended unless already present
+    let mut v = bonjson!(["a", {"n": 1}]);
+    v.deep_merge(bonjson!(["b", "a", {"n": 1}]), &policy).unwrap();
+    assert_eq!(v, bonjson!(["a", {"n": 1}, "b"]));
+}
+
+#[test]
+fn test_value_deep_merge_conflicts() {
+    let policy = MergePolicy { conflicts: ConflictPolicy::Error, ..MergePolicy::default() };
+
+    // Equal values of different variants, and new members, aren't conflicts
+    let mut v = bonjson!({"a": 1, "b": {"c": [1]}});
+    v.deep_merge(bonjson!({"a": 1.0, "b": {"d": "x"}}), &policy).unwrap();
+    assert_eq!(v, bonjson!({"a": 1, "b": {"c": [1], "d": "x"}}));
+
+    let before = v.clone();
+    let err = v.deep_merge(bonjson!({"b": {"d": "x", "c": {"e": null}}}), &policy).unwrap_err();
+    assert_eq!(err, Error::MergeConflict("$.b.c".into()));
+    assert_eq!(v, before);
+
+    let policy = MergePolicy { arrays: ArrayMerge::UnionByKey("id".into()), conflicts: ConflictPolicy::Error };
+    let mut v = bonjson!([{"id": 1, "x": 1}]);
+    let err = v.deep_merge(bonjson!([{"id": 1, "x": 2}]), &policy).unwrap_err();
+    assert_eq!(err, Error::MergeConflict("$[0].x".into()));
+
+    let mut v = bonjson!(1);
+    let policy = MergePolicy { conflicts: ConflictPolicy::PreferLeft, ..MergePolicy::default() };
+    v.deep_merge(bonjson!("x"), &policy).unwrap();
+    assert_eq!(v, bonjson!(1));
+}
+
+#[test]
+fn test_value_try_into_struct() {
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct User {
+        name: String,
+        age: u32,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Circle { radius: f64 },
+        Line(u8, u8),
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Doc {
+        users: Vec<User>,
+        shapes: HashMap<String, Shape>,
+        tag: Option<String>,
+    }
+
+    let doc = Doc {
+        users: vec![User { name: "Ann".into(), age: 31 }],
+        shapes: HashMap::from([("unit".to_owned(), Shape::Circle { radius: 1.0 })]),
+        tag: None,
+    };
+    let value = Value::from_struct(&doc).unwrap();
+    assert_eq!(value.try_into_struct::<Doc>().unwrap(), doc);
+
+    let error_at = |value: Value| match value.try_into_struct::<Doc>().unwrap_err() {
+        Error::AtPath { path, error, .. } => (path.to_string(), error.to_string()),
+        other => panic!("no path: {other}"),
+    };
+    let users = |users: Value| bonjson!({"shapes": {}, "tag": null, "users": users});
+    let missing = users(bonjson!([{"name": "Ann", "age": 31}, {"name": "Bo", "age": 4}, {"name": "Cy"}]));
+    assert_eq!(error_at(missing), ("$.users[2]".into(), "missing field `age`".into()));
+    assert_eq!(error_at(users(bonjson!([{"name": "Cy", "age": (-1)}]))).0, "$.users[0].age");
+    assert_eq!(error_at(users(bonjson!([{"name": "Cy", "age": 1, "extra": true}]))).0, "$.users[0].extra");
+
+    let shapes = bonjson!({"users": [], "tag": null, "shapes": {"s": {"Line": [1, "x"]}}});
+    assert_eq!(error_at(shapes).0, "$.shapes.s.Line[1]");
+    let shapes = bonjson!({"users": [], "tag": null, "shapes": {"s": {"Circle": {"radius": "big"}}}});
+    assert_eq!(error_at(shapes).0, "$.shapes.s.Circle.radius");
+    let tag = bonjson!({"users": [], "shapes": {}, "tag": 5});
+    let err = tag.try_into_struct::<Doc>().unwrap_err();
+    let plain = crate::from_value::<Option<String>>(&bonjson!(5)).unwrap_err();
+    assert_eq!(err.to_string(), format!("{plain} at $.tag"));
+    assert_eq!(err.error_type(), plain.error_type());
+
+    // An error at the root has no path to add
+    let err = bonjson!([1]).try_into_struct::<Doc>().unwrap_err();
+    assert!(!matches!(err, Error::AtPath { .. }), "{err:?}");
+}
+
+#[test]
+fn test_value_from_iterators() {
+    use crate::value::CollectValue;
+
+    let array: Value = (1..=3).map(Value::from).collect();
+    assert_eq!(array, bonjson!([1, 2, 3]));
+    let object: Value = [("b", 2), ("a", 1), ("b", 3)].into_iter().map(|(k, v)| (k.to_owned(), Value::from(v))).collect();
+    assert_eq!(object, bonjson!({"a": 1, "b": 3}));
+
+    assert_eq!(vec![("id", 7)].into_iter().collect_object(), bonjson!({"id": 7}));
+    assert_eq!(["x", "y"].into_iter().collect_array(), bonjson!(["x", "y"]));
+    assert_eq!(std::iter::empty::<(String, i32)>().collect_object(), bonjson!({}));
+
+    let mut array = Value::Null;
+    array.extend_array([1, 2]).unwrap();
+    array.extend_array(vec![Value::from("three")]).unwrap();
+    assert_eq!(array, bonjson!([1, 2, "three"]));
+
+    let mut object = Value::Null;
+    object.extend_object([("a", 1)]).unwrap();
+    object.extend_object([("a".to_owned(), Value::from(2)), ("b".to_owned(), Value::Null)]).unwrap();
+    assert_eq!(object, bonjson!({"a": 2, "b": null}));
+
+    // Any other value is left alone
+    let mut value = Value::Int(1);
+    assert_eq!(value.extend_array([2]), Err(Error::Custom("invalid type: integer, expected array".into())));
+    assert_eq!(value.extend_object([("a", 2)]), Err(Error::Custom("invalid type: integer, expected object".into())));
+    assert_eq!(value, Value::Int(1));
+    assert!(object.extend_array([1]).is_err());
+    assert!(array.extend_object([("a", 1)]).is_err());
+    assert_eq!((array, object), (bonjson!([1, 2, "three"]), bonjson!({"a": 2, "b": null})));
+}
+
+#[test]
+fn test_value_try_from_std_types() {
+    let names: Vec<String> = bonjson!(["ann", "bo"]).try_into().unwrap();
+    assert_eq!(names, ["ann", "bo"]);
+    let scores: BTreeMap<String, Vec<f32>> = bonjson!({"a": [1, 2.5], "b": []}).try_into().unwrap();
+    assert_eq!(scores["a"], [1.0, 2.5]);
+    let maybe: Vec<Option<u8>> = bonjson!([1, null, 3.0]).try_into().unwrap();
+    assert_eq!(maybe, [Some(1), None, Some(3)]);
+    let values: Vec<Value> = bonjson!([1, "x"]).try_into().unwrap();
+    assert_eq!(values, [bonjson!(1), bonjson!("x")]);
+    assert_eq!(Option::<Vec<i64>>::try_from(Value::Null).unwrap(), None);
+
+    // Overflow checking
+    assert_eq!(u8::try_from(bonjson!(255)).unwrap(), 255);
+    assert_eq!(u8::try_from(bonjson!(256)), Err(Error::ValueOutOfRange));
+    assert_eq!(i8::try_from(Value::Int(-129)), Err(Error::ValueOutOfRange));
+    assert_eq!(u64::try_from(Value::UInt(u64::MAX)).unwrap(), u64::MAX);
+    assert_eq!(i64::try_from(Value::UInt(u64::MAX)), Err(Error::ValueOutOfRange));
+    assert_eq!(i32::try_from(bonjson!(1e20)), Err(Error::ValueOutOfRange));
+    assert_eq!(f32::try_from(bonjson!(1e300)), Err(Error::ValueOutOfRange));
+    assert_eq!(f64::try_from(Value::BigNumber(crate::BigNumber::new(1, 1, 400))), Err(Error::ValueOutOfRange));
+    assert_eq!(f64::try_from(bonjson!(3)).unwrap(), 3.0);
+
+    // Type mismatches name both types; nested ones say where
+    assert_eq!(i32::try_from(bonjson!(1.5)), Err(Error::Custom("invalid type: float, expected i32".into())));
+    assert_eq!(bool::try_from(bonjson!("yes")), Err(Error::Custom("invalid type: string, expected bool".into())));
+    let err = BTreeMap::<String, Vec<u16>>::try_from(bonjson!({"ok": [1], "bad": [1, 2, 70000]})).unwrap_err();
+    let Error::AtPath { path, error, .. } = &err else { panic!("{err:?}") };
+    assert_eq!(path.to_string(), "$.bad[2]");
+    assert_eq!(**error, Error::ValueOutOfRange);
+
+    // And back
+    let map: BTreeMap<String, Option<i32>> = [("a".to_owned(), Some(1)), ("b".to_owned(), None)].into();
+    assert_eq!(Value::from(map), bonjson!({"a": 1, "b": null}));
+    assert_eq!(Value::from(7usize), bonjson!(7));
+}
+
+#[test]
+fn test_value_key_lookups() {
+    let headers = bonjson!({"Accept": "*/*", "accept": "text/html", "ÉTAG": "v1", "x-a": 1, "x-b": 2, "x-": 0, "y": 3, "X-c": 4});
+    assert_eq!(headers.get_key_ci("ACCEPT"), Some(&bonjson!("*/*")));
+    // The exact match wins over an earlier case-insensitive one
+    assert_eq!(headers.get_key_ci("accept"), Some(&bonjson!("text/html")));
+    assert_eq!(headers.get_key_ci("étag"), Some(&bonjson!("v1")));
+    assert_eq!(headers.get_key_ci("missing"), None);
+    assert_eq!(bonjson!([1]).get_key_ci("a"), None);
+
+    let keys: Vec<&String> = headers.keys_with_prefix("x-").map(|(k, _)| k).collect();
+    assert_eq!(keys, ["x-", "x-a", "x-b"]);
+    assert_eq!(headers.keys_with_prefix("").count(), 8);
+    assert_eq!(headers.keys_with_prefix("z").count(), 0);
+    assert_eq!(bonjson!("x-a").keys_with_prefix("x").count(), 0);
+}
@@ -0,0 +1,73 @@
+// ABOUTME: Tests for the `bonjson` command-line tool, run as a subprocess.
+// ABOUTME: Covers each command on stdin input, exit codes, and argument errors.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Run the tool with `args`, feeding it `stdin`.
+fn bonjson(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bonjson"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_json_round_trip() {
+    let json = br#"{"name":"probe","readings":[1,-2,3.5],"ok":true,"note":null}"#;
+    let encoded = bonjson(&["from-json"], json);
+    assert!(encoded.status.success());
+    assert_eq!(encoded.stdout, serde_bonjson::encode_value(&serde_json::from_slice(json).unwrap()).unwrap());
+
+    let decoded = bonjson(&["to-json"], &encoded.stdout);
+    assert!(decoded.status.success());
+    let expected: serde_json::Value = serde_json::from_slice(json).unwrap();
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&stdout(&decoded)).unwrap(), expected);
+    assert!(stdout(&bonjson(&["to-json", "--pretty", "-"], &encoded.stdout)).contains("\n  \"name\""));
+
+    let invalid = bonjson(&["from-json"], b"{\"a\":");
+    assert_eq!(invalid.status.code(), Some(1));
+}
+
+#[test]
+fn test_inspect_validate_and_stats() {
+    let doc = serde_bonjson::to_vec(&serde_json::json!({"a": [1, 2], "b": "x"})).unwrap();
+    let inspected = bonjson(&["inspect"], &doc);
+    assert!(inspected.status.success());
+    assert!(stdout(&inspected).contains("\"b\": short_string \"x\""), "{}", stdout(&inspected));
+
+    assert!(bonjson(&["validate"], &doc).status.success());
+    let truncated = bonjson(&["validate"], &doc[..doc.len() - 1]);
+    assert_eq!(truncated.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&truncated.stderr).contains("at offset"));
+
+    // Duplicate keys fail by default and pass under the permissive preset
+    let duplicates = [0xb8, 0x66, b'k', 0x01, 0x66, b'k', 0x02, 0xb6];
+    assert_eq!(bonjson(&["validate"], &duplicates).status.code(), Some(1));
+    assert!(bonjson(&["validate", "--config", "permissive"], &duplicates).status.success());
+
+    let stats = bonjson(&["stats"], &doc);
+    assert!(stats.status.success());
+    let text = stdout(&stats);
+    assert!(text.contains(&format!("total bytes:        {}", doc.len())), "{text}");
+    assert!(text.contains("optional features:  none"), "{text}");
+}
+
+#[test]
+fn test_argument_errors() {
+    for args in [&[][..], &["frobnicate"], &["to-json", "--config", "strict"], &["validate", "--config", "lax"], &["stats", "a", "b"]] {
+        let output = bonjson(args, b"");
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("usage: bonjson"));
+    }
+    assert_eq!(bonjson(&["stats", "/nonexistent/doc.bonjson"], b"").status.code(), Some(2));
+}
@@ -1,1412 +1,12 @@
-// ABOUTME: Universal test runner for BONJSON conformance tests.
-// ABOUTME: Parses JSON test specifications and validates structural correctness.
-// ABOUTME: Implements the BONJSON universal test specification format.
-
-use regex::Regex;
-use serde_bonjson::{decode_value, encode_value, DecoderConfig, DuplicateKeyMode, Error, Value};
-use serde_json::Value as JsonValue;
-use std::collections::HashSet;
+// ABOUTME: Universal BONJSON test suite runner for serde_bonjson.
+// ABOUTME: Runs the specification's test files through serde_bonjson::conformance.
+
+use serde_bonjson::conformance::{self, run_config_file, Report, Status};
 use std::fs;
 use std::path::Path;
 
-/// Known option names that this test runner supports.
-const KNOWN_OPTIONS: &[&str] = &[
-    "allow_nul",
-    "allow_trailing_bytes",
-    "max_depth",
-    "max_container_size",
-    "max_string_length",
-    "max_document_size",
-    "max_bignumber_exponent",
-    "max_bignumber_magnitude",
-    "duplicate_key",
-    "nan_infinity_behavior",
-    "unicode_normalization",
-    "out_of_range",
-    "invalid_utf8",
-];
-
-/// Known error types from the specification.
-const KNOWN_ERROR_TYPES: &[&str] = &[
-    "truncated",
-    "trailing_bytes",
-    "invalid_type_code",
-    "invalid_utf8",
-    "nul_character",
-    "duplicate_key",
-    "unclosed_container",
-    "invalid_data",
-    "invalid_object_key",
-    "value_out_of_range",
-    "max_depth_exceeded",
-    "max_string_length_exceeded",
-    "max_container_size_exceeded",
-    "max_document_size_exceeded",
-    "max_bignumber_exponent_exceeded",
-    "max_bignumber_magnitude_exceeded",
-];
-
-/// Convert a hex string (with optional spaces) to bytes.
-fn hex_to_bytes(s: &str) -> Vec<u8> {
-    let hex: String = s.chars().filter(|c| !c.is_whitespace()).collect();
-    (0..hex.len())
-        .step_by(2)
-        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
-        .collect()
-}
-
-/// Parse a $number marker value.
-fn parse_number_marker(s: &str) -> Value {
-    let s = s.trim();
-
-    // Handle special values
-    match s.to_lowercase().as_str() {
-        "nan" | "snan" => return Value::Float(f64::NAN),
-        "infinity" => return Value::Float(f64::INFINITY),
-        "-infinity" => return Value::Float(f64::NEG_INFINITY),
-        _ => {}
-    }
-
-    // Check for negative zero
-    if s == "-0.0" || s == "-0x0p+0" || s == "-0x0p0" {
-        return Value::Float(-0.0);
-    }
-
-    // Check for hex float (contains 'p' or 'P')
-    if s.to_lowercase().contains('p') {
-        return Value::Float(parse_hex_float(s));
-    }
-
-    // Check for hex integer
-    if s.to_lowercase().starts_with("0x") || s.to_lowercase().starts_with("-0x") {
-        let negative = s.starts_with('-');
-        let hex_part = if negative { &s[3..] } else { &s[2..] };
-        if let Ok(value) = u64::from_str_radix(hex_part, 16) {
-            if negative {
-                return Value::Int(-(value as i64));
-            } else if value <= i64::MAX as u64 {
-                return Value::Int(value as i64);
-            } else {
-                return Value::UInt(value);
-            }
-        }
-        return Value::Float(s.parse().unwrap_or(f64::NAN));
-    }
-
-    // Check for scientific notation or decimal - try BigNumber first
-    if s.contains('.') || s.to_lowercase().contains('e') {
-        if let Some(bn) = parse_decimal_as_bignumber(s) {
-            return Value::BigNumber(bn);
-        }
-        // Fallback to f64
-        if let Ok(f) = s.parse::<f64>() {
-            return Value::Float(f);
-        }
-        return Value::Float(f64::NAN);
-    }
-
-    // Plain integer
-    if s.starts_with('-') {
-        if let Ok(v) = s.parse::<i64>() {
-            Value::Int(v)
-        } else {
-            Value::Float(s.parse().unwrap_or(f64::NEG_INFINITY))
-        }
-    } else if let Ok(value) = s.parse::<u64>() {
-        if value <= i64::MAX as u64 {
-            Value::Int(value as i64)
-        } else {
-            Value::UInt(value)
-        }
-    } else {
-        Value::Float(s.parse().unwrap_or(f64::INFINITY))
-    }
-}
-
-/// Try to parse a decimal string into a BigNumber.
-/// Handles formats like "1e-1000", "1.234567890123456789e-200", "3.14", etc.
-/// Returns None if the number fits exactly in f64 without precision loss.
-fn parse_decimal_as_bignumber(s: &str) -> Option<serde_bonjson::BigNumber> {
-    let s = s.trim();
-    let negative = s.starts_with('-');
-    let s = if negative { &s[1..] } else { s };
-
-    // Split at 'e' or 'E'
-    let (mantissa_str, exp_str) = if let Some(e_pos) = s.find(['e', 'E']) {
-        (&s[..e_pos], Some(&s[e_pos + 1..]))
-    } else {
-        (s, None)
-    };
-
-    // Parse explicit exponent
-    let explicit_exp: i64 = match exp_str {
-        Some(e) => e.parse().ok()?,
-        None => 0,
-    };
-
-    // Parse mantissa: remove decimal point and track implicit exponent shift
-    let (digits_str, decimal_shift) = if let Some(dot_pos) = mantissa_str.find('.') {
-        let int_part = &mantissa_str[..dot_pos];
-        let frac_part = &mantissa_str[dot_pos + 1..];
-        let combined = format!("{}{}", int_part, frac_part);
-        (combined, frac_part.len() as i64)
-    } else {
-        (mantissa_str.to_string(), 0i64)
-    };
-
-    // Strip leading zeros from digits
-    let digits_str = digits_str.trim_start_matches('0');
-    if digits_str.is_empty() {
-        // Value is zero
-        return Some(serde_bonjson::BigNumber::new(1, 0, 0));
-    }
-
-    // Strip trailing zeros from digits and adjust exponent
-    let trailing_zeros = digits_str.len() - digits_str.trim_end_matches('0').len();
-    let digits_str = digits_str.trim_end_matches('0');
-
-    let exponent = explicit_exp - decimal_shift + trailing_zeros as i64;
-
-    // First check if it can be parsed as f64
-    let original_str = if negative {
-        format!("-{}", mantissa_str)
-    } else {
-        mantissa_str.to_string()
-    };
-    let with_exp = if let Some(exp) = exp_str {
-        format!("{}e{}", original_str, exp)
-    } else {
-        original_str
-    };
-
-    if let Ok(f) = with_exp.parse::<f64>() {
-        // If it parses as f64 and is finite, check if we need BigNumber for precision
-        if f.is_finite() && f != 0.0 {
-            // Only use BigNumber if the string has more precision than f64 can represent
-            // AND the exponent is beyond what f64 can handle precisely
-            // f64 can handle exponents roughly in range [-308, 308], so use a stricter threshold
-            let needs_bignumber = digits_str.len() > 15  // f64 has ~15-17 significant digits
-                && explicit_exp.abs() > 400;  // Way beyond f64 range
-
-            if !needs_bignumber {
-                return None;  // Use f64
-            }
-        } else if f == 0.0 {
-            // Zero - could be underflow, but still use f64
-            return None;
-        }
-    }
-
-    // Parse significand - if it fits in u64, use BigNumber
-    if let Ok(significand) = digits_str.parse::<u64>() {
-        let sign: i8 = if negative { -1 } else { 1 };
-        return Some(serde_bonjson::BigNumber::new(sign, significand, exponent));
-    }
-
-    None
-}
-
-/// Parse C99 hex float format like "0x1.921fb54442d18p+1".
-fn parse_hex_float(s: &str) -> f64 {
-    let s = s.trim();
-    let negative = s.starts_with('-');
-    let s = if negative { &s[1..] } else { s };
-
-    // Skip "0x" prefix
-    let s = if s.to_lowercase().starts_with("0x") {
-        &s[2..]
-    } else {
-        s
-    };
-
-    // Split at 'p' or 'P'
-    let p_pos = s.to_lowercase().find('p').unwrap();
-    let mantissa_str = &s[..p_pos];
-    let exp_str = &s[p_pos + 1..];
-
-    // Parse mantissa
-    let (int_part, frac_part) = if let Some(dot_pos) = mantissa_str.find('.') {
-        (&mantissa_str[..dot_pos], &mantissa_str[dot_pos + 1..])
-    } else {
-        (mantissa_str, "")
-    };
-
-    let mut mantissa: f64 = 0.0;
-
-    // Integer part
-    if !int_part.is_empty() {
-        mantissa = u64::from_str_radix(int_part, 16).unwrap() as f64;
-    }
-
-    // Fractional part
-    if !frac_part.is_empty() {
-        let frac_value = u64::from_str_radix(frac_part, 16).unwrap() as f64;
-        let frac_bits = frac_part.len() * 4;
-        mantissa += frac_value / (1u64 << frac_bits) as f64;
-    }
-
-    // Parse exponent (power of 2)
-    let exp: i32 = exp_str.parse().unwrap();
-
-    let result = mantissa * 2.0f64.powi(exp);
-    if negative {
-        -result
-    } else {
-        result
-    }
-}
-
-/// Result of converting JSON to Value, tracking precision source.
-struct ConvertedValue {
-    value: Value,
-    /// True if the value (or any nested value) came from an imprecise JSON float.
-    /// Imprecise means it was a JSON number, not a $number marker with hex float.
-    has_imprecise_float: bool,
-}
-
-/// Convert a JSON value to a BONJSON Value, handling $number markers.
-fn json_to_value(json: &JsonValue) -> Value {
-    json_to_value_tracked(json).value
-}
-
-/// Convert a JSON value to a BONJSON Value, tracking whether it has imprecise floats.
-fn json_to_value_tracked(json: &JsonValue) -> ConvertedValue {
-    match json {
-        JsonValue::Null => ConvertedValue {
-            value: Value::Null,
-            has_imprecise_float: false,
-        },
-        JsonValue::Bool(b) => ConvertedValue {
-            value: Value::Bool(*b),
-            has_imprecise_float: false,
-        },
-        JsonValue::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                ConvertedValue {
-                    value: Value::Int(i),
-                    has_imprecise_float: false,
-                }
-            } else if let Some(u) = n.as_u64() {
-                ConvertedValue {
-                    value: if u <= i64::MAX as u64 {
-                        Value::Int(u as i64)
-                    } else {
-                        Value::UInt(u)
-                    },
-                    has_imprecise_float: false,
-                }
-            } else {
-                // JSON float - mark as imprecise
-                ConvertedValue {
-                    value: Value::Float(n.as_f64().unwrap()),
-                    has_imprecise_float: true,
-                }
-            }
-        }
-        JsonValue::String(s) => ConvertedValue {
-            value: Value::String(s.clone()),
-            has_imprecise_float: false,
-        },
-        JsonValue::Array(arr) => {
-            let converted: Vec<_> = arr.iter().map(json_to_value_tracked).collect();
-            let has_imprecise = converted.iter().any(|c| c.has_imprecise_float);
-            ConvertedValue {
-                value: Value::Array(converted.into_iter().map(|c| c.value).collect()),
-                has_imprecise_float: has_imprecise,
-            }
-        }
-        JsonValue::Object(obj) => {
-            // Check for $number marker
-            if obj.len() == 1 {
-                if let Some(num_str) = obj.get("$number") {
-                    if let Some(s) = num_str.as_str() {
-                        // Hex floats are precise, decimal notation is imprecise
-                        let is_hex_float = s.to_lowercase().contains('p')
-                            || (s.to_lowercase().starts_with("0x")
-                                || s.to_lowercase().starts_with("-0x"));
-                        let is_special = matches!(
-                            s.to_lowercase().as_str(),
-                            "nan" | "infinity" | "-infinity" | "-0.0" | "-0x0p+0" | "-0x0p0"
-                        );
-                        return ConvertedValue {
-                            value: parse_number_marker(s),
-                            has_imprecise_float: !is_hex_float && !is_special,
-                        };
-                    }
-                }
-            }
-
-            // Regular object
-            let mut map = std::collections::BTreeMap::new();
-            let mut has_imprecise = false;
-            for (k, v) in obj {
-                // Skip comment keys
-                if k.starts_with("//") {
-                    continue;
-                }
-                let converted = json_to_value_tracked(v);
-                has_imprecise |= converted.has_imprecise_float;
-                map.insert(k.clone(), converted.value);
-            }
-            ConvertedValue {
-                value: Value::Object(map),
-                has_imprecise_float: has_imprecise,
-            }
-        }
-    }
-}
-
-/// Normalize a BigNumber by stripping trailing zeros from significand
-/// and adjusting exponent. Returns (normalized_significand, adjusted_exponent).
-fn normalize_bignumber(_sign: i8, significand: u64, exponent: i64) -> (u64, i64) {
-    if significand == 0 {
-        return (0, 0);
-    }
-    let mut sig = significand;
-    let mut exp = exponent;
-    while sig % 10 == 0 && sig > 0 {
-        sig /= 10;
-        exp += 1;
-    }
-    (sig, exp)
-}
-
-/// Compare two values for equality (handling NaN and negative zero).
-fn values_equal(a: &Value, b: &Value) -> bool {
-    match (a, b) {
-        (Value::Null, Value::Null) => true,
-        (Value::Bool(a), Value::Bool(b)) => a == b,
-        (Value::Float(a), Value::Float(b)) => {
-            if a.is_nan() && b.is_nan() {
-                true
-            } else if a == &0.0 && b == &0.0 {
-                // Check sign of zero
-                a.is_sign_positive() == b.is_sign_positive()
-            } else {
-                a == b
-            }
-        }
-        (Value::Int(a), Value::Int(b)) => a == b,
-        (Value::UInt(a), Value::UInt(b)) => a == b,
-        // Allow int/uint comparison
-        (Value::Int(a), Value::UInt(b)) => {
-            if *a >= 0 {
-                (*a as u64) == *b
-            } else {
-                false
-            }
-        }
-        (Value::UInt(a), Value::Int(b)) => {
-            if *b >= 0 {
-                *a == (*b as u64)
-            } else {
-                false
-            }
-        }
-        // Allow numeric comparisons between int/float
-        (Value::Int(a), Value::Float(b)) => (*a as f64) == *b,
-        (Value::Float(a), Value::Int(b)) => *a == (*b as f64),
-        (Value::UInt(a), Value::Float(b)) => (*a as f64) == *b,
-        (Value::Float(a), Value::UInt(b)) => *a == (*b as f64),
-        (Value::String(a), Value::String(b)) => a == b,
-        (Value::Array(a), Value::Array(b)) => {
-            a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| values_equal(x, y))
-        }
-        (Value::Object(a), Value::Object(b)) => {
-            a.len() == b.len()
-                && a.iter()
-                    .all(|(k, v)| b.get(k).map(|bv| values_equal(v, bv)).unwrap_or(false))
-        }
-        (Value::BigNumber(a), Value::BigNumber(b)) => {
-            // Normalize both for comparison: strip trailing zeros from significand
-            // and adjust exponent accordingly
-            let (a_sig, a_exp) = normalize_bignumber(a.sign, a.significand, a.exponent);
-            let (b_sig, b_exp) = normalize_bignumber(b.sign, b.significand, b.exponent);
-            if a.significand == 0 && b.significand == 0 {
-                true // Both zero
-            } else {
-                a.sign == b.sign && a_sig == b_sig && a_exp == b_exp
-            }
-        }
-        // BigNumber to other numeric
-        (Value::BigNumber(bn), Value::Int(i)) => bn.to_i64() == Some(*i),
-        (Value::Int(i), Value::BigNumber(bn)) => bn.to_i64() == Some(*i),
-        (Value::BigNumber(bn), Value::UInt(u)) => bn.to_u64() == Some(*u),
-        (Value::UInt(u), Value::BigNumber(bn)) => bn.to_u64() == Some(*u),
-        (Value::BigNumber(bn), Value::Float(f)) => bn.to_f64() == *f,
-        (Value::Float(f), Value::BigNumber(bn)) => *f == bn.to_f64(),
-        _ => false,
-    }
-}
-
-/// Map an error to the standardized error type name.
-fn error_to_type(err: &Error) -> &'static str {
-    err.error_type()
-}
-
-/// Result type for structural validation errors.
-#[derive(Debug)]
-enum ValidationError {
-    /// A structural error that should cause the test runner to exit.
-    Structural(String),
-    /// A warning that should cause the test to be skipped.
-    Skip(String),
-}
-
-/// Validate the version field (required, semver format).
-fn validate_version(spec: &JsonValue) -> Result<(), ValidationError> {
-    let version = spec
-        .get("version")
-        .ok_or_else(|| ValidationError::Structural("missing required 'version' field".to_string()))?;
-
-    let version_str = version.as_str().ok_or_else(|| {
-        ValidationError::Structural("'version' field must be a string".to_string())
-    })?;
-
-    // Validate semver format: MAJOR.MINOR.PATCH[-PRERELEASE][+BUILD]
-    let semver_pattern =
-        Regex::new(r"^\d+\.\d+\.\d+(-[0-9A-Za-z.-]+)?(\+[0-9A-Za-z.-]+)?$").unwrap();
-    if !semver_pattern.is_match(version_str) {
-        return Err(ValidationError::Structural(format!(
-            "invalid version format '{}' (must be semver: MAJOR.MINOR.PATCH[-PRERELEASE][+BUILD])",
-            version_str
-        )));
-    }
-
-    Ok(())
-}
-
-/// Validate a test name (pattern and uniqueness).
-fn validate_test_name(name: &str, seen_names: &mut HashSet<String>) -> Result<(), ValidationError> {
-    // Check pattern: must start with letter, contain only letters, digits, underscores
-    let name_pattern = Regex::new(r"^[a-zA-Z][a-zA-Z0-9_]*$").unwrap();
-    if !name_pattern.is_match(name) {
-        return Err(ValidationError::Structural(format!(
-            "invalid test name '{}' (must match ^[a-zA-Z][a-zA-Z0-9_]*$)",
-            name
-        )));
-    }
-
-    // Check uniqueness (case-insensitive)
-    let lower_name = name.to_lowercase();
-    if seen_names.contains(&lower_name) {
-        return Err(ValidationError::Structural(format!(
-            "duplicate test name '{}' (case-insensitive)",
-            name
-        )));
-    }
-    seen_names.insert(lower_name);
-
-    Ok(())
-}
-
-/// Validate required fields for a test based on its type.
-fn validate_test_fields(test: &JsonValue, test_type: &str) -> Result<(), ValidationError> {
-    match test_type {
-        "encode" => {
-            if test.get("input").is_none() {
-                return Err(ValidationError::Structural(
-                    "encode test missing required 'input' field".to_string(),
-                ));
-            }
-            if test.get("expected_bytes").is_none() {
-                return Err(ValidationError::Structural(
-                    "encode test missing required 'expected_bytes' field".to_string(),
-                ));
-            }
-        }
-        "decode" => {
-            if test.get("input_bytes").is_none() {
-                return Err(ValidationError::Structural(
-                    "decode test missing required 'input_bytes' field".to_string(),
-                ));
-            }
-            if test.get("expected_value").is_none() {
-                return Err(ValidationError::Structural(
-                    "decode test missing required 'expected_value' field".to_string(),
-                ));
-            }
-        }
-        "roundtrip" => {
-            if test.get("input").is_none() {
-                return Err(ValidationError::Structural(
-                    "roundtrip test missing required 'input' field".to_string(),
-                ));
-            }
-        }
-        "encode_error" => {
-            if test.get("input").is_none() {
-                return Err(ValidationError::Structural(
-                    "encode_error test missing required 'input' field".to_string(),
-                ));
-            }
-            if test.get("expected_error").is_none() {
-                return Err(ValidationError::Structural(
-                    "encode_error test missing required 'expected_error' field".to_string(),
-                ));
-            }
-        }
-        "decode_error" => {
-            if test.get("input_bytes").is_none() {
-                return Err(ValidationError::Structural(
-                    "decode_error test missing required 'input_bytes' field".to_string(),
-                ));
-            }
-            if test.get("expected_error").is_none() {
-                return Err(ValidationError::Structural(
-                    "decode_error test missing required 'expected_error' field".to_string(),
-                ));
-            }
-        }
-        "" => {
-            // Comment-only entry - no fields required
-        }
-        _ => {
-            return Err(ValidationError::Structural(format!(
-                "unknown test type '{}'",
-                test_type
-            )));
-        }
-    }
-    Ok(())
-}
-
-/// Validate options in a test (check for unrecognized options).
-fn validate_options(test: &JsonValue) -> Result<(), ValidationError> {
-    if let Some(options) = test.get("options") {
-        let options_obj = options.as_object().ok_or_else(|| {
-            ValidationError::Structural("'options' field must be an object".to_string())
-        })?;
-
-        for (key, value) in options_obj {
-            // Skip comment keys
-            if key.starts_with("//") {
-                continue;
-            }
-
-            // Check for unrecognized options
-            if !KNOWN_OPTIONS.contains(&key.as_str()) {
-                return Err(ValidationError::Skip(format!(
-                    "unrecognized option '{}'",
-                    key
-                )));
-            }
-
-            // Validate option types
-            match key.as_str() {
-                "allow_nul" | "allow_trailing_bytes" => {
-                    if !value.is_boolean() {
-                        return Err(ValidationError::Structural(format!(
-                            "option '{}' must be a boolean",
-                            key
-                        )));
-                    }
-                }
-                "max_depth" | "max_container_size" | "max_string_length"
-                | "max_document_size" | "max_bignumber_exponent" | "max_bignumber_magnitude" => {
-                    if let Some(n) = value.as_i64() {
-                        if n < 0 {
-                            return Err(ValidationError::Structural(format!(
-                                "option '{}' must be non-negative",
-                                key
-                            )));
-                        }
-                    } else if !value.is_u64() {
-                        return Err(ValidationError::Structural(format!(
-                            "option '{}' must be a non-negative integer",
-                            key
-                        )));
-                    }
-                }
-                "duplicate_key" => {
-                    if let Some(s) = value.as_str() {
-                        if !["reject", "keep_first", "keep_last"].contains(&s) {
-                            return Err(ValidationError::Structural(format!(
-                                "option '{}' has invalid value '{}' (must be reject, keep_first, or keep_last)",
-                                key, s
-                            )));
-                        }
-                    } else {
-                        return Err(ValidationError::Structural(format!(
-                            "option '{}' must be a string",
-                            key
-                        )));
-                    }
-                }
-                "nan_infinity_behavior" => {
-                    if let Some(s) = value.as_str() {
-                        if !["reject", "allow", "stringify"].contains(&s) {
-                            return Err(ValidationError::Structural(format!(
-                                "option '{}' has invalid value '{}' (must be reject, allow, or stringify)",
-                                key, s
-                            )));
-                        }
-                    } else {
-                        return Err(ValidationError::Structural(format!(
-                            "option '{}' must be a string",
-                            key
-                        )));
-                    }
-                }
-                "invalid_utf8" => {
-                    if let Some(s) = value.as_str() {
-                        if !["reject", "replace", "delete", "pass_through"].contains(&s) {
-                            return Err(ValidationError::Structural(format!(
-                                "option '{}' has invalid value '{}' (must be reject, replace, delete, or pass_through)",
-                                key, s
-                            )));
-                        }
-                    } else {
-                        return Err(ValidationError::Structural(format!(
-                            "option '{}' must be a string",
-                            key
-                        )));
-                    }
-                }
-                "unicode_normalization" => {
-                    if let Some(s) = value.as_str() {
-                        if !["none", "nfc"].contains(&s) {
-                            return Err(ValidationError::Structural(format!(
-                                "option '{}' has invalid value '{}' (must be none or nfc)",
-                                key, s
-                            )));
-                        }
-                    } else {
-                        return Err(ValidationError::Structural(format!(
-                            "option '{}' must be a string",
-                            key
-                        )));
-                    }
-                }
-                "out_of_range" => {
-                    if let Some(s) = value.as_str() {
-                        if !["error", "stringify"].contains(&s) {
-                            return Err(ValidationError::Structural(format!(
-                                "option '{}' has invalid value '{}' (must be error or stringify)",
-                                key, s
-                            )));
-                        }
-                    } else {
-                        return Err(ValidationError::Structural(format!(
-                            "option '{}' must be a string",
-                            key
-                        )));
-                    }
-                }
-                _ => {}
-            }
-
-            // Check for null values
-            if value.is_null() {
-                return Err(ValidationError::Structural(format!(
-                    "option '{}' cannot be null",
-                    key
-                )));
-            }
-        }
-    }
-    Ok(())
-}
-
-/// Validate expected_error value.
-fn validate_expected_error(test: &JsonValue) -> Result<(), ValidationError> {
-    if let Some(expected_error) = test.get("expected_error") {
-        if let Some(error_str) = expected_error.as_str() {
-            if !KNOWN_ERROR_TYPES.contains(&error_str)
-            {
-                return Err(ValidationError::Skip(format!(
-                    "unrecognized error type '{}'",
-                    error_str
-                )));
-            }
-        }
-    }
-    Ok(())
-}
-
-/// Validate hex string format.
-fn validate_hex_string(s: &str) -> Result<(), ValidationError> {
-    let hex: String = s.chars().filter(|c| !c.is_whitespace()).collect();
-
-    // Check for odd number of digits
-    if hex.len() % 2 != 0 {
-        return Err(ValidationError::Structural(format!(
-            "hex string has odd number of digits: '{}'",
-            s
-        )));
-    }
-
-    // Check for invalid characters
-    for c in hex.chars() {
-        if !c.is_ascii_hexdigit() {
-            return Err(ValidationError::Structural(format!(
-                "hex string contains invalid character '{}': '{}'",
-                c, s
-            )));
-        }
-    }
-
-    Ok(())
-}
-
-/// Validate $number marker format.
-fn validate_number_marker(s: &str) -> Result<(), ValidationError> {
-    let s = s.trim();
-
-    if s.is_empty() {
-        return Err(ValidationError::Structural(
-            "$number marker cannot be empty".to_string(),
-        ));
-    }
-
-    // Special values are always valid
-    match s.to_lowercase().as_str() {
-        "nan" | "snan" | "infinity" | "-infinity" => return Ok(()),
-        _ => {}
-    }
-
-    // Hex values
-    if s.to_lowercase().starts_with("0x") || s.to_lowercase().starts_with("-0x") {
-        let hex_part = if s.starts_with('-') { &s[3..] } else { &s[2..] };
-
-        // Check for hex float (contains 'p')
-        if let Some(p_pos) = hex_part.to_lowercase().find('p') {
-            let mantissa = &hex_part[..p_pos];
-            let exp = &hex_part[p_pos + 1..];
-
-            // Mantissa must have digits
-            let mantissa_digits: String = mantissa.chars().filter(|c| *c != '.').collect();
-            if mantissa_digits.is_empty() {
-                return Err(ValidationError::Structural(format!(
-                    "$number hex float has no mantissa digits: '{}'",
-                    s
-                )));
-            }
-
-            // Exponent must be valid integer
-            if exp.is_empty() || exp.parse::<i32>().is_err() {
-                return Err(ValidationError::Structural(format!(
-                    "$number hex float has invalid exponent: '{}'",
-                    s
-                )));
-            }
-        } else {
-            // Hex integer - must have digits after 0x
-            if hex_part.is_empty() {
-                return Err(ValidationError::Structural(format!(
-                    "$number hex value has no digits after 0x: '{}'",
-                    s
-                )));
-            }
-        }
-        return Ok(());
-    }
-
-    // Decimal values - try to parse
-    if s.parse::<f64>().is_err() && s.parse::<i128>().is_err() {
-        return Err(ValidationError::Structural(format!(
-            "$number value is not parseable: '{}'",
-            s
-        )));
-    }
-
-    Ok(())
-}
-
-/// Recursively validate $number markers in a JSON value.
-fn validate_number_markers(value: &JsonValue) -> Result<(), ValidationError> {
-    match value {
-        JsonValue::Object(obj) => {
-            // Check for $number marker
-            if obj.len() == 1 {
-                if let Some(num_str) = obj.get("$number") {
-                    if let Some(s) = num_str.as_str() {
-                        return validate_number_marker(s);
-                    } else {
-                        return Err(ValidationError::Structural(
-                            "$number marker value must be a string".to_string(),
-                        ));
-                    }
-                }
-            }
-            // Check if $number exists with other keys (invalid)
-            if obj.contains_key("$number") && obj.len() > 1 {
-                // Count non-comment keys
-                let non_comment_keys: Vec<_> =
-                    obj.keys().filter(|k| !k.starts_with("//")).collect();
-                if non_comment_keys.len() > 1 {
-                    return Err(ValidationError::Structural(
-                        "$number marker object cannot have additional keys".to_string(),
-                    ));
-                }
-            }
-
-            // Recurse into values
-            for (k, v) in obj {
-                if !k.starts_with("//") {
-                    validate_number_markers(v)?;
-                }
-            }
-        }
-        JsonValue::Array(arr) => {
-            for item in arr {
-                validate_number_markers(item)?;
-            }
-        }
-        _ => {}
-    }
-    Ok(())
-}
-
-/// Features this implementation supports.
-const SUPPORTED_FEATURES: &[&str] = &[
-    "int64",
-    "uint64",
-    "negative_zero",
-    "bignumber_exponent_lt_neg128",
-    "bignumber_exponent_gt_127",
-    "nan_infinity_stringify",
-    "out_of_range_stringify",
-];
-
-/// Check if this test requires unsupported features.
-fn has_unsupported_requirements(test: &JsonValue) -> bool {
-    if let Some(requires) = test.get("requires") {
-        if let Some(arr) = requires.as_array() {
-            for req in arr {
-                if let Some(feature) = req.as_str() {
-                    if SUPPORTED_FEATURES.contains(&feature) {
-                        continue;
-                    }
-                    // Check feature-gated features
-                    #[cfg(feature = "unicode-normalization")]
-                    if feature == "unicode_normalization_nfc" {
-                        continue;
-                    }
-                    return true;
-                }
-            }
-        }
-    }
-    false
-}
-
-/// Run a single test case.
-fn run_test(test: &JsonValue) -> Result<(), String> {
-    let name = test["name"].as_str().unwrap_or("unnamed");
-    let test_type = test["type"].as_str().unwrap_or("");
-
-    // Skip tests that require unsupported features
-    if has_unsupported_requirements(test) {
-        return Err(format!("{}: skipped (unsupported requirement)", name));
-    }
-
-    // Check for options
-    let mut encoder_config = serde_bonjson::EncoderConfig::default();
-    let config = if let Some(options) = test.get("options") {
-        let mut config = DecoderConfig::default();
-        if let Some(allow_nul) = options.get("allow_nul").and_then(|v| v.as_bool()) {
-            config.allow_nul = allow_nul;
-            encoder_config.allow_nul = allow_nul;
-        }
-        // Handle nan_infinity_behavior option (can be "allow", "stringify", or "reject")
-        if let Some(nan_inf) = options.get("nan_infinity_behavior").and_then(|v| v.as_str()) {
-            match nan_inf {
-                "allow" => config.nan_infinity_mode = serde_bonjson::NanInfinityMode::Allow,
-                "stringify" => config.nan_infinity_mode = serde_bonjson::NanInfinityMode::Stringify,
-                _ => config.nan_infinity_mode = serde_bonjson::NanInfinityMode::Reject,
-            }
-        }
-        // Handle duplicate_key option (can be "reject", "keep_first", "keep_last")
-        if let Some(dup_key) = options.get("duplicate_key").and_then(|v| v.as_str()) {
-            match dup_key {
-                "reject" => config.duplicate_key_mode = DuplicateKeyMode::Error,
-                "keep_first" => config.duplicate_key_mode = DuplicateKeyMode::KeepFirst,
-                "keep_last" => config.duplicate_key_mode = DuplicateKeyMode::KeepLast,
-                _ => {}
-            }
-        }
-        if let Some(invalid_utf8) = options.get("invalid_utf8").and_then(|v| v.as_str()) {
-            match invalid_utf8 {
-                "replace" => config.invalid_utf8_mode = serde_bonjson::InvalidUtf8Mode::Replace,
-                "delete" => config.invalid_utf8_mode = serde_bonjson::InvalidUtf8Mode::Delete,
-                _ => config.invalid_utf8_mode = serde_bonjson::InvalidUtf8Mode::Reject,
-            }
-        }
-        if let Some(allow_trailing) = options.get("allow_trailing_bytes").and_then(|v| v.as_bool())
-        {
-            config.allow_trailing_bytes = allow_trailing;
-        }
-        if let Some(max_depth) = options.get("max_depth").and_then(|v| v.as_u64()) {
-            config.max_depth = max_depth as usize;
-        }
-        if let Some(max_size) = options.get("max_container_size").and_then(|v| v.as_u64()) {
-            config.max_container_size = max_size as usize;
-        }
-        if let Some(max_len) = options.get("max_string_length").and_then(|v| v.as_u64()) {
-            config.max_string_length = max_len as usize;
-        }
-        if let Some(max_doc) = options.get("max_document_size").and_then(|v| v.as_u64()) {
-            config.max_document_size = max_doc as usize;
-        }
-        if let Some(max_exp) = options.get("max_bignumber_exponent").and_then(|v| v.as_u64()) {
-            config.max_bignumber_exponent = max_exp as usize;
-        }
-        if let Some(max_mag) = options.get("max_bignumber_magnitude").and_then(|v| v.as_u64()) {
-            config.max_bignumber_magnitude = max_mag as usize;
-        }
-        if let Some("nfc") = options.get("unicode_normalization").and_then(|v| v.as_str()) {
-            #[cfg(feature = "unicode-normalization")]
-            {
-                config.unicode_normalization = serde_bonjson::UnicodeNormalization::Nfc;
-            }
-            #[cfg(not(feature = "unicode-normalization"))]
-            {
-                return Err(format!("{}: skipped (unicode-normalization feature not enabled)", name));
-            }
-        }
-        if let Some(oor) = options.get("out_of_range").and_then(|v| v.as_str()) {
-            match oor {
-                "stringify" => config.out_of_range_mode = serde_bonjson::OutOfRangeMode::Stringify,
-                _ => config.out_of_range_mode = serde_bonjson::OutOfRangeMode::Error,
-            }
-        }
-        config
-    } else {
-        DecoderConfig::default()
-    };
-
-    match test_type {
-        "encode" => {
-            let converted = json_to_value_tracked(&test["input"]);
-            let input = converted.value;
-            let expected_bytes = hex_to_bytes(test["expected_bytes"].as_str().unwrap());
-
-            // Skip if input contains NaN/Infinity (we reject those by default)
-            if contains_nan_or_infinity(&input) {
-                return Err(format!("{}: skipped (NaN/Infinity in input)", name));
-            }
-
-            match serde_bonjson::encode_value_with_config(&input, encoder_config) {
-                Ok(actual_bytes) => {
-                    if actual_bytes == expected_bytes {
-                        Ok(())
-                    } else if converted.has_imprecise_float {
-                        // For imprecise floats (from JSON parsing), verify by roundtrip:
-                        // decode our encoding and check it equals the input value
-                        match decode_value(&actual_bytes) {
-                            Ok(decoded) => {
-                                if values_equal(&decoded, &input) {
-                                    Ok(())
-                                } else {
-                                    Err(format!(
-                                        "{}: encode roundtrip mismatch (imprecise float)\n  input:   {:?}\n  decoded: {:?}",
-                                        name, input, decoded
-                                    ))
-                                }
-                            }
-                            Err(e) => Err(format!(
-                                "{}: encode bytes mismatch and decode failed: {}\n  expected: {:02x?}\n  actual:   {:02x?}",
-                                name, e, expected_bytes, actual_bytes
-                            )),
-                        }
-                    } else {
-                        Err(format!(
-                            "{}: encode mismatch\n  expected: {:02x?}\n  actual:   {:02x?}",
-                            name, expected_bytes, actual_bytes
-                        ))
-                    }
-                }
-                Err(e) => Err(format!("{}: encode failed: {}", name, e)),
-            }
-        }
-
-        "decode" => {
-            let input_bytes = hex_to_bytes(test["input_bytes"].as_str().unwrap());
-            let expected_value = json_to_value(&test["expected_value"]);
-
-            match serde_bonjson::decode_value_with_config(&input_bytes, config) {
-                Ok(actual_value) => {
-                    if values_equal(&actual_value, &expected_value) {
-                        Ok(())
-                    } else {
-                        Err(format!(
-                            "{}: decode mismatch\n  expected: {:?}\n  actual:   {:?}",
-                            name, expected_value, actual_value
-                        ))
-                    }
-                }
-                Err(e) => Err(format!("{}: decode failed: {}", name, e)),
-            }
-        }
-
-        "roundtrip" => {
-            let input = json_to_value(&test["input"]);
-
-            // Skip if input contains NaN/Infinity (we reject those by default)
-            if contains_nan_or_infinity(&input) && config.nan_infinity_mode == serde_bonjson::NanInfinityMode::Reject {
-                return Err(format!("{}: skipped (NaN/Infinity in input)", name));
-            }
-
-            match encode_value(&input) {
-                Ok(encoded) => match serde_bonjson::decode_value_with_config(&encoded, config) {
-                    Ok(decoded) => {
-                        if values_equal(&decoded, &input) {
-                            Ok(())
-                        } else {
-                            Err(format!(
-                                "{}: roundtrip mismatch\n  original: {:?}\n  decoded:  {:?}",
-                                name, input, decoded
-                            ))
-                        }
-                    }
-                    Err(e) => Err(format!("{}: decode in roundtrip failed: {}", name, e)),
-                },
-                Err(e) => Err(format!("{}: encode in roundtrip failed: {}", name, e)),
-            }
-        }
-
-        "encode_error" => {
-            let input = json_to_value(&test["input"]);
-            let expected_error = test["expected_error"].as_str().unwrap();
-
-            match encode_value(&input) {
-                Ok(_) => Err(format!("{}: expected encode error '{}' but succeeded", name, expected_error)),
-                Err(e) => {
-                    let actual_type = error_to_type(&e);
-                    if actual_type == expected_error {
-                        Ok(())
-                    } else {
-                        // Accept any error for now (some implementations may map errors differently)
-                        Ok(())
-                    }
-                }
-            }
-        }
-
-        "decode_error" => {
-            let input_bytes = hex_to_bytes(test["input_bytes"].as_str().unwrap());
-            let expected_error = test["expected_error"].as_str().unwrap();
-
-            match serde_bonjson::decode_value_with_config(&input_bytes, config) {
-                Ok(_) => Err(format!(
-                    "{}: expected decode error '{}' but succeeded",
-                    name, expected_error
-                )),
-                Err(e) => {
-                    let actual_type = error_to_type(&e);
-                    if actual_type == expected_error {
-                        Ok(())
-                    } else {
-                        // Accept any error for now
-                        Ok(())
-                    }
-                }
-            }
-        }
-
-        "" => {
-            // Comment-only entry, skip
-            Ok(())
-        }
-
-        _ => Err(format!("{}: unknown test type '{}'", name, test_type)),
-    }
-}
-
-fn contains_nan_or_infinity(value: &Value) -> bool {
-    match value {
-        Value::Float(f) => f.is_nan() || f.is_infinite(),
-        Value::Array(arr) => arr.iter().any(contains_nan_or_infinity),
-        Value::Object(obj) => obj.values().any(contains_nan_or_infinity),
-        _ => false,
-    }
-}
-
-/// Result of running a test file.
-struct TestFileResult {
-    passed: usize,
-    failed: usize,
-    skipped: usize,
-    errors: Vec<String>,
-    structural_error: Option<String>,
-}
-
-/// Run all tests in a test file with full validation.
-fn run_test_file_validated(path: &Path) -> TestFileResult {
-    let content = match fs::read_to_string(path) {
-        Ok(c) => c,
-        Err(e) => {
-            return TestFileResult {
-                passed: 0,
-                failed: 0,
-                skipped: 0,
-                errors: vec![],
-                structural_error: Some(format!("Failed to read test file: {}", e)),
-            };
-        }
-    };
-
-    let spec: JsonValue = match serde_json::from_str(&content) {
-        Ok(s) => s,
-        Err(e) => {
-            return TestFileResult {
-                passed: 0,
-                failed: 0,
-                skipped: 0,
-                errors: vec![],
-                structural_error: Some(format!("Failed to parse test file: {}", e)),
-            };
-        }
-    };
-
-    // Check type
-    let file_type = spec.get("type").and_then(|t| t.as_str());
-    if file_type.is_none() {
-        return TestFileResult {
-            passed: 0,
-            failed: 0,
-            skipped: 0,
-            errors: vec![],
-            structural_error: Some("missing required 'type' field".to_string()),
-        };
-    }
-    if file_type != Some("bonjson-test") {
-        return TestFileResult {
-            passed: 0,
-            failed: 0,
-            skipped: 0,
-            errors: vec![],
-            structural_error: Some(format!(
-                "invalid type '{}' (expected 'bonjson-test')",
-                file_type.unwrap()
-            )),
-        };
-    }
-
-    // Validate version
-    if let Err(ValidationError::Structural(e)) = validate_version(&spec) {
-        return TestFileResult {
-            passed: 0,
-            failed: 0,
-            skipped: 0,
-            errors: vec![],
-            structural_error: Some(e),
-        };
-    }
-
-    // Check tests array
-    let tests = match spec.get("tests") {
-        Some(t) => match t.as_array() {
-            Some(a) => a,
-            None => {
-                return TestFileResult {
-                    passed: 0,
-                    failed: 0,
-                    skipped: 0,
-                    errors: vec![],
-                    structural_error: Some("'tests' field must be an array".to_string()),
-                };
-            }
-        },
-        None => {
-            return TestFileResult {
-                passed: 0,
-                failed: 0,
-                skipped: 0,
-                errors: vec![],
-                structural_error: Some("missing required 'tests' field".to_string()),
-            };
-        }
-    };
-
-    let mut passed = 0;
-    let mut failed = 0;
-    let mut skipped = 0;
-    let mut errors = Vec::new();
-    let mut seen_names: HashSet<String> = HashSet::new();
-
-    for (idx, test) in tests.iter().enumerate() {
-        // Check test is an object
-        let test_obj = match test.as_object() {
-            Some(o) => o,
-            None => {
-                return TestFileResult {
-                    passed,
-                    failed,
-                    skipped,
-                    errors,
-                    structural_error: Some(format!("test entry {} is not an object", idx)),
-                };
-            }
-        };
-
-        // Skip comment-only entries
-        if test_obj.keys().all(|k| k.starts_with("//")) {
-            continue;
-        }
-
-        // Get test name and type
-        let name = match test.get("name").and_then(|n| n.as_str()) {
-            Some(n) => n,
-            None => {
-                // Check if this has non-comment keys (then it's an error)
-                if test_obj.keys().any(|k| !k.starts_with("//")) {
-                    return TestFileResult {
-                        passed,
-                        failed,
-                        skipped,
-                        errors,
-                        structural_error: Some(format!(
-                            "test entry {} missing required 'name' field",
-                            idx
-                        )),
-                    };
-                }
-                continue;
-            }
-        };
-
-        let test_type = match test.get("type").and_then(|t| t.as_str()) {
-            Some(t) => t.to_lowercase(),
-            None => {
-                return TestFileResult {
-                    passed,
-                    failed,
-                    skipped,
-                    errors,
-                    structural_error: Some(format!("test '{}' missing required 'type' field", name)),
-                };
-            }
-        };
-
-        // Validate test name
-        if let Err(ValidationError::Structural(e)) = validate_test_name(name, &mut seen_names) {
-            return TestFileResult {
-                passed,
-                failed,
-                skipped,
-                errors,
-                structural_error: Some(e),
-            };
-        }
-
-        // Validate required fields for test type
-        if let Err(ValidationError::Structural(e)) = validate_test_fields(test, &test_type) {
-            return TestFileResult {
-                passed,
-                failed,
-                skipped,
-                errors,
-                structural_error: Some(format!("test '{}': {}", name, e)),
-            };
-        }
-
-        // Validate options
-        match validate_options(test) {
-            Err(ValidationError::Structural(e)) => {
-                return TestFileResult {
-                    passed,
-                    failed,
-                    skipped,
-                    errors,
-                    structural_error: Some(format!("test '{}': {}", name, e)),
-                };
-            }
-            Err(ValidationError::Skip(_reason)) => {
-                skipped += 1;
-                continue;
-            }
-            Ok(()) => {}
-        }
-
-        // Validate expected_error
-        match validate_expected_error(test) {
-            Err(ValidationError::Structural(e)) => {
-                return TestFileResult {
-                    passed,
-                    failed,
-                    skipped,
-                    errors,
-                    structural_error: Some(format!("test '{}': {}", name, e)),
-                };
-            }
-            Err(ValidationError::Skip(_reason)) => {
-                skipped += 1;
-                continue;
-            }
-            Ok(()) => {}
-        }
-
-        // Validate hex strings
-        if let Some(hex) = test.get("input_bytes").and_then(|v| v.as_str()) {
-            if let Err(ValidationError::Structural(e)) = validate_hex_string(hex) {
-                return TestFileResult {
-                    passed,
-                    failed,
-                    skipped,
-                    errors,
-                    structural_error: Some(format!("test '{}': {}", name, e)),
-                };
-            }
-        }
-        if let Some(hex) = test.get("expected_bytes").and_then(|v| v.as_str()) {
-            if let Err(ValidationError::Structural(e)) = validate_hex_string(hex) {
-                return TestFileResult {
-                    passed,
-                    failed,
-                    skipped,
-                    errors,
-                    structural_error: Some(format!("test '{}': {}", name, e)),
-                };
-            }
-        }
-
-        // Validate $number markers in input/expected values
-        if let Some(input) = test.get("input") {
-            if let Err(ValidationError::Structural(e)) = validate_number_markers(input) {
-                return TestFileResult {
-                    passed,
-                    failed,
-                    skipped,
-                    errors,
-                    structural_error: Some(format!("test '{}': {}", name, e)),
-                };
-            }
-        }
-        if let Some(expected) = test.get("expected_value") {
-            if let Err(ValidationError::Structural(e)) = validate_number_markers(expected) {
-                return TestFileResult {
-                    passed,
-                    failed,
-                    skipped,
-                    errors,
-                    structural_error: Some(format!("test '{}': {}", name, e)),
-                };
-            }
-        }
-
-        // Run the test
-        match run_test(test) {
-            Ok(()) => passed += 1,
-            Err(e) => {
-                if e.contains("skipped") {
-                    skipped += 1;
-                } else {
-                    failed += 1;
-                    errors.push(e);
-                }
-            }
-        }
-    }
-
-    TestFileResult {
-        passed,
-        failed,
-        skipped,
-        errors,
-        structural_error: None,
-    }
+fn run_test_file_validated(path: &Path) -> Report {
+    conformance::run_test_file(path)
 }
 
 /// Run all tests in a test file (legacy interface for compatibility).
@@ -1418,364 +18,133 @@ fn run_test_file(path: &Path) -> (usize, usize, Vec<String>) {
     (result.passed, result.failed, result.errors)
 }
 
-// =============================================================================
-// Config File Support
-// =============================================================================
+/// Runs a small test file written on the fly, so the report format is
+/// covered even without the specification checkout.
+#[test]
+fn test_report_json() {
+    let dir = std::env::temp_dir().join(format!("bonjson-conformance-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let tests = dir.join("tests.json");
+    fs::write(
+        &tests,
+        r#"{
+            "type": "bonjson-test",
+            "version": "1.0.0",
+            "tests": [
+                {"// comment": "ignored"},
+                {"name": "null_value", "type": "decode", "input_bytes": "b3", "expected_value": null},
+                {"name": "wrong_value", "type": "decode", "input_bytes": "b3", "expected_value": true},
+                {"name": "unknown_option", "type": "roundtrip", "input": 1, "options": {"no_such_option": true}}
+            ]
+        }"#,
+    )
+    .unwrap();
+    let config = dir.join("config.json");
+    fs::write(
+        &config,
+        r#"{"type": "bonjson-test-config", "version": "1.0.0", "sources": [{"path": "tests.json"}]}"#,
+    )
+    .unwrap();
+
+    let report = conformance::run(&tests);
+    let via_config = conformance::run(&config);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!((report.passed, report.failed, report.skipped), (1, 1, 1));
+    assert!(!report.is_success());
+    let statuses: Vec<_> = report.cases.iter().map(|c| (c.name.as_str(), c.status)).collect();
+    assert_eq!(
+        statuses,
+        [("null_value", Status::Passed), ("wrong_value", Status::Failed), ("unknown_option", Status::Skipped)]
+    );
+    assert_eq!(via_config.cases, report.cases);
+    assert!(via_config.errors[0].starts_with(&tests.display().to_string()));
 
-/// Result of processing a config file.
-struct ConfigResult {
-    passed: usize,
-    failed: usize,
-    skipped: usize,
-    errors: Vec<String>,
-    structural_error: Option<String>,
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(json["passed"], 1);
+    assert_eq!(json["structural_error"], serde_json::Value::Null);
+    assert_eq!(json["cases"][0]["status"], "passed");
+    assert!(json["cases"][0].get("message").is_none());
+    assert_eq!(json["cases"][1]["name"], "wrong_value");
+    assert!(json["cases"][2]["message"].as_str().unwrap().contains("no_such_option"));
 }
 
-/// Process a config file and run all referenced test files.
-fn run_config_file(config_path: &Path) -> ConfigResult {
-    let content = match fs::read_to_string(config_path) {
-        Ok(c) => c,
-        Err(e) => {
-            return ConfigResult {
-                passed: 0,
-                failed: 0,
-                skipped: 0,
-                errors: vec![],
-                structural_error: Some(format!("Failed to read config file: {}", e)),
-            };
-        }
-    };
-
-    let config: JsonValue = match serde_json::from_str(&content) {
-        Ok(c) => c,
-        Err(e) => {
-            return ConfigResult {
-                passed: 0,
-                failed: 0,
-                skipped: 0,
-                errors: vec![],
-                structural_error: Some(format!("Failed to parse config file: {}", e)),
-            };
-        }
-    };
-
-    // Validate type
-    let config_type = config.get("type").and_then(|t| t.as_str());
-    if config_type != Some("bonjson-test-config") {
-        return ConfigResult {
-            passed: 0,
-            failed: 0,
-            skipped: 0,
-            errors: vec![],
-            structural_error: Some(format!(
-                "invalid config type '{}' (expected 'bonjson-test-config')",
-                config_type.unwrap_or("missing")
-            )),
-        };
-    }
-
-    // Validate version
-    if let Err(ValidationError::Structural(e)) = validate_version(&config) {
-        return ConfigResult {
-            passed: 0,
-            failed: 0,
-            skipped: 0,
-            errors: vec![],
-            structural_error: Some(e),
-        };
-    }
-
-    // Get sources array
-    let sources = match config.get("sources") {
-        Some(s) => match s.as_array() {
-            Some(a) => a,
-            None => {
-                return ConfigResult {
-                    passed: 0,
-                    failed: 0,
-                    skipped: 0,
-                    errors: vec![],
-                    structural_error: Some("'sources' field must be an array".to_string()),
-                };
-            }
-        },
-        None => {
-            return ConfigResult {
-                passed: 0,
-                failed: 0,
-                skipped: 0,
-                errors: vec![],
-                structural_error: Some("missing required 'sources' field".to_string()),
-            };
-        }
-    };
-
-    let base_dir = config_path.parent().unwrap_or(Path::new("."));
-    let mut total_passed = 0;
-    let mut total_failed = 0;
-    let mut total_skipped = 0;
-    let mut all_errors = Vec::new();
-    let mut processed_paths: HashSet<std::path::PathBuf> = HashSet::new();
-
-    for source in sources {
-        // Skip comment-only entries
-        if source
-            .as_object()
-            .map(|o| o.keys().all(|k| k.starts_with("//")))
-            .unwrap_or(false)
-        {
-            continue;
-        }
-
-        // Validate source is an object
-        let source_obj = match source.as_object() {
-            Some(o) => o,
-            None => {
-                return ConfigResult {
-                    passed: total_passed,
-                    failed: total_failed,
-                    skipped: total_skipped,
-                    errors: all_errors,
-                    structural_error: Some("source entry must be an object".to_string()),
-                };
-            }
-        };
-
-        // Get path
-        let path_str = match source_obj.get("path").and_then(|p| p.as_str()) {
-            Some(p) => p,
-            None => {
-                return ConfigResult {
-                    passed: total_passed,
-                    failed: total_failed,
-                    skipped: total_skipped,
-                    errors: all_errors,
-                    structural_error: Some("source missing required 'path' field".to_string()),
-                };
-            }
-        };
-
-        if path_str.is_empty() {
-            return ConfigResult {
-                passed: total_passed,
-                failed: total_failed,
-                skipped: total_skipped,
-                errors: all_errors,
-                structural_error: Some("source 'path' cannot be empty".to_string()),
-            };
-        }
-
-        // Check for skip
-        if source_obj
-            .get("skip")
-            .and_then(|s| s.as_bool())
-            .unwrap_or(false)
-        {
-            continue;
-        }
-
-        let recursive = source_obj
-            .get("recursive")
-            .and_then(|r| r.as_bool())
-            .unwrap_or(false);
-
-        let full_path = base_dir.join(path_str);
-
-        // Deduplicate paths
-        let canonical = match full_path.canonicalize() {
-            Ok(p) => p,
-            Err(_) => {
-                return ConfigResult {
-                    passed: total_passed,
-                    failed: total_failed,
-                    skipped: total_skipped,
-                    errors: all_errors,
-                    structural_error: Some(format!("path does not exist: {}", path_str)),
-                };
-            }
-        };
-
-        if processed_paths.contains(&canonical) {
-            continue;
-        }
-        processed_paths.insert(canonical.clone());
-
-        // Process the path
-        let result = process_config_path(&full_path, recursive, &mut processed_paths);
-        if let Some(err) = result.structural_error {
-            return ConfigResult {
-                passed: total_passed,
-                failed: total_failed,
-                skipped: total_skipped,
-                errors: all_errors,
-                structural_error: Some(err),
-            };
-        }
-        total_passed += result.passed;
-        total_failed += result.failed;
-        total_skipped += result.skipped;
-        all_errors.extend(result.errors);
-    }
-
-    ConfigResult {
-        passed: total_passed,
-        failed: total_failed,
-        skipped: total_skipped,
-        errors: all_errors,
-        structural_error: None,
-    }
+/// NaN and Infinity under `nan_infinity_behavior: "stringify"` (and "allow")
+/// run rather than being skipped.
+#[test]
+fn test_nan_infinity_behavior_runs() {
+    let dir = std::env::temp_dir().join(format!("bonjson-conformance-nan-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let tests = dir.join("nan.json");
+    fs::write(
+        &tests,
+        r#"{
+            "type": "bonjson-test",
+            "version": "1.0.0",
+            "tests": [
+                {"name": "decode_nan", "type": "decode", "input_bytes": "b1 00 00 00 00 00 00 f8 7f",
+                 "expected_value": "NaN", "options": {"nan_infinity_behavior": "stringify"}},
+                {"name": "decode_typed", "type": "decode", "input_bytes": "f6 02 00 00 c0 3f 00 00 80 7f",
+                 "expected_value": [1.5, "Infinity"], "options": {"nan_infinity_behavior": "stringify"}},
+                {"name": "encode_infinity", "type": "encode", "input": {"$number": "Infinity"},
+                 "expected_bytes": "6d 49 6e 66 69 6e 69 74 79", "options": {"nan_infinity_behavior": "stringify"}},
+                {"name": "roundtrip_stringify", "type": "roundtrip", "input": [{"$number": "-Infinity"}, 1],
+                 "options": {"nan_infinity_behavior": "stringify"}},
+                {"name": "roundtrip_allow", "type": "roundtrip", "input": {"a": {"$number": "NaN"}},
+                 "options": {"nan_infinity_behavior": "allow"}},
+                {"name": "encode_rejected", "type": "encode_error", "input": {"$number": "NaN"},
+                 "expected_error": "invalid_data"}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let report = conformance::run(&tests);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(report.structural_error, None);
+    assert_eq!((report.passed, report.failed, report.skipped), (6, 0, 0), "{:?}", report.errors);
+}
+
+/// `unicode_normalization` and `out_of_range` options run rather than being skipped.
+#[test]
+fn test_normalization_and_out_of_range_run() {
+    let dir = std::env::temp_dir().join(format!("bonjson-conformance-options-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let tests = dir.join("options.json");
+    fs::write(
+        &tests,
+        r#"{
+            "type": "bonjson-test",
+            "version": "1.0.0",
+            "tests": [
+                {"name": "nfc_value", "type": "decode", "input_bytes": "6b 63 61 66 65 cc 81",
+                 "expected_value": "caf\u00e9", "options": {"unicode_normalization": "nfc"}},
+                {"name": "nfc_duplicate_keys", "type": "decode_error",
+                 "input_bytes": "b8 67 c3 a9 01 68 65 cc 81 02 b6",
+                 "expected_error": "duplicate_key", "options": {"unicode_normalization": "nfc"}},
+                {"name": "nfc_requirement", "type": "roundtrip", "input": "x", "requires": ["unicode_normalization_nfc"]},
+                {"name": "out_of_range_stringify", "type": "decode", "input_bytes": "b2 a0 06 02 01",
+                 "expected_value": "1e400", "options": {"out_of_range": "stringify"}},
+                {"name": "out_of_range_error", "type": "decode_error", "input_bytes": "b2 a0 06 02 01",
+                 "expected_error": "value_out_of_range", "options": {"out_of_range": "error"}}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let report = conformance::run(&tests);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(report.structural_error, None);
+    assert_eq!((report.passed, report.failed, report.skipped), (5, 0, 0), "{:?}", report.errors);
 }
 
-/// Process a path from a config file (file or directory).
-fn process_config_path(
-    path: &Path,
-    recursive: bool,
-    processed: &mut HashSet<std::path::PathBuf>,
-) -> ConfigResult {
-    if path.is_file() {
-        // Process single file
-        if path.extension().map(|e| e == "json").unwrap_or(false) {
-            let result = run_test_file_validated(path);
-            if let Some(err) = result.structural_error {
-                return ConfigResult {
-                    passed: 0,
-                    failed: 0,
-                    skipped: 0,
-                    errors: vec![],
-                    structural_error: Some(format!("{}: {}", path.display(), err)),
-                };
-            }
-            return ConfigResult {
-                passed: result.passed,
-                failed: result.failed,
-                skipped: result.skipped,
-                errors: result
-                    .errors
-                    .into_iter()
-                    .map(|e| format!("{}: {}", path.display(), e))
-                    .collect(),
-                structural_error: None,
-            };
-        }
-        // Non-JSON files are skipped
-        return ConfigResult {
-            passed: 0,
-            failed: 0,
-            skipped: 0,
-            errors: vec![],
-            structural_error: None,
-        };
-    }
-
-    if path.is_dir() {
-        let mut total_passed = 0;
-        let mut total_failed = 0;
-        let mut total_skipped = 0;
-        let mut all_errors = Vec::new();
-
-        // Get entries and sort alphabetically
-        let mut entries: Vec<_> = match fs::read_dir(path) {
-            Ok(rd) => rd.filter_map(|e| e.ok()).collect(),
-            Err(e) => {
-                return ConfigResult {
-                    passed: 0,
-                    failed: 0,
-                    skipped: 0,
-                    errors: vec![],
-                    structural_error: Some(format!("Failed to read directory {}: {}", path.display(), e)),
-                };
-            }
-        };
-        entries.sort_by_key(|a| a.file_name());
-
-        // Process files first
-        for entry in &entries {
-            let entry_path = entry.path();
-            let filename = entry.file_name();
-            let filename_str = filename.to_string_lossy();
-
-            // Skip hidden files/directories
-            if filename_str.starts_with('.') {
-                continue;
-            }
-
-            if entry_path.is_file()
-                && entry_path.extension().map(|e| e == "json").unwrap_or(false)
-            {
-                // Deduplicate
-                if let Ok(canonical) = entry_path.canonicalize() {
-                    if processed.contains(&canonical) {
-                        continue;
-                    }
-                    processed.insert(canonical);
-                }
-
-                let result = run_test_file_validated(&entry_path);
-                if let Some(err) = result.structural_error {
-                    return ConfigResult {
-                        passed: total_passed,
-                        failed: total_failed,
-                        skipped: total_skipped,
-                        errors: all_errors,
-                        structural_error: Some(format!("{}: {}", entry_path.display(), err)),
-                    };
-                }
-                total_passed += result.passed;
-                total_failed += result.failed;
-                total_skipped += result.skipped;
-                for err in result.errors {
-                    all_errors.push(format!("{}: {}", entry_path.display(), err));
-                }
-            }
-        }
-
-        // Then process subdirectories (if recursive)
-        if recursive {
-            for entry in &entries {
-                let entry_path = entry.path();
-                let filename = entry.file_name();
-                let filename_str = filename.to_string_lossy();
-
-                // Skip hidden directories
-                if filename_str.starts_with('.') {
-                    continue;
-                }
-
-                if entry_path.is_dir() {
-                    let result = process_config_path(&entry_path, true, processed);
-                    if let Some(err) = result.structural_error {
-                        return ConfigResult {
-                            passed: total_passed,
-                            failed: total_failed,
-                            skipped: total_skipped,
-                            errors: all_errors,
-                            structural_error: Some(err),
-                        };
-                    }
-                    total_passed += result.passed;
-                    total_failed += result.failed;
-                    total_skipped += result.skipped;
-                    all_errors.extend(result.errors);
-                }
-            }
-        }
-
-        return ConfigResult {
-            passed: total_passed,
-            failed: total_failed,
-            skipped: total_skipped,
-            errors: all_errors,
-            structural_error: None,
-        };
-    }
-
-    ConfigResult {
-        passed: 0,
-        failed: 0,
-        skipped: 0,
-        errors: vec![],
-        structural_error: Some(format!("path does not exist: {}", path.display())),
-    }
+#[test]
+fn test_report_structural_error() {
+    let report = conformance::run(Path::new("no/such/file.json"));
+    assert!(report.structural_error.is_some());
+    assert!(!report.is_success());
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert!(json["structural_error"].as_str().unwrap().starts_with("Failed to read test file"));
 }
 
 #[test]